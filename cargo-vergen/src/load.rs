@@ -0,0 +1,257 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The configuration and the package variables of the project
+
+use anyhow::{anyhow, Context, Result};
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
+use toml::Value;
+use vergen::Config;
+
+/// The name of the configuration file, next to the manifest
+pub(crate) const CONFIG_FILE: &str = "vergen.toml";
+
+/// The configuration presets, see `--preset`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Preset {
+    /// `Config::default()`
+    Default,
+    /// `Config::minimal()`
+    Minimal,
+    /// `Config::all_enabled()`
+    All,
+    /// `Config::ci()`
+    Ci,
+}
+
+impl Preset {
+    /// The preset with the given name
+    pub(crate) fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "default" => Ok(Preset::Default),
+            "minimal" => Ok(Preset::Minimal),
+            "all" => Ok(Preset::All),
+            "ci" => Ok(Preset::Ci),
+            _ => Err(anyhow!(
+                "unknown preset '{}', use default, minimal, all, or ci",
+                name
+            )),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Preset::Default => "default",
+            Preset::Minimal => "minimal",
+            Preset::All => "all",
+            Preset::Ci => "ci",
+        }
+    }
+
+    fn config(self) -> Config {
+        match self {
+            Preset::Default => Config::default(),
+            Preset::Minimal => Config::minimal(),
+            Preset::All => Config::all_enabled(),
+            Preset::Ci => Config::ci(),
+        }
+    }
+}
+
+/// Where the configuration was read from
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Source {
+    /// The `vergen.toml` file
+    File(PathBuf),
+    /// The `[package.metadata.vergen]` table of the manifest
+    Metadata(PathBuf),
+    /// A preset, named with `--preset` or the default one
+    Preset(Preset),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Metadata(path) => write!(f, "[package.metadata.vergen] in {}", path.display()),
+            Source::Preset(preset) => write!(f, "the {} preset", preset.name()),
+        }
+    }
+}
+
+/// The configuration of the project in `dir`, from the preset if one is named, or else `vergen.toml`, the
+/// `[package.metadata.vergen]` table of the manifest, or the default preset, in that order
+///
+/// Call this from the project directory, the default `git.base_dir` is the current directory.
+pub(crate) fn load(
+    manifest: &Value,
+    manifest_path: &Path,
+    dir: &Path,
+    preset: Option<Preset>,
+) -> Result<(Config, Source)> {
+    if let Some(preset) = preset {
+        return Ok((preset.config(), Source::Preset(preset)));
+    }
+
+    let path = dir.join(CONFIG_FILE);
+    if path.is_file() {
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("can't read '{}'", path.display()))?;
+        let config = toml::from_str(&contents)
+            .with_context(|| format!("'{}' isn't a valid configuration", path.display()))?;
+        return Ok((config, Source::File(path)));
+    }
+
+    if let Some(metadata) = manifest
+        .get("package")
+        .and_then(|package| package.get("metadata"))
+        .and_then(|metadata| metadata.get("vergen"))
+    {
+        let config = metadata.clone().try_into().with_context(|| {
+            format!(
+                "[package.metadata.vergen] in '{}' isn't a valid configuration",
+                manifest_path.display()
+            )
+        })?;
+        return Ok((config, Source::Metadata(manifest_path.to_path_buf())));
+    }
+
+    Ok((Preset::Default.config(), Source::Preset(Preset::Default)))
+}
+
+/// Set the `CARGO_MANIFEST_DIR` and `CARGO_PKG_*` variables cargo sets for a build script from the manifest, as the
+/// values are read from them
+///
+/// A field the manifest doesn't set as a plain value, i.e. one inherited from the workspace, is unset, so its
+/// instruction is skipped with a warning rather than describing another package.
+pub(crate) fn set_package_env(manifest: &Value, dir: &Path) {
+    env::set_var("CARGO_MANIFEST_DIR", dir);
+
+    let package = manifest.get("package");
+    let string = |name: &str| {
+        package
+            .and_then(|package| package.get(name))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    let authors = package
+        .and_then(|package| package.get("authors"))
+        .and_then(Value::as_array)
+        .map(|authors| {
+            authors
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<&str>>()
+                .join(":")
+        });
+    let version = string("version");
+    let parts = version.as_deref().map(version_parts);
+
+    let vars = [
+        ("CARGO_PKG_NAME", string("name")),
+        ("CARGO_PKG_VERSION", version.clone()),
+        (
+            "CARGO_PKG_VERSION_MAJOR",
+            parts.as_ref().map(|parts| parts.0.clone()),
+        ),
+        (
+            "CARGO_PKG_VERSION_MINOR",
+            parts.as_ref().map(|parts| parts.1.clone()),
+        ),
+        (
+            "CARGO_PKG_VERSION_PATCH",
+            parts.as_ref().map(|parts| parts.2.clone()),
+        ),
+        (
+            "CARGO_PKG_VERSION_PRE",
+            parts.as_ref().map(|parts| parts.3.clone()),
+        ),
+        ("CARGO_PKG_AUTHORS", authors),
+        ("CARGO_PKG_DESCRIPTION", string("description")),
+        ("CARGO_PKG_HOMEPAGE", string("homepage")),
+        ("CARGO_PKG_REPOSITORY", string("repository")),
+        ("CARGO_PKG_LICENSE", string("license")),
+        ("CARGO_PKG_LICENSE_FILE", string("license-file")),
+    ];
+    for (name, value) in &vars {
+        match value {
+            Some(value) => env::set_var(name, value),
+            None => env::remove_var(name),
+        }
+    }
+}
+
+/// The major, minor, patch, and pre-release parts of a version, as cargo splits them, i.e. `1.2.3-beta.1`
+fn version_parts(version: &str) -> (String, String, String, String) {
+    let (core, pre) = match version.find('-') {
+        Some(dash) => (&version[..dash], &version[dash + 1..]),
+        None => (version.split('+').next().unwrap_or_default(), ""),
+    };
+    let pre = pre.split('+').next().unwrap_or_default();
+    let mut numbers = core.splitn(3, '.');
+    let mut next = || numbers.next().unwrap_or_default().to_string();
+    let (major, minor, patch) = (next(), next(), next());
+    (major, minor, patch, pre.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{load, version_parts, Preset, Source};
+    use std::path::Path;
+    use toml::Value;
+
+    #[test]
+    fn presets() {
+        assert_eq!(Preset::from_name("ci").unwrap(), Preset::Ci);
+        assert!(Preset::from_name("everything").is_err());
+    }
+
+    #[test]
+    fn metadata_or_default() {
+        let dir = Path::new("no-such-dir");
+        let manifest: Value =
+            "[package]\nname = \"a\"\n\n[package.metadata.vergen]\nsource_date_epoch = true\n"
+                .parse()
+                .unwrap();
+        let (mut config, source) = load(&manifest, &dir.join("Cargo.toml"), dir, None).unwrap();
+        assert!(*config.source_date_epoch_mut());
+        assert_eq!(source, Source::Metadata(dir.join("Cargo.toml")));
+
+        let manifest: Value = "[package]\nname = \"a\"\n".parse().unwrap();
+        let (_, source) = load(&manifest, &dir.join("Cargo.toml"), dir, None).unwrap();
+        assert_eq!(source, Source::Preset(Preset::Default));
+
+        let manifest: Value = "[package.metadata.vergen]\nbogus = true\n".parse().unwrap();
+        assert!(load(&manifest, &dir.join("Cargo.toml"), dir, None).is_err());
+    }
+
+    #[test]
+    fn versions() {
+        assert_eq!(
+            version_parts("1.2.3-beta.1+build.5"),
+            (
+                "1".to_string(),
+                "2".to_string(),
+                "3".to_string(),
+                "beta.1".to_string()
+            )
+        );
+        assert_eq!(
+            version_parts("0.10.0+build"),
+            (
+                "0".to_string(),
+                "10".to_string(),
+                "0".to_string(),
+                String::new()
+            )
+        );
+    }
+}
@@ -0,0 +1,150 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `cargo vergen`, print the values [`vergen`](https://docs.rs/vergen) would generate for a project, without building
+//! it.
+//!
+//! Run it from the project directory, or point `--manifest-path` at its `Cargo.toml`.  The configuration is read
+//! from a `vergen.toml` next to the manifest, or else from the `[package.metadata.vergen]` table of the manifest,
+//! in the format of the `vergen` `serde` feature, and the default configuration is used without either.  Name a
+//! preset with `--preset` to use it instead.
+//!
+//! The report lists where the configuration was read from, the git directory of the discovered repository, the
+//! values, the warnings, and how long each feature took.  Pass `--json` for one JSON object instead.
+//!
+//! * The `CARGO_PKG_*` variables are set from the manifest, but the ones cargo only sets while building, i.e.
+//!   `TARGET` or `PROFILE`, aren't, so the values read from them are skipped with a warning unless you set them.
+//! * **NOTE** - Nothing is written as a `cargo:` instruction, so running it from a build script doesn't change the
+//!   build.
+
+#![deny(missing_docs)]
+
+mod load;
+mod report;
+
+use crate::load::{load, set_package_env, Preset};
+use anyhow::{anyhow, Context, Result};
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+use toml::Value;
+
+const USAGE: &str = "Print the values vergen would generate for a project, without building it
+
+Usage: cargo vergen [OPTIONS]
+
+Options:
+      --json                   Print the report as JSON
+      --manifest-path <PATH>   The Cargo.toml of the project [default: ./Cargo.toml]
+      --preset <NAME>          Use a preset instead of the project configuration: default, minimal, all, or ci
+  -h, --help                   Print this help
+";
+
+/// The command line arguments
+#[derive(Debug, Default)]
+struct Args {
+    json: bool,
+    help: bool,
+    manifest_path: Option<PathBuf>,
+    preset: Option<Preset>,
+}
+
+/// The arguments, without the program name, or the `vergen` cargo passes when run as `cargo vergen`
+fn parse_args<I>(args: I) -> Result<Args>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter().peekable();
+    if args.peek().map(String::as_str) == Some("vergen") {
+        let _ = args.next();
+    }
+
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| anyhow!("{} needs a value\n\n{}", name, USAGE))
+        };
+        match arg.as_str() {
+            "--json" => parsed.json = true,
+            "-h" | "--help" => parsed.help = true,
+            "--manifest-path" => parsed.manifest_path = Some(value("--manifest-path")?.into()),
+            "--preset" => parsed.preset = Some(Preset::from_name(&value("--preset")?)?),
+            _ => return Err(anyhow!("unknown argument '{}'\n\n{}", arg, USAGE)),
+        }
+    }
+    Ok(parsed)
+}
+
+fn main() -> Result<()> {
+    let args = parse_args(env::args().skip(1))?;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    if args.help {
+        write!(out, "{}", USAGE)?;
+        return Ok(());
+    }
+
+    let manifest_path = match args.manifest_path {
+        Some(path) => path,
+        None => env::current_dir()?.join("Cargo.toml"),
+    };
+    let manifest_path = fs::canonicalize(&manifest_path)
+        .with_context(|| format!("can't find '{}'", manifest_path.display()))?;
+    let manifest: Value = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("can't read '{}'", manifest_path.display()))?
+        .parse()
+        .with_context(|| format!("'{}' isn't a valid manifest", manifest_path.display()))?;
+    let dir = manifest_path
+        .parent()
+        .ok_or_else(|| anyhow!("'{}' has no directory", manifest_path.display()))?
+        .to_path_buf();
+
+    // The values are gathered from the project directory, as in its build script
+    env::set_current_dir(&dir)?;
+    set_package_env(&manifest, &dir);
+    if env::var_os("OUT_DIR").is_none() {
+        let out_dir = env::temp_dir().join("cargo-vergen");
+        fs::create_dir_all(&out_dir)?;
+        env::set_var("OUT_DIR", out_dir);
+    }
+
+    let (config, source) = load(&manifest, &manifest_path, &dir, args.preset)?;
+    let inspection = vergen::inspect(&config)?;
+    if args.json {
+        report::json(&source, &inspection, &mut out)
+    } else {
+        report::human(&source, &inspection, &mut out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_args, Preset};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| (*arg).to_string()).collect()
+    }
+
+    #[test]
+    fn as_cargo_subcommand() {
+        let parsed = parse_args(args(&["vergen", "--json", "--preset", "minimal"])).unwrap();
+        assert!(parsed.json);
+        assert_eq!(parsed.preset, Some(Preset::Minimal));
+        assert!(parsed.manifest_path.is_none());
+    }
+
+    #[test]
+    fn bad_arguments() {
+        assert!(parse_args(args(&["--bogus"])).is_err());
+        assert!(parse_args(args(&["--manifest-path"])).is_err());
+        assert!(parse_args(args(&["--preset", "everything"])).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The human and JSON reports
+//!
+//! Every line of a report starts with a section name, indentation, or JSON punctuation, never with `cargo:`, so the
+//! report can't be read as instructions when it's run from a build script.
+
+use crate::load::Source;
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::io::Write;
+use vergen::Inspection;
+
+/// Write the report as labeled sections, one value per line
+pub(crate) fn human<T>(source: &Source, inspection: &Inspection, out: &mut T) -> Result<()>
+where
+    T: Write,
+{
+    writeln!(out, "Configuration: {}", source)?;
+    match inspection.repo_path() {
+        Some(path) => writeln!(out, "Repository: {}", path.display())?,
+        None => writeln!(out, "Repository: none")?,
+    }
+
+    let rows: Vec<(String, String)> = inspection
+        .values()
+        .iter()
+        .map(|(name, value)| (name.clone(), one_line(value)))
+        .collect();
+    section(out, "Values", &rows)?;
+
+    writeln!(out, "Warnings:")?;
+    if inspection.warnings().is_empty() {
+        writeln!(out, "  none")?;
+    }
+    for warning in inspection.warnings() {
+        writeln!(out, "  {}", one_line(warning))?;
+    }
+
+    let rows: Vec<(String, String)> = inspection
+        .timings()
+        .iter()
+        .map(|(feature, elapsed)| (feature.clone(), format!("{:?}", elapsed)))
+        .collect();
+    section(out, "Timings", &rows)
+}
+
+/// Write the report as one JSON object
+pub(crate) fn json<T>(source: &Source, inspection: &Inspection, out: &mut T) -> Result<()>
+where
+    T: Write,
+{
+    let values: Map<String, Value> = inspection
+        .values()
+        .iter()
+        .map(|(name, value)| (name.clone(), Value::from(value.as_str())))
+        .collect();
+    let timings: Map<String, Value> = inspection
+        .timings()
+        .iter()
+        .map(|(feature, elapsed)| (feature.clone(), Value::from(elapsed.as_secs_f64() * 1000.0)))
+        .collect();
+    let report = json!({
+        "config": source.to_string(),
+        "repo_path": inspection.repo_path().as_ref().map(|path| path.display().to_string()),
+        "values": values,
+        "warnings": inspection.warnings(),
+        "timings_ms": timings,
+    });
+    writeln!(out, "{}", serde_json::to_string_pretty(&report)?)?;
+    Ok(())
+}
+
+/// Write the rows of a section with the values aligned
+fn section<T>(out: &mut T, title: &str, rows: &[(String, String)]) -> Result<()>
+where
+    T: Write,
+{
+    writeln!(out, "{}:", title)?;
+    if rows.is_empty() {
+        writeln!(out, "  none")?;
+    }
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    for (name, value) in rows {
+        writeln!(out, "  {:width$}  {}", name, value, width = width)?;
+    }
+    Ok(())
+}
+
+/// The text with its line breaks escaped, so it can't start a line of its own
+fn one_line(text: &str) -> String {
+    text.replace('\r', "\\r").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::one_line;
+
+    #[test]
+    fn line_breaks_are_escaped() {
+        assert_eq!(
+            one_line("abc\ncargo:rustc-env=A=1\r\n"),
+            "abc\\ncargo:rustc-env=A=1\\r\\n"
+        );
+    }
+}
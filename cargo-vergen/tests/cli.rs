@@ -0,0 +1,144 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+use serde_json::Value;
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-vergen"))
+        .current_dir(dir)
+        .args(args)
+        .env_remove("OUT_DIR")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+fn run_json(dir: &Path, args: &[&str]) -> Value {
+    let mut args = args.to_vec();
+    args.push("--json");
+    serde_json::from_slice(&run(dir, &args).stdout).unwrap()
+}
+
+#[test]
+fn human_report() {
+    let output = run(&fixture("file"), &["vergen"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("Configuration: "), "{}", stdout);
+    assert!(stdout.contains("vergen.toml\n"), "{}", stdout);
+    assert!(stdout.contains("\nValues:\n"), "{}", stdout);
+    assert!(stdout.contains("\nWarnings:\n"), "{}", stdout);
+    assert!(stdout.contains("\nTimings:\n"), "{}", stdout);
+    #[cfg(feature = "build")]
+    assert!(
+        stdout
+            .lines()
+            .any(|line| line.starts_with("  VERGEN_BUILD_SEMVER ")
+                && line.ends_with(" 1.2.3-beta.1")),
+        "{}",
+        stdout
+    );
+    // Nothing can be mistaken for an instruction by an enclosing build
+    assert!(
+        stdout.lines().all(|line| !line.starts_with("cargo:")),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn json_report_from_the_file() {
+    let report = run_json(&fixture("file"), &[]);
+    assert!(report["config"].as_str().unwrap().ends_with("vergen.toml"));
+    #[cfg(feature = "build")]
+    {
+        assert_eq!(report["values"]["VERGEN_BUILD_SEMVER"], "1.2.3-beta.1");
+        assert!(report["timings_ms"]["build"].is_number());
+    }
+    #[cfg(feature = "cargo")]
+    assert_eq!(report["values"]["VERGEN_CARGO_PKG_LICENSE"], "MIT");
+    #[cfg(feature = "git")]
+    {
+        // The fixture is inside the vergen repository
+        let repo_path = report["repo_path"].as_str().unwrap();
+        assert!(Path::new(repo_path).join("HEAD").is_file(), "{}", repo_path);
+        assert!(report["values"]["VERGEN_GIT_SHA"].is_string());
+        assert!(report["values"].get("VERGEN_GIT_BRANCH").is_none());
+    }
+}
+
+#[test]
+fn json_report_from_the_metadata() {
+    let report = run_json(&fixture("metadata"), &[]);
+    assert!(report["config"]
+        .as_str()
+        .unwrap()
+        .starts_with("[package.metadata.vergen] in "));
+    assert!(report["repo_path"].is_null());
+    assert!(report["values"].get("VERGEN_GIT_SHA").is_none());
+    assert!(report["timings_ms"].get("git").is_none());
+    #[cfg(feature = "build")]
+    assert_eq!(report["values"]["VERGEN_BUILD_SEMVER"], "0.4.0");
+}
+
+#[test]
+fn preset_replaces_the_project_configuration() {
+    let report = run_json(&fixture("metadata"), &["--preset", "minimal"]);
+    assert_eq!(report["config"], "the minimal preset");
+    let mut names: Vec<&str> = report["values"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    names.sort_unstable();
+    let mut expected = vec![];
+    #[cfg(feature = "build")]
+    expected.extend(&["VERGEN_BUILD_SEMVER", "VERGEN_BUILD_TIMESTAMP"]);
+    #[cfg(feature = "git")]
+    expected.push("VERGEN_GIT_SHA");
+    assert_eq!(names, expected);
+}
+
+#[test]
+fn manifest_path() {
+    let manifest = fixture("metadata").join("Cargo.toml");
+    let report = run_json(
+        Path::new(env!("CARGO_MANIFEST_DIR")),
+        &["--manifest-path", manifest.to_str().unwrap()],
+    );
+    assert!(report["config"]
+        .as_str()
+        .unwrap()
+        .starts_with("[package.metadata.vergen] in "));
+}
+
+#[test]
+fn unknown_argument() {
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-vergen"))
+        .arg("--bogus")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown argument '--bogus'"));
+}
@@ -0,0 +1 @@
+//! A project for the `cargo vergen` tests, which is never built
@@ -0,0 +1,211 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` version banner
+
+use crate::{
+    config::{Config, VergenKey},
+    error::ConfigError,
+};
+use std::env;
+
+/// The supported placeholders and the instructions they are filled from, first match wins
+const PLACEHOLDERS: [(&str, &[VergenKey]); 5] = [
+    ("name", &[]),
+    (
+        "semver",
+        &[
+            VergenKey::BuildSemver,
+            VergenKey::Semver,
+            VergenKey::SemverLightweight,
+        ],
+    ),
+    ("sha", &[VergenKey::ShortSha, VergenKey::Sha]),
+    (
+        "date",
+        &[
+            VergenKey::CommitDate,
+            VergenKey::CommitTimestamp,
+            VergenKey::BuildDate,
+            VergenKey::BuildTimestamp,
+        ],
+    ),
+    ("profile", &[VergenKey::CargoProfile]),
+];
+
+/// A piece of a format, see [`tokenize`]
+#[derive(Debug, PartialEq)]
+pub(crate) enum Token<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+/// Split the format into text and placeholders, or None if the braces are unbalanced
+pub(crate) fn tokenize(format: &str) -> Option<Vec<Token<'_>>> {
+    let mut tokens = vec![];
+    let mut rest = format;
+
+    while let Some(idx) = rest.find(['{', '}']) {
+        if idx > 0 {
+            tokens.push(Token::Text(&rest[..idx]));
+        }
+        let brace = &rest[idx..=idx];
+        rest = &rest[idx + 1..];
+
+        if rest.starts_with(brace) {
+            // '{{' and '}}' are escaped braces
+            tokens.push(Token::Text(brace));
+            rest = &rest[1..];
+        } else if brace == "{" {
+            let end = rest.find('}')?;
+            tokens.push(Token::Placeholder(&rest[..end]));
+            rest = &rest[end + 1..];
+        } else {
+            return None;
+        }
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+    Some(tokens)
+}
+
+pub(crate) fn validate_banner(format: &str, errors: &mut Vec<ConfigError>) {
+    let suggestion = "use '{name}', '{semver}', '{sha}', '{date}', or '{profile}', and '{{' or '}}' for a literal brace";
+
+    match tokenize(format) {
+        Some(tokens) => {
+            for token in tokens {
+                if let Token::Placeholder(name) = token {
+                    if PLACEHOLDERS.iter().all(|(known, _)| *known != name) {
+                        errors.push(ConfigError::new(
+                            "banner_format",
+                            format!("{{{}}}", name),
+                            suggestion,
+                        ));
+                    }
+                }
+            }
+        }
+        None => errors.push(ConfigError::new(
+            "banner_format",
+            format!("{:?}", format),
+            suggestion,
+        )),
+    }
+}
+
+/// Fill in the banner placeholders from the generated values, and add the `VERGEN_BANNER` instruction.
+///
+/// The format is assumed to be valid.
+pub(crate) fn add_banner(format: &str, config: &mut Config) {
+    let mut banner = String::new();
+
+    for token in tokenize(format).unwrap_or_default() {
+        match token {
+            Token::Text(text) => banner.push_str(text),
+            Token::Placeholder(name) => banner.push_str(&value(name, config)),
+        }
+    }
+
+    let _ = config.cfg_map_mut().insert(VergenKey::Banner, Some(banner));
+}
+
+fn value(name: &str, config: &Config) -> String {
+    let keys = PLACEHOLDERS
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map_or(&[][..], |(_, keys)| *keys);
+
+    keys.iter()
+        .find_map(|key| config.cfg_map().get(key).cloned().flatten())
+        .map(|value| match name {
+            // Only keep the date part of a timestamp
+            "date" => value.chars().take(10).collect(),
+            _ => value,
+        })
+        .or_else(|| match name {
+            "name" => env::var("CARGO_PKG_NAME").ok(),
+            "semver" => env::var("CARGO_PKG_VERSION").ok(),
+            "profile" => env::var("PROFILE").ok(),
+            _ => None,
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{add_banner, tokenize, validate_banner, Token};
+    use crate::config::{Config, VergenKey};
+
+    fn banner(format: &str, values: &[(VergenKey, &str)]) -> String {
+        let mut config = Config::default();
+        for (key, value) in values {
+            let _ = config
+                .cfg_map_mut()
+                .insert(*key, Some((*value).to_string()));
+        }
+        add_banner(format, &mut config);
+        config.cfg_map()[&VergenKey::Banner].clone().unwrap()
+    }
+
+    #[test]
+    fn tokens() {
+        assert_eq!(
+            tokenize("v{semver} {{x}}").unwrap(),
+            vec![
+                Token::Text("v"),
+                Token::Placeholder("semver"),
+                Token::Text(" "),
+                Token::Text("{"),
+                Token::Text("x"),
+                Token::Text("}"),
+            ]
+        );
+        assert!(tokenize("{semver").is_none());
+        assert!(tokenize("semver}").is_none());
+    }
+
+    #[test]
+    fn unknown_placeholders_are_invalid() {
+        let mut errors = vec![];
+        validate_banner("{name} {semver} ({sha} {date}, {profile})", &mut errors);
+        assert!(errors.is_empty());
+
+        validate_banner("{name} {version} {sha}", &mut errors);
+        validate_banner("{name", &mut errors);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].field(), "banner_format");
+        assert_eq!(errors[0].value(), "{version}");
+        assert_eq!(errors[1].value(), "\"{name\"");
+    }
+
+    #[test]
+    fn substitutes_generated_values() {
+        let values = [
+            (VergenKey::BuildSemver, "1.2.3"),
+            (VergenKey::Sha, "abc1234def5678"),
+            (VergenKey::ShortSha, "abc1234"),
+            (VergenKey::CommitTimestamp, "2024-01-15T10:11:12+00:00"),
+            (VergenKey::CargoProfile, "release"),
+        ];
+        assert_eq!(
+            banner("myapp {semver} ({sha} {date}, {profile})", &values),
+            "myapp 1.2.3 (abc1234 2024-01-15, release)"
+        );
+    }
+
+    #[test]
+    fn missing_values_are_unknown() {
+        assert_eq!(banner("{sha} {date}", &[]), "unknown unknown");
+        assert_eq!(
+            banner("{semver}", &[]),
+            std::env::var("CARGO_PKG_VERSION").unwrap()
+        );
+    }
+}
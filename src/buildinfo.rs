@@ -0,0 +1,584 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` reproducible-builds `.buildinfo` output
+
+use crate::{
+    command,
+    config::{Config, VergenKey},
+    error::ConfigError,
+    merge::{pick, union, SetFields},
+    output::{out_dir, write_atomic},
+};
+use anyhow::Result;
+#[cfg(feature = "hash")]
+use sha2::{Digest, Sha256};
+#[cfg(feature = "hash")]
+use std::fs;
+use std::{
+    env,
+    fmt::Write,
+    path::PathBuf,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+/// The environment variables listed in the `Environment` field, in addition to the `env` field
+const ENVIRONMENT: [&str; 6] = [
+    "CARGO_ENCODED_RUSTFLAGS",
+    "DEBUG",
+    "OPT_LEVEL",
+    "PROFILE",
+    "RUSTC_WRAPPER",
+    "SOURCE_DATE_EPOCH",
+];
+
+/// How long `cargo --version` may take before the `cargo` version is left out
+const CARGO_VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Configuration for writing a reproducible-builds `.buildinfo` style provenance document
+///
+/// When the `enabled` field is true, the source version, toolchain versions, and build environment are written as
+/// `Field: value` lines, in the [RFC 822] style of the Debian `.buildinfo` files the reproducible-builds tooling reads.
+///
+/// ```text
+/// Format: 1.0
+/// Source: myapp
+/// Version: 1.4.0
+/// Git-Commit: 75b390dc6c05a6a4aa2791cc7b3934591803bc22
+/// Build-Architecture: x86_64-unknown-linux-gnu
+/// Host-Architecture: aarch64-unknown-linux-gnu
+/// Build-Date: 2021-02-12T01:54:15+00:00
+/// Installed-Build-Depends:
+///  rustc (= 1.55.0),
+///  cargo (= 1.55.0)
+/// Environment:
+///  PROFILE="release"
+///  SOURCE_DATE_EPOCH="1613094855"
+/// Checksums-Sha256:
+///  5f1e3c0a... 18270 Cargo.lock
+/// ```
+///
+/// The fields are always in this order, and a field without a value is left out:
+///
+/// | Field | Value |
+/// | ----- | ----- |
+/// | `Format` | `1.0` |
+/// | `Source` | `CARGO_PKG_NAME` |
+/// | `Version` | `VERGEN_BUILD_SEMVER`, or `CARGO_PKG_VERSION` |
+/// | `Git-Commit` | `VERGEN_GIT_SHA` |
+/// | `Git-Describe` | `VERGEN_GIT_SEMVER`, or `VERGEN_GIT_SEMVER_LIGHTWEIGHT` |
+/// | `Hg-Commit` | `VERGEN_HG_SHA` |
+/// | `Build-Architecture` | `VERGEN_RUSTC_HOST_TRIPLE` |
+/// | `Host-Architecture` | `VERGEN_CARGO_TARGET_TRIPLE` |
+/// | `Build-Profile` | `VERGEN_CARGO_PROFILE` |
+/// | `Build-Features` | `VERGEN_CARGO_FEATURES` |
+/// | `Build-Date` | `VERGEN_BUILD_TIMESTAMP` |
+/// | `Rustc-Commit-Hash` | `VERGEN_RUSTC_COMMIT_HASH` |
+/// | `Installed-Build-Depends` | `VERGEN_RUSTC_SEMVER`, `VERGEN_RUSTC_LLVM_VERSION`, and `cargo --version` |
+/// | `Environment` | the set variables, see below |
+/// | `Checksums-Sha256` | the `Cargo.lock` of the package or its workspace |
+///
+/// * The file is written to `$OUT_DIR/vergen.buildinfo`, unless the `path` field is set.
+/// * The values are the generated ones, so the features and instructions they come from must be enabled, and they are
+///   [sanitized](crate::Sanitize) and [truncated](crate::Truncate) the same way.
+/// * `Environment` lists the `CARGO_ENCODED_RUSTFLAGS`, `DEBUG`, `OPT_LEVEL`, `PROFILE`, `RUSTC_WRAPPER`, and
+///   `SOURCE_DATE_EPOCH` variables, and the ones named by the `env` field, that are set, sorted by name.  Each one is a
+///   `NAME="value"` line, with `\` and `"` escaped with a `\`, and any other non-printable character as `\xNN`.
+/// * `Checksums-Sha256` is the SHA-256 digest, size in bytes, and name of the `Cargo.lock` found in the package root or
+///   the closest directory above it.  Cargo re-runs the build script when it changes.  It needs the `hash` feature, and
+///   is left out without it.
+/// * **NOTE** - If the `SOURCE_DATE_EPOCH` environment variable is set but the `source_date_epoch` field on the
+///   [`Config`](crate::Config) is false, the build timestamp is the current time, so `Build-Date` is left out rather
+///   than making the document differ between otherwise identical builds.
+///
+/// # Example
+///
+/// ```
+/// use vergen::Config;
+///
+/// let mut config = Config::reproducible();
+/// *config.build_info_mut().enabled_mut() = true;
+/// config
+///     .build_info_mut()
+///     .env_mut()
+///     .push("CC".to_string());
+/// ```
+///
+/// [RFC 822]: https://www.rfc-editor.org/rfc/rfc822
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
+pub struct BuildInfo {
+    /// Enable/Disable the `.buildinfo` output
+    enabled: bool,
+    /// The path of the file, or None for `$OUT_DIR/vergen.buildinfo`.
+    path: Option<PathBuf>,
+    /// The other environment variables to list in the `Environment` field, by name.
+    env: Vec<String>,
+    /// The fields set through their `set_*` setter, which win in a [merge](crate::Config::merge)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    set_fields: SetFields,
+}
+
+impl BuildInfo {
+    /// Enable/Disable the `.buildinfo` output
+    pub(crate) fn enabled(&self) -> &bool {
+        &self.enabled
+    }
+
+    /// Enable/Disable the `.buildinfo` output
+    pub fn enabled_mut(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+
+    /// Enable/Disable the `.buildinfo` output
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "enabled");
+        self.enabled = enabled;
+    }
+
+    /// The path of the file, or None for `$OUT_DIR/vergen.buildinfo`.
+    pub fn path_mut(&mut self) -> &mut Option<PathBuf> {
+        &mut self.path
+    }
+
+    /// The path of the file, or None for `$OUT_DIR/vergen.buildinfo`.
+    pub fn set_path(&mut self, path: Option<PathBuf>) {
+        self.set_fields.mark(MERGED_FIELDS, "path");
+        self.path = path;
+    }
+
+    /// The other environment variables to list in the `Environment` field, by name.
+    pub fn env_mut(&mut self) -> &mut Vec<String> {
+        &mut self.env
+    }
+}
+
+/// The [`BuildInfo`] fields that win in a [merge](crate::Config::merge) once set, see [`SetFields`]
+const MERGED_FIELDS: &[&str] = &["enabled", "path"];
+
+impl BuildInfo {
+    pub(crate) fn merge(self, overlay: Self) -> Self {
+        let default = Self::default();
+        let overlay_set = overlay.set_fields;
+        let set = |name| overlay_set.contains(MERGED_FIELDS, name);
+        Self {
+            enabled: pick(
+                self.enabled,
+                overlay.enabled,
+                &default.enabled,
+                set("enabled"),
+            ),
+            path: pick(self.path, overlay.path, &default.path, set("path")),
+            env: union(self.env, overlay.env, &default.env),
+            set_fields: self.set_fields.union(overlay_set),
+        }
+    }
+
+    pub(crate) fn validate(&self, errors: &mut Vec<ConfigError>) {
+        if !self.enabled {
+            return;
+        }
+        if self
+            .path
+            .as_ref()
+            .map_or(false, |path| path.as_os_str().is_empty())
+        {
+            errors.push(ConfigError::new(
+                "build_info.path",
+                "\"\"",
+                "use the path of the file, or None for '$OUT_DIR/vergen.buildinfo'",
+            ));
+        }
+        for name in self
+            .env
+            .iter()
+            .filter(|name| name.is_empty() || name.contains('='))
+        {
+            errors.push(ConfigError::new(
+                "build_info.env",
+                format!("{:?}", name),
+                "use the name of an environment variable, i.e. 'CC'",
+            ));
+        }
+    }
+
+    /// The names of the environment variables listed in the `Environment` field, sorted
+    pub(crate) fn env_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = ENVIRONMENT
+            .iter()
+            .copied()
+            .chain(self.env.iter().map(String::as_str))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+}
+
+/// The values that aren't generated instructions
+struct Provenance {
+    package: Option<String>,
+    version: Option<String>,
+    build_date: bool,
+    cargo_version: Option<String>,
+    environment: Vec<(String, String)>,
+    checksums: Vec<(String, String, u64)>,
+}
+
+impl Provenance {
+    fn gather(build_info: &BuildInfo, source_date_epoch: bool) -> Result<Self> {
+        let environment = build_info
+            .env_names()
+            .into_iter()
+            .filter_map(|name| env::var(name).ok().map(|value| (name.to_string(), value)))
+            .collect();
+        let mut checksums = vec![];
+        if let Some((digest, size)) = lockfile_digest()? {
+            checksums.push(("Cargo.lock".to_string(), digest, size));
+        }
+        Ok(Self {
+            package: env::var("CARGO_PKG_NAME").ok(),
+            version: env::var("CARGO_PKG_VERSION").ok(),
+            build_date: source_date_epoch || env::var_os("SOURCE_DATE_EPOCH").is_none(),
+            cargo_version: cargo_version(),
+            environment,
+            checksums,
+        })
+    }
+}
+
+/// The `Cargo.lock` of the package, or of the workspace it belongs to
+pub(crate) fn lockfile() -> Option<PathBuf> {
+    let manifest_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR")?);
+    manifest_dir
+        .ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.is_file())
+}
+
+/// The hex SHA-256 digest and size in bytes of the [`lockfile`], or None if there isn't one
+#[cfg(feature = "hash")]
+pub(crate) fn lockfile_digest() -> Result<Option<(String, u64)>> {
+    match lockfile() {
+        Some(path) => {
+            let contents = fs::read(&path)?;
+            Ok(Some((
+                hex(&Sha256::digest(&contents)),
+                contents.len() as u64,
+            )))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Without the `hash` feature there's no digest of the [`lockfile`]
+#[cfg(not(feature = "hash"))]
+#[allow(clippy::unnecessary_wraps)]
+pub(crate) fn lockfile_digest() -> Result<Option<(String, u64)>> {
+    Ok(None)
+}
+
+/// The version of the `cargo` running the build, i.e. `1.55.0` from `cargo 1.55.0 (32da73ab1 2021-08-23)`
+fn cargo_version() -> Option<String> {
+    let cargo = env::var_os("CARGO")?;
+    let output = command::output(
+        Command::new(cargo).arg("--version"),
+        Some(Instant::now() + CARGO_VERSION_TIMEOUT),
+    )
+    .ok()
+    .filter(|output| output.status.success())?;
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .nth(1)
+        .map(str::to_string)
+}
+
+#[cfg(feature = "hash")]
+fn hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    hex
+}
+
+/// The value quoted for the `Environment` field
+fn quoted(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\\' | '"' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\x{:02x}", u32::from(c));
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The contents of the `.buildinfo`
+fn buildinfo(provenance: &Provenance, config: &Config) -> String {
+    let value = |key| {
+        config
+            .cfg_map()
+            .get(&key)
+            .and_then(|value: &Option<String>| value.clone())
+    };
+    let fields = [
+        ("Format", Some("1.0".to_string())),
+        ("Source", provenance.package.clone()),
+        (
+            "Version",
+            value(VergenKey::BuildSemver).or_else(|| provenance.version.clone()),
+        ),
+        ("Git-Commit", value(VergenKey::Sha)),
+        (
+            "Git-Describe",
+            value(VergenKey::Semver).or_else(|| value(VergenKey::SemverLightweight)),
+        ),
+        ("Hg-Commit", value(VergenKey::HgSha)),
+        ("Build-Architecture", value(VergenKey::RustcHostTriple)),
+        ("Host-Architecture", value(VergenKey::CargoTargetTriple)),
+        ("Build-Profile", value(VergenKey::CargoProfile)),
+        ("Build-Features", value(VergenKey::CargoFeatures)),
+        (
+            "Build-Date",
+            value(VergenKey::BuildTimestamp).filter(|_| provenance.build_date),
+        ),
+        ("Rustc-Commit-Hash", value(VergenKey::RustcCommitHash)),
+    ];
+
+    let mut contents = String::new();
+    for (name, value) in &fields {
+        if let Some(value) = value {
+            let _ = writeln!(contents, "{}: {}", name, value);
+        }
+    }
+
+    let depends: Vec<String> = [
+        ("rustc", value(VergenKey::RustcSemver)),
+        ("llvm", value(VergenKey::RustcLlvmVersion)),
+        ("cargo", provenance.cargo_version.clone()),
+    ]
+    .iter()
+    .filter_map(|(name, version)| {
+        version
+            .as_ref()
+            .map(|version| format!(" {} (= {})", name, version))
+    })
+    .collect();
+    let environment: Vec<String> = provenance
+        .environment
+        .iter()
+        .map(|(name, value)| format!(" {}={}", name, quoted(value)))
+        .collect();
+    let checksums: Vec<String> = provenance
+        .checksums
+        .iter()
+        .map(|(name, digest, size)| format!(" {} {} {}", digest, size, name))
+        .collect();
+    for (name, lines, separator) in &[
+        ("Installed-Build-Depends", depends, ",\n"),
+        ("Environment", environment, "\n"),
+        ("Checksums-Sha256", checksums, "\n"),
+    ] {
+        if !lines.is_empty() {
+            let _ = writeln!(contents, "{}:\n{}", name, lines.join(separator));
+        }
+    }
+    contents
+}
+
+/// Write the `.buildinfo`
+pub(crate) fn output_build_info(
+    build_info: &BuildInfo,
+    source_date_epoch: bool,
+    config: &Config,
+) -> Result<()> {
+    let path = match &build_info.path {
+        Some(path) => path.clone(),
+        None => out_dir()?.join("vergen.buildinfo"),
+    };
+    let provenance = Provenance::gather(build_info, source_date_epoch)?;
+    write_atomic(&path, &buildinfo(&provenance, config))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{buildinfo, lockfile, quoted, BuildInfo, Provenance};
+    use crate::config::{Config, VergenKey};
+    use serial_test::serial;
+    use std::{env, fs, path::PathBuf};
+
+    fn golden() -> String {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata")
+            .join("buildinfo")
+            .join("golden.buildinfo");
+        fs::read_to_string(path).unwrap()
+    }
+
+    fn provenance(build_date: bool) -> Provenance {
+        Provenance {
+            package: Some("myapp".to_string()),
+            version: Some("1.4.0".to_string()),
+            build_date,
+            cargo_version: Some("1.55.0".to_string()),
+            environment: vec![
+                (
+                    "CARGO_ENCODED_RUSTFLAGS".to_string(),
+                    "-C\u{1f}opt-level=3".to_string(),
+                ),
+                ("PROFILE".to_string(), "release".to_string()),
+                ("SOURCE_DATE_EPOCH".to_string(), "1613094855".to_string()),
+            ],
+            checksums: vec![(
+                "Cargo.lock".to_string(),
+                "4f5c3a43e3cd2d4a5a7e0ee0ebd3f0b7c7aef1a1b0c5f5d0b6d0c8c1d6c5f3a1".to_string(),
+                18270,
+            )],
+        }
+    }
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        for (key, value) in &[
+            (VergenKey::Sha, "75b390dc6c05a6a4aa2791cc7b3934591803bc22"),
+            (VergenKey::Semver, "v1.4.0"),
+            (VergenKey::RustcHostTriple, "x86_64-unknown-linux-gnu"),
+            (VergenKey::CargoTargetTriple, "aarch64-unknown-linux-gnu"),
+            (VergenKey::CargoProfile, "release"),
+            (VergenKey::CargoFeatures, "tls,zstd"),
+            (VergenKey::BuildTimestamp, "2021-02-12T01:54:15+00:00"),
+            (
+                VergenKey::RustcCommitHash,
+                "c8dfcfe046a7680554bf4eb612bad840e7631c4b",
+            ),
+            (VergenKey::RustcSemver, "1.55.0"),
+            (VergenKey::RustcLlvmVersion, "12.0"),
+        ] {
+            let _ = config
+                .cfg_map_mut()
+                .insert(*key, Some((*value).to_string()));
+        }
+        config
+    }
+
+    #[test]
+    fn golden_format() {
+        assert_eq!(buildinfo(&provenance(true), &config()), golden());
+    }
+
+    #[test]
+    fn build_date_left_out() {
+        let contents = buildinfo(&provenance(false), &config());
+        assert!(!contents.contains("Build-Date:"));
+        assert_eq!(contents.lines().count(), golden().lines().count() - 1);
+    }
+
+    #[test]
+    fn missing_values_left_out() {
+        let provenance = Provenance {
+            package: None,
+            version: Some("1.4.0".to_string()),
+            build_date: true,
+            cargo_version: None,
+            environment: vec![],
+            checksums: vec![],
+        };
+        assert_eq!(
+            buildinfo(&provenance, &Config::default()),
+            "Format: 1.0\nVersion: 1.4.0\n"
+        );
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quoted("release"), "\"release\"");
+        assert_eq!(quoted("say \"hi\" \\"), "\"say \\\"hi\\\" \\\\\"");
+        assert_eq!(quoted("-C\u{1f}debuginfo=2"), "\"-C\\x1fdebuginfo=2\"");
+    }
+
+    #[test]
+    fn env_names() {
+        let mut build_info = BuildInfo::default();
+        build_info.env_mut().push("CC".to_string());
+        build_info.env_mut().push("PROFILE".to_string());
+        assert_eq!(
+            build_info.env_names(),
+            [
+                "CARGO_ENCODED_RUSTFLAGS",
+                "CC",
+                "DEBUG",
+                "OPT_LEVEL",
+                "PROFILE",
+                "RUSTC_WRAPPER",
+                "SOURCE_DATE_EPOCH"
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_settings() {
+        let mut build_info = BuildInfo::default();
+        *build_info.path_mut() = Some(PathBuf::new());
+        build_info.env_mut().push("A=B".to_string());
+        let mut errors = vec![];
+        build_info.validate(&mut errors);
+        assert!(errors.is_empty());
+        *build_info.enabled_mut() = true;
+        build_info.validate(&mut errors);
+        let fields: Vec<&str> = errors.iter().map(|error| error.field().as_str()).collect();
+        assert_eq!(fields, ["build_info.path", "build_info.env"]);
+    }
+
+    #[test]
+    fn workspace_lockfile() {
+        assert_eq!(
+            lockfile(),
+            Some(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.lock"))
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn gathered_provenance() {
+        env::set_var("SOURCE_DATE_EPOCH", "1613094855");
+        let mut build_info = BuildInfo::default();
+        *build_info.enabled_mut() = true;
+        let provenance = Provenance::gather(&build_info, false).unwrap();
+        assert!(!provenance.build_date);
+        assert!(provenance
+            .environment
+            .contains(&("SOURCE_DATE_EPOCH".to_string(), "1613094855".to_string())));
+        assert!(Provenance::gather(&build_info, true).unwrap().build_date);
+        env::remove_var("SOURCE_DATE_EPOCH");
+        assert!(Provenance::gather(&build_info, false).unwrap().build_date);
+
+        assert_eq!(provenance.package.as_deref(), Some("vergen"));
+        #[cfg(feature = "hash")]
+        {
+            assert_eq!(provenance.checksums.len(), 1);
+            assert_eq!(provenance.checksums[0].1.len(), 64);
+        }
+    }
+}
@@ -0,0 +1,572 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` output compatible with the `built` crate
+
+use crate::{
+    config::{Config, VergenKey},
+    error::ConfigError,
+    merge::{pick, SetFields},
+    output::{out_dir, write_atomic},
+};
+use anyhow::Result;
+use std::{fmt::Write, path::PathBuf};
+
+/// Configuration for writing the generated values as a `built` compatible `built.rs`
+///
+/// When the `enabled` field is true, a `built.rs` with the constants generated by the
+/// [`built`](https://docs.rs/built) crate is written, so code that includes it compiles unchanged
+/// while migrating to `vergen`.
+///
+/// ```text
+/// pub mod built_info {
+///     include!(concat!(env!("OUT_DIR"), "/built.rs"));
+/// }
+/// ```
+///
+/// * The file is written to `$OUT_DIR/built.rs`, unless the `path` field is set.
+/// * Each constant is filled in from the matching `vergen` instruction, i.e. `GIT_COMMIT_HASH` from `VERGEN_GIT_SHA`,
+///   so the instruction must be enabled.
+/// * Every constant is generated.  A constant without a matching instruction, or whose instruction is disabled, is
+///   empty, `0`, `false`, or `None`, and its doc comment says so.
+/// * **NOTE** - `BUILT_TIME_UTC` is the `VERGEN_BUILD_TIMESTAMP` value, which is RFC 3339 rather than RFC 2822.
+///
+/// # Example
+///
+/// ```
+/// use vergen::Config;
+///
+/// let mut config = Config::default();
+/// *config.built_compat_mut().enabled_mut() = true;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
+pub struct BuiltCompat {
+    /// Enable/Disable the `built.rs` output
+    enabled: bool,
+    /// The path of the file, or None for `$OUT_DIR/built.rs`.
+    path: Option<PathBuf>,
+    /// The fields set through their `set_*` setter, which win in a [merge](crate::Config::merge)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    set_fields: SetFields,
+}
+
+impl BuiltCompat {
+    /// Enable/Disable the `built.rs` output
+    pub(crate) fn enabled(&self) -> &bool {
+        &self.enabled
+    }
+
+    /// Enable/Disable the `built.rs` output
+    pub fn enabled_mut(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+
+    /// Enable/Disable the `built.rs` output
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "enabled");
+        self.enabled = enabled;
+    }
+
+    /// The path of the file, or None for `$OUT_DIR/built.rs`.
+    pub fn path_mut(&mut self) -> &mut Option<PathBuf> {
+        &mut self.path
+    }
+
+    /// The path of the file, or None for `$OUT_DIR/built.rs`.
+    pub fn set_path(&mut self, path: Option<PathBuf>) {
+        self.set_fields.mark(MERGED_FIELDS, "path");
+        self.path = path;
+    }
+}
+
+/// The [`BuiltCompat`] fields that win in a [merge](crate::Config::merge) once set, see [`SetFields`]
+const MERGED_FIELDS: &[&str] = &["enabled", "path"];
+
+impl BuiltCompat {
+    pub(crate) fn merge(self, overlay: Self) -> Self {
+        let default = Self::default();
+        let overlay_set = overlay.set_fields;
+        let set = |name| overlay_set.contains(MERGED_FIELDS, name);
+        Self {
+            enabled: pick(
+                self.enabled,
+                overlay.enabled,
+                &default.enabled,
+                set("enabled"),
+            ),
+            path: pick(self.path, overlay.path, &default.path, set("path")),
+            set_fields: self.set_fields.union(overlay_set),
+        }
+    }
+
+    pub(crate) fn validate(&self, errors: &mut Vec<ConfigError>) {
+        if let Some(path) = &self.path {
+            if self.enabled && path.as_os_str().is_empty() {
+                errors.push(ConfigError::new(
+                    "built_compat.path",
+                    "\"\"",
+                    "use the path of the file, or None for '$OUT_DIR/built.rs'",
+                ));
+            }
+        }
+    }
+}
+
+const UNPOPULATED: &str = "Not populated by vergen.";
+
+/// Writes the constants, with their doc comments
+struct Constants<'a> {
+    config: &'a Config,
+    contents: String,
+}
+
+impl<'a> Constants<'a> {
+    fn value(&self, key: VergenKey) -> Option<&'a str> {
+        self.config
+            .cfg_map()
+            .get(&key)
+            .and_then(|value| value.as_deref())
+    }
+
+    fn constant(&mut self, doc: &str, name: &str, ty: &str, value: &str) {
+        let _ = writeln!(
+            self.contents,
+            "/// {}\n#[allow(dead_code)]\npub const {}: {} = {};",
+            doc, name, ty, value
+        );
+    }
+
+    fn text(&mut self, doc: &str, name: &str, value: Option<&str>) {
+        match value {
+            Some(value) => self.constant(doc, name, "&str", &format!("{:?}", value)),
+            None => self.constant(&format!("{}  {}", doc, UNPOPULATED), name, "&str", "\"\""),
+        }
+    }
+
+    fn key(&mut self, doc: &str, name: &str, key: VergenKey) {
+        let value = self.value(key);
+        self.text(doc, name, value);
+    }
+
+    fn option(&mut self, doc: &str, name: &str, value: Option<String>) {
+        match value {
+            Some(value) => self.constant(doc, name, "Option<&str>", &format!("Some({:?})", value)),
+            None => self.constant(
+                &format!("{}  {}", doc, UNPOPULATED),
+                name,
+                "Option<&str>",
+                "None",
+            ),
+        }
+    }
+
+    /// The `FEATURES*` constants
+    fn features(&mut self) {
+        let features: Vec<String> = self
+            .value(VergenKey::CargoFeatures)
+            .map(|features| features.split(',').map(str::to_string).collect())
+            .unwrap_or_default();
+        let list = |features: &[String]| {
+            features
+                .iter()
+                .map(|feature| format!("{:?}", feature))
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        let lowercase: Vec<String> = features.iter().map(|f| f.to_lowercase()).collect();
+        let ty = format!("[&str; {}]", features.len());
+        let doc = if self.value(VergenKey::CargoFeatures).is_some() {
+            String::new()
+        } else {
+            format!("  {}", UNPOPULATED)
+        };
+
+        self.constant(
+            &format!("The features that were enabled during compilation.{}", doc),
+            "FEATURES",
+            &ty,
+            &format!("[{}]", list(&features)),
+        );
+        self.constant(
+            &format!("The features as a comma-separated string.{}", doc),
+            "FEATURES_STR",
+            "&str",
+            &format!("{:?}", features.join(", ")),
+        );
+        self.constant(
+            &format!("The features as above, as lowercase strings.{}", doc),
+            "FEATURES_LOWERCASE",
+            &ty,
+            &format!("[{}]", list(&lowercase)),
+        );
+        self.constant(
+            &format!(
+                "The feature-string as above, from lowercase strings.{}",
+                doc
+            ),
+            "FEATURES_LOWERCASE_STR",
+            "&str",
+            &format!("{:?}", lowercase.join(", ")),
+        );
+    }
+
+    /// The `PKG_*` constants
+    fn package(&mut self) {
+        let version = self.value(VergenKey::BuildSemver);
+        let [major, minor, patch, pre] = version_parts(version);
+        self.text("The full version.", "PKG_VERSION", version);
+        self.text("The major version.", "PKG_VERSION_MAJOR", major);
+        self.text("The minor version.", "PKG_VERSION_MINOR", minor);
+        self.text("The patch version.", "PKG_VERSION_PATCH", patch);
+        self.text("The pre-release version.", "PKG_VERSION_PRE", pre);
+        self.key(
+            "A colon-separated list of authors.",
+            "PKG_AUTHORS",
+            VergenKey::CargoPkgAuthors,
+        );
+        self.text("The name of the package.", "PKG_NAME", None);
+        self.key(
+            "The description.",
+            "PKG_DESCRIPTION",
+            VergenKey::CargoPkgDescription,
+        );
+        self.key("The homepage.", "PKG_HOMEPAGE", VergenKey::CargoPkgHomepage);
+        self.key("The license.", "PKG_LICENSE", VergenKey::CargoPkgLicense);
+        self.key(
+            "The source repository as advertised in Cargo.toml.",
+            "PKG_REPOSITORY",
+            VergenKey::CargoPkgRepository,
+        );
+    }
+
+    /// The compiler and profile constants
+    fn compiler(&mut self) {
+        let target = self
+            .value(VergenKey::CargoTargetTriple)
+            .or_else(|| self.value(VergenKey::TargetTriple));
+        self.text(
+            "The target triple that was being compiled for.",
+            "TARGET",
+            target,
+        );
+        self.key(
+            "The host triple of the rust compiler.",
+            "HOST",
+            VergenKey::RustcHostTriple,
+        );
+        self.key(
+            "`release` for release builds, `debug` for other builds.",
+            "PROFILE",
+            VergenKey::CargoProfile,
+        );
+        self.text("The compiler that cargo resolved to use.", "RUSTC", None);
+        self.text(
+            "The documentation generator that cargo resolved to use.",
+            "RUSTDOC",
+            None,
+        );
+        let rustc_version = rustc_version(self);
+        self.text(
+            "The output of `rustc -V`",
+            "RUSTC_VERSION",
+            rustc_version.as_deref(),
+        );
+        self.text("The output of `rustdoc -V`", "RUSTDOC_VERSION", None);
+        self.text(
+            "Value of OPT_LEVEL for the profile used during compilation.",
+            "OPT_LEVEL",
+            None,
+        );
+        self.constant(
+            &format!(
+                "The parallelism that was specified during compilation.  {}",
+                UNPOPULATED
+            ),
+            "NUM_JOBS",
+            "u32",
+            "0",
+        );
+        self.constant(
+            &format!(
+                "Value of DEBUG for the profile used during compilation.  {}",
+                UNPOPULATED
+            ),
+            "DEBUG",
+            "bool",
+            "false",
+        );
+    }
+
+    /// The `GIT_*` and `CI_PLATFORM` constants
+    fn git(&mut self) {
+        self.option(
+            "The Continuous Integration platform detected during compilation.",
+            "CI_PLATFORM",
+            None,
+        );
+        let git_version = self
+            .value(VergenKey::Semver)
+            .or_else(|| self.value(VergenKey::SemverLightweight))
+            .map(str::to_string);
+        self.option(
+            "If the crate was compiled from within a git-repository, `GIT_VERSION` contains HEAD's tag. The short commit id is used if HEAD is not tagged.",
+            "GIT_VERSION",
+            git_version,
+        );
+        self.constant(
+            &format!("If the repository had dirty/staged files.  {}", UNPOPULATED),
+            "GIT_DIRTY",
+            "Option<bool>",
+            "None",
+        );
+        let head_ref = self
+            .value(VergenKey::Branch)
+            .filter(|branch| *branch != "detached HEAD")
+            .map(|branch| format!("refs/heads/{}", branch));
+        self.option(
+            "If the crate was compiled from within a git-repository, `GIT_HEAD_REF` contains full name to the reference pointed to by HEAD (e.g.: `refs/heads/master`). If HEAD is detached or the branch name is not valid UTF-8 `None` will be stored.",
+            "GIT_HEAD_REF",
+            head_ref,
+        );
+        let sha = self.value(VergenKey::Sha).map(str::to_string);
+        self.option(
+            "If the crate was compiled from within a git-repository, `GIT_COMMIT_HASH` contains HEAD's full commit SHA-1 hash.",
+            "GIT_COMMIT_HASH",
+            sha,
+        );
+        let short_sha = self.value(VergenKey::ShortSha).map(str::to_string);
+        self.option(
+            "If the crate was compiled from within a git-repository, `GIT_COMMIT_HASH_SHORT` contains HEAD's short commit SHA-1 hash.",
+            "GIT_COMMIT_HASH_SHORT",
+            short_sha,
+        );
+    }
+
+    /// The build time, target, and dependency constants
+    fn target(&mut self) {
+        self.key(
+            "The built-time in RFC 3339 (not RFC 2822, as generated by `built`).",
+            "BUILT_TIME_UTC",
+            VergenKey::BuildTimestamp,
+        );
+        self.key(
+            "The target architecture, given by `CARGO_CFG_TARGET_ARCH`.",
+            "CFG_TARGET_ARCH",
+            VergenKey::CargoTargetArch,
+        );
+        self.key(
+            "The endianness, given by `CARGO_CFG_TARGET_ENDIAN`.",
+            "CFG_ENDIAN",
+            VergenKey::CargoTargetEndian,
+        );
+        self.key(
+            "The toolchain-environment, given by `CARGO_CFG_TARGET_ENV`.",
+            "CFG_ENV",
+            VergenKey::CargoTargetEnv,
+        );
+        self.text(
+            "The OS-family, given by `CARGO_CFG_TARGET_FAMILY`.",
+            "CFG_FAMILY",
+            None,
+        );
+        self.key(
+            "The operating system, given by `CARGO_CFG_TARGET_OS`.",
+            "CFG_OS",
+            VergenKey::CargoTargetOs,
+        );
+        self.key(
+            "The pointer width, given by `CARGO_CFG_TARGET_POINTER_WIDTH`.",
+            "CFG_POINTER_WIDTH",
+            VergenKey::CargoTargetPointerWidth,
+        );
+        self.constant(
+            &format!(
+                "An array of effective dependencies as documented by `Cargo.lock`.  {}",
+                UNPOPULATED
+            ),
+            "DEPENDENCIES",
+            "[(&str, &str); 0]",
+            "[]",
+        );
+        self.text(
+            "The effective dependencies as a comma-separated string.",
+            "DEPENDENCIES_STR",
+            None,
+        );
+    }
+}
+
+/// The `MAJOR.MINOR.PATCH-PRE` parts of the version
+pub(crate) fn version_parts(version: Option<&str>) -> [Option<&str>; 4] {
+    version.map_or([None; 4], |version| {
+        let (core, pre) = match version.find(&['-', '+'][..]) {
+            Some(idx) if version[idx..].starts_with('-') => {
+                let pre = &version[idx + 1..];
+                (&version[..idx], pre.split('+').next())
+            }
+            Some(idx) => (&version[..idx], Some("")),
+            None => (version, Some("")),
+        };
+        let mut parts = core.split('.');
+        [parts.next(), parts.next(), parts.next(), pre]
+    })
+}
+
+/// The `rustc --version` output
+fn rustc_version(constants: &Constants<'_>) -> Option<String> {
+    constants.value(VergenKey::RustcSemver).map(|semver| {
+        match (
+            constants.value(VergenKey::RustcCommitHash),
+            constants.value(VergenKey::RustcCommitDate),
+        ) {
+            (Some(hash), Some(date)) => format!(
+                "rustc {} ({} {})",
+                semver,
+                hash.get(..9).unwrap_or(hash),
+                date
+            ),
+            _ => format!("rustc {}", semver),
+        }
+    })
+}
+
+/// The contents of the `built.rs`
+fn built_rs(config: &Config) -> String {
+    let mut constants = Constants {
+        config,
+        contents: String::from("// Generated by vergen, compatible with the `built` crate\n\n"),
+    };
+
+    constants.package();
+    constants.compiler();
+    constants.features();
+    constants.git();
+    constants.target();
+
+    constants.contents
+}
+
+/// Write the `built.rs`
+pub(crate) fn output_built_compat(built_compat: &BuiltCompat, config: &Config) -> Result<()> {
+    let path = match &built_compat.path {
+        Some(path) => path.clone(),
+        None => out_dir()?.join("built.rs"),
+    };
+    write_atomic(&path, &built_rs(config))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{built_rs, output_built_compat, version_parts, BuiltCompat};
+    use crate::config::{Config, VergenKey};
+    use std::{env, fs, path::PathBuf, process::Command};
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        for (key, value) in &[
+            (VergenKey::BuildSemver, "1.2.3-beta.1+build"),
+            (VergenKey::Sha, "95fc0f5d066710f16e0c23ce3239d6e040abca0d"),
+            (VergenKey::Branch, "main"),
+            (VergenKey::CargoFeatures, "GIT,BUILD"),
+            (VergenKey::RustcSemver, "1.52.0"),
+            (
+                VergenKey::RustcCommitHash,
+                "88f19c6dab716c6281af7602e30f413e809c5974",
+            ),
+            (VergenKey::RustcCommitDate, "2021-05-03"),
+        ] {
+            let _ = config
+                .cfg_map_mut()
+                .insert(*key, Some((*value).to_string()));
+        }
+        config
+    }
+
+    #[test]
+    fn versions() {
+        assert_eq!(
+            version_parts(Some("1.2.3-beta.1+build")),
+            [Some("1"), Some("2"), Some("3"), Some("beta.1")]
+        );
+        assert_eq!(
+            version_parts(Some("1.2.3+build")),
+            [Some("1"), Some("2"), Some("3"), Some("")]
+        );
+        assert_eq!(version_parts(None), [None; 4]);
+    }
+
+    #[test]
+    fn constants() {
+        let built = built_rs(&config());
+        assert!(built.contains("pub const PKG_VERSION: &str = \"1.2.3-beta.1+build\";\n"));
+        assert!(built.contains("pub const PKG_VERSION_PRE: &str = \"beta.1\";\n"));
+        assert!(built.contains(
+            "pub const GIT_COMMIT_HASH: Option<&str> = Some(\"95fc0f5d066710f16e0c23ce3239d6e040abca0d\");\n"
+        ));
+        assert!(
+            built.contains("pub const GIT_HEAD_REF: Option<&str> = Some(\"refs/heads/main\");\n")
+        );
+        assert!(built.contains("pub const FEATURES: [&str; 2] = [\"GIT\", \"BUILD\"];\n"));
+        assert!(built.contains("pub const FEATURES_LOWERCASE_STR: &str = \"git, build\";\n"));
+        assert!(built.contains(
+            "pub const RUSTC_VERSION: &str = \"rustc 1.52.0 (88f19c6da 2021-05-03)\";\n"
+        ));
+        assert!(built.contains(
+            "/// The name of the package.  Not populated by vergen.\n#[allow(dead_code)]\npub const PKG_NAME: &str = \"\";\n"
+        ));
+        assert!(built.contains("pub const GIT_COMMIT_HASH_SHORT: Option<&str> = None;\n"));
+
+        let built = built_rs(&Config::default());
+        assert!(built.contains("pub const FEATURES: [&str; 0] = [];\n"));
+        assert!(built.contains("pub const GIT_VERSION: Option<&str> = None;\n"));
+    }
+
+    #[test]
+    fn invalid_path() {
+        let mut built_compat = BuiltCompat::default();
+        *built_compat.path_mut() = Some(PathBuf::new());
+        let mut errors = vec![];
+        built_compat.validate(&mut errors);
+        assert!(errors.is_empty());
+        *built_compat.enabled_mut() = true;
+        built_compat.validate(&mut errors);
+        assert_eq!(errors[0].field(), "built_compat.path");
+    }
+
+    #[test]
+    fn consumer_compiles() {
+        let dir = env::temp_dir().join("vergen_built_compat");
+        let _ = fs::remove_dir_all(&dir);
+        for config in &[config(), Config::default()] {
+            let mut built_compat = BuiltCompat::default();
+            *built_compat.path_mut() = Some(dir.join("built.rs"));
+            output_built_compat(&built_compat, config).unwrap();
+
+            let status = Command::new(env::var_os("RUSTC").unwrap_or_else(|| "rustc".into()))
+                .env("OUT_DIR", &dir)
+                .arg("--edition=2018")
+                .arg("--crate-type=lib")
+                .arg("--out-dir")
+                .arg(&dir)
+                .arg(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/built/consumer.rs"))
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
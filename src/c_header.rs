@@ -0,0 +1,283 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` C header output
+
+use crate::{
+    config::Config,
+    error::ConfigError,
+    merge::{pick, SetFields},
+    output::{out_dir, write_atomic},
+};
+use anyhow::Result;
+use std::{fmt::Write, path::PathBuf};
+
+/// Configuration for writing the generated values to a C header
+///
+/// When the `enabled` field is true, a header with a `#define` for every generated value is written, i.e.
+/// `#define VERGEN_GIT_SHA "95fc0f5d066710f16e0c23ce3239d6e040abca0d"`, for C and C++ code linking against the crate.
+///
+/// * The header is written to `$OUT_DIR/vergen.h`, unless the `path` field is set.
+/// * The header has a `VERGEN_H` include guard.
+/// * The values are C string literals, with quotes, backslashes, and any non-printable or non-ASCII bytes escaped.
+/// * If the `table` field is true, a `vergen_table` array of `{ name, value }` pairs is also generated, terminated by `{ 0, 0 }`.
+///
+/// # Example
+///
+/// ```
+/// use vergen::Config;
+///
+/// let mut config = Config::default();
+/// *config.c_header_mut().enabled_mut() = true;
+/// *config.c_header_mut().table_mut() = true;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
+pub struct CHeader {
+    /// Enable/Disable the C header
+    enabled: bool,
+    /// The path of the header, or None for `$OUT_DIR/vergen.h`.
+    path: Option<PathBuf>,
+    /// Generate the `vergen_table` array of name/value pairs.
+    table: bool,
+    /// The fields set through their `set_*` setter, which win in a [merge](crate::Config::merge)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    set_fields: SetFields,
+}
+
+impl CHeader {
+    /// Enable/Disable the C header
+    pub(crate) fn enabled(&self) -> &bool {
+        &self.enabled
+    }
+
+    /// Enable/Disable the C header
+    pub fn enabled_mut(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+
+    /// Enable/Disable the C header
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "enabled");
+        self.enabled = enabled;
+    }
+
+    /// The path of the header, or None for `$OUT_DIR/vergen.h`.
+    pub fn path_mut(&mut self) -> &mut Option<PathBuf> {
+        &mut self.path
+    }
+
+    /// The path of the header, or None for `$OUT_DIR/vergen.h`.
+    pub fn set_path(&mut self, path: Option<PathBuf>) {
+        self.set_fields.mark(MERGED_FIELDS, "path");
+        self.path = path;
+    }
+
+    /// Generate the `vergen_table` array of name/value pairs.
+    pub fn table_mut(&mut self) -> &mut bool {
+        &mut self.table
+    }
+
+    /// Generate the `vergen_table` array of name/value pairs.
+    pub fn set_table(&mut self, table: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "table");
+        self.table = table;
+    }
+}
+
+/// The [`CHeader`] fields that win in a [merge](crate::Config::merge) once set, see [`SetFields`]
+const MERGED_FIELDS: &[&str] = &["enabled", "path", "table"];
+
+impl CHeader {
+    pub(crate) fn merge(self, overlay: Self) -> Self {
+        let default = Self::default();
+        let overlay_set = overlay.set_fields;
+        let set = |name| overlay_set.contains(MERGED_FIELDS, name);
+        Self {
+            enabled: pick(
+                self.enabled,
+                overlay.enabled,
+                &default.enabled,
+                set("enabled"),
+            ),
+            path: pick(self.path, overlay.path, &default.path, set("path")),
+            table: pick(self.table, overlay.table, &default.table, set("table")),
+            set_fields: self.set_fields.union(overlay_set),
+        }
+    }
+
+    pub(crate) fn validate(&self, errors: &mut Vec<ConfigError>) {
+        if let Some(path) = &self.path {
+            if self.enabled && path.as_os_str().is_empty() {
+                errors.push(ConfigError::new(
+                    "c_header.path",
+                    "\"\"",
+                    "use the path of the header, or None for '$OUT_DIR/vergen.h'",
+                ));
+            }
+        }
+    }
+}
+
+/// The value as a C string literal
+fn c_string(value: &str) -> String {
+    let mut literal = String::with_capacity(value.len() + 2);
+    literal.push('"');
+    for byte in value.bytes() {
+        match byte {
+            b'"' => literal.push_str("\\\""),
+            b'\\' => literal.push_str("\\\\"),
+            // Avoid trigraphs
+            b'?' => literal.push_str("\\?"),
+            // Octal escapes are at most 3 digits, so unlike hex they can't swallow the next character
+            0x20..=0x7e => literal.push(char::from(byte)),
+            _ => {
+                let _ = write!(literal, "\\{:03o}", byte);
+            }
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// The contents of the C header
+fn c_header(c_header: &CHeader, config: &Config) -> String {
+    let values: Vec<(&str, &String)> = config
+        .cfg_map()
+        .iter()
+        .filter_map(|(key, value)| value.as_ref().map(|value| (key.name(), value)))
+        .collect();
+
+    let mut header =
+        String::from("/* Generated by vergen */\n#ifndef VERGEN_H\n#define VERGEN_H\n\n");
+    for (name, value) in &values {
+        let _ = writeln!(header, "#define {} {}", name, c_string(value));
+    }
+
+    if c_header.table {
+        header.push_str("\nstatic const char *const vergen_table[][2] = {\n");
+        for (name, _) in &values {
+            let _ = writeln!(header, "    {{ \"{}\", {} }},", name, name);
+        }
+        header.push_str("    { 0, 0 }\n};\n");
+    }
+
+    header.push_str("\n#endif /* VERGEN_H */\n");
+    header
+}
+
+/// Write the C header
+pub(crate) fn output_c_header(c_header_config: &CHeader, config: &Config) -> Result<()> {
+    let path = match &c_header_config.path {
+        Some(path) => path.clone(),
+        None => out_dir()?.join("vergen.h"),
+    };
+    write_atomic(&path, &c_header(c_header_config, config))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{c_header, c_string, output_c_header, CHeader};
+    use crate::config::{Config, VergenKey};
+    use std::{env, fs, path::PathBuf, process::Command};
+
+    const TRICKY: &str = "say \"hi\" \\ ??= tab\there\u{1}7 ünï";
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Sha, Some("abc123".to_string()));
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Branch, Some(TRICKY.to_string()));
+        config
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(c_string("abc"), "\"abc\"");
+        assert_eq!(c_string("a\"b\\c"), "\"a\\\"b\\\\c\"");
+        assert_eq!(c_string("??="), "\"\\?\\?=\"");
+        assert_eq!(c_string("\t1"), "\"\\0111\"");
+        assert_eq!(c_string("ü"), "\"\\303\\274\"");
+    }
+
+    #[test]
+    fn header_layout() {
+        let mut header_config = CHeader::default();
+        let header = c_header(&header_config, &config());
+        assert!(header.contains("#ifndef VERGEN_H\n#define VERGEN_H\n"));
+        assert!(header.contains("#define VERGEN_GIT_SHA \"abc123\"\n"));
+        assert!(header.ends_with("#endif /* VERGEN_H */\n"));
+        assert!(!header.contains("vergen_table"));
+
+        *header_config.table_mut() = true;
+        let header = c_header(&header_config, &config());
+        assert!(header.contains("    { \"VERGEN_GIT_SHA\", VERGEN_GIT_SHA },\n    { 0, 0 }\n};\n"));
+    }
+
+    #[test]
+    fn invalid_path() {
+        let mut header_config = CHeader::default();
+        *header_config.path_mut() = Some(PathBuf::new());
+        let mut errors = vec![];
+        header_config.validate(&mut errors);
+        assert!(errors.is_empty());
+        *header_config.enabled_mut() = true;
+        header_config.validate(&mut errors);
+        assert_eq!(errors[0].field(), "c_header.path");
+    }
+
+    #[test]
+    fn compiles_and_round_trips() {
+        let dir = env::temp_dir().join("vergen_c_header");
+        let _ = fs::remove_dir_all(&dir);
+        let mut header_config = CHeader::default();
+        *header_config.path_mut() = Some(dir.join("include").join("vergen.h"));
+        *header_config.table_mut() = true;
+        output_c_header(&header_config, &config()).unwrap();
+
+        let source = dir.join("main.c");
+        fs::write(
+            &source,
+            "#include <stdio.h>\n#include \"vergen.h\"\n#include \"vergen.h\"\nint main(void) {\n    \
+             const char *const (*row)[2];\n    fputs(VERGEN_GIT_BRANCH, stdout);\n    \
+             for (row = vergen_table; (*row)[0]; row++) { if ((*row)[1] == 0) return 1; }\n    \
+             return 0;\n}\n",
+        )
+        .unwrap();
+        let binary = dir.join("main");
+        let compiled = Command::new(env::var_os("CC").unwrap_or_else(|| "cc".into()))
+            .arg("-Wall")
+            .arg("-Werror")
+            .arg("-I")
+            .arg(dir.join("include"))
+            .arg("-o")
+            .arg(&binary)
+            .arg(&source)
+            .status();
+
+        // Skipped when no C compiler is available
+        if let Ok(status) = compiled {
+            assert!(status.success());
+            let output = Command::new(&binary).output().unwrap();
+            assert!(output.status.success());
+            assert_eq!(String::from_utf8(output.stdout).unwrap(), TRICKY);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
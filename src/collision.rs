@@ -0,0 +1,325 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` instruction name collisions between the sources of the emitted values
+
+use crate::{
+    config::{Config, DuplicatePolicy, Instructions, VergenKey},
+    error::Error,
+    package::suffix,
+};
+use anyhow::Result;
+use std::{collections::BTreeMap, fmt};
+
+/// Where an emitted instruction comes from
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Source {
+    /// A built-in instruction
+    BuiltIn,
+    /// A built-in instruction whose value was read from the overrides file
+    OverridesFile,
+    /// A command added with `add_command`
+    Command,
+    /// A command added with `add_command_key`
+    CommandKey,
+    /// A package's suffixed copy of a built-in instruction
+    Package(String, VergenKey),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::BuiltIn => write!(f, "built-in key"),
+            Source::OverridesFile => write!(f, "overrides file"),
+            Source::Command => write!(f, "command"),
+            Source::CommandKey => write!(f, "command key"),
+            Source::Package(package, _) => write!(f, "package '{}'", package),
+        }
+    }
+}
+
+/// The emitted `rustc-env` instruction names and their sources, in the order they're emitted
+fn emitted(instructions: &Instructions, config: &Config) -> Vec<(String, Source)> {
+    let mut emitted = vec![];
+    for (key, _) in config.cfg_map().iter().filter(|(_, value)| value.is_some()) {
+        let source = if config.file_keys().contains(key) {
+            Source::OverridesFile
+        } else {
+            Source::BuiltIn
+        };
+        emitted.push((key.name().to_string(), source));
+    }
+    for name in config.commands().keys() {
+        let source = if instructions.commands().contains_key(name) {
+            Source::Command
+        } else {
+            Source::CommandKey
+        };
+        emitted.push((name.clone(), source));
+    }
+    for (package, values) in config.packages() {
+        let suffix = suffix(package);
+        for (key, _) in values.iter().filter(|(_, value)| value.is_some()) {
+            emitted.push((
+                format!("{}_{}", key.name(), suffix),
+                Source::Package(package.clone(), *key),
+            ));
+        }
+    }
+    emitted
+}
+
+/// Apply the duplicate policy to the emitted instruction names that are the same, ignoring case
+///
+/// Windows environment variable names ignore case, so `BUILD_NUMBER` and `build_number` are the same variable there.
+pub(crate) fn check_collisions(instructions: &Instructions, config: &mut Config) -> Result<()> {
+    let mut by_name: BTreeMap<String, Vec<(String, Source)>> = BTreeMap::new();
+    for (name, source) in emitted(instructions, config) {
+        by_name
+            .entry(name.to_ascii_uppercase())
+            .or_default()
+            .push((name, source));
+    }
+    let collisions: Vec<&Vec<(String, Source)>> = by_name
+        .values()
+        .filter(|sources| sources.len() > 1)
+        .collect();
+    if collisions.is_empty() {
+        return Ok(());
+    }
+
+    let described = collisions
+        .iter()
+        .map(|sources| {
+            sources
+                .iter()
+                .map(|(name, source)| format!("{} ({})", name, source))
+                .collect::<Vec<String>>()
+                .join(" and ")
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+    match *instructions.on_duplicate_key() {
+        DuplicatePolicy::Error => return Err(Error::KeyCollision(described).into()),
+        DuplicatePolicy::KeepFirst => {
+            for (name, source) in collisions.iter().flat_map(|sources| sources.iter().skip(1)) {
+                match source {
+                    Source::BuiltIn | Source::OverridesFile => {}
+                    Source::Command | Source::CommandKey => {
+                        let _ = config.commands_mut().remove(name);
+                    }
+                    Source::Package(package, key) => {
+                        if let Some(values) = config.packages_mut().get_mut(package) {
+                            let _ = values.insert(*key, None);
+                        }
+                    }
+                }
+            }
+        }
+        DuplicatePolicy::Overwrite => config.warnings_mut().push(format!(
+            "vergen: {} are the same instruction, ignoring case, the later value wins",
+            described
+        )),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::check_collisions;
+    use crate::{
+        config::{Config, DuplicatePolicy, Instructions, VergenKey},
+        package::Package,
+    };
+    use std::process::Command;
+
+    fn set(config: &mut Config, key: VergenKey) {
+        let _ = config.cfg_map_mut().insert(key, Some("value".to_string()));
+    }
+
+    fn command(instructions: &mut Instructions, config: &mut Config, name: &str) {
+        instructions.add_command(name, "true", &[]);
+        let _ = config
+            .commands_mut()
+            .insert(name.to_string(), "value".to_string());
+    }
+
+    fn command_key(instructions: &mut Instructions, config: &mut Config, name: &str) {
+        instructions.add_command_key(name, Command::new("true"));
+        let _ = config
+            .commands_mut()
+            .insert(format!("VERGEN_CMD_{}", name), "value".to_string());
+    }
+
+    fn package(instructions: &mut Instructions, config: &mut Config, name: &str, key: VergenKey) {
+        instructions.for_package(name, Package::default());
+        let _ = config
+            .packages_mut()
+            .entry(name.to_string())
+            .or_default()
+            .insert(key, Some("value".to_string()));
+    }
+
+    fn collision(instructions: &Instructions, config: &mut Config) -> String {
+        check_collisions(instructions, config)
+            .unwrap_err()
+            .to_string()
+    }
+
+    fn error_policy() -> Instructions {
+        let mut instructions = Instructions::default();
+        *instructions.on_duplicate_key_mut() = DuplicatePolicy::Error;
+        instructions
+    }
+
+    #[test]
+    fn built_in_keys() {
+        let mut instructions = error_policy();
+        let mut config = Config::default();
+        for key in VergenKey::ALL.iter().copied() {
+            let _ = config.cfg_map_mut().insert(key, Some("value".to_string()));
+        }
+        command(&mut instructions, &mut config, "BUILD_NUMBER");
+        command_key(&mut instructions, &mut config, "RELEASE_TRAIN");
+        package(&mut instructions, &mut config, "api", VergenKey::Sha);
+        assert!(check_collisions(&instructions, &mut config).is_ok());
+        assert!(config.warnings().is_empty());
+    }
+
+    #[test]
+    fn built_in_and_command() {
+        let mut instructions = error_policy();
+        let mut config = Config::default();
+        set(&mut config, VergenKey::Sha);
+        command(&mut instructions, &mut config, "vergen_git_sha");
+        assert!(collision(&instructions, &mut config)
+            .ends_with("VERGEN_GIT_SHA (built-in key) and vergen_git_sha (command)"));
+    }
+
+    #[test]
+    fn built_in_and_package() {
+        let mut instructions = error_policy();
+        let mut config = Config::default();
+        set(&mut config, VergenKey::ShortSha);
+        package(&mut instructions, &mut config, "short", VergenKey::Sha);
+        assert!(collision(&instructions, &mut config).ends_with(
+            "VERGEN_GIT_SHA_SHORT (built-in key) and VERGEN_GIT_SHA_SHORT (package 'short')"
+        ));
+    }
+
+    #[test]
+    fn overrides_file_and_command() {
+        let mut instructions = error_policy();
+        let mut config = Config::default();
+        set(&mut config, VergenKey::Sha);
+        let _ = config.file_keys_mut().insert(VergenKey::Sha);
+        command(&mut instructions, &mut config, "Vergen_Git_Sha");
+        assert!(collision(&instructions, &mut config)
+            .ends_with("VERGEN_GIT_SHA (overrides file) and Vergen_Git_Sha (command)"));
+    }
+
+    #[test]
+    fn overrides_file_and_package() {
+        let mut instructions = error_policy();
+        let mut config = Config::default();
+        set(&mut config, VergenKey::ShortSha);
+        let _ = config.file_keys_mut().insert(VergenKey::ShortSha);
+        package(&mut instructions, &mut config, "short", VergenKey::Sha);
+        assert!(collision(&instructions, &mut config).ends_with(
+            "VERGEN_GIT_SHA_SHORT (overrides file) and VERGEN_GIT_SHA_SHORT (package 'short')"
+        ));
+    }
+
+    #[test]
+    fn command_and_command_key() {
+        let mut instructions = error_policy();
+        let mut config = Config::default();
+        command(&mut instructions, &mut config, "VERGEN_CMD_RELEASE_TRAIN");
+        command_key(&mut instructions, &mut config, "release_train");
+        assert!(collision(&instructions, &mut config).ends_with(
+            "VERGEN_CMD_RELEASE_TRAIN (command) and VERGEN_CMD_release_train (command key)"
+        ));
+    }
+
+    #[test]
+    fn command_and_package() {
+        let mut instructions = error_policy();
+        let mut config = Config::default();
+        command(&mut instructions, &mut config, "VERGEN_GIT_SHA_API");
+        package(&mut instructions, &mut config, "api", VergenKey::Sha);
+        assert!(collision(&instructions, &mut config)
+            .ends_with("VERGEN_GIT_SHA_API (command) and VERGEN_GIT_SHA_API (package 'api')"));
+    }
+
+    #[test]
+    fn provider_and_command() {
+        // The command fails validation before it runs, and the pass would catch it if it didn't
+        let mut instructions = error_policy();
+        instructions.value_provider("VERGEN_GIT_SHA", || Some("75b390d".to_string()));
+        let mut config = Config::default();
+        set(&mut config, VergenKey::Sha);
+        command(&mut instructions, &mut config, "Vergen_Git_Sha");
+        assert!(instructions.validate().is_err());
+        assert!(collision(&instructions, &mut config)
+            .ends_with("VERGEN_GIT_SHA (built-in key) and Vergen_Git_Sha (command)"));
+    }
+
+    #[test]
+    fn same_source() {
+        // Each name is unique within its source, but not ignoring case
+        let mut instructions = error_policy();
+        let mut config = Config::default();
+        command(&mut instructions, &mut config, "BUILD_NUMBER");
+        command(&mut instructions, &mut config, "build_number");
+        assert!(collision(&instructions, &mut config)
+            .ends_with("BUILD_NUMBER (command) and build_number (command)"));
+
+        let mut instructions = error_policy();
+        let mut config = Config::default();
+        command_key(&mut instructions, &mut config, "TRAIN");
+        command_key(&mut instructions, &mut config, "train");
+        assert!(collision(&instructions, &mut config)
+            .ends_with("VERGEN_CMD_TRAIN (command key) and VERGEN_CMD_train (command key)"));
+    }
+
+    #[test]
+    fn overwrite_warns() {
+        let mut instructions = Instructions::default();
+        let mut config = Config::default();
+        command(&mut instructions, &mut config, "VERGEN_GIT_SHA_API");
+        package(&mut instructions, &mut config, "api", VergenKey::Sha);
+        assert!(check_collisions(&instructions, &mut config).is_ok());
+        assert_eq!(
+            config.warnings(),
+            &["vergen: VERGEN_GIT_SHA_API (command) and VERGEN_GIT_SHA_API (package 'api') are the same instruction, ignoring case, the later value wins"]
+        );
+        assert_eq!(
+            config.packages()["api"][&VergenKey::Sha],
+            Some("value".to_string())
+        );
+    }
+
+    #[test]
+    fn keep_first_drops_later() {
+        let mut instructions = Instructions::default();
+        *instructions.on_duplicate_key_mut() = DuplicatePolicy::KeepFirst;
+        let mut config = Config::default();
+        set(&mut config, VergenKey::Sha);
+        command(&mut instructions, &mut config, "vergen_git_sha");
+        command(&mut instructions, &mut config, "VERGEN_GIT_SHA_API");
+        package(&mut instructions, &mut config, "api", VergenKey::Sha);
+        assert!(check_collisions(&instructions, &mut config).is_ok());
+        assert!(config.warnings().is_empty());
+        assert_eq!(config.cfg_map()[&VergenKey::Sha], Some("value".to_string()));
+        assert_eq!(
+            config.commands().keys().collect::<Vec<_>>(),
+            ["VERGEN_GIT_SHA_API"]
+        );
+        assert_eq!(config.packages()["api"][&VergenKey::Sha], None);
+    }
+}
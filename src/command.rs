@@ -0,0 +1,333 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` child processes
+
+#[cfg(command_current_dir)]
+use std::env;
+use std::{
+    io::{self, Read},
+    process::{Command, Output, Stdio},
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How often a running command is checked against the deadline
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long a command added with [`add_command`](crate::Config::add_command) or
+/// [`add_command_key`](crate::Config::add_command_key), or run to detect the libc version, may run
+pub(crate) const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The prefix of the instruction names of the commands added with [`add_command_key`](crate::Config::add_command_key)
+pub(crate) const COMMAND_KEY_PREFIX: &str = "VERGEN_CMD_";
+
+/// A command whose output is emitted as an instruction, see [`add_command`](crate::Config::add_command)
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
+pub(crate) struct CustomCommand {
+    /// The program to run
+    pub(crate) program: String,
+    /// The arguments to pass to the program
+    pub(crate) args: Vec<String>,
+}
+
+impl CustomCommand {
+    /// The first line of the command's stdout, trimmed, or why it failed
+    pub(crate) fn run(&self) -> Result<String, String> {
+        let mut command = Command::new(&self.program);
+        let _ = command.args(&self.args);
+        let line = command_line(&command);
+        default_current_dir(&mut command);
+        run(&mut command, &line, OutputRule::FirstLine)
+    }
+}
+
+/// A command whose output is emitted as a `VERGEN_CMD_` instruction, see
+/// [`add_command_key`](crate::Config::add_command_key)
+///
+/// A [`Command`] can't be cloned, so the clones of the configuration share it, and it's locked while it runs.
+#[derive(Clone, Debug)]
+pub(crate) struct CommandKey {
+    /// The command, with its working directory defaulted
+    command: Arc<Mutex<Command>>,
+    /// The command line as it was added, to name the command in errors
+    line: String,
+}
+
+impl CommandKey {
+    pub(crate) fn new(mut command: Command) -> Self {
+        let line = command_line(&command);
+        default_current_dir(&mut command);
+        Self {
+            command: Arc::new(Mutex::new(command)),
+            line,
+        }
+    }
+
+    /// The command's stdout, its lines trimmed and joined with a space, or why it failed
+    pub(crate) fn run(&self) -> Result<String, String> {
+        let mut command = self.command.lock().unwrap_or_else(PoisonError::into_inner);
+        run(&mut command, &self.line, OutputRule::Lines)
+    }
+}
+
+/// How a command's stdout becomes the instruction value
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum OutputRule {
+    /// The first line, trimmed
+    FirstLine,
+    /// The non-blank lines, trimmed and joined with a space, as an instruction value can't hold a line break.  A
+    /// command that prints nothing fails.
+    Lines,
+}
+
+/// The command line, i.e. `./scripts/print-release-train.sh --short` from the
+/// `"./scripts/print-release-train.sh" "--short"` debug format
+fn command_line(command: &Command) -> String {
+    format!("{:?}", command).replace('"', "")
+}
+
+/// Run the command, killing it after the [`COMMAND_TIMEOUT`], and return its stdout by the output rule, or why it
+/// failed, naming the command line
+fn run(command: &mut Command, line: &str, rule: OutputRule) -> Result<String, String> {
+    let output =
+        output(command, Some(Instant::now() + COMMAND_TIMEOUT)).map_err(|e| match e.kind() {
+            io::ErrorKind::TimedOut => format!(
+                "'{}' was killed after {} seconds",
+                line,
+                COMMAND_TIMEOUT.as_secs()
+            ),
+            _ => format!("unable to run '{}': {}", line, e),
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut reason = format!("'{}' failed with {}", line, output.status);
+        if !stderr.trim().is_empty() {
+            reason = format!("{}: {}", reason, stderr.trim());
+        }
+        return Err(reason);
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match rule {
+        OutputRule::FirstLine => Ok(stdout.lines().next().unwrap_or_default().trim().to_string()),
+        OutputRule::Lines => {
+            let value = collapse_lines(&stdout);
+            if value.is_empty() {
+                Err(format!("'{}' printed nothing", line))
+            } else {
+                Ok(value)
+            }
+        }
+    }
+}
+
+/// Run the command from the runtime `CARGO_MANIFEST_DIR`, the package root, unless it was given its own working
+/// directory
+#[cfg(command_current_dir)]
+fn default_current_dir(command: &mut Command) {
+    if command.get_current_dir().is_none() {
+        if let Some(manifest_dir) = env::var_os("CARGO_MANIFEST_DIR") {
+            let _ = command.current_dir(manifest_dir);
+        }
+    }
+}
+
+/// The working directory can't be read before Rust 1.57, so the command runs from the build script's, which cargo sets
+/// to `CARGO_MANIFEST_DIR`, unless it was given its own
+#[cfg(not(command_current_dir))]
+fn default_current_dir(_command: &mut Command) {}
+
+/// The non-blank lines, trimmed and joined with a space
+fn collapse_lines(stdout: &str) -> String {
+    stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// An error once the deadline has passed, so a feature stops between its steps rather than running on past it
+#[cfg(feature = "git")]
+pub(crate) fn check_deadline(deadline: Option<Instant>) -> io::Result<()> {
+    match deadline {
+        Some(deadline) if Instant::now() >= deadline => Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "stopped at the 'max_duration' deadline",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Run the command to completion, like [`Command::output`], but kill it if it's still running at the deadline
+pub(crate) fn output(command: &mut Command, deadline: Option<Instant>) -> io::Result<Output> {
+    match deadline {
+        Some(deadline) => output_until(command, deadline),
+        None => command.output(),
+    }
+}
+
+/// Kill the command at the deadline, and wait on it so it doesn't outlive the build script as a zombie
+fn output_until(command: &mut Command, deadline: Instant) -> io::Result<Output> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Drain the pipes while waiting, so a chatty command can't block on a full pipe
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Output {
+                status,
+                stdout: stdout.join().unwrap_or_default(),
+                stderr: stderr.join().unwrap_or_default(),
+            });
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            // A grandchild may still hold the pipes open, so the readers are left to finish on their own
+            let _ = child.kill();
+            let _ = child.wait()?;
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "killed at the 'max_duration' deadline",
+            ));
+        }
+        thread::sleep(POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+fn drain<R>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>>
+where
+    R: Read + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut buf = vec![];
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::{collapse_lines, output, CommandKey, CustomCommand};
+    use std::{
+        io::ErrorKind,
+        path::Path,
+        process::Command,
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn completes_before_deadline() {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let output = output(Command::new("echo").arg("hi"), Some(deadline)).unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"hi\n");
+    }
+
+    #[test]
+    fn killed_at_deadline() {
+        let start = Instant::now();
+        let mut command = Command::new("sleep");
+        let _ = command.arg("10");
+        let err = output(&mut command, Some(start + Duration::from_millis(100))).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    fn custom(program: &str, args: &[&str]) -> Result<String, String> {
+        CustomCommand {
+            program: program.to_string(),
+            args: args.iter().map(|arg| (*arg).to_string()).collect(),
+        }
+        .run()
+    }
+
+    #[test]
+    fn custom_command_first_line() {
+        assert_eq!(
+            custom("printf", &["  build-42 \\nsecond line\\n"]),
+            Ok("build-42".to_string())
+        );
+        assert_eq!(custom("true", &[]), Ok(String::new()));
+    }
+
+    #[test]
+    fn custom_command_failures() {
+        assert!(custom("false", &[])
+            .unwrap_err()
+            .starts_with("'false' failed"));
+        assert!(custom("vergen-no-such-program", &[])
+            .unwrap_err()
+            .starts_with("unable to run 'vergen-no-such-program'"));
+    }
+
+    fn command_key(program: &str, args: &[&str]) -> Result<String, String> {
+        let mut command = Command::new(program);
+        let _ = command.args(args);
+        CommandKey::new(command).run()
+    }
+
+    #[test]
+    fn command_key_output() {
+        assert_eq!(
+            command_key("echo", &["release-2021.03 "]),
+            Ok("release-2021.03".to_string())
+        );
+        assert_eq!(
+            command_key("printf", &["train\\n  2021.03 \\n\\n"]),
+            Ok("train 2021.03".to_string())
+        );
+        assert_eq!(collapse_lines("a\r\n\r\n b \n"), "a b");
+    }
+
+    #[test]
+    fn command_key_failures() {
+        assert_eq!(
+            command_key("sh", &["-c", "echo broken >&2; exit 3"]),
+            Err("'sh -c echo broken >&2; exit 3' failed with exit status: 3: broken".to_string())
+        );
+        assert_eq!(
+            command_key("true", &[]),
+            Err("'true' printed nothing".to_string())
+        );
+        assert!(command_key("vergen-no-such-program", &[])
+            .unwrap_err()
+            .starts_with("unable to run 'vergen-no-such-program'"));
+    }
+
+    #[cfg(command_current_dir)]
+    #[test]
+    fn default_current_dir() {
+        let mut command = Command::new("pwd");
+        super::default_current_dir(&mut command);
+        assert_eq!(
+            command.get_current_dir(),
+            Some(Path::new(env!("CARGO_MANIFEST_DIR")))
+        );
+
+        let mut command = Command::new("pwd");
+        let _ = command.current_dir("/");
+        super::default_current_dir(&mut command);
+        assert_eq!(command.get_current_dir(), Some(Path::new("/")));
+        assert_eq!(CommandKey::new(command).run(), Ok("/".to_string()));
+    }
+}
@@ -0,0 +1,575 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` 3.x `ConstantsFlags` compatibility
+#![allow(deprecated)]
+
+use crate::{
+    config::{Instructions, VergenKey},
+    feature::{SemverKind, ShaKind, TimestampKind},
+    gen::vergen_to,
+    repeat::{Emitted, EMITTED},
+};
+use anyhow::Result;
+use std::{
+    io::{self, Write},
+    ops::{BitAnd, BitOr, BitOrAssign, Not},
+};
+
+/// The `vergen` 3.x flags, as a set
+///
+/// Each flag turns on one instruction of the 3.x `generate_cargo_keys`, see [`generate_cargo_keys`] for the
+/// instruction names.  Flags combine with `|`, i.e. `ConstantsFlags::SHA | ConstantsFlags::BUILD_TIMESTAMP`.
+#[deprecated(
+    since = "6.0.0",
+    note = "use vergen::Config and vergen::vergen, i.e. `*config.git_mut().sha_mut() = true`"
+)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ConstantsFlags {
+    bits: u64,
+}
+
+impl ConstantsFlags {
+    /// `VERGEN_BUILD_TIMESTAMP`
+    pub const BUILD_TIMESTAMP: Self = Self::from_bits(0x0000_0001);
+    /// `VERGEN_BUILD_DATE`
+    pub const BUILD_DATE: Self = Self::from_bits(0x0000_0002);
+    /// `VERGEN_SHA`
+    pub const SHA: Self = Self::from_bits(0x0000_0004);
+    /// `VERGEN_SHA_SHORT`
+    pub const SHA_SHORT: Self = Self::from_bits(0x0000_0008);
+    /// `VERGEN_COMMIT_DATE`
+    pub const COMMIT_DATE: Self = Self::from_bits(0x0000_0010);
+    /// `VERGEN_TARGET_TRIPLE`
+    pub const TARGET_TRIPLE: Self = Self::from_bits(0x0000_0020);
+    /// `VERGEN_SEMVER`
+    pub const SEMVER: Self = Self::from_bits(0x0000_0040);
+    /// `VERGEN_SEMVER_LIGHTWEIGHT`
+    pub const SEMVER_LIGHTWEIGHT: Self = Self::from_bits(0x0000_0080);
+    /// The `cargo:rerun-if-changed` instructions for `HEAD`
+    pub const REBUILD_ON_HEAD_CHANGE: Self = Self::from_bits(0x0000_0100);
+    /// `VERGEN_SEMVER` and `VERGEN_SEMVER_LIGHTWEIGHT` from `CARGO_PKG_VERSION`, rather than `git describe`
+    pub const SEMVER_FROM_CARGO_PKG: Self = Self::from_bits(0x0000_0200);
+    /// `VERGEN_BRANCH`
+    pub const BRANCH: Self = Self::from_bits(0x0000_0400);
+    /// `VERGEN_RUSTC_SEMVER`
+    pub const RUSTC_SEMVER: Self = Self::from_bits(0x0000_0800);
+    /// `VERGEN_RUSTC_CHANNEL`
+    pub const RUSTC_CHANNEL: Self = Self::from_bits(0x0000_1000);
+    /// `VERGEN_HOST_TRIPLE`
+    pub const HOST_TRIPLE: Self = Self::from_bits(0x0000_2000);
+    /// `VERGEN_RUSTC_COMMIT_HASH`
+    pub const RUSTC_COMMIT_HASH: Self = Self::from_bits(0x0000_4000);
+    /// `VERGEN_RUSTC_COMMIT_DATE`
+    pub const RUSTC_COMMIT_DATE: Self = Self::from_bits(0x0000_8000);
+    /// `VERGEN_RUSTC_LLVM_VERSION`
+    pub const RUSTC_LLVM_VERSION: Self = Self::from_bits(0x0001_0000);
+
+    const fn from_bits(bits: u64) -> Self {
+        Self { bits }
+    }
+
+    /// No flags
+    #[must_use]
+    pub const fn empty() -> Self {
+        Self::from_bits(0)
+    }
+
+    /// Every flag
+    #[must_use]
+    pub const fn all() -> Self {
+        Self::from_bits(0x0001_ffff)
+    }
+
+    /// The flags as bits
+    #[must_use]
+    pub const fn bits(self) -> u64 {
+        self.bits
+    }
+
+    /// Are no flags set
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.bits == 0
+    }
+
+    /// Are all the given flags set
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.bits & other.bits == other.bits
+    }
+
+    /// Set the given flags
+    pub fn insert(&mut self, other: Self) {
+        self.bits |= other.bits;
+    }
+
+    /// Clear the given flags
+    pub fn remove(&mut self, other: Self) {
+        self.bits &= !other.bits;
+    }
+
+    /// Flip the given flags
+    pub fn toggle(&mut self, other: Self) {
+        self.bits ^= other.bits;
+    }
+}
+
+impl BitOr for ConstantsFlags {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self::from_bits(self.bits | other.bits)
+    }
+}
+
+impl BitOrAssign for ConstantsFlags {
+    fn bitor_assign(&mut self, other: Self) {
+        self.insert(other);
+    }
+}
+
+impl BitAnd for ConstantsFlags {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        Self::from_bits(self.bits & other.bits)
+    }
+}
+
+impl Not for ConstantsFlags {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self::from_bits(!self.bits & Self::all().bits)
+    }
+}
+
+/// The flags that name an instruction, with the instruction `vergen` generates today and the 3.x name it's emitted as
+const RENAMES: [(ConstantsFlags, VergenKey, &str); 15] = [
+    (
+        ConstantsFlags::BUILD_TIMESTAMP,
+        VergenKey::BuildTimestamp,
+        "VERGEN_BUILD_TIMESTAMP",
+    ),
+    (
+        ConstantsFlags::BUILD_DATE,
+        VergenKey::BuildDate,
+        "VERGEN_BUILD_DATE",
+    ),
+    (ConstantsFlags::SHA, VergenKey::Sha, "VERGEN_SHA"),
+    (
+        ConstantsFlags::SHA_SHORT,
+        VergenKey::ShortSha,
+        "VERGEN_SHA_SHORT",
+    ),
+    (
+        ConstantsFlags::COMMIT_DATE,
+        VergenKey::CommitDate,
+        "VERGEN_COMMIT_DATE",
+    ),
+    (
+        ConstantsFlags::TARGET_TRIPLE,
+        VergenKey::CargoTargetTriple,
+        "VERGEN_TARGET_TRIPLE",
+    ),
+    (ConstantsFlags::SEMVER, VergenKey::Semver, "VERGEN_SEMVER"),
+    (
+        ConstantsFlags::SEMVER_LIGHTWEIGHT,
+        VergenKey::SemverLightweight,
+        "VERGEN_SEMVER_LIGHTWEIGHT",
+    ),
+    (ConstantsFlags::BRANCH, VergenKey::Branch, "VERGEN_BRANCH"),
+    (
+        ConstantsFlags::RUSTC_SEMVER,
+        VergenKey::RustcSemver,
+        "VERGEN_RUSTC_SEMVER",
+    ),
+    (
+        ConstantsFlags::RUSTC_CHANNEL,
+        VergenKey::RustcChannel,
+        "VERGEN_RUSTC_CHANNEL",
+    ),
+    (
+        ConstantsFlags::HOST_TRIPLE,
+        VergenKey::RustcHostTriple,
+        "VERGEN_HOST_TRIPLE",
+    ),
+    (
+        ConstantsFlags::RUSTC_COMMIT_HASH,
+        VergenKey::RustcCommitHash,
+        "VERGEN_RUSTC_COMMIT_HASH",
+    ),
+    (
+        ConstantsFlags::RUSTC_COMMIT_DATE,
+        VergenKey::RustcCommitDate,
+        "VERGEN_RUSTC_COMMIT_DATE",
+    ),
+    (
+        ConstantsFlags::RUSTC_LLVM_VERSION,
+        VergenKey::RustcLlvmVersion,
+        "VERGEN_RUSTC_LLVM_VERSION",
+    ),
+];
+
+/// The instructions to generate for the flags, as `(current key, 3.x name)` pairs
+fn renamed_keys(flags: ConstantsFlags) -> Vec<(VergenKey, &'static str)> {
+    RENAMES
+        .iter()
+        .filter(|(flag, _, _)| flags.contains(*flag))
+        .map(|(flag, key, old_name)| {
+            let from_cargo_pkg = flags.contains(ConstantsFlags::SEMVER_FROM_CARGO_PKG)
+                && (*flag == ConstantsFlags::SEMVER || *flag == ConstantsFlags::SEMVER_LIGHTWEIGHT);
+            if from_cargo_pkg {
+                (VergenKey::BuildSemver, *old_name)
+            } else {
+                (*key, *old_name)
+            }
+        })
+        .collect()
+}
+
+/// The configurations that generate the flags' instructions
+///
+/// `git describe` has one kind per run, so `VERGEN_SEMVER_LIGHTWEIGHT` is generated by a second configuration when
+/// both kinds are requested.
+fn instructions(flags: ConstantsFlags) -> Vec<Instructions> {
+    let keys = renamed_keys(flags);
+    let wants = |key| keys.iter().any(|(wanted, _)| *wanted == key);
+    let wants_subsystem = |subsystem| keys.iter().any(|(key, _)| key.subsystem() == subsystem);
+    let normal = wants(VergenKey::Semver);
+    let lightweight = wants(VergenKey::SemverLightweight);
+    let rerun = flags.contains(ConstantsFlags::REBUILD_ON_HEAD_CHANGE);
+
+    let mut first = Instructions::none();
+    *first.build_mut().enabled_mut() = wants_subsystem("build");
+    *first.build_mut().kind_mut() = TimestampKind::All;
+    *first.cargo_mut().enabled_mut() = wants_subsystem("cargo");
+    *first.rustc_mut().enabled_mut() = wants_subsystem("rustc");
+    let git = first.git_mut();
+    *git.enabled_mut() = wants_subsystem("git") || rerun;
+    *git.branch_mut() = wants(VergenKey::Branch);
+    *git.commit_timestamp_mut() = wants(VergenKey::CommitDate);
+    if *git.commit_timestamp_mut() {
+        *git.commit_timestamp_kind_mut() = TimestampKind::DateOnly;
+    }
+    *git.semver_mut() = normal || lightweight;
+    if lightweight && !normal {
+        *git.semver_kind_mut() = SemverKind::Lightweight;
+    }
+    *git.sha_mut() = wants(VergenKey::Sha) || wants(VergenKey::ShortSha);
+    if *git.sha_mut() {
+        *git.sha_kind_mut() = ShaKind::Both;
+    }
+    *git.rerun_on_head_change_mut() = rerun;
+    // Both semver kinds can't come from one run, so the lightweight one is left to the second
+    let names: Vec<&str> = keys
+        .iter()
+        .filter(|(key, _)| !(normal && *key == VergenKey::SemverLightweight))
+        .map(|(key, _)| key.name())
+        .collect();
+    first.only(&names);
+
+    if normal && lightweight {
+        let mut second = Instructions::none();
+        let git = second.git_mut();
+        *git.enabled_mut() = true;
+        *git.semver_kind_mut() = SemverKind::Lightweight;
+        *git.rerun_on_head_change_mut() = false;
+        second.only(&[VergenKey::SemverLightweight.name()]);
+        vec![first, second]
+    } else {
+        vec![first]
+    }
+}
+
+/// Rename a `rustc-env` instruction to its 3.x names, and pass any other line through
+///
+/// With `SEMVER_FROM_CARGO_PKG`, one instruction is emitted under both of the semver names.
+fn rename(line: &str, keys: &[(VergenKey, &str)]) -> Vec<String> {
+    const DIRECTIVE: &str = "rustc-env=";
+    if let Some(start) = line.find(DIRECTIVE) {
+        let (directive, rest) = line.split_at(start + DIRECTIVE.len());
+        if let Some((name, value)) = rest.split_once('=') {
+            let renamed: Vec<String> = keys
+                .iter()
+                .filter(|(key, _)| key.name() == name)
+                .map(|(_, old_name)| format!("{}{}={}", directive, old_name, value))
+                .collect();
+            if !renamed.is_empty() {
+                return renamed;
+            }
+        }
+    }
+    vec![line.to_string()]
+}
+
+fn generate<T>(flags: ConstantsFlags, emitted: Option<&Emitted>, stdout: &mut T) -> Result<()>
+where
+    T: Write,
+{
+    let keys = renamed_keys(flags);
+    for instructions in instructions(flags) {
+        let mut buf = vec![];
+        vergen_to(&instructions, emitted, &mut buf)?;
+        for line in String::from_utf8_lossy(&buf).lines() {
+            for line in rename(line, &keys) {
+                writeln!(stdout, "{}", line)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Generate the `cargo:` instructions of the `vergen` 3.x flags, under their 3.x names
+///
+/// This eases an upgrade from `vergen` 3.x, where every build script can't move to [`Config`](crate::Config) at once.
+/// It needs the `build`, `cargo`, `git`, and `rustc` features, which are on by default.
+///
+/// The values are the ones `vergen` generates today, under the 3.x names.  The names that changed are:
+///
+/// | 3.x Name | Current Name |
+/// | -------- | ------------ |
+/// | `VERGEN_SHA` | `VERGEN_GIT_SHA` |
+/// | `VERGEN_SHA_SHORT` | `VERGEN_GIT_SHA_SHORT` |
+/// | `VERGEN_COMMIT_DATE` | `VERGEN_GIT_COMMIT_DATE` |
+/// | `VERGEN_TARGET_TRIPLE` | `VERGEN_CARGO_TARGET_TRIPLE` |
+/// | `VERGEN_SEMVER` | `VERGEN_GIT_SEMVER`, or `VERGEN_BUILD_SEMVER` with `SEMVER_FROM_CARGO_PKG` |
+/// | `VERGEN_SEMVER_LIGHTWEIGHT` | `VERGEN_GIT_SEMVER_LIGHTWEIGHT`, or `VERGEN_BUILD_SEMVER` with `SEMVER_FROM_CARGO_PKG` |
+/// | `VERGEN_BRANCH` | `VERGEN_GIT_BRANCH` |
+/// | `VERGEN_HOST_TRIPLE` | `VERGEN_RUSTC_HOST_TRIPLE` |
+///
+/// * The other flags' names are unchanged.
+/// * **NOTE** - The `VERGEN_BUILD_TIMESTAMP` value has microseconds, i.e. `2021-02-12T01:54:15.134750Z`, see
+///   [`Precision`](crate::Precision).
+/// * **NOTE** - The `VERGEN_DISABLE_*` [overrides](crate::Config::apply_env_overrides) use the current names.
+///
+/// # Errors
+///
+/// The same errors as [`vergen`](crate::vergen).
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// #[allow(deprecated)]
+/// use vergen::{generate_cargo_keys, ConstantsFlags};
+///
+/// # pub fn main() -> Result<()> {
+/// #[allow(deprecated)]
+/// generate_cargo_keys(ConstantsFlags::SHA | ConstantsFlags::BUILD_TIMESTAMP)?;
+/// # Ok(())
+/// # }
+/// ```
+#[deprecated(
+    since = "6.0.0",
+    note = "use vergen::vergen with a vergen::Config, and read the VERGEN_GIT_*, VERGEN_CARGO_*, and VERGEN_RUSTC_* names"
+)]
+pub fn generate_cargo_keys(flags: ConstantsFlags) -> Result<()> {
+    EMITTED.with(|emitted| generate(flags, Some(emitted), &mut io::stdout()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{generate, instructions, rename, renamed_keys, ConstantsFlags};
+    use crate::config::VergenKey;
+
+    fn output(flags: ConstantsFlags) -> String {
+        let mut stdout_buf = vec![];
+        generate(flags, None, &mut stdout_buf).unwrap();
+        String::from_utf8(stdout_buf).unwrap()
+    }
+
+    fn names(output: &str) -> Vec<&str> {
+        output
+            .lines()
+            .filter_map(|line| line.strip_prefix("cargo:rustc-env="))
+            .filter_map(|line| line.split('=').next())
+            .collect()
+    }
+
+    #[test]
+    fn flag_set() {
+        let mut flags = ConstantsFlags::SHA | ConstantsFlags::BUILD_TIMESTAMP;
+        assert!(flags.contains(ConstantsFlags::SHA));
+        assert!(!flags.contains(ConstantsFlags::BRANCH));
+        flags |= ConstantsFlags::BRANCH;
+        flags.remove(ConstantsFlags::SHA);
+        assert_eq!(
+            flags,
+            ConstantsFlags::BUILD_TIMESTAMP | ConstantsFlags::BRANCH
+        );
+        flags.toggle(ConstantsFlags::BRANCH);
+        assert_eq!(flags & ConstantsFlags::BRANCH, ConstantsFlags::empty());
+        assert!((!ConstantsFlags::all()).is_empty());
+        assert_eq!(ConstantsFlags::all().bits(), 0x0001_ffff);
+    }
+
+    #[test]
+    fn each_flag_maps_to_a_key() {
+        let expected = [
+            (
+                ConstantsFlags::BUILD_TIMESTAMP,
+                VergenKey::BuildTimestamp,
+                "VERGEN_BUILD_TIMESTAMP",
+            ),
+            (
+                ConstantsFlags::BUILD_DATE,
+                VergenKey::BuildDate,
+                "VERGEN_BUILD_DATE",
+            ),
+            (ConstantsFlags::SHA, VergenKey::Sha, "VERGEN_SHA"),
+            (
+                ConstantsFlags::SHA_SHORT,
+                VergenKey::ShortSha,
+                "VERGEN_SHA_SHORT",
+            ),
+            (
+                ConstantsFlags::COMMIT_DATE,
+                VergenKey::CommitDate,
+                "VERGEN_COMMIT_DATE",
+            ),
+            (
+                ConstantsFlags::TARGET_TRIPLE,
+                VergenKey::CargoTargetTriple,
+                "VERGEN_TARGET_TRIPLE",
+            ),
+            (ConstantsFlags::SEMVER, VergenKey::Semver, "VERGEN_SEMVER"),
+            (
+                ConstantsFlags::SEMVER_LIGHTWEIGHT,
+                VergenKey::SemverLightweight,
+                "VERGEN_SEMVER_LIGHTWEIGHT",
+            ),
+            (ConstantsFlags::BRANCH, VergenKey::Branch, "VERGEN_BRANCH"),
+            (
+                ConstantsFlags::RUSTC_SEMVER,
+                VergenKey::RustcSemver,
+                "VERGEN_RUSTC_SEMVER",
+            ),
+            (
+                ConstantsFlags::RUSTC_CHANNEL,
+                VergenKey::RustcChannel,
+                "VERGEN_RUSTC_CHANNEL",
+            ),
+            (
+                ConstantsFlags::HOST_TRIPLE,
+                VergenKey::RustcHostTriple,
+                "VERGEN_HOST_TRIPLE",
+            ),
+            (
+                ConstantsFlags::RUSTC_COMMIT_HASH,
+                VergenKey::RustcCommitHash,
+                "VERGEN_RUSTC_COMMIT_HASH",
+            ),
+            (
+                ConstantsFlags::RUSTC_COMMIT_DATE,
+                VergenKey::RustcCommitDate,
+                "VERGEN_RUSTC_COMMIT_DATE",
+            ),
+            (
+                ConstantsFlags::RUSTC_LLVM_VERSION,
+                VergenKey::RustcLlvmVersion,
+                "VERGEN_RUSTC_LLVM_VERSION",
+            ),
+        ];
+        for (flag, key, old_name) in &expected {
+            assert_eq!(renamed_keys(*flag), vec![(*key, *old_name)]);
+            let instructions = instructions(*flag);
+            assert_eq!(instructions.len(), 1);
+            assert!(instructions[0].listed(*key));
+            assert!(!instructions[0].listed(VergenKey::BuildSemver));
+        }
+        assert!(renamed_keys(ConstantsFlags::REBUILD_ON_HEAD_CHANGE).is_empty());
+        assert!(renamed_keys(ConstantsFlags::SEMVER_FROM_CARGO_PKG).is_empty());
+    }
+
+    #[test]
+    fn semver_from_cargo_pkg() {
+        assert_eq!(
+            renamed_keys(ConstantsFlags::SEMVER | ConstantsFlags::SEMVER_FROM_CARGO_PKG),
+            vec![(VergenKey::BuildSemver, "VERGEN_SEMVER")]
+        );
+        let output = output(
+            ConstantsFlags::SEMVER
+                | ConstantsFlags::SEMVER_LIGHTWEIGHT
+                | ConstantsFlags::SEMVER_FROM_CARGO_PKG,
+        );
+        let version = env!("CARGO_PKG_VERSION");
+        assert!(output.contains(&format!("cargo:rustc-env=VERGEN_SEMVER={}\n", version)));
+        assert!(output.contains(&format!(
+            "cargo:rustc-env=VERGEN_SEMVER_LIGHTWEIGHT={}\n",
+            version
+        )));
+    }
+
+    #[test]
+    fn both_semver_kinds() {
+        let instructions =
+            instructions(ConstantsFlags::SEMVER | ConstantsFlags::SEMVER_LIGHTWEIGHT);
+        assert_eq!(instructions.len(), 2);
+        assert!(instructions[0].listed(VergenKey::Semver));
+        assert!(!instructions[0].listed(VergenKey::SemverLightweight));
+        assert!(instructions[1].listed(VergenKey::SemverLightweight));
+        assert!(!instructions[1].listed(VergenKey::Semver));
+    }
+
+    #[test]
+    fn renamed_lines() {
+        let keys = renamed_keys(ConstantsFlags::SHA | ConstantsFlags::SHA_SHORT);
+        assert_eq!(
+            rename("cargo:rustc-env=VERGEN_GIT_SHA=75b390d", &keys),
+            vec!["cargo:rustc-env=VERGEN_SHA=75b390d"]
+        );
+        assert_eq!(
+            rename("cargo::rustc-env=VERGEN_GIT_SHA_SHORT=75b390d", &keys),
+            vec!["cargo::rustc-env=VERGEN_SHA_SHORT=75b390d"]
+        );
+        assert_eq!(
+            rename("cargo:rerun-if-changed=.git/HEAD", &keys),
+            vec!["cargo:rerun-if-changed=.git/HEAD"]
+        );
+    }
+
+    #[test]
+    fn old_names_emitted() {
+        let output = output(
+            ConstantsFlags::SHA
+                | ConstantsFlags::BUILD_DATE
+                | ConstantsFlags::RUSTC_SEMVER
+                | ConstantsFlags::HOST_TRIPLE
+                | ConstantsFlags::SEMVER
+                | ConstantsFlags::SEMVER_LIGHTWEIGHT,
+        );
+        let mut names = names(&output);
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            vec![
+                "VERGEN_BUILD_DATE",
+                "VERGEN_HOST_TRIPLE",
+                "VERGEN_RUSTC_SEMVER",
+                "VERGEN_SEMVER",
+                "VERGEN_SEMVER_LIGHTWEIGHT",
+                "VERGEN_SHA",
+            ]
+        );
+    }
+
+    #[test]
+    fn rebuild_on_head_change() {
+        let reruns_on_head = |output: String| {
+            output
+                .lines()
+                .any(|line| line.starts_with("cargo:rerun-if-changed=") && line.ends_with("HEAD"))
+        };
+        assert!(!reruns_on_head(output(ConstantsFlags::SHA)));
+        assert!(reruns_on_head(output(
+            ConstantsFlags::SHA | ConstantsFlags::REBUILD_ON_HEAD_CHANGE
+        )));
+    }
+}
@@ -0,0 +1,363 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` conditional emission
+
+use crate::{error::ConfigError, overrides::parse_bool, sticky::find_key};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+/// The subsystems a condition can be set for, along with the instruction names
+const SUBSYSTEMS: [&str; 7] = ["build", "cargo", "git", "hg", "rustc", "sysinfo", "vergen"];
+
+/// The build a [`Condition`] is checked against
+///
+/// The values are read from the environment `cargo` sets for the build script.
+///
+/// | Value | Variable |
+/// | ----- | -------- |
+/// | `profile` | `PROFILE`, i.e. `release` |
+/// | `target` | `TARGET`, i.e. `aarch64-unknown-linux-gnu` |
+/// | `host` | `HOST`, i.e. `x86_64-unknown-linux-gnu` |
+/// | `is_ci` | `CI`, true if it's set to anything but an empty value, `0`, `false`, `no`, or `off` |
+/// | `features` | `CARGO_FEATURE_<NAME>`, i.e. `TELEMETRY` for `CARGO_FEATURE_TELEMETRY` |
+///
+/// * A variable that isn't set is None.
+/// * cargo sets a `CARGO_FEATURE_<NAME>` variable for each enabled feature of the package being built, with the
+///   feature name uppercased and each `-` replaced by `_`, so the `fast-hash` feature sets `CARGO_FEATURE_FAST_HASH`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EmissionContext {
+    /// The profile being built, i.e. `release`.
+    profile: Option<String>,
+    /// The target triple being built for.
+    target: Option<String>,
+    /// The triple of the host running the build.
+    host: Option<String>,
+    /// Is the build running in CI.
+    is_ci: bool,
+    /// The enabled features of the package being built, as named by their `CARGO_FEATURE_<NAME>` variables.
+    features: BTreeSet<String>,
+}
+
+impl EmissionContext {
+    /// The profile being built, i.e. `release`.
+    #[must_use]
+    pub fn profile(&self) -> &Option<String> {
+        &self.profile
+    }
+
+    /// The target triple being built for.
+    #[must_use]
+    pub fn target(&self) -> &Option<String> {
+        &self.target
+    }
+
+    /// The triple of the host running the build.
+    #[must_use]
+    pub fn host(&self) -> &Option<String> {
+        &self.host
+    }
+
+    /// Is the build running in CI.
+    #[must_use]
+    pub fn is_ci(&self) -> &bool {
+        &self.is_ci
+    }
+
+    /// The enabled features of the package being built, as named by their `CARGO_FEATURE_<NAME>` variables.
+    #[must_use]
+    pub fn features(&self) -> &BTreeSet<String> {
+        &self.features
+    }
+}
+
+impl EmissionContext {
+    pub(crate) fn from_vars(vars: &[(String, String)]) -> Self {
+        let var = |name: &str| {
+            vars.iter()
+                .find(|(var, _)| var == name)
+                .map(|(_, value)| value.clone())
+        };
+        Self {
+            profile: var("PROFILE"),
+            target: var("TARGET"),
+            host: var("HOST"),
+            is_ci: var("CI").map_or(false, |value| {
+                !value.trim().is_empty() && parse_bool(&value) != Some(false)
+            }),
+            features: vars
+                .iter()
+                .filter_map(|(var, _)| var.strip_prefix("CARGO_FEATURE_"))
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    /// Is the given feature of the package being built, i.e. `telemetry` or `fast-hash`, enabled
+    #[must_use]
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.contains(&feature_var_name(feature))
+    }
+
+    /// Is the target different from the host
+    #[must_use]
+    pub fn is_cross_compile(&self) -> bool {
+        self.target != self.host
+    }
+}
+
+/// The builds a feature or an instruction is emitted in, see [`emit_when`](crate::Config::emit_when)
+///
+/// The condition holds if the profile is one of the `profiles`, the target is one of the `targets`, and the `when`
+/// function returns true for the [`EmissionContext`] of the build.  An empty list, or no function, allows any build.
+///
+/// * **NOTE** - The `when` function isn't serialized with the `serde` feature, only the lists are.
+///
+/// # Example
+///
+/// ```
+/// use vergen::{Condition, Config};
+///
+/// let mut config = Config::default();
+/// config.emit_when("git", Condition::only_profiles(&["release"]));
+/// config.emit_when("sysinfo", Condition::when(|context| !context.is_cross_compile()));
+///
+/// let mut condition = Condition::only_targets(&["x86_64-unknown-linux-gnu"]);
+/// condition.profiles_mut().push("release".to_string());
+/// config.emit_when("VERGEN_CARGO_FEATURES", condition);
+/// ```
+#[derive(Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
+pub struct Condition {
+    /// The profiles to emit in, or empty for any profile.
+    profiles: Vec<String>,
+    /// The target triples to emit for, or empty for any target.
+    targets: Vec<String>,
+    /// The function deciding whether to emit, or None to only check the lists.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    when: Option<fn(&EmissionContext) -> bool>,
+}
+
+impl Condition {
+    /// The profiles to emit in, or empty for any profile.
+    pub fn profiles_mut(&mut self) -> &mut Vec<String> {
+        &mut self.profiles
+    }
+
+    /// The target triples to emit for, or empty for any target.
+    pub fn targets_mut(&mut self) -> &mut Vec<String> {
+        &mut self.targets
+    }
+}
+
+impl Condition {
+    /// Emit only in the given profiles, i.e. `release`
+    #[must_use]
+    pub fn only_profiles(profiles: &[&str]) -> Self {
+        Self {
+            profiles: profiles.iter().map(|name| (*name).to_string()).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Emit only for the given target triples, i.e. `x86_64-unknown-linux-gnu`
+    #[must_use]
+    pub fn only_targets(targets: &[&str]) -> Self {
+        Self {
+            targets: targets.iter().map(|name| (*name).to_string()).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Emit only when the function returns true for the build
+    #[must_use]
+    pub fn when(when: fn(&EmissionContext) -> bool) -> Self {
+        Self {
+            when: Some(when),
+            ..Self::default()
+        }
+    }
+
+    /// A short description for the configuration summary, i.e. `profiles [release], a function`
+    pub(crate) fn describe(&self) -> String {
+        let mut parts = vec![];
+        if !self.profiles.is_empty() {
+            parts.push(format!("profiles [{}]", self.profiles.join(", ")));
+        }
+        if !self.targets.is_empty() {
+            parts.push(format!("targets [{}]", self.targets.join(", ")));
+        }
+        if self.when.is_some() {
+            parts.push("a function".to_string());
+        }
+        if parts.is_empty() {
+            "always".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+
+    /// Does the condition hold for the build
+    pub(crate) fn holds(&self, context: &EmissionContext) -> bool {
+        let allows = |names: &[String], value: &Option<String>| {
+            names.is_empty() || value.as_ref().map_or(false, |value| names.contains(value))
+        };
+        allows(&self.profiles, &context.profile)
+            && allows(&self.targets, &context.target)
+            && self.when.map_or(true, |when| when(context))
+    }
+}
+
+// Older compilers don't implement `Debug` for a function pointer taking a reference
+impl fmt::Debug for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Condition")
+            .field("profiles", &self.profiles)
+            .field("targets", &self.targets)
+            .field("when", &self.when.map(|when| when as usize))
+            .finish()
+    }
+}
+
+/// The name cargo gives the `CARGO_FEATURE_<NAME>` variable of a feature, i.e. `FAST_HASH` for `fast-hash`
+pub(crate) fn feature_var_name(feature: &str) -> String {
+    feature.to_uppercase().replace('-', "_")
+}
+
+pub(crate) fn validate(conditions: &BTreeMap<String, Condition>, errors: &mut Vec<ConfigError>) {
+    for name in conditions
+        .keys()
+        .filter(|name| !SUBSYSTEMS.contains(&name.as_str()) && find_key(name).is_none())
+    {
+        errors.push(ConfigError::new(
+            "conditions",
+            format!("{:?}", name),
+            "use a feature name, i.e. 'git', or the name of a generated instruction, i.e. 'VERGEN_GIT_SHA'",
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{validate, Condition, EmissionContext};
+    use std::collections::BTreeMap;
+
+    fn context(vars: &[(&str, &str)]) -> EmissionContext {
+        let vars: Vec<(String, String)> = vars
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+            .collect();
+        EmissionContext::from_vars(&vars)
+    }
+
+    #[test]
+    fn context_from_vars() {
+        let context = context(&[
+            ("PROFILE", "release"),
+            ("TARGET", "aarch64-unknown-linux-gnu"),
+            ("HOST", "x86_64-unknown-linux-gnu"),
+            ("CI", "true"),
+        ]);
+        assert_eq!(context.profile().as_deref(), Some("release"));
+        assert_eq!(
+            context.target().as_deref(),
+            Some("aarch64-unknown-linux-gnu")
+        );
+        assert!(*context.is_ci());
+        assert!(context.is_cross_compile());
+        assert!(context.features().is_empty());
+        assert_eq!(EmissionContext::from_vars(&[]), EmissionContext::default());
+    }
+
+    #[test]
+    fn ci_values() {
+        for (value, is_ci) in &[
+            ("1", true),
+            ("true", true),
+            ("woodpecker", true),
+            ("", false),
+            ("0", false),
+            ("false", false),
+        ] {
+            assert_eq!(*context(&[("CI", *value)]).is_ci(), *is_ci, "{}", value);
+        }
+    }
+
+    #[test]
+    fn features() {
+        let context = context(&[
+            ("CARGO_FEATURE_TELEMETRY", "1"),
+            ("CARGO_FEATURE_FAST_HASH", "1"),
+            ("CARGO_PKG_NAME", "vergen"),
+        ]);
+        assert_eq!(
+            context.features().iter().collect::<Vec<_>>(),
+            ["FAST_HASH", "TELEMETRY"]
+        );
+        assert!(context.has_feature("telemetry"));
+        assert!(context.has_feature("fast-hash"));
+        assert!(context.has_feature("FAST_HASH"));
+        assert!(!context.has_feature("serde"));
+    }
+
+    #[test]
+    fn profiles_and_targets() {
+        let debug = context(&[("PROFILE", "debug"), ("TARGET", "wasm32-wasi")]);
+        let release = context(&[("PROFILE", "release"), ("TARGET", "wasm32-wasi")]);
+        let condition = Condition::only_profiles(&["release"]);
+        assert!(!condition.holds(&debug));
+        assert!(condition.holds(&release));
+        assert!(!condition.holds(&EmissionContext::default()));
+
+        let mut condition = Condition::only_targets(&["x86_64-unknown-linux-gnu"]);
+        assert!(!condition.holds(&release));
+        condition.targets_mut().push("wasm32-wasi".to_string());
+        condition.profiles_mut().push("release".to_string());
+        assert!(condition.holds(&release));
+        assert!(!condition.holds(&debug));
+
+        assert!(Condition::default().holds(&EmissionContext::default()));
+    }
+
+    #[test]
+    fn when_function() {
+        let condition = Condition::when(|context| !context.is_cross_compile());
+        let native = context(&[
+            ("TARGET", "x86_64-apple-darwin"),
+            ("HOST", "x86_64-apple-darwin"),
+        ]);
+        let cross = context(&[
+            ("TARGET", "aarch64-apple-ios"),
+            ("HOST", "x86_64-apple-darwin"),
+        ]);
+        assert!(condition.holds(&native));
+        assert!(!condition.holds(&cross));
+    }
+
+    #[test]
+    fn invalid_names() {
+        let mut conditions = BTreeMap::new();
+        for name in &["git", "vergen", "VERGEN_GIT_SHA", "gti", "VERGEN_BOGUS"] {
+            let _ = conditions.insert((*name).to_string(), Condition::default());
+        }
+        let mut errors = vec![];
+        validate(&conditions, &mut errors);
+        let values: Vec<&str> = errors.iter().map(|error| error.value().as_str()).collect();
+        assert_eq!(values, ["\"VERGEN_BOGUS\"", "\"gti\""]);
+    }
+}
@@ -10,35 +10,85 @@
 
 #[cfg(feature = "build")]
 use crate::feature::Build;
-#[cfg(feature = "cargo")]
-use crate::feature::Cargo;
-#[cfg(feature = "git")]
-use crate::feature::Git;
+#[cfg(feature = "hg")]
+use crate::feature::Hg;
 #[cfg(feature = "rustc")]
 use crate::feature::Rustc;
 #[cfg(feature = "si")]
 use crate::feature::Sysinfo;
+#[cfg(feature = "cargo")]
+use crate::feature::{Cargo, TargetNames};
+#[cfg(feature = "git")]
+use crate::feature::{Git, ShaKind};
+#[cfg(any(feature = "build", feature = "git"))]
+use crate::feature::{TimeZone, TimestampKind};
+#[cfg(feature = "hash")]
+use crate::hash::ValueHash;
+#[cfg(feature = "licenses")]
+use crate::licenses::Licenses;
+#[cfg(all(
+    any(test, feature = "test-util"),
+    any(feature = "build", feature = "git", feature = "rustc")
+))]
+use crate::provider::Providers;
 use crate::{
+    banner::validate_banner,
+    buildinfo::BuildInfo,
+    built::BuiltCompat,
+    c_header::CHeader,
+    command::{CommandKey, CustomCommand, COMMAND_KEY_PREFIX},
+    condition::{self, Condition, EmissionContext},
     constants::{
-        BUILD_DATE_NAME, BUILD_SEMVER_NAME, BUILD_TIMESTAMP_NAME, BUILD_TIME_NAME, CARGO_FEATURES,
-        CARGO_PROFILE, CARGO_TARGET_TRIPLE, GIT_BRANCH_NAME, GIT_COMMIT_DATE_NAME,
-        GIT_COMMIT_TIMESTAMP_NAME, GIT_COMMIT_TIME_NAME, GIT_SEMVER_NAME, GIT_SEMVER_TAGS_NAME,
-        GIT_SHA_NAME, GIT_SHA_SHORT_NAME, RUSTC_CHANNEL_NAME, RUSTC_COMMIT_DATE, RUSTC_COMMIT_HASH,
-        RUSTC_HOST_TRIPLE_NAME, RUSTC_LLVM_VERSION, RUSTC_SEMVER_NAME, SYSINFO_CPU_BRAND,
+        BANNER_NAME, BUILD_CPU_COUNT_NAME, BUILD_DATE_NAME, BUILD_NUMBER_NAME,
+        BUILD_SEMVER_MAJOR_NAME, BUILD_SEMVER_MINOR_NAME, BUILD_SEMVER_NAME,
+        BUILD_SEMVER_PATCH_NAME, BUILD_SEMVER_PRE_NAME, BUILD_TIMESTAMP_NAME, BUILD_TIME_NAME,
+        CARGO_FEATURES, CARGO_LIBC_VERSION, CARGO_PKG_AUTHORS, CARGO_PKG_DESCRIPTION,
+        CARGO_PKG_HOMEPAGE, CARGO_PKG_LICENSE, CARGO_PKG_LICENSE_FILE, CARGO_PKG_REPOSITORY,
+        CARGO_PROFILE, CARGO_TARGET_ARCH, CARGO_TARGET_ENDIAN, CARGO_TARGET_ENV, CARGO_TARGET_OS,
+        CARGO_TARGET_POINTER_WIDTH, CARGO_TARGET_TRIPLE, DEPENDENCY_LICENSES_NAME,
+        ENABLED_FEATURES_NAME, GIT_BRANCH_COUNT_NAME, GIT_BRANCH_NAME, GIT_COMMITS_SINCE_TAG_NAME,
+        GIT_COMMITS_SINCE_TAG_SUBJECTS_NAME, GIT_COMMIT_AGE_SECONDS_NAME, GIT_COMMIT_DATE_NAME,
+        GIT_COMMIT_TIMESTAMP_NAME, GIT_COMMIT_TIME_NAME, GIT_CONTRIBUTOR_COUNT_NAME,
+        GIT_MERGE_BASE_NAME, GIT_OBJECT_FORMAT_NAME, GIT_PARENT_COUNT_NAME, GIT_ROOT_NAME,
+        GIT_SEMVER_NAME, GIT_SEMVER_TAGS_NAME, GIT_SHA_NAME, GIT_SHA_SHORT_NAME, GIT_SIGNED_NAME,
+        GIT_SIGNER_NAME, GIT_TAG_COUNT_NAME, GIT_TRACKED_FILES_NAME, GIT_TREE_HASH_NAME,
+        GIT_WORKTREE_NAME, HASH_NAME, HG_BRANCH_NAME, HG_REV_NAME, HG_SHA_NAME,
+        IS_CROSS_COMPILE_NAME, PLACEHOLDER, PROVENANCE_JSON_NAME, RELEASE_URL_NAME,
+        RUSTC_CHANNEL_NAME, RUSTC_COMMIT_DATE, RUSTC_COMMIT_HASH, RUSTC_HOST_TRIPLE_NAME,
+        RUSTC_LLVM_VERSION, RUSTC_SEMVER_NAME, SCRIPT_DURATION_NAME, SYSINFO_CPU_BRAND,
         SYSINFO_CPU_CORE_COUNT, SYSINFO_CPU_FREQUENCY, SYSINFO_CPU_NAME, SYSINFO_CPU_VENDOR,
-        SYSINFO_MEMORY, SYSINFO_NAME, SYSINFO_OS_VERSION, SYSINFO_USER,
+        SYSINFO_MEMORY, SYSINFO_NAME, SYSINFO_OS_VERSION, SYSINFO_USER, TARGET_ARCH_NAME,
+        TARGET_ENV_NAME, TARGET_OS_NAME, TARGET_TRIPLE_NAME, UNAVAILABLE,
     },
+    emit_cfg::EmitCfg,
+    error::{ConfigError, Error},
     feature::{
-        configure_build, configure_cargo, configure_git, configure_rustc, configure_sysinfo,
+        configure_build, configure_cargo, configure_git, configure_hg, configure_rustc,
+        configure_sysinfo, placeholder_build, placeholder_cargo, placeholder_git, placeholder_hg,
+        placeholder_rustc, placeholder_sysinfo,
     },
+    merge::{pick, union, union_map, SetFields},
+    output::{OutputFormat, OutputTarget},
+    package::{self, Package},
+    release::validate_url_template,
+    sanitize::{trim_value, Sanitize},
+    sticky::{find_key, is_key_name, Sticky},
+    summary::write_summary,
+    syntax::CargoSyntax,
+    truncate::Truncate,
 };
 use anyhow::Result;
-use enum_iterator::IntoEnumIterator;
-use getset::{Getters, MutGetters};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
+    fmt, panic,
     path::{Path, PathBuf},
+    process::Command,
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
+#[cfg(any(feature = "build", feature = "git"))]
+use {crate::provider::now_utc, time::OffsetDateTime};
 
 /// Configure `vergen` to produce the `cargo:` instructions you need
 ///
@@ -47,6 +97,323 @@ use std::{
 /// * See [`Git`](crate::Git) for details on `VERGEN_GIT_*` instruction configuration
 /// * See [`Rustc`](crate::Rustc) for details on `VERGEN_RUSTC_*` instruction configuration
 /// * See [`Sysinfo`](crate::Sysinfo) for details on `VERGEN_SYSINFO_*` instruction configuration
+/// * See [`Hg`](crate::Hg) for details on `VERGEN_HG_*` instruction configuration
+///
+/// # Link Section
+/// If the `emit_link_section` field is set, the generated `KEY=value` pairs are also embedded,
+/// NUL separated, in a linker section with the given name (i.e. `.vergen`).  This allows tools like `strings`
+/// or `objdump -s -j .vergen` to extract the version information from a binary without running it.
+///
+/// * **NOTE** - This is implemented with a linker script written to `OUT_DIR` and passed along via
+/// `cargo:rustc-link-arg`, so it only applies to the binaries, tests, examples, and benches of the
+/// crate running the build script.
+/// * **NOTE** - Only ELF targets (`unix` family, non-Apple) linked with a GNU compatible linker (`ld.bfd`, `gold`, `lld`)
+/// are supported.  On other targets a `cargo:warning` is generated and no link section is emitted.
+/// * **NOTE** - The section name may only contain ASCII alphanumeric characters, `.`, and `_`.
+///
+/// # Banner
+/// If the `banner_format` field is set, a `VERGEN_BANNER` instruction is generated from it, i.e.
+/// `myapp {semver} ({sha} {date}, {profile})` becomes `myapp 1.2.3 (abc1234 2024-01-15, release)`.
+///
+/// | Placeholder | Value |
+/// | ----------- | ----- |
+/// | `{name}` | `CARGO_PKG_NAME` |
+/// | `{semver}` | `VERGEN_BUILD_SEMVER`, `VERGEN_GIT_SEMVER`, `VERGEN_GIT_SEMVER_LIGHTWEIGHT`, or `CARGO_PKG_VERSION` |
+/// | `{sha}` | `VERGEN_GIT_SHA_SHORT` or `VERGEN_GIT_SHA` |
+/// | `{date}` | The date of `VERGEN_GIT_COMMIT_DATE`, `VERGEN_GIT_COMMIT_TIMESTAMP`, `VERGEN_BUILD_DATE`, or `VERGEN_BUILD_TIMESTAMP` |
+/// | `{profile}` | `VERGEN_CARGO_PROFILE` or `PROFILE` |
+///
+/// * The first generated value listed is used, and `unknown` is used if there is none.
+/// * Use `{{` and `}}` for literal braces.
+/// * **NOTE** - An unknown placeholder fails [validation](Self::validate).
+///
+/// # Version Control
+/// The commit information is read from git by default.  With the `hg` feature enabled, set the `vcs` field to
+/// [`Vcs::Hg`] to read it from mercurial instead.  Only one of the [`Git`](crate::Git) or [`Hg`](crate::Hg)
+/// configurations is used, the other is ignored.
+///
+/// # Environment Overrides
+/// Unless the `env_overrides` field is set to false, the `VERGEN_DISABLE_*` environment variables are applied
+/// on top of this configuration before any instructions are generated, and the environment wins.
+/// See [`apply_env_overrides`](Self::apply_env_overrides) for the supported variables.
+///
+/// # Overrides File
+/// A hermetic build, i.e. one from exported sources without the repository, may know values `vergen` can't gather.
+/// Set the `overrides_file` field, or the `VERGEN_OVERRIDES_FILE` environment variable, to the path of a file of
+/// `KEY=value` lines, i.e. `VERGEN_GIT_SHA=75b390dc6c05a6a4aa2791cc7b3934591803bc22`, and those values are used.
+///
+/// * A value from the file wins over the gathered value, the [value providers](Self::value_provider), and the
+///   [`Sticky`] cache.  The instructions the file doesn't name are generated as usual.
+/// * The values are emitted even if their feature or instruction is turned off, so the `VERGEN_DISABLE_*`
+///   [overrides](Self::apply_env_overrides) can turn off the gathering the build doesn't allow, while the file
+///   provides the values.  A value from the file counts for a [required](Self::require) instruction.
+/// * The values are [sanitized](crate::Sanitize) and [truncated](crate::Truncate) like the gathered ones, and
+///   [`only`](Self::only) still applies.
+/// * The `VERGEN_OVERRIDES_FILE` variable wins over the field, as the environment does for the `VERGEN_DISABLE_*`
+///   overrides, unless the `env_overrides` field is false.  An empty variable turns the file off.
+/// * Blank lines and lines starting with `#` are skipped, and the value is everything after the first `=`, trimmed.
+///   A `cargo:warning` names each unknown instruction and malformed line.
+/// * A relative path is relative to the package root, and a `cargo:rerun-if-changed` instruction is generated for
+///   the file.
+/// * **NOTE** - A file that can't be read fails the build, rather than silently using the gathered values.
+///
+/// # Parallel Gathering
+/// The enabled features gather their information in parallel, one thread per feature, and the results are
+/// merged before any instructions are generated, so the output doesn't depend on which feature finishes first.
+/// If more than one feature fails, the error from the first feature in `build`, `git`, `hg`, `rustc`, `cargo`, `sysinfo`
+/// order is returned.  Set the `single_threaded` field to true to gather one feature at a time on the calling thread,
+/// i.e. for debugging.
+///
+/// # Time Limit
+/// A hung `git` or `hg` command stalls the build with no feedback.  Set the `max_duration` field to stop waiting
+/// once the features have run for that long.  The information gathered so far is emitted, and a `cargo:warning`
+/// names the features that didn't finish.  Their instructions are skipped, or use the unavailable placeholder if
+/// the `emit_all_with_placeholders` field is true.  Nothing is left running: a feature stops at its next step once the
+/// deadline has passed, any `git` or `hg` command still running at the deadline is killed, and `vergen` waits for each
+/// feature to stop before it returns.  With the `single_threaded` field also set, the features after the deadline
+/// aren't started.
+///
+/// # Warnings
+/// `vergen` reports problems that don't stop the build, i.e. a misspelled `VERGEN_DISABLE_*` override, as `cargo:warning`
+/// instructions.  Use the `warnings` field to quiet them, see [`WarningLevel`].
+///
+/// An instruction that is turned on, or named by [`only`](Self::only), but has no value isn't emitted, so an `env!`
+/// that reads it fails in a later crate.  Each one gets a `cargo:warning=vergen: skipped VERGEN_X because <reason>`
+/// instruction, i.e. `vergen: skipped VERGEN_GIT_BRANCH because HEAD isn't on a local branch`, once per instruction.
+/// Set the `quiet` field to true to turn these warnings off.
+///
+/// # Verbose Logging
+/// Set the `verbose` field to true to log how long each feature took, and how long opening the git repository took, to
+/// stderr, i.e. `vergen: the git feature took 12.4ms`.  Cargo only shows the build script's stderr with
+/// `cargo build -vv`, or when it fails.
+///
+/// Set the `print_table` field to true to write the generated values, and the [command](Self::add_command) outputs, to
+/// stderr as an `Instruction | Value` table once they're emitted.  The rows are sorted by name, as in the
+/// [`Dotenv`](crate::OutputFormat::Dotenv) file, so the tables of two builds can be diffed.
+///
+/// ```text
+/// vergen: Instruction            | Value
+/// vergen: -----------------------+------
+/// vergen: VERGEN_BUILD_SEMVER    | 6.0.0
+/// vergen: VERGEN_BUILD_TIMESTAMP | 2021-02-25T23:28:39.493201+00:00
+/// ```
+///
+/// # Sticky Values
+/// See [`Sticky`] for details on re-using previously generated values to avoid needless rebuilds.
+///
+/// # rustc-cfg Flags
+/// See [`EmitCfg`] for details on emitting boolean values as `cargo:rustc-cfg` flags.
+///
+/// # Dependent Crates
+/// A library can pass its values to the build scripts of the crates that depend on it, i.e. to compose them into their
+/// own version strings.  Name the instructions, or the [commands](Self::add_command), in the `links_metadata` field,
+/// and give the package a `links` key.  Each value is emitted as `cargo:NAME=value` metadata, or
+/// `cargo::metadata=NAME=value` with the [`CargoSyntax::Modern`] syntax, and cargo sets it as the
+/// `DEP_<LINKS>_<NAME>` environment variable for the build scripts of the direct dependents.  Cargo upper cases the
+/// `links` key and the name, and replaces `-` with `_`, so `links = "my-lib"` and `VERGEN_GIT_SHA` is read from
+/// `DEP_MY_LIB_VERGEN_GIT_SHA`.
+///
+/// ```toml
+/// [package]
+/// name = "my-lib"
+/// links = "my-lib"
+/// ```
+///
+/// * Nothing is passed by default.
+/// * A value that isn't generated isn't passed.
+/// * **NOTE** - Without a `links` key, cargo doesn't pass metadata on, so none is emitted and a `cargo:warning` is
+///   generated instead.
+/// * **NOTE** - The metadata is part of the `cargo` instructions, so it isn't written for an output target without
+///   standard output.
+///
+/// # Value Hash
+/// See [`ValueHash`](crate::ValueHash) for details on the `VERGEN_HASH` instruction, a hash over every other generated
+/// value, with the `hash` feature.
+///
+/// # Unavailable Values
+/// An instruction that isn't generated breaks any `env!` that reads it.  Set the `emit_all_with_placeholders` field
+/// to true to generate every instruction of the enabled features, even the ones that are turned off, with the
+/// `unavailable_placeholder` value (`VERGEN_UNAVAILABLE` by default) for any value that isn't available.
+///
+/// * The real value is used whenever it is available.
+/// * A feature that fails to gather its information generates a `cargo:warning` instead of an error.
+///
+/// # Conditional Emission
+/// See [`emit_when`](Self::emit_when) for details on emitting a feature or an instruction only in some builds, i.e.
+/// only in `release` builds, or only when not cross-compiling.
+///
+/// # Value Providers
+/// See [`value_provider`](Self::value_provider) for details on computing an instruction value with your own function.
+/// The providers are not called when building on docs.rs.
+///
+/// # Value Overrides
+/// See [`override_value`](Self::override_value) for details on setting the exact value of an instruction, i.e. from
+/// release tooling that builds the configuration in Rust.  An override wins over every other source of the value.
+///
+/// # Reproducible Builds
+/// If the `source_date_epoch` field is true and the `SOURCE_DATE_EPOCH` environment variable is set to a number of seconds
+/// since the Unix epoch, that time is used instead of the current time for the build date/time instructions.
+/// See [`reproducible`](Self::reproducible) for a configuration aimed at deterministic binaries.  Without an external
+/// variable, the `timestamp_from_git` field of the `Build` configuration uses the `HEAD` commit time instead.
+///
+/// * **NOTE** - A `SOURCE_DATE_EPOCH` value that isn't a number is ignored, and the current time is used.
+///
+/// # Unified Clock
+/// Each instruction that depends on the current time reads it when it's gathered, so the build time and the git commit
+/// age can describe instants a few milliseconds apart.  If the `unify_clock` field is true, the current time is read
+/// once, before anything is gathered, and every one of them uses it.
+///
+/// * These instructions use it: `VERGEN_BUILD_DATE`, `VERGEN_BUILD_TIME`, `VERGEN_BUILD_TIMESTAMP`, and
+///   `VERGEN_GIT_COMMIT_AGE_SECONDS`, along with the ones of each [`Package`](crate::Package).
+/// * With the `timestamp_from_git` field of the `Build` configuration, the build time is the `HEAD` commit time, and
+///   the unified time is only used when the commit time can't be read.
+/// * **NOTE** - The time is read the same way as without the field, so `SOURCE_DATE_EPOCH` still wins when it's used.
+///
+/// # Self Timing
+/// If the `self_timing` field is true, a `VERGEN_BUILD_SCRIPT_DURATION_MS` instruction is generated with the number of
+/// milliseconds `vergen` took to gather and generate the other values, for profiling build scripts.
+///
+/// * **NOTE** - This is diagnostic only.  The value changes on every run, so it is left out of the
+/// [`ValueHash`] by default and is never generated by the [`reproducible`](Self::reproducible) configuration.
+///
+/// # Enabled Features
+/// If the `emit_enabled_summary` field is true, a `VERGEN_ENABLED_FEATURES` instruction is generated with the
+/// subsystems that generated at least one value, sorted and comma separated, i.e. `build,git,rustc`.  A binary can
+/// report which subsystems ran at build time without checking every instruction.
+///
+/// * **NOTE** - A subsystem whose values are all placeholders, from the `emit_all_with_placeholders` field, isn't
+///   listed.
+///
+/// # Provenance
+/// If the `provenance` field is true, a `VERGEN_PROVENANCE_JSON` instruction is generated with a single line JSON
+/// document describing the build, for attaching SLSA style provenance to a release:
+///
+/// ```text
+/// {"schema":"vergen-provenance/1","builder":{"id":"vergen","version":"6.0.0","rustc":"1.55.0"},"build":{"target":"x86_64-unknown-linux-gnu","timestamp":"2021-02-12T01:54:15Z"},"source":{"git_sha":"75b390dc6c05a6a4aa2791cc7b3934591803bc22"},"materials":[{"uri":"Cargo.lock","digest":{"sha256":"5f1e3c0a..."}}]}
+/// ```
+///
+/// * `builder.rustc` is `VERGEN_RUSTC_SEMVER`, `build.target` is `VERGEN_CARGO_TARGET_TRIPLE`, or the `TARGET`
+///   environment variable, `build.timestamp` is `VERGEN_BUILD_TIMESTAMP`, and `source.git_sha` is `VERGEN_GIT_SHA`.
+///   A value whose instruction isn't generated is `null`.
+/// * `materials` has the SHA-256 digest of the `Cargo.lock` found in the package root or the closest directory above
+///   it, or is empty if there isn't one.  Cargo re-runs the build script when it changes.
+/// * The members are always present and in this order, and the `schema` changes whenever one is added, removed, or
+///   changes meaning.
+/// * **NOTE** - As with the [`BuildInfo`] `Build-Date`, `build.timestamp` is `null` if the `SOURCE_DATE_EPOCH`
+///   environment variable is set but the `source_date_epoch` field is false.
+///
+/// # Build Number
+/// If the `build_number` field is true, a `VERGEN_BUILD_NUMBER` instruction is generated with the number of commits
+/// reachable from `HEAD`, the equivalent of `git rev-list --count HEAD`.  If the working tree is dirty, a `+N` suffix
+/// is added, where `N` is the number of tracked files with uncommitted changes, i.e. `1342+2`.  Untracked files are
+/// ignored, as they are by `git describe --dirty`.
+///
+/// * **NOTE** - The values are read from the git repository, so this needs the `git` feature, the [`Vcs::Git`]
+///   version control system, and the [`Git`](crate::Git) `enabled` field set to true, although none of the
+///   `VERGEN_GIT_*` instructions need to be generated.  If any of them is missing, or there is no repository,
+///   the value is empty and a `cargo:warning` is generated.
+/// * **NOTE** - A change to the working tree doesn't rerun the build script, so the suffix can be stale until `HEAD`
+///   changes or the crate is rebuilt.
+///
+/// # Release URL
+/// If the `release_url` field is true, a `VERGEN_RELEASE_URL` instruction is generated with the release page of the tag
+/// on `HEAD`, built from [`CARGO_PKG_REPOSITORY`] and the tag, i.e.
+/// `https://github.com/rustyhorde/vergen/releases/tag/5.0.0`.  The `url_template` field replaces the GitHub style
+/// `{repo}/releases/tag/{tag}` for other forges, i.e. `{repo}/-/releases/{tag}` for GitLab.  A trailing `/` or `.git`
+/// is removed from the repository URL before it's filled in.
+///
+/// * The value is empty if `HEAD` isn't exactly on a tag, or the `repository` field isn't set in the `Cargo.toml`.
+/// * **NOTE** - The tag is read from the git repository, so this needs the `git` feature, the [`Vcs::Git`] version
+///   control system, and the [`Git`](crate::Git) `enabled` field set to true.  Without them, the value is empty and
+///   a `cargo:warning` is generated.
+///
+/// [`CARGO_PKG_REPOSITORY`]: https://doc.rust-lang.org/cargo/reference/environment-variables.html#environment-variables-cargo-sets-for-crates
+///
+/// # Value Sanitization
+/// See [`Sanitize`] for details on how values that would corrupt the cargo instructions, i.e. a line break, are handled.
+///
+/// # Value Length
+/// See [`Truncate`] for details on limiting the length of the generated values, i.e. the commit subjects.
+///
+/// # Cargo Syntax
+/// The instructions use the single colon `cargo:` syntax by default.  See [`CargoSyntax`] for details on using the
+/// double colon `cargo::` syntax of newer cargo versions.
+///
+/// # Output Target
+/// See [`OutputTarget`] for details on writing the values to a file for build systems other than cargo.
+///
+/// # Layered Configurations
+/// See [`merge`](Self::merge) for details on combining a base configuration with per-project changes.
+///
+/// # C Header
+/// See [`CHeader`] for details on writing the values to a C header, for C and C++ code linking against the crate.
+///
+/// # `built` Compatibility
+/// See [`BuiltCompat`] for details on writing the values as the constants generated by the `built` crate.
+///
+/// # Reproducible Builds Provenance
+/// See [`BuildInfo`] for details on writing a `.buildinfo` style provenance document for reproducible-builds
+/// verification.
+///
+/// # Dependency Licenses
+/// See [`Licenses`](crate::Licenses) for details on writing the licenses of the dependencies to a file, with the
+/// `licenses` feature.
+///
+/// # Include File
+/// Set the `generate_include` field to true to also write `$OUT_DIR/vergen.rs`, with a `pub fn vergen_pretty() -> String`
+/// that returns every generated value as a `NAME  value` line, with the values aligned.  This saves an `env!` per
+/// value when all you want is a version report, i.e. for a `--version` flag.  The same values are also in a
+/// `pub static VERGEN_MAP: &[(&str, &str)]`, sorted by name, to iterate over at runtime.
+///
+/// ```text
+/// include!(concat!(env!("OUT_DIR"), "/vergen.rs"));
+///
+/// fn main() {
+///     println!("{}", vergen_pretty());
+///     for (name, value) in VERGEN_MAP {
+///         log::debug!("{} = {}", name, value);
+///     }
+/// }
+/// ```
+///
+/// An instruction the binary can't do without is marked with [`require`](Self::require).  If it has no value, the
+/// `vergen.rs` fails to compile with the reason, rather than the binary reporting it as missing at runtime.
+///
+/// # Duplicate Values
+/// Each instruction is generated once, from a single value.  If a feature generates a value for an instruction that
+/// already has one, the `on_duplicate_key` field decides which value is emitted, see [`DuplicatePolicy`].  By default
+/// the later value wins.
+///
+/// * **NOTE** - A [value provider](Self::value_provider) replaces the built-in value on purpose, and isn't a duplicate.
+///
+/// The instruction names are also checked against each other, ignoring case as Windows does, after every value is
+/// generated.  A built-in instruction, one read from the [overrides file](Self#overrides-file), an
+/// [added command](Self::add_command), a [command key](Self::add_command_key), or a [package](Self::for_package) set
+/// instruction with the same name as another is a collision, i.e. an added `VERGEN_GIT_SHA_API` command and the
+/// `api` package's `VERGEN_GIT_SHA_API`.  The policy decides it the same way, and the error or `cargo:warning` names
+/// the source of each instruction.  With [`KeepFirst`](DuplicatePolicy::KeepFirst) the built-in instructions come
+/// first, then the commands, then the package sets.
+///
+/// The same policy applies when [`vergen`](crate::vergen) is called more than once in a build script, i.e. from two
+/// helper functions.  An instruction an earlier call already emitted is emitted again along with a `cargo:warning`
+/// ([`Overwrite`](DuplicatePolicy::Overwrite)), skipped ([`KeepFirst`](DuplicatePolicy::KeepFirst)), or fails the
+/// call before anything is emitted ([`Error`](DuplicatePolicy::Error)).  Calls emitting different instructions are fine.
+///
+/// * **NOTE** - Only the calls in the same build script are checked, the build scripts of other crates in a workspace
+///   run in their own processes.  The files written by the [`OutputTarget`], [`CHeader`], [`BuiltCompat`], and
+///   [`BuildInfo`] outputs are replaced by each call, and aren't checked.
+///
+/// # Non-UTF-8 Paths
+/// The repository paths are kept as they are on disk until they're emitted, but `cargo` only reads UTF-8 instructions.
+/// When a `cargo:rerun-if-changed` path or the `VERGEN_GIT_ROOT` value isn't valid UTF-8, the `non_utf8_paths` field
+/// decides whether it's emitted with replacement characters or skipped, see [`NonUtf8Policy`].  Either way, a
+/// `cargo:warning` names the path.
+///
+/// # docs.rs
+/// When the `DOCS_RS` environment variable is set, there is no git repository, network, or writable
+/// source tree to probe.  Unless the `docs_rs_placeholder` field is set to false, `vergen` then emits every
+/// enabled instruction with the value `VERGEN_IDEMPOTENT_OUTPUT`, without touching git, rustc, or the system,
+/// and without any `cargo:rerun-if-changed` instructions.
 ///
 /// # Example
 ///
@@ -91,9 +458,17 @@ use std::{
 "##
 )]
 /// ```
-#[derive(Clone, Debug, Getters, MutGetters)]
-#[cfg_attr(not(feature = "git"), derive(Copy))]
-#[getset(get = "pub(crate)", get_mut = "pub")]
+#[derive(Clone, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
 pub struct Instructions {
     /// Use this to modify the [`Build`] feature configuration.
     #[cfg(feature = "build")]
@@ -110,193 +485,4064 @@ pub struct Instructions {
     /// Use this to modify the [`Sysinfo`] feature configuration.
     #[cfg(feature = "si")]
     sysinfo: Sysinfo,
+    /// Use this to modify the [`Hg`] feature configuration.
+    #[cfg(feature = "hg")]
+    hg: Hg,
+    /// The version control system to read the `VERGEN_GIT_*` or `VERGEN_HG_*` information from.
+    vcs: Vcs,
+    /// Embed the generated values in the linker section with this name.
+    emit_link_section: Option<String>,
+    /// Emit a `VERGEN_BANNER` instruction built from this format.
+    banner_format: Option<String>,
+    /// Apply the `VERGEN_DISABLE_*` environment variable overrides.
+    env_overrides: bool,
+    /// The path of a file of `KEY=value` lines whose values win over the generated ones.
+    overrides_file: Option<PathBuf>,
+    /// Emit placeholder values instead of probing when building on docs.rs.
+    docs_rs_placeholder: bool,
+    /// Gather the feature information one feature at a time, rather than in parallel.
+    single_threaded: bool,
+    /// Stop waiting for the feature information after this long, and emit what was gathered.
+    max_duration: Option<Duration>,
+    /// Use the `SOURCE_DATE_EPOCH` environment variable, when set, as the current time.
+    source_date_epoch: bool,
+    /// Read the current time once, and use it for every instruction that depends on it.
+    unify_clock: bool,
+    /// Generate the `VERGEN_BUILD_SCRIPT_DURATION_MS` instruction.
+    self_timing: bool,
+    /// Generate the `VERGEN_BUILD_NUMBER` instruction.
+    build_number: bool,
+    /// Generate the `VERGEN_RELEASE_URL` instruction.
+    release_url: bool,
+    /// The `VERGEN_RELEASE_URL` template, with `{repo}` and `{tag}` placeholders, or None for the GitHub style.
+    url_template: Option<String>,
+    /// Generate the `VERGEN_ENABLED_FEATURES` instruction.
+    emit_enabled_summary: bool,
+    /// Generate the `VERGEN_PROVENANCE_JSON` instruction.
+    provenance: bool,
+    /// The warnings to emit as `cargo:warning` instructions.
+    warnings: WarningLevel,
+    /// Don't warn about the requested instructions that were skipped.
+    quiet: bool,
+    /// Log how long each feature took to stderr.
+    verbose: bool,
+    /// Write a table of the generated values to stderr.
+    print_table: bool,
+    /// Use this to modify the [`Sticky`] value cache configuration.
+    sticky: Sticky,
+    /// Use this to modify the [`EmitCfg`] rustc-cfg flag configuration.
+    emit_cfg: EmitCfg,
+    /// The instructions whose values are passed to the dependent crates, as `DEP_<LINKS>_<NAME>` variables.
+    links_metadata: Vec<String>,
+    /// Use this to modify the [`ValueHash`] configuration.
+    #[cfg(feature = "hash")]
+    hash: ValueHash,
+    /// Where the generated values are written.
+    output_target: OutputTarget,
+    /// The format of the file written for the output target.
+    output_format: OutputFormat,
+    /// Write the `cargo:rerun-if-changed` paths to a sidecar of the output file.
+    rerun_sidecar: bool,
+    /// Emit every instruction of the enabled features, using a placeholder for any value that is unavailable.
+    emit_all_with_placeholders: bool,
+    /// The placeholder value for the unavailable values.
+    unavailable_placeholder: String,
+    /// Use this to modify the [`Sanitize`] value sanitization configuration.
+    sanitize: Sanitize,
+    /// Use this to modify the [`Truncate`] value length configuration.
+    truncate: Truncate,
+    /// The syntax of the generated `cargo` instructions.
+    cargo_syntax: CargoSyntax,
+    /// Use this to modify the [`CHeader`] C header configuration.
+    c_header: CHeader,
+    /// Use this to modify the [`BuiltCompat`] `built.rs` configuration.
+    built_compat: BuiltCompat,
+    /// Use this to modify the [`BuildInfo`] `.buildinfo` configuration.
+    build_info: BuildInfo,
+    /// Use this to modify the [`Licenses`] dependency license configuration.
+    #[cfg(feature = "licenses")]
+    licenses: Licenses,
+    /// Write a `vergen.rs` with a `vergen_pretty` function to `OUT_DIR`.
+    generate_include: bool,
+    /// What to do when an instruction value is generated more than once.
+    on_duplicate_key: DuplicatePolicy,
+    /// What to do with a path that isn't valid UTF-8.
+    non_utf8_paths: NonUtf8Policy,
+    /// The only instructions to emit, or empty for every enabled instruction.
+    only: Vec<String>,
+    /// The instructions the generated `vergen.rs` fails to compile without.
+    required: Vec<String>,
+    /// The instructions that are only emitted when the build runs in CI.
+    ci_only: Vec<String>,
+    /// The feature of the package being built each named instruction is only emitted with, by instruction name.
+    if_features: BTreeMap<String, String>,
+    /// The commands whose output is emitted, by instruction name.
+    commands: BTreeMap<String, CustomCommand>,
+    /// The commands whose output is emitted as a `VERGEN_CMD_` instruction, by the name after the prefix.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    command_keys: BTreeMap<String, CommandKey>,
+    /// The packages whose values are emitted with their own suffix, by package name.
+    packages: BTreeMap<String, Package>,
+    /// The functions that compute an instruction value instead of the built-in logic, by instruction name.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    value_providers: BTreeMap<String, fn() -> Option<String>>,
+    /// The exact values of instructions, by instruction name.
+    value_overrides: BTreeMap<String, String>,
+    /// The conditions the instructions are emitted under, by feature or instruction name.
+    conditions: BTreeMap<String, Condition>,
+    /// The feature and instruction names whose condition doesn't hold for this build.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    excluded: BTreeSet<String>,
+    /// The current time every instruction uses, when the `unify_clock` field is true.
+    #[cfg(any(feature = "build", feature = "git"))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    reference_time: Option<OffsetDateTime>,
+    /// Use this to install fixed data providers for tests.
+    #[cfg(all(
+        any(test, feature = "test-util"),
+        any(feature = "build", feature = "git", feature = "rustc")
+    ))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    providers: Providers,
+    /// The fields set through their `set_*` setter, which win in a [merge](crate::Config::merge)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    set_fields: SetFields,
 }
 
-#[cfg_attr(msrv, allow(clippy::derivable_impls))]
-impl Default for Instructions {
-    fn default() -> Self {
-        Self {
-            #[cfg(feature = "build")]
-            build: Build::default(),
-            #[cfg(feature = "cargo")]
-            cargo: Cargo::default(),
-            #[cfg(feature = "git")]
-            git: Git::default(),
-            #[cfg(feature = "rustc")]
-            rustc: Rustc::default(),
-            #[cfg(feature = "si")]
-            sysinfo: Sysinfo::default(),
-        }
+impl Instructions {
+    /// Use this to modify the [`Build`] feature configuration.
+    #[cfg(feature = "build")]
+    pub(crate) fn build(&self) -> &Build {
+        &self.build
     }
-}
 
-impl Instructions {
-    pub(crate) fn config<T>(self, repo_path: Option<T>) -> Result<Config>
-    where
-        T: AsRef<Path>,
-    {
-        let mut config = Config::default();
+    /// Use this to modify the [`Build`] feature configuration.
+    #[cfg(feature = "build")]
+    pub fn build_mut(&mut self) -> &mut Build {
+        &mut self.build
+    }
 
-        configure_build(&self, &mut config);
-        configure_git(&self, repo_path, &mut config)?;
-        configure_rustc(&self, &mut config)?;
-        configure_cargo(&self, &mut config);
-        configure_sysinfo(&self, &mut config)?;
+    /// Use this to modify the [`Cargo`] feature configuration.
+    #[cfg(feature = "cargo")]
+    pub(crate) fn cargo(&self) -> &Cargo {
+        &self.cargo
+    }
 
-        Ok(config)
+    /// Use this to modify the [`Cargo`] feature configuration.
+    #[cfg(feature = "cargo")]
+    pub fn cargo_mut(&mut self) -> &mut Cargo {
+        &mut self.cargo
     }
-}
 
-/// Build information keys.
-#[derive(Clone, Copy, Debug, IntoEnumIterator, Hash, Eq, Ord, PartialEq, PartialOrd)]
-pub(crate) enum VergenKey {
-    /// The build date. (VERGEN_BUILD_DATE)
-    BuildDate,
-    /// The build time. (VERGEN_BUILD_TIME)
-    BuildTime,
-    /// The build timestamp. (VERGEN_BUILD_TIMESTAMP)
-    BuildTimestamp,
-    /// The build semver. (VERGEN_BUILD_SEMVER)
-    BuildSemver,
-    /// The current working branch name (VERGEN_GIT_BRANCH)
-    Branch,
-    /// The commit date. (VERGEN_GIT_COMMIT_DATE)
-    CommitDate,
-    /// The commit time. (VERGEN_GIT_COMMIT_TIME)
-    CommitTime,
-    /// The commit timestamp. (VERGEN_GIT_COMMIT_TIMESTAMP)
-    CommitTimestamp,
-    /// The semver version from the last git tag. (VERGEN_GIT_SEMVER)
-    Semver,
-    /// The semver version from the last git tag, including lightweight.
-    /// (VERGEN_GIT_SEMVER_LIGHTWEIGHT)
-    SemverLightweight,
-    /// The latest commit SHA. (VERGEN_GIT_SHA)
-    Sha,
-    /// The latest commit short SHA. (VERGEN_GIT_SHA_SHORT)
-    ShortSha,
-    /// The release channel of the rust compiler. (VERGEN_RUSTC_CHANNEL)
-    RustcChannel,
-    /// The rustc commit date. (VERGEN_RUSTC_COMMIT_DATE)
-    RustcCommitDate,
-    /// The rustc commit hash. (VERGEN_RUSTC_COMMIT_HASH)
-    RustcCommitHash,
-    /// The host triple. (VERGEN_HOST_TRIPLE)
-    RustcHostTriple,
-    /// The rustc LLVM version. (VERGEN_RUSTC_LLVM_VERSION)
-    RustcLlvmVersion,
-    /// The version information of the rust compiler. (VERGEN_RUSTC_SEMVER)
-    RustcSemver,
-    /// The cargo target triple (VERGEN_CARGO_TARGET_TRIPLE)
-    CargoTargetTriple,
-    /// The cargo profile (VERGEN_CARGO_PROFILE)
-    CargoProfile,
-    /// The cargo features (VERGEN_CARGO_FEATURES)
-    CargoFeatures,
-    /// The sysinfo system name (VERGEN_SYSINFO_NAME)
-    SysinfoName,
-    /// The sysinfo os version (VERGEN_SYSINFO_OS_VERSION)
-    SysinfoOsVersion,
-    /// The sysinfo user name (VERGEN_SYSINFO_USER)
-    SysinfoUser,
-    /// The sysinfo total memory (VERGEN_SYSINFO_TOTAL_MEMORY)
-    SysinfoMemory,
-    /// The sysinfo cpu vendor (VERGEN_SYSINFO_CPU_VENDOR)
-    SysinfoCpuVendor,
-    /// The sysinfo cpu core count (VERGEN_SYSINFO_CPU_CORE_COUNT)
-    SysinfoCpuCoreCount,
-    /// The sysinfo cpu core count (VERGEN_SYSINFO_CPU_NAME)
-    SysinfoCpuName,
-    /// The sysinfo cpu core count (VERGEN_SYSINFO_CPU_BRAND)
-    SysinfoCpuBrand,
-    /// The sysinfo cpu core count (VERGEN_SYSINFO_CPU_FREQUENCY)
-    SysinfoCpuFrequency,
-}
+    /// Use this to modify the [`Git`] feature configuration.
+    #[cfg(feature = "git")]
+    pub(crate) fn git(&self) -> &Git {
+        &self.git
+    }
 
-impl VergenKey {
-    /// Get the name for the given key.
-    pub(crate) fn name(self) -> &'static str {
-        match self {
-            VergenKey::BuildDate => BUILD_DATE_NAME,
-            VergenKey::BuildTime => BUILD_TIME_NAME,
-            VergenKey::BuildTimestamp => BUILD_TIMESTAMP_NAME,
-            VergenKey::BuildSemver => BUILD_SEMVER_NAME,
-            VergenKey::Branch => GIT_BRANCH_NAME,
-            VergenKey::CommitDate => GIT_COMMIT_DATE_NAME,
-            VergenKey::CommitTime => GIT_COMMIT_TIME_NAME,
-            VergenKey::CommitTimestamp => GIT_COMMIT_TIMESTAMP_NAME,
-            VergenKey::Semver => GIT_SEMVER_NAME,
-            VergenKey::SemverLightweight => GIT_SEMVER_TAGS_NAME,
-            VergenKey::Sha => GIT_SHA_NAME,
-            VergenKey::ShortSha => GIT_SHA_SHORT_NAME,
-            VergenKey::RustcChannel => RUSTC_CHANNEL_NAME,
-            VergenKey::RustcCommitDate => RUSTC_COMMIT_DATE,
-            VergenKey::RustcCommitHash => RUSTC_COMMIT_HASH,
-            VergenKey::RustcHostTriple => RUSTC_HOST_TRIPLE_NAME,
-            VergenKey::RustcLlvmVersion => RUSTC_LLVM_VERSION,
-            VergenKey::RustcSemver => RUSTC_SEMVER_NAME,
-            VergenKey::CargoTargetTriple => CARGO_TARGET_TRIPLE,
-            VergenKey::CargoProfile => CARGO_PROFILE,
-            VergenKey::CargoFeatures => CARGO_FEATURES,
-            VergenKey::SysinfoName => SYSINFO_NAME,
-            VergenKey::SysinfoOsVersion => SYSINFO_OS_VERSION,
-            VergenKey::SysinfoUser => SYSINFO_USER,
-            VergenKey::SysinfoMemory => SYSINFO_MEMORY,
-            VergenKey::SysinfoCpuVendor => SYSINFO_CPU_VENDOR,
-            VergenKey::SysinfoCpuCoreCount => SYSINFO_CPU_CORE_COUNT,
-            VergenKey::SysinfoCpuName => SYSINFO_CPU_NAME,
-            VergenKey::SysinfoCpuBrand => SYSINFO_CPU_BRAND,
-            VergenKey::SysinfoCpuFrequency => SYSINFO_CPU_FREQUENCY,
-        }
+    /// Use this to modify the [`Git`] feature configuration.
+    #[cfg(feature = "git")]
+    pub fn git_mut(&mut self) -> &mut Git {
+        &mut self.git
     }
-}
 
-#[derive(Clone, Debug, Getters, MutGetters)]
-#[getset(get = "pub(crate)")]
-#[getset(get_mut = "pub(crate)")]
-pub(crate) struct Config {
-    cfg_map: BTreeMap<VergenKey, Option<String>>,
-    head_path: Option<PathBuf>,
-    ref_path: Option<PathBuf>,
-}
+    /// Use this to modify the [`Rustc`] feature configuration.
+    #[cfg(feature = "rustc")]
+    pub(crate) fn rustc(&self) -> &Rustc {
+        &self.rustc
+    }
 
-impl Default for Config {
-    fn default() -> Config {
-        Self {
-            cfg_map: VergenKey::into_enum_iter().map(|x| (x, None)).collect(),
-            head_path: Option::default(),
-            ref_path: Option::default(),
-        }
+    /// Use this to modify the [`Rustc`] feature configuration.
+    #[cfg(feature = "rustc")]
+    pub fn rustc_mut(&mut self) -> &mut Rustc {
+        &mut self.rustc
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::{Config, Instructions};
+    /// Use this to modify the [`Sysinfo`] feature configuration.
+    #[cfg(feature = "si")]
+    pub(crate) fn sysinfo(&self) -> &Sysinfo {
+        &self.sysinfo
+    }
 
-    #[test]
-    fn default_config_works() {
-        assert!(!Config::default().cfg_map().is_empty());
+    /// Use this to modify the [`Sysinfo`] feature configuration.
+    #[cfg(feature = "si")]
+    pub fn sysinfo_mut(&mut self) -> &mut Sysinfo {
+        &mut self.sysinfo
     }
 
-    #[cfg(feature = "build")]
-    fn check_build_config(instructions: &Instructions) {
-        use crate::{TimeZone, TimestampKind};
+    /// Use this to modify the [`Hg`] feature configuration.
+    #[cfg(feature = "hg")]
+    pub(crate) fn hg(&self) -> &Hg {
+        &self.hg
+    }
 
-        let config = instructions.build();
-        assert!(config.has_enabled());
-        assert!(config.timestamp());
-        assert_eq!(*config.timezone(), TimeZone::Utc);
-        assert_eq!(*config.kind(), TimestampKind::Timestamp);
-        assert!(config.semver());
+    /// Use this to modify the [`Hg`] feature configuration.
+    #[cfg(feature = "hg")]
+    pub fn hg_mut(&mut self) -> &mut Hg {
+        &mut self.hg
     }
 
-    #[cfg(not(feature = "build"))]
-    fn check_build_config(_instructions: &Instructions) {}
+    /// The version control system to read the `VERGEN_GIT_*` or `VERGEN_HG_*` information from.
+    #[cfg(any(feature = "git", feature = "hg"))]
+    pub(crate) fn vcs(&self) -> &Vcs {
+        &self.vcs
+    }
 
-    #[cfg(feature = "cargo")]
-    fn check_cargo_config(instructions: &Instructions) {
-        let config = instructions.cargo();
+    /// The version control system to read the `VERGEN_GIT_*` or `VERGEN_HG_*` information from.
+    pub fn vcs_mut(&mut self) -> &mut Vcs {
+        &mut self.vcs
+    }
+
+    /// The version control system to read the `VERGEN_GIT_*` or `VERGEN_HG_*` information from.
+    pub fn set_vcs(&mut self, vcs: Vcs) {
+        self.set_fields.mark(MERGED_FIELDS, "vcs");
+        self.vcs = vcs;
+    }
+
+    /// Embed the generated values in the linker section with this name.
+    pub(crate) fn emit_link_section(&self) -> &Option<String> {
+        &self.emit_link_section
+    }
+
+    /// Embed the generated values in the linker section with this name.
+    pub fn emit_link_section_mut(&mut self) -> &mut Option<String> {
+        &mut self.emit_link_section
+    }
+
+    /// Embed the generated values in the linker section with this name.
+    pub fn set_emit_link_section(&mut self, emit_link_section: Option<String>) {
+        self.set_fields.mark(MERGED_FIELDS, "emit_link_section");
+        self.emit_link_section = emit_link_section;
+    }
+
+    /// Emit a `VERGEN_BANNER` instruction built from this format.
+    pub(crate) fn banner_format(&self) -> &Option<String> {
+        &self.banner_format
+    }
+
+    /// Emit a `VERGEN_BANNER` instruction built from this format.
+    pub fn banner_format_mut(&mut self) -> &mut Option<String> {
+        &mut self.banner_format
+    }
+
+    /// Emit a `VERGEN_BANNER` instruction built from this format.
+    pub fn set_banner_format(&mut self, banner_format: Option<String>) {
+        self.set_fields.mark(MERGED_FIELDS, "banner_format");
+        self.banner_format = banner_format;
+    }
+
+    /// Apply the `VERGEN_DISABLE_*` environment variable overrides.
+    pub(crate) fn env_overrides(&self) -> &bool {
+        &self.env_overrides
+    }
+
+    /// Apply the `VERGEN_DISABLE_*` environment variable overrides.
+    pub fn env_overrides_mut(&mut self) -> &mut bool {
+        &mut self.env_overrides
+    }
+
+    /// Apply the `VERGEN_DISABLE_*` environment variable overrides.
+    pub fn set_env_overrides(&mut self, env_overrides: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "env_overrides");
+        self.env_overrides = env_overrides;
+    }
+
+    /// The path of a file of `KEY=value` lines whose values win over the generated ones.
+    pub(crate) fn overrides_file(&self) -> &Option<PathBuf> {
+        &self.overrides_file
+    }
+
+    /// The path of a file of `KEY=value` lines whose values win over the generated ones.
+    pub fn overrides_file_mut(&mut self) -> &mut Option<PathBuf> {
+        &mut self.overrides_file
+    }
+
+    /// The path of a file of `KEY=value` lines whose values win over the generated ones.
+    pub fn set_overrides_file(&mut self, overrides_file: Option<PathBuf>) {
+        self.set_fields.mark(MERGED_FIELDS, "overrides_file");
+        self.overrides_file = overrides_file;
+    }
+
+    /// Emit placeholder values instead of probing when building on docs.rs.
+    pub(crate) fn docs_rs_placeholder(&self) -> &bool {
+        &self.docs_rs_placeholder
+    }
+
+    /// Emit placeholder values instead of probing when building on docs.rs.
+    pub fn docs_rs_placeholder_mut(&mut self) -> &mut bool {
+        &mut self.docs_rs_placeholder
+    }
+
+    /// Emit placeholder values instead of probing when building on docs.rs.
+    pub fn set_docs_rs_placeholder(&mut self, docs_rs_placeholder: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "docs_rs_placeholder");
+        self.docs_rs_placeholder = docs_rs_placeholder;
+    }
+
+    /// Gather the feature information one feature at a time, rather than in parallel.
+    #[cfg(test)]
+    pub(crate) fn single_threaded(&self) -> &bool {
+        &self.single_threaded
+    }
+
+    /// Gather the feature information one feature at a time, rather than in parallel.
+    pub fn single_threaded_mut(&mut self) -> &mut bool {
+        &mut self.single_threaded
+    }
+
+    /// Gather the feature information one feature at a time, rather than in parallel.
+    pub fn set_single_threaded(&mut self, single_threaded: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "single_threaded");
+        self.single_threaded = single_threaded;
+    }
+
+    /// Stop waiting for the feature information after this long, and emit what was gathered.
+    pub fn max_duration_mut(&mut self) -> &mut Option<Duration> {
+        &mut self.max_duration
+    }
+
+    /// Stop waiting for the feature information after this long, and emit what was gathered.
+    pub fn set_max_duration(&mut self, max_duration: Option<Duration>) {
+        self.set_fields.mark(MERGED_FIELDS, "max_duration");
+        self.max_duration = max_duration;
+    }
+
+    /// Use the `SOURCE_DATE_EPOCH` environment variable, when set, as the current time.
+    pub(crate) fn source_date_epoch(&self) -> &bool {
+        &self.source_date_epoch
+    }
+
+    /// Use the `SOURCE_DATE_EPOCH` environment variable, when set, as the current time.
+    pub fn source_date_epoch_mut(&mut self) -> &mut bool {
+        &mut self.source_date_epoch
+    }
+
+    /// Use the `SOURCE_DATE_EPOCH` environment variable, when set, as the current time.
+    pub fn set_source_date_epoch(&mut self, source_date_epoch: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "source_date_epoch");
+        self.source_date_epoch = source_date_epoch;
+    }
+
+    /// Read the current time once, and use it for every instruction that depends on it.
+    pub fn unify_clock_mut(&mut self) -> &mut bool {
+        &mut self.unify_clock
+    }
+
+    /// Read the current time once, and use it for every instruction that depends on it.
+    pub fn set_unify_clock(&mut self, unify_clock: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "unify_clock");
+        self.unify_clock = unify_clock;
+    }
+
+    /// Generate the `VERGEN_BUILD_SCRIPT_DURATION_MS` instruction.
+    pub(crate) fn self_timing(&self) -> &bool {
+        &self.self_timing
+    }
+
+    /// Generate the `VERGEN_BUILD_SCRIPT_DURATION_MS` instruction.
+    pub fn self_timing_mut(&mut self) -> &mut bool {
+        &mut self.self_timing
+    }
+
+    /// Generate the `VERGEN_BUILD_SCRIPT_DURATION_MS` instruction.
+    pub fn set_self_timing(&mut self, self_timing: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "self_timing");
+        self.self_timing = self_timing;
+    }
+
+    /// Generate the `VERGEN_BUILD_NUMBER` instruction.
+    pub(crate) fn build_number(&self) -> &bool {
+        &self.build_number
+    }
+
+    /// Generate the `VERGEN_BUILD_NUMBER` instruction.
+    pub fn build_number_mut(&mut self) -> &mut bool {
+        &mut self.build_number
+    }
+
+    /// Generate the `VERGEN_BUILD_NUMBER` instruction.
+    pub fn set_build_number(&mut self, build_number: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "build_number");
+        self.build_number = build_number;
+    }
+
+    /// Generate the `VERGEN_RELEASE_URL` instruction.
+    pub(crate) fn release_url(&self) -> &bool {
+        &self.release_url
+    }
+
+    /// Generate the `VERGEN_RELEASE_URL` instruction.
+    pub fn release_url_mut(&mut self) -> &mut bool {
+        &mut self.release_url
+    }
+
+    /// Generate the `VERGEN_RELEASE_URL` instruction.
+    pub fn set_release_url(&mut self, release_url: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "release_url");
+        self.release_url = release_url;
+    }
+
+    /// The `VERGEN_RELEASE_URL` template, with `{repo}` and `{tag}` placeholders, or None for the GitHub style.
+    #[cfg(feature = "git")]
+    pub(crate) fn url_template(&self) -> &Option<String> {
+        &self.url_template
+    }
+
+    /// The `VERGEN_RELEASE_URL` template, with `{repo}` and `{tag}` placeholders, or None for the GitHub style.
+    pub fn url_template_mut(&mut self) -> &mut Option<String> {
+        &mut self.url_template
+    }
+
+    /// The `VERGEN_RELEASE_URL` template, with `{repo}` and `{tag}` placeholders, or None for the GitHub style.
+    pub fn set_url_template(&mut self, url_template: Option<String>) {
+        self.set_fields.mark(MERGED_FIELDS, "url_template");
+        self.url_template = url_template;
+    }
+
+    /// Generate the `VERGEN_ENABLED_FEATURES` instruction.
+    pub(crate) fn emit_enabled_summary(&self) -> &bool {
+        &self.emit_enabled_summary
+    }
+
+    /// Generate the `VERGEN_ENABLED_FEATURES` instruction.
+    pub fn emit_enabled_summary_mut(&mut self) -> &mut bool {
+        &mut self.emit_enabled_summary
+    }
+
+    /// Generate the `VERGEN_ENABLED_FEATURES` instruction.
+    pub fn set_emit_enabled_summary(&mut self, emit_enabled_summary: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "emit_enabled_summary");
+        self.emit_enabled_summary = emit_enabled_summary;
+    }
+
+    /// Generate the `VERGEN_PROVENANCE_JSON` instruction.
+    pub(crate) fn provenance(&self) -> &bool {
+        &self.provenance
+    }
+
+    /// Generate the `VERGEN_PROVENANCE_JSON` instruction.
+    pub fn provenance_mut(&mut self) -> &mut bool {
+        &mut self.provenance
+    }
+
+    /// Generate the `VERGEN_PROVENANCE_JSON` instruction.
+    pub fn set_provenance(&mut self, provenance: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "provenance");
+        self.provenance = provenance;
+    }
+
+    /// The warnings to emit as `cargo:warning` instructions.
+    pub(crate) fn warnings(&self) -> &WarningLevel {
+        &self.warnings
+    }
+
+    /// The warnings to emit as `cargo:warning` instructions.
+    pub fn warnings_mut(&mut self) -> &mut WarningLevel {
+        &mut self.warnings
+    }
+
+    /// The warnings to emit as `cargo:warning` instructions.
+    pub fn set_warnings(&mut self, warnings: WarningLevel) {
+        self.set_fields.mark(MERGED_FIELDS, "warnings");
+        self.warnings = warnings;
+    }
+
+    /// Don't warn about the requested instructions that were skipped.
+    pub fn quiet_mut(&mut self) -> &mut bool {
+        &mut self.quiet
+    }
+
+    /// Don't warn about the requested instructions that were skipped.
+    pub fn set_quiet(&mut self, quiet: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "quiet");
+        self.quiet = quiet;
+    }
+
+    /// Log how long each feature took to stderr.
+    pub(crate) fn verbose(&self) -> &bool {
+        &self.verbose
+    }
+
+    /// Log how long each feature took to stderr.
+    pub fn verbose_mut(&mut self) -> &mut bool {
+        &mut self.verbose
+    }
+
+    /// Log how long each feature took to stderr.
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "verbose");
+        self.verbose = verbose;
+    }
+
+    /// Write a table of the generated values to stderr.
+    pub(crate) fn print_table(&self) -> &bool {
+        &self.print_table
+    }
+
+    /// Write a table of the generated values to stderr.
+    pub fn print_table_mut(&mut self) -> &mut bool {
+        &mut self.print_table
+    }
+
+    /// Write a table of the generated values to stderr.
+    pub fn set_print_table(&mut self, print_table: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "print_table");
+        self.print_table = print_table;
+    }
+
+    /// Use this to modify the [`Sticky`] value cache configuration.
+    pub(crate) fn sticky(&self) -> &Sticky {
+        &self.sticky
+    }
+
+    /// Use this to modify the [`Sticky`] value cache configuration.
+    pub fn sticky_mut(&mut self) -> &mut Sticky {
+        &mut self.sticky
+    }
+
+    /// Use this to modify the [`EmitCfg`] rustc-cfg flag configuration.
+    pub(crate) fn emit_cfg(&self) -> &EmitCfg {
+        &self.emit_cfg
+    }
+
+    /// Use this to modify the [`EmitCfg`] rustc-cfg flag configuration.
+    pub fn emit_cfg_mut(&mut self) -> &mut EmitCfg {
+        &mut self.emit_cfg
+    }
+
+    /// The instructions whose values are passed to the dependent crates, as `DEP_<LINKS>_<NAME>` variables.
+    pub(crate) fn links_metadata(&self) -> &Vec<String> {
+        &self.links_metadata
+    }
+
+    /// The instructions whose values are passed to the dependent crates, as `DEP_<LINKS>_<NAME>` variables.
+    pub fn links_metadata_mut(&mut self) -> &mut Vec<String> {
+        &mut self.links_metadata
+    }
+
+    /// Use this to modify the [`ValueHash`] configuration.
+    #[cfg(feature = "hash")]
+    pub(crate) fn hash(&self) -> &ValueHash {
+        &self.hash
+    }
+
+    /// Use this to modify the [`ValueHash`] configuration.
+    #[cfg(feature = "hash")]
+    pub fn hash_mut(&mut self) -> &mut ValueHash {
+        &mut self.hash
+    }
+
+    /// Where the generated values are written.
+    pub(crate) fn output_target(&self) -> &OutputTarget {
+        &self.output_target
+    }
+
+    /// Where the generated values are written.
+    pub fn output_target_mut(&mut self) -> &mut OutputTarget {
+        &mut self.output_target
+    }
+
+    /// Where the generated values are written.
+    pub fn set_output_target(&mut self, output_target: OutputTarget) {
+        self.set_fields.mark(MERGED_FIELDS, "output_target");
+        self.output_target = output_target;
+    }
+
+    /// The format of the file written for the output target.
+    pub(crate) fn output_format(&self) -> &OutputFormat {
+        &self.output_format
+    }
+
+    /// The format of the file written for the output target.
+    pub fn output_format_mut(&mut self) -> &mut OutputFormat {
+        &mut self.output_format
+    }
+
+    /// The format of the file written for the output target.
+    pub fn set_output_format(&mut self, output_format: OutputFormat) {
+        self.set_fields.mark(MERGED_FIELDS, "output_format");
+        self.output_format = output_format;
+    }
+
+    /// Write the `cargo:rerun-if-changed` paths to a sidecar of the output file.
+    pub(crate) fn rerun_sidecar(&self) -> &bool {
+        &self.rerun_sidecar
+    }
+
+    /// Write the `cargo:rerun-if-changed` paths to a sidecar of the output file.
+    pub fn rerun_sidecar_mut(&mut self) -> &mut bool {
+        &mut self.rerun_sidecar
+    }
+
+    /// Write the `cargo:rerun-if-changed` paths to a sidecar of the output file.
+    pub fn set_rerun_sidecar(&mut self, rerun_sidecar: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "rerun_sidecar");
+        self.rerun_sidecar = rerun_sidecar;
+    }
+
+    /// Emit every instruction of the enabled features, using a placeholder for any value that is unavailable.
+    pub(crate) fn emit_all_with_placeholders(&self) -> &bool {
+        &self.emit_all_with_placeholders
+    }
+
+    /// Emit every instruction of the enabled features, using a placeholder for any value that is unavailable.
+    pub fn emit_all_with_placeholders_mut(&mut self) -> &mut bool {
+        &mut self.emit_all_with_placeholders
+    }
+
+    /// Emit every instruction of the enabled features, using a placeholder for any value that is unavailable.
+    pub fn set_emit_all_with_placeholders(&mut self, emit_all_with_placeholders: bool) {
+        self.set_fields
+            .mark(MERGED_FIELDS, "emit_all_with_placeholders");
+        self.emit_all_with_placeholders = emit_all_with_placeholders;
+    }
+
+    /// The placeholder value for the unavailable values.
+    #[cfg(test)]
+    pub(crate) fn unavailable_placeholder(&self) -> &String {
+        &self.unavailable_placeholder
+    }
+
+    /// The placeholder value for the unavailable values.
+    pub fn unavailable_placeholder_mut(&mut self) -> &mut String {
+        &mut self.unavailable_placeholder
+    }
+
+    /// The placeholder value for the unavailable values.
+    pub fn set_unavailable_placeholder(&mut self, unavailable_placeholder: String) {
+        self.set_fields
+            .mark(MERGED_FIELDS, "unavailable_placeholder");
+        self.unavailable_placeholder = unavailable_placeholder;
+    }
+
+    /// Use this to modify the [`Sanitize`] value sanitization configuration.
+    pub(crate) fn sanitize(&self) -> &Sanitize {
+        &self.sanitize
+    }
+
+    /// Use this to modify the [`Sanitize`] value sanitization configuration.
+    pub fn sanitize_mut(&mut self) -> &mut Sanitize {
+        &mut self.sanitize
+    }
+
+    /// Use this to modify the [`Truncate`] value length configuration.
+    pub(crate) fn truncate(&self) -> &Truncate {
+        &self.truncate
+    }
+
+    /// Use this to modify the [`Truncate`] value length configuration.
+    pub fn truncate_mut(&mut self) -> &mut Truncate {
+        &mut self.truncate
+    }
+
+    /// The syntax of the generated `cargo` instructions.
+    pub(crate) fn cargo_syntax(&self) -> &CargoSyntax {
+        &self.cargo_syntax
+    }
+
+    /// The syntax of the generated `cargo` instructions.
+    pub fn cargo_syntax_mut(&mut self) -> &mut CargoSyntax {
+        &mut self.cargo_syntax
+    }
+
+    /// The syntax of the generated `cargo` instructions.
+    pub fn set_cargo_syntax(&mut self, cargo_syntax: CargoSyntax) {
+        self.set_fields.mark(MERGED_FIELDS, "cargo_syntax");
+        self.cargo_syntax = cargo_syntax;
+    }
+
+    /// Use this to modify the [`CHeader`] C header configuration.
+    pub(crate) fn c_header(&self) -> &CHeader {
+        &self.c_header
+    }
+
+    /// Use this to modify the [`CHeader`] C header configuration.
+    pub fn c_header_mut(&mut self) -> &mut CHeader {
+        &mut self.c_header
+    }
+
+    /// Use this to modify the [`BuiltCompat`] `built.rs` configuration.
+    pub(crate) fn built_compat(&self) -> &BuiltCompat {
+        &self.built_compat
+    }
+
+    /// Use this to modify the [`BuiltCompat`] `built.rs` configuration.
+    pub fn built_compat_mut(&mut self) -> &mut BuiltCompat {
+        &mut self.built_compat
+    }
+
+    /// Use this to modify the [`BuildInfo`] `.buildinfo` configuration.
+    pub(crate) fn build_info(&self) -> &BuildInfo {
+        &self.build_info
+    }
+
+    /// Use this to modify the [`BuildInfo`] `.buildinfo` configuration.
+    pub fn build_info_mut(&mut self) -> &mut BuildInfo {
+        &mut self.build_info
+    }
+
+    /// Use this to modify the [`Licenses`] dependency license configuration.
+    #[cfg(feature = "licenses")]
+    pub(crate) fn licenses(&self) -> &Licenses {
+        &self.licenses
+    }
+
+    /// Use this to modify the [`Licenses`] dependency license configuration.
+    #[cfg(feature = "licenses")]
+    pub fn licenses_mut(&mut self) -> &mut Licenses {
+        &mut self.licenses
+    }
+
+    /// Write a `vergen.rs` with a `vergen_pretty` function to `OUT_DIR`.
+    pub(crate) fn generate_include(&self) -> &bool {
+        &self.generate_include
+    }
+
+    /// Write a `vergen.rs` with a `vergen_pretty` function to `OUT_DIR`.
+    pub fn generate_include_mut(&mut self) -> &mut bool {
+        &mut self.generate_include
+    }
+
+    /// Write a `vergen.rs` with a `vergen_pretty` function to `OUT_DIR`.
+    pub fn set_generate_include(&mut self, generate_include: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "generate_include");
+        self.generate_include = generate_include;
+    }
+
+    /// What to do when an instruction value is generated more than once.
+    pub(crate) fn on_duplicate_key(&self) -> &DuplicatePolicy {
+        &self.on_duplicate_key
+    }
+
+    /// What to do when an instruction value is generated more than once.
+    pub fn on_duplicate_key_mut(&mut self) -> &mut DuplicatePolicy {
+        &mut self.on_duplicate_key
+    }
+
+    /// What to do when an instruction value is generated more than once.
+    pub fn set_on_duplicate_key(&mut self, on_duplicate_key: DuplicatePolicy) {
+        self.set_fields.mark(MERGED_FIELDS, "on_duplicate_key");
+        self.on_duplicate_key = on_duplicate_key;
+    }
+
+    /// What to do with a path that isn't valid UTF-8.
+    #[cfg(any(feature = "git", feature = "hg"))]
+    pub(crate) fn non_utf8_paths(&self) -> &NonUtf8Policy {
+        &self.non_utf8_paths
+    }
+
+    /// What to do with a path that isn't valid UTF-8.
+    pub fn non_utf8_paths_mut(&mut self) -> &mut NonUtf8Policy {
+        &mut self.non_utf8_paths
+    }
+
+    /// What to do with a path that isn't valid UTF-8.
+    pub fn set_non_utf8_paths(&mut self, non_utf8_paths: NonUtf8Policy) {
+        self.set_fields.mark(MERGED_FIELDS, "non_utf8_paths");
+        self.non_utf8_paths = non_utf8_paths;
+    }
+
+    /// Use this to install fixed data providers for tests.
+    #[cfg(all(
+        any(test, feature = "test-util"),
+        any(feature = "build", feature = "git", feature = "rustc")
+    ))]
+    pub(crate) fn providers(&self) -> &Providers {
+        &self.providers
+    }
+
+    /// Use this to install fixed data providers for tests.
+    #[cfg(all(
+        any(test, feature = "test-util"),
+        any(feature = "build", feature = "git", feature = "rustc")
+    ))]
+    pub fn providers_mut(&mut self) -> &mut Providers {
+        &mut self.providers
+    }
+}
+
+#[cfg_attr(msrv, allow(clippy::derivable_impls))]
+impl Default for Instructions {
+    fn default() -> Self {
+        Self {
+            #[cfg(feature = "build")]
+            build: Build::default(),
+            #[cfg(feature = "cargo")]
+            cargo: Cargo::default(),
+            #[cfg(feature = "git")]
+            git: Git::default(),
+            #[cfg(feature = "rustc")]
+            rustc: Rustc::default(),
+            #[cfg(feature = "si")]
+            sysinfo: Sysinfo::default(),
+            #[cfg(feature = "hg")]
+            hg: Hg::default(),
+            vcs: Vcs::default(),
+            emit_link_section: None,
+            banner_format: None,
+            env_overrides: true,
+            overrides_file: None,
+            docs_rs_placeholder: true,
+            single_threaded: false,
+            max_duration: None,
+            source_date_epoch: false,
+            unify_clock: false,
+            self_timing: false,
+            build_number: false,
+            release_url: false,
+            url_template: None,
+            emit_enabled_summary: false,
+            provenance: false,
+            warnings: WarningLevel::All,
+            quiet: false,
+            verbose: false,
+            print_table: false,
+            sticky: Sticky::default(),
+            emit_cfg: EmitCfg::default(),
+            links_metadata: Vec::new(),
+            #[cfg(feature = "hash")]
+            hash: ValueHash::default(),
+            output_target: OutputTarget::default(),
+            output_format: OutputFormat::default(),
+            rerun_sidecar: false,
+            emit_all_with_placeholders: false,
+            unavailable_placeholder: UNAVAILABLE.to_string(),
+            sanitize: Sanitize::default(),
+            truncate: Truncate::default(),
+            cargo_syntax: CargoSyntax::default(),
+            c_header: CHeader::default(),
+            built_compat: BuiltCompat::default(),
+            build_info: BuildInfo::default(),
+            #[cfg(feature = "licenses")]
+            licenses: Licenses::default(),
+            generate_include: false,
+            on_duplicate_key: DuplicatePolicy::default(),
+            non_utf8_paths: NonUtf8Policy::default(),
+            only: Vec::new(),
+            required: Vec::new(),
+            ci_only: Vec::new(),
+            if_features: BTreeMap::new(),
+            commands: BTreeMap::new(),
+            command_keys: BTreeMap::new(),
+            packages: BTreeMap::new(),
+            value_providers: BTreeMap::new(),
+            value_overrides: BTreeMap::new(),
+            conditions: BTreeMap::new(),
+            excluded: BTreeSet::new(),
+            #[cfg(any(feature = "build", feature = "git"))]
+            reference_time: None,
+            #[cfg(all(
+                any(test, feature = "test-util"),
+                any(feature = "build", feature = "git", feature = "rustc")
+            ))]
+            providers: Providers::default(),
+            set_fields: SetFields::default(),
+        }
+    }
+}
+
+/// A summary of the configuration, for reviewing configuration changes with snapshots
+///
+/// The summary has a section for each enabled feature, in the order `build`, `cargo`, `git`, `hg`, `rustc`, and
+/// `sysinfo`, listing the instructions it emits along with their source, as [`describe_effective`](Self::describe_effective)
+/// does.  The `vergen` section lists the instructions that aren't part of a feature, i.e. `VERGEN_BANNER`.  The
+/// `settings` section lists every setting that differs from the default, by field path, i.e.
+/// `git.semver_kind = Lightweight`, in declaration order.  The `commands` section lists the added commands, and the
+/// `conditions` section, if there are any, the [emission conditions](Self::emit_when).
+///
+/// The format only changes when the configuration does, so the summary can be checked in and compared in review.
+///
+/// * Setting values longer than 64 characters, i.e. a captured `rustc -vV` output, are replaced by their length.
+/// * Only the program of an added command is shown, not its arguments.
+/// * Only the names of the value providers are shown, as the source of their instructions.
+///
+/// ```text
+/// build
+///   VERGEN_BUILD_TIMESTAMP from the time of the build
+///   VERGEN_BUILD_SEMVER from the CARGO_PKG_VERSION environment variable
+/// git
+///   VERGEN_GIT_BRANCH from the git repository
+///   VERGEN_GIT_SHA from a registered value provider
+/// settings
+///   git.semver_kind = Lightweight
+///   single_threaded = true
+/// commands
+///   BUILD_NUMBER = build-number-cli (1 argument(s))
+/// ```
+///
+/// # Example
+///
+/// ```
+/// use vergen::Config;
+///
+/// let config = Config::default();
+/// println!("{}", config);
+/// ```
+impl fmt::Display for Instructions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_summary(self, f)
+    }
+}
+
+/// The [`Instructions`] fields that win in a [merge](crate::Config::merge) once set, see [`SetFields`]
+const MERGED_FIELDS: &[&str] = &[
+    "vcs",
+    "emit_link_section",
+    "banner_format",
+    "env_overrides",
+    "overrides_file",
+    "docs_rs_placeholder",
+    "single_threaded",
+    "max_duration",
+    "source_date_epoch",
+    "unify_clock",
+    "self_timing",
+    "build_number",
+    "release_url",
+    "url_template",
+    "emit_enabled_summary",
+    "provenance",
+    "warnings",
+    "quiet",
+    "verbose",
+    "print_table",
+    "output_target",
+    "output_format",
+    "rerun_sidecar",
+    "emit_all_with_placeholders",
+    "unavailable_placeholder",
+    "cargo_syntax",
+    "generate_include",
+    "on_duplicate_key",
+    "non_utf8_paths",
+];
+
+impl Instructions {
+    /// The default configuration with only the [`Build`] feature enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anyhow::Result;
+    /// # use vergen::{vergen, Config};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// // Generate only the 'VERGEN_BUILD_*' instructions
+    /// vergen(Config::only_build())
+    /// # }
+    /// ```
+    #[cfg(feature = "build")]
+    #[must_use]
+    pub fn only_build() -> Self {
+        let mut instructions = Self::none();
+        *instructions.build.enabled_mut() = true;
+        instructions
+    }
+
+    /// The default configuration with only the [`Cargo`] feature enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anyhow::Result;
+    /// # use vergen::{vergen, Config};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// // Generate only the 'VERGEN_CARGO_*' instructions
+    /// vergen(Config::only_cargo())
+    /// # }
+    /// ```
+    #[cfg(feature = "cargo")]
+    #[must_use]
+    pub fn only_cargo() -> Self {
+        let mut instructions = Self::none();
+        *instructions.cargo.enabled_mut() = true;
+        instructions
+    }
+
+    /// The default configuration with only the [`Git`] feature enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anyhow::Result;
+    /// # use vergen::{vergen, Config};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// // Generate only the 'VERGEN_GIT_*' instructions
+    /// vergen(Config::only_git())
+    /// # }
+    /// ```
+    #[cfg(feature = "git")]
+    #[must_use]
+    pub fn only_git() -> Self {
+        let mut instructions = Self::none();
+        *instructions.git.enabled_mut() = true;
+        instructions
+    }
+
+    /// The default configuration with only the [`Rustc`] feature enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anyhow::Result;
+    /// # use vergen::{vergen, Config};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// // Generate only the 'VERGEN_RUSTC_*' instructions
+    /// vergen(Config::only_rustc())
+    /// # }
+    /// ```
+    #[cfg(feature = "rustc")]
+    #[must_use]
+    pub fn only_rustc() -> Self {
+        let mut instructions = Self::none();
+        *instructions.rustc.enabled_mut() = true;
+        instructions
+    }
+
+    /// The default configuration with only the [`Sysinfo`] feature enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anyhow::Result;
+    /// # use vergen::{vergen, Config};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// // Generate only the 'VERGEN_SYSINFO_*' instructions
+    /// vergen(Config::only_sysinfo())
+    /// # }
+    /// ```
+    #[cfg(feature = "si")]
+    #[must_use]
+    pub fn only_sysinfo() -> Self {
+        let mut instructions = Self::none();
+        *instructions.sysinfo.enabled_mut() = true;
+        instructions
+    }
+
+    /// The default configuration with only the [`Hg`] feature enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anyhow::Result;
+    /// # use vergen::{vergen, Config};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// // Generate only the 'VERGEN_HG_*' instructions
+    /// let config = Config::only_hg();
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "hg")]
+    #[must_use]
+    pub fn only_hg() -> Self {
+        let mut instructions = Self::none();
+        *instructions.hg.enabled_mut() = true;
+        instructions.vcs = Vcs::Hg;
+        instructions
+    }
+
+    /// The default configuration, adjusted for reproducible builds.
+    ///
+    /// Compared to [`Instructions::default`], this configuration:
+    ///
+    /// * Turns off the [`Sysinfo`](crate::Sysinfo) instructions, which describe the machine and the user running the build.
+    /// * Sets the `source_date_epoch` field to true, so the `SOURCE_DATE_EPOCH` environment variable is used as the build time.
+    /// * Sets the build and commit timestamp timezones to [`TimeZone::Utc`](crate::TimeZone::Utc).
+    /// * Sets the `self_timing` field to false.
+    ///
+    /// * **NOTE** - The build date/time instructions still use the current time if `SOURCE_DATE_EPOCH` is not set.
+    /// * **NOTE** - The rustc, cargo, and git instructions are unchanged, they are part of the build inputs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anyhow::Result;
+    /// # use vergen::{vergen, Config};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let config = Config::reproducible();
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn reproducible() -> Self {
+        let mut instructions = Self::default();
+        #[cfg(feature = "si")]
+        {
+            *instructions.sysinfo.enabled_mut() = false;
+        }
+        #[cfg(feature = "build")]
+        {
+            *instructions.build.timezone_mut() = TimeZone::Utc;
+        }
+        #[cfg(feature = "git")]
+        {
+            *instructions.git.commit_timestamp_timezone_mut() = TimeZone::Utc;
+        }
+        instructions.source_date_epoch = true;
+        instructions.self_timing = false;
+        instructions
+    }
+
+    /// A configuration with only the version, the commit, and the build time.
+    ///
+    /// Compared to [`Instructions::default`], this configuration only generates:
+    ///
+    /// * `VERGEN_BUILD_TIMESTAMP` and `VERGEN_BUILD_SEMVER`, with the `build` feature.
+    /// * `VERGEN_GIT_SHA`, with the `git` feature.
+    ///
+    /// The other features are turned off, change the fields afterwards to add to it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anyhow::Result;
+    /// # use vergen::{vergen, Config};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let config = Config::minimal();
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn minimal() -> Self {
+        #[allow(unused_mut)]
+        let mut instructions = Self::none();
+        #[cfg(feature = "build")]
+        {
+            // The timestamp and semver instructions are on by default
+            *instructions.build.enabled_mut() = true;
+        }
+        #[cfg(feature = "git")]
+        {
+            let git = &mut instructions.git;
+            *git.enabled_mut() = true;
+            *git.branch_mut() = false;
+            *git.commit_timestamp_mut() = false;
+            *git.semver_mut() = false;
+        }
+        instructions
+    }
+
+    /// A configuration with every instruction of the compiled-in features turned on.
+    ///
+    /// Compared to [`Instructions::default`], this configuration:
+    ///
+    /// * Sets the build and commit timestamp kinds to [`TimestampKind::All`](crate::TimestampKind::All), and turns
+    ///   on the build semver components.
+    /// * Turns on every [`Cargo`](crate::Cargo) instruction, with both the `VERGEN_CARGO_TARGET_*` and
+    ///   `VERGEN_TARGET_*` names.
+    /// * Turns on every [`Git`](crate::Git) instruction, with both SHA kinds, the subjects of up to 80 characters of
+    ///   the commits since the tag, and the `build_number` and `release_url` fields.
+    ///
+    /// * **NOTE** - The rustc, sysinfo, and hg instructions are all on by default.  The hg instructions are only
+    ///   generated when the `vcs` field is [`Vcs::Hg`].
+    /// * **NOTE** - `VERGEN_GIT_SEMVER_LIGHTWEIGHT` replaces `VERGEN_GIT_SEMVER`, so it's left off.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anyhow::Result;
+    /// # use vergen::{vergen, Config};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let config = Config::all_enabled();
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn all_enabled() -> Self {
+        #[allow(unused_mut)]
+        let mut instructions = Self::default();
+        #[cfg(feature = "build")]
+        {
+            let build = &mut instructions.build;
+            *build.kind_mut() = TimestampKind::All;
+            *build.semver_components_mut() = true;
+        }
+        #[cfg(feature = "cargo")]
+        {
+            let cargo = &mut instructions.cargo;
+            *cargo.pkg_description_mut() = true;
+            *cargo.pkg_authors_mut() = true;
+            *cargo.pkg_homepage_mut() = true;
+            *cargo.pkg_repository_mut() = true;
+            *cargo.pkg_license_mut() = true;
+            *cargo.pkg_license_file_mut() = true;
+            *cargo.target_os_mut() = true;
+            *cargo.target_arch_mut() = true;
+            *cargo.target_env_mut() = true;
+            *cargo.target_pointer_width_mut() = true;
+            *cargo.target_endian_mut() = true;
+            *cargo.libc_version_mut() = true;
+            *cargo.is_cross_compile_mut() = true;
+            *cargo.target_names_mut() = TargetNames::Both;
+        }
+        #[cfg(feature = "git")]
+        {
+            let git = &mut instructions.git;
+            *git.commit_timestamp_kind_mut() = TimestampKind::All;
+            *git.sha_kind_mut() = ShaKind::Both;
+            *git.tracked_files_mut() = true;
+            *git.commit_age_mut() = true;
+            *git.commits_since_tag_mut() = true;
+            *git.commits_since_tag_subjects_mut() = Some(80);
+            *git.signed_mut() = true;
+            *git.branch_count_mut() = true;
+            *git.tag_count_mut() = true;
+            *git.repo_root_mut() = true;
+            *git.worktree_mut() = true;
+            *git.tree_hash_mut() = true;
+            *git.contributor_count_mut() = true;
+            *git.merge_base_mut() = true;
+            *git.object_format_mut() = true;
+            *git.parent_count_mut() = true;
+            instructions.build_number = true;
+            instructions.release_url = true;
+        }
+        instructions
+    }
+
+    /// The [`all_enabled`](Self::all_enabled) configuration, adjusted for continuous integration builds.
+    ///
+    /// Compared to [`Instructions::all_enabled`], this configuration:
+    ///
+    /// * Sets the `emit_all_with_placeholders` field to true, so a value the CI checkout can't provide, i.e. the branch
+    ///   of a detached `HEAD` or the tags of a shallow clone, is the unavailable placeholder, and a feature that fails
+    ///   to gather its information generates a `cargo:warning` instead of failing the build.
+    /// * Sets the `source_date_epoch` field to true, so the `SOURCE_DATE_EPOCH` environment variable is used as the
+    ///   build time when it's set.
+    /// * Sets the `unify_clock` field to true, so every instruction that depends on the current time reads it once.
+    ///
+    /// * **NOTE** - The `VERGEN_DISABLE_*` environment variable overrides stay on, so a CI job can still turn
+    ///   instructions off.
+    /// * **NOTE** - `VERGEN_GIT_SEMVER` falls back to the commit SHA when there's no tag, as it does by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use anyhow::Result;
+    /// # use vergen::{vergen, Config};
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let config = Config::ci();
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn ci() -> Self {
+        let mut instructions = Self::all_enabled();
+        instructions.emit_all_with_placeholders = true;
+        instructions.source_date_epoch = true;
+        instructions.unify_clock = true;
+        instructions
+    }
+
+    pub(crate) fn none() -> Self {
+        #[allow(unused_mut)]
+        let mut instructions = Self::default();
+        #[cfg(feature = "build")]
+        {
+            *instructions.build.enabled_mut() = false;
+        }
+        #[cfg(feature = "cargo")]
+        {
+            *instructions.cargo.enabled_mut() = false;
+        }
+        #[cfg(feature = "git")]
+        {
+            *instructions.git.enabled_mut() = false;
+        }
+        #[cfg(feature = "rustc")]
+        {
+            *instructions.rustc.enabled_mut() = false;
+        }
+        #[cfg(feature = "si")]
+        {
+            *instructions.sysinfo.enabled_mut() = false;
+        }
+        #[cfg(feature = "hg")]
+        {
+            *instructions.hg.enabled_mut() = false;
+        }
+        instructions
+    }
+
+    /// Use the given function to compute the value of the named instruction, i.e. `VERGEN_GIT_SHA`,
+    /// instead of the built-in logic.
+    ///
+    /// * If the function returns `None`, the built-in value is used.
+    /// * The value is generated even if the feature the instruction belongs to is disabled.
+    /// * Registering another function for the same instruction replaces the previous one.
+    /// * **NOTE** - An unknown instruction name fails [validation](Self::validate).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vergen::Config;
+    ///
+    /// // Use the SHA provided by the CI server, if any
+    /// fn ci_sha() -> Option<String> {
+    ///     std::env::var("BUILD_VCS_NUMBER").ok()
+    /// }
+    ///
+    /// let mut config = Config::default();
+    /// config.value_provider("VERGEN_GIT_SHA", ci_sha);
+    /// ```
+    pub fn value_provider<T>(&mut self, name: T, provider: fn() -> Option<String>)
+    where
+        T: Into<String>,
+    {
+        let _ = self.value_providers.insert(name.into(), provider);
+    }
+
+    /// Use the given value for the named instruction, i.e. `VERGEN_GIT_SHA`, instead of computing it.
+    ///
+    /// * The override wins over everything else, including the [value providers](Self::value_provider), the
+    ///   `overrides_file` values, and the [`Sticky`] cache.  The value provider for the same instruction
+    ///   isn't called.
+    /// * The value is generated even if the feature the instruction belongs to is disabled, and counts for a
+    ///   [required](Self::require) instruction.
+    /// * The value is [sanitized](crate::Sanitize) and [truncated](crate::Truncate) like the gathered ones, and
+    ///   [`only`](Self::only) still applies.
+    /// * Overriding the same instruction again replaces the previous value.
+    /// * **NOTE** - An unknown instruction name fails [validation](Self::validate).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vergen::Config;
+    ///
+    /// // Use the SHA of the release being tagged
+    /// let mut config = Config::default();
+    /// config.override_value("VERGEN_GIT_SHA", "75b390dc6c05a6a4aa2791cc7b3934591803bc22");
+    /// ```
+    pub fn override_value<T, U>(&mut self, name: T, value: U)
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        let _ = self.value_overrides.insert(name.into(), value.into());
+    }
+
+    /// Run the program with the given arguments at build time, and emit the first line of its output, trimmed, as
+    /// the named instruction, i.e. `cargo:rustc-env=BUILD_NUMBER=42`.
+    ///
+    /// * The command is run from `CARGO_MANIFEST_DIR`, the package root.
+    /// * The command is killed if it runs for more than 10 seconds.
+    /// * If the command can't be run, fails, or is killed, a `cargo:warning` is emitted instead of the instruction.
+    /// * Adding another command with the same name replaces the previous one.
+    /// * When building on docs.rs, the command isn't run and the instruction has a placeholder value.
+    /// * **NOTE** - A name that isn't a valid environment variable name, or is the name of a `vergen` instruction in
+    ///   any case, including the `VERGEN_TARGET_*` names, fails [validation](Self::validate).
+    ///
+    /// # Security
+    /// The command runs with the full permissions of the build, on every machine that builds the crate, including
+    /// machines building it as a dependency.  Only run programs you trust, with fixed arguments.  Never build the
+    /// program or its arguments from the environment or from files outside your control, and remember that a
+    /// program found on the `PATH` can be shadowed by whoever controls the `PATH`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vergen::Config;
+    ///
+    /// let mut config = Config::default();
+    /// config.add_command("BUILD_NUMBER", "build-number-cli", &["--current"]);
+    /// ```
+    pub fn add_command<T, U>(&mut self, name: T, program: U, args: &[&str])
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        let command = CustomCommand {
+            program: program.into(),
+            args: args.iter().map(|arg| (*arg).to_string()).collect(),
+        };
+        let _ = self.commands.insert(name.into(), command);
+    }
+
+    /// Run the command at build time, and emit its output as the named instruction with a `VERGEN_CMD_` prefix, i.e.
+    /// `cargo:rustc-env=VERGEN_CMD_RELEASE_TRAIN=2021.03` for `RELEASE_TRAIN`.
+    ///
+    /// * The lines of the command's stdout are trimmed and joined with a space, as an instruction value is one line.
+    /// * The command runs from `CARGO_MANIFEST_DIR`, the package root, unless it's given its own
+    ///   [working directory](Command::current_dir).
+    /// * The command is killed if it runs for more than 10 seconds.
+    /// * If the command can't be run, fails, prints nothing, or is killed, the build fails with an error naming the
+    ///   command line.  If the `emit_all_with_placeholders` field is true, i.e. with `VERGEN_ERROR_POLICY=warn`, the
+    ///   instruction has the `unavailable_placeholder` value and a `cargo:warning` naming the command line is emitted
+    ///   instead, see [unavailable values](Self#unavailable-values).
+    /// * Adding another command with the same name replaces the previous one.
+    /// * When building on docs.rs, the command isn't run and the instruction has a placeholder value.
+    /// * **NOTE** - The command can't be read from or written to a [configuration file](Self::from_file).
+    /// * **NOTE** - The instruction can't be [required](Self::require), which only names the built-in instructions.  A
+    ///   failed command already fails the build unless the placeholder is asked for.
+    /// * **NOTE** - A name that isn't ASCII alphanumeric characters and `_`, or whose `VERGEN_CMD_` instruction is the
+    ///   name of an [added command](Self::add_command), fails [validation](Self::validate).
+    ///
+    /// # Security
+    /// The same as for [`add_command`](Self::add_command), only run programs you trust, with fixed arguments.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::process::Command;
+    /// use vergen::Config;
+    ///
+    /// let mut config = Config::default();
+    /// config.add_command_key("RELEASE_TRAIN", Command::new("./scripts/print-release-train.sh"));
+    /// ```
+    pub fn add_command_key<T>(&mut self, name: T, command: Command)
+    where
+        T: Into<String>,
+    {
+        let _ = self
+            .command_keys
+            .insert(name.into(), CommandKey::new(command));
+    }
+
+    /// Emit a set of the values for the named package of the workspace, with the package's own values in place of the
+    /// shared ones, i.e. `cargo:rustc-env=VERGEN_GIT_SHA_API=...` for the `api` package.
+    ///
+    /// * Every emitted instruction is emitted again for each package, with a `_` and the package name, uppercase with
+    ///   `-` replaced by `_`, appended to its name.
+    /// * The shared values are only gathered once.  Only the package's SHA and commit date/time/age values, for its
+    ///   [`path_scope`](crate::Package), and its semver are its own.
+    /// * A crate shared by the workspace members, i.e. `build-support`, can generate every member's values in its build
+    ///   script, and each member selects its own with the suffix.
+    /// * Adding another package with the same name replaces the previous one.
+    /// * **NOTE** - The package sets are only emitted as `cargo:` instructions, not to the
+    ///   [output file](Self::output_target) or the other generated files.
+    /// * **NOTE** - A name that isn't ASCII alphanumeric characters, `-`, and `_`, or that has the same suffix as
+    ///   another package, fails [validation](Self::validate).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vergen::{Config, Package};
+    ///
+    /// let mut config = Config::default();
+    /// for name in &["api", "cli"] {
+    ///     let mut package = Package::default();
+    ///     *package.path_scope_mut() = Some(format!("crates/{}", name).into());
+    ///     config.for_package(*name, package);
+    /// }
+    /// ```
+    pub fn for_package<T>(&mut self, name: T, package: Package)
+    where
+        T: Into<String>,
+    {
+        let _ = self.packages.insert(name.into(), package);
+    }
+
+    /// Only emit the named instructions, i.e. `VERGEN_GIT_SHA`, rather than every enabled instruction.
+    ///
+    /// * A feature without a named instruction isn't run at all, so the build doesn't pay for probing it.  The
+    ///   `sysinfo` system information isn't loaded, and the repository isn't opened, unless one of their
+    ///   instructions is named.
+    /// * An instruction that is named but disabled is still not emitted.
+    /// * The [`VERGEN_BANNER`](Self::banner_format) is built from the emitted values, so name the instructions it
+    ///   uses too.  The `VERGEN_HASH` is over the emitted values.
+    /// * The [added commands](Self::add_command) and [command keys](Self::add_command_key) are always run.
+    /// * Calling this again replaces the names.  An empty list emits every enabled instruction, as by default.
+    /// * **NOTE** - An unknown instruction name fails [validation](Self::validate).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vergen::Config;
+    ///
+    /// let mut config = Config::default();
+    /// config.only(&["VERGEN_GIT_SHA", "VERGEN_BUILD_TIMESTAMP"]);
+    /// ```
+    pub fn only(&mut self, names: &[&str]) {
+        self.only = names.iter().map(|name| (*name).to_string()).collect();
+    }
+
+    /// Only emit the instructions of the named feature, i.e. `git`, or the named instruction, i.e.
+    /// `VERGEN_GIT_SEMVER`, in the builds the condition holds for.
+    ///
+    /// * The condition is checked against the [`EmissionContext`] of the build, read from the `PROFILE`, `TARGET`,
+    ///   `HOST`, and `CI` environment variables.
+    /// * A feature whose condition doesn't hold isn't run at all, as with [`only`](Self::only), so the build doesn't pay
+    ///   for probing it.  The feature of an instruction with its own condition is still run, and only that value is
+    ///   left out.
+    /// * An instruction is only emitted if both the condition of its feature and its own condition hold.  The
+    ///   `vergen` name covers the instructions that aren't part of a feature, i.e. `VERGEN_BANNER`.
+    /// * An instruction that is left out isn't emitted, or uses the unavailable placeholder if the
+    ///   `emit_all_with_placeholders` field is true and its feature is enabled, so an `env!` that reads it still
+    ///   compiles.  No skipped warning is emitted for it, but it doesn't count for a [required](Self::require)
+    ///   instruction.
+    /// * The condition wins over the [value overrides](Self::override_value) and the `overrides_file` values.
+    /// * Setting another condition for the same name replaces the previous one.
+    /// * **NOTE** - A name that isn't `build`, `cargo`, `git`, `hg`, `rustc`, `sysinfo`, `vergen`, or the name of a
+    ///   generated instruction, fails [validation](Self::validate).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vergen::{Condition, Config};
+    ///
+    /// let mut config = Config::default();
+    /// // Only describe the commit in release builds
+    /// config.emit_when("VERGEN_GIT_SEMVER", Condition::only_profiles(&["release"]));
+    /// // Only probe the system when it's the one the binary runs on
+    /// config.emit_when("sysinfo", Condition::when(|context| !context.is_cross_compile()));
+    /// ```
+    pub fn emit_when<T>(&mut self, name: T, condition: Condition)
+    where
+        T: Into<String>,
+    {
+        let _ = self.conditions.insert(name.into(), condition);
+    }
+
+    /// Fail the build where the generated `vergen.rs` is included if any of the named instructions, i.e.
+    /// `VERGEN_GIT_SHA`, has no value.
+    ///
+    /// * The `vergen.rs` holds a `compile_error!` with the reason for each required instruction without a value, i.e.
+    ///   `vergen: VERGEN_GIT_SHA was not available: the git feature failed, ...`, in place of a binary that reports
+    ///   an empty or placeholder value.
+    /// * The [`unavailable_placeholder`](Self::unavailable_placeholder) doesn't count as a value for a required
+    ///   instruction.  The other instructions keep their placeholders.
+    /// * Calling this again replaces the names.
+    /// * **NOTE** - An unknown instruction name, a name not also named by [`only`](Self::only), or a required
+    ///   instruction without the `generate_include` field set, fails [validation](Self::validate).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vergen::Config;
+    ///
+    /// let mut config = Config::default();
+    /// *config.generate_include_mut() = true;
+    /// config.require(&["VERGEN_GIT_SHA"]);
+    /// ```
+    pub fn require(&mut self, names: &[&str]) {
+        self.required = names.iter().map(|name| (*name).to_string()).collect();
+    }
+
+    /// Only emit the named instructions, i.e. `VERGEN_GIT_SEMVER`, when the build runs in CI, so local builds stay lean.
+    ///
+    /// * The build runs in CI if the `CI` environment variable is set to anything but an empty value, `0`, `false`,
+    ///   `no`, or `off`, the same as the [`is_ci`](EmissionContext::is_ci) of a [condition](Self::emit_when).  GitHub
+    ///   Actions, GitLab CI/CD, CircleCI, Travis CI, Bitbucket Pipelines, and Buildkite set it to `true`.  Jenkins and
+    ///   Azure Pipelines don't, so set `CI=true` in their build environment.
+    /// * Outside of CI, an instruction named here is left out as if its [condition](Self::emit_when) didn't hold.  It
+    ///   isn't emitted, or uses the unavailable placeholder if the `emit_all_with_placeholders` field is true and its
+    ///   feature is enabled, and no skipped warning is emitted for it.
+    /// * Calling this again replaces the names.
+    /// * **NOTE** - An unknown instruction name fails [validation](Self::validate).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vergen::Config;
+    ///
+    /// let mut config = Config::default();
+    /// config.ci_only(&["VERGEN_GIT_SEMVER", "VERGEN_BUILD_TIMESTAMP"]);
+    /// ```
+    pub fn ci_only(&mut self, names: &[&str]) {
+        self.ci_only = names.iter().map(|name| (*name).to_string()).collect();
+    }
+
+    /// Only emit the named instruction, i.e. `VERGEN_GIT_SHA`, when the given feature of the package being built, i.e.
+    /// `telemetry`, is enabled.
+    ///
+    /// * The feature is enabled if cargo set its `CARGO_FEATURE_<NAME>` environment variable for the build script.
+    ///   The `<NAME>` is the feature name uppercased with each `-` replaced by `_`, so the `fast-hash` feature is
+    ///   checked with `CARGO_FEATURE_FAST_HASH`, the same as the [`has_feature`](EmissionContext::has_feature) of a
+    ///   [condition](Self::emit_when).
+    /// * Without the feature, the instruction is left out as if its [condition](Self::emit_when) didn't hold.  It
+    ///   isn't emitted, or uses the unavailable placeholder if the `emit_all_with_placeholders` field is true and its
+    ///   feature is enabled, and no skipped warning is emitted for it.
+    /// * Setting another feature for the same instruction replaces the previous one.
+    /// * **NOTE** - An unknown instruction name, or a feature name with characters cargo doesn't allow, fails
+    ///   [validation](Self::validate).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vergen::Config;
+    ///
+    /// let mut config = Config::default();
+    /// // Only emit the commit SHA when the `telemetry` feature is enabled, i.e. `cargo build --features telemetry`
+    /// config.if_feature("telemetry", "VERGEN_GIT_SHA");
+    /// ```
+    pub fn if_feature(&mut self, feature: &str, name: &str) {
+        let _ = self
+            .if_features
+            .insert(name.to_string(), feature.to_string());
+    }
+
+    /// The required instructions without a value, with the reason each is missing, see [`require`](Self::require)
+    pub(crate) fn unavailable_required(&self, config: &Config) -> Vec<(&'static str, String)> {
+        let requested = self.placeholder_config();
+        self.required
+            .iter()
+            .filter_map(|name| find_key(name))
+            .filter(|key| !self.has_value(config.cfg_map[key].as_ref()))
+            .filter_map(|key| Some((key.name(), self.skip_reason(key, config, &requested)?)))
+            .collect()
+    }
+
+    /// The subsystems that generated at least one value, sorted and comma separated, i.e. `build,git,rustc`
+    pub(crate) fn enabled_summary(&self, config: &Config) -> String {
+        let subsystems: BTreeSet<&str> = config
+            .cfg_map
+            .iter()
+            .filter(|(key, value)| key.subsystem() != "vergen" && self.has_value(value.as_ref()))
+            .map(|(key, _)| key.subsystem())
+            .collect();
+        subsystems.into_iter().collect::<Vec<&str>>().join(",")
+    }
+
+    /// Is the value generated, rather than missing or the unavailable placeholder
+    fn has_value(&self, value: Option<&String>) -> bool {
+        value.map_or(false, |value| {
+            !self.emit_all_with_placeholders || *value != self.unavailable_placeholder
+        })
+    }
+
+    /// Is the instruction for the given key emitted, see [`only`](Self::only)
+    pub(crate) fn listed(&self, key: VergenKey) -> bool {
+        self.only.is_empty() || self.only.iter().any(|name| name == key.name())
+    }
+
+    /// Is the instruction for the given key left out of this build, see [`emit_when`](Self::emit_when)
+    pub(crate) fn excluded(&self, key: VergenKey) -> bool {
+        self.excluded.contains(key.subsystem()) || self.excluded.contains(key.name())
+    }
+
+    /// Is the instruction for the given key emitted, and not left out of this build
+    pub(crate) fn emits(&self, key: VergenKey) -> bool {
+        self.listed(key) && !self.excluded(key)
+    }
+
+    /// Check the conditions against the build, see [`emit_when`](Self::emit_when), [`ci_only`](Self::ci_only), and
+    /// [`if_feature`](Self::if_feature)
+    pub(crate) fn apply_conditions(&mut self, context: &EmissionContext) {
+        self.excluded = self
+            .conditions
+            .iter()
+            .filter(|(_, condition)| !condition.holds(context))
+            .map(|(name, _)| name.clone())
+            .collect();
+        if !*context.is_ci() {
+            self.excluded.extend(self.ci_only.iter().cloned());
+        }
+        self.excluded.extend(
+            self.if_features
+                .iter()
+                .filter(|(_, feature)| !context.has_feature(feature))
+                .map(|(name, _)| name.clone()),
+        );
+    }
+
+    /// Read the current time every instruction uses, if the `unify_clock` field is true
+    #[cfg(any(feature = "build", feature = "git"))]
+    pub(crate) fn unify_time(&mut self) {
+        self.reference_time = None;
+        if self.unify_clock {
+            self.reference_time = Some(now_utc(self));
+        }
+    }
+
+    #[cfg(not(any(feature = "build", feature = "git")))]
+    pub(crate) fn unify_time(&mut self) {}
+
+    /// The current time every instruction uses, see [`unify_time`](Self::unify_time)
+    #[cfg(any(feature = "build", feature = "git"))]
+    pub(crate) fn reference_time(&self) -> Option<OffsetDateTime> {
+        self.reference_time
+    }
+
+    /// Drop the values of the instructions left out of this build, see [`emit_when`](Self::emit_when)
+    pub(crate) fn drop_excluded(&self, config: &mut Config) {
+        for (key, value) in config.cfg_map_mut().iter_mut() {
+            if self.excluded(*key) {
+                *value = None;
+            }
+        }
+    }
+
+    /// Does any emitted instruction come from the named gatherer, see [`only`](Self::only)
+    fn gathers(&self, gatherer: &str) -> bool {
+        if self.excluded.contains(gatherer) {
+            return false;
+        }
+        self.only.is_empty()
+            || self
+                .only
+                .iter()
+                .filter_map(|name| find_key(name))
+                .any(|key| match key {
+                    // The build number is counted, and the release tag read, from the repository
+                    VergenKey::BuildNumber | VergenKey::ReleaseUrl => gatherer == "git",
+                    key => key.subsystem() == gatherer,
+                })
+    }
+
+    /// Drop the values of the instructions that aren't emitted, see [`only`](Self::only)
+    pub(crate) fn retain_listed(&self, config: &mut Config) {
+        for (key, value) in config.cfg_map_mut().iter_mut() {
+            if !self.listed(*key) {
+                *value = None;
+            }
+        }
+    }
+
+    /// Layer the overlay configuration on top of this one, i.e. a per-project configuration on top of a
+    /// company-wide base configuration.
+    ///
+    /// * Each field the overlay changed from its default wins.  A field the overlay set with its `set_*` setter, i.e.
+    ///   [`set_single_threaded`](Self::set_single_threaded), wins even if it was set to its default value, so an
+    ///   overlay can turn a field back on that `self` turned off.  Every other field keeps the value from `self`.
+    /// * Reading a field through its `_mut` accessor doesn't count as setting it.
+    /// * The instruction name lists, i.e. the [`Sticky`] `keys`, are merged as a union.  A list the overlay left at its
+    ///   default keeps the value from `self`.
+    /// * The [value providers](Self::value_provider) and the [`EmitCfg`] `names` are merged, the overlay wins for
+    ///   an instruction in both.
+    /// * Merge more than two configurations by chaining the calls, the last overlay wins.
+    ///
+    /// * **NOTE** - A configuration deserialized with the `serde` feature has no record of which fields were in the
+    ///   input, so for an overlay read that way, only the fields that differ from their default win.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vergen::Config;
+    ///
+    /// let mut base = Config::default();
+    /// *base.single_threaded_mut() = true;
+    /// *base.self_timing_mut() = true;
+    ///
+    /// let mut project = Config::default();
+    /// *project.quiet_mut() = true;
+    /// // Back to the default, which wins as it was set with the setter
+    /// project.set_single_threaded(false);
+    ///
+    /// let mut config = base.merge(project);
+    /// assert!(!*config.single_threaded_mut());
+    /// assert!(*config.self_timing_mut());
+    /// assert!(*config.quiet_mut());
+    /// ```
+    #[must_use]
+    #[allow(clippy::too_many_lines)]
+    pub fn merge(self, overlay: Instructions) -> Instructions {
+        let default = Self::default();
+        let overlay_set = overlay.set_fields;
+        let set = |name| overlay_set.contains(MERGED_FIELDS, name);
+        Self {
+            #[cfg(feature = "build")]
+            build: self.build.merge(overlay.build),
+            #[cfg(feature = "cargo")]
+            cargo: self.cargo.merge(overlay.cargo),
+            #[cfg(feature = "git")]
+            git: self.git.merge(overlay.git),
+            #[cfg(feature = "rustc")]
+            rustc: self.rustc.merge(overlay.rustc),
+            #[cfg(feature = "si")]
+            sysinfo: self.sysinfo.merge(overlay.sysinfo),
+            #[cfg(feature = "hg")]
+            hg: self.hg.merge(overlay.hg),
+            vcs: pick(self.vcs, overlay.vcs, &default.vcs, set("vcs")),
+            emit_link_section: pick(
+                self.emit_link_section,
+                overlay.emit_link_section,
+                &default.emit_link_section,
+                set("emit_link_section"),
+            ),
+            banner_format: pick(
+                self.banner_format,
+                overlay.banner_format,
+                &default.banner_format,
+                set("banner_format"),
+            ),
+            env_overrides: pick(
+                self.env_overrides,
+                overlay.env_overrides,
+                &default.env_overrides,
+                set("env_overrides"),
+            ),
+            overrides_file: pick(
+                self.overrides_file,
+                overlay.overrides_file,
+                &default.overrides_file,
+                set("overrides_file"),
+            ),
+            docs_rs_placeholder: pick(
+                self.docs_rs_placeholder,
+                overlay.docs_rs_placeholder,
+                &default.docs_rs_placeholder,
+                set("docs_rs_placeholder"),
+            ),
+            single_threaded: pick(
+                self.single_threaded,
+                overlay.single_threaded,
+                &default.single_threaded,
+                set("single_threaded"),
+            ),
+            max_duration: pick(
+                self.max_duration,
+                overlay.max_duration,
+                &default.max_duration,
+                set("max_duration"),
+            ),
+            source_date_epoch: pick(
+                self.source_date_epoch,
+                overlay.source_date_epoch,
+                &default.source_date_epoch,
+                set("source_date_epoch"),
+            ),
+            unify_clock: pick(
+                self.unify_clock,
+                overlay.unify_clock,
+                &default.unify_clock,
+                set("unify_clock"),
+            ),
+            self_timing: pick(
+                self.self_timing,
+                overlay.self_timing,
+                &default.self_timing,
+                set("self_timing"),
+            ),
+            build_number: pick(
+                self.build_number,
+                overlay.build_number,
+                &default.build_number,
+                set("build_number"),
+            ),
+            release_url: pick(
+                self.release_url,
+                overlay.release_url,
+                &default.release_url,
+                set("release_url"),
+            ),
+            url_template: pick(
+                self.url_template,
+                overlay.url_template,
+                &default.url_template,
+                set("url_template"),
+            ),
+            emit_enabled_summary: pick(
+                self.emit_enabled_summary,
+                overlay.emit_enabled_summary,
+                &default.emit_enabled_summary,
+                set("emit_enabled_summary"),
+            ),
+            provenance: pick(
+                self.provenance,
+                overlay.provenance,
+                &default.provenance,
+                set("provenance"),
+            ),
+            warnings: pick(
+                self.warnings,
+                overlay.warnings,
+                &default.warnings,
+                set("warnings"),
+            ),
+            quiet: pick(self.quiet, overlay.quiet, &default.quiet, set("quiet")),
+            verbose: pick(
+                self.verbose,
+                overlay.verbose,
+                &default.verbose,
+                set("verbose"),
+            ),
+            print_table: pick(
+                self.print_table,
+                overlay.print_table,
+                &default.print_table,
+                set("print_table"),
+            ),
+            sticky: self.sticky.merge(overlay.sticky),
+            emit_cfg: self.emit_cfg.merge(overlay.emit_cfg),
+            links_metadata: union(
+                self.links_metadata,
+                overlay.links_metadata,
+                &default.links_metadata,
+            ),
+            #[cfg(feature = "hash")]
+            hash: self.hash.merge(overlay.hash),
+            output_target: pick(
+                self.output_target,
+                overlay.output_target,
+                &default.output_target,
+                set("output_target"),
+            ),
+            output_format: pick(
+                self.output_format,
+                overlay.output_format,
+                &default.output_format,
+                set("output_format"),
+            ),
+            rerun_sidecar: pick(
+                self.rerun_sidecar,
+                overlay.rerun_sidecar,
+                &default.rerun_sidecar,
+                set("rerun_sidecar"),
+            ),
+            emit_all_with_placeholders: pick(
+                self.emit_all_with_placeholders,
+                overlay.emit_all_with_placeholders,
+                &default.emit_all_with_placeholders,
+                set("emit_all_with_placeholders"),
+            ),
+            unavailable_placeholder: pick(
+                self.unavailable_placeholder,
+                overlay.unavailable_placeholder,
+                &default.unavailable_placeholder,
+                set("unavailable_placeholder"),
+            ),
+            sanitize: self.sanitize.merge(overlay.sanitize),
+            truncate: self.truncate.merge(overlay.truncate),
+            cargo_syntax: pick(
+                self.cargo_syntax,
+                overlay.cargo_syntax,
+                &default.cargo_syntax,
+                set("cargo_syntax"),
+            ),
+            c_header: self.c_header.merge(overlay.c_header),
+            built_compat: self.built_compat.merge(overlay.built_compat),
+            build_info: self.build_info.merge(overlay.build_info),
+            #[cfg(feature = "licenses")]
+            licenses: self.licenses.merge(overlay.licenses),
+            generate_include: pick(
+                self.generate_include,
+                overlay.generate_include,
+                &default.generate_include,
+                set("generate_include"),
+            ),
+            on_duplicate_key: pick(
+                self.on_duplicate_key,
+                overlay.on_duplicate_key,
+                &default.on_duplicate_key,
+                set("on_duplicate_key"),
+            ),
+            non_utf8_paths: pick(
+                self.non_utf8_paths,
+                overlay.non_utf8_paths,
+                &default.non_utf8_paths,
+                set("non_utf8_paths"),
+            ),
+            only: union(self.only, overlay.only, &default.only),
+            required: union(self.required, overlay.required, &default.required),
+            ci_only: union(self.ci_only, overlay.ci_only, &default.ci_only),
+            if_features: union_map(self.if_features, overlay.if_features),
+            commands: union_map(self.commands, overlay.commands),
+            command_keys: union_map(self.command_keys, overlay.command_keys),
+            packages: union_map(self.packages, overlay.packages),
+            value_providers: union_map(self.value_providers, overlay.value_providers),
+            value_overrides: union_map(self.value_overrides, overlay.value_overrides),
+            conditions: union_map(self.conditions, overlay.conditions),
+            excluded: self.excluded.union(&overlay.excluded).cloned().collect(),
+            #[cfg(any(feature = "build", feature = "git"))]
+            reference_time: overlay.reference_time.or(self.reference_time),
+            #[cfg(all(
+                any(test, feature = "test-util"),
+                any(feature = "build", feature = "git", feature = "rustc")
+            ))]
+            providers: self.providers.merge(overlay.providers),
+            set_fields: self.set_fields.union(overlay_set),
+        }
+    }
+
+    /// Describe the instructions this configuration generates, and where each value comes from.
+    ///
+    /// Each entry is the instruction name, i.e. `VERGEN_GIT_SHA`, and a short description of the source
+    /// of its value, i.e. `the git repository`.  The entries are in the same order as the output.
+    ///
+    /// * Nothing is gathered, so this is cheap and can't fail.
+    /// * **NOTE** - The `VERGEN_DISABLE_*` environment variable [overrides](Self::env_overrides) aren't applied.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vergen::Config;
+    ///
+    /// for (name, source) in Config::default().describe_effective() {
+    ///     println!("{} from {}", name, source);
+    /// }
+    /// ```
+    #[must_use]
+    pub fn describe_effective(&self) -> Vec<(&'static str, &'static str)> {
+        let mut config = self.placeholder_config();
+        if self.banner_format.is_some() {
+            let _ = config
+                .cfg_map_mut()
+                .insert(VergenKey::Banner, Some(String::new()));
+        }
+        #[cfg(feature = "hash")]
+        if *self.hash.enabled() {
+            let _ = config
+                .cfg_map_mut()
+                .insert(VergenKey::Hash, Some(String::new()));
+        }
+        if self.self_timing {
+            let _ = config
+                .cfg_map_mut()
+                .insert(VergenKey::ScriptDuration, Some(String::new()));
+        }
+        if self.build_number {
+            let _ = config
+                .cfg_map_mut()
+                .insert(VergenKey::BuildNumber, Some(String::new()));
+        }
+        if self.release_url {
+            let _ = config
+                .cfg_map_mut()
+                .insert(VergenKey::ReleaseUrl, Some(String::new()));
+        }
+        if self.emit_enabled_summary {
+            let _ = config
+                .cfg_map_mut()
+                .insert(VergenKey::EnabledFeatures, Some(String::new()));
+        }
+        if self.provenance {
+            let _ = config
+                .cfg_map_mut()
+                .insert(VergenKey::ProvenanceJson, Some(String::new()));
+        }
+        #[cfg(feature = "licenses")]
+        if *self.licenses.enabled() {
+            let _ = config
+                .cfg_map_mut()
+                .insert(VergenKey::DependencyLicenses, Some(String::new()));
+        }
+
+        config
+            .cfg_map()
+            .iter()
+            .filter(|(key, _)| self.listed(**key))
+            .filter_map(|(key, value)| {
+                if self.value_overrides.contains_key(key.name()) {
+                    Some((key.name(), "a value override"))
+                } else if self.value_providers.contains_key(key.name()) {
+                    Some((key.name(), "a registered value provider"))
+                } else if value.is_some() {
+                    Some((key.name(), key.source()))
+                } else if self.emit_all_with_placeholders && self.feature_enabled(*key) {
+                    Some((key.name(), "the unavailable placeholder"))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Check the configuration for problems before any instructions are generated.
+    ///
+    /// This is called automatically by [`vergen`](crate::vergen), but can be called
+    /// directly to inspect every problem at once.  Disabled features are not checked.
+    ///
+    /// # Errors
+    ///
+    /// A [`ConfigError`] is returned for each problem found, naming the offending field,
+    /// the invalid value, and a suggestion for fixing it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vergen::Config;
+    ///
+    /// let mut config = Config::default();
+    /// *config.emit_link_section_mut() = Some(".ver gen".to_string());
+    ///
+    /// let errors = config.validate().unwrap_err();
+    /// assert_eq!(errors.len(), 1);
+    /// assert_eq!(errors[0].field(), "emit_link_section");
+    /// ```
+    #[allow(clippy::too_many_lines)]
+    pub fn validate(&self) -> std::result::Result<(), Vec<ConfigError>> {
+        let mut errors = vec![];
+
+        #[cfg(feature = "build")]
+        self.build.validate(&mut errors);
+        #[cfg(feature = "git")]
+        self.git.validate(&mut errors);
+        #[cfg(feature = "rustc")]
+        self.rustc.validate(&mut errors);
+        self.sticky.validate(&mut errors);
+        self.emit_cfg.validate(&mut errors);
+        #[cfg(feature = "hash")]
+        self.hash.validate(&mut errors);
+        self.truncate.validate(&mut errors);
+        self.output_target
+            .validate(self.rerun_sidecar, self.output_format, &mut errors);
+        self.c_header.validate(&mut errors);
+        self.built_compat.validate(&mut errors);
+        self.build_info.validate(&mut errors);
+        #[cfg(feature = "licenses")]
+        self.licenses.validate(&mut errors);
+
+        if let Some(section) = &self.emit_link_section {
+            if section.is_empty()
+                || !section
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_')
+            {
+                errors.push(ConfigError::new(
+                    "emit_link_section",
+                    format!("{:?}", section),
+                    "use only ASCII alphanumeric characters, '.', and '_', i.e. '.vergen'",
+                ));
+            }
+        }
+
+        if let Some(format) = &self.banner_format {
+            validate_banner(format, &mut errors);
+        }
+
+        if let Some(template) = &self.url_template {
+            if self.release_url {
+                validate_url_template(template, &mut errors);
+            } else {
+                errors.push(ConfigError::new(
+                    "url_template",
+                    format!("Some({:?})", template),
+                    "set 'release_url' to true, or set 'url_template' to None",
+                ));
+            }
+        }
+
+        if self
+            .overrides_file
+            .as_ref()
+            .map_or(false, |path| path.as_os_str().is_empty())
+        {
+            errors.push(ConfigError::new(
+                "overrides_file",
+                "Some(\"\")",
+                "use the path of a file of 'KEY=value' lines, or None for no overrides",
+            ));
+        }
+
+        if self.max_duration == Some(Duration::from_secs(0)) {
+            errors.push(ConfigError::new(
+                "max_duration",
+                "Some(0s)",
+                "use a duration greater than zero, or None to wait for every feature",
+            ));
+        }
+
+        for name in self.commands.keys().filter(|name| {
+            name.is_empty()
+                || is_key_name(name)
+                || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }) {
+            errors.push(ConfigError::new(
+                "commands",
+                format!("{:?}", name),
+                "use ASCII alphanumeric characters and '_' only, and not the name of a vergen instruction in any case",
+            ));
+        }
+
+        for name in self.command_keys.keys().filter(|name| {
+            name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }) {
+            errors.push(ConfigError::new(
+                "command_keys",
+                format!("{:?}", name),
+                "use ASCII alphanumeric characters and '_' only",
+            ));
+        }
+
+        for name in self.command_keys.keys().filter(|name| {
+            let key = format!("{}{}", COMMAND_KEY_PREFIX, name);
+            self.commands
+                .keys()
+                .any(|command| command.eq_ignore_ascii_case(&key))
+        }) {
+            errors.push(ConfigError::new(
+                "command_keys",
+                format!("{:?}", name),
+                "use a name whose 'VERGEN_CMD_' instruction isn't also the name of an added command",
+            ));
+        }
+
+        for name in self.links_metadata.iter().filter(|name| {
+            find_key(name).is_none()
+                && !self.commands.contains_key(*name)
+                && !name
+                    .strip_prefix(COMMAND_KEY_PREFIX)
+                    .map_or(false, |name| self.command_keys.contains_key(name))
+        }) {
+            errors.push(ConfigError::new(
+                "links_metadata",
+                format!("{:?}", name),
+                "use the name of a generated instruction, i.e. 'VERGEN_GIT_SHA', or of a command",
+            ));
+        }
+
+        for name in self.only.iter().filter(|name| find_key(name).is_none()) {
+            errors.push(ConfigError::new(
+                "only",
+                format!("{:?}", name),
+                "use the name of a generated instruction, i.e. 'VERGEN_GIT_SHA'",
+            ));
+        }
+
+        for name in self.ci_only.iter().filter(|name| find_key(name).is_none()) {
+            errors.push(ConfigError::new(
+                "ci_only",
+                format!("{:?}", name),
+                "use the name of a generated instruction, i.e. 'VERGEN_GIT_SHA'",
+            ));
+        }
+
+        for (name, feature) in &self.if_features {
+            if find_key(name).is_none() {
+                errors.push(ConfigError::new(
+                    "if_features",
+                    format!("{:?}", name),
+                    "use the name of a generated instruction, i.e. 'VERGEN_GIT_SHA'",
+                ));
+            }
+            if feature.is_empty()
+                || !feature.chars().all(|c| {
+                    c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '+' || c == '.'
+                })
+            {
+                errors.push(ConfigError::new(
+                    "if_features",
+                    format!("{:?}", feature),
+                    "use the name of a cargo feature, i.e. 'telemetry'",
+                ));
+            }
+        }
+
+        for name in &self.required {
+            let reason = if find_key(name).is_none() {
+                "use the name of a generated instruction, i.e. 'VERGEN_GIT_SHA'"
+            } else if !self.generate_include {
+                "set 'generate_include' too, the check is in the generated 'vergen.rs'"
+            } else if !self.only.is_empty() && !self.only.contains(name) {
+                "name it in 'only' too, or it's never emitted"
+            } else {
+                continue;
+            };
+            errors.push(ConfigError::new("required", format!("{:?}", name), reason));
+        }
+
+        validate_names("value_providers", self.value_providers.keys(), &mut errors);
+        validate_names("value_overrides", self.value_overrides.keys(), &mut errors);
+        condition::validate(&self.conditions, &mut errors);
+
+        package::validate(&self.packages, &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub(crate) fn config<T>(&self, repo_path: Option<T>) -> Result<Config>
+    where
+        T: AsRef<Path>,
+    {
+        let repo_path = repo_path.map(|path| path.as_ref().to_path_buf());
+        let mut config = Config::with_duplicate_policy(self.on_duplicate_key);
+
+        let deadline = self.max_duration.map(|max| Instant::now() + max);
+        let gathered = if self.single_threaded {
+            GATHERERS
+                .iter()
+                .map(|(name, gather)| self.gather(name, *gather, repo_path.as_deref(), deadline))
+                .collect()
+        } else {
+            self.gather_parallel(repo_path.as_deref(), deadline)
+        };
+
+        let unfinished: Vec<&str> = GATHERERS
+            .iter()
+            .zip(&gathered)
+            .filter(|(_, gathered)| gathered.is_none())
+            .map(|((name, _), _)| *name)
+            .collect();
+        if let (false, Some(max)) = (unfinished.is_empty(), self.max_duration) {
+            config.warnings.push(format!(
+                "vergen: the {} feature(s) didn't finish within {:?}",
+                unfinished.join(", "),
+                max
+            ));
+            for name in &unfinished {
+                self.skip_feature(
+                    &mut config,
+                    name,
+                    &format!("the {} feature didn't finish within {:?}", name, max),
+                );
+            }
+        }
+
+        // The finished features are merged in feature order
+        for ((name, _), gathered) in GATHERERS.iter().zip(gathered) {
+            if let Some((elapsed, gathered)) = gathered {
+                if self.gathers(name) {
+                    config.timings.push((name, elapsed));
+                }
+                self.merge_gathered(&mut config, name, gathered)?;
+            }
+        }
+
+        if config.duplicates.is_empty() {
+            Ok(config)
+        } else {
+            let names: Vec<&str> = config.duplicates.iter().map(|key| key.name()).collect();
+            Err(Error::DuplicateKey(names.join(", ")).into())
+        }
+    }
+
+    /// Gather a feature, or None if it didn't finish by the deadline, with the time it took
+    ///
+    /// A feature isn't started once the deadline has passed, and one that's running stops at its next step.
+    fn gather(
+        &self,
+        name: &str,
+        gather: Gather,
+        repo_path: Option<&Path>,
+        deadline: Option<Instant>,
+    ) -> Option<(Duration, Result<Config>)> {
+        let mut partial = Config::with_duplicate_policy(self.on_duplicate_key);
+        if !self.gathers(name) {
+            return Some((Duration::default(), Ok(partial)));
+        }
+        let start = Instant::now();
+        let past = || deadline.map_or(false, |deadline| Instant::now() >= deadline);
+        if past() {
+            return None;
+        }
+        let gathered = gather(self, repo_path, deadline, &mut partial).map(|()| partial);
+        if past() {
+            None
+        } else {
+            Some((start.elapsed(), gathered))
+        }
+    }
+
+    /// Gather each feature on a worker thread, and wait for every worker to finish
+    ///
+    /// The workers stop at the deadline, so none of them outlives the call.  A worker's panic is passed on once they all
+    /// have finished.
+    fn gather_parallel(
+        &self,
+        repo_path: Option<&Path>,
+        deadline: Option<Instant>,
+    ) -> Vec<Option<(Duration, Result<Config>)>> {
+        let instructions = Arc::new(self.clone());
+        let workers: Vec<JoinHandle<_>> = GATHERERS
+            .iter()
+            .map(|&(name, gather)| {
+                let instructions = Arc::clone(&instructions);
+                let repo_path = repo_path.map(Path::to_path_buf);
+                thread::spawn(move || {
+                    instructions.gather(name, gather, repo_path.as_deref(), deadline)
+                })
+            })
+            .collect();
+        let joined: Vec<thread::Result<_>> = workers.into_iter().map(JoinHandle::join).collect();
+        joined
+            .into_iter()
+            .map(|gathered| gathered.unwrap_or_else(|panic| panic::resume_unwind(panic)))
+            .collect()
+    }
+
+    /// Merge a feature's gathered information, or turn its error into a warning if every instruction is emitted anyway
+    fn merge_gathered(
+        &self,
+        config: &mut Config,
+        name: &str,
+        gathered: Result<Config>,
+    ) -> Result<()> {
+        match gathered {
+            Ok(partial) => config.merge(partial),
+            Err(e) if self.emit_all_with_placeholders => {
+                self.skip_feature(config, name, &format!("the {} feature failed, {}", name, e));
+                config
+                    .warnings
+                    .push(format!("vergen: using placeholders, {}", e));
+            }
+            Err(e) => return Err(e),
+        }
+        Ok(())
+    }
+
+    /// Record why the requested instructions of a feature have no value
+    fn skip_feature(&self, config: &mut Config, name: &str, reason: &str) {
+        let requested = self.placeholder_config();
+        for (key, _) in requested
+            .cfg_map
+            .iter()
+            .filter(|(key, value)| value.is_some() && key.subsystem() == name)
+        {
+            config.skip(*key, reason);
+        }
+    }
+
+    /// Whether the feature the key belongs to is enabled
+    pub(crate) fn feature_enabled(&self, key: VergenKey) -> bool {
+        let features: &[(&str, bool)] = &[
+            #[cfg(feature = "build")]
+            ("VERGEN_BUILD_", *self.build.enabled()),
+            #[cfg(feature = "cargo")]
+            ("VERGEN_CARGO_", *self.cargo.enabled()),
+            #[cfg(feature = "cargo")]
+            ("VERGEN_TARGET_", *self.cargo.enabled()),
+            #[cfg(feature = "cargo")]
+            ("VERGEN_IS_CROSS_COMPILE", *self.cargo.enabled()),
+            #[cfg(feature = "git")]
+            ("VERGEN_GIT_", *self.git.enabled() && self.vcs == Vcs::Git),
+            #[cfg(feature = "hg")]
+            ("VERGEN_HG_", *self.hg.enabled() && self.vcs == Vcs::Hg),
+            #[cfg(feature = "rustc")]
+            ("VERGEN_RUSTC_", *self.rustc.enabled()),
+            #[cfg(feature = "si")]
+            ("VERGEN_SYSINFO_", *self.sysinfo.enabled()),
+        ];
+        // The script duration and build number aren't part of the build feature
+        key != VergenKey::ScriptDuration
+            && key != VergenKey::BuildNumber
+            && features
+                .iter()
+                .any(|(prefix, enabled)| *enabled && key.name().starts_with(prefix))
+    }
+
+    /// Use the placeholder for every unavailable value of the enabled features
+    pub(crate) fn fill_unavailable(&self, config: &mut Config) {
+        for (key, value) in config.cfg_map_mut().iter_mut() {
+            if value.is_none() && self.feature_enabled(*key) {
+                *value = Some(self.unavailable_placeholder.clone());
+            }
+        }
+    }
+
+    /// Run the added commands, and warn about any that fail
+    ///
+    /// A failed command key fails the build, or uses the placeholder if the `emit_all_with_placeholders` field is true.
+    pub(crate) fn run_commands(&self, config: &mut Config) -> Result<()> {
+        for (name, command) in &self.commands {
+            match command.run() {
+                Ok(value) => {
+                    let _ = config.commands.insert(name.clone(), value);
+                }
+                Err(e) => config
+                    .warnings
+                    .push(format!("vergen: skipping '{}', {}", name, e)),
+            }
+        }
+        for (name, command) in &self.command_keys {
+            let name = format!("{}{}", COMMAND_KEY_PREFIX, name);
+            let value = match command.run() {
+                Ok(value) => value,
+                Err(reason) if self.emit_all_with_placeholders => {
+                    config.warnings.push(format!(
+                        "vergen: using the placeholder for '{}', {}",
+                        name, reason
+                    ));
+                    self.unavailable_placeholder.clone()
+                }
+                Err(reason) => return Err(Error::Command { name, reason }.into()),
+            };
+            let _ = config.commands.insert(name, value);
+        }
+        Ok(())
+    }
+
+    /// The added commands, by instruction name
+    pub(crate) fn commands(&self) -> &BTreeMap<String, CustomCommand> {
+        &self.commands
+    }
+
+    /// The commands whose output is emitted as a `VERGEN_CMD_` instruction, by the name after the prefix
+    pub(crate) fn command_keys(&self) -> &BTreeMap<String, CommandKey> {
+        &self.command_keys
+    }
+
+    pub(crate) fn packages(&self) -> &BTreeMap<String, Package> {
+        &self.packages
+    }
+
+    /// The emission conditions, by feature or instruction name
+    pub(crate) fn conditions(&self) -> &BTreeMap<String, Condition> {
+        &self.conditions
+    }
+
+    /// The names of the enabled features, in gatherer order
+    pub(crate) fn enabled_features(&self) -> Vec<&'static str> {
+        let features: &[(&str, bool)] = &[
+            #[cfg(feature = "build")]
+            ("build", *self.build.enabled()),
+            #[cfg(feature = "cargo")]
+            ("cargo", *self.cargo.enabled()),
+            #[cfg(feature = "git")]
+            ("git", *self.git.enabled() && self.vcs == Vcs::Git),
+            #[cfg(feature = "hg")]
+            ("hg", *self.hg.enabled() && self.vcs == Vcs::Hg),
+            #[cfg(feature = "rustc")]
+            ("rustc", *self.rustc.enabled()),
+            #[cfg(feature = "si")]
+            ("sysinfo", *self.sysinfo.enabled()),
+        ];
+        features
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    /// Use the placeholder for the added commands, without running them
+    pub(crate) fn placeholder_commands(&self, config: &mut Config) {
+        for name in self.commands.keys() {
+            let _ = config
+                .commands
+                .insert(name.clone(), PLACEHOLDER.to_string());
+        }
+        for name in self.command_keys.keys() {
+            let _ = config.commands.insert(
+                format!("{}{}", COMMAND_KEY_PREFIX, name),
+                PLACEHOLDER.to_string(),
+            );
+        }
+    }
+
+    /// Replace the built-in values with the values from the registered providers
+    ///
+    /// The providers of overridden instructions aren't called.
+    pub(crate) fn apply_value_providers(&self, config: &mut Config) {
+        for (name, provider) in &self.value_providers {
+            if self.value_overrides.contains_key(name) {
+                continue;
+            }
+            if let (Some(key), Some(value)) = (find_key(name), provider()) {
+                let _ = config.cfg_map_mut().insert(key, Some(trim_value(value)));
+            }
+        }
+    }
+
+    /// Replace the values with the overrides, after every other source
+    pub(crate) fn apply_value_overrides(&self, config: &mut Config) {
+        for (name, value) in &self.value_overrides {
+            if let Some(key) = find_key(name) {
+                let _ = config
+                    .cfg_map_mut()
+                    .insert(key, Some(trim_value(value.clone())));
+            }
+        }
+    }
+
+    /// Warn about each requested instruction without a value, unless the `quiet` field is true
+    ///
+    /// An instruction is requested if its feature and toggle are on, if it's named by `only`, or if a feature
+    /// recorded why it was skipped.
+    pub(crate) fn warn_skipped(&self, config: &mut Config) {
+        if self.quiet {
+            return;
+        }
+        let requested = self.placeholder_config();
+        let mut warnings = vec![];
+        for (key, _) in config.cfg_map.iter().filter(|(_, value)| value.is_none()) {
+            if let (true, Some(reason)) =
+                (self.emits(*key), self.skip_reason(*key, config, &requested))
+            {
+                warnings.push(format!("vergen: skipped {} because {}", key.name(), reason));
+            }
+        }
+        config.warnings.extend(warnings);
+    }
+
+    /// Why an instruction has no value, or None if it wasn't asked for
+    fn skip_reason(&self, key: VergenKey, config: &Config, requested: &Config) -> Option<String> {
+        let named = |names: &[String]| names.iter().any(|name| name == key.name());
+        if self.excluded(key) {
+            return Some("its condition doesn't hold for this build".to_string());
+        }
+        match config.skipped.get(&key) {
+            Some(reason) => Some(reason.clone()),
+            None if requested.cfg_map[&key].is_some() => Some("no value was generated".to_string()),
+            None if named(&self.only) || named(&self.required) => Some(self.disabled_reason(key)),
+            None => None,
+        }
+    }
+
+    /// Why an instruction named by `only` isn't generated
+    fn disabled_reason(&self, key: VergenKey) -> String {
+        let subsystem = key.subsystem();
+        if subsystem == "vergen" {
+            "it's turned off".to_string()
+        } else if self.enabled_features().contains(&subsystem) {
+            format!("it's turned off in the {} configuration", subsystem)
+        } else {
+            format!("the {} feature is disabled", subsystem)
+        }
+    }
+
+    pub(crate) fn placeholder_config(&self) -> Config {
+        let mut config = Config::default();
+
+        placeholder_build(self, &mut config);
+        placeholder_git(self, &mut config);
+        placeholder_hg(self, &mut config);
+        placeholder_rustc(self, &mut config);
+        placeholder_cargo(self, &mut config);
+        placeholder_sysinfo(self, &mut config);
+
+        config
+    }
+}
+
+/// Add an error for each name that isn't the name of a generated instruction
+fn validate_names<'a, I>(field: &str, names: I, errors: &mut Vec<ConfigError>)
+where
+    I: Iterator<Item = &'a String>,
+{
+    for name in names.filter(|name| find_key(name).is_none()) {
+        errors.push(ConfigError::new(
+            field,
+            format!("{:?}", name),
+            "use the name of a generated instruction, i.e. 'VERGEN_GIT_SHA'",
+        ));
+    }
+}
+
+/// The version control system to read the commit information from
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Vcs {
+    /// Generate the `VERGEN_GIT_*` instructions, see [`Git`](crate::Git)
+    Git,
+    /// Generate the `VERGEN_HG_*` instructions, see [`Hg`](crate::Hg)
+    #[cfg(feature = "hg")]
+    Hg,
+}
+
+#[cfg_attr(msrv, allow(clippy::derivable_impls))]
+impl Default for Vcs {
+    fn default() -> Self {
+        Self::Git
+    }
+}
+
+/// The warnings `vergen` emits as `cargo:warning` instructions
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum WarningLevel {
+    /// Emit every warning
+    All,
+    /// Only emit warnings about mistakes in the configuration, i.e. an unknown override name
+    Errors,
+    /// Don't emit any warnings
+    None,
+}
+
+impl WarningLevel {
+    pub(crate) fn allows(self, is_error: bool) -> bool {
+        match self {
+            WarningLevel::All => true,
+            WarningLevel::Errors => is_error,
+            WarningLevel::None => false,
+        }
+    }
+}
+
+/// What to do when an instruction value is generated more than once
+///
+/// * [`Overwrite`](Self::Overwrite) emits the later value.
+/// * [`KeepFirst`](Self::KeepFirst) emits the first value, and ignores the later ones.
+/// * [`Error`](Self::Error) fails the build, naming the instructions that were generated more than once.
+///
+/// The same policy applies to emitted instruction names that are the same ignoring case, see
+/// [duplicate values](crate::Config#duplicate-values).
+///
+/// * **NOTE** - The features are merged in `build`, `git`, `hg`, `rustc`, `cargo`, `sysinfo` order, even when they are
+///   gathered in parallel, so the first and later values are the same on every run.
+///
+/// # Example
+///
+/// ```
+/// use vergen::{Config, DuplicatePolicy};
+///
+/// let mut config = Config::default();
+/// *config.on_duplicate_key_mut() = DuplicatePolicy::Error;
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum DuplicatePolicy {
+    /// Replace the value with the later one
+    Overwrite,
+    /// Fail the build
+    Error,
+    /// Keep the first value
+    KeepFirst,
+}
+
+#[cfg_attr(msrv, allow(clippy::derivable_impls))]
+impl Default for DuplicatePolicy {
+    fn default() -> Self {
+        Self::Overwrite
+    }
+}
+
+/// What to do with a path that isn't valid UTF-8 when it's emitted
+///
+/// * [`Lossy`](Self::Lossy) emits the path with the invalid bytes replaced by `U+FFFD`.  A `cargo:rerun-if-changed`
+///   path that doesn't exist makes `cargo` re-run the build script every time.
+/// * [`Skip`](Self::Skip) doesn't emit the path.  `VERGEN_GIT_ROOT` is emitted empty, and `cargo` won't re-run the
+///   build script when the skipped path changes.
+///
+/// Both emit a `cargo:warning` naming the path.
+///
+/// # Example
+///
+/// ```
+/// use vergen::{Config, NonUtf8Policy};
+///
+/// let mut config = Config::default();
+/// *config.non_utf8_paths_mut() = NonUtf8Policy::Skip;
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum NonUtf8Policy {
+    /// Emit the path with replacement characters
+    Lossy,
+    /// Don't emit the path
+    Skip,
+}
+
+#[cfg_attr(msrv, allow(clippy::derivable_impls))]
+impl Default for NonUtf8Policy {
+    fn default() -> Self {
+        Self::Lossy
+    }
+}
+
+type Gather = fn(&Instructions, Option<&Path>, Option<Instant>, &mut Config) -> Result<()>;
+
+/// The feature names and gatherers, in the order their errors are reported.
+const GATHERERS: [(&str, Gather); 6] = [
+    ("build", |instructions, repo_path, deadline, config| {
+        configure_build(instructions, repo_path, deadline, config);
+        Ok(())
+    }),
+    ("git", |instructions, repo_path, deadline, config| {
+        configure_git(instructions, repo_path, deadline, config)
+    }),
+    ("hg", |instructions, repo_path, deadline, config| {
+        configure_hg(instructions, repo_path, deadline, config)
+    }),
+    ("rustc", |instructions, _, _, config| {
+        configure_rustc(instructions, config)
+    }),
+    ("cargo", |instructions, _, _, config| {
+        configure_cargo(instructions, config);
+        Ok(())
+    }),
+    ("sysinfo", |instructions, _, _, config| {
+        configure_sysinfo(instructions, config)
+    }),
+];
+
+/// Build information keys.
+#[derive(Clone, Copy, Debug, Hash, Eq, Ord, PartialEq, PartialOrd)]
+pub(crate) enum VergenKey {
+    /// The build date. (VERGEN_BUILD_DATE)
+    BuildDate,
+    /// The build time. (VERGEN_BUILD_TIME)
+    BuildTime,
+    /// The build timestamp. (VERGEN_BUILD_TIMESTAMP)
+    BuildTimestamp,
+    /// The build semver. (VERGEN_BUILD_SEMVER)
+    BuildSemver,
+    /// The major version of the build semver. (VERGEN_BUILD_SEMVER_MAJOR)
+    BuildSemverMajor,
+    /// The minor version of the build semver. (VERGEN_BUILD_SEMVER_MINOR)
+    BuildSemverMinor,
+    /// The patch version of the build semver. (VERGEN_BUILD_SEMVER_PATCH)
+    BuildSemverPatch,
+    /// The pre-release version of the build semver. (VERGEN_BUILD_SEMVER_PRE)
+    BuildSemverPre,
+    /// The number of logical CPUs of the build machine. (VERGEN_BUILD_CPU_COUNT)
+    BuildCpuCount,
+    /// The current working branch name (VERGEN_GIT_BRANCH)
+    Branch,
+    /// The commit date. (VERGEN_GIT_COMMIT_DATE)
+    CommitDate,
+    /// The commit time. (VERGEN_GIT_COMMIT_TIME)
+    CommitTime,
+    /// The commit timestamp. (VERGEN_GIT_COMMIT_TIMESTAMP)
+    CommitTimestamp,
+    /// The semver version from the last git tag. (VERGEN_GIT_SEMVER)
+    Semver,
+    /// The semver version from the last git tag, including lightweight.
+    /// (VERGEN_GIT_SEMVER_LIGHTWEIGHT)
+    SemverLightweight,
+    /// The latest commit SHA. (VERGEN_GIT_SHA)
+    Sha,
+    /// The latest commit short SHA. (VERGEN_GIT_SHA_SHORT)
+    ShortSha,
+    /// The number of files tracked in the index. (VERGEN_GIT_TRACKED_FILES)
+    TrackedFiles,
+    /// The number of seconds between the commit and the build. (VERGEN_GIT_COMMIT_AGE_SECONDS)
+    CommitAgeSeconds,
+    /// The number of commits since the last tag. (VERGEN_GIT_COMMITS_SINCE_TAG)
+    CommitsSinceTag,
+    /// The subjects of the commits since the last tag. (VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS)
+    CommitsSinceTagSubjects,
+    /// Whether the latest commit has a good signature. (VERGEN_GIT_SIGNED)
+    Signed,
+    /// The key id of the latest commit signature. (VERGEN_GIT_SIGNER)
+    Signer,
+    /// The number of local and remote branches. (VERGEN_GIT_BRANCH_COUNT)
+    BranchCount,
+    /// The number of tags. (VERGEN_GIT_TAG_COUNT)
+    TagCount,
+    /// The absolute path of the repository root. (VERGEN_GIT_ROOT)
+    GitRoot,
+    /// The absolute path of the linked worktree. (VERGEN_GIT_WORKTREE)
+    GitWorktree,
+    /// The hash of the tree of the latest commit. (VERGEN_GIT_TREE_HASH)
+    TreeHash,
+    /// The number of distinct commit authors. (VERGEN_GIT_CONTRIBUTOR_COUNT)
+    ContributorCount,
+    /// The commit where `HEAD` diverged from the base ref. (VERGEN_GIT_MERGE_BASE)
+    MergeBase,
+    /// The object format of the repository, `sha1` or `sha256`. (VERGEN_GIT_OBJECT_FORMAT)
+    ObjectFormat,
+    /// The number of parents of the `HEAD` commit, 2 or more for a merge. (VERGEN_GIT_PARENT_COUNT)
+    ParentCount,
+    /// The current mercurial branch name (VERGEN_HG_BRANCH)
+    HgBranch,
+    /// The local mercurial revision number (VERGEN_HG_REV)
+    HgRev,
+    /// The mercurial changeset id (VERGEN_HG_SHA)
+    HgSha,
+    /// The release channel of the rust compiler. (VERGEN_RUSTC_CHANNEL)
+    RustcChannel,
+    /// The rustc commit date. (VERGEN_RUSTC_COMMIT_DATE)
+    RustcCommitDate,
+    /// The rustc commit hash. (VERGEN_RUSTC_COMMIT_HASH)
+    RustcCommitHash,
+    /// The host triple. (VERGEN_HOST_TRIPLE)
+    RustcHostTriple,
+    /// The rustc LLVM version. (VERGEN_RUSTC_LLVM_VERSION)
+    RustcLlvmVersion,
+    /// The version information of the rust compiler. (VERGEN_RUSTC_SEMVER)
+    RustcSemver,
+    /// The cargo target triple (VERGEN_CARGO_TARGET_TRIPLE)
+    CargoTargetTriple,
+    /// The cargo profile (VERGEN_CARGO_PROFILE)
+    CargoProfile,
+    /// The cargo features (VERGEN_CARGO_FEATURES)
+    CargoFeatures,
+    /// The package description (VERGEN_CARGO_PKG_DESCRIPTION)
+    CargoPkgDescription,
+    /// The package authors (VERGEN_CARGO_PKG_AUTHORS)
+    CargoPkgAuthors,
+    /// The package homepage (VERGEN_CARGO_PKG_HOMEPAGE)
+    CargoPkgHomepage,
+    /// The package repository (VERGEN_CARGO_PKG_REPOSITORY)
+    CargoPkgRepository,
+    /// The package license (VERGEN_CARGO_PKG_LICENSE)
+    CargoPkgLicense,
+    /// The package license file (VERGEN_CARGO_PKG_LICENSE_FILE)
+    CargoPkgLicenseFile,
+    /// The cargo target OS (VERGEN_CARGO_TARGET_OS)
+    CargoTargetOs,
+    /// The cargo target architecture (VERGEN_CARGO_TARGET_ARCH)
+    CargoTargetArch,
+    /// The cargo target environment (VERGEN_CARGO_TARGET_ENV)
+    CargoTargetEnv,
+    /// The cargo target pointer width (VERGEN_CARGO_TARGET_POINTER_WIDTH)
+    CargoTargetPointerWidth,
+    /// The cargo target endianness (VERGEN_CARGO_TARGET_ENDIAN)
+    CargoTargetEndian,
+    /// The libc of the host, when building for it (VERGEN_CARGO_LIBC_VERSION)
+    CargoLibcVersion,
+    /// The target triple (VERGEN_TARGET_TRIPLE)
+    TargetTriple,
+    /// The target OS (VERGEN_TARGET_OS)
+    TargetOs,
+    /// The target architecture (VERGEN_TARGET_ARCH)
+    TargetArch,
+    /// The target environment (VERGEN_TARGET_ENV)
+    TargetEnv,
+    /// Whether the host and target triples differ (VERGEN_IS_CROSS_COMPILE)
+    IsCrossCompile,
+    /// The sysinfo system name (VERGEN_SYSINFO_NAME)
+    SysinfoName,
+    /// The sysinfo os version (VERGEN_SYSINFO_OS_VERSION)
+    SysinfoOsVersion,
+    /// The sysinfo user name (VERGEN_SYSINFO_USER)
+    SysinfoUser,
+    /// The sysinfo total memory (VERGEN_SYSINFO_TOTAL_MEMORY)
+    SysinfoMemory,
+    /// The sysinfo cpu vendor (VERGEN_SYSINFO_CPU_VENDOR)
+    SysinfoCpuVendor,
+    /// The sysinfo cpu core count (VERGEN_SYSINFO_CPU_CORE_COUNT)
+    SysinfoCpuCoreCount,
+    /// The sysinfo cpu core count (VERGEN_SYSINFO_CPU_NAME)
+    SysinfoCpuName,
+    /// The sysinfo cpu core count (VERGEN_SYSINFO_CPU_BRAND)
+    SysinfoCpuBrand,
+    /// The sysinfo cpu core count (VERGEN_SYSINFO_CPU_FREQUENCY)
+    SysinfoCpuFrequency,
+    /// The one-line version banner (VERGEN_BANNER)
+    Banner,
+    /// The hash over every other generated value (VERGEN_HASH)
+    Hash,
+    /// The time vergen took to generate the values (VERGEN_BUILD_SCRIPT_DURATION_MS)
+    ScriptDuration,
+    /// The commit count, with the number of changed files if dirty (VERGEN_BUILD_NUMBER)
+    BuildNumber,
+    /// The path of the dependency license file (VERGEN_DEPENDENCY_LICENSES)
+    DependencyLicenses,
+    /// The subsystems that generated a value (VERGEN_ENABLED_FEATURES)
+    EnabledFeatures,
+    /// The build provenance document (VERGEN_PROVENANCE_JSON)
+    ProvenanceJson,
+    /// The release page of the tag on HEAD (VERGEN_RELEASE_URL)
+    ReleaseUrl,
+}
+
+impl VergenKey {
+    /// Every key, in declaration order
+    pub(crate) const ALL: [VergenKey; 78] = [
+        VergenKey::BuildDate,
+        VergenKey::BuildTime,
+        VergenKey::BuildTimestamp,
+        VergenKey::BuildSemver,
+        VergenKey::BuildSemverMajor,
+        VergenKey::BuildSemverMinor,
+        VergenKey::BuildSemverPatch,
+        VergenKey::BuildSemverPre,
+        VergenKey::BuildCpuCount,
+        VergenKey::Branch,
+        VergenKey::CommitDate,
+        VergenKey::CommitTime,
+        VergenKey::CommitTimestamp,
+        VergenKey::Semver,
+        VergenKey::SemverLightweight,
+        VergenKey::Sha,
+        VergenKey::ShortSha,
+        VergenKey::TrackedFiles,
+        VergenKey::CommitAgeSeconds,
+        VergenKey::CommitsSinceTag,
+        VergenKey::CommitsSinceTagSubjects,
+        VergenKey::Signed,
+        VergenKey::Signer,
+        VergenKey::BranchCount,
+        VergenKey::TagCount,
+        VergenKey::GitRoot,
+        VergenKey::GitWorktree,
+        VergenKey::TreeHash,
+        VergenKey::ContributorCount,
+        VergenKey::MergeBase,
+        VergenKey::ObjectFormat,
+        VergenKey::ParentCount,
+        VergenKey::HgBranch,
+        VergenKey::HgRev,
+        VergenKey::HgSha,
+        VergenKey::RustcChannel,
+        VergenKey::RustcCommitDate,
+        VergenKey::RustcCommitHash,
+        VergenKey::RustcHostTriple,
+        VergenKey::RustcLlvmVersion,
+        VergenKey::RustcSemver,
+        VergenKey::CargoTargetTriple,
+        VergenKey::CargoProfile,
+        VergenKey::CargoFeatures,
+        VergenKey::CargoPkgDescription,
+        VergenKey::CargoPkgAuthors,
+        VergenKey::CargoPkgHomepage,
+        VergenKey::CargoPkgRepository,
+        VergenKey::CargoPkgLicense,
+        VergenKey::CargoPkgLicenseFile,
+        VergenKey::CargoTargetOs,
+        VergenKey::CargoTargetArch,
+        VergenKey::CargoTargetEnv,
+        VergenKey::CargoTargetPointerWidth,
+        VergenKey::CargoTargetEndian,
+        VergenKey::CargoLibcVersion,
+        VergenKey::TargetTriple,
+        VergenKey::TargetOs,
+        VergenKey::TargetArch,
+        VergenKey::TargetEnv,
+        VergenKey::IsCrossCompile,
+        VergenKey::SysinfoName,
+        VergenKey::SysinfoOsVersion,
+        VergenKey::SysinfoUser,
+        VergenKey::SysinfoMemory,
+        VergenKey::SysinfoCpuVendor,
+        VergenKey::SysinfoCpuCoreCount,
+        VergenKey::SysinfoCpuName,
+        VergenKey::SysinfoCpuBrand,
+        VergenKey::SysinfoCpuFrequency,
+        VergenKey::Banner,
+        VergenKey::Hash,
+        VergenKey::ScriptDuration,
+        VergenKey::BuildNumber,
+        VergenKey::DependencyLicenses,
+        VergenKey::EnabledFeatures,
+        VergenKey::ProvenanceJson,
+        VergenKey::ReleaseUrl,
+    ];
+
+    /// Get the name for the given key.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            VergenKey::BuildDate => BUILD_DATE_NAME,
+            VergenKey::BuildTime => BUILD_TIME_NAME,
+            VergenKey::BuildTimestamp => BUILD_TIMESTAMP_NAME,
+            VergenKey::BuildSemver => BUILD_SEMVER_NAME,
+            VergenKey::BuildSemverMajor => BUILD_SEMVER_MAJOR_NAME,
+            VergenKey::BuildSemverMinor => BUILD_SEMVER_MINOR_NAME,
+            VergenKey::BuildSemverPatch => BUILD_SEMVER_PATCH_NAME,
+            VergenKey::BuildSemverPre => BUILD_SEMVER_PRE_NAME,
+            VergenKey::BuildCpuCount => BUILD_CPU_COUNT_NAME,
+            VergenKey::Branch => GIT_BRANCH_NAME,
+            VergenKey::CommitDate => GIT_COMMIT_DATE_NAME,
+            VergenKey::CommitTime => GIT_COMMIT_TIME_NAME,
+            VergenKey::CommitTimestamp => GIT_COMMIT_TIMESTAMP_NAME,
+            VergenKey::Semver => GIT_SEMVER_NAME,
+            VergenKey::SemverLightweight => GIT_SEMVER_TAGS_NAME,
+            VergenKey::Sha => GIT_SHA_NAME,
+            VergenKey::ShortSha => GIT_SHA_SHORT_NAME,
+            VergenKey::TrackedFiles => GIT_TRACKED_FILES_NAME,
+            VergenKey::CommitAgeSeconds => GIT_COMMIT_AGE_SECONDS_NAME,
+            VergenKey::CommitsSinceTag => GIT_COMMITS_SINCE_TAG_NAME,
+            VergenKey::CommitsSinceTagSubjects => GIT_COMMITS_SINCE_TAG_SUBJECTS_NAME,
+            VergenKey::Signed => GIT_SIGNED_NAME,
+            VergenKey::Signer => GIT_SIGNER_NAME,
+            VergenKey::BranchCount => GIT_BRANCH_COUNT_NAME,
+            VergenKey::TagCount => GIT_TAG_COUNT_NAME,
+            VergenKey::GitRoot => GIT_ROOT_NAME,
+            VergenKey::GitWorktree => GIT_WORKTREE_NAME,
+            VergenKey::TreeHash => GIT_TREE_HASH_NAME,
+            VergenKey::ContributorCount => GIT_CONTRIBUTOR_COUNT_NAME,
+            VergenKey::MergeBase => GIT_MERGE_BASE_NAME,
+            VergenKey::ObjectFormat => GIT_OBJECT_FORMAT_NAME,
+            VergenKey::ParentCount => GIT_PARENT_COUNT_NAME,
+            VergenKey::HgBranch => HG_BRANCH_NAME,
+            VergenKey::HgRev => HG_REV_NAME,
+            VergenKey::HgSha => HG_SHA_NAME,
+            VergenKey::RustcChannel => RUSTC_CHANNEL_NAME,
+            VergenKey::RustcCommitDate => RUSTC_COMMIT_DATE,
+            VergenKey::RustcCommitHash => RUSTC_COMMIT_HASH,
+            VergenKey::RustcHostTriple => RUSTC_HOST_TRIPLE_NAME,
+            VergenKey::RustcLlvmVersion => RUSTC_LLVM_VERSION,
+            VergenKey::RustcSemver => RUSTC_SEMVER_NAME,
+            VergenKey::CargoTargetTriple => CARGO_TARGET_TRIPLE,
+            VergenKey::CargoProfile => CARGO_PROFILE,
+            VergenKey::CargoFeatures => CARGO_FEATURES,
+            VergenKey::CargoPkgDescription => CARGO_PKG_DESCRIPTION,
+            VergenKey::CargoPkgAuthors => CARGO_PKG_AUTHORS,
+            VergenKey::CargoPkgHomepage => CARGO_PKG_HOMEPAGE,
+            VergenKey::CargoPkgRepository => CARGO_PKG_REPOSITORY,
+            VergenKey::CargoPkgLicense => CARGO_PKG_LICENSE,
+            VergenKey::CargoPkgLicenseFile => CARGO_PKG_LICENSE_FILE,
+            VergenKey::CargoTargetOs => CARGO_TARGET_OS,
+            VergenKey::CargoTargetArch => CARGO_TARGET_ARCH,
+            VergenKey::CargoTargetEnv => CARGO_TARGET_ENV,
+            VergenKey::CargoTargetPointerWidth => CARGO_TARGET_POINTER_WIDTH,
+            VergenKey::CargoTargetEndian => CARGO_TARGET_ENDIAN,
+            VergenKey::CargoLibcVersion => CARGO_LIBC_VERSION,
+            VergenKey::TargetTriple => TARGET_TRIPLE_NAME,
+            VergenKey::TargetOs => TARGET_OS_NAME,
+            VergenKey::TargetArch => TARGET_ARCH_NAME,
+            VergenKey::TargetEnv => TARGET_ENV_NAME,
+            VergenKey::IsCrossCompile => IS_CROSS_COMPILE_NAME,
+            VergenKey::SysinfoName => SYSINFO_NAME,
+            VergenKey::SysinfoOsVersion => SYSINFO_OS_VERSION,
+            VergenKey::SysinfoUser => SYSINFO_USER,
+            VergenKey::SysinfoMemory => SYSINFO_MEMORY,
+            VergenKey::SysinfoCpuVendor => SYSINFO_CPU_VENDOR,
+            VergenKey::SysinfoCpuCoreCount => SYSINFO_CPU_CORE_COUNT,
+            VergenKey::SysinfoCpuName => SYSINFO_CPU_NAME,
+            VergenKey::SysinfoCpuBrand => SYSINFO_CPU_BRAND,
+            VergenKey::SysinfoCpuFrequency => SYSINFO_CPU_FREQUENCY,
+            VergenKey::Banner => BANNER_NAME,
+            VergenKey::Hash => HASH_NAME,
+            VergenKey::ScriptDuration => SCRIPT_DURATION_NAME,
+            VergenKey::BuildNumber => BUILD_NUMBER_NAME,
+            VergenKey::DependencyLicenses => DEPENDENCY_LICENSES_NAME,
+            VergenKey::EnabledFeatures => ENABLED_FEATURES_NAME,
+            VergenKey::ProvenanceJson => PROVENANCE_JSON_NAME,
+            VergenKey::ReleaseUrl => RELEASE_URL_NAME,
+        }
+    }
+
+    /// A short description of where the value for the given key comes from.
+    pub(crate) fn source(self) -> &'static str {
+        match self {
+            VergenKey::BuildDate | VergenKey::BuildTime | VergenKey::BuildTimestamp => {
+                "the time of the build"
+            }
+            VergenKey::BuildSemver => "the CARGO_PKG_VERSION environment variable",
+            VergenKey::BuildSemverMajor
+            | VergenKey::BuildSemverMinor
+            | VergenKey::BuildSemverPatch
+            | VergenKey::BuildSemverPre => {
+                "the CARGO_PKG_VERSION_* environment variables, or CARGO_PKG_VERSION"
+            }
+            VergenKey::BuildCpuCount => "the parallelism the standard library reports",
+            VergenKey::Branch
+            | VergenKey::CommitDate
+            | VergenKey::CommitTime
+            | VergenKey::CommitTimestamp
+            | VergenKey::Semver
+            | VergenKey::SemverLightweight
+            | VergenKey::Sha
+            | VergenKey::ShortSha
+            | VergenKey::TrackedFiles
+            | VergenKey::CommitAgeSeconds
+            | VergenKey::CommitsSinceTag
+            | VergenKey::CommitsSinceTagSubjects
+            | VergenKey::Signed
+            | VergenKey::Signer
+            | VergenKey::BranchCount
+            | VergenKey::TagCount
+            | VergenKey::GitRoot
+            | VergenKey::GitWorktree
+            | VergenKey::TreeHash
+            | VergenKey::ContributorCount
+            | VergenKey::MergeBase
+            | VergenKey::ObjectFormat
+            | VergenKey::ParentCount => "the git repository",
+            VergenKey::HgBranch | VergenKey::HgRev | VergenKey::HgSha => "the mercurial repository",
+            VergenKey::RustcChannel
+            | VergenKey::RustcCommitDate
+            | VergenKey::RustcCommitHash
+            | VergenKey::RustcHostTriple
+            | VergenKey::RustcLlvmVersion
+            | VergenKey::RustcSemver => "the rustc version metadata",
+            VergenKey::CargoTargetTriple
+            | VergenKey::CargoProfile
+            | VergenKey::CargoFeatures
+            | VergenKey::CargoPkgDescription
+            | VergenKey::CargoPkgAuthors
+            | VergenKey::CargoPkgHomepage
+            | VergenKey::CargoPkgRepository
+            | VergenKey::CargoPkgLicense
+            | VergenKey::CargoPkgLicenseFile
+            | VergenKey::CargoTargetOs
+            | VergenKey::CargoTargetArch
+            | VergenKey::CargoTargetEnv
+            | VergenKey::CargoTargetPointerWidth
+            | VergenKey::CargoTargetEndian
+            | VergenKey::TargetTriple
+            | VergenKey::TargetOs
+            | VergenKey::TargetArch
+            | VergenKey::TargetEnv
+            | VergenKey::IsCrossCompile => "the cargo build script environment",
+            VergenKey::CargoLibcVersion => "the libc of the host",
+            VergenKey::SysinfoName
+            | VergenKey::SysinfoOsVersion
+            | VergenKey::SysinfoUser
+            | VergenKey::SysinfoMemory
+            | VergenKey::SysinfoCpuVendor
+            | VergenKey::SysinfoCpuCoreCount
+            | VergenKey::SysinfoCpuName
+            | VergenKey::SysinfoCpuBrand
+            | VergenKey::SysinfoCpuFrequency => "the build machine",
+            VergenKey::Banner => "the banner format",
+            VergenKey::Hash => "a hash over the other values",
+            VergenKey::ScriptDuration => "the time vergen took to run",
+            VergenKey::BuildNumber => "the git commit count and working tree",
+            VergenKey::DependencyLicenses => "the cargo metadata of the dependencies",
+            VergenKey::EnabledFeatures => "the subsystems that generated a value",
+            VergenKey::ProvenanceJson => "the rustc, git, build, and Cargo.lock values",
+            VergenKey::ReleaseUrl => "CARGO_PKG_REPOSITORY and the git tag on HEAD",
+        }
+    }
+
+    /// The subsystem that generates the value for the given key, or `vergen` for the values that aren't part of a feature.
+    pub(crate) fn subsystem(self) -> &'static str {
+        match self {
+            VergenKey::BuildDate
+            | VergenKey::BuildTime
+            | VergenKey::BuildTimestamp
+            | VergenKey::BuildSemver
+            | VergenKey::BuildSemverMajor
+            | VergenKey::BuildSemverMinor
+            | VergenKey::BuildSemverPatch
+            | VergenKey::BuildSemverPre
+            | VergenKey::BuildCpuCount => "build",
+            VergenKey::Branch
+            | VergenKey::CommitDate
+            | VergenKey::CommitTime
+            | VergenKey::CommitTimestamp
+            | VergenKey::Semver
+            | VergenKey::SemverLightweight
+            | VergenKey::Sha
+            | VergenKey::ShortSha
+            | VergenKey::TrackedFiles
+            | VergenKey::CommitAgeSeconds
+            | VergenKey::CommitsSinceTag
+            | VergenKey::CommitsSinceTagSubjects
+            | VergenKey::Signed
+            | VergenKey::Signer
+            | VergenKey::BranchCount
+            | VergenKey::TagCount
+            | VergenKey::GitRoot
+            | VergenKey::GitWorktree
+            | VergenKey::TreeHash
+            | VergenKey::ContributorCount
+            | VergenKey::MergeBase
+            | VergenKey::ObjectFormat
+            | VergenKey::ParentCount => "git",
+            VergenKey::HgBranch | VergenKey::HgRev | VergenKey::HgSha => "hg",
+            VergenKey::RustcChannel
+            | VergenKey::RustcCommitDate
+            | VergenKey::RustcCommitHash
+            | VergenKey::RustcHostTriple
+            | VergenKey::RustcLlvmVersion
+            | VergenKey::RustcSemver => "rustc",
+            VergenKey::CargoTargetTriple
+            | VergenKey::CargoProfile
+            | VergenKey::CargoFeatures
+            | VergenKey::CargoPkgDescription
+            | VergenKey::CargoPkgAuthors
+            | VergenKey::CargoPkgHomepage
+            | VergenKey::CargoPkgRepository
+            | VergenKey::CargoPkgLicense
+            | VergenKey::CargoPkgLicenseFile
+            | VergenKey::CargoTargetOs
+            | VergenKey::CargoTargetArch
+            | VergenKey::CargoTargetEnv
+            | VergenKey::CargoTargetPointerWidth
+            | VergenKey::CargoTargetEndian
+            | VergenKey::CargoLibcVersion
+            | VergenKey::TargetTriple
+            | VergenKey::TargetOs
+            | VergenKey::TargetArch
+            | VergenKey::TargetEnv
+            | VergenKey::IsCrossCompile => "cargo",
+            VergenKey::SysinfoName
+            | VergenKey::SysinfoOsVersion
+            | VergenKey::SysinfoUser
+            | VergenKey::SysinfoMemory
+            | VergenKey::SysinfoCpuVendor
+            | VergenKey::SysinfoCpuCoreCount
+            | VergenKey::SysinfoCpuName
+            | VergenKey::SysinfoCpuBrand
+            | VergenKey::SysinfoCpuFrequency => "sysinfo",
+            VergenKey::Banner
+            | VergenKey::Hash
+            | VergenKey::ScriptDuration
+            | VergenKey::BuildNumber
+            | VergenKey::DependencyLicenses
+            | VergenKey::EnabledFeatures
+            | VergenKey::ProvenanceJson
+            | VergenKey::ReleaseUrl => "vergen",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Config {
+    cfg_map: BTreeMap<VergenKey, Option<String>>,
+    commands: BTreeMap<String, String>,
+    head_path: Option<PathBuf>,
+    ref_path: Option<PathBuf>,
+    /// The overrides file the values were read from
+    overrides_path: Option<PathBuf>,
+    /// The keys whose values were read from the overrides file
+    file_keys: BTreeSet<VergenKey>,
+    /// The override variables that are set, for `cargo:rerun-if-env-changed`
+    override_vars: Vec<String>,
+    warnings: Vec<String>,
+    /// Why the instructions without a value were skipped, by key
+    skipped: BTreeMap<VergenKey, String>,
+    /// The values of each package, by package name
+    packages: BTreeMap<String, BTreeMap<VergenKey, Option<String>>>,
+    /// The `links` key of the package, for the values passed to the dependent crates
+    links: Option<String>,
+    /// The git directory of the discovered repository
+    repo_path: Option<PathBuf>,
+    /// How long each gathered feature took, in feature order
+    timings: Vec<(&'static str, Duration)>,
+    on_duplicate_key: DuplicatePolicy,
+    duplicates: Vec<VergenKey>,
+}
+
+impl Config {
+    pub(crate) fn cfg_map(&self) -> &BTreeMap<VergenKey, Option<String>> {
+        &self.cfg_map
+    }
+
+    pub(crate) fn cfg_map_mut(&mut self) -> &mut BTreeMap<VergenKey, Option<String>> {
+        &mut self.cfg_map
+    }
+
+    pub(crate) fn commands(&self) -> &BTreeMap<String, String> {
+        &self.commands
+    }
+
+    pub(crate) fn commands_mut(&mut self) -> &mut BTreeMap<String, String> {
+        &mut self.commands
+    }
+
+    pub(crate) fn head_path(&self) -> &Option<PathBuf> {
+        &self.head_path
+    }
+
+    #[cfg(any(test, feature = "git", feature = "hg"))]
+    pub(crate) fn head_path_mut(&mut self) -> &mut Option<PathBuf> {
+        &mut self.head_path
+    }
+
+    pub(crate) fn ref_path(&self) -> &Option<PathBuf> {
+        &self.ref_path
+    }
+
+    #[cfg(any(test, feature = "git"))]
+    pub(crate) fn ref_path_mut(&mut self) -> &mut Option<PathBuf> {
+        &mut self.ref_path
+    }
+
+    /// The overrides file the values were read from
+    pub(crate) fn overrides_path(&self) -> &Option<PathBuf> {
+        &self.overrides_path
+    }
+
+    /// The overrides file the values were read from
+    pub(crate) fn overrides_path_mut(&mut self) -> &mut Option<PathBuf> {
+        &mut self.overrides_path
+    }
+
+    /// The keys whose values were read from the overrides file
+    pub(crate) fn file_keys(&self) -> &BTreeSet<VergenKey> {
+        &self.file_keys
+    }
+
+    /// The keys whose values were read from the overrides file
+    pub(crate) fn file_keys_mut(&mut self) -> &mut BTreeSet<VergenKey> {
+        &mut self.file_keys
+    }
+
+    /// The override variables that are set, for `cargo:rerun-if-env-changed`
+    pub(crate) fn override_vars(&self) -> &Vec<String> {
+        &self.override_vars
+    }
+
+    /// The override variables that are set, for `cargo:rerun-if-env-changed`
+    pub(crate) fn override_vars_mut(&mut self) -> &mut Vec<String> {
+        &mut self.override_vars
+    }
+
+    pub(crate) fn warnings(&self) -> &Vec<String> {
+        &self.warnings
+    }
+
+    pub(crate) fn warnings_mut(&mut self) -> &mut Vec<String> {
+        &mut self.warnings
+    }
+
+    /// The values of each package, by package name
+    pub(crate) fn packages(&self) -> &BTreeMap<String, BTreeMap<VergenKey, Option<String>>> {
+        &self.packages
+    }
+
+    /// The values of each package, by package name
+    pub(crate) fn packages_mut(
+        &mut self,
+    ) -> &mut BTreeMap<String, BTreeMap<VergenKey, Option<String>>> {
+        &mut self.packages
+    }
+
+    /// The `links` key of the package, for the values passed to the dependent crates
+    pub(crate) fn links(&self) -> &Option<String> {
+        &self.links
+    }
+
+    /// The `links` key of the package, for the values passed to the dependent crates
+    pub(crate) fn links_mut(&mut self) -> &mut Option<String> {
+        &mut self.links
+    }
+
+    /// The git directory of the discovered repository
+    #[cfg(feature = "inspect")]
+    pub(crate) fn repo_path(&self) -> &Option<PathBuf> {
+        &self.repo_path
+    }
+
+    /// The git directory of the discovered repository
+    #[cfg(feature = "git")]
+    pub(crate) fn repo_path_mut(&mut self) -> &mut Option<PathBuf> {
+        &mut self.repo_path
+    }
+
+    /// How long each gathered feature took, in feature order
+    pub(crate) fn timings(&self) -> &Vec<(&'static str, Duration)> {
+        &self.timings
+    }
+
+    /// How long each gathered feature took, in feature order
+    #[cfg(test)]
+    pub(crate) fn timings_mut(&mut self) -> &mut Vec<(&'static str, Duration)> {
+        &mut self.timings
+    }
+
+    #[cfg(test)]
+    pub(crate) fn duplicates(&self) -> &Vec<VergenKey> {
+        &self.duplicates
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Self {
+            cfg_map: VergenKey::ALL.iter().copied().map(|x| (x, None)).collect(),
+            commands: BTreeMap::new(),
+            head_path: Option::default(),
+            ref_path: Option::default(),
+            overrides_path: Option::default(),
+            file_keys: BTreeSet::new(),
+            override_vars: Vec::default(),
+            warnings: Vec::default(),
+            skipped: BTreeMap::new(),
+            packages: BTreeMap::new(),
+            links: None,
+            repo_path: None,
+            timings: Vec::default(),
+            on_duplicate_key: DuplicatePolicy::default(),
+            duplicates: Vec::default(),
+        }
+    }
+}
+
+impl Config {
+    pub(crate) fn with_duplicate_policy(on_duplicate_key: DuplicatePolicy) -> Config {
+        Self {
+            on_duplicate_key,
+            ..Config::default()
+        }
+    }
+
+    /// Set the value of the key, applying the duplicate policy if the key already has a value
+    pub(crate) fn add_value(&mut self, key: VergenKey, value: Option<String>) {
+        let has_value = self.cfg_map.get(&key).map_or(false, Option::is_some);
+        match (has_value, self.on_duplicate_key) {
+            (false, _) | (true, DuplicatePolicy::Overwrite) => {
+                let _ = self.cfg_map.insert(key, value);
+            }
+            (true, DuplicatePolicy::KeepFirst) => {}
+            (true, DuplicatePolicy::Error) => self.add_duplicate(key),
+        }
+    }
+
+    /// Record why the instruction was skipped, keeping the first reason
+    pub(crate) fn skip<S>(&mut self, key: VergenKey, reason: S)
+    where
+        S: Into<String>,
+    {
+        let _ = self.skipped.entry(key).or_insert_with(|| reason.into());
+    }
+
+    fn add_duplicate(&mut self, key: VergenKey) {
+        if !self.duplicates.contains(&key) {
+            self.duplicates.push(key);
+        }
+    }
+
+    fn merge(&mut self, other: Config) {
+        for key in other.duplicates {
+            self.add_duplicate(key);
+        }
+        for (key, value) in other.cfg_map {
+            if value.is_some() {
+                self.add_value(key, value);
+            }
+        }
+        self.commands.extend(other.commands);
+        if other.head_path.is_some() {
+            self.head_path = other.head_path;
+        }
+        if other.ref_path.is_some() {
+            self.ref_path = other.ref_path;
+        }
+        if other.repo_path.is_some() {
+            self.repo_path = other.repo_path;
+        }
+        self.warnings.extend(other.warnings);
+        for (key, reason) in other.skipped {
+            self.skip(key, reason);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Config, DuplicatePolicy, Instructions, VergenKey, WarningLevel};
+    use crate::{output::OutputTarget, syntax::CargoSyntax};
+    use std::{path::PathBuf, process::Command, time::Duration};
+
+    #[test]
+    fn default_config_works() {
+        assert!(!Config::default().cfg_map().is_empty());
+    }
+
+    #[test]
+    fn warning_levels() {
+        assert!(WarningLevel::All.allows(false));
+        assert!(WarningLevel::All.allows(true));
+        assert!(!WarningLevel::Errors.allows(false));
+        assert!(WarningLevel::Errors.allows(true));
+        assert!(!WarningLevel::None.allows(false));
+        assert!(!WarningLevel::None.allows(true));
+    }
+
+    #[cfg(feature = "build")]
+    #[test]
+    fn unify_clock() {
+        let timestamp = |instructions: &Instructions| {
+            instructions.config(None::<PathBuf>).unwrap().cfg_map()[&VergenKey::BuildTimestamp]
+                .clone()
+        };
+        let mut instructions = Instructions::default();
+        *instructions.unify_clock_mut() = true;
+        instructions.unify_time();
+        assert!(instructions.reference_time().is_some());
+        let first = timestamp(&instructions);
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(timestamp(&instructions), first);
+
+        *instructions.unify_clock_mut() = false;
+        instructions.unify_time();
+        assert_eq!(instructions.reference_time(), None);
+        let first = timestamp(&instructions);
+        std::thread::sleep(Duration::from_millis(5));
+        assert_ne!(timestamp(&instructions), first);
+    }
+
+    fn described(instructions: &Instructions) -> Vec<&'static str> {
+        let mut names: Vec<&'static str> = instructions
+            .describe_effective()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn minimal_preset() {
+        let instructions = Instructions::minimal();
+        instructions.validate().unwrap();
+        #[allow(unused_mut)]
+        let mut expected: Vec<&str> = vec![];
+        #[cfg(feature = "build")]
+        expected.extend(&["VERGEN_BUILD_SEMVER", "VERGEN_BUILD_TIMESTAMP"]);
+        #[cfg(feature = "git")]
+        expected.push("VERGEN_GIT_SHA");
+        expected.sort_unstable();
+        assert_eq!(described(&instructions), expected);
+    }
+
+    #[test]
+    fn all_enabled_preset() {
+        let instructions = Instructions::all_enabled();
+        instructions.validate().unwrap();
+        #[allow(unused_mut)]
+        let mut expected: Vec<&str> = vec![];
+        #[cfg(feature = "build")]
+        expected.extend(&[
+            "VERGEN_BUILD_DATE",
+            "VERGEN_BUILD_TIME",
+            "VERGEN_BUILD_TIMESTAMP",
+            "VERGEN_BUILD_SEMVER",
+            "VERGEN_BUILD_SEMVER_MAJOR",
+            "VERGEN_BUILD_SEMVER_MINOR",
+            "VERGEN_BUILD_SEMVER_PATCH",
+            "VERGEN_BUILD_SEMVER_PRE",
+        ]);
+        #[cfg(feature = "cargo")]
+        expected.extend(&[
+            "VERGEN_CARGO_FEATURES",
+            "VERGEN_CARGO_LIBC_VERSION",
+            "VERGEN_CARGO_PKG_AUTHORS",
+            "VERGEN_CARGO_PKG_DESCRIPTION",
+            "VERGEN_CARGO_PKG_HOMEPAGE",
+            "VERGEN_CARGO_PKG_LICENSE",
+            "VERGEN_CARGO_PKG_LICENSE_FILE",
+            "VERGEN_CARGO_PKG_REPOSITORY",
+            "VERGEN_CARGO_PROFILE",
+            "VERGEN_CARGO_TARGET_ARCH",
+            "VERGEN_CARGO_TARGET_ENDIAN",
+            "VERGEN_CARGO_TARGET_ENV",
+            "VERGEN_CARGO_TARGET_OS",
+            "VERGEN_CARGO_TARGET_POINTER_WIDTH",
+            "VERGEN_CARGO_TARGET_TRIPLE",
+            "VERGEN_IS_CROSS_COMPILE",
+            "VERGEN_TARGET_ARCH",
+            "VERGEN_TARGET_ENV",
+            "VERGEN_TARGET_OS",
+            "VERGEN_TARGET_TRIPLE",
+        ]);
+        #[cfg(feature = "git")]
+        expected.extend(&[
+            "VERGEN_BUILD_NUMBER",
+            "VERGEN_GIT_BRANCH",
+            "VERGEN_GIT_BRANCH_COUNT",
+            "VERGEN_GIT_COMMITS_SINCE_TAG",
+            "VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS",
+            "VERGEN_GIT_COMMIT_AGE_SECONDS",
+            "VERGEN_GIT_COMMIT_DATE",
+            "VERGEN_GIT_COMMIT_TIME",
+            "VERGEN_GIT_COMMIT_TIMESTAMP",
+            "VERGEN_GIT_CONTRIBUTOR_COUNT",
+            "VERGEN_GIT_MERGE_BASE",
+            "VERGEN_GIT_OBJECT_FORMAT",
+            "VERGEN_GIT_PARENT_COUNT",
+            "VERGEN_GIT_ROOT",
+            "VERGEN_GIT_SEMVER",
+            "VERGEN_GIT_SHA",
+            "VERGEN_GIT_SHA_SHORT",
+            "VERGEN_GIT_SIGNED",
+            "VERGEN_GIT_SIGNER",
+            "VERGEN_GIT_TAG_COUNT",
+            "VERGEN_GIT_TRACKED_FILES",
+            "VERGEN_GIT_TREE_HASH",
+            "VERGEN_GIT_WORKTREE",
+            "VERGEN_RELEASE_URL",
+        ]);
+        #[cfg(feature = "rustc")]
+        expected.extend(&[
+            "VERGEN_RUSTC_CHANNEL",
+            "VERGEN_RUSTC_COMMIT_DATE",
+            "VERGEN_RUSTC_COMMIT_HASH",
+            "VERGEN_RUSTC_HOST_TRIPLE",
+            "VERGEN_RUSTC_LLVM_VERSION",
+            "VERGEN_RUSTC_SEMVER",
+        ]);
+        #[cfg(feature = "si")]
+        expected.extend(&[
+            "VERGEN_SYSINFO_CPU_BRAND",
+            "VERGEN_SYSINFO_CPU_CORE_COUNT",
+            "VERGEN_SYSINFO_CPU_FREQUENCY",
+            "VERGEN_SYSINFO_CPU_NAME",
+            "VERGEN_SYSINFO_CPU_VENDOR",
+            "VERGEN_SYSINFO_NAME",
+            "VERGEN_SYSINFO_OS_VERSION",
+            "VERGEN_SYSINFO_TOTAL_MEMORY",
+            "VERGEN_SYSINFO_USER",
+        ]);
+        expected.sort_unstable();
+        assert_eq!(described(&instructions), expected);
+    }
+
+    #[test]
+    fn ci_preset() {
+        let instructions = Instructions::ci();
+        instructions.validate().unwrap();
+        let names = described(&instructions);
+        assert!(described(&Instructions::all_enabled())
+            .iter()
+            .all(|name| names.contains(name)));
+        assert!(instructions.emit_all_with_placeholders);
+        assert!(instructions.source_date_epoch);
+        assert!(instructions.unify_clock);
+        assert!(instructions.env_overrides);
+    }
+
+    #[test]
+    fn all_keys_in_declaration_order() {
+        for (idx, key) in VergenKey::ALL.iter().enumerate() {
+            assert_eq!(*key as usize, idx);
+        }
+    }
+
+    #[test]
+    fn key_names_are_unique() {
+        // The names from the other sources are checked against these when they're emitted, see `check_collisions`
+        let mut names: Vec<String> = VergenKey::ALL
+            .iter()
+            .copied()
+            .map(|key| key.name().to_ascii_uppercase())
+            .collect();
+        let count = names.len();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), count);
+    }
+
+    #[cfg(feature = "macros")]
+    mod derive {
+        include!("../vergen-macros/src/keys.rs");
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn derive_keys_match() {
+        let mut names: Vec<&str> = VergenKey::ALL
+            .iter()
+            .copied()
+            .map(|key| key.name().trim_start_matches("VERGEN_"))
+            .collect();
+        let mut keys = derive::KEYS.to_vec();
+        names.sort_unstable();
+        keys.sort_unstable();
+        assert_eq!(keys, names);
+    }
+
+    #[test]
+    fn merge_keeps_values() {
+        let mut config = Config::default();
+        let mut partial = Config::default();
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Sha, Some("abc".to_string()));
+        let _ = partial
+            .cfg_map_mut()
+            .insert(VergenKey::Branch, Some("main".to_string()));
+        *partial.head_path_mut() = Some(PathBuf::from(".git/HEAD"));
+        config.merge(partial);
+        assert_eq!(
+            config.cfg_map().get(&VergenKey::Sha),
+            Some(&Some("abc".to_string()))
+        );
+        assert_eq!(
+            config.cfg_map().get(&VergenKey::Branch),
+            Some(&Some("main".to_string()))
+        );
+        assert_eq!(config.head_path(), &Some(PathBuf::from(".git/HEAD")));
+    }
+
+    #[test]
+    fn merge_duplicates() {
+        let merged = |policy| {
+            let mut config = Config::with_duplicate_policy(policy);
+            for sha in &["abc", "def"] {
+                let mut partial = Config::with_duplicate_policy(policy);
+                partial.add_value(VergenKey::Sha, Some((*sha).to_string()));
+                config.merge(partial);
+            }
+            config
+        };
+        let sha = |config: &Config| config.cfg_map()[&VergenKey::Sha].clone().unwrap();
+
+        assert_eq!(sha(&merged(DuplicatePolicy::Overwrite)), "def");
+        assert_eq!(sha(&merged(DuplicatePolicy::KeepFirst)), "abc");
+        let config = merged(DuplicatePolicy::Error);
+        assert_eq!(sha(&config), "abc");
+        assert_eq!(config.duplicates(), &[VergenKey::Sha]);
+
+        // A missing value doesn't count
+        let mut config = Config::with_duplicate_policy(DuplicatePolicy::Error);
+        config.add_value(VergenKey::Sha, None);
+        config.add_value(VergenKey::Sha, Some("abc".to_string()));
+        assert_eq!(sha(&config), "abc");
+        assert!(config.duplicates().is_empty());
+    }
+
+    #[test]
+    fn parallel_matches_single_threaded() {
+        let is_time = |key: &VergenKey| {
+            matches!(
+                key,
+                VergenKey::BuildDate | VergenKey::BuildTime | VergenKey::BuildTimestamp
+            )
+        };
+        let parallel = Instructions::default().config(Some(".")).unwrap();
+        let mut instructions = Instructions::default();
+        *instructions.single_threaded_mut() = true;
+        let serial = instructions.config(Some(".")).unwrap();
+
+        for (key, value) in parallel.cfg_map() {
+            assert_eq!(value.is_some(), serial.cfg_map()[key].is_some());
+            if !is_time(key) {
+                assert_eq!(value, &serial.cfg_map()[key]);
+            }
+        }
+        assert_eq!(parallel.head_path(), serial.head_path());
+        assert_eq!(parallel.ref_path(), serial.ref_path());
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn parallel_reports_errors() {
+        assert!(Instructions::default()
+            .config(Some(std::env::temp_dir().join("vergen_no_such_repo")))
+            .is_err());
+    }
+
+    #[cfg(all(feature = "build", feature = "git"))]
+    #[test]
+    fn only_skips_unlisted_features() {
+        use crate::provider::FakeRepo;
+        use std::{sync::Arc, time::Instant};
+
+        // Any probe of the repository would fail, or take 30 seconds
+        let no_such_repo = std::env::temp_dir().join("vergen_no_such_repo");
+        let mut instructions = Instructions::default();
+        *instructions.build_number_mut() = true;
+        *instructions.self_timing_mut() = true;
+        instructions.only(&["VERGEN_BUILD_TIMESTAMP"]);
+        for single_threaded in &[true, false] {
+            *instructions.single_threaded_mut() = *single_threaded;
+            let mut config = instructions.config(Some(&no_such_repo)).unwrap();
+            instructions.retain_listed(&mut config);
+            let emitted: Vec<VergenKey> = config
+                .cfg_map()
+                .iter()
+                .filter_map(|(key, value)| value.as_ref().map(|_| *key))
+                .collect();
+            assert_eq!(emitted, vec![VergenKey::BuildTimestamp]);
+        }
+
+        // The repository is opened for the build number
+        instructions.only(&["VERGEN_BUILD_NUMBER"]);
+        assert!(instructions.config(Some(&no_such_repo)).is_err());
+        instructions.only(&["VERGEN_BUILD_TIMESTAMP"]);
+
+        *instructions.providers_mut().git_mut() = Some(Arc::new(FakeRepo {
+            delay: Duration::from_secs(30),
+            ..FakeRepo::default()
+        }));
+        let start = Instant::now();
+        let _config = instructions.config(Some(".")).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(20));
+        assert_eq!(
+            instructions.describe_effective(),
+            vec![("VERGEN_BUILD_TIMESTAMP", "the time of the build")]
+        );
+    }
+
+    #[test]
+    fn only_unknown_name() {
+        let mut instructions = Instructions::default();
+        instructions.only(&["VERGEN_GIT_SHA", "VERGEN_GIT_SHAA"]);
+        let errors = instructions.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field(), "only");
+        assert_eq!(errors[0].value(), "\"VERGEN_GIT_SHAA\"");
+    }
+
+    #[cfg(feature = "git")]
+    fn slow_git(single_threaded: bool) -> (Instructions, Config, Duration) {
+        use crate::provider::FakeRepo;
+        use std::{sync::Arc, time::Instant};
+
+        // Each query takes a second, so the git feature stops at the first check after the deadline
+        let mut instructions = Instructions::default();
+        *instructions.providers_mut().git_mut() = Some(Arc::new(FakeRepo {
+            delay: Duration::from_secs(1),
+            ..FakeRepo::default()
+        }));
+        *instructions.single_threaded_mut() = single_threaded;
+        *instructions.max_duration_mut() = Some(Duration::from_secs(3));
+        let start = Instant::now();
+        let config = instructions.config(Some(".")).unwrap();
+        (instructions, config, start.elapsed())
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn max_duration_emits_partial() {
+        let (instructions, mut config, elapsed) = slow_git(false);
+        assert!(elapsed < Duration::from_secs(20));
+        assert!(config
+            .cfg_map()
+            .get(&VergenKey::Sha)
+            .cloned()
+            .flatten()
+            .is_none());
+        #[cfg(feature = "build")]
+        assert!(config.cfg_map()[&VergenKey::BuildTimestamp].is_some());
+        assert_eq!(
+            config.warnings(),
+            &vec!["vergen: the git feature(s) didn't finish within 3s".to_string()]
+        );
+
+        instructions.warn_skipped(&mut config);
+        let skipped: Vec<&String> = config
+            .warnings()
+            .iter()
+            .filter(|warning| warning.contains("VERGEN_GIT_SHA "))
+            .collect();
+        assert_eq!(
+            skipped,
+            &["vergen: skipped VERGEN_GIT_SHA because the git feature didn't finish within 3s"]
+        );
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn max_duration_single_threaded() {
+        let (_, config, elapsed) = slow_git(true);
+        assert!(elapsed < Duration::from_secs(20));
+        #[cfg(feature = "build")]
+        assert!(config.cfg_map()[&VergenKey::BuildTimestamp].is_some());
+        assert_eq!(config.warnings().len(), 1);
+        assert!(config.warnings()[0].starts_with("vergen: the git, hg, rustc"));
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn only_warns_skipped() {
+        let mut instructions = Instructions::default();
+        *instructions.git_mut().sha_mut() = false;
+        instructions.only(&["VERGEN_GIT_SHA"]);
+        let mut config = Config::default();
+        instructions.warn_skipped(&mut config);
+        assert_eq!(
+            config.warnings(),
+            &["vergen: skipped VERGEN_GIT_SHA because it's turned off in the git configuration"]
+        );
+
+        *instructions.git_mut().enabled_mut() = false;
+        let mut config = Config::default();
+        instructions.warn_skipped(&mut config);
+        assert_eq!(
+            config.warnings(),
+            &["vergen: skipped VERGEN_GIT_SHA because the git feature is disabled"]
+        );
+
+        *instructions.quiet_mut() = true;
+        let mut config = Config::default();
+        instructions.warn_skipped(&mut config);
+        assert!(config.warnings().is_empty());
+    }
+
+    #[test]
+    fn enabled_summary() {
+        let mut instructions = Instructions::default();
+        let mut config = Config::default();
+        assert_eq!(instructions.enabled_summary(&config), "");
+
+        for (key, value) in &[
+            (VergenKey::Sha, "abc123"),
+            (VergenKey::BuildTimestamp, "now"),
+            (VergenKey::RustcSemver, "VERGEN_UNAVAILABLE"),
+            (VergenKey::Hash, "4f8bd4b5e0a3"),
+        ] {
+            let _ = config.cfg_map_mut().insert(*key, Some(value.to_string()));
+        }
+        assert_eq!(instructions.enabled_summary(&config), "build,git,rustc");
+
+        // The placeholders aren't generated values
+        *instructions.emit_all_with_placeholders_mut() = true;
+        *instructions.unavailable_placeholder_mut() = "VERGEN_UNAVAILABLE".to_string();
+        assert_eq!(instructions.enabled_summary(&config), "build,git");
+    }
+
+    #[test]
+    fn required_validation() {
+        let mut instructions = Instructions::default();
+        instructions.require(&["VERGEN_GIT_SHA", "VERGEN_GIT_SHAA"]);
+        let errors = instructions.validate().unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().all(|error| error.field() == "required"));
+
+        *instructions.generate_include_mut() = true;
+        instructions.require(&["VERGEN_GIT_SHA"]);
+        assert!(instructions.validate().is_ok());
+        instructions.only(&["VERGEN_BUILD_TIMESTAMP"]);
+        let errors = instructions.validate().unwrap_err();
+        assert_eq!(errors[0].value(), "\"VERGEN_GIT_SHA\"");
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn required_unavailable() {
+        use anyhow::anyhow;
+
+        let mut instructions = Instructions::default();
+        *instructions.generate_include_mut() = true;
+        *instructions.git_mut().sha_mut() = false;
+        instructions.require(&["VERGEN_GIT_SHA", "VERGEN_GIT_BRANCH"]);
+        let mut config = Config::default();
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Branch, Some("main".to_string()));
+        assert_eq!(
+            instructions.unavailable_required(&config),
+            &[(
+                "VERGEN_GIT_SHA",
+                "it's turned off in the git configuration".to_string()
+            )]
+        );
+
+        // The placeholder isn't a value, and the reason is the gathering failure
+        *instructions.git_mut().sha_mut() = true;
+        *instructions.emit_all_with_placeholders_mut() = true;
+        let mut config = Config::default();
+        instructions
+            .merge_gathered(&mut config, "git", Err(anyhow!("not a repository")))
+            .unwrap();
+        instructions.fill_unavailable(&mut config);
+        assert_eq!(
+            instructions.unavailable_required(&config),
+            &[
+                (
+                    "VERGEN_GIT_SHA",
+                    "the git feature failed, not a repository".to_string()
+                ),
+                (
+                    "VERGEN_GIT_BRANCH",
+                    "the git feature failed, not a repository".to_string()
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn zero_max_duration() {
+        let mut instructions = Instructions::default();
+        *instructions.max_duration_mut() = Some(Duration::from_secs(0));
+        let errors = instructions.validate().unwrap_err();
+        assert_eq!(errors[0].field(), "max_duration");
+    }
+
+    #[test]
+    fn url_template_without_release_url() {
+        let mut instructions = Instructions::default();
+        *instructions.url_template_mut() = Some("{repo}/-/releases/{tag}".to_string());
+        let errors = instructions.validate().unwrap_err();
+        assert_eq!(errors[0].field(), "url_template");
+        *instructions.release_url_mut() = true;
+        assert!(instructions.validate().is_ok());
+    }
+
+    #[cfg(feature = "build")]
+    fn check_build_config(instructions: &Instructions) {
+        use crate::{TimeZone, TimestampKind};
+
+        let config = instructions.build();
+        assert!(config.has_enabled());
+        assert!(config.timestamp());
+        assert_eq!(*config.timezone(), TimeZone::Utc);
+        assert_eq!(*config.kind(), TimestampKind::Timestamp);
+        assert!(config.semver());
+    }
+
+    #[cfg(not(feature = "build"))]
+    fn check_build_config(_instructions: &Instructions) {}
+
+    #[cfg(feature = "cargo")]
+    fn check_cargo_config(instructions: &Instructions) {
+        let config = instructions.cargo();
         assert!(config.has_enabled());
         assert!(config.features());
         assert!(config.profile());
@@ -321,6 +4567,7 @@ mod test {
         assert_eq!(*config.semver_kind(), SemverKind::Normal);
         assert!(config.sha());
         assert_eq!(*config.sha_kind(), ShaKind::Normal);
+        assert!(!config.tracked_files());
     }
 
     #[cfg(not(feature = "git"))]
@@ -355,6 +4602,513 @@ mod test {
     #[cfg(not(feature = "si"))]
     fn check_sysinfo_config(_instructions: &Instructions) {}
 
+    #[test]
+    fn default_instructions_are_valid() {
+        assert!(Instructions::default().validate().is_ok());
+    }
+
+    #[test]
+    fn invalid_link_section() {
+        let mut instructions = Instructions::default();
+        *instructions.emit_link_section_mut() = Some(String::new());
+        let errors = instructions.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field(), "emit_link_section");
+        assert_eq!(errors[0].value(), "\"\"");
+
+        *instructions.emit_link_section_mut() = Some(".vergen_1".to_string());
+        assert!(instructions.validate().is_ok());
+    }
+
+    #[cfg(all(feature = "build", feature = "git"))]
+    #[test]
+    fn all_errors_reported() {
+        use crate::TimestampKind;
+
+        let mut instructions = Instructions::default();
+        *instructions.build_mut().timestamp_mut() = false;
+        *instructions.build_mut().kind_mut() = TimestampKind::All;
+        *instructions.git_mut().semver_mut() = false;
+        *instructions.git_mut().semver_dirty_mut() = Some("-dirty");
+        *instructions.emit_link_section_mut() = Some("bad name".to_string());
+        let errors = instructions.validate().unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|e| e.field().as_str()).collect();
+        assert_eq!(
+            fields,
+            vec!["build.kind", "git.semver_dirty", "emit_link_section"]
+        );
+    }
+
+    #[test]
+    fn reproducible() {
+        let instructions = Instructions::reproducible();
+        assert!(*instructions.source_date_epoch());
+        assert!(!*Instructions::default().source_date_epoch());
+        #[cfg(feature = "si")]
+        assert!(!instructions.sysinfo().has_enabled());
+        #[cfg(feature = "build")]
+        assert_eq!(*instructions.build().timezone(), crate::TimeZone::Utc);
+        #[cfg(feature = "git")]
+        assert_eq!(
+            *instructions.git().commit_timestamp_timezone(),
+            crate::TimeZone::Utc
+        );
+        assert!(instructions.validate().is_ok());
+    }
+
+    #[test]
+    fn merge_features() {
+        let mut base = Instructions::default();
+        let mut overlay = Instructions::default();
+        #[cfg(feature = "build")]
+        {
+            *base.build_mut().semver_mut() = false;
+            *overlay.build_mut().timezone_mut() = crate::TimeZone::Local;
+        }
+        #[cfg(feature = "cargo")]
+        {
+            *base.cargo_mut().features_mut() = false;
+            *overlay.cargo_mut().target_os_mut() = true;
+        }
+        #[cfg(feature = "git")]
+        {
+            *base.git_mut().branch_mut() = false;
+            *overlay.git_mut().commits_since_tag_subjects_mut() = Some(5);
+        }
+        #[cfg(feature = "rustc")]
+        {
+            *base.rustc_mut().channel_mut() = false;
+            *overlay.rustc_mut().llvm_version_mut() = false;
+        }
+        #[cfg(feature = "si")]
+        {
+            *base.sysinfo_mut().user_mut() = false;
+            *overlay.sysinfo_mut().enabled_mut() = false;
+        }
+        #[cfg(feature = "hg")]
+        {
+            *base.hg_mut().rev_mut() = false;
+            *overlay.hg_mut().enabled_mut() = false;
+        }
+
+        let merged = base.merge(overlay);
+        #[cfg(feature = "build")]
+        {
+            assert!(!*merged.build().semver());
+            assert_eq!(*merged.build().timezone(), crate::TimeZone::Local);
+        }
+        #[cfg(feature = "cargo")]
+        {
+            assert!(!*merged.cargo().features());
+            assert!(*merged.cargo().target_os());
+        }
+        #[cfg(feature = "git")]
+        {
+            assert!(!*merged.git().branch());
+            assert_eq!(*merged.git().commits_since_tag_subjects(), Some(5));
+            assert!(*merged.git().sha());
+        }
+        #[cfg(feature = "rustc")]
+        {
+            assert!(!*merged.rustc().channel());
+            assert!(!*merged.rustc().llvm_version());
+        }
+        #[cfg(feature = "si")]
+        {
+            assert!(!*merged.sysinfo().user());
+            assert!(!*merged.sysinfo().enabled());
+        }
+        #[cfg(feature = "hg")]
+        {
+            assert!(!*merged.hg().rev());
+            assert!(!*merged.hg().enabled());
+        }
+    }
+
+    #[test]
+    fn merge_settings() {
+        let mut base = Instructions::default();
+        *base.banner_format_mut() = Some("{name} {semver}".to_string());
+        *base.warnings_mut() = WarningLevel::Errors;
+        *base.sanitize_mut().escape_non_printable_mut() = true;
+        *base.c_header_mut().enabled_mut() = true;
+        *base.unavailable_placeholder_mut() = "n/a".to_string();
+        let mut overlay = Instructions::default();
+        *overlay.banner_format_mut() = Some("{sha}".to_string());
+        *overlay.env_overrides_mut() = false;
+        *overlay.cargo_syntax_mut() = CargoSyntax::Modern;
+        *overlay.sanitize_mut().reject_directives_mut() = true;
+        *overlay.built_compat_mut().enabled_mut() = true;
+        *overlay.output_target_mut() = OutputTarget::Both(PathBuf::from("vergen.env"));
+
+        let merged = base.merge(overlay);
+        assert_eq!(merged.banner_format().as_deref(), Some("{sha}"));
+        assert_eq!(*merged.warnings(), WarningLevel::Errors);
+        assert!(!*merged.env_overrides());
+        assert_eq!(*merged.cargo_syntax(), CargoSyntax::Modern);
+        assert!(*merged.sanitize().escape_non_printable());
+        assert!(*merged.sanitize().reject_directives());
+        assert!(*merged.c_header().enabled());
+        assert!(*merged.built_compat().enabled());
+        assert_eq!(merged.unavailable_placeholder(), "n/a");
+        assert_eq!(
+            *merged.output_target(),
+            OutputTarget::Both(PathBuf::from("vergen.env"))
+        );
+        assert!(*merged.docs_rs_placeholder());
+    }
+
+    #[test]
+    fn merge_collections() {
+        let mut base = Instructions::default();
+        base.sticky_mut()
+            .triggers_mut()
+            .push("VERGEN_RUSTC_SEMVER".to_string());
+        base.emit_cfg_mut()
+            .keys_mut()
+            .push("VERGEN_GIT_SIGNED".to_string());
+        let _ = base
+            .emit_cfg_mut()
+            .names_mut()
+            .insert("VERGEN_GIT_SIGNED".to_string(), "base".to_string());
+        #[cfg(feature = "hash")]
+        base.hash_mut().exclude_mut().clear();
+        base.value_provider("VERGEN_GIT_SHA", || Some("base".to_string()));
+        base.value_provider("VERGEN_GIT_BRANCH", || Some("base".to_string()));
+
+        let mut overlay = Instructions::default();
+        overlay
+            .sticky_mut()
+            .triggers_mut()
+            .push("VERGEN_CARGO_PROFILE".to_string());
+        overlay
+            .emit_cfg_mut()
+            .keys_mut()
+            .push("VERGEN_GIT_SHA".to_string());
+        let _ = overlay
+            .emit_cfg_mut()
+            .names_mut()
+            .insert("VERGEN_GIT_SIGNED".to_string(), "overlay".to_string());
+        overlay.value_provider("VERGEN_GIT_SHA", || Some("overlay".to_string()));
+
+        let merged = base.merge(overlay);
+        assert_eq!(
+            merged.sticky().triggers(),
+            &vec![
+                "VERGEN_GIT_SHA".to_string(),
+                "VERGEN_GIT_SHA_SHORT".to_string(),
+                "VERGEN_RUSTC_SEMVER".to_string(),
+                "VERGEN_CARGO_PROFILE".to_string(),
+            ]
+        );
+        assert_eq!(
+            merged.sticky().keys(),
+            Instructions::default().sticky().keys()
+        );
+        assert_eq!(
+            merged.emit_cfg().keys(),
+            &vec![
+                "VERGEN_GIT_SIGNED".to_string(),
+                "VERGEN_GIT_SHA".to_string()
+            ]
+        );
+        assert_eq!(merged.emit_cfg().names()["VERGEN_GIT_SIGNED"], "overlay");
+        // The default exclude list in the overlay doesn't undo the base clearing it
+        #[cfg(feature = "hash")]
+        assert!(merged.hash().exclude().is_empty());
+
+        let mut config = Config::default();
+        merged.apply_value_providers(&mut config);
+        assert_eq!(
+            config.cfg_map()[&VergenKey::Sha],
+            Some("overlay".to_string())
+        );
+        assert_eq!(
+            config.cfg_map()[&VergenKey::Branch],
+            Some("base".to_string())
+        );
+    }
+
+    #[test]
+    fn merge_set_to_default() {
+        let mut base = Instructions::default();
+        *base.single_threaded_mut() = true;
+        #[cfg(feature = "hash")]
+        {
+            *base.hash_mut().enabled_mut() = true;
+        }
+        *base.sanitize_mut().escape_non_printable_mut() = true;
+        #[cfg(feature = "git")]
+        {
+            *base.git_mut().sha_mut() = false;
+        }
+        #[cfg(feature = "build")]
+        {
+            *base.build_mut().semver_mut() = false;
+        }
+
+        let mut overlay = Instructions::default();
+        overlay.set_single_threaded(false);
+        #[cfg(feature = "hash")]
+        overlay.hash_mut().set_enabled(false);
+        #[cfg(feature = "git")]
+        {
+            overlay.git_mut().set_sha(true);
+        }
+        #[cfg(feature = "build")]
+        {
+            overlay.build_mut().set_semver(true);
+        }
+
+        let merged = base.merge(overlay);
+        assert!(!*merged.single_threaded());
+        #[cfg(feature = "hash")]
+        assert!(!*merged.hash().enabled());
+        assert!(*merged.sanitize().escape_non_printable());
+        #[cfg(feature = "git")]
+        assert!(*merged.git().sha());
+        #[cfg(feature = "build")]
+        assert!(*merged.build().semver());
+
+        // The set fields carry over, so a later layer that leaves them alone doesn't undo them
+        let merged = merged.merge(Instructions::default());
+        assert!(!*merged.single_threaded());
+        #[cfg(feature = "git")]
+        assert!(*merged.git().sha());
+    }
+
+    #[test]
+    fn merge_read_overlay() {
+        let mut base = Instructions::default();
+        *base.single_threaded_mut() = true;
+        #[cfg(feature = "hash")]
+        {
+            *base.hash_mut().enabled_mut() = true;
+        }
+        #[cfg(feature = "git")]
+        {
+            *base.git_mut().sha_mut() = false;
+        }
+
+        // Reading through the accessors doesn't count as setting the fields
+        let mut overlay = Instructions::default();
+        assert!(!*overlay.single_threaded_mut());
+        #[cfg(feature = "hash")]
+        assert!(!*overlay.hash_mut().enabled_mut());
+        #[cfg(feature = "git")]
+        {
+            assert!(*overlay.git_mut().sha_mut());
+        }
+
+        let merged = base.merge(overlay);
+        assert!(*merged.single_threaded());
+        #[cfg(feature = "hash")]
+        assert!(*merged.hash().enabled());
+        #[cfg(feature = "git")]
+        assert!(!*merged.git().sha());
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn merge_three_layers() {
+        let mut company = Instructions::default();
+        *company.single_threaded_mut() = true;
+        *company.hash_mut().enabled_mut() = true;
+        *company.hash_mut().length_mut() = Some(12);
+        company
+            .sticky_mut()
+            .keys_mut()
+            .push("VERGEN_GIT_BRANCH".to_string());
+
+        let mut team = Instructions::default();
+        *team.hash_mut().length_mut() = Some(16);
+        *team.self_timing_mut() = true;
+        team.sticky_mut()
+            .keys_mut()
+            .push("VERGEN_RUSTC_SEMVER".to_string());
+
+        let mut project = Instructions::default();
+        *project.hash_mut().length_mut() = Some(8);
+        *project.emit_link_section_mut() = Some(".vergen".to_string());
+
+        let merged = company.merge(team).merge(project);
+        assert!(*merged.single_threaded());
+        assert!(*merged.self_timing());
+        assert!(*merged.hash().enabled());
+        assert_eq!(*merged.hash().length(), Some(8));
+        assert_eq!(merged.emit_link_section().as_deref(), Some(".vergen"));
+        assert!(merged.sticky().keys().ends_with(&[
+            "VERGEN_GIT_BRANCH".to_string(),
+            "VERGEN_RUSTC_SEMVER".to_string()
+        ]));
+        assert!(merged.validate().is_ok());
+    }
+
+    #[cfg(all(feature = "git", feature = "hash"))]
+    #[test]
+    fn describe_effective() {
+        let mut instructions = Instructions::only_git();
+        instructions.value_provider("VERGEN_GIT_SHA", || Some("custom".to_string()));
+        *instructions.hash_mut().enabled_mut() = true;
+        let described = instructions.describe_effective();
+        assert!(described.contains(&("VERGEN_GIT_BRANCH", "the git repository")));
+        assert!(described.contains(&("VERGEN_GIT_SHA", "a registered value provider")));
+        assert_eq!(
+            described.last(),
+            Some(&("VERGEN_HASH", "a hash over the other values"))
+        );
+        assert!(described
+            .iter()
+            .all(|(name, _)| name.starts_with("VERGEN_GIT_") || *name == "VERGEN_HASH"));
+        assert!(!described
+            .iter()
+            .any(|(name, _)| *name == "VERGEN_GIT_SIGNER"));
+
+        *instructions.emit_all_with_placeholders_mut() = true;
+        assert!(instructions
+            .describe_effective()
+            .contains(&("VERGEN_GIT_SIGNER", "the unavailable placeholder")));
+    }
+
+    #[test]
+    fn value_providers() {
+        let mut instructions = Instructions::default();
+        instructions.value_provider("VERGEN_GIT_SHA", || Some("custom\n".to_string()));
+        instructions.value_provider("VERGEN_GIT_BRANCH", || None);
+        assert!(instructions.validate().is_ok());
+
+        let mut config = Config::default();
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Branch, Some("main".to_string()));
+        instructions.apply_value_providers(&mut config);
+        assert_eq!(
+            config.cfg_map()[&VergenKey::Sha],
+            Some("custom".to_string())
+        );
+        assert_eq!(
+            config.cfg_map()[&VergenKey::Branch],
+            Some("main".to_string())
+        );
+    }
+
+    #[test]
+    fn command_names() {
+        let mut instructions = Instructions::default();
+        instructions.add_command("BUILD_NUMBER", "build-number-cli", &["--current"]);
+        assert!(instructions.validate().is_ok());
+
+        for name in &[
+            "",
+            "BUILD NUMBER",
+            "A=B",
+            "VERGEN_GIT_SHA",
+            "vergen_git_sha",
+            "Vergen_Cargo_Target_Os",
+            "Vergen_Target_Os",
+            "VERGEN_IS_CROSS_COMPILE",
+        ] {
+            let mut instructions = Instructions::default();
+            instructions.add_command(*name, "true", &[]);
+            let errors = instructions.validate().unwrap_err();
+            assert_eq!(errors[0].field(), "commands");
+        }
+    }
+
+    #[test]
+    fn ci_only_names() {
+        let mut instructions = Instructions::default();
+        instructions.ci_only(&["VERGEN_GIT_SHA", "VERGEN_GIT_SHAA"]);
+        let errors = instructions.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field(), "ci_only");
+        assert_eq!(errors[0].value(), "\"VERGEN_GIT_SHAA\"");
+    }
+
+    #[test]
+    fn if_feature_names() {
+        let mut instructions = Instructions::default();
+        instructions.if_feature("telemetry", "VERGEN_GIT_SHA");
+        instructions.if_feature("fast-hash", "VERGEN_GIT_SHAA");
+        instructions.if_feature("dep:serde", "VERGEN_BUILD_SEMVER");
+        let errors = instructions.validate().unwrap_err();
+        let values: Vec<&str> = errors.iter().map(|error| error.value().as_str()).collect();
+        assert_eq!(values, ["\"dep:serde\"", "\"VERGEN_GIT_SHAA\""]);
+        assert!(errors.iter().all(|error| error.field() == "if_features"));
+    }
+
+    #[test]
+    fn command_key_names() {
+        let mut instructions = Instructions::default();
+        instructions.add_command_key("RELEASE_TRAIN", Command::new("true"));
+        instructions
+            .links_metadata_mut()
+            .push("VERGEN_CMD_RELEASE_TRAIN".to_string());
+        assert!(instructions.validate().is_ok());
+
+        for name in &["", "RELEASE TRAIN", "A=B"] {
+            let mut instructions = Instructions::default();
+            instructions.add_command_key(*name, Command::new("true"));
+            let errors = instructions.validate().unwrap_err();
+            assert_eq!(errors[0].field(), "command_keys");
+        }
+
+        let mut instructions = Instructions::default();
+        instructions.add_command("VERGEN_CMD_RELEASE_TRAIN", "true", &[]);
+        instructions.add_command_key("RELEASE_TRAIN", Command::new("true"));
+        let errors = instructions.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field(), "command_keys");
+    }
+
+    #[test]
+    fn value_provider_unknown_name() {
+        let mut instructions = Instructions::default();
+        instructions.value_provider("VERGEN_GIT_SHAA", || None);
+        let errors = instructions.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field(), "value_providers");
+        assert_eq!(errors[0].value(), "\"VERGEN_GIT_SHAA\"");
+    }
+
+    #[test]
+    fn value_overrides() {
+        let mut instructions = Instructions::default();
+        instructions.value_provider("VERGEN_GIT_SHA", || panic!("overridden provider called"));
+        instructions.value_provider("VERGEN_GIT_BRANCH", || Some("provided".to_string()));
+        instructions.override_value("VERGEN_GIT_SHA", "75b390d\n");
+        instructions.override_value("VERGEN_RUSTC_CHANNEL", "stable");
+        assert!(instructions.validate().is_ok());
+        assert!(instructions
+            .describe_effective()
+            .contains(&("VERGEN_GIT_SHA", "a value override")));
+
+        let mut config = Config::default();
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Sha, Some("abc123".to_string()));
+        instructions.apply_value_providers(&mut config);
+        instructions.apply_value_overrides(&mut config);
+        assert_eq!(
+            config.cfg_map()[&VergenKey::Sha],
+            Some("75b390d".to_string())
+        );
+        assert_eq!(
+            config.cfg_map()[&VergenKey::Branch],
+            Some("provided".to_string())
+        );
+        assert_eq!(
+            config.cfg_map()[&VergenKey::RustcChannel],
+            Some("stable".to_string())
+        );
+
+        instructions.override_value("VERGEN_GIT_SHAA", "75b390d");
+        let errors = instructions.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field(), "value_overrides");
+        assert_eq!(errors[0].value(), "\"VERGEN_GIT_SHAA\"");
+    }
+
     #[test]
     fn default_instructions() {
         let default = Instructions::default();
@@ -363,5 +5117,93 @@ mod test {
         check_git_config(&default);
         check_rustc_config(&default);
         check_sysinfo_config(&default);
+        assert!(*default.env_overrides());
+        assert!(*default.docs_rs_placeholder());
+        assert!(!*default.single_threaded());
+        assert_eq!(*default.warnings(), WarningLevel::All);
+        assert!(default.emit_link_section().is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_json(instructions: &Instructions) -> String {
+        serde_json::to_string_pretty(instructions).unwrap()
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut instructions = Instructions::default();
+        *instructions.banner_format_mut() = Some("{name} {semver}".to_string());
+        *instructions.warnings_mut() = WarningLevel::Errors;
+        #[cfg(feature = "git")]
+        {
+            use crate::TimestampKind;
+
+            *instructions.git_mut().semver_dirty_mut() = Some("-dirty");
+            *instructions.git_mut().commit_timestamp_kind_mut() = TimestampKind::DateOnly;
+        }
+
+        let json = to_json(&instructions);
+        let round_trip: Instructions = serde_json::from_str(&json).unwrap();
+        assert_eq!(to_json(&round_trip), json);
+    }
+
+    #[cfg(all(feature = "serde", feature = "git"))]
+    #[test]
+    fn serde_enum_names() {
+        use crate::{TimeZone, TimestampKind};
+
+        let instructions: Instructions = serde_json::from_str(
+            r#"{ "git": { "commit_timestamp_kind": "date_and_time", "commit_timestamp_timezone": "local" } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            *instructions.git().commit_timestamp_kind(),
+            TimestampKind::DateAndTime
+        );
+        assert_eq!(
+            *instructions.git().commit_timestamp_timezone(),
+            TimeZone::Local
+        );
+        assert!(serde_json::from_str::<Instructions>(
+            r#"{ "git": { "commit_timestamp_kind": "DateOnly" } }"#
+        )
+        .is_err());
+    }
+
+    #[cfg(all(feature = "serde", not(feature = "serde-ignore-unknown")))]
+    #[test]
+    fn serde_unknown_fields_error() {
+        assert!(serde_json::from_str::<Instructions>(r#"{ "bogus": true }"#).is_err());
+        assert!(
+            serde_json::from_str::<Instructions>(r#"{ "sticky": { "bogus": true } }"#).is_err()
+        );
+    }
+
+    #[cfg(feature = "serde-ignore-unknown")]
+    #[test]
+    fn serde_unknown_fields_ignored() {
+        assert!(serde_json::from_str::<Instructions>(r#"{ "bogus": true }"#).is_ok());
+        assert!(serde_json::from_str::<Instructions>(r#"{ "sticky": { "bogus": true } }"#).is_ok());
+    }
+
+    #[cfg(all(
+        feature = "serde",
+        feature = "build",
+        feature = "cargo",
+        feature = "git",
+        feature = "rustc",
+        feature = "si",
+        not(feature = "hg")
+    ))]
+    #[test]
+    fn serde_default_snapshot() {
+        let mut instructions = Instructions::default();
+        // The base directory is the current directory, which differs between machines
+        *instructions.git_mut().base_dir_mut() = None;
+        assert_eq!(
+            to_json(&instructions),
+            include_str!("../testdata/default_config.json").trim_end()
+        );
     }
 }
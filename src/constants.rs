@@ -8,11 +8,21 @@
 
 //! Internal Constants
 
+// Placeholder value for docs.rs builds
+pub(crate) const PLACEHOLDER: &str = "VERGEN_IDEMPOTENT_OUTPUT";
+// Default placeholder value for the values that are unavailable
+pub(crate) const UNAVAILABLE: &str = "VERGEN_UNAVAILABLE";
+
 // Build Constants
 pub(crate) const BUILD_TIMESTAMP_NAME: &str = "VERGEN_BUILD_TIMESTAMP";
 pub(crate) const BUILD_DATE_NAME: &str = "VERGEN_BUILD_DATE";
 pub(crate) const BUILD_TIME_NAME: &str = "VERGEN_BUILD_TIME";
 pub(crate) const BUILD_SEMVER_NAME: &str = "VERGEN_BUILD_SEMVER";
+pub(crate) const BUILD_SEMVER_MAJOR_NAME: &str = "VERGEN_BUILD_SEMVER_MAJOR";
+pub(crate) const BUILD_SEMVER_MINOR_NAME: &str = "VERGEN_BUILD_SEMVER_MINOR";
+pub(crate) const BUILD_SEMVER_PATCH_NAME: &str = "VERGEN_BUILD_SEMVER_PATCH";
+pub(crate) const BUILD_SEMVER_PRE_NAME: &str = "VERGEN_BUILD_SEMVER_PRE";
+pub(crate) const BUILD_CPU_COUNT_NAME: &str = "VERGEN_BUILD_CPU_COUNT";
 
 // git Constants
 pub(crate) const GIT_BRANCH_NAME: &str = "VERGEN_GIT_BRANCH";
@@ -23,6 +33,22 @@ pub(crate) const GIT_SEMVER_NAME: &str = "VERGEN_GIT_SEMVER";
 pub(crate) const GIT_SEMVER_TAGS_NAME: &str = "VERGEN_GIT_SEMVER_LIGHTWEIGHT";
 pub(crate) const GIT_SHA_NAME: &str = "VERGEN_GIT_SHA";
 pub(crate) const GIT_SHA_SHORT_NAME: &str = "VERGEN_GIT_SHA_SHORT";
+pub(crate) const GIT_TRACKED_FILES_NAME: &str = "VERGEN_GIT_TRACKED_FILES";
+pub(crate) const GIT_COMMIT_AGE_SECONDS_NAME: &str = "VERGEN_GIT_COMMIT_AGE_SECONDS";
+pub(crate) const GIT_COMMITS_SINCE_TAG_NAME: &str = "VERGEN_GIT_COMMITS_SINCE_TAG";
+pub(crate) const GIT_COMMITS_SINCE_TAG_SUBJECTS_NAME: &str =
+    "VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS";
+pub(crate) const GIT_SIGNED_NAME: &str = "VERGEN_GIT_SIGNED";
+pub(crate) const GIT_SIGNER_NAME: &str = "VERGEN_GIT_SIGNER";
+pub(crate) const GIT_BRANCH_COUNT_NAME: &str = "VERGEN_GIT_BRANCH_COUNT";
+pub(crate) const GIT_TAG_COUNT_NAME: &str = "VERGEN_GIT_TAG_COUNT";
+pub(crate) const GIT_ROOT_NAME: &str = "VERGEN_GIT_ROOT";
+pub(crate) const GIT_WORKTREE_NAME: &str = "VERGEN_GIT_WORKTREE";
+pub(crate) const GIT_TREE_HASH_NAME: &str = "VERGEN_GIT_TREE_HASH";
+pub(crate) const GIT_CONTRIBUTOR_COUNT_NAME: &str = "VERGEN_GIT_CONTRIBUTOR_COUNT";
+pub(crate) const GIT_MERGE_BASE_NAME: &str = "VERGEN_GIT_MERGE_BASE";
+pub(crate) const GIT_OBJECT_FORMAT_NAME: &str = "VERGEN_GIT_OBJECT_FORMAT";
+pub(crate) const GIT_PARENT_COUNT_NAME: &str = "VERGEN_GIT_PARENT_COUNT";
 
 // rustc Constants
 pub(crate) const RUSTC_CHANNEL_NAME: &str = "VERGEN_RUSTC_CHANNEL";
@@ -36,6 +62,30 @@ pub(crate) const RUSTC_LLVM_VERSION: &str = "VERGEN_RUSTC_LLVM_VERSION";
 pub(crate) const CARGO_TARGET_TRIPLE: &str = "VERGEN_CARGO_TARGET_TRIPLE";
 pub(crate) const CARGO_PROFILE: &str = "VERGEN_CARGO_PROFILE";
 pub(crate) const CARGO_FEATURES: &str = "VERGEN_CARGO_FEATURES";
+pub(crate) const CARGO_PKG_DESCRIPTION: &str = "VERGEN_CARGO_PKG_DESCRIPTION";
+pub(crate) const CARGO_PKG_AUTHORS: &str = "VERGEN_CARGO_PKG_AUTHORS";
+pub(crate) const CARGO_PKG_HOMEPAGE: &str = "VERGEN_CARGO_PKG_HOMEPAGE";
+pub(crate) const CARGO_PKG_REPOSITORY: &str = "VERGEN_CARGO_PKG_REPOSITORY";
+pub(crate) const CARGO_PKG_LICENSE: &str = "VERGEN_CARGO_PKG_LICENSE";
+pub(crate) const CARGO_PKG_LICENSE_FILE: &str = "VERGEN_CARGO_PKG_LICENSE_FILE";
+pub(crate) const CARGO_TARGET_OS: &str = "VERGEN_CARGO_TARGET_OS";
+pub(crate) const CARGO_TARGET_ARCH: &str = "VERGEN_CARGO_TARGET_ARCH";
+pub(crate) const CARGO_TARGET_ENV: &str = "VERGEN_CARGO_TARGET_ENV";
+pub(crate) const CARGO_TARGET_POINTER_WIDTH: &str = "VERGEN_CARGO_TARGET_POINTER_WIDTH";
+pub(crate) const CARGO_TARGET_ENDIAN: &str = "VERGEN_CARGO_TARGET_ENDIAN";
+pub(crate) const CARGO_LIBC_VERSION: &str = "VERGEN_CARGO_LIBC_VERSION";
+
+// target Constants
+pub(crate) const TARGET_TRIPLE_NAME: &str = "VERGEN_TARGET_TRIPLE";
+pub(crate) const TARGET_OS_NAME: &str = "VERGEN_TARGET_OS";
+pub(crate) const TARGET_ARCH_NAME: &str = "VERGEN_TARGET_ARCH";
+pub(crate) const TARGET_ENV_NAME: &str = "VERGEN_TARGET_ENV";
+pub(crate) const IS_CROSS_COMPILE_NAME: &str = "VERGEN_IS_CROSS_COMPILE";
+
+// hg Constants
+pub(crate) const HG_BRANCH_NAME: &str = "VERGEN_HG_BRANCH";
+pub(crate) const HG_REV_NAME: &str = "VERGEN_HG_REV";
+pub(crate) const HG_SHA_NAME: &str = "VERGEN_HG_SHA";
 
 // sysinfo Constants
 pub(crate) const SYSINFO_NAME: &str = "VERGEN_SYSINFO_NAME";
@@ -48,15 +98,33 @@ pub(crate) const SYSINFO_CPU_NAME: &str = "VERGEN_SYSINFO_CPU_NAME";
 pub(crate) const SYSINFO_CPU_BRAND: &str = "VERGEN_SYSINFO_CPU_BRAND";
 pub(crate) const SYSINFO_CPU_FREQUENCY: &str = "VERGEN_SYSINFO_CPU_FREQUENCY";
 
+// banner Constants
+pub(crate) const BANNER_NAME: &str = "VERGEN_BANNER";
+pub(crate) const HASH_NAME: &str = "VERGEN_HASH";
+pub(crate) const SCRIPT_DURATION_NAME: &str = "VERGEN_BUILD_SCRIPT_DURATION_MS";
+pub(crate) const BUILD_NUMBER_NAME: &str = "VERGEN_BUILD_NUMBER";
+pub(crate) const DEPENDENCY_LICENSES_NAME: &str = "VERGEN_DEPENDENCY_LICENSES";
+pub(crate) const ENABLED_FEATURES_NAME: &str = "VERGEN_ENABLED_FEATURES";
+pub(crate) const PROVENANCE_JSON_NAME: &str = "VERGEN_PROVENANCE_JSON";
+pub(crate) const RELEASE_URL_NAME: &str = "VERGEN_RELEASE_URL";
+
 #[cfg(test)]
 mod test {
     use super::*;
 
     #[test]
     fn constants_dont_change() {
+        assert_eq!(PLACEHOLDER, "VERGEN_IDEMPOTENT_OUTPUT");
+        assert_eq!(UNAVAILABLE, "VERGEN_UNAVAILABLE");
+
         // Build Constants
         assert_eq!(BUILD_TIMESTAMP_NAME, "VERGEN_BUILD_TIMESTAMP");
         assert_eq!(BUILD_DATE_NAME, "VERGEN_BUILD_DATE");
+        assert_eq!(BUILD_SEMVER_MAJOR_NAME, "VERGEN_BUILD_SEMVER_MAJOR");
+        assert_eq!(BUILD_SEMVER_MINOR_NAME, "VERGEN_BUILD_SEMVER_MINOR");
+        assert_eq!(BUILD_SEMVER_PATCH_NAME, "VERGEN_BUILD_SEMVER_PATCH");
+        assert_eq!(BUILD_SEMVER_PRE_NAME, "VERGEN_BUILD_SEMVER_PRE");
+        assert_eq!(BUILD_CPU_COUNT_NAME, "VERGEN_BUILD_CPU_COUNT");
 
         // git Constants
         assert_eq!(GIT_BRANCH_NAME, "VERGEN_GIT_BRANCH");
@@ -65,6 +133,24 @@ mod test {
         assert_eq!(GIT_COMMIT_DATE_NAME, "VERGEN_GIT_COMMIT_DATE");
         assert_eq!(GIT_SEMVER_NAME, "VERGEN_GIT_SEMVER");
         assert_eq!(GIT_SEMVER_TAGS_NAME, "VERGEN_GIT_SEMVER_LIGHTWEIGHT");
+        assert_eq!(GIT_TRACKED_FILES_NAME, "VERGEN_GIT_TRACKED_FILES");
+        assert_eq!(GIT_COMMIT_AGE_SECONDS_NAME, "VERGEN_GIT_COMMIT_AGE_SECONDS");
+        assert_eq!(GIT_COMMITS_SINCE_TAG_NAME, "VERGEN_GIT_COMMITS_SINCE_TAG");
+        assert_eq!(
+            GIT_COMMITS_SINCE_TAG_SUBJECTS_NAME,
+            "VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS"
+        );
+        assert_eq!(GIT_SIGNED_NAME, "VERGEN_GIT_SIGNED");
+        assert_eq!(GIT_SIGNER_NAME, "VERGEN_GIT_SIGNER");
+        assert_eq!(GIT_BRANCH_COUNT_NAME, "VERGEN_GIT_BRANCH_COUNT");
+        assert_eq!(GIT_TAG_COUNT_NAME, "VERGEN_GIT_TAG_COUNT");
+        assert_eq!(GIT_ROOT_NAME, "VERGEN_GIT_ROOT");
+        assert_eq!(GIT_WORKTREE_NAME, "VERGEN_GIT_WORKTREE");
+        assert_eq!(GIT_TREE_HASH_NAME, "VERGEN_GIT_TREE_HASH");
+        assert_eq!(GIT_CONTRIBUTOR_COUNT_NAME, "VERGEN_GIT_CONTRIBUTOR_COUNT");
+        assert_eq!(GIT_MERGE_BASE_NAME, "VERGEN_GIT_MERGE_BASE");
+        assert_eq!(GIT_OBJECT_FORMAT_NAME, "VERGEN_GIT_OBJECT_FORMAT");
+        assert_eq!(GIT_PARENT_COUNT_NAME, "VERGEN_GIT_PARENT_COUNT");
 
         // rustc Constants
         assert_eq!(RUSTC_SEMVER_NAME, "VERGEN_RUSTC_SEMVER");
@@ -76,8 +162,35 @@ mod test {
 
         // cargo Constants
         assert_eq!(CARGO_TARGET_TRIPLE, "VERGEN_CARGO_TARGET_TRIPLE");
+        assert_eq!(CARGO_PKG_DESCRIPTION, "VERGEN_CARGO_PKG_DESCRIPTION");
+        assert_eq!(CARGO_PKG_AUTHORS, "VERGEN_CARGO_PKG_AUTHORS");
+        assert_eq!(CARGO_PKG_HOMEPAGE, "VERGEN_CARGO_PKG_HOMEPAGE");
+        assert_eq!(CARGO_PKG_REPOSITORY, "VERGEN_CARGO_PKG_REPOSITORY");
+        assert_eq!(CARGO_PKG_LICENSE, "VERGEN_CARGO_PKG_LICENSE");
+        assert_eq!(CARGO_PKG_LICENSE_FILE, "VERGEN_CARGO_PKG_LICENSE_FILE");
         assert_eq!(CARGO_PROFILE, "VERGEN_CARGO_PROFILE");
         assert_eq!(CARGO_FEATURES, "VERGEN_CARGO_FEATURES");
+        assert_eq!(CARGO_TARGET_OS, "VERGEN_CARGO_TARGET_OS");
+        assert_eq!(CARGO_TARGET_ARCH, "VERGEN_CARGO_TARGET_ARCH");
+        assert_eq!(CARGO_TARGET_ENV, "VERGEN_CARGO_TARGET_ENV");
+        assert_eq!(
+            CARGO_TARGET_POINTER_WIDTH,
+            "VERGEN_CARGO_TARGET_POINTER_WIDTH"
+        );
+        assert_eq!(CARGO_TARGET_ENDIAN, "VERGEN_CARGO_TARGET_ENDIAN");
+        assert_eq!(CARGO_LIBC_VERSION, "VERGEN_CARGO_LIBC_VERSION");
+
+        // target Constants
+        assert_eq!(TARGET_TRIPLE_NAME, "VERGEN_TARGET_TRIPLE");
+        assert_eq!(TARGET_OS_NAME, "VERGEN_TARGET_OS");
+        assert_eq!(TARGET_ARCH_NAME, "VERGEN_TARGET_ARCH");
+        assert_eq!(TARGET_ENV_NAME, "VERGEN_TARGET_ENV");
+        assert_eq!(IS_CROSS_COMPILE_NAME, "VERGEN_IS_CROSS_COMPILE");
+
+        // hg Constants
+        assert_eq!(HG_BRANCH_NAME, "VERGEN_HG_BRANCH");
+        assert_eq!(HG_REV_NAME, "VERGEN_HG_REV");
+        assert_eq!(HG_SHA_NAME, "VERGEN_HG_SHA");
 
         // sysinfo Constants
         assert_eq!(SYSINFO_NAME, "VERGEN_SYSINFO_NAME");
@@ -89,5 +202,15 @@ mod test {
         assert_eq!(SYSINFO_CPU_NAME, "VERGEN_SYSINFO_CPU_NAME");
         assert_eq!(SYSINFO_CPU_BRAND, "VERGEN_SYSINFO_CPU_BRAND");
         assert_eq!(SYSINFO_CPU_FREQUENCY, "VERGEN_SYSINFO_CPU_FREQUENCY");
+
+        // banner Constants
+        assert_eq!(BANNER_NAME, "VERGEN_BANNER");
+        assert_eq!(HASH_NAME, "VERGEN_HASH");
+        assert_eq!(SCRIPT_DURATION_NAME, "VERGEN_BUILD_SCRIPT_DURATION_MS");
+        assert_eq!(BUILD_NUMBER_NAME, "VERGEN_BUILD_NUMBER");
+        assert_eq!(DEPENDENCY_LICENSES_NAME, "VERGEN_DEPENDENCY_LICENSES");
+        assert_eq!(ENABLED_FEATURES_NAME, "VERGEN_ENABLED_FEATURES");
+        assert_eq!(PROVENANCE_JSON_NAME, "VERGEN_PROVENANCE_JSON");
+        assert_eq!(RELEASE_URL_NAME, "VERGEN_RELEASE_URL");
     }
 }
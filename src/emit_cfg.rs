@@ -0,0 +1,232 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` rustc-cfg flags
+
+use crate::{
+    config::Config,
+    error::ConfigError,
+    merge::{union, union_map},
+    sticky::find_key,
+    syntax::{write_directive, CargoSyntax},
+};
+use anyhow::Result;
+use std::{collections::BTreeMap, io::Write};
+
+/// Configuration for emitting boolean values as `cargo:rustc-cfg` flags
+///
+/// For each instruction named in the `keys` field, a `cargo:rustc-check-cfg=cfg(<name>)` instruction
+/// is generated so cargo doesn't warn about an unexpected cfg, and a `cargo:rustc-cfg=<name>` instruction
+/// is generated when the value is `true`.  This allows `#[cfg(vergen_...)]` rather than parsing
+/// the environment variable at runtime.
+///
+/// * The cfg name defaults to the lower case instruction name, i.e. `VERGEN_GIT_SIGNED` becomes `vergen_git_signed`.
+/// * Use the `names` field to override the cfg name for an instruction.
+/// * **NOTE** - Any value other than `true` is false.
+///
+/// # Example
+///
+/// ```
+/// use vergen::Config;
+///
+/// let mut config = Config::default();
+/// config.emit_cfg_mut().keys_mut().push("VERGEN_GIT_SIGNED".to_string());
+/// let _ = config
+///     .emit_cfg_mut()
+///     .names_mut()
+///     .insert("VERGEN_GIT_SIGNED".to_string(), "signed_release".to_string());
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
+pub struct EmitCfg {
+    /// The instructions to emit as cfg flags.
+    keys: Vec<String>,
+    /// The cfg name to use for an instruction, instead of the lower case instruction name.
+    names: BTreeMap<String, String>,
+}
+
+impl EmitCfg {
+    /// The instructions to emit as cfg flags.
+    #[cfg(test)]
+    pub(crate) fn keys(&self) -> &Vec<String> {
+        &self.keys
+    }
+
+    /// The instructions to emit as cfg flags.
+    pub fn keys_mut(&mut self) -> &mut Vec<String> {
+        &mut self.keys
+    }
+
+    /// The cfg name to use for an instruction, instead of the lower case instruction name.
+    #[cfg(test)]
+    pub(crate) fn names(&self) -> &BTreeMap<String, String> {
+        &self.names
+    }
+
+    /// The cfg name to use for an instruction, instead of the lower case instruction name.
+    pub fn names_mut(&mut self) -> &mut BTreeMap<String, String> {
+        &mut self.names
+    }
+}
+
+impl EmitCfg {
+    pub(crate) fn merge(self, overlay: Self) -> Self {
+        let default = Self::default();
+        Self {
+            keys: union(self.keys, overlay.keys, &default.keys),
+            names: union_map(self.names, overlay.names),
+        }
+    }
+
+    pub(crate) fn validate(&self, errors: &mut Vec<ConfigError>) {
+        for name in self.keys.iter().filter(|name| find_key(name).is_none()) {
+            errors.push(ConfigError::new(
+                "emit_cfg.keys",
+                format!("{:?}", name),
+                "use the name of a generated instruction, i.e. 'VERGEN_GIT_SHA'",
+            ));
+        }
+
+        for cfg in self.names.values().filter(|cfg| !is_ident(cfg)) {
+            errors.push(ConfigError::new(
+                "emit_cfg.names",
+                format!("{:?}", cfg),
+                "use a valid rust identifier, i.e. 'vergen_sha'",
+            ));
+        }
+    }
+
+    fn cfg_name(&self, name: &str) -> String {
+        self.names
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_ascii_lowercase())
+    }
+}
+
+fn is_ident(cfg: &str) -> bool {
+    let mut chars = cfg.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && cfg != "_"
+}
+
+pub(crate) fn output_emit_cfg<T>(
+    emit_cfg: &EmitCfg,
+    syntax: CargoSyntax,
+    config: &Config,
+    stdout: &mut T,
+) -> Result<()>
+where
+    T: Write,
+{
+    for name in &emit_cfg.keys {
+        let cfg = emit_cfg.cfg_name(name);
+        write_directive(stdout, syntax, "rustc-check-cfg", format!("cfg({})", cfg))?;
+
+        let value = find_key(name).and_then(|key| config.cfg_map().get(&key).cloned().flatten());
+        if value.as_deref() == Some("true") {
+            write_directive(stdout, syntax, "rustc-cfg", &cfg)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{output_emit_cfg, EmitCfg};
+    use crate::{
+        config::{Config, VergenKey},
+        syntax::CargoSyntax,
+    };
+
+    fn output(emit_cfg: &EmitCfg, value: &str) -> String {
+        let mut config = Config::default();
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Sha, Some(value.to_string()));
+        let mut stdout_buf = vec![];
+        output_emit_cfg(emit_cfg, CargoSyntax::Legacy, &config, &mut stdout_buf).unwrap();
+        String::from_utf8(stdout_buf).unwrap()
+    }
+
+    #[test]
+    fn true_emits_cfg() {
+        let mut emit_cfg = EmitCfg::default();
+        emit_cfg.keys_mut().push("VERGEN_GIT_SHA".to_string());
+        assert_eq!(
+            output(&emit_cfg, "true"),
+            "cargo:rustc-check-cfg=cfg(vergen_git_sha)\ncargo:rustc-cfg=vergen_git_sha\n"
+        );
+    }
+
+    #[test]
+    fn false_only_checks_cfg() {
+        let mut emit_cfg = EmitCfg::default();
+        emit_cfg.keys_mut().push("VERGEN_GIT_SHA".to_string());
+        assert_eq!(
+            output(&emit_cfg, "false"),
+            "cargo:rustc-check-cfg=cfg(vergen_git_sha)\n"
+        );
+        assert_eq!(
+            output(&emit_cfg, "abc123"),
+            "cargo:rustc-check-cfg=cfg(vergen_git_sha)\n"
+        );
+    }
+
+    #[test]
+    fn names_override() {
+        let mut emit_cfg = EmitCfg::default();
+        emit_cfg.keys_mut().push("VERGEN_GIT_SHA".to_string());
+        let _ = emit_cfg
+            .names_mut()
+            .insert("VERGEN_GIT_SHA".to_string(), "has_sha".to_string());
+        assert_eq!(
+            output(&emit_cfg, "true"),
+            "cargo:rustc-check-cfg=cfg(has_sha)\ncargo:rustc-cfg=has_sha\n"
+        );
+    }
+
+    #[test]
+    fn modern_syntax() {
+        let mut emit_cfg = EmitCfg::default();
+        emit_cfg.keys_mut().push("VERGEN_GIT_SHA".to_string());
+        let mut config = Config::default();
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Sha, Some("true".to_string()));
+        let mut stdout_buf = vec![];
+        output_emit_cfg(&emit_cfg, CargoSyntax::Modern, &config, &mut stdout_buf).unwrap();
+        assert_eq!(
+            String::from_utf8(stdout_buf).unwrap(),
+            "cargo::rustc-check-cfg=cfg(vergen_git_sha)\ncargo::rustc-cfg=vergen_git_sha\n"
+        );
+    }
+
+    #[test]
+    fn invalid_keys_and_names() {
+        let mut emit_cfg = EmitCfg::default();
+        emit_cfg.keys_mut().push("VERGEN_GIT_SHAA".to_string());
+        let _ = emit_cfg
+            .names_mut()
+            .insert("VERGEN_GIT_SHA".to_string(), "has sha".to_string());
+        let mut errors = vec![];
+        emit_cfg.validate(&mut errors);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].field(), "emit_cfg.keys");
+        assert_eq!(errors[1].field(), "emit_cfg.names");
+    }
+}
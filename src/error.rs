@@ -13,6 +13,7 @@ use std::fmt;
 enum ErrKind {
     Protocol,
     Env,
+    Config,
 }
 
 impl fmt::Display for ErrKind {
@@ -20,6 +21,7 @@ impl fmt::Display for ErrKind {
         let err_kind = match self {
             Self::Protocol => "protocol",
             Self::Env => "env",
+            Self::Config => "config",
         };
         write!(f, "{}", err_kind)
     }
@@ -29,9 +31,30 @@ impl fmt::Display for ErrKind {
 #[derive(Debug, thiserror::Error)]
 pub(crate) enum Error {
     /// An error from the `git2` library
-    #[cfg(feature = "git")]
+    #[cfg(all(feature = "git", not(target_os = "wasi")))]
     #[error("{}: An error occurred in the 'git2' library: {}", ErrKind::Protocol, .0)]
     Git2(#[from] git2::Error),
+    /// An error running `git`
+    #[cfg(all(feature = "git", not(target_os = "wasi")))]
+    #[error("{}: An error occurred running 'git': {}", ErrKind::Protocol, .0)]
+    Git(String),
+    /// The version tag on `HEAD` doesn't match `CARGO_PKG_VERSION`
+    #[cfg(feature = "git")]
+    #[error(
+        "{}: The '{}' tag on HEAD doesn't match the CARGO_PKG_VERSION '{}'",
+        ErrKind::Config,
+        tag,
+        version
+    )]
+    TagVersion { tag: String, version: String },
+    /// An error running `hg`
+    #[cfg(feature = "hg")]
+    #[error("{}: An error occurred running 'hg': {}", ErrKind::Protocol, .0)]
+    Hg(String),
+    /// An error running `cargo metadata` for the dependency licenses
+    #[cfg(feature = "licenses")]
+    #[error("{}: An error occurred running 'cargo metadata': {}", ErrKind::Protocol, .0)]
+    Licenses(String),
     /// An error writing the cargo instructions to stdout
     #[error("{}: There was an error writing the cargo instructions to stdout: {}", ErrKind::Protocol, .0)]
     Io(#[from] std::io::Error),
@@ -39,9 +62,57 @@ pub(crate) enum Error {
     #[error("{}: An error occurred in the 'rustc_version' library: {}", ErrKind::Protocol, .0)]
     #[cfg(feature = "rustc")]
     RustcVersion(#[from] rustc_version::Error),
+    /// An error reading the `rustc -vV` output from the `version_override` file
+    #[cfg(feature = "rustc")]
+    #[error(
+        "{}: Unable to read the 'rustc -vV' output from '{}': {}",
+        ErrKind::Config,
+        path,
+        source
+    )]
+    RustcOverride {
+        path: String,
+        source: std::io::Error,
+    },
+    /// An error reading the overrides file
+    #[error(
+        "{}: Unable to read the overrides file '{}': {}",
+        ErrKind::Config,
+        path,
+        source
+    )]
+    OverridesFile {
+        path: String,
+        source: std::io::Error,
+    },
     /// An error getting the 'CARGO_PKG_VERSION' environment variable
     #[error("{}: The 'CARGO_PKG_VERSION' environment variable may not be set: {}", ErrKind::Env, .0)]
     Var(#[from] std::env::VarError),
+    /// An error getting the 'OUT_DIR' environment variable
+    #[error("{}: The 'OUT_DIR' environment variable may not be set: {}", ErrKind::Env, .0)]
+    OutDir(std::env::VarError),
+    /// A generated value would start a new cargo instruction
+    #[error("{}: The '{}' value would start a new cargo instruction", ErrKind::Protocol, .0)]
+    UnsafeValue(String),
+    /// An instruction value was generated more than once, with the `Error` duplicate policy
+    #[error("{}: The '{}' value(s) were generated more than once", ErrKind::Config, .0)]
+    DuplicateKey(String),
+    /// An earlier `vergen` call in this build script already emitted an instruction, with the `Error` duplicate policy
+    #[error(
+        "{}: vergen was already called in this build script, and emitted the '{}' value(s)",
+        ErrKind::Config,
+        .0
+    )]
+    RepeatedCall(String),
+    /// Emitted instruction names from different sources are the same, ignoring case, with the `Error` duplicate policy
+    #[error("{}: The instruction names are the same, ignoring case: {}", ErrKind::Config, .0)]
+    KeyCollision(String),
+    /// A command added with `add_command_key` failed, without the `emit_all_with_placeholders` field set
+    #[error("{}: Unable to generate '{}', {}", ErrKind::Protocol, name, reason)]
+    Command { name: String, reason: String },
+    /// The configuration failed validation
+    #[error("{}: The configuration is invalid: {}", ErrKind::Config, display_errors(.0))]
+    Validation(Vec<ConfigError>),
     /// An error getting the current pid
     #[cfg(feature = "si")]
     #[error(
@@ -49,14 +120,78 @@ pub(crate) enum Error {
         ErrKind::Protocol,
         msg
     )]
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(not(any(target_os = "macos", target_os = "wasi")))]
     Pid { msg: &'static str },
 }
 
+fn display_errors(errors: &[ConfigError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join("; ")
+}
+
+/// A configuration problem found by [`validate`](crate::Config::validate)
+///
+/// Each error names the offending field, the invalid value, and a suggestion for fixing it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigError {
+    /// The path of the offending field, i.e. `git.semver_dirty`
+    field: String,
+    /// The invalid value
+    value: String,
+    /// A suggestion for fixing the problem
+    suggestion: String,
+}
+
+impl ConfigError {
+    /// The path of the offending field, i.e. `git.semver_dirty`
+    #[must_use]
+    pub fn field(&self) -> &String {
+        &self.field
+    }
+
+    /// The invalid value
+    #[must_use]
+    pub fn value(&self) -> &String {
+        &self.value
+    }
+
+    /// A suggestion for fixing the problem
+    #[must_use]
+    pub fn suggestion(&self) -> &String {
+        &self.suggestion
+    }
+}
+
+impl ConfigError {
+    pub(crate) fn new<T, U, V>(field: T, value: U, suggestion: V) -> Self
+    where
+        T: Into<String>,
+        U: Into<String>,
+        V: Into<String>,
+    {
+        Self {
+            field: field.into(),
+            value: value.into(),
+            suggestion: suggestion.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' = {} ({})", self.field, self.value, self.suggestion)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
 #[cfg(test)]
 mod test {
-    use super::Error;
-    #[cfg(feature = "git")]
+    use super::{ConfigError, Error};
+    #[cfg(all(feature = "git", not(target_os = "wasi")))]
     use git2::Repository;
     #[cfg(feature = "rustc")]
     use rustc_version::version_meta_for;
@@ -86,7 +221,7 @@ mod test {
         );
     }
 
-    #[cfg(feature = "git")]
+    #[cfg(all(feature = "git", not(target_os = "wasi")))]
     #[test]
     fn git2_error() {
         let res = Repository::open("blah").map_err(|e| Error::from(e));
@@ -98,7 +233,20 @@ mod test {
         assert_eq!("protocol: An error occurred in the \'git2\' library: failed to resolve path \'blah\': The system cannot find the file specified.\r\n; class=Os (2); code=NotFound (-3)", format!("{}", err));
     }
 
-    #[cfg(all(feature = "si", not(target_os = "macos")))]
+    #[cfg(feature = "git")]
+    #[test]
+    fn tag_version_error() {
+        let err = Error::TagVersion {
+            tag: "v1.4.0".to_string(),
+            version: "1.3.2".to_string(),
+        };
+        assert_eq!(
+            "config: The \'v1.4.0\' tag on HEAD doesn't match the CARGO_PKG_VERSION \'1.3.2\'",
+            format!("{}", err)
+        );
+    }
+
+    #[cfg(all(feature = "si", not(target_os = "macos"), not(target_os = "wasi")))]
     #[test]
     fn pid_error() {
         let err: Error = Error::Pid { msg: "test" };
@@ -118,4 +266,27 @@ mod test {
             format!("{}", err)
         );
     }
+
+    #[test]
+    fn out_dir_error() {
+        let res = env::var("yoda").map_err(Error::OutDir);
+        assert!(res.is_err());
+        let err = res.err().unwrap();
+        assert_eq!(
+            "env: The \'OUT_DIR\' environment variable may not be set: environment variable not found",
+            format!("{}", err)
+        );
+    }
+
+    #[test]
+    fn validation_error() {
+        let err = Error::Validation(vec![
+            ConfigError::new("a.b", "1", "fix a.b"),
+            ConfigError::new("c", "true", "fix c"),
+        ]);
+        assert_eq!(
+            "config: The configuration is invalid: \'a.b\' = 1 (fix a.b); \'c\' = true (fix c)",
+            format!("{}", err)
+        );
+    }
 }
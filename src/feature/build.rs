@@ -9,15 +9,22 @@
 //! `vergen` build feature implementation
 
 use crate::config::{Config, Instructions};
+use std::{path::Path, time::Instant};
 #[cfg(feature = "build")]
 use {
     crate::{
+        built::version_parts,
         config::VergenKey,
-        feature::{add_entry, TimeZone, TimestampKind},
+        error::ConfigError,
+        feature::{
+            add_entry, add_entry_or_skip, add_placeholders, head_commit_time, TimeZone,
+            TimestampKind,
+        },
+        merge::{pick, SetFields},
+        provider::{now_local, now_utc, source_date_epoch},
     },
-    getset::{Getters, MutGetters},
     std::env,
-    time::{format_description, macros::format_description, OffsetDateTime},
+    time::{format_description::FormatItem, macros::format_description, OffsetDateTime, UtcOffset},
 };
 
 /// Configuration for the `VERGEN_BUILD_*` instructions
@@ -31,11 +38,33 @@ use {
 /// | `cargo:rustc-env=VERGEN_BUILD_TIME=11:22:34` | |
 /// | `cargo:rustc-env=VERGEN_BUILD_TIMESTAMP=2021-02-12T01:54:15.134750+00:00` | * |
 /// | `cargo:rustc-env=VERGEN_BUILD_SEMVER=4.2.0` | * |
+/// | `cargo:rustc-env=VERGEN_BUILD_SEMVER_MAJOR=4` | |
+/// | `cargo:rustc-env=VERGEN_BUILD_SEMVER_MINOR=2` | |
+/// | `cargo:rustc-env=VERGEN_BUILD_SEMVER_PATCH=0` | |
+/// | `cargo:rustc-env=VERGEN_BUILD_SEMVER_PRE=` | |
+/// | `cargo:rustc-env=VERGEN_BUILD_CPU_COUNT=8` | |
 ///
 /// * If the `timestamp` field is false, the date/time instructions will not be generated.
 /// * If the `semver` field is false, the semver instruction will not be generated.
+/// * If the `semver_components` field is true, the major, minor, patch, and pre-release instructions will be generated.
+///   Each is read from the `CARGO_PKG_VERSION_MAJOR`, `CARGO_PKG_VERSION_MINOR`, `CARGO_PKG_VERSION_PATCH`, or
+///   `CARGO_PKG_VERSION_PRE` environment variable set by cargo, and only parsed from `CARGO_PKG_VERSION` if that
+///   variable is missing.  The pre-release instruction is empty for a release version.
+/// * If the `timestamp_from_git` field is true, the date/time instructions use the time of the `HEAD` commit instead of
+///   the current time, so the build date is the commit date and doesn't change from one build to the next.
+/// * If the `timezone_from_git` field is true, the date/time instructions are shown in the timezone of the `HEAD`
+///   committer, i.e. with git's `%cz` offset, rather than the one of the `timezone` field.
+/// * If the `cpu_count` field is true, the number of logical CPUs the standard library reports is generated, without
+///   the `sysinfo` dependency of the `VERGEN_SYSINFO_CPU_CORE_COUNT` instruction.  It's empty if the number can't be
+///   read, or if `vergen` was compiled with a Rust older than 1.59.
 /// * **NOTE** - By default, the date/time related instructions will use [`UTC`](TimeZone::Utc).
+/// * **NOTE** - The `timestamp_from_git` field requires the `git` feature.  If the repository can't be found or read, a
+///   `cargo:warning` is emitted and the current time is used.  A `SOURCE_DATE_EPOCH` honored by the
+///   [`source_date_epoch`](crate::Config#reproducible-builds) field takes precedence.
+/// * **NOTE** - The `timezone_from_git` field requires the `git` feature as well.  If the repository can't be found or
+///   read, a `cargo:warning` is emitted and the `timezone` field is used.
 /// * **NOTE** - The date/time instruction output is determined by the [`kind`](TimestampKind) field and can be any combination of the three.
+/// * **NOTE** - The number of subsecond digits in the timestamp instruction is determined by the [`timestamp_precision`](Precision) field.
 ///
 /// # Example
 ///
@@ -61,8 +90,17 @@ vergen(config)?;
 /// # Ok(())
 /// # }
 #[cfg(feature = "build")]
-#[derive(Clone, Copy, Debug, Getters, MutGetters)]
-#[getset(get = "pub(crate)", get_mut = "pub")]
+#[derive(Clone, Copy, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
 pub struct Build {
     /// Enable/Disable the build output
     enabled: bool,
@@ -74,6 +112,206 @@ pub struct Build {
     kind: TimestampKind,
     /// Enable/Disable the `VERGEN_BUILD_SEMVER` instruction.
     semver: bool,
+    /// Enable/Disable the `VERGEN_BUILD_SEMVER_MAJOR`, `VERGEN_BUILD_SEMVER_MINOR`, `VERGEN_BUILD_SEMVER_PATCH`, and
+    /// `VERGEN_BUILD_SEMVER_PRE` instructions.
+    semver_components: bool,
+    /// The subsecond precision of the `VERGEN_BUILD_TIMESTAMP` instruction.
+    timestamp_precision: Precision,
+    /// Use the `HEAD` commit time, rather than the current time, for the date/time instructions.
+    timestamp_from_git: bool,
+    /// Use the `HEAD` committer's timezone, rather than the `timezone` field, for the date/time instructions.
+    timezone_from_git: bool,
+    /// Enable/Disable the `VERGEN_BUILD_CPU_COUNT` instruction.
+    cpu_count: bool,
+    /// The fields set through their `set_*` setter, which win in a [merge](crate::Config::merge)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    set_fields: SetFields,
+}
+
+#[cfg(feature = "build")]
+impl Build {
+    /// Enable/Disable the build output
+    pub(crate) fn enabled(&self) -> &bool {
+        &self.enabled
+    }
+
+    /// Enable/Disable the build output
+    pub fn enabled_mut(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+
+    /// Enable/Disable the build output
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "enabled");
+        self.enabled = enabled;
+    }
+
+    /// Enable/Disable the `VERGEN_BUILD_DATE`, `VERGEN_BUILD_TIME`, and `VERGEN_BUILD_TIMESTAMP` instructions.
+    pub(crate) fn timestamp(&self) -> &bool {
+        &self.timestamp
+    }
+
+    /// Enable/Disable the `VERGEN_BUILD_DATE`, `VERGEN_BUILD_TIME`, and `VERGEN_BUILD_TIMESTAMP` instructions.
+    pub fn timestamp_mut(&mut self) -> &mut bool {
+        &mut self.timestamp
+    }
+
+    /// Enable/Disable the `VERGEN_BUILD_DATE`, `VERGEN_BUILD_TIME`, and `VERGEN_BUILD_TIMESTAMP` instructions.
+    pub fn set_timestamp(&mut self, timestamp: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "timestamp");
+        self.timestamp = timestamp;
+    }
+
+    /// The timezone to use for the date/time instructions.
+    pub(crate) fn timezone(&self) -> &TimeZone {
+        &self.timezone
+    }
+
+    /// The timezone to use for the date/time instructions.
+    pub fn timezone_mut(&mut self) -> &mut TimeZone {
+        &mut self.timezone
+    }
+
+    /// The timezone to use for the date/time instructions.
+    pub fn set_timezone(&mut self, timezone: TimeZone) {
+        self.set_fields.mark(MERGED_FIELDS, "timezone");
+        self.timezone = timezone;
+    }
+
+    /// The kind of date/time instructions to output.
+    pub(crate) fn kind(&self) -> &TimestampKind {
+        &self.kind
+    }
+
+    /// The kind of date/time instructions to output.
+    pub fn kind_mut(&mut self) -> &mut TimestampKind {
+        &mut self.kind
+    }
+
+    /// The kind of date/time instructions to output.
+    pub fn set_kind(&mut self, kind: TimestampKind) {
+        self.set_fields.mark(MERGED_FIELDS, "kind");
+        self.kind = kind;
+    }
+
+    /// Enable/Disable the `VERGEN_BUILD_SEMVER` instruction.
+    pub(crate) fn semver(&self) -> &bool {
+        &self.semver
+    }
+
+    /// Enable/Disable the `VERGEN_BUILD_SEMVER` instruction.
+    pub fn semver_mut(&mut self) -> &mut bool {
+        &mut self.semver
+    }
+
+    /// Enable/Disable the `VERGEN_BUILD_SEMVER` instruction.
+    pub fn set_semver(&mut self, semver: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "semver");
+        self.semver = semver;
+    }
+
+    /// Enable/Disable the `VERGEN_BUILD_SEMVER_MAJOR`, `VERGEN_BUILD_SEMVER_MINOR`, `VERGEN_BUILD_SEMVER_PATCH`, and
+    /// `VERGEN_BUILD_SEMVER_PRE` instructions.
+    pub(crate) fn semver_components(&self) -> &bool {
+        &self.semver_components
+    }
+
+    /// Enable/Disable the `VERGEN_BUILD_SEMVER_MAJOR`, `VERGEN_BUILD_SEMVER_MINOR`, `VERGEN_BUILD_SEMVER_PATCH`, and
+    /// `VERGEN_BUILD_SEMVER_PRE` instructions.
+    pub fn semver_components_mut(&mut self) -> &mut bool {
+        &mut self.semver_components
+    }
+
+    /// Enable/Disable the `VERGEN_BUILD_SEMVER_MAJOR`, `VERGEN_BUILD_SEMVER_MINOR`, `VERGEN_BUILD_SEMVER_PATCH`, and
+    /// `VERGEN_BUILD_SEMVER_PRE` instructions.
+    pub fn set_semver_components(&mut self, semver_components: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "semver_components");
+        self.semver_components = semver_components;
+    }
+
+    /// The subsecond precision of the `VERGEN_BUILD_TIMESTAMP` instruction.
+    #[cfg(test)]
+    pub(crate) fn timestamp_precision(&self) -> &Precision {
+        &self.timestamp_precision
+    }
+
+    /// The subsecond precision of the `VERGEN_BUILD_TIMESTAMP` instruction.
+    pub fn timestamp_precision_mut(&mut self) -> &mut Precision {
+        &mut self.timestamp_precision
+    }
+
+    /// The subsecond precision of the `VERGEN_BUILD_TIMESTAMP` instruction.
+    pub fn set_timestamp_precision(&mut self, timestamp_precision: Precision) {
+        self.set_fields.mark(MERGED_FIELDS, "timestamp_precision");
+        self.timestamp_precision = timestamp_precision;
+    }
+
+    /// Use the `HEAD` commit time, rather than the current time, for the date/time instructions.
+    pub(crate) fn timestamp_from_git(&self) -> &bool {
+        &self.timestamp_from_git
+    }
+
+    /// Use the `HEAD` commit time, rather than the current time, for the date/time instructions.
+    pub fn timestamp_from_git_mut(&mut self) -> &mut bool {
+        &mut self.timestamp_from_git
+    }
+
+    /// Use the `HEAD` commit time, rather than the current time, for the date/time instructions.
+    pub fn set_timestamp_from_git(&mut self, timestamp_from_git: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "timestamp_from_git");
+        self.timestamp_from_git = timestamp_from_git;
+    }
+
+    /// Use the `HEAD` committer's timezone, rather than the `timezone` field, for the date/time instructions.
+    pub(crate) fn timezone_from_git(&self) -> &bool {
+        &self.timezone_from_git
+    }
+
+    /// Use the `HEAD` committer's timezone, rather than the `timezone` field, for the date/time instructions.
+    pub fn timezone_from_git_mut(&mut self) -> &mut bool {
+        &mut self.timezone_from_git
+    }
+
+    /// Use the `HEAD` committer's timezone, rather than the `timezone` field, for the date/time instructions.
+    pub fn set_timezone_from_git(&mut self, timezone_from_git: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "timezone_from_git");
+        self.timezone_from_git = timezone_from_git;
+    }
+
+    /// Enable/Disable the `VERGEN_BUILD_CPU_COUNT` instruction.
+    pub(crate) fn cpu_count(&self) -> &bool {
+        &self.cpu_count
+    }
+
+    /// Enable/Disable the `VERGEN_BUILD_CPU_COUNT` instruction.
+    pub fn cpu_count_mut(&mut self) -> &mut bool {
+        &mut self.cpu_count
+    }
+
+    /// Enable/Disable the `VERGEN_BUILD_CPU_COUNT` instruction.
+    pub fn set_cpu_count(&mut self, cpu_count: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "cpu_count");
+        self.cpu_count = cpu_count;
+    }
+}
+
+/// The subsecond precision of a timestamp
+#[cfg(feature = "build")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Precision {
+    /// Whole seconds, i.e. `2021-02-12T01:54:15+00:00`
+    Seconds,
+    /// Milliseconds, i.e. `2021-02-12T01:54:15.134+00:00`
+    Millis,
+    /// Microseconds, i.e. `2021-02-12T01:54:15.134750+00:00`
+    Micros,
+    /// Nanoseconds, i.e. `2021-02-12T01:54:15.134750213+00:00`
+    Nanos,
 }
 
 #[cfg(feature = "build")]
@@ -85,44 +323,272 @@ impl Default for Build {
             timezone: TimeZone::Utc,
             kind: TimestampKind::Timestamp,
             semver: true,
+            semver_components: false,
+            timestamp_precision: Precision::Micros,
+            timestamp_from_git: false,
+            timezone_from_git: false,
+            cpu_count: false,
+            set_fields: SetFields::default(),
         }
     }
 }
 
+/// The [`Build`] fields that win in a [merge](crate::Config::merge) once set, see [`SetFields`]
+#[cfg(feature = "build")]
+const MERGED_FIELDS: &[&str] = &[
+    "enabled",
+    "timestamp",
+    "timezone",
+    "kind",
+    "semver",
+    "semver_components",
+    "timestamp_precision",
+    "timestamp_from_git",
+    "timezone_from_git",
+    "cpu_count",
+];
+
 #[cfg(feature = "build")]
 impl Build {
+    pub(crate) fn merge(self, overlay: Self) -> Self {
+        let default = Self::default();
+        let overlay_set = overlay.set_fields;
+        let set = |name| overlay_set.contains(MERGED_FIELDS, name);
+        Self {
+            enabled: pick(
+                self.enabled,
+                overlay.enabled,
+                &default.enabled,
+                set("enabled"),
+            ),
+            timestamp: pick(
+                self.timestamp,
+                overlay.timestamp,
+                &default.timestamp,
+                set("timestamp"),
+            ),
+            timezone: pick(
+                self.timezone,
+                overlay.timezone,
+                &default.timezone,
+                set("timezone"),
+            ),
+            kind: pick(self.kind, overlay.kind, &default.kind, set("kind")),
+            semver: pick(self.semver, overlay.semver, &default.semver, set("semver")),
+            semver_components: pick(
+                self.semver_components,
+                overlay.semver_components,
+                &default.semver_components,
+                set("semver_components"),
+            ),
+            timestamp_precision: pick(
+                self.timestamp_precision,
+                overlay.timestamp_precision,
+                &default.timestamp_precision,
+                set("timestamp_precision"),
+            ),
+            timestamp_from_git: pick(
+                self.timestamp_from_git,
+                overlay.timestamp_from_git,
+                &default.timestamp_from_git,
+                set("timestamp_from_git"),
+            ),
+            timezone_from_git: pick(
+                self.timezone_from_git,
+                overlay.timezone_from_git,
+                &default.timezone_from_git,
+                set("timezone_from_git"),
+            ),
+            cpu_count: pick(
+                self.cpu_count,
+                overlay.cpu_count,
+                &default.cpu_count,
+                set("cpu_count"),
+            ),
+            set_fields: self.set_fields.union(overlay_set),
+        }
+    }
+
     pub(crate) fn has_enabled(self) -> bool {
-        self.enabled && (self.timestamp || self.semver)
+        self.enabled && (self.timestamp || self.semver || self.semver_components || self.cpu_count)
+    }
+
+    pub(crate) fn validate(self, errors: &mut Vec<ConfigError>) {
+        if self.enabled && !self.timestamp {
+            if self.kind != TimestampKind::Timestamp {
+                errors.push(ConfigError::new(
+                    "build.kind",
+                    format!("{:?}", self.kind),
+                    "set 'build.timestamp' to true, or leave 'build.kind' at the default",
+                ));
+            }
+            if self.timezone != TimeZone::Utc {
+                errors.push(ConfigError::new(
+                    "build.timezone",
+                    format!("{:?}", self.timezone),
+                    "set 'build.timestamp' to true, or leave 'build.timezone' at the default",
+                ));
+            }
+            if self.timestamp_precision != Precision::Micros {
+                errors.push(ConfigError::new(
+                    "build.timestamp_precision",
+                    format!("{:?}", self.timestamp_precision),
+                    "set 'build.timestamp' to true, or leave 'build.timestamp_precision' at the default",
+                ));
+            }
+            if self.timestamp_from_git {
+                errors.push(ConfigError::new(
+                    "build.timestamp_from_git",
+                    "true",
+                    "set 'build.timestamp' to true, or set 'build.timestamp_from_git' to false",
+                ));
+            }
+            if self.timezone_from_git {
+                errors.push(ConfigError::new(
+                    "build.timezone_from_git",
+                    "true",
+                    "set 'build.timestamp' to true, or set 'build.timezone_from_git' to false",
+                ));
+            }
+        }
+        if self.enabled && self.timestamp_from_git && cfg!(not(feature = "git")) {
+            errors.push(ConfigError::new(
+                "build.timestamp_from_git",
+                "true",
+                "enable the 'git' feature, or set 'build.timestamp_from_git' to false",
+            ));
+        }
+        if self.enabled && self.timezone_from_git && cfg!(not(feature = "git")) {
+            errors.push(ConfigError::new(
+                "build.timezone_from_git",
+                "true",
+                "enable the 'git' feature, or set 'build.timezone_from_git' to false",
+            ));
+        }
     }
 }
 
 #[cfg(feature = "build")]
-pub(crate) fn configure_build(instructions: &Instructions, config: &mut Config) {
+pub(crate) fn configure_build(
+    instructions: &Instructions,
+    repo_path: Option<&Path>,
+    deadline: Option<Instant>,
+    config: &mut Config,
+) {
     let build_config = instructions.build();
 
     if build_config.has_enabled() {
         if *build_config.timestamp() {
-            match build_config.timezone() {
-                TimeZone::Utc => {
-                    add_config_entries(config, *build_config, &OffsetDateTime::now_utc());
-                }
+            let timestamp_from_git =
+                *build_config.timestamp_from_git() && source_date_epoch(instructions).is_none();
+            // The repository is only opened once, for both the commit time and the committer's timezone
+            let head_time = if timestamp_from_git || *build_config.timezone_from_git() {
+                head_commit_time(instructions, repo_path, deadline)
+            } else {
+                None
+            };
+            let commit_time = if timestamp_from_git {
+                commit_time(head_time, config)
+            } else {
+                None
+            };
+            let now = match build_config.timezone() {
+                TimeZone::Utc => commit_time.map_or_else(
+                    || now_utc(instructions),
+                    |time| time.to_offset(UtcOffset::UTC),
+                ),
                 TimeZone::Local => {
-                    add_config_entries(
-                        config,
-                        *build_config,
-                        &OffsetDateTime::now_local().expect("unable to retrieve local datetime"),
-                    );
+                    let now = now_local(instructions, config);
+                    commit_time.map_or(now, |time| time.to_offset(now.offset()))
                 }
             };
+            let now = if *build_config.timezone_from_git() {
+                commit_offset(head_time, config).map_or(now, |offset| now.to_offset(offset))
+            } else {
+                now
+            };
+            add_config_entries(config, *build_config, &now);
         }
 
         if *build_config.semver() {
-            add_entry(
-                config.cfg_map_mut(),
+            add_entry_or_skip(
+                config,
                 VergenKey::BuildSemver,
                 env::var("CARGO_PKG_VERSION").ok(),
+                "CARGO_PKG_VERSION isn't set",
             );
         }
+
+        if *build_config.semver_components() {
+            add_semver_components(config);
+        }
+
+        if *build_config.cpu_count() {
+            add_entry(config, VergenKey::BuildCpuCount, Some(cpu_count()));
+        }
+    }
+}
+
+/// The number of logical CPUs, or empty if it can't be read
+#[cfg(all(feature = "build", available_parallelism))]
+fn cpu_count() -> String {
+    std::thread::available_parallelism()
+        .map(|count| count.to_string())
+        .unwrap_or_default()
+}
+
+/// The number of logical CPUs, always empty as `available_parallelism` is newer than this Rust
+#[cfg(all(feature = "build", not(available_parallelism)))]
+fn cpu_count() -> String {
+    String::new()
+}
+
+/// The `HEAD` commit time to use for the date/time instructions
+#[cfg(feature = "build")]
+fn commit_time(head_time: Option<OffsetDateTime>, config: &mut Config) -> Option<OffsetDateTime> {
+    if head_time.is_none() {
+        config.warnings_mut().push(
+            "vergen: build.timestamp_from_git couldn't read the HEAD commit time, the current time is used"
+                .to_string(),
+        );
+    }
+    head_time
+}
+
+/// The `HEAD` committer's timezone offset to show the date/time instructions in
+#[cfg(feature = "build")]
+fn commit_offset(head_time: Option<OffsetDateTime>, config: &mut Config) -> Option<UtcOffset> {
+    if head_time.is_none() {
+        config.warnings_mut().push(
+            "vergen: build.timezone_from_git couldn't read the HEAD commit timezone, the configured timezone is used"
+                .to_string(),
+        );
+    }
+    head_time.map(OffsetDateTime::offset)
+}
+
+/// The variables cargo sets for each component of the version, in the order of [`version_parts`]
+#[cfg(feature = "build")]
+const SEMVER_COMPONENTS: [(VergenKey, &str); 4] = [
+    (VergenKey::BuildSemverMajor, "CARGO_PKG_VERSION_MAJOR"),
+    (VergenKey::BuildSemverMinor, "CARGO_PKG_VERSION_MINOR"),
+    (VergenKey::BuildSemverPatch, "CARGO_PKG_VERSION_PATCH"),
+    (VergenKey::BuildSemverPre, "CARGO_PKG_VERSION_PRE"),
+];
+
+#[cfg(feature = "build")]
+fn add_semver_components(config: &mut Config) {
+    let version = env::var("CARGO_PKG_VERSION").ok();
+    let parts = version_parts(version.as_deref());
+    for ((key, var), part) in SEMVER_COMPONENTS.iter().zip(parts.iter()) {
+        // Cargo's own breakdown is preferred, the version is only parsed if it's missing
+        let value = env::var(var).ok().or_else(|| part.map(str::to_string));
+        add_entry_or_skip(
+            config,
+            *key,
+            value,
+            &format!("neither {} nor CARGO_PKG_VERSION is set", var),
+        );
     }
 }
 
@@ -135,11 +601,13 @@ fn add_config_entries(config: &mut Config, build_config: Build, now: &OffsetDate
             add_date_entry(config, now);
             add_time_entry(config, now);
         }
-        TimestampKind::Timestamp => add_timestamp_entry(config, now),
+        TimestampKind::Timestamp => {
+            add_timestamp_entry(config, now, build_config.timestamp_precision);
+        }
         TimestampKind::All => {
             add_date_entry(config, now);
             add_time_entry(config, now);
-            add_timestamp_entry(config, now);
+            add_timestamp_entry(config, now, build_config.timestamp_precision);
         }
     }
 }
@@ -147,7 +615,7 @@ fn add_config_entries(config: &mut Config, build_config: Build, now: &OffsetDate
 #[cfg(feature = "build")]
 fn add_date_entry(config: &mut Config, now: &OffsetDateTime) {
     add_entry(
-        config.cfg_map_mut(),
+        config,
         VergenKey::BuildDate,
         now.format(format_description!("[year]-[month]-[day]")).ok(),
     );
@@ -156,7 +624,7 @@ fn add_date_entry(config: &mut Config, now: &OffsetDateTime) {
 #[cfg(feature = "build")]
 fn add_time_entry(config: &mut Config, now: &OffsetDateTime) {
     add_entry(
-        config.cfg_map_mut(),
+        config,
         VergenKey::BuildTime,
         now.format(format_description!("[hour]-[minute]-[second]"))
             .ok(),
@@ -164,23 +632,87 @@ fn add_time_entry(config: &mut Config, now: &OffsetDateTime) {
 }
 
 #[cfg(feature = "build")]
-fn add_timestamp_entry(config: &mut Config, now: &OffsetDateTime) {
+fn add_timestamp_entry(config: &mut Config, now: &OffsetDateTime, precision: Precision) {
     add_entry(
-        config.cfg_map_mut(),
+        config,
         VergenKey::BuildTimestamp,
-        now.format(&format_description::well_known::Rfc3339).ok(),
+        now.format(timestamp_format(precision)).ok(),
     );
 }
 
+#[cfg(feature = "build")]
+fn timestamp_format(precision: Precision) -> &'static [FormatItem<'static>] {
+    match precision {
+        Precision::Seconds => format_description!(
+            "[year]-[month]-[day]T[hour]:[minute]:[second][offset_hour sign:mandatory]:[offset_minute]"
+        ),
+        Precision::Millis => format_description!(
+            "[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:3][offset_hour sign:mandatory]:[offset_minute]"
+        ),
+        Precision::Micros => format_description!(
+            "[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:6][offset_hour sign:mandatory]:[offset_minute]"
+        ),
+        Precision::Nanos => format_description!(
+            "[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:9][offset_hour sign:mandatory]:[offset_minute]"
+        ),
+    }
+}
+
+#[cfg(not(feature = "build"))]
+pub(crate) fn configure_build(
+    _instructions: &Instructions,
+    _repo_path: Option<&Path>,
+    _deadline: Option<Instant>,
+    _config: &mut Config,
+) {
+}
+
+#[cfg(feature = "build")]
+pub(crate) fn placeholder_build(instructions: &Instructions, config: &mut Config) {
+    let build_config = instructions.build();
+
+    if build_config.has_enabled() {
+        let timestamp = *build_config.timestamp();
+        let kind = *build_config.kind();
+        add_placeholders(
+            config,
+            &[
+                (timestamp && kind.has_date(), VergenKey::BuildDate),
+                (timestamp && kind.has_time(), VergenKey::BuildTime),
+                (timestamp && kind.has_timestamp(), VergenKey::BuildTimestamp),
+                (*build_config.semver(), VergenKey::BuildSemver),
+                (
+                    *build_config.semver_components(),
+                    VergenKey::BuildSemverMajor,
+                ),
+                (
+                    *build_config.semver_components(),
+                    VergenKey::BuildSemverMinor,
+                ),
+                (
+                    *build_config.semver_components(),
+                    VergenKey::BuildSemverPatch,
+                ),
+                (*build_config.semver_components(), VergenKey::BuildSemverPre),
+                (*build_config.cpu_count(), VergenKey::BuildCpuCount),
+            ],
+        );
+    }
+}
+
 #[cfg(not(feature = "build"))]
-pub(crate) fn configure_build(_instructions: &Instructions, _config: &mut Config) {}
+pub(crate) fn placeholder_build(_instructions: &Instructions, _config: &mut Config) {}
 
 #[cfg(all(test, feature = "build"))]
 mod test {
+    use super::{configure_build, timestamp_format, Precision, SEMVER_COMPONENTS};
     use crate::{
-        config::Instructions,
+        config::{Config, Instructions, VergenKey},
         feature::{TimeZone, TimestampKind},
+        provider::FixedClock,
     };
+    use std::{env, sync::Arc};
+    use time::macros::datetime;
 
     #[test]
     fn build_config() {
@@ -213,6 +745,370 @@ mod test {
         *config.build_mut().semver_mut() = false;
         assert!(!config.build().has_enabled());
     }
+
+    #[test]
+    fn kind_requires_timestamp() {
+        let mut config = Instructions::default();
+        *config.build_mut().timestamp_mut() = false;
+        *config.build_mut().kind_mut() = TimestampKind::DateOnly;
+        let mut errors = vec![];
+        config.build().validate(&mut errors);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field(), "build.kind");
+        assert_eq!(errors[0].value(), "DateOnly");
+    }
+
+    #[test]
+    fn timezone_requires_timestamp() {
+        let mut config = Instructions::default();
+        *config.build_mut().timestamp_mut() = false;
+        *config.build_mut().timezone_mut() = TimeZone::Local;
+        let mut errors = vec![];
+        config.build().validate(&mut errors);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field(), "build.timezone");
+    }
+
+    #[test]
+    fn disabled_is_not_validated() {
+        let mut config = Instructions::default();
+        *config.build_mut().enabled_mut() = false;
+        *config.build_mut().timestamp_mut() = false;
+        *config.build_mut().kind_mut() = TimestampKind::DateOnly;
+        let mut errors = vec![];
+        config.build().validate(&mut errors);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn precision_requires_timestamp() {
+        let mut config = Instructions::default();
+        *config.build_mut().timestamp_mut() = false;
+        *config.build_mut().timestamp_precision_mut() = Precision::Seconds;
+        let mut errors = vec![];
+        config.build().validate(&mut errors);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field(), "build.timestamp_precision");
+    }
+
+    #[test]
+    fn default_precision() {
+        let config = Instructions::default();
+        assert_eq!(config.build().timestamp_precision(), &Precision::Micros);
+    }
+
+    fn format(precision: Precision) -> String {
+        datetime!(2021-02-12 01:54:15.134_750_213 +00:00)
+            .format(timestamp_format(precision))
+            .unwrap()
+    }
+
+    #[test]
+    fn precision_seconds() {
+        assert_eq!(format(Precision::Seconds), "2021-02-12T01:54:15+00:00");
+    }
+
+    #[test]
+    fn precision_millis() {
+        assert_eq!(format(Precision::Millis), "2021-02-12T01:54:15.134+00:00");
+    }
+
+    #[test]
+    fn precision_micros() {
+        assert_eq!(
+            format(Precision::Micros),
+            "2021-02-12T01:54:15.134750+00:00"
+        );
+    }
+
+    #[test]
+    fn fixed_clock() {
+        let mut instructions = Instructions::default();
+        *instructions.build_mut().kind_mut() = TimestampKind::All;
+        *instructions.providers_mut().clock_mut() = Some(Arc::new(FixedClock(
+            datetime!(2021-02-12 01:54:15.134_750_213 +00:00),
+        )));
+        let mut config = Config::default();
+        configure_build(&instructions, None, None, &mut config);
+        let get = |key| config.cfg_map()[&key].clone().unwrap();
+        assert_eq!(get(VergenKey::BuildDate), "2021-02-12");
+        assert_eq!(get(VergenKey::BuildTime), "01-54-15");
+        assert_eq!(
+            get(VergenKey::BuildTimestamp),
+            "2021-02-12T01:54:15.134750+00:00"
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn source_date_epoch() {
+        let mut instructions = Instructions::default();
+        *instructions.build_mut().timestamp_precision_mut() = Precision::Seconds;
+        env::set_var("SOURCE_DATE_EPOCH", "1613094855");
+        let mut ignored = Config::default();
+        configure_build(&instructions, None, None, &mut ignored);
+        *instructions.source_date_epoch_mut() = true;
+        let mut config = Config::default();
+        configure_build(&instructions, None, None, &mut config);
+        env::set_var("SOURCE_DATE_EPOCH", "yesterday");
+        let mut invalid = Config::default();
+        configure_build(&instructions, None, None, &mut invalid);
+        env::remove_var("SOURCE_DATE_EPOCH");
+
+        let timestamp = |config: &Config| config.cfg_map()[&VergenKey::BuildTimestamp].clone();
+        assert_eq!(
+            timestamp(&config),
+            Some("2021-02-12T01:54:15+00:00".to_string())
+        );
+        assert_ne!(timestamp(&ignored), timestamp(&config));
+        assert_ne!(timestamp(&invalid), timestamp(&config));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn skipped_semver() {
+        let version = env::var("CARGO_PKG_VERSION");
+        env::remove_var("CARGO_PKG_VERSION");
+        let instructions = Instructions::default();
+        let mut config = Config::default();
+        configure_build(&instructions, None, None, &mut config);
+        if let Ok(version) = version {
+            env::set_var("CARGO_PKG_VERSION", version);
+        }
+
+        // Only the build feature ran, the other features' instructions are skipped too
+        instructions.warn_skipped(&mut config);
+        let warnings: Vec<&String> = config
+            .warnings()
+            .iter()
+            .filter(|warning| warning.contains("VERGEN_BUILD_"))
+            .collect();
+        assert_eq!(
+            warnings,
+            &["vergen: skipped VERGEN_BUILD_SEMVER because CARGO_PKG_VERSION isn't set"]
+        );
+    }
+
+    /// The semver components generated with the given cargo variables set, and the others removed
+    fn semver_components(vars: &[(&str, &str)]) -> Vec<Option<String>> {
+        let mut names = vec!["CARGO_PKG_VERSION"];
+        names.extend(SEMVER_COMPONENTS.iter().map(|(_, var)| *var));
+        let saved: Vec<(&str, Option<String>)> = names
+            .iter()
+            .map(|name| (*name, env::var(name).ok()))
+            .collect();
+        for name in &names {
+            env::remove_var(name);
+        }
+        for (name, value) in vars {
+            env::set_var(name, value);
+        }
+
+        let mut instructions = Instructions::default();
+        *instructions.build_mut().semver_components_mut() = true;
+        let mut config = Config::default();
+        configure_build(&instructions, None, None, &mut config);
+
+        for (name, value) in saved {
+            match value {
+                Some(value) => env::set_var(name, value),
+                None => env::remove_var(name),
+            }
+        }
+        SEMVER_COMPONENTS
+            .iter()
+            .map(|(key, _)| config.cfg_map()[key].clone())
+            .collect()
+    }
+
+    fn some(values: &[&str]) -> Vec<Option<String>> {
+        values.iter().map(|value| Some(value.to_string())).collect()
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn semver_components_from_cargo() {
+        // Cargo's breakdown wins over the version
+        let components = semver_components(&[
+            ("CARGO_PKG_VERSION", "9.9.9"),
+            ("CARGO_PKG_VERSION_MAJOR", "1"),
+            ("CARGO_PKG_VERSION_MINOR", "2"),
+            ("CARGO_PKG_VERSION_PATCH", "3"),
+            ("CARGO_PKG_VERSION_PRE", "beta.1"),
+        ]);
+        assert_eq!(components, some(&["1", "2", "3", "beta.1"]));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn semver_components_parsed() {
+        let components = semver_components(&[("CARGO_PKG_VERSION", "1.2.3-rc.1+build.5")]);
+        assert_eq!(components, some(&["1", "2", "3", "rc.1"]));
+        let components = semver_components(&[("CARGO_PKG_VERSION", "4.5.6")]);
+        assert_eq!(components, some(&["4", "5", "6", ""]));
+        // Only the missing components are parsed
+        let components = semver_components(&[
+            ("CARGO_PKG_VERSION", "4.5.6"),
+            ("CARGO_PKG_VERSION_MAJOR", "7"),
+        ]);
+        assert_eq!(components, some(&["7", "5", "6", ""]));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn semver_components_skipped() {
+        assert_eq!(semver_components(&[]), vec![None; 4]);
+    }
+
+    #[test]
+    fn cpu_count() {
+        let mut instructions = Instructions::default();
+        let mut config = Config::default();
+        configure_build(&instructions, None, None, &mut config);
+        assert_eq!(config.cfg_map()[&VergenKey::BuildCpuCount], None);
+
+        *instructions.build_mut().cpu_count_mut() = true;
+        let mut config = Config::default();
+        configure_build(&instructions, None, None, &mut config);
+        let count = config.cfg_map()[&VergenKey::BuildCpuCount].clone().unwrap();
+        if cfg!(available_parallelism) {
+            assert!(count.parse::<usize>().unwrap() > 0);
+        } else {
+            assert!(count.is_empty());
+        }
+    }
+
+    #[test]
+    fn timestamp_from_git_requires_timestamp() {
+        let mut config = Instructions::default();
+        *config.build_mut().timestamp_mut() = false;
+        *config.build_mut().timestamp_from_git_mut() = true;
+        let mut errors = vec![];
+        config.build().validate(&mut errors);
+        // Without the git feature, it's reported twice
+        assert!(!errors.is_empty());
+        assert!(errors
+            .iter()
+            .all(|error| error.field() == "build.timestamp_from_git"));
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    #[serial_test::serial]
+    fn timestamp_from_git() {
+        use crate::provider::FakeRepo;
+
+        let mut instructions = Instructions::default();
+        *instructions.build_mut().timestamp_from_git_mut() = true;
+        *instructions.build_mut().timestamp_precision_mut() = Precision::Seconds;
+        *instructions.providers_mut().git_mut() = Some(Arc::new(FakeRepo {
+            commit_time: datetime!(2021-02-12 01:54:15 UTC),
+            ..FakeRepo::default()
+        }));
+        let mut config = Config::default();
+        configure_build(&instructions, None, None, &mut config);
+        assert_eq!(
+            config.cfg_map()[&VergenKey::BuildTimestamp],
+            Some("2021-02-12T01:54:15+00:00".to_string())
+        );
+        assert!(config.warnings().is_empty());
+
+        // SOURCE_DATE_EPOCH, when honored, takes precedence
+        *instructions.source_date_epoch_mut() = true;
+        env::set_var("SOURCE_DATE_EPOCH", "1700000000");
+        let mut config = Config::default();
+        configure_build(&instructions, None, None, &mut config);
+        env::remove_var("SOURCE_DATE_EPOCH");
+        assert_eq!(
+            config.cfg_map()[&VergenKey::BuildTimestamp],
+            Some("2023-11-14T22:13:20+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn timestamp_from_git_without_repository() {
+        let mut instructions = Instructions::default();
+        *instructions.build_mut().timestamp_from_git_mut() = true;
+        let mut config = Config::default();
+        configure_build(&instructions, None, None, &mut config);
+        assert!(config.cfg_map()[&VergenKey::BuildTimestamp].is_some());
+        assert_eq!(
+            config.warnings(),
+            &["vergen: build.timestamp_from_git couldn't read the HEAD commit time, the current time is used"]
+        );
+    }
+
+    #[test]
+    fn timezone_from_git_requires_timestamp() {
+        let mut config = Instructions::default();
+        *config.build_mut().timestamp_mut() = false;
+        *config.build_mut().timezone_from_git_mut() = true;
+        let mut errors = vec![];
+        config.build().validate(&mut errors);
+        // Without the git feature, it's reported twice
+        assert!(!errors.is_empty());
+        assert!(errors
+            .iter()
+            .all(|error| error.field() == "build.timezone_from_git"));
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn timezone_from_git() {
+        use crate::provider::FakeRepo;
+        use time::UtcOffset;
+
+        let mut instructions = Instructions::default();
+        *instructions.build_mut().timezone_from_git_mut() = true;
+        *instructions.build_mut().timestamp_precision_mut() = Precision::Seconds;
+        *instructions.providers_mut().clock_mut() =
+            Some(Arc::new(FixedClock(datetime!(2021-02-12 01:54:15 UTC))));
+        *instructions.providers_mut().git_mut() = Some(Arc::new(FakeRepo {
+            commit_time: datetime!(2021-02-12 01:54:15 UTC),
+            commit_offset: UtcOffset::from_hms(2, 0, 0).unwrap(),
+            ..FakeRepo::default()
+        }));
+        let mut config = Config::default();
+        configure_build(&instructions, None, None, &mut config);
+        assert_eq!(
+            config.cfg_map()[&VergenKey::BuildTimestamp],
+            Some("2021-02-12T03:54:15+02:00".to_string())
+        );
+        assert!(config.warnings().is_empty());
+
+        // Both come from the one HEAD commit
+        *instructions.build_mut().timestamp_from_git_mut() = true;
+        *instructions.providers_mut().clock_mut() =
+            Some(Arc::new(FixedClock(datetime!(2022-01-01 00:00:00 UTC))));
+        let mut config = Config::default();
+        configure_build(&instructions, None, None, &mut config);
+        assert_eq!(
+            config.cfg_map()[&VergenKey::BuildTimestamp],
+            Some("2021-02-12T03:54:15+02:00".to_string())
+        );
+        assert!(config.warnings().is_empty());
+    }
+
+    #[test]
+    fn timezone_from_git_without_repository() {
+        let mut instructions = Instructions::default();
+        *instructions.build_mut().timezone_from_git_mut() = true;
+        let mut config = Config::default();
+        configure_build(&instructions, None, None, &mut config);
+        assert!(config.cfg_map()[&VergenKey::BuildTimestamp].is_some());
+        assert_eq!(
+            config.warnings(),
+            &["vergen: build.timezone_from_git couldn't read the HEAD commit timezone, the configured timezone is used"]
+        );
+    }
+
+    #[test]
+    fn precision_nanos() {
+        assert_eq!(
+            format(Precision::Nanos),
+            "2021-02-12T01:54:15.134750213+00:00"
+        );
+    }
 }
 
 #[cfg(all(test, not(feature = "build")))]
@@ -11,9 +11,13 @@
 use crate::config::{Config, Instructions};
 #[cfg(feature = "cargo")]
 use {
-    crate::{config::VergenKey, feature::add_entry},
-    getset::{Getters, MutGetters},
-    std::env,
+    crate::{
+        command,
+        config::VergenKey,
+        feature::{add_entry, add_entry_or_skip, add_placeholders},
+        merge::{pick, SetFields},
+    },
+    std::{env, process::Command, time::Instant},
 };
 
 /// Configuration for the `VERGEN_CARGO_*` instructions
@@ -26,11 +30,49 @@ use {
 /// | `cargo:rustc-env=VERGEN_CARGO_TARGET_TRIPLE=x86_64-unknown-linux-gnu` | * |
 /// | `cargo:rustc-env=VERGEN_CARGO_PROFILE=debug` | * |
 /// | `cargo:rustc-env=VERGEN_CARGO_FEATURES=git,build` | * |
+/// | `cargo:rustc-env=VERGEN_CARGO_PKG_DESCRIPTION=Generate 'cargo:rustc-env' instructions...` | |
+/// | `cargo:rustc-env=VERGEN_CARGO_PKG_AUTHORS=Jason Ozias <jason.g.ozias@gmail.com>` | |
+/// | `cargo:rustc-env=VERGEN_CARGO_PKG_HOMEPAGE=https://github.com/rustyhorde/vergen` | |
+/// | `cargo:rustc-env=VERGEN_CARGO_PKG_REPOSITORY=https://github.com/rustyhorde/vergen` | |
+/// | `cargo:rustc-env=VERGEN_CARGO_PKG_LICENSE=MIT OR Apache-2.0` | |
+/// | `cargo:rustc-env=VERGEN_CARGO_PKG_LICENSE_FILE=LICENSE.txt` | |
+/// | `cargo:rustc-env=VERGEN_CARGO_TARGET_OS=linux` | |
+/// | `cargo:rustc-env=VERGEN_CARGO_TARGET_ARCH=x86_64` | |
+/// | `cargo:rustc-env=VERGEN_CARGO_TARGET_ENV=gnu` | |
+/// | `cargo:rustc-env=VERGEN_CARGO_TARGET_POINTER_WIDTH=64` | |
+/// | `cargo:rustc-env=VERGEN_CARGO_TARGET_ENDIAN=little` | |
+/// | `cargo:rustc-env=VERGEN_CARGO_LIBC_VERSION=glibc 2.36` | |
+/// | `cargo:rustc-env=VERGEN_IS_CROSS_COMPILE=false` | |
 ///
 /// * If the `features` field is false, the features instruction will not be generated.
+/// * If the `pkg_description` field is true, the package description instruction will be generated.
+/// * If the `pkg_authors` field is true, the package authors instruction will be generated.
+/// * **NOTE** - Cargo separates multiple authors with `:`, set the `pkg_authors_commas` field to true to separate them with `, ` instead.
+/// * If the `pkg_homepage` or `pkg_repository` fields are true, the package homepage or repository instruction will be generated.
+/// * If the `pkg_license` or `pkg_license_file` fields are true, the package license or license file instruction will be
+///   generated.
+/// * **NOTE** - A homepage, repository, license, or license file the manifest doesn't set is generated as an empty value.
 /// * If the `profile` field is false, the profile instruction will not be generated.
 /// * If the `target_triple` field is false, the target triple instruction will not be generated.
 /// * **NOTE** - the `target_triple` instruction can differ from the `host_triple` instruction, i.e. during cross compilation
+/// * If the `target_os`, `target_arch` or `target_env` fields are true, the matching component of the target is generated from
+/// the `CARGO_CFG_TARGET_*` variables cargo sets.
+/// * **NOTE** - A target component cargo doesn't set, i.e. the env on `x86_64-apple-darwin`, is generated as an empty value.
+/// * If the `target_pointer_width` or `target_endian` fields are true, the pointer width or endianness of the target is
+/// generated from `CARGO_CFG_TARGET_POINTER_WIDTH` or `CARGO_CFG_TARGET_ENDIAN`, or as an empty value if cargo doesn't
+/// set it.
+/// * If the `libc_version` field is true, the libc and its version, i.e. `glibc 2.36` or `musl 1.2.4`, is generated, to
+/// tell a glibc binary from a musl one when triaging a crash report.  It's detected with `getconf GNU_LIBC_VERSION`, or
+/// `ldd --version` for musl.
+/// * **NOTE** - The libc is the one of the host, so it's only generated when building for a Linux host, not when
+/// cross compiling.  If it can't be detected, it isn't generated, and a `cargo:warning` names the reason.
+/// * If the `is_cross_compile` field is true, the `VERGEN_IS_CROSS_COMPILE` instruction will be generated.  It is `true` when
+/// the `HOST` and `TARGET` triples cargo sets differ.
+///
+/// # Host and Target
+/// The target triple and components describe the platform being built for, and the `VERGEN_RUSTC_*` and
+/// `VERGEN_SYSINFO_*` instructions describe the host doing the build.  The `target_names` field decides whether the
+/// target values are named `VERGEN_CARGO_TARGET_*`, `VERGEN_TARGET_*`, or both while migrating, see [`TargetNames`].
 ///
 /// # Example
 ///
@@ -53,8 +95,17 @@ vergen(config)?;
 /// # Ok(())
 /// # }
 #[cfg(feature = "cargo")]
-#[derive(Clone, Copy, Debug, Getters, MutGetters)]
-#[getset(get = "pub(crate)", get_mut = "pub")]
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
 pub struct Cargo {
     /// Enable/Disable the cargo output
     enabled: bool,
@@ -64,6 +115,360 @@ pub struct Cargo {
     profile: bool,
     /// Enable/Disable the `VERGEN_CARGO_TARGET_TRIPLE` instruction
     target_triple: bool,
+    /// Enable/Disable the `VERGEN_CARGO_PKG_DESCRIPTION` instruction
+    pkg_description: bool,
+    /// Enable/Disable the `VERGEN_CARGO_PKG_AUTHORS` instruction
+    pkg_authors: bool,
+    /// Separate the `VERGEN_CARGO_PKG_AUTHORS` values with `, ` rather than `:`
+    pkg_authors_commas: bool,
+    /// Enable/Disable the `VERGEN_CARGO_PKG_HOMEPAGE` instruction
+    pkg_homepage: bool,
+    /// Enable/Disable the `VERGEN_CARGO_PKG_REPOSITORY` instruction
+    pkg_repository: bool,
+    /// Enable/Disable the `VERGEN_CARGO_PKG_LICENSE` instruction
+    pkg_license: bool,
+    /// Enable/Disable the `VERGEN_CARGO_PKG_LICENSE_FILE` instruction
+    pkg_license_file: bool,
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_OS` instruction
+    target_os: bool,
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_ARCH` instruction
+    target_arch: bool,
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_ENV` instruction
+    target_env: bool,
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_POINTER_WIDTH` instruction
+    target_pointer_width: bool,
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_ENDIAN` instruction
+    target_endian: bool,
+    /// Enable/Disable the `VERGEN_CARGO_LIBC_VERSION` instruction
+    libc_version: bool,
+    /// The names of the target triple and component instructions
+    target_names: TargetNames,
+    /// Enable/Disable the `VERGEN_IS_CROSS_COMPILE` instruction
+    is_cross_compile: bool,
+    /// The fields set through their `set_*` setter, which win in a [merge](crate::Config::merge)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    set_fields: SetFields,
+}
+
+#[cfg(feature = "cargo")]
+impl Cargo {
+    /// Enable/Disable the cargo output
+    pub(crate) fn enabled(&self) -> &bool {
+        &self.enabled
+    }
+
+    /// Enable/Disable the cargo output
+    pub fn enabled_mut(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+
+    /// Enable/Disable the cargo output
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "enabled");
+        self.enabled = enabled;
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_FEATURES` instruction
+    pub(crate) fn features(&self) -> &bool {
+        &self.features
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_FEATURES` instruction
+    pub fn features_mut(&mut self) -> &mut bool {
+        &mut self.features
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_FEATURES` instruction
+    pub fn set_features(&mut self, features: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "features");
+        self.features = features;
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_PROFILE` instruction
+    pub(crate) fn profile(&self) -> &bool {
+        &self.profile
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_PROFILE` instruction
+    pub fn profile_mut(&mut self) -> &mut bool {
+        &mut self.profile
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_PROFILE` instruction
+    pub fn set_profile(&mut self, profile: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "profile");
+        self.profile = profile;
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_TRIPLE` instruction
+    #[cfg(test)]
+    pub(crate) fn target_triple(&self) -> &bool {
+        &self.target_triple
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_TRIPLE` instruction
+    pub fn target_triple_mut(&mut self) -> &mut bool {
+        &mut self.target_triple
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_TRIPLE` instruction
+    pub fn set_target_triple(&mut self, target_triple: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "target_triple");
+        self.target_triple = target_triple;
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_PKG_DESCRIPTION` instruction
+    pub(crate) fn pkg_description(&self) -> &bool {
+        &self.pkg_description
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_PKG_DESCRIPTION` instruction
+    pub fn pkg_description_mut(&mut self) -> &mut bool {
+        &mut self.pkg_description
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_PKG_DESCRIPTION` instruction
+    pub fn set_pkg_description(&mut self, pkg_description: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "pkg_description");
+        self.pkg_description = pkg_description;
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_PKG_AUTHORS` instruction
+    pub(crate) fn pkg_authors(&self) -> &bool {
+        &self.pkg_authors
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_PKG_AUTHORS` instruction
+    pub fn pkg_authors_mut(&mut self) -> &mut bool {
+        &mut self.pkg_authors
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_PKG_AUTHORS` instruction
+    pub fn set_pkg_authors(&mut self, pkg_authors: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "pkg_authors");
+        self.pkg_authors = pkg_authors;
+    }
+
+    /// Separate the `VERGEN_CARGO_PKG_AUTHORS` values with `, ` rather than `:`
+    pub(crate) fn pkg_authors_commas(&self) -> &bool {
+        &self.pkg_authors_commas
+    }
+
+    /// Separate the `VERGEN_CARGO_PKG_AUTHORS` values with `, ` rather than `:`
+    pub fn pkg_authors_commas_mut(&mut self) -> &mut bool {
+        &mut self.pkg_authors_commas
+    }
+
+    /// Separate the `VERGEN_CARGO_PKG_AUTHORS` values with `, ` rather than `:`
+    pub fn set_pkg_authors_commas(&mut self, pkg_authors_commas: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "pkg_authors_commas");
+        self.pkg_authors_commas = pkg_authors_commas;
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_PKG_HOMEPAGE` instruction
+    pub fn pkg_homepage_mut(&mut self) -> &mut bool {
+        &mut self.pkg_homepage
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_PKG_HOMEPAGE` instruction
+    pub fn set_pkg_homepage(&mut self, pkg_homepage: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "pkg_homepage");
+        self.pkg_homepage = pkg_homepage;
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_PKG_REPOSITORY` instruction
+    pub fn pkg_repository_mut(&mut self) -> &mut bool {
+        &mut self.pkg_repository
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_PKG_REPOSITORY` instruction
+    pub fn set_pkg_repository(&mut self, pkg_repository: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "pkg_repository");
+        self.pkg_repository = pkg_repository;
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_PKG_LICENSE` instruction
+    pub fn pkg_license_mut(&mut self) -> &mut bool {
+        &mut self.pkg_license
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_PKG_LICENSE` instruction
+    pub fn set_pkg_license(&mut self, pkg_license: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "pkg_license");
+        self.pkg_license = pkg_license;
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_PKG_LICENSE_FILE` instruction
+    pub fn pkg_license_file_mut(&mut self) -> &mut bool {
+        &mut self.pkg_license_file
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_PKG_LICENSE_FILE` instruction
+    pub fn set_pkg_license_file(&mut self, pkg_license_file: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "pkg_license_file");
+        self.pkg_license_file = pkg_license_file;
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_OS` instruction
+    #[cfg(test)]
+    pub(crate) fn target_os(&self) -> &bool {
+        &self.target_os
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_OS` instruction
+    pub fn target_os_mut(&mut self) -> &mut bool {
+        &mut self.target_os
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_OS` instruction
+    pub fn set_target_os(&mut self, target_os: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "target_os");
+        self.target_os = target_os;
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_ARCH` instruction
+    pub fn target_arch_mut(&mut self) -> &mut bool {
+        &mut self.target_arch
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_ARCH` instruction
+    pub fn set_target_arch(&mut self, target_arch: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "target_arch");
+        self.target_arch = target_arch;
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_ENV` instruction
+    pub fn target_env_mut(&mut self) -> &mut bool {
+        &mut self.target_env
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_ENV` instruction
+    pub fn set_target_env(&mut self, target_env: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "target_env");
+        self.target_env = target_env;
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_POINTER_WIDTH` instruction
+    pub fn target_pointer_width_mut(&mut self) -> &mut bool {
+        &mut self.target_pointer_width
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_POINTER_WIDTH` instruction
+    pub fn set_target_pointer_width(&mut self, target_pointer_width: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "target_pointer_width");
+        self.target_pointer_width = target_pointer_width;
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_ENDIAN` instruction
+    pub fn target_endian_mut(&mut self) -> &mut bool {
+        &mut self.target_endian
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_TARGET_ENDIAN` instruction
+    pub fn set_target_endian(&mut self, target_endian: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "target_endian");
+        self.target_endian = target_endian;
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_LIBC_VERSION` instruction
+    pub(crate) fn libc_version(&self) -> &bool {
+        &self.libc_version
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_LIBC_VERSION` instruction
+    pub fn libc_version_mut(&mut self) -> &mut bool {
+        &mut self.libc_version
+    }
+
+    /// Enable/Disable the `VERGEN_CARGO_LIBC_VERSION` instruction
+    pub fn set_libc_version(&mut self, libc_version: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "libc_version");
+        self.libc_version = libc_version;
+    }
+
+    /// The names of the target triple and component instructions
+    pub(crate) fn target_names(&self) -> &TargetNames {
+        &self.target_names
+    }
+
+    /// The names of the target triple and component instructions
+    pub fn target_names_mut(&mut self) -> &mut TargetNames {
+        &mut self.target_names
+    }
+
+    /// The names of the target triple and component instructions
+    pub fn set_target_names(&mut self, target_names: TargetNames) {
+        self.set_fields.mark(MERGED_FIELDS, "target_names");
+        self.target_names = target_names;
+    }
+
+    /// Enable/Disable the `VERGEN_IS_CROSS_COMPILE` instruction
+    pub(crate) fn is_cross_compile(&self) -> &bool {
+        &self.is_cross_compile
+    }
+
+    /// Enable/Disable the `VERGEN_IS_CROSS_COMPILE` instruction
+    pub fn is_cross_compile_mut(&mut self) -> &mut bool {
+        &mut self.is_cross_compile
+    }
+
+    /// Enable/Disable the `VERGEN_IS_CROSS_COMPILE` instruction
+    pub fn set_is_cross_compile(&mut self, is_cross_compile: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "is_cross_compile");
+        self.is_cross_compile = is_cross_compile;
+    }
+}
+
+/// The names of the instructions describing the platform being built for
+///
+/// * [`Cargo`](Self::Cargo) emits `VERGEN_CARGO_TARGET_TRIPLE`, `VERGEN_CARGO_TARGET_OS`, `VERGEN_CARGO_TARGET_ARCH`, and
+///   `VERGEN_CARGO_TARGET_ENV`.
+/// * [`Target`](Self::Target) emits `VERGEN_TARGET_TRIPLE`, `VERGEN_TARGET_OS`, `VERGEN_TARGET_ARCH`, and
+///   `VERGEN_TARGET_ENV`.
+/// * [`Both`](Self::Both) emits both sets, while moving code from the old names to the new ones.
+///
+/// # Example
+///
+/// ```
+/// use vergen::{Config, TargetNames};
+///
+/// let mut config = Config::default();
+/// *config.cargo_mut().target_names_mut() = TargetNames::Both;
+/// ```
+#[cfg(feature = "cargo")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum TargetNames {
+    /// The `VERGEN_CARGO_TARGET_*` names
+    Cargo,
+    /// The `VERGEN_TARGET_*` names
+    Target,
+    /// Both sets of names
+    Both,
+}
+
+#[cfg(feature = "cargo")]
+#[cfg_attr(msrv, allow(clippy::derivable_impls))]
+impl Default for TargetNames {
+    fn default() -> Self {
+        Self::Cargo
+    }
+}
+
+#[cfg(feature = "cargo")]
+impl TargetNames {
+    /// The keys a target value is emitted as, given its `VERGEN_CARGO_TARGET_*` and `VERGEN_TARGET_*` keys
+    fn keys(self, cargo: VergenKey, target: VergenKey) -> [Option<VergenKey>; 2] {
+        match self {
+            Self::Cargo => [Some(cargo), None],
+            Self::Target => [None, Some(target)],
+            Self::Both => [Some(cargo), Some(target)],
+        }
+    }
 }
 
 #[cfg(feature = "cargo")]
@@ -74,14 +479,262 @@ impl Default for Cargo {
             features: true,
             profile: true,
             target_triple: true,
+            pkg_description: false,
+            pkg_authors: false,
+            pkg_authors_commas: false,
+            pkg_homepage: false,
+            pkg_repository: false,
+            pkg_license: false,
+            pkg_license_file: false,
+            target_os: false,
+            target_arch: false,
+            target_env: false,
+            target_pointer_width: false,
+            target_endian: false,
+            libc_version: false,
+            target_names: TargetNames::default(),
+            is_cross_compile: false,
+            set_fields: SetFields::default(),
         }
     }
 }
 
+/// The [`Cargo`] fields that win in a [merge](crate::Config::merge) once set, see [`SetFields`]
+#[cfg(feature = "cargo")]
+const MERGED_FIELDS: &[&str] = &[
+    "enabled",
+    "features",
+    "profile",
+    "target_triple",
+    "pkg_description",
+    "pkg_authors",
+    "pkg_authors_commas",
+    "pkg_homepage",
+    "pkg_repository",
+    "pkg_license",
+    "pkg_license_file",
+    "target_os",
+    "target_arch",
+    "target_env",
+    "target_pointer_width",
+    "target_endian",
+    "libc_version",
+    "target_names",
+    "is_cross_compile",
+];
+
 #[cfg(feature = "cargo")]
 impl Cargo {
+    #[allow(clippy::too_many_lines)]
+    pub(crate) fn merge(self, overlay: Self) -> Self {
+        let default = Self::default();
+        let overlay_set = overlay.set_fields;
+        let set = |name| overlay_set.contains(MERGED_FIELDS, name);
+        Self {
+            enabled: pick(
+                self.enabled,
+                overlay.enabled,
+                &default.enabled,
+                set("enabled"),
+            ),
+            features: pick(
+                self.features,
+                overlay.features,
+                &default.features,
+                set("features"),
+            ),
+            profile: pick(
+                self.profile,
+                overlay.profile,
+                &default.profile,
+                set("profile"),
+            ),
+            target_triple: pick(
+                self.target_triple,
+                overlay.target_triple,
+                &default.target_triple,
+                set("target_triple"),
+            ),
+            pkg_description: pick(
+                self.pkg_description,
+                overlay.pkg_description,
+                &default.pkg_description,
+                set("pkg_description"),
+            ),
+            pkg_authors: pick(
+                self.pkg_authors,
+                overlay.pkg_authors,
+                &default.pkg_authors,
+                set("pkg_authors"),
+            ),
+            pkg_authors_commas: pick(
+                self.pkg_authors_commas,
+                overlay.pkg_authors_commas,
+                &default.pkg_authors_commas,
+                set("pkg_authors_commas"),
+            ),
+            pkg_homepage: pick(
+                self.pkg_homepage,
+                overlay.pkg_homepage,
+                &default.pkg_homepage,
+                set("pkg_homepage"),
+            ),
+            pkg_repository: pick(
+                self.pkg_repository,
+                overlay.pkg_repository,
+                &default.pkg_repository,
+                set("pkg_repository"),
+            ),
+            pkg_license: pick(
+                self.pkg_license,
+                overlay.pkg_license,
+                &default.pkg_license,
+                set("pkg_license"),
+            ),
+            pkg_license_file: pick(
+                self.pkg_license_file,
+                overlay.pkg_license_file,
+                &default.pkg_license_file,
+                set("pkg_license_file"),
+            ),
+            target_os: pick(
+                self.target_os,
+                overlay.target_os,
+                &default.target_os,
+                set("target_os"),
+            ),
+            target_arch: pick(
+                self.target_arch,
+                overlay.target_arch,
+                &default.target_arch,
+                set("target_arch"),
+            ),
+            target_env: pick(
+                self.target_env,
+                overlay.target_env,
+                &default.target_env,
+                set("target_env"),
+            ),
+            target_pointer_width: pick(
+                self.target_pointer_width,
+                overlay.target_pointer_width,
+                &default.target_pointer_width,
+                set("target_pointer_width"),
+            ),
+            target_endian: pick(
+                self.target_endian,
+                overlay.target_endian,
+                &default.target_endian,
+                set("target_endian"),
+            ),
+            libc_version: pick(
+                self.libc_version,
+                overlay.libc_version,
+                &default.libc_version,
+                set("libc_version"),
+            ),
+            target_names: pick(
+                self.target_names,
+                overlay.target_names,
+                &default.target_names,
+                set("target_names"),
+            ),
+            is_cross_compile: pick(
+                self.is_cross_compile,
+                overlay.is_cross_compile,
+                &default.is_cross_compile,
+                set("is_cross_compile"),
+            ),
+            set_fields: self.set_fields.union(overlay_set),
+        }
+    }
+
     pub(crate) fn has_enabled(self) -> bool {
-        self.enabled && (self.features || self.profile || self.target_triple)
+        self.enabled
+            && (self.features
+                || self.profile
+                || self.target_triple
+                || self.pkg_description
+                || self.pkg_authors
+                || self.pkg_homepage
+                || self.pkg_repository
+                || self.pkg_license
+                || self.pkg_license_file
+                || self.target_os
+                || self.target_arch
+                || self.target_env
+                || self.target_pointer_width
+                || self.target_endian
+                || self.libc_version
+                || self.is_cross_compile)
+    }
+
+    /// The instructions generated as an empty value when cargo doesn't set their variable, by whether they're
+    /// enabled, key, and the variable cargo sets
+    fn optional_values(self) -> [(bool, VergenKey, &'static str); 6] {
+        [
+            (
+                self.pkg_homepage,
+                VergenKey::CargoPkgHomepage,
+                "CARGO_PKG_HOMEPAGE",
+            ),
+            (
+                self.pkg_repository,
+                VergenKey::CargoPkgRepository,
+                "CARGO_PKG_REPOSITORY",
+            ),
+            (
+                self.pkg_license,
+                VergenKey::CargoPkgLicense,
+                "CARGO_PKG_LICENSE",
+            ),
+            (
+                self.pkg_license_file,
+                VergenKey::CargoPkgLicenseFile,
+                "CARGO_PKG_LICENSE_FILE",
+            ),
+            (
+                self.target_pointer_width,
+                VergenKey::CargoTargetPointerWidth,
+                "CARGO_CFG_TARGET_POINTER_WIDTH",
+            ),
+            (
+                self.target_endian,
+                VergenKey::CargoTargetEndian,
+                "CARGO_CFG_TARGET_ENDIAN",
+            ),
+        ]
+    }
+
+    /// The target instructions, by whether they're enabled, `VERGEN_CARGO_TARGET_*` key, `VERGEN_TARGET_*` key, and
+    /// the variable cargo sets
+    fn targets(self) -> [(bool, VergenKey, VergenKey, &'static str); 4] {
+        [
+            (
+                self.target_triple,
+                VergenKey::CargoTargetTriple,
+                VergenKey::TargetTriple,
+                "TARGET",
+            ),
+            (
+                self.target_os,
+                VergenKey::CargoTargetOs,
+                VergenKey::TargetOs,
+                "CARGO_CFG_TARGET_OS",
+            ),
+            (
+                self.target_arch,
+                VergenKey::CargoTargetArch,
+                VergenKey::TargetArch,
+                "CARGO_CFG_TARGET_ARCH",
+            ),
+            (
+                self.target_env,
+                VergenKey::CargoTargetEnv,
+                VergenKey::TargetEnv,
+                "CARGO_CFG_TARGET_ENV",
+            ),
+        ]
     }
 }
 
@@ -100,19 +753,51 @@ pub(crate) fn configure_cargo(instructions: &Instructions, config: &mut Config)
     let cargo_config = instructions.cargo();
 
     if cargo_config.has_enabled() {
-        if *cargo_config.target_triple() {
-            add_entry(
-                config.cfg_map_mut(),
-                VergenKey::CargoTargetTriple,
-                env::var("TARGET").ok(),
+        for (enabled, cargo_key, target_key, var) in &cargo_config.targets() {
+            if *enabled {
+                // The triple is always set, but a component cargo doesn't set is empty
+                let value = match env::var(var) {
+                    Ok(value) => Some(value),
+                    Err(_) if *var == "TARGET" => None,
+                    Err(_) => Some(String::new()),
+                };
+                for key in cargo_config
+                    .target_names()
+                    .keys(*cargo_key, *target_key)
+                    .iter()
+                    .flatten()
+                {
+                    add_entry_or_skip(config, *key, value.clone(), "TARGET isn't set");
+                }
+            }
+        }
+
+        if *cargo_config.is_cross_compile() {
+            let cross = match (env::var("HOST"), env::var("TARGET")) {
+                (Ok(host), Ok(target)) => Some((host != target).to_string()),
+                _ => None,
+            };
+            add_entry_or_skip(
+                config,
+                VergenKey::IsCrossCompile,
+                cross,
+                "HOST or TARGET isn't set",
             );
         }
 
+        if *cargo_config.libc_version() {
+            match libc_version() {
+                Ok(version) => add_entry(config, VergenKey::CargoLibcVersion, Some(version)),
+                Err(reason) => config.skip(VergenKey::CargoLibcVersion, reason),
+            }
+        }
+
         if *cargo_config.profile() {
-            add_entry(
-                config.cfg_map_mut(),
+            add_entry_or_skip(
+                config,
                 VergenKey::CargoProfile,
                 env::var("PROFILE").ok(),
+                "PROFILE isn't set",
             );
         }
 
@@ -124,21 +809,155 @@ pub(crate) fn configure_cargo(instructions: &Instructions, config: &mut Config)
             } else {
                 Some(feature_str)
             };
-            add_entry(config.cfg_map_mut(), VergenKey::CargoFeatures, value);
+            add_entry(config, VergenKey::CargoFeatures, value);
+        }
+
+        if *cargo_config.pkg_description() {
+            add_entry_or_skip(
+                config,
+                VergenKey::CargoPkgDescription,
+                env::var("CARGO_PKG_DESCRIPTION").ok(),
+                "CARGO_PKG_DESCRIPTION isn't set",
+            );
+        }
+
+        if *cargo_config.pkg_authors() {
+            add_entry_or_skip(
+                config,
+                VergenKey::CargoPkgAuthors,
+                env::var("CARGO_PKG_AUTHORS")
+                    .ok()
+                    .map(|authors| format_authors(&authors, *cargo_config.pkg_authors_commas())),
+                "CARGO_PKG_AUTHORS isn't set",
+            );
+        }
+
+        for (enabled, key, var) in &cargo_config.optional_values() {
+            if *enabled {
+                add_entry(config, *key, Some(env::var(var).unwrap_or_default()));
+            }
         }
     }
 }
 
+/// The libc of the host, i.e. `glibc 2.36`, if the build is for the host, or why it isn't available
+#[cfg(feature = "cargo")]
+fn libc_version() -> Result<String, &'static str> {
+    match (env::var("HOST"), env::var("TARGET")) {
+        (Ok(host), Ok(target)) if host != target => {
+            return Err("the build is cross compiling, the host libc doesn't describe the target")
+        }
+        (Ok(_), Ok(_)) => {}
+        _ => return Err("HOST or TARGET isn't set"),
+    }
+    if env::var("CARGO_CFG_TARGET_OS").map_or(true, |os| os != "linux") {
+        return Err("the target isn't Linux");
+    }
+
+    let output = |program: &str, arg: &str| {
+        command::output(
+            Command::new(program).arg(arg),
+            Some(Instant::now() + command::COMMAND_TIMEOUT),
+        )
+        .ok()
+    };
+    output("getconf", "GNU_LIBC_VERSION")
+        .filter(|output| output.status.success())
+        .and_then(|output| glibc_version(&String::from_utf8_lossy(&output.stdout)))
+        .or_else(|| {
+            // musl's ldd prints its version to stderr, and fails
+            output("ldd", "--version")
+                .and_then(|output| musl_version(&String::from_utf8_lossy(&output.stderr)))
+        })
+        .ok_or("the libc version couldn't be detected")
+}
+
+/// The glibc version from the `getconf GNU_LIBC_VERSION` output, i.e. `glibc 2.36`
+#[cfg(feature = "cargo")]
+fn glibc_version(output: &str) -> Option<String> {
+    let version = output.trim().strip_prefix("glibc ")?;
+    Some(format!("glibc {}", version))
+}
+
+/// The musl version from the `ldd --version` output, i.e. `musl 1.2.4`
+#[cfg(feature = "cargo")]
+fn musl_version(output: &str) -> Option<String> {
+    if !output.trim_start().starts_with("musl libc") {
+        return None;
+    }
+    let version = output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Version "))?;
+    Some(format!("musl {}", version.trim()))
+}
+
+#[cfg(feature = "cargo")]
+fn format_authors(authors: &str, commas: bool) -> String {
+    if commas {
+        authors.split(':').collect::<Vec<&str>>().join(", ")
+    } else {
+        authors.to_string()
+    }
+}
+
 #[cfg(not(feature = "cargo"))]
 pub(crate) fn configure_cargo(_instructions: &Instructions, _config: &mut Config) {}
 
+#[cfg(feature = "cargo")]
+pub(crate) fn placeholder_cargo(instructions: &Instructions, config: &mut Config) {
+    let cargo_config = instructions.cargo();
+
+    if cargo_config.has_enabled() {
+        let mut keys = vec![];
+        for (enabled, cargo_key, target_key) in cargo_config
+            .targets()
+            .iter()
+            .map(|(enabled, cargo_key, target_key, _)| (*enabled, *cargo_key, *target_key))
+        {
+            let names = cargo_config.target_names().keys(cargo_key, target_key);
+            keys.extend(names.iter().flatten().map(|key| (enabled, *key)));
+        }
+        keys.push((*cargo_config.is_cross_compile(), VergenKey::IsCrossCompile));
+        keys.push((*cargo_config.libc_version(), VergenKey::CargoLibcVersion));
+        keys.extend(
+            cargo_config
+                .optional_values()
+                .iter()
+                .map(|(enabled, key, _)| (*enabled, *key)),
+        );
+        add_placeholders(config, &keys);
+
+        add_placeholders(
+            config,
+            &[
+                (*cargo_config.profile(), VergenKey::CargoProfile),
+                (*cargo_config.features(), VergenKey::CargoFeatures),
+                (
+                    *cargo_config.pkg_description(),
+                    VergenKey::CargoPkgDescription,
+                ),
+                (*cargo_config.pkg_authors(), VergenKey::CargoPkgAuthors),
+            ],
+        );
+    }
+}
+
+#[cfg(not(feature = "cargo"))]
+pub(crate) fn placeholder_cargo(_instructions: &Instructions, _config: &mut Config) {}
+
 #[cfg(all(test, feature = "cargo"))]
 mod test {
+    use super::{format_authors, glibc_version, musl_version, TargetNames};
     use crate::{
-        config::Instructions,
+        config::{Config, Instructions, VergenKey},
         testutils::{setup, teardown},
     };
     use std::env;
+    #[cfg(feature = "rustc")]
+    use {
+        crate::{feature::configure_rustc, provider::FakeRustc},
+        std::sync::Arc,
+    };
 
     #[test]
     #[serial_test::serial]
@@ -198,6 +1017,349 @@ mod test {
         *config.cargo_mut().target_triple_mut() = false;
         assert!(!config.cargo().has_enabled());
     }
+
+    #[test]
+    fn pkg_off_by_default() {
+        let config = Instructions::default();
+        assert!(!config.cargo().pkg_description);
+        assert!(!config.cargo().pkg_authors);
+        assert!(!config.cargo().pkg_authors_commas);
+        assert!(!config.cargo().pkg_homepage);
+        assert!(!config.cargo().pkg_repository);
+    }
+
+    #[test]
+    fn target_components_off_by_default() {
+        let mut config = Instructions::default();
+        assert!(!config.cargo().target_os);
+        assert!(!config.cargo().target_arch);
+        assert!(!config.cargo().target_env);
+        *config.cargo_mut().features_mut() = false;
+        *config.cargo_mut().profile_mut() = false;
+        *config.cargo_mut().target_triple_mut() = false;
+        *config.cargo_mut().target_env_mut() = true;
+        assert!(config.cargo().has_enabled());
+    }
+
+    #[test]
+    fn pkg_only() {
+        let mut config = Instructions::default();
+        *config.cargo_mut().features_mut() = false;
+        *config.cargo_mut().profile_mut() = false;
+        *config.cargo_mut().target_triple_mut() = false;
+        *config.cargo_mut().pkg_authors_mut() = true;
+        assert!(config.cargo().has_enabled());
+    }
+
+    #[test]
+    fn authors_formatting() {
+        assert_eq!(
+            format_authors("a <a@b.c>:d <d@e.f>", false),
+            "a <a@b.c>:d <d@e.f>"
+        );
+        assert_eq!(
+            format_authors("a <a@b.c>:d <d@e.f>", true),
+            "a <a@b.c>, d <d@e.f>"
+        );
+        assert_eq!(format_authors("a <a@b.c>", true), "a <a@b.c>");
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn pkg_entries() {
+        env::set_var("CARGO_PKG_DESCRIPTION", "a description");
+        env::set_var("CARGO_PKG_AUTHORS", "a:b");
+        let mut instructions = Instructions::default();
+        *instructions.cargo_mut().pkg_description_mut() = true;
+        *instructions.cargo_mut().pkg_authors_mut() = true;
+        *instructions.cargo_mut().pkg_authors_commas_mut() = true;
+        let mut config = Config::default();
+        super::configure_cargo(&instructions, &mut config);
+        env::remove_var("CARGO_PKG_DESCRIPTION");
+        env::remove_var("CARGO_PKG_AUTHORS");
+        assert_eq!(
+            config.cfg_map().get(&VergenKey::CargoPkgDescription),
+            Some(&Some("a description".to_string()))
+        );
+        assert_eq!(
+            config.cfg_map().get(&VergenKey::CargoPkgAuthors),
+            Some(&Some("a, b".to_string()))
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn pkg_urls() {
+        env::set_var("CARGO_PKG_HOMEPAGE", "https://example.com");
+        env::set_var("CARGO_PKG_REPOSITORY", "");
+        let mut instructions = Instructions::default();
+        *instructions.cargo_mut().pkg_homepage_mut() = true;
+        *instructions.cargo_mut().pkg_repository_mut() = true;
+        let mut config = Config::default();
+        super::configure_cargo(&instructions, &mut config);
+        env::remove_var("CARGO_PKG_HOMEPAGE");
+        env::remove_var("CARGO_PKG_REPOSITORY");
+        assert_eq!(
+            config.cfg_map().get(&VergenKey::CargoPkgHomepage),
+            Some(&Some("https://example.com".to_string()))
+        );
+        assert_eq!(
+            config.cfg_map().get(&VergenKey::CargoPkgRepository),
+            Some(&Some(String::new()))
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn pkg_license() {
+        env::set_var("CARGO_PKG_LICENSE", "MIT OR Apache-2.0");
+        env::remove_var("CARGO_PKG_LICENSE_FILE");
+        let mut instructions = Instructions::default();
+        *instructions.cargo_mut().pkg_license_mut() = true;
+        *instructions.cargo_mut().pkg_license_file_mut() = true;
+        let mut config = Config::default();
+        super::configure_cargo(&instructions, &mut config);
+        env::remove_var("CARGO_PKG_LICENSE");
+        assert_eq!(
+            config.cfg_map().get(&VergenKey::CargoPkgLicense),
+            Some(&Some("MIT OR Apache-2.0".to_string()))
+        );
+        assert_eq!(
+            config.cfg_map().get(&VergenKey::CargoPkgLicenseFile),
+            Some(&Some(String::new()))
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn pointer_width_and_endian() {
+        env::set_var("CARGO_CFG_TARGET_POINTER_WIDTH", "64");
+        env::remove_var("CARGO_CFG_TARGET_ENDIAN");
+        let mut instructions = Instructions::default();
+        *instructions.cargo_mut().target_pointer_width_mut() = true;
+        *instructions.cargo_mut().target_endian_mut() = true;
+        let mut config = Config::default();
+        super::configure_cargo(&instructions, &mut config);
+        env::remove_var("CARGO_CFG_TARGET_POINTER_WIDTH");
+        assert_eq!(
+            config.cfg_map().get(&VergenKey::CargoTargetPointerWidth),
+            Some(&Some("64".to_string()))
+        );
+        assert_eq!(
+            config.cfg_map().get(&VergenKey::CargoTargetEndian),
+            Some(&Some(String::new()))
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn target_entries() {
+        env::set_var("CARGO_CFG_TARGET_OS", "linux");
+        env::set_var("CARGO_CFG_TARGET_ARCH", "x86_64");
+        env::remove_var("CARGO_CFG_TARGET_ENV");
+        let mut instructions = Instructions::default();
+        *instructions.cargo_mut().target_os_mut() = true;
+        *instructions.cargo_mut().target_arch_mut() = true;
+        *instructions.cargo_mut().target_env_mut() = true;
+        let mut config = Config::default();
+        super::configure_cargo(&instructions, &mut config);
+        env::remove_var("CARGO_CFG_TARGET_OS");
+        env::remove_var("CARGO_CFG_TARGET_ARCH");
+        assert_eq!(
+            config.cfg_map().get(&VergenKey::CargoTargetOs),
+            Some(&Some("linux".to_string()))
+        );
+        assert_eq!(
+            config.cfg_map().get(&VergenKey::CargoTargetArch),
+            Some(&Some("x86_64".to_string()))
+        );
+        assert_eq!(
+            config.cfg_map().get(&VergenKey::CargoTargetEnv),
+            Some(&Some(String::new()))
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn cross_compile() {
+        let vars = [
+            ("HOST", "x86_64-unknown-linux-gnu"),
+            ("TARGET", "aarch64-linux-android"),
+            ("CARGO_CFG_TARGET_OS", "android"),
+            ("CARGO_CFG_TARGET_ARCH", "aarch64"),
+            ("CARGO_CFG_TARGET_ENV", ""),
+        ];
+        for (var, value) in &vars {
+            env::set_var(var, value);
+        }
+        let mut instructions = Instructions::default();
+        let cargo = instructions.cargo_mut();
+        *cargo.target_os_mut() = true;
+        *cargo.target_arch_mut() = true;
+        *cargo.target_env_mut() = true;
+        *cargo.is_cross_compile_mut() = true;
+        *cargo.target_names_mut() = TargetNames::Both;
+        let mut config = Config::default();
+        super::configure_cargo(&instructions, &mut config);
+        #[cfg(feature = "rustc")]
+        {
+            *instructions.providers_mut().rustc_mut() = Some(Arc::new(FakeRustc::default()));
+            configure_rustc(&instructions, &mut config).unwrap();
+        }
+        for (var, _) in &vars {
+            env::remove_var(var);
+        }
+
+        let target = [
+            (VergenKey::CargoTargetTriple, "aarch64-linux-android"),
+            (VergenKey::CargoTargetOs, "android"),
+            (VergenKey::CargoTargetArch, "aarch64"),
+            (VergenKey::CargoTargetEnv, ""),
+            (VergenKey::TargetTriple, "aarch64-linux-android"),
+            (VergenKey::TargetOs, "android"),
+            (VergenKey::TargetArch, "aarch64"),
+            (VergenKey::TargetEnv, ""),
+        ];
+        for (key, value) in &target {
+            assert_eq!(config.cfg_map()[key].as_deref(), Some(*value));
+        }
+        assert_eq!(
+            config.cfg_map()[&VergenKey::IsCrossCompile].as_deref(),
+            Some("true")
+        );
+        #[cfg(feature = "rustc")]
+        assert_eq!(
+            config.cfg_map()[&VergenKey::RustcHostTriple].as_deref(),
+            Some("x86_64-unknown-linux-gnu")
+        );
+
+        // Only the target keys describe the target
+        for (key, value) in config.cfg_map() {
+            if let Some(value) = value {
+                if !target.iter().any(|(target_key, _)| target_key == key) {
+                    assert!(
+                        !value.contains("aarch64"),
+                        "{} is a target value",
+                        key.name()
+                    );
+                    assert!(
+                        !value.contains("android"),
+                        "{} is a target value",
+                        key.name()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn libc_versions() {
+        assert_eq!(
+            glibc_version("glibc 2.36\n"),
+            Some("glibc 2.36".to_string())
+        );
+        assert_eq!(glibc_version("undefined variable\n"), None);
+        let ldd = "musl libc (x86_64)\nVersion 1.2.4\nDynamic Program Loader\nUsage: ldd [options] [--] pathname\n";
+        assert_eq!(musl_version(ldd), Some("musl 1.2.4".to_string()));
+        assert_eq!(musl_version("ldd (GNU libc) 2.36\n"), None);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn libc_version_cross_compile() {
+        env::set_var("HOST", "x86_64-unknown-linux-gnu");
+        env::set_var("TARGET", "aarch64-unknown-linux-gnu");
+        env::set_var("CARGO_CFG_TARGET_OS", "linux");
+        let mut instructions = Instructions::default();
+        *instructions.cargo_mut().libc_version_mut() = true;
+        let mut config = Config::default();
+        super::configure_cargo(&instructions, &mut config);
+        instructions.warn_skipped(&mut config);
+        env::remove_var("HOST");
+        env::remove_var("TARGET");
+        env::remove_var("CARGO_CFG_TARGET_OS");
+
+        assert!(config.cfg_map()[&VergenKey::CargoLibcVersion].is_none());
+        assert!(config
+            .warnings()
+            .iter()
+            .any(|warning| warning.contains("VERGEN_CARGO_LIBC_VERSION")
+                && warning.contains("cross compiling")));
+    }
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    #[test]
+    #[serial_test::serial]
+    fn libc_version_detected() {
+        env::set_var("HOST", "x86_64-unknown-linux-gnu");
+        env::set_var("TARGET", "x86_64-unknown-linux-gnu");
+        env::set_var("CARGO_CFG_TARGET_OS", "linux");
+        let mut instructions = Instructions::default();
+        *instructions.cargo_mut().libc_version_mut() = true;
+        let mut config = Config::default();
+        super::configure_cargo(&instructions, &mut config);
+        env::remove_var("HOST");
+        env::remove_var("TARGET");
+        env::remove_var("CARGO_CFG_TARGET_OS");
+
+        assert!(config.cfg_map()[&VergenKey::CargoLibcVersion]
+            .as_deref()
+            .map_or(false, |version| version.starts_with("glibc ")));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn target_names() {
+        env::set_var("TARGET", "x86_64-unknown-linux-gnu");
+        env::set_var("HOST", "x86_64-unknown-linux-gnu");
+        let configure = |names| {
+            let mut instructions = Instructions::default();
+            *instructions.cargo_mut().target_names_mut() = names;
+            *instructions.cargo_mut().is_cross_compile_mut() = true;
+            let mut config = Config::default();
+            super::configure_cargo(&instructions, &mut config);
+            config
+        };
+        let cargo = configure(TargetNames::Cargo);
+        let target = configure(TargetNames::Target);
+        env::remove_var("TARGET");
+        env::remove_var("HOST");
+
+        assert!(cargo.cfg_map()[&VergenKey::CargoTargetTriple].is_some());
+        assert!(cargo.cfg_map()[&VergenKey::TargetTriple].is_none());
+        assert!(target.cfg_map()[&VergenKey::CargoTargetTriple].is_none());
+        assert_eq!(
+            target.cfg_map()[&VergenKey::TargetTriple].as_deref(),
+            Some("x86_64-unknown-linux-gnu")
+        );
+        assert_eq!(
+            target.cfg_map()[&VergenKey::IsCrossCompile].as_deref(),
+            Some("false")
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn skipped_profile() {
+        setup();
+        env::remove_var("PROFILE");
+        let instructions = Instructions::default();
+        let mut config = Config::default();
+        super::configure_cargo(&instructions, &mut config);
+        teardown();
+
+        // Only the cargo feature ran, the other features' instructions are skipped too
+        instructions.warn_skipped(&mut config);
+        let warnings: Vec<&String> = config
+            .warnings()
+            .iter()
+            .filter(|warning| warning.contains("VERGEN_CARGO_"))
+            .collect();
+        assert_eq!(
+            warnings,
+            &["vergen: skipped VERGEN_CARGO_PROFILE because PROFILE isn't set"]
+        );
+    }
 }
 
 #[cfg(all(test, not(feature = "cargo")))]
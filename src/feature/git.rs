@@ -8,18 +8,36 @@
 
 //! `vergen` git feature implementation
 
+// On wasi, the repository is never opened, so only the fake repository reaches the gathering code
+#![cfg_attr(
+    all(target_os = "wasi", not(feature = "test-util")),
+    allow(dead_code, unused_imports)
+)]
+
 use crate::config::{Config, Instructions};
 use anyhow::Result;
-use std::path::Path;
+use std::{path::Path, time::Instant};
+#[cfg(all(feature = "git", not(target_os = "wasi")))]
+use {
+    crate::command,
+    git2::{
+        BranchType, Commit, DescribeOptions, ErrorCode, ObjectType, ReferenceType, Repository,
+        Status, StatusOptions, TreeWalkMode, TreeWalkResult,
+    },
+    std::{cell::Cell, collections::BTreeSet, fmt, fs, process::Command},
+};
 #[cfg(feature = "git")]
 use {
     crate::{
-        config::VergenKey,
-        error::Error,
-        feature::{self, add_entry, TimestampKind},
+        command::check_deadline,
+        config::{Vcs, VergenKey},
+        error::{ConfigError, Error},
+        feature::{self, add_entry, add_entry_or_skip, add_placeholders, skip_all, TimestampKind},
+        merge::{pick, SetFields},
+        paths,
+        provider::{local_offset, now_utc, GitProvider, PathCommit},
+        release::release_url,
     },
-    getset::{CopyGetters, Getters, MutGetters},
-    git2::{BranchType, DescribeFormatOptions, DescribeOptions, Repository},
     std::{env, path::PathBuf},
     time::{format_description, macros::format_description, OffsetDateTime, UtcOffset},
 };
@@ -27,6 +45,11 @@ use {
 /// The semver kind to output
 #[cfg(feature = "git")]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum SemverKind {
     /// Output the `git describe` kind
     Normal,
@@ -37,6 +60,11 @@ pub enum SemverKind {
 /// The SHA kind to output
 #[cfg(feature = "git")]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum ShaKind {
     /// Output the normal 40 digit SHA: `VERGEN_GIT_SHA`
     Normal,
@@ -61,6 +89,20 @@ pub enum ShaKind {
 /// | `cargo:rustc-env=VERGEN_GIT_SEMVER_LIGHTWEIGHT=feature-test` | |
 /// | `cargo:rustc-env=VERGEN_GIT_SHA=95fc0f5d066710f16e0c23ce3239d6e040abca0d` | * |
 /// | `cargo:rustc-env=VERGEN_GIT_SHA_SHORT=95fc0f5` | |
+/// | `cargo:rustc-env=VERGEN_GIT_TRACKED_FILES=42` | |
+/// | `cargo:rustc-env=VERGEN_GIT_COMMIT_AGE_SECONDS=86400` | |
+/// | `cargo:rustc-env=VERGEN_GIT_COMMITS_SINCE_TAG=3` | |
+/// | `cargo:rustc-env=VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS=Fix the docs; Add a feature; Bump the version` | |
+/// | `cargo:rustc-env=VERGEN_GIT_SIGNED=true` | |
+/// | `cargo:rustc-env=VERGEN_GIT_SIGNER=4AEE18F83AFDEB23` | |
+/// | `cargo:rustc-env=VERGEN_GIT_BRANCH_COUNT=12` | |
+/// | `cargo:rustc-env=VERGEN_GIT_TAG_COUNT=87` | |
+/// | `cargo:rustc-env=VERGEN_GIT_ROOT=/Users/yoda/projects/rust-lang/vergen` | |
+/// | `cargo:rustc-env=VERGEN_GIT_WORKTREE=/Users/yoda/projects/rust-lang/vergen-hotfix` | |
+/// | `cargo:rustc-env=VERGEN_GIT_TREE_HASH=3b18e512dba79e4c8300dd08aeb37f8e728b8dad` | |
+/// | `cargo:rustc-env=VERGEN_GIT_CONTRIBUTOR_COUNT=27` | |
+/// | `cargo:rustc-env=VERGEN_GIT_OBJECT_FORMAT=sha1` | |
+/// | `cargo:rustc-env=VERGEN_GIT_PARENT_COUNT=1` | |
 /// | `cargo:rerun-if-changed=/Users/yoda/projects/rust-lang/vergen/.git/HEAD` | * |
 /// | `cargo:rerun-if-changed=/Users/yoda/projects/rust-lang/vergen/.git/refs/heads/feature/git2` | * |
 ///
@@ -69,10 +111,35 @@ pub enum ShaKind {
 /// * If the `rerun_on_head_changed` field is false, the `cargo:rerun-if-changed` instructions will not be generated.
 /// * If the `semver` field is false, the `VERGEN_GIT_SEMVER` instruction will not be generated.
 /// * If the `sha` field is fale, the `VERGEN_GIT_SHA` instruction will not be generated.
+/// * If the `tracked_files` field is true, the `VERGEN_GIT_TRACKED_FILES` instruction will be generated.  This is the equivalent of `git ls-files | wc -l` and is `0` in a bare or empty repository.
+/// * If the `commit_age` field is true, the `VERGEN_GIT_COMMIT_AGE_SECONDS` instruction will be generated.  This is the number of seconds between the `HEAD` committer time and the build, and is useful for detecting stale builds.
+/// * **NOTE** - The commit age depends on when the build runs, which defeats reproducible builds.  If the `SOURCE_DATE_EPOCH` environment variable is set, the commit age is always `0`.
+/// * If the `commits_since_tag` field is true, the `VERGEN_GIT_COMMITS_SINCE_TAG` instruction will be generated.  This counts the commits reachable from `HEAD` but not from any tag, so every commit is counted if your repository has no tags.
+/// * If the `commits_since_tag_subjects` field is set, the `VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS` instruction will also be generated.  This is the first line of each commit message, newest first, joined with `; ` and truncated to the given number of characters.
+/// * If the `signed` field is true, the `VERGEN_GIT_SIGNED` and `VERGEN_GIT_SIGNER` instructions will be generated.  The commit is signed if `git log -1 --format=%G?` reports a good signature (`G`) or a good signature of unknown validity (`U`), and the signer is the signing key id.  An unsigned commit is `false` with an empty signer.
+/// * If the `branch_count` field is true, the `VERGEN_GIT_BRANCH_COUNT` instruction will be generated.  This is the number of local and remote branches, the equivalent of `git branch -a | wc -l` without the detached `HEAD` and `origin/HEAD -> origin/main` lines.
+/// * If the `tag_count` field is true, the `VERGEN_GIT_TAG_COUNT` instruction will be generated.  This is the equivalent of `git tag | wc -l`.
+/// * If the `repo_root` field is true, the `VERGEN_GIT_ROOT` instruction will be generated.  This is the absolute path of the working tree, the equivalent of `git rev-parse --show-toplevel`, for a dev-mode binary that loads assets relative to the checkout.  It is empty for a bare repository, or outside a repository if no other instruction needs one.
+/// * If the `worktree` field is true, the `VERGEN_GIT_WORKTREE` instruction will be generated.  This is the absolute path of the working tree when the build runs in a linked worktree, created by `git worktree add`, and is empty in the main working tree.
+/// * If the `tree_hash` field is true, the `VERGEN_GIT_TREE_HASH` instruction will be generated.  This is the hash of the tree of the latest commit, the equivalent of `git rev-parse HEAD^{tree}`.  Unlike the SHA, it only changes when the content does, i.e. not for a reworded commit or a rebase that keeps the tree, so it suits a cache key.  It is empty outside a repository if no other instruction needs one.
+/// * If the `contributor_count` field is true, the `VERGEN_GIT_CONTRIBUTOR_COUNT` instruction will be generated.  This is the number of distinct author emails in the history of `HEAD`, after the `.mailmap`, the equivalent of `git shortlog -sne HEAD | wc -l`.
+/// * If the `merge_base` field is true, the `VERGEN_GIT_MERGE_BASE` instruction will be generated.  This is the commit where `HEAD` diverged from the `merge_base_ref` field, `origin/main` by default, the equivalent of `git merge-base HEAD origin/main`, for PR-diff tooling that reports the divergence point.  It is empty if the ref is None, doesn't resolve, i.e. in a clone without that remote, or shares no history with `HEAD`, and outside a repository if no other instruction needs one.
+/// * **NOTE** - Only a change to `HEAD` reruns the build script, so a fetch that moves the base ref doesn't update the merge base until the next commit or checkout.
+/// * If the `object_format` field is true, the `VERGEN_GIT_OBJECT_FORMAT` instruction will be generated.  This is the hash algorithm of the repository's object ids, the output of `git rev-parse --show-object-format`: `sha1`, the 40 hex digit SHAs of almost every repository, or `sha256`, the 64 hex digit SHAs of a repository created with `git init --object-format=sha256`.  A `git` older than 2.25, which can't show the format, only supports `sha1`.
+/// * **NOTE** - The `git2` library the repository is read with can't open a `sha256` repository yet, so in one the `git` feature fails, or uses the placeholders with a `cargo:warning` if the `emit_all_with_placeholders` field is true, see [unavailable values](crate::Config#unavailable-values).
+/// * If the `parent_count` field is true, the `VERGEN_GIT_PARENT_COUNT` instruction will be generated.  This is the number of parents of the `HEAD` commit, the equivalent of `git rev-parse HEAD^@ | wc -l`: `0` for the initial commit, `1` for a regular commit, and `2` or more for a merge commit, for versioning logic that treats merges differently.
+/// * **NOTE** - The contributor count walks every commit, which is slow on a large history.  To only pay for it in release builds, use [`emit_when`](crate::Config::emit_when) with [`Condition::only_profiles`](crate::Condition::only_profiles), which skips the walk in the other builds.  In a shallow clone only the fetched history is counted, and a `cargo:warning` says the count is partial.
+/// * **NOTE** - A linked worktree has a `.git` file with a `gitdir:` line rather than a `.git` directory.  The other instructions describe the worktree's `HEAD`, and the branch refs, which live in the main repository, are watched for the `cargo:rerun-if-changed` instructions.
+/// * **NOTE** - The repository root is a path on the build machine, which ends up in the binary.  Set the [`redact_paths`](crate::Sanitize) field to replace the home directory with `~`.
+/// * **NOTE** - The signature is checked by running `git`, so git's GPG configuration must be functional.  Any other signature status, i.e. an untrusted or expired key, is reported as unsigned.
 /// * **NOTE** - The SHA defaults to the [`Normal`](ShaKind::Normal) variant, but can be changed via the `sha_kind` field.
+/// * If the `path_scope` field is set, the SHA and commit date/time/age instructions describe the last commit that touched that path, the equivalent of `git log -1 -- <path>`, rather than `HEAD`.  This is useful for a crate in a subdirectory of a monorepo.  The path is relative to the repository root, and the values are empty if the path has no history.
+/// * If the `ref_name` field is set, i.e. `Some("origin/release")`, every instruction describes that ref rather than `HEAD`, for a build from a worktree checked out at a different commit than the one being released.  Any revision `git rev-parse` understands works, i.e. a remote-tracking branch, a tag, or a SHA.  The build fails if it doesn't resolve to a commit.  The branch is the short name of a local or remote-tracking branch, i.e. `origin/release`, the tracked file count is the number of files in the commit, the [SemVer] is never dirty, and the `cargo:rerun-if-changed` instructions watch the ref rather than `HEAD`.  In a configuration file, the field is named `ref`.
+/// * If the `uppercase_sha` field is true, the `VERGEN_GIT_SHA` and `VERGEN_GIT_SHA_SHORT` values are upper case hex, i.e. `95FC0F5`.
 /// * **NOTE** - The [SemVer] defaults to the [`Normal`](SemverKind::Normal) variant, but can be changed via the `semver_kind` field.
-/// * **NOTE** - The [SemVer] is only useful if you have tags on your repository.  If your repository has no tags, this will default to [`CARGO_PKG_VERSION`].
-/// * **NOTE** - You can add a `-dirty` flag to the [SemVer] output via the `semver_dirty` field.
+/// * **NOTE** - The [SemVer] is only useful if you have tags on your repository.  If no tag is reachable from `HEAD`, it falls back to the abbreviated commit SHA, the equivalent of `git describe --always`, i.e. `75b390d`.  If the `describe_always` field is false, or the repository has no commits, this will default to [`CARGO_PKG_VERSION`] instead.
+/// * **NOTE** - You can add a `-dirty` flag to the [SemVer] output via the `semver_dirty` field.  This is the equivalent of `git describe --dirty=<suffix>`, i.e. `Some("-modified")` gives `v1.2.3-4-gabc1234-modified` when tracked files have uncommitted changes.  The suffix is also added to the [`CARGO_PKG_VERSION`] fallback.
+/// * If the `check_tag_version` field is true and `HEAD` is exactly on a version tag, i.e. `v1.4.0`, the tag is compared against [`CARGO_PKG_VERSION`], ignoring the `v` prefix and any build metadata.  A mismatch is reported as a `cargo:warning`, or fails the build if the `check_tag_version_strict` field is also true.  Nothing is checked if `HEAD` isn't tagged, the tag isn't a version, or `CARGO_PKG_VERSION` isn't set.
 /// * **NOTE** - The [`Lightweight`](SemverKind::Lightweight) variant will only differ from the [`Normal`](SemverKind::Normal) variant if you use [lightweight] tags in your repository.
 /// * **NOTE** - By default, the date/time related instructions will use [`UTC`](crate::TimeZone::Utc).
 /// * **NOTE** - The date/time instruction output is determined by the [`kind`](crate::TimestampKind) field and can be any combination of the three.
@@ -110,378 +177,3527 @@ vergen(config)?;
 /// [`CARGO_PKG_VERSION`]: https://doc.rust-lang.org/cargo/reference/environment-variables.html#environment-variables-cargo-sets-for-crates
 ///
 #[cfg(feature = "git")]
-#[derive(Clone, Debug, CopyGetters, Getters, MutGetters)]
-#[getset(get_mut = "pub")]
+#[derive(Clone, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
 pub struct Git {
     /// Enable/Disable the git output
-    #[getset(get = "pub(crate)")]
     enabled: bool,
     /// Optional git base directory
-    #[getset(get = "pub(crate)")]
     base_dir: Option<PathBuf>,
+    /// Limit the SHA and commit date/time/age to the last commit that touched this path, relative to the repository root
+    path_scope: Option<PathBuf>,
+    /// The ref to describe rather than `HEAD`, i.e. `origin/release`
+    #[cfg_attr(feature = "serde", serde(rename = "ref"))]
+    ref_name: Option<String>,
     /// Enable/Disable the `VERGEN_GIT_BRANCH` instruction
-    #[getset(get = "pub(crate)")]
     branch: bool,
     /// Enable/Disable the `VERGEN_GIT_COMMIT_DATE`, `VERGEN_GIT_COMMIT_TIME`, and `VERGEN_GIT_COMMIT_TIMESTAMP` instructions
-    #[getset(get = "pub(crate)")]
     commit_timestamp: bool,
     /// The timezone to use for the date/time instructions.
-    #[getset(get = "pub(crate)")]
     commit_timestamp_timezone: feature::TimeZone,
     /// The kind of date/time instructions to output.
-    #[getset(get = "pub(crate)")]
     commit_timestamp_kind: TimestampKind,
     /// Enable/Disable the `cargo:rerun-if-changed` instructions
-    #[getset(get = "pub(crate)")]
     rerun_on_head_change: bool,
     /// Enable/Disable the `VERGEN_GIT_SEMVER` instruction
-    #[getset(get = "pub(crate)")]
     semver: bool,
     /// The kind of semver instruction to output.
-    #[getset(get = "pub(crate)")]
     semver_kind: SemverKind,
     /// Enable/Disable the `-dirty` flag on `VERGEN_GIT_SEMVER*` output
-    #[getset(get_copy = "pub(crate)")]
-    semver_dirty: Option<&'static str>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "deserialize_semver_dirty")
+    )]
+    semver_dirty: SemverDirty,
+    /// Fall back to the abbreviated SHA for `VERGEN_GIT_SEMVER*` when no tag is reachable, rather than `CARGO_PKG_VERSION`
+    describe_always: bool,
+    /// Compare the version tag on `HEAD` against `CARGO_PKG_VERSION`
+    check_tag_version: bool,
+    /// Fail, rather than warn, when the version tag on `HEAD` and `CARGO_PKG_VERSION` disagree.
+    check_tag_version_strict: bool,
     /// Enable/Disable the `VERGEN_GIT_SHA` instruction
-    #[getset(get = "pub(crate)")]
     sha: bool,
     /// The kind of SHA instruction to output.
-    #[getset(get = "pub(crate)")]
     sha_kind: ShaKind,
+    /// Output the `VERGEN_GIT_SHA` and `VERGEN_GIT_SHA_SHORT` values in upper case
+    uppercase_sha: bool,
+    /// Enable/Disable the `VERGEN_GIT_TRACKED_FILES` instruction
+    tracked_files: bool,
+    /// Enable/Disable the `VERGEN_GIT_COMMIT_AGE_SECONDS` instruction
+    commit_age: bool,
+    /// Enable/Disable the `VERGEN_GIT_COMMITS_SINCE_TAG` instruction
+    commits_since_tag: bool,
+    /// The maximum length of the `VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS` instruction, or None to disable it
+    commits_since_tag_subjects: Option<usize>,
+    /// Enable/Disable the `VERGEN_GIT_SIGNED` and `VERGEN_GIT_SIGNER` instructions
+    signed: bool,
+    /// Enable/Disable the `VERGEN_GIT_BRANCH_COUNT` instruction
+    branch_count: bool,
+    /// Enable/Disable the `VERGEN_GIT_TAG_COUNT` instruction
+    tag_count: bool,
+    /// Enable/Disable the `VERGEN_GIT_ROOT` instruction
+    repo_root: bool,
+    /// Enable/Disable the `VERGEN_GIT_WORKTREE` instruction
+    worktree: bool,
+    /// Enable/Disable the `VERGEN_GIT_TREE_HASH` instruction
+    tree_hash: bool,
+    /// Enable/Disable the `VERGEN_GIT_CONTRIBUTOR_COUNT` instruction
+    contributor_count: bool,
+    /// Enable/Disable the `VERGEN_GIT_MERGE_BASE` instruction
+    merge_base: bool,
+    /// The ref `HEAD` is compared against for the `VERGEN_GIT_MERGE_BASE` instruction, i.e. `origin/main`
+    merge_base_ref: Option<String>,
+    /// Enable/Disable the `VERGEN_GIT_OBJECT_FORMAT` instruction
+    object_format: bool,
+    /// Enable/Disable the `VERGEN_GIT_PARENT_COUNT` instruction
+    parent_count: bool,
+    /// The fields set through their `set_*` setter, which win in a [merge](crate::Config::merge)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    set_fields: SetFields,
 }
 
 #[cfg(feature = "git")]
-impl Default for Git {
-    fn default() -> Self {
-        let base_dir = if let Ok(dir) = env::current_dir() {
-            Some(dir)
-        } else {
-            None
-        };
-        Self {
-            enabled: true,
-            base_dir,
-            branch: true,
-            commit_timestamp: true,
-            commit_timestamp_timezone: feature::TimeZone::Utc,
-            commit_timestamp_kind: TimestampKind::Timestamp,
-            rerun_on_head_change: true,
-            semver: true,
-            semver_kind: SemverKind::Normal,
-            semver_dirty: None,
-            sha: true,
-            sha_kind: ShaKind::Normal,
-        }
+impl Git {
+    /// Enable/Disable the git output
+    pub(crate) fn enabled(&self) -> &bool {
+        &self.enabled
     }
-}
 
-#[cfg(feature = "git")]
-impl Git {
-    pub(crate) fn has_enabled(&self) -> bool {
-        self.enabled
-            && (self.branch
-                || self.commit_timestamp
-                || self.rerun_on_head_change
-                || self.semver
-                || self.sha)
+    /// Enable/Disable the git output
+    pub fn enabled_mut(&mut self) -> &mut bool {
+        &mut self.enabled
     }
-}
 
-#[cfg(not(feature = "git"))]
-pub(crate) fn configure_git<T>(
-    _instructions: &Instructions,
-    _repo: Option<T>,
-    _config: &mut Config,
-) -> Result<()>
-where
-    T: AsRef<Path>,
-{
-    Ok(())
-}
+    /// Enable/Disable the git output
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "enabled");
+        self.enabled = enabled;
+    }
 
-#[cfg(feature = "git")]
-pub(crate) fn configure_git<T>(
-    instructions: &Instructions,
-    repo_path_opt: Option<T>,
-    config: &mut Config,
-) -> Result<()>
-where
-    T: AsRef<Path>,
-{
-    if let Some(repo_path) = repo_path_opt {
-        let git_config = instructions.git();
-        if git_config.has_enabled() {
-            let repo = Repository::discover(repo_path)?;
-            let ref_head = repo.find_reference("HEAD")?;
-            let repo_path = repo.path().to_path_buf();
+    /// Optional git base directory
+    pub(crate) fn base_dir(&self) -> &Option<PathBuf> {
+        &self.base_dir
+    }
 
-            if *git_config.branch() {
-                add_branch_name(&repo, config)?;
-            }
+    /// Optional git base directory
+    pub fn base_dir_mut(&mut self) -> &mut Option<PathBuf> {
+        &mut self.base_dir
+    }
 
-            if *git_config.commit_timestamp() || *git_config.sha() {
-                let commit = ref_head.peel_to_commit()?;
-
-                if *git_config.commit_timestamp() {
-                    let timestamp =
-                        OffsetDateTime::from_unix_timestamp(commit.time().seconds()).unwrap();
-
-                    match git_config.commit_timestamp_timezone() {
-                        crate::TimeZone::Utc => {
-                            add_config_entries(config, git_config, &timestamp);
-                        }
-                        crate::TimeZone::Local => {
-                            add_config_entries(
-                                config,
-                                git_config,
-                                &timestamp.to_offset(
-                                    UtcOffset::current_local_offset()
-                                        .expect("unable to get local offset"),
-                                ),
-                            );
-                        }
-                    }
-                }
+    /// Optional git base directory
+    pub fn set_base_dir(&mut self, base_dir: Option<PathBuf>) {
+        self.set_fields.mark(MERGED_FIELDS, "base_dir");
+        self.base_dir = base_dir;
+    }
 
-                if *git_config.sha() {
-                    match git_config.sha_kind() {
-                        crate::ShaKind::Normal => {
-                            add_entry(
-                                config.cfg_map_mut(),
-                                VergenKey::Sha,
-                                Some(commit.id().to_string()),
-                            );
-                        }
-                        crate::ShaKind::Short => {
-                            let obj = repo.revparse_single("HEAD")?;
-                            add_entry(
-                                config.cfg_map_mut(),
-                                VergenKey::ShortSha,
-                                obj.short_id()?.as_str().map(str::to_string),
-                            );
-                        }
-                        crate::ShaKind::Both => {
-                            add_entry(
-                                config.cfg_map_mut(),
-                                VergenKey::Sha,
-                                Some(commit.id().to_string()),
-                            );
-
-                            let obj = repo.revparse_single("HEAD")?;
-                            add_entry(
-                                config.cfg_map_mut(),
-                                VergenKey::ShortSha,
-                                obj.short_id()?.as_str().map(str::to_string),
-                            );
-                        }
-                    }
-                }
-            }
+    /// Limit the SHA and commit date/time/age to the last commit that touched this path, relative to the repository root
+    pub(crate) fn path_scope(&self) -> &Option<PathBuf> {
+        &self.path_scope
+    }
 
-            if *git_config.semver() {
-                let dirty = git_config.semver_dirty();
-                match *git_config.semver_kind() {
-                    crate::SemverKind::Normal => {
-                        add_semver(&repo, &DescribeOptions::new(), false, dirty, config);
-                    }
-                    crate::SemverKind::Lightweight => {
-                        let mut opts = DescribeOptions::new();
-                        let _ = opts.describe_tags();
+    /// Limit the SHA and commit date/time/age to the last commit that touched this path, relative to the repository root
+    pub fn path_scope_mut(&mut self) -> &mut Option<PathBuf> {
+        &mut self.path_scope
+    }
 
-                        add_semver(&repo, &opts, true, dirty, config);
-                    }
-                }
-            }
+    /// Limit the SHA and commit date/time/age to the last commit that touched this path, relative to the repository root
+    pub fn set_path_scope(&mut self, path_scope: Option<PathBuf>) {
+        self.set_fields.mark(MERGED_FIELDS, "path_scope");
+        self.path_scope = path_scope;
+    }
 
-            if let Ok(resolved) = ref_head.resolve() {
-                if let Some(name) = resolved.name() {
-                    let path = repo_path.join(name);
-                    // Check whether the path exists in the filesystem before emitting it
-                    if path.exists() {
-                        *config.ref_path_mut() = Some(path);
-                    }
-                }
-            }
-            *config.head_path_mut() = Some(repo_path.join("HEAD"));
-        }
+    /// The ref to describe rather than `HEAD`, i.e. `origin/release`
+    pub(crate) fn ref_name(&self) -> &Option<String> {
+        &self.ref_name
     }
-    Ok(())
-}
 
-#[cfg(feature = "git")]
-fn add_config_entries(config: &mut Config, git_config: &Git, now: &OffsetDateTime) {
-    match git_config.commit_timestamp_kind() {
-        TimestampKind::DateOnly => add_date_entry(config, now),
-        TimestampKind::TimeOnly => add_time_entry(config, now),
-        TimestampKind::DateAndTime => {
-            add_date_entry(config, now);
-            add_time_entry(config, now);
-        }
-        TimestampKind::Timestamp => add_timestamp_entry(config, now),
-        TimestampKind::All => {
-            add_date_entry(config, now);
-            add_time_entry(config, now);
-            add_timestamp_entry(config, now);
-        }
+    /// The ref to describe rather than `HEAD`, i.e. `origin/release`
+    pub fn ref_name_mut(&mut self) -> &mut Option<String> {
+        &mut self.ref_name
     }
-}
 
-#[cfg(feature = "git")]
-fn add_date_entry(config: &mut Config, now: &OffsetDateTime) {
-    add_entry(
-        config.cfg_map_mut(),
-        VergenKey::CommitDate,
-        now.format(format_description!("[year]-[month]-[day]")).ok(),
-    );
-}
+    /// The ref to describe rather than `HEAD`, i.e. `origin/release`
+    pub fn set_ref_name(&mut self, ref_name: Option<String>) {
+        self.set_fields.mark(MERGED_FIELDS, "ref_name");
+        self.ref_name = ref_name;
+    }
 
-#[cfg(feature = "git")]
-fn add_time_entry(config: &mut Config, now: &OffsetDateTime) {
-    add_entry(
-        config.cfg_map_mut(),
-        VergenKey::CommitTime,
-        now.format(format_description!("[hour]-[minute]-[second]"))
-            .ok(),
-    );
-}
+    /// Enable/Disable the `VERGEN_GIT_BRANCH` instruction
+    pub(crate) fn branch(&self) -> &bool {
+        &self.branch
+    }
 
-#[cfg(feature = "git")]
-fn add_timestamp_entry(config: &mut Config, now: &OffsetDateTime) {
-    add_entry(
-        config.cfg_map_mut(),
-        VergenKey::CommitTimestamp,
-        now.format(&format_description::well_known::Rfc3339).ok(),
-    );
-}
+    /// Enable/Disable the `VERGEN_GIT_BRANCH` instruction
+    pub fn branch_mut(&mut self) -> &mut bool {
+        &mut self.branch
+    }
 
-#[cfg(feature = "git")]
-fn add_branch_name(repo: &Repository, config: &mut Config) -> Result<()> {
-    if repo.head_detached()? {
-        add_entry(
-            config.cfg_map_mut(),
-            VergenKey::Branch,
-            Some("detached HEAD".to_string()),
-        );
-    } else {
-        let locals = repo.branches(Some(BranchType::Local))?;
-        for (local, _bt) in locals.filter_map(std::result::Result::ok) {
-            if local.is_head() {
-                if let Some(name) = local.name()? {
-                    add_entry(
-                        config.cfg_map_mut(),
-                        VergenKey::Branch,
-                        Some(name.to_string()),
-                    );
-                }
-            }
-        }
+    /// Enable/Disable the `VERGEN_GIT_BRANCH` instruction
+    pub fn set_branch(&mut self, branch: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "branch");
+        self.branch = branch;
     }
-    Ok(())
-}
 
-#[cfg(feature = "git")]
-fn add_semver(
-    repo: &Repository,
-    opts: &DescribeOptions,
-    lw: bool,
-    dirty: Option<&'static str>,
-    config: &mut Config,
-) {
-    let key = if lw {
-        VergenKey::SemverLightweight
-    } else {
-        VergenKey::Semver
-    };
-    let mut format_opts = DescribeFormatOptions::new();
-    if let Some(dirty_text) = dirty {
-        let _ = format_opts.dirty_suffix(dirty_text);
-    };
+    /// Enable/Disable the `VERGEN_GIT_COMMIT_DATE`, `VERGEN_GIT_COMMIT_TIME`, and `VERGEN_GIT_COMMIT_TIMESTAMP` instructions
+    pub(crate) fn commit_timestamp(&self) -> &bool {
+        &self.commit_timestamp
+    }
 
-    let semver: Option<String> = repo
-        .describe(opts)
-        .map_or_else(
-            |_| env::var("CARGO_PKG_VERSION").map_err(Error::from),
-            |x| x.format(Some(&format_opts)).map_err(Error::from),
-        )
-        .ok();
-    add_entry(config.cfg_map_mut(), key, semver);
-}
+    /// Enable/Disable the `VERGEN_GIT_COMMIT_DATE`, `VERGEN_GIT_COMMIT_TIME`, and `VERGEN_GIT_COMMIT_TIMESTAMP` instructions
+    pub fn commit_timestamp_mut(&mut self) -> &mut bool {
+        &mut self.commit_timestamp
+    }
 
-#[cfg(all(test, feature = "git"))]
-mod test {
-    use super::{SemverKind, ShaKind};
-    use crate::{
-        config::Instructions,
-        feature::{TimeZone, TimestampKind},
-    };
+    /// Enable/Disable the `VERGEN_GIT_COMMIT_DATE`, `VERGEN_GIT_COMMIT_TIME`, and `VERGEN_GIT_COMMIT_TIMESTAMP` instructions
+    pub fn set_commit_timestamp(&mut self, commit_timestamp: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "commit_timestamp");
+        self.commit_timestamp = commit_timestamp;
+    }
 
-    #[test]
-    fn git_config() {
-        let mut config = Instructions::default();
-        assert!(config.git().branch);
-        assert!(config.git().commit_timestamp);
-        assert_eq!(config.git().commit_timestamp_timezone, TimeZone::Utc);
-        assert_eq!(config.git().commit_timestamp_kind, TimestampKind::Timestamp);
-        assert!(config.git().rerun_on_head_change);
-        assert!(config.git().semver);
-        assert_eq!(config.git().semver_kind, SemverKind::Normal);
-        assert!(config.git().sha);
-        assert_eq!(config.git().sha_kind, ShaKind::Normal);
-        config.git_mut().commit_timestamp_kind = TimestampKind::All;
-        assert_eq!(config.git().commit_timestamp_kind, TimestampKind::All);
+    /// The timezone to use for the date/time instructions.
+    pub(crate) fn commit_timestamp_timezone(&self) -> &feature::TimeZone {
+        &self.commit_timestamp_timezone
     }
 
-    #[test]
-    fn not_enabled() {
-        let mut config = Instructions::default();
-        *config.git_mut().enabled_mut() = false;
-        assert!(!config.git().has_enabled());
+    /// The timezone to use for the date/time instructions.
+    pub fn commit_timestamp_timezone_mut(&mut self) -> &mut feature::TimeZone {
+        &mut self.commit_timestamp_timezone
     }
 
-    #[test]
-    fn no_branch() {
-        let mut config = Instructions::default();
-        *config.git_mut().branch_mut() = false;
-        assert!(config.git().has_enabled());
+    /// The timezone to use for the date/time instructions.
+    pub fn set_commit_timestamp_timezone(&mut self, commit_timestamp_timezone: feature::TimeZone) {
+        self.set_fields
+            .mark(MERGED_FIELDS, "commit_timestamp_timezone");
+        self.commit_timestamp_timezone = commit_timestamp_timezone;
     }
 
-    #[test]
-    fn no_timestamp() {
-        let mut config = Instructions::default();
-        *config.git_mut().branch_mut() = false;
-        *config.git_mut().commit_timestamp_mut() = false;
-        assert!(config.git().has_enabled());
+    /// The kind of date/time instructions to output.
+    pub(crate) fn commit_timestamp_kind(&self) -> &TimestampKind {
+        &self.commit_timestamp_kind
     }
 
-    #[test]
-    fn no_rerun_on_head_change() {
-        let mut config = Instructions::default();
-        *config.git_mut().branch_mut() = false;
-        *config.git_mut().commit_timestamp_mut() = false;
-        *config.git_mut().rerun_on_head_change_mut() = false;
-        assert!(config.git().has_enabled());
+    /// The kind of date/time instructions to output.
+    pub fn commit_timestamp_kind_mut(&mut self) -> &mut TimestampKind {
+        &mut self.commit_timestamp_kind
     }
 
-    #[test]
-    fn no_semver() {
-        let mut config = Instructions::default();
-        *config.git_mut().branch_mut() = false;
-        *config.git_mut().commit_timestamp_mut() = false;
-        *config.git_mut().rerun_on_head_change_mut() = false;
-        *config.git_mut().semver_mut() = false;
-        assert!(config.git().has_enabled());
+    /// The kind of date/time instructions to output.
+    pub fn set_commit_timestamp_kind(&mut self, commit_timestamp_kind: TimestampKind) {
+        self.set_fields.mark(MERGED_FIELDS, "commit_timestamp_kind");
+        self.commit_timestamp_kind = commit_timestamp_kind;
     }
 
-    #[test]
-    fn nothing() {
-        let mut config = Instructions::default();
-        *config.git_mut().branch_mut() = false;
-        *config.git_mut().commit_timestamp_mut() = false;
-        *config.git_mut().rerun_on_head_change_mut() = false;
-        *config.git_mut().semver_mut() = false;
-        *config.git_mut().sha_mut() = false;
-        assert!(!config.git().has_enabled());
+    /// Enable/Disable the `cargo:rerun-if-changed` instructions
+    pub(crate) fn rerun_on_head_change(&self) -> &bool {
+        &self.rerun_on_head_change
+    }
+
+    /// Enable/Disable the `cargo:rerun-if-changed` instructions
+    pub fn rerun_on_head_change_mut(&mut self) -> &mut bool {
+        &mut self.rerun_on_head_change
+    }
+
+    /// Enable/Disable the `cargo:rerun-if-changed` instructions
+    pub fn set_rerun_on_head_change(&mut self, rerun_on_head_change: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "rerun_on_head_change");
+        self.rerun_on_head_change = rerun_on_head_change;
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_SEMVER` instruction
+    pub(crate) fn semver(&self) -> &bool {
+        &self.semver
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_SEMVER` instruction
+    pub fn semver_mut(&mut self) -> &mut bool {
+        &mut self.semver
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_SEMVER` instruction
+    pub fn set_semver(&mut self, semver: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "semver");
+        self.semver = semver;
+    }
+
+    /// The kind of semver instruction to output.
+    pub(crate) fn semver_kind(&self) -> &SemverKind {
+        &self.semver_kind
+    }
+
+    /// The kind of semver instruction to output.
+    pub fn semver_kind_mut(&mut self) -> &mut SemverKind {
+        &mut self.semver_kind
+    }
+
+    /// The kind of semver instruction to output.
+    pub fn set_semver_kind(&mut self, semver_kind: SemverKind) {
+        self.set_fields.mark(MERGED_FIELDS, "semver_kind");
+        self.semver_kind = semver_kind;
+    }
+
+    /// Enable/Disable the `-dirty` flag on `VERGEN_GIT_SEMVER*` output
+    pub(crate) fn semver_dirty(&self) -> SemverDirty {
+        self.semver_dirty
+    }
+
+    /// Enable/Disable the `-dirty` flag on `VERGEN_GIT_SEMVER*` output
+    pub fn semver_dirty_mut(&mut self) -> &mut SemverDirty {
+        &mut self.semver_dirty
+    }
+
+    /// Enable/Disable the `-dirty` flag on `VERGEN_GIT_SEMVER*` output
+    pub fn set_semver_dirty(&mut self, semver_dirty: SemverDirty) {
+        self.set_fields.mark(MERGED_FIELDS, "semver_dirty");
+        self.semver_dirty = semver_dirty;
+    }
+
+    /// Fall back to the abbreviated SHA for `VERGEN_GIT_SEMVER*` when no tag is reachable, rather than `CARGO_PKG_VERSION`
+    pub(crate) fn describe_always(&self) -> &bool {
+        &self.describe_always
+    }
+
+    /// Fall back to the abbreviated SHA for `VERGEN_GIT_SEMVER*` when no tag is reachable, rather than `CARGO_PKG_VERSION`
+    pub fn describe_always_mut(&mut self) -> &mut bool {
+        &mut self.describe_always
+    }
+
+    /// Fall back to the abbreviated SHA for `VERGEN_GIT_SEMVER*` when no tag is reachable, rather than `CARGO_PKG_VERSION`
+    pub fn set_describe_always(&mut self, describe_always: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "describe_always");
+        self.describe_always = describe_always;
+    }
+
+    /// Compare the version tag on `HEAD` against `CARGO_PKG_VERSION`
+    pub(crate) fn check_tag_version(&self) -> &bool {
+        &self.check_tag_version
+    }
+
+    /// Compare the version tag on `HEAD` against `CARGO_PKG_VERSION`
+    pub fn check_tag_version_mut(&mut self) -> &mut bool {
+        &mut self.check_tag_version
+    }
+
+    /// Compare the version tag on `HEAD` against `CARGO_PKG_VERSION`
+    pub fn set_check_tag_version(&mut self, check_tag_version: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "check_tag_version");
+        self.check_tag_version = check_tag_version;
+    }
+
+    /// Fail, rather than warn, when the version tag on `HEAD` and `CARGO_PKG_VERSION` disagree.
+    pub(crate) fn check_tag_version_strict(&self) -> &bool {
+        &self.check_tag_version_strict
+    }
+
+    /// Fail, rather than warn, when the version tag on `HEAD` and `CARGO_PKG_VERSION` disagree.
+    pub fn check_tag_version_strict_mut(&mut self) -> &mut bool {
+        &mut self.check_tag_version_strict
+    }
+
+    /// Fail, rather than warn, when the version tag on `HEAD` and `CARGO_PKG_VERSION` disagree.
+    pub fn set_check_tag_version_strict(&mut self, check_tag_version_strict: bool) {
+        self.set_fields
+            .mark(MERGED_FIELDS, "check_tag_version_strict");
+        self.check_tag_version_strict = check_tag_version_strict;
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_SHA` instruction
+    pub(crate) fn sha(&self) -> &bool {
+        &self.sha
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_SHA` instruction
+    pub fn sha_mut(&mut self) -> &mut bool {
+        &mut self.sha
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_SHA` instruction
+    pub fn set_sha(&mut self, sha: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "sha");
+        self.sha = sha;
+    }
+
+    /// The kind of SHA instruction to output.
+    pub(crate) fn sha_kind(&self) -> &ShaKind {
+        &self.sha_kind
+    }
+
+    /// The kind of SHA instruction to output.
+    pub fn sha_kind_mut(&mut self) -> &mut ShaKind {
+        &mut self.sha_kind
+    }
+
+    /// The kind of SHA instruction to output.
+    pub fn set_sha_kind(&mut self, sha_kind: ShaKind) {
+        self.set_fields.mark(MERGED_FIELDS, "sha_kind");
+        self.sha_kind = sha_kind;
+    }
+
+    /// Output the `VERGEN_GIT_SHA` and `VERGEN_GIT_SHA_SHORT` values in upper case
+    pub(crate) fn uppercase_sha(&self) -> &bool {
+        &self.uppercase_sha
+    }
+
+    /// Output the `VERGEN_GIT_SHA` and `VERGEN_GIT_SHA_SHORT` values in upper case
+    pub fn uppercase_sha_mut(&mut self) -> &mut bool {
+        &mut self.uppercase_sha
+    }
+
+    /// Output the `VERGEN_GIT_SHA` and `VERGEN_GIT_SHA_SHORT` values in upper case
+    pub fn set_uppercase_sha(&mut self, uppercase_sha: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "uppercase_sha");
+        self.uppercase_sha = uppercase_sha;
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_TRACKED_FILES` instruction
+    pub(crate) fn tracked_files(&self) -> &bool {
+        &self.tracked_files
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_TRACKED_FILES` instruction
+    pub fn tracked_files_mut(&mut self) -> &mut bool {
+        &mut self.tracked_files
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_TRACKED_FILES` instruction
+    pub fn set_tracked_files(&mut self, tracked_files: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "tracked_files");
+        self.tracked_files = tracked_files;
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_COMMIT_AGE_SECONDS` instruction
+    pub(crate) fn commit_age(&self) -> &bool {
+        &self.commit_age
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_COMMIT_AGE_SECONDS` instruction
+    pub fn commit_age_mut(&mut self) -> &mut bool {
+        &mut self.commit_age
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_COMMIT_AGE_SECONDS` instruction
+    pub fn set_commit_age(&mut self, commit_age: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "commit_age");
+        self.commit_age = commit_age;
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_COMMITS_SINCE_TAG` instruction
+    pub(crate) fn commits_since_tag(&self) -> &bool {
+        &self.commits_since_tag
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_COMMITS_SINCE_TAG` instruction
+    pub fn commits_since_tag_mut(&mut self) -> &mut bool {
+        &mut self.commits_since_tag
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_COMMITS_SINCE_TAG` instruction
+    pub fn set_commits_since_tag(&mut self, commits_since_tag: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "commits_since_tag");
+        self.commits_since_tag = commits_since_tag;
+    }
+
+    /// The maximum length of the `VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS` instruction, or None to disable it
+    pub(crate) fn commits_since_tag_subjects(&self) -> &Option<usize> {
+        &self.commits_since_tag_subjects
+    }
+
+    /// The maximum length of the `VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS` instruction, or None to disable it
+    pub fn commits_since_tag_subjects_mut(&mut self) -> &mut Option<usize> {
+        &mut self.commits_since_tag_subjects
+    }
+
+    /// The maximum length of the `VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS` instruction, or None to disable it
+    pub fn set_commits_since_tag_subjects(&mut self, commits_since_tag_subjects: Option<usize>) {
+        self.set_fields
+            .mark(MERGED_FIELDS, "commits_since_tag_subjects");
+        self.commits_since_tag_subjects = commits_since_tag_subjects;
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_SIGNED` and `VERGEN_GIT_SIGNER` instructions
+    pub(crate) fn signed(&self) -> &bool {
+        &self.signed
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_SIGNED` and `VERGEN_GIT_SIGNER` instructions
+    pub fn signed_mut(&mut self) -> &mut bool {
+        &mut self.signed
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_SIGNED` and `VERGEN_GIT_SIGNER` instructions
+    pub fn set_signed(&mut self, signed: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "signed");
+        self.signed = signed;
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_BRANCH_COUNT` instruction
+    pub(crate) fn branch_count(&self) -> &bool {
+        &self.branch_count
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_BRANCH_COUNT` instruction
+    pub fn branch_count_mut(&mut self) -> &mut bool {
+        &mut self.branch_count
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_BRANCH_COUNT` instruction
+    pub fn set_branch_count(&mut self, branch_count: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "branch_count");
+        self.branch_count = branch_count;
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_TAG_COUNT` instruction
+    pub(crate) fn tag_count(&self) -> &bool {
+        &self.tag_count
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_TAG_COUNT` instruction
+    pub fn tag_count_mut(&mut self) -> &mut bool {
+        &mut self.tag_count
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_TAG_COUNT` instruction
+    pub fn set_tag_count(&mut self, tag_count: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "tag_count");
+        self.tag_count = tag_count;
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_ROOT` instruction
+    pub(crate) fn repo_root(&self) -> &bool {
+        &self.repo_root
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_ROOT` instruction
+    pub fn repo_root_mut(&mut self) -> &mut bool {
+        &mut self.repo_root
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_ROOT` instruction
+    pub fn set_repo_root(&mut self, repo_root: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "repo_root");
+        self.repo_root = repo_root;
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_WORKTREE` instruction
+    pub(crate) fn worktree(&self) -> &bool {
+        &self.worktree
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_WORKTREE` instruction
+    pub fn worktree_mut(&mut self) -> &mut bool {
+        &mut self.worktree
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_WORKTREE` instruction
+    pub fn set_worktree(&mut self, worktree: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "worktree");
+        self.worktree = worktree;
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_TREE_HASH` instruction
+    pub(crate) fn tree_hash(&self) -> &bool {
+        &self.tree_hash
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_TREE_HASH` instruction
+    pub fn tree_hash_mut(&mut self) -> &mut bool {
+        &mut self.tree_hash
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_TREE_HASH` instruction
+    pub fn set_tree_hash(&mut self, tree_hash: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "tree_hash");
+        self.tree_hash = tree_hash;
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_CONTRIBUTOR_COUNT` instruction
+    pub(crate) fn contributor_count(&self) -> &bool {
+        &self.contributor_count
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_CONTRIBUTOR_COUNT` instruction
+    pub fn contributor_count_mut(&mut self) -> &mut bool {
+        &mut self.contributor_count
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_CONTRIBUTOR_COUNT` instruction
+    pub fn set_contributor_count(&mut self, contributor_count: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "contributor_count");
+        self.contributor_count = contributor_count;
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_MERGE_BASE` instruction
+    pub(crate) fn merge_base(&self) -> &bool {
+        &self.merge_base
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_MERGE_BASE` instruction
+    pub fn merge_base_mut(&mut self) -> &mut bool {
+        &mut self.merge_base
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_MERGE_BASE` instruction
+    pub fn set_merge_base(&mut self, merge_base: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "merge_base");
+        self.merge_base = merge_base;
+    }
+
+    /// The ref `HEAD` is compared against for the `VERGEN_GIT_MERGE_BASE` instruction, i.e. `origin/main`
+    pub(crate) fn merge_base_ref(&self) -> &Option<String> {
+        &self.merge_base_ref
+    }
+
+    /// The ref `HEAD` is compared against for the `VERGEN_GIT_MERGE_BASE` instruction, i.e. `origin/main`
+    pub fn merge_base_ref_mut(&mut self) -> &mut Option<String> {
+        &mut self.merge_base_ref
+    }
+
+    /// The ref `HEAD` is compared against for the `VERGEN_GIT_MERGE_BASE` instruction, i.e. `origin/main`
+    pub fn set_merge_base_ref(&mut self, merge_base_ref: Option<String>) {
+        self.set_fields.mark(MERGED_FIELDS, "merge_base_ref");
+        self.merge_base_ref = merge_base_ref;
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_OBJECT_FORMAT` instruction
+    pub(crate) fn object_format(&self) -> &bool {
+        &self.object_format
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_OBJECT_FORMAT` instruction
+    pub fn object_format_mut(&mut self) -> &mut bool {
+        &mut self.object_format
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_OBJECT_FORMAT` instruction
+    pub fn set_object_format(&mut self, object_format: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "object_format");
+        self.object_format = object_format;
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_PARENT_COUNT` instruction
+    pub(crate) fn parent_count(&self) -> &bool {
+        &self.parent_count
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_PARENT_COUNT` instruction
+    pub fn parent_count_mut(&mut self) -> &mut bool {
+        &mut self.parent_count
+    }
+
+    /// Enable/Disable the `VERGEN_GIT_PARENT_COUNT` instruction
+    pub fn set_parent_count(&mut self, parent_count: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "parent_count");
+        self.parent_count = parent_count;
+    }
+}
+
+/// An alias so `serde` doesn't treat the field as borrowed from the input, which would require `'de: 'static`
+#[cfg(feature = "git")]
+type SemverDirty = Option<&'static str>;
+
+/// The `semver_dirty` field is a `&'static str`, so a deserialized suffix is leaked.
+#[cfg(all(feature = "git", feature = "serde"))]
+fn deserialize_semver_dirty<'de, D>(deserializer: D) -> std::result::Result<SemverDirty, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let dirty: Option<String> = serde::Deserialize::deserialize(deserializer)?;
+    Ok(dirty.map(|dirty| &*Box::leak(dirty.into_boxed_str())))
+}
+
+#[cfg(feature = "git")]
+impl Default for Git {
+    fn default() -> Self {
+        let base_dir = if let Ok(dir) = env::current_dir() {
+            Some(dir)
+        } else {
+            None
+        };
+        Self {
+            enabled: true,
+            base_dir,
+            path_scope: None,
+            ref_name: None,
+            branch: true,
+            commit_timestamp: true,
+            commit_timestamp_timezone: feature::TimeZone::Utc,
+            commit_timestamp_kind: TimestampKind::Timestamp,
+            rerun_on_head_change: true,
+            semver: true,
+            semver_kind: SemverKind::Normal,
+            semver_dirty: None,
+            describe_always: true,
+            check_tag_version: false,
+            check_tag_version_strict: false,
+            sha: true,
+            sha_kind: ShaKind::Normal,
+            uppercase_sha: false,
+            tracked_files: false,
+            commit_age: false,
+            commits_since_tag: false,
+            commits_since_tag_subjects: None,
+            signed: false,
+            branch_count: false,
+            tag_count: false,
+            repo_root: false,
+            worktree: false,
+            tree_hash: false,
+            contributor_count: false,
+            merge_base: false,
+            merge_base_ref: Some("origin/main".to_string()),
+            object_format: false,
+            parent_count: false,
+            set_fields: SetFields::default(),
+        }
+    }
+}
+
+/// The [`Git`] fields that win in a [merge](crate::Config::merge) once set, see [`SetFields`]
+#[cfg(feature = "git")]
+const MERGED_FIELDS: &[&str] = &[
+    "enabled",
+    "base_dir",
+    "path_scope",
+    "ref_name",
+    "branch",
+    "commit_timestamp",
+    "commit_timestamp_timezone",
+    "commit_timestamp_kind",
+    "rerun_on_head_change",
+    "semver",
+    "semver_kind",
+    "semver_dirty",
+    "describe_always",
+    "check_tag_version",
+    "check_tag_version_strict",
+    "sha",
+    "sha_kind",
+    "uppercase_sha",
+    "tracked_files",
+    "commit_age",
+    "commits_since_tag",
+    "commits_since_tag_subjects",
+    "signed",
+    "branch_count",
+    "tag_count",
+    "repo_root",
+    "worktree",
+    "tree_hash",
+    "contributor_count",
+    "merge_base",
+    "merge_base_ref",
+    "object_format",
+    "parent_count",
+];
+
+#[cfg(feature = "git")]
+impl Git {
+    #[allow(clippy::too_many_lines)]
+    pub(crate) fn merge(self, overlay: Self) -> Self {
+        let default = Self::default();
+        let overlay_set = overlay.set_fields;
+        let set = |name| overlay_set.contains(MERGED_FIELDS, name);
+        Self {
+            enabled: pick(
+                self.enabled,
+                overlay.enabled,
+                &default.enabled,
+                set("enabled"),
+            ),
+            base_dir: pick(
+                self.base_dir,
+                overlay.base_dir,
+                &default.base_dir,
+                set("base_dir"),
+            ),
+            path_scope: pick(
+                self.path_scope,
+                overlay.path_scope,
+                &default.path_scope,
+                set("path_scope"),
+            ),
+            ref_name: pick(
+                self.ref_name,
+                overlay.ref_name,
+                &default.ref_name,
+                set("ref_name"),
+            ),
+            branch: pick(self.branch, overlay.branch, &default.branch, set("branch")),
+            commit_timestamp: pick(
+                self.commit_timestamp,
+                overlay.commit_timestamp,
+                &default.commit_timestamp,
+                set("commit_timestamp"),
+            ),
+            commit_timestamp_timezone: pick(
+                self.commit_timestamp_timezone,
+                overlay.commit_timestamp_timezone,
+                &default.commit_timestamp_timezone,
+                set("commit_timestamp_timezone"),
+            ),
+            commit_timestamp_kind: pick(
+                self.commit_timestamp_kind,
+                overlay.commit_timestamp_kind,
+                &default.commit_timestamp_kind,
+                set("commit_timestamp_kind"),
+            ),
+            rerun_on_head_change: pick(
+                self.rerun_on_head_change,
+                overlay.rerun_on_head_change,
+                &default.rerun_on_head_change,
+                set("rerun_on_head_change"),
+            ),
+            semver: pick(self.semver, overlay.semver, &default.semver, set("semver")),
+            semver_kind: pick(
+                self.semver_kind,
+                overlay.semver_kind,
+                &default.semver_kind,
+                set("semver_kind"),
+            ),
+            semver_dirty: pick(
+                self.semver_dirty,
+                overlay.semver_dirty,
+                &default.semver_dirty,
+                set("semver_dirty"),
+            ),
+            describe_always: pick(
+                self.describe_always,
+                overlay.describe_always,
+                &default.describe_always,
+                set("describe_always"),
+            ),
+            check_tag_version: pick(
+                self.check_tag_version,
+                overlay.check_tag_version,
+                &default.check_tag_version,
+                set("check_tag_version"),
+            ),
+            check_tag_version_strict: pick(
+                self.check_tag_version_strict,
+                overlay.check_tag_version_strict,
+                &default.check_tag_version_strict,
+                set("check_tag_version_strict"),
+            ),
+            sha: pick(self.sha, overlay.sha, &default.sha, set("sha")),
+            sha_kind: pick(
+                self.sha_kind,
+                overlay.sha_kind,
+                &default.sha_kind,
+                set("sha_kind"),
+            ),
+            uppercase_sha: pick(
+                self.uppercase_sha,
+                overlay.uppercase_sha,
+                &default.uppercase_sha,
+                set("uppercase_sha"),
+            ),
+            tracked_files: pick(
+                self.tracked_files,
+                overlay.tracked_files,
+                &default.tracked_files,
+                set("tracked_files"),
+            ),
+            commit_age: pick(
+                self.commit_age,
+                overlay.commit_age,
+                &default.commit_age,
+                set("commit_age"),
+            ),
+            commits_since_tag: pick(
+                self.commits_since_tag,
+                overlay.commits_since_tag,
+                &default.commits_since_tag,
+                set("commits_since_tag"),
+            ),
+            commits_since_tag_subjects: pick(
+                self.commits_since_tag_subjects,
+                overlay.commits_since_tag_subjects,
+                &default.commits_since_tag_subjects,
+                set("commits_since_tag_subjects"),
+            ),
+            signed: pick(self.signed, overlay.signed, &default.signed, set("signed")),
+            branch_count: pick(
+                self.branch_count,
+                overlay.branch_count,
+                &default.branch_count,
+                set("branch_count"),
+            ),
+            tag_count: pick(
+                self.tag_count,
+                overlay.tag_count,
+                &default.tag_count,
+                set("tag_count"),
+            ),
+            repo_root: pick(
+                self.repo_root,
+                overlay.repo_root,
+                &default.repo_root,
+                set("repo_root"),
+            ),
+            worktree: pick(
+                self.worktree,
+                overlay.worktree,
+                &default.worktree,
+                set("worktree"),
+            ),
+            tree_hash: pick(
+                self.tree_hash,
+                overlay.tree_hash,
+                &default.tree_hash,
+                set("tree_hash"),
+            ),
+            contributor_count: pick(
+                self.contributor_count,
+                overlay.contributor_count,
+                &default.contributor_count,
+                set("contributor_count"),
+            ),
+            merge_base: pick(
+                self.merge_base,
+                overlay.merge_base,
+                &default.merge_base,
+                set("merge_base"),
+            ),
+            merge_base_ref: pick(
+                self.merge_base_ref,
+                overlay.merge_base_ref,
+                &default.merge_base_ref,
+                set("merge_base_ref"),
+            ),
+            object_format: pick(
+                self.object_format,
+                overlay.object_format,
+                &default.object_format,
+                set("object_format"),
+            ),
+            parent_count: pick(
+                self.parent_count,
+                overlay.parent_count,
+                &default.parent_count,
+                set("parent_count"),
+            ),
+            set_fields: self.set_fields.union(overlay_set),
+        }
+    }
+
+    pub(crate) fn has_enabled(&self) -> bool {
+        self.enabled
+            && (self.branch
+                || self.commit_timestamp
+                || self.rerun_on_head_change
+                || self.semver
+                || self.sha
+                || self.tracked_files
+                || self.commit_age
+                || self.commits_since_tag
+                || self.signed
+                || self.branch_count
+                || self.tag_count
+                || self.repo_root
+                || self.worktree
+                || self.tree_hash
+                || self.contributor_count
+                || self.merge_base
+                || self.object_format
+                || self.parent_count)
+    }
+
+    /// Whether an instruction other than the repository root, worktree, tree hash, and merge base, which are empty outside a repository, is enabled
+    fn needs_repository(&self) -> bool {
+        self.branch
+            || self.commit_timestamp
+            || self.semver
+            || self.sha
+            || self.tracked_files
+            || self.commit_age
+            || self.commits_since_tag
+            || self.signed
+            || self.branch_count
+            || self.tag_count
+            || self.contributor_count
+            || self.object_format
+            || self.parent_count
+            || self.check_tag_version
+    }
+
+    #[allow(clippy::too_many_lines)]
+    pub(crate) fn validate(&self, errors: &mut Vec<ConfigError>) {
+        if !self.enabled {
+            return;
+        }
+
+        if let Some(base_dir) = &self.base_dir {
+            if !base_dir.exists() {
+                errors.push(ConfigError::new(
+                    "git.base_dir",
+                    format!("{}", base_dir.display()),
+                    "point 'git.base_dir' at an existing directory inside the repository",
+                ));
+            }
+        }
+
+        if let Some(path_scope) = &self.path_scope {
+            if path_scope.as_os_str().is_empty() {
+                errors.push(ConfigError::new(
+                    "git.path_scope",
+                    "Some(\"\")",
+                    "use a path relative to the repository root, or None for the whole repository",
+                ));
+            }
+        }
+
+        if self.ref_name.as_deref() == Some("") {
+            errors.push(ConfigError::new(
+                "git.ref_name",
+                "Some(\"\")",
+                "use a ref like 'origin/release', or None to describe HEAD",
+            ));
+        }
+
+        if !self.commit_timestamp {
+            if self.commit_timestamp_kind != TimestampKind::Timestamp {
+                errors.push(ConfigError::new(
+                    "git.commit_timestamp_kind",
+                    format!("{:?}", self.commit_timestamp_kind),
+                    "set 'git.commit_timestamp' to true, or leave 'git.commit_timestamp_kind' at the default",
+                ));
+            }
+            if self.commit_timestamp_timezone != feature::TimeZone::Utc {
+                errors.push(ConfigError::new(
+                    "git.commit_timestamp_timezone",
+                    format!("{:?}", self.commit_timestamp_timezone),
+                    "set 'git.commit_timestamp' to true, or leave 'git.commit_timestamp_timezone' at the default",
+                ));
+            }
+        }
+
+        if self.semver {
+            if self.semver_dirty == Some("") {
+                errors.push(ConfigError::new(
+                    "git.semver_dirty",
+                    "Some(\"\")",
+                    "use a non-empty suffix like '-dirty', or None to disable the suffix",
+                ));
+            }
+        } else {
+            if self.semver_kind != SemverKind::Normal {
+                errors.push(ConfigError::new(
+                    "git.semver_kind",
+                    format!("{:?}", self.semver_kind),
+                    "set 'git.semver' to true, or leave 'git.semver_kind' at the default",
+                ));
+            }
+            if let Some(dirty) = self.semver_dirty {
+                errors.push(ConfigError::new(
+                    "git.semver_dirty",
+                    format!("Some({:?})", dirty),
+                    "set 'git.semver' to true, or set 'git.semver_dirty' to None",
+                ));
+            }
+            if !self.describe_always {
+                errors.push(ConfigError::new(
+                    "git.describe_always",
+                    "false",
+                    "set 'git.semver' to true, or leave 'git.describe_always' at the default",
+                ));
+            }
+        }
+
+        if let Some(max) = self.commits_since_tag_subjects {
+            if !self.commits_since_tag {
+                errors.push(ConfigError::new(
+                    "git.commits_since_tag_subjects",
+                    format!("Some({})", max),
+                    "set 'git.commits_since_tag' to true, or set 'git.commits_since_tag_subjects' to None",
+                ));
+            } else if max == 0 {
+                errors.push(ConfigError::new(
+                    "git.commits_since_tag_subjects",
+                    "Some(0)",
+                    "use a maximum length greater than zero, or None to disable the subjects",
+                ));
+            }
+        }
+
+        if self.check_tag_version_strict && !self.check_tag_version {
+            errors.push(ConfigError::new(
+                "git.check_tag_version_strict",
+                "true",
+                "set 'git.check_tag_version' to true, or set 'git.check_tag_version_strict' to false",
+            ));
+        }
+
+        if !self.sha && self.sha_kind != ShaKind::Normal {
+            errors.push(ConfigError::new(
+                "git.sha_kind",
+                format!("{:?}", self.sha_kind),
+                "set 'git.sha' to true, or leave 'git.sha_kind' at the default",
+            ));
+        }
+    }
+}
+
+#[cfg(not(feature = "git"))]
+pub(crate) fn configure_git<T>(
+    _instructions: &Instructions,
+    _repo: Option<T>,
+    _deadline: Option<Instant>,
+    _config: &mut Config,
+) -> Result<()>
+where
+    T: AsRef<Path>,
+{
+    Ok(())
+}
+
+#[cfg(feature = "git")]
+#[cfg_attr(target_os = "wasi", allow(unused_variables))]
+pub(crate) fn configure_git<T>(
+    instructions: &Instructions,
+    repo_path_opt: Option<T>,
+    deadline: Option<Instant>,
+    config: &mut Config,
+) -> Result<()>
+where
+    T: AsRef<Path>,
+{
+    let from_repo = (*instructions.build_number() || *instructions.release_url())
+        && *instructions.git().enabled();
+    if *instructions.vcs() != Vcs::Git || !(instructions.git().has_enabled() || from_repo) {
+        return Ok(());
+    }
+
+    #[cfg(any(test, feature = "test-util"))]
+    {
+        if let Some(provider) = instructions.providers().git() {
+            return gather_git(instructions, &**provider, deadline, config);
+        }
+    }
+
+    if let Some(repo_path) = repo_path_opt {
+        // wasi can neither link libgit2 nor run git
+        if cfg!(target_os = "wasi") {
+            skip_all(
+                config,
+                &git_keys(instructions.git()),
+                "the git repository can't be read on wasi",
+            );
+            return Ok(());
+        }
+
+        #[cfg(not(target_os = "wasi"))]
+        {
+            let opening = Instant::now();
+            let repo = match discover(instructions, repo_path.as_ref()) {
+                Ok(repo) => repo,
+                Err(e)
+                    if e.code() == ErrorCode::NotFound
+                        && !instructions.git().needs_repository() =>
+                {
+                    // Outside a repository, the root is empty and there's no HEAD to rerun on
+                    if *instructions.git().repo_root() {
+                        add_entry(config, VergenKey::GitRoot, Some(String::new()));
+                    }
+                    if *instructions.git().worktree() {
+                        add_entry(config, VergenKey::GitWorktree, Some(String::new()));
+                    }
+                    if *instructions.git().tree_hash() {
+                        add_entry(config, VergenKey::TreeHash, Some(String::new()));
+                    }
+                    if *instructions.git().merge_base() {
+                        add_entry(config, VergenKey::MergeBase, Some(String::new()));
+                    }
+                    return Ok(());
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if *instructions.verbose() {
+                eprintln!(
+                    "vergen: opened the git repository at {} in {:?}",
+                    repo.path().display(),
+                    opening.elapsed()
+                );
+            }
+            *config.repo_path_mut() = Some(repo.path().to_path_buf());
+            let provider = Git2Repo::open(&repo, instructions.git(), deadline)?;
+            gather_git(instructions, &provider, deadline, config)?;
+            if !*instructions.git().rerun_on_head_change() {
+                return Ok(());
+            }
+
+            let policy = *instructions.non_utf8_paths();
+            let repo_path = repo.path().to_path_buf();
+            // A SHA has no ref to watch, and a ref rather than HEAD doesn't change on checkout
+            let resolved = match instructions.git().ref_name() {
+                Some(git_ref) => repo.resolve_reference_from_short_name(git_ref),
+                None => repo.find_reference("HEAD"),
+            };
+            if let Ok(resolved) = resolved.and_then(|reference| reference.resolve()) {
+                if let Some(name) = resolved.name() {
+                    let path = paths::normalize(&common_dir(&repo_path).join(name));
+                    // Check whether the path exists in the filesystem before emitting it
+                    if path.exists() {
+                        *config.ref_path_mut() = paths::emitted_path(policy, path, config);
+                    }
+                }
+            }
+            if instructions.git().ref_name().is_none() {
+                let head_path = paths::normalize(&repo_path.join("HEAD"));
+                *config.head_path_mut() = paths::emitted_path(policy, head_path, config);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The commit date/time/age and SHA values for the `path_scope` of a [`Package`](crate::Package)
+#[cfg(feature = "git")]
+#[cfg_attr(target_os = "wasi", allow(unused_variables))]
+pub(crate) fn configure_package_git(
+    instructions: &Instructions,
+    path_scope: &Path,
+    repo_path: Option<&Path>,
+    config: &mut Config,
+) -> Result<()> {
+    if *instructions.vcs() != Vcs::Git || !*instructions.git().enabled() {
+        return Ok(());
+    }
+    let mut scoped = instructions.clone();
+    *scoped.git_mut().path_scope_mut() = Some(path_scope.to_path_buf());
+
+    #[cfg(any(test, feature = "test-util"))]
+    {
+        if let Some(provider) = instructions.providers().git() {
+            return add_commit_entries(&scoped, &**provider, config);
+        }
+    }
+
+    // On wasi, the shared values are skipped with a warning, so these are empty too
+    #[cfg(not(target_os = "wasi"))]
+    {
+        if let Some(repo_path) = repo_path {
+            let repo = match discover(instructions, repo_path) {
+                Ok(repo) => repo,
+                // Outside a repository, the shared values are empty too
+                Err(e) if e.code() == ErrorCode::NotFound => return Ok(()),
+                Err(e) => return Err(e.into()),
+            };
+            add_commit_entries(&scoped, &Git2Repo::open(&repo, scoped.git(), None)?, config)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "git"))]
+pub(crate) fn configure_package_git(
+    _instructions: &Instructions,
+    _path_scope: &Path,
+    _repo_path: Option<&Path>,
+    _config: &mut Config,
+) -> Result<()> {
+    Ok(())
+}
+
+/// The `HEAD` committer time, in the committer's timezone, for the `timestamp_from_git` and `timezone_from_git`
+/// [`Build`](crate::Build) fields, or None if there's no repository or it can't be read
+#[cfg(all(feature = "build", feature = "git"))]
+#[cfg_attr(
+    any(not(any(test, feature = "test-util")), target_os = "wasi"),
+    allow(unused_variables)
+)]
+pub(crate) fn head_commit_time(
+    instructions: &Instructions,
+    repo_path: Option<&Path>,
+    deadline: Option<Instant>,
+) -> Option<OffsetDateTime> {
+    #[cfg(any(test, feature = "test-util"))]
+    {
+        if let Some(provider) = instructions.providers().git() {
+            return committer_time(provider.as_ref());
+        }
+    }
+
+    #[cfg(not(target_os = "wasi"))]
+    {
+        let repo = discover(instructions, repo_path?).ok()?;
+        let commit_time =
+            committer_time(&Git2Repo::open(&repo, instructions.git(), deadline).ok()?);
+        commit_time
+    }
+    #[cfg(target_os = "wasi")]
+    {
+        None
+    }
+}
+
+#[cfg(all(feature = "build", not(feature = "git")))]
+pub(crate) fn head_commit_time(
+    _instructions: &Instructions,
+    _repo_path: Option<&Path>,
+    _deadline: Option<Instant>,
+) -> Option<time::OffsetDateTime> {
+    None
+}
+
+/// The `HEAD` committer time, in the committer's timezone
+#[cfg(all(feature = "build", feature = "git"))]
+fn committer_time(provider: &dyn GitProvider) -> Option<OffsetDateTime> {
+    let offset = provider.commit_offset().ok()?;
+    Some(provider.commit_time().ok()?.to_offset(offset))
+}
+
+/// The git directory that holds the branches, which is the main repository's for a linked worktree
+///
+/// A linked worktree only has its own `HEAD`, and names the main repository's git directory, relative to its own, in
+/// its `commondir` file.
+#[cfg(all(feature = "git", not(target_os = "wasi")))]
+fn common_dir(repo_path: &Path) -> PathBuf {
+    match fs::read_to_string(repo_path.join("commondir")) {
+        Ok(common) => {
+            let common = repo_path.join(common.trim());
+            common.canonicalize().unwrap_or(common)
+        }
+        Err(_) => repo_path.to_path_buf(),
+    }
+}
+
+/// Gather the git values, stopping between the queries once the deadline has passed
+#[cfg(feature = "git")]
+#[allow(clippy::too_many_lines)]
+fn gather_git<P>(
+    instructions: &Instructions,
+    provider: &P,
+    deadline: Option<Instant>,
+    config: &mut Config,
+) -> Result<()>
+where
+    P: GitProvider + ?Sized,
+{
+    let git_config = instructions.git();
+
+    if *git_config.branch() {
+        add_entry_or_skip(
+            config,
+            VergenKey::Branch,
+            provider.branch()?,
+            if git_config.ref_name().is_some() {
+                "the ref isn't a local or remote-tracking branch"
+            } else {
+                "HEAD isn't on a local branch"
+            },
+        );
+    }
+
+    check_deadline(deadline)?;
+    add_commit_entries(instructions, provider, config)?;
+
+    check_deadline(deadline)?;
+    if *git_config.semver() {
+        let (key, lightweight) = match *git_config.semver_kind() {
+            SemverKind::Normal => (VergenKey::Semver, false),
+            SemverKind::Lightweight => (VergenKey::SemverLightweight, true),
+        };
+        add_entry_or_skip(
+            config,
+            key,
+            semver(git_config, lightweight, provider),
+            "no tag is reachable from HEAD, and CARGO_PKG_VERSION isn't set",
+        );
+    }
+
+    check_deadline(deadline)?;
+    if *git_config.check_tag_version() {
+        check_tag_version(git_config, provider, config)?;
+    }
+
+    check_deadline(deadline)?;
+    if *git_config.tracked_files() {
+        add_entry(
+            config,
+            VergenKey::TrackedFiles,
+            Some(provider.tracked_files().to_string()),
+        );
+    }
+
+    check_deadline(deadline)?;
+    if *git_config.commits_since_tag() {
+        add_commits_since_tag(provider, *git_config.commits_since_tag_subjects(), config)?;
+    } else if git_config.commits_since_tag_subjects().is_some() {
+        config.skip(
+            VergenKey::CommitsSinceTagSubjects,
+            "git.commits_since_tag is false",
+        );
+    }
+
+    check_deadline(deadline)?;
+    if *git_config.signed() {
+        let signer = provider.signer()?;
+        add_entry(
+            config,
+            VergenKey::Signed,
+            Some(signer.is_some().to_string()),
+        );
+        add_entry(config, VergenKey::Signer, Some(signer.unwrap_or_default()));
+    }
+
+    check_deadline(deadline)?;
+    if *git_config.branch_count() {
+        add_entry(
+            config,
+            VergenKey::BranchCount,
+            Some(provider.branch_count()?.to_string()),
+        );
+    }
+
+    check_deadline(deadline)?;
+    if *git_config.tag_count() {
+        add_entry(
+            config,
+            VergenKey::TagCount,
+            Some(provider.tag_count()?.to_string()),
+        );
+    }
+
+    check_deadline(deadline)?;
+    if *git_config.repo_root() {
+        let root = provider
+            .repo_root()
+            .and_then(|root| paths::emitted_path(*instructions.non_utf8_paths(), root, config))
+            .map(|root| root.display().to_string())
+            .unwrap_or_default();
+        add_entry(config, VergenKey::GitRoot, Some(root));
+    }
+
+    check_deadline(deadline)?;
+    if *git_config.worktree() {
+        let worktree = provider
+            .worktree()
+            .and_then(|worktree| {
+                paths::emitted_path(*instructions.non_utf8_paths(), worktree, config)
+            })
+            .map(|worktree| worktree.display().to_string())
+            .unwrap_or_default();
+        add_entry(config, VergenKey::GitWorktree, Some(worktree));
+    }
+
+    check_deadline(deadline)?;
+    if *git_config.tree_hash() {
+        add_entry(config, VergenKey::TreeHash, Some(provider.tree_hash()?));
+    }
+
+    check_deadline(deadline)?;
+    // Walking the whole history is slow, so skip it when the value would be dropped anyway
+    check_deadline(deadline)?;
+    if *git_config.contributor_count() && !instructions.excluded(VergenKey::ContributorCount) {
+        let (count, partial) = provider.contributor_count()?;
+        if partial {
+            config.warnings_mut().push(format!(
+                "vergen: the repository is a shallow clone, VERGEN_GIT_CONTRIBUTOR_COUNT only counts the {} author(s) in the fetched history",
+                count
+            ));
+        }
+        add_entry(config, VergenKey::ContributorCount, Some(count.to_string()));
+    }
+
+    check_deadline(deadline)?;
+    if *git_config.merge_base() {
+        let merge_base = match git_config.merge_base_ref() {
+            Some(base) => provider.merge_base(base)?,
+            None => None,
+        };
+        add_entry(
+            config,
+            VergenKey::MergeBase,
+            Some(merge_base.unwrap_or_default()),
+        );
+    }
+
+    check_deadline(deadline)?;
+    if *git_config.object_format() {
+        add_entry(
+            config,
+            VergenKey::ObjectFormat,
+            Some(provider.object_format()?),
+        );
+    }
+
+    check_deadline(deadline)?;
+    if *git_config.parent_count() {
+        add_entry(
+            config,
+            VergenKey::ParentCount,
+            Some(provider.parent_count()?.to_string()),
+        );
+    }
+
+    check_deadline(deadline)?;
+    if *instructions.build_number() {
+        let count = provider.commit_count()?;
+        let number = match provider.dirty_files()? {
+            0 => count.to_string(),
+            dirty => format!("{}+{}", count, dirty),
+        };
+        add_entry(config, VergenKey::BuildNumber, Some(number));
+    }
+
+    check_deadline(deadline)?;
+    if *instructions.release_url() {
+        let url = release_url(
+            instructions.url_template().as_deref(),
+            env::var("CARGO_PKG_REPOSITORY").ok().as_deref(),
+            provider.exact_tag()?.as_deref(),
+        );
+        add_entry(config, VergenKey::ReleaseUrl, Some(url));
+    }
+    Ok(())
+}
+
+/// The `git describe` output, or `CARGO_PKG_VERSION` if `HEAD` can't be described
+#[cfg(feature = "git")]
+fn semver<P>(git_config: &Git, lightweight: bool, provider: &P) -> Option<String>
+where
+    P: GitProvider + ?Sized,
+{
+    provider
+        .semver(
+            lightweight,
+            *git_config.describe_always(),
+            git_config.semver_dirty(),
+        )
+        .ok()
+        .or_else(|| pkg_version_semver(git_config, provider))
+}
+
+/// `CARGO_PKG_VERSION`, for a repository without tags, with the `semver_dirty` suffix if tracked files have changes
+#[cfg(feature = "git")]
+fn pkg_version_semver<P>(git_config: &Git, provider: &P) -> Option<String>
+where
+    P: GitProvider + ?Sized,
+{
+    let version = env::var("CARGO_PKG_VERSION").ok()?;
+    match git_config.semver_dirty() {
+        Some(suffix) if provider.dirty_files().unwrap_or_default() > 0 => {
+            Some(format!("{}{}", version, suffix))
+        }
+        _ => Some(version),
+    }
+}
+
+/// The version of a tag or `CARGO_PKG_VERSION`, without the `v` prefix and build metadata, or None if it isn't a version
+#[cfg(feature = "git")]
+fn tag_version(tag: &str) -> Option<&str> {
+    let version = tag.strip_prefix('v').unwrap_or(tag);
+    let version = version.split('+').next().unwrap_or_default();
+    if version.starts_with(|c: char| c.is_ascii_digit()) {
+        Some(version)
+    } else {
+        None
+    }
+}
+
+/// Compare the version tag on `HEAD`, if any, against `CARGO_PKG_VERSION`
+#[cfg(feature = "git")]
+fn check_tag_version<P>(git_config: &Git, provider: &P, config: &mut Config) -> Result<()>
+where
+    P: GitProvider + ?Sized,
+{
+    if let (Some(tag), Ok(version)) = (provider.exact_tag()?, env::var("CARGO_PKG_VERSION")) {
+        if let Some(tagged) = tag_version(&tag) {
+            if Some(tagged) != tag_version(&version) {
+                if *git_config.check_tag_version_strict() {
+                    return Err(Error::TagVersion { tag, version }.into());
+                }
+                config.warnings_mut().push(format!(
+                    "vergen: the '{}' tag on HEAD doesn't match the CARGO_PKG_VERSION '{}'",
+                    tag, version
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The commit date/time/age and SHA instructions, for `HEAD` or the last commit that touched `path_scope`
+#[cfg(feature = "git")]
+fn add_commit_entries<P>(
+    instructions: &Instructions,
+    provider: &P,
+    config: &mut Config,
+) -> Result<()>
+where
+    P: GitProvider + ?Sized,
+{
+    let git_config = instructions.git();
+    let scoped = if let Some(path_scope) = git_config.path_scope() {
+        if let Some(commit) = provider.path_commit(path_scope)? {
+            Some(commit)
+        } else {
+            add_no_history_entries(git_config, config);
+            return Ok(());
+        }
+    } else {
+        None
+    };
+
+    if *git_config.commit_timestamp() || *git_config.commit_age() {
+        let timestamp = match &scoped {
+            Some(commit) => commit.commit_time,
+            None => provider.commit_time()?,
+        };
+
+        if *git_config.commit_timestamp() {
+            match git_config.commit_timestamp_timezone() {
+                crate::TimeZone::Utc => {
+                    add_config_entries(config, git_config, &timestamp);
+                }
+                crate::TimeZone::Local => {
+                    let offset = local_offset(config);
+                    add_config_entries(config, git_config, &timestamp.to_offset(offset));
+                }
+            }
+        }
+
+        if *git_config.commit_age() {
+            add_commit_age(instructions, timestamp.unix_timestamp(), config);
+        }
+    }
+
+    if *git_config.sha() {
+        let case = |sha: String| {
+            if *git_config.uppercase_sha() {
+                sha.to_ascii_uppercase()
+            } else {
+                sha
+            }
+        };
+        if *git_config.sha_kind() != ShaKind::Short {
+            let sha = match &scoped {
+                Some(commit) => commit.sha.clone(),
+                None => provider.sha()?,
+            };
+            add_entry(config, VergenKey::Sha, Some(case(sha)));
+        }
+        if *git_config.sha_kind() != ShaKind::Normal {
+            let short_sha = match &scoped {
+                Some(commit) => commit.short_sha.clone(),
+                None => provider.short_sha()?,
+            };
+            add_entry(config, VergenKey::ShortSha, Some(case(short_sha)));
+        }
+    }
+    Ok(())
+}
+
+/// Empty commit date/time/age and SHA values, when the `path_scope` path has no history
+#[cfg(feature = "git")]
+fn add_no_history_entries(git_config: &Git, config: &mut Config) {
+    let timestamp = *git_config.commit_timestamp();
+    let kind = *git_config.commit_timestamp_kind();
+    let sha = *git_config.sha();
+    let sha_kind = *git_config.sha_kind();
+    let keys = [
+        (timestamp && kind.has_date(), VergenKey::CommitDate),
+        (timestamp && kind.has_time(), VergenKey::CommitTime),
+        (
+            timestamp && kind.has_timestamp(),
+            VergenKey::CommitTimestamp,
+        ),
+        (*git_config.commit_age(), VergenKey::CommitAgeSeconds),
+        (sha && sha_kind != ShaKind::Short, VergenKey::Sha),
+        (sha && sha_kind != ShaKind::Normal, VergenKey::ShortSha),
+    ];
+    for (_, key) in keys.iter().filter(|(enabled, _)| *enabled) {
+        add_entry(config, *key, Some(String::new()));
+    }
+}
+
+/// The `git log` arguments to output the signature status and key id, followed by the revision
+#[cfg(all(feature = "git", not(target_os = "wasi")))]
+const SIGNER_ARGS: &[&str] = &["log", "-1", "--format=%G?%n%GK"];
+
+/// The `git log` arguments to output the SHA, short SHA, and committer time of the last commit that touched a path,
+/// followed by the revision, `--`, and the path
+#[cfg(all(feature = "git", not(target_os = "wasi")))]
+const PATH_COMMIT_ARGS: &[&str] = &["log", "-1", "--format=%H%n%h%n%ct"];
+
+/// The `git rev-parse` arguments to output the object format of the repository
+#[cfg(all(feature = "git", not(target_os = "wasi")))]
+const OBJECT_FORMAT_ARGS: &[&str] = &["rev-parse", "--show-object-format"];
+
+/// Open the repository that contains the path
+#[cfg(all(feature = "git", not(target_os = "wasi")))]
+#[cfg_attr(not(any(test, feature = "test-util")), allow(unused_variables))]
+fn discover(
+    instructions: &Instructions,
+    path: &Path,
+) -> std::result::Result<Repository, git2::Error> {
+    #[cfg(any(test, feature = "test-util"))]
+    {
+        if let Some(discoveries) = instructions.providers().git_discoveries() {
+            let _ = discoveries.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+    Repository::discover(paths::normalize(path))
+}
+
+/// The production [`GitProvider`], reading from a `git2` repository, and killing `git` at the deadline
+///
+/// `HEAD`, or the `ref_name` [`Git`] field, is resolved once, when the provider is created, and the working tree status
+/// is scanned at most once, for both the dirty file count and the `git describe` dirty suffix.
+#[cfg(all(feature = "git", not(target_os = "wasi")))]
+struct Git2Repo<'repo> {
+    repo: &'repo Repository,
+    head: std::result::Result<Commit<'repo>, git2::Error>,
+    git_ref: Option<String>,
+    dirty_files: Cell<Option<usize>>,
+    deadline: Option<Instant>,
+}
+
+#[cfg(all(feature = "git", not(target_os = "wasi")))]
+impl<'repo> Git2Repo<'repo> {
+    fn new(repo: &'repo Repository, deadline: Option<Instant>) -> Self {
+        Self {
+            repo,
+            head: repo.head().and_then(|head| head.peel_to_commit()),
+            git_ref: None,
+            dirty_files: Cell::new(None),
+            deadline,
+        }
+    }
+
+    /// A provider for the `ref_name` [`Git`] field, or `HEAD` if it's None
+    fn open(repo: &'repo Repository, git_config: &Git, deadline: Option<Instant>) -> Result<Self> {
+        match git_config.ref_name() {
+            Some(git_ref) => Self::at_ref(repo, git_ref, deadline),
+            None => Ok(Self::new(repo, deadline)),
+        }
+    }
+
+    /// A provider for a ref rather than `HEAD`, which fails if the ref doesn't resolve to a commit
+    ///
+    /// The working tree isn't checked out at the ref, so it's never dirty.
+    fn at_ref(repo: &'repo Repository, git_ref: &str, deadline: Option<Instant>) -> Result<Self> {
+        let commit = repo
+            .revparse_single(git_ref)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|e| {
+                Error::Git(format!(
+                    "the '{}' ref doesn't resolve to a commit: {}",
+                    git_ref,
+                    e.message()
+                ))
+            })?;
+        Ok(Self {
+            repo,
+            head: Ok(commit),
+            git_ref: Some(git_ref.to_string()),
+            dirty_files: Cell::new(Some(0)),
+            deadline,
+        })
+    }
+
+    /// The described commit, or the error resolving it, i.e. for a repository without commits
+    fn head(&self) -> std::result::Result<&Commit<'repo>, git2::Error> {
+        self.head
+            .as_ref()
+            .map_err(|e| git2::Error::new(e.code(), e.class(), e.message()))
+    }
+
+    /// The revision to pass to `git`
+    fn rev(&self) -> &str {
+        self.git_ref.as_deref().unwrap_or("HEAD")
+    }
+}
+
+#[cfg(all(feature = "git", not(target_os = "wasi")))]
+impl fmt::Debug for Git2Repo<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Git2Repo").field(&self.repo.path()).finish()
+    }
+}
+
+/// The `git describe` of the described commit, without the dirty suffix
+///
+/// As with `git describe` of the working tree, this fails for a bare repository, unless a ref is described.
+#[cfg(all(feature = "git", not(target_os = "wasi")))]
+fn describe_head(repo: &Git2Repo<'_>, opts: &DescribeOptions) -> Result<String> {
+    if repo.git_ref.is_none() && repo.repo.is_bare() {
+        return Err(
+            Error::Git("a bare repository has no working tree to describe".to_string()).into(),
+        );
+    }
+    Ok(repo.head()?.as_object().describe(opts)?.format(None)?)
+}
+
+#[cfg(all(feature = "git", not(target_os = "wasi")))]
+impl GitProvider for Git2Repo<'_> {
+    fn branch(&self) -> Result<Option<String>> {
+        let repo = self.repo;
+        if let Some(git_ref) = &self.git_ref {
+            // Only a local or remote-tracking branch has a name, not a tag or a SHA
+            return Ok(repo
+                .resolve_reference_from_short_name(git_ref)
+                .ok()
+                .filter(|reference| reference.is_branch() || reference.is_remote())
+                .and_then(|reference| reference.shorthand().map(str::to_string)));
+        }
+        if repo.head_detached()? {
+            return Ok(Some("detached HEAD".to_string()));
+        }
+
+        let mut branch = None;
+        let locals = repo.branches(Some(BranchType::Local))?;
+        for (local, _bt) in locals.filter_map(std::result::Result::ok) {
+            if local.is_head() {
+                if let Some(name) = local.name()? {
+                    branch = Some(name.to_string());
+                }
+            }
+        }
+        Ok(branch)
+    }
+
+    fn commit_time(&self) -> Result<OffsetDateTime> {
+        Ok(OffsetDateTime::from_unix_timestamp(
+            self.head()?.time().seconds(),
+        )?)
+    }
+
+    fn commit_offset(&self) -> Result<UtcOffset> {
+        Ok(UtcOffset::from_whole_seconds(
+            self.head()?.time().offset_minutes() * 60,
+        )?)
+    }
+
+    fn sha(&self) -> Result<String> {
+        Ok(self.head()?.id().to_string())
+    }
+
+    fn tree_hash(&self) -> Result<String> {
+        Ok(self.head()?.tree_id().to_string())
+    }
+
+    fn short_sha(&self) -> Result<String> {
+        let short_id = self.head()?.as_object().short_id()?;
+        Ok(short_id.as_str().unwrap_or_default().to_string())
+    }
+
+    fn semver(
+        &self,
+        lightweight: bool,
+        always: bool,
+        dirty_suffix: Option<&str>,
+    ) -> Result<String> {
+        let mut opts = DescribeOptions::new();
+        if lightweight {
+            let _ = opts.describe_tags();
+        }
+        let _ = opts.show_commit_oid_as_fallback(always);
+        let mut semver = describe_head(self, &opts)?;
+        if let Some(dirty_text) = dirty_suffix {
+            if self.dirty_files()? > 0 {
+                semver.push_str(dirty_text);
+            }
+        }
+        Ok(semver)
+    }
+
+    fn tracked_files(&self) -> usize {
+        let repo = self.repo;
+        if self.git_ref.is_some() {
+            // The index is the working tree's, so count the files in the commit, including submodules
+            return self.head().and_then(Commit::tree).map_or(0, |tree| {
+                let mut count = 0_usize;
+                let _ = tree.walk(TreeWalkMode::PreOrder, |_, entry| {
+                    if entry.kind() != Some(ObjectType::Tree) {
+                        count += 1;
+                    }
+                    TreeWalkResult::Ok
+                });
+                count
+            });
+        }
+        if repo.is_bare() {
+            0
+        } else {
+            // Conflicted paths have an index entry per stage, so only count each path once.
+            repo.index().map_or(0, |index| {
+                let mut count = 0_usize;
+                let mut last_path = None;
+                for entry in index.iter() {
+                    if last_path.as_ref() != Some(&entry.path) {
+                        count += 1;
+                        last_path = Some(entry.path);
+                    }
+                }
+                count
+            })
+        }
+    }
+
+    fn commits_since_tag(&self, subjects: bool) -> Result<(usize, Vec<String>)> {
+        let repo = self.repo;
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(self.head()?.id())?;
+
+        // Hide every tagged commit, and therefore its history, from the walk
+        repo.tag_foreach(|oid, _name| {
+            if let Ok(commit) = repo
+                .find_object(oid, None)
+                .and_then(|obj| obj.peel_to_commit())
+            {
+                let _ = revwalk.hide(commit.id());
+            }
+            true
+        })?;
+
+        let mut count = 0_usize;
+        let mut summaries = vec![];
+        for oid in revwalk {
+            count += 1;
+            if subjects {
+                let commit = repo.find_commit(oid?)?;
+                summaries.push(commit.summary().unwrap_or_default().to_string());
+            }
+        }
+        Ok((count, summaries))
+    }
+
+    fn signer(&self) -> Result<Option<String>> {
+        // git2 can extract the signature, but not verify it, so ask git
+        let dir = self.repo.workdir().unwrap_or_else(|| self.repo.path());
+        let mut git = Command::new("git");
+        let _ = git.arg("-C").arg(dir).args(SIGNER_ARGS).arg(self.rev());
+        let output = command::output(&mut git, self.deadline)
+            .map_err(|e| Error::Git(format!("unable to run 'git': {}", e)))?;
+
+        if !output.status.success() {
+            return Err(Error::Git(format!(
+                "'git log' failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+            .into());
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut lines = stdout.lines();
+        match lines.next() {
+            Some(status) if status == "G" || status == "U" => {
+                Ok(Some(lines.next().unwrap_or_default().to_string()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn branch_count(&self) -> Result<usize> {
+        let mut count = 0_usize;
+        for branch in self.repo.branches(None)? {
+            let (branch, _) = branch?;
+            // Skip the 'origin/HEAD -> origin/main' symbolic refs
+            if branch.get().kind() != Some(ReferenceType::Symbolic) {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    fn tag_count(&self) -> Result<usize> {
+        Ok(self.repo.tag_names(None)?.len())
+    }
+
+    fn contributor_count(&self) -> Result<(usize, bool)> {
+        let repo = self.repo;
+        let mailmap = repo.mailmap()?;
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(self.head()?.id())?;
+
+        let mut emails = BTreeSet::new();
+        for oid in revwalk {
+            let author = repo.find_commit(oid?)?.author_with_mailmap(&mailmap)?;
+            let _ = emails.insert(String::from_utf8_lossy(author.email_bytes()).to_lowercase());
+        }
+        Ok((emails.len(), repo.is_shallow()))
+    }
+
+    fn merge_base(&self, base: &str) -> Result<Option<String>> {
+        let repo = self.repo;
+        let base = match repo
+            .revparse_single(base)
+            .and_then(|base| base.peel_to_commit())
+        {
+            Ok(base) => base.id(),
+            Err(_) => return Ok(None),
+        };
+        let head = self.head()?.id();
+        match repo.merge_base(head, base) {
+            Ok(merge_base) => Ok(Some(merge_base.to_string())),
+            Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn exact_tag(&self) -> Result<Option<String>> {
+        let mut opts = DescribeOptions::new();
+        let _ = opts.describe_tags().max_candidates_tags(0);
+        // Without candidates, describe only succeeds if a tag points at HEAD
+        Ok(describe_head(self, &opts).ok())
+    }
+
+    fn repo_root(&self) -> Option<PathBuf> {
+        // The working directory has a trailing separator, which `git rev-parse --show-toplevel` doesn't
+        self.repo
+            .workdir()
+            .map(|dir| paths::normalize(&dir.components().collect::<PathBuf>()))
+    }
+
+    fn worktree(&self) -> Option<PathBuf> {
+        if self.repo.is_worktree() {
+            self.repo_root()
+        } else {
+            None
+        }
+    }
+
+    fn commit_count(&self) -> Result<usize> {
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.push(self.head()?.id())?;
+        Ok(revwalk.count())
+    }
+
+    fn dirty_files(&self) -> Result<usize> {
+        if let Some(dirty) = self.dirty_files.get() {
+            return Ok(dirty);
+        }
+        let repo = self.repo;
+        if repo.is_bare() {
+            return Ok(0);
+        }
+        let mut opts = StatusOptions::new();
+        let _ = opts.include_untracked(false).include_ignored(false);
+        let dirty = repo
+            .statuses(Some(&mut opts))?
+            .iter()
+            .filter(|entry| entry.status() != Status::CURRENT)
+            .count();
+        self.dirty_files.set(Some(dirty));
+        Ok(dirty)
+    }
+
+    fn path_commit(&self, path: &Path) -> Result<Option<PathCommit>> {
+        // Run from the repository root, so the path is relative to it rather than the working directory
+        let dir = self.repo.workdir().unwrap_or_else(|| self.repo.path());
+        let mut git = Command::new("git");
+        let _ = git
+            .arg("-C")
+            .arg(dir)
+            .args(PATH_COMMIT_ARGS)
+            .arg(self.rev())
+            .arg("--")
+            .arg(path);
+        let output = command::output(&mut git, self.deadline)
+            .map_err(|e| Error::Git(format!("unable to run 'git': {}", e)))?;
+
+        if !output.status.success() {
+            return Err(Error::Git(format!(
+                "'git log' failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+            .into());
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut lines = stdout.lines();
+        match (lines.next(), lines.next(), lines.next()) {
+            (Some(sha), Some(short_sha), Some(seconds)) => Ok(Some(PathCommit {
+                sha: sha.to_string(),
+                short_sha: short_sha.to_string(),
+                commit_time: OffsetDateTime::from_unix_timestamp(seconds.parse().map_err(
+                    |e| Error::Git(format!("invalid commit time '{}': {}", seconds, e)),
+                )?)?,
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    fn object_format(&self) -> Result<String> {
+        // git2 doesn't know the object format, so ask git
+        let mut git = Command::new("git");
+        let _ = git.arg("-C").arg(self.repo.path()).args(OBJECT_FORMAT_ARGS);
+        let output = command::output(&mut git, self.deadline)
+            .map_err(|e| Error::Git(format!("unable to run 'git': {}", e)))?;
+
+        if !output.status.success() {
+            return Err(Error::Git(format!(
+                "'git rev-parse' failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+            .into());
+        }
+
+        // A git older than 2.25 echoes the unknown option back, and only supports sha1
+        match String::from_utf8_lossy(&output.stdout).trim() {
+            "--show-object-format" => Ok("sha1".to_string()),
+            format => Ok(format.to_string()),
+        }
+    }
+
+    fn parent_count(&self) -> Result<usize> {
+        Ok(self.head()?.parent_count())
+    }
+}
+
+#[cfg(feature = "git")]
+pub(crate) fn placeholder_git(instructions: &Instructions, config: &mut Config) {
+    let git_config = instructions.git();
+
+    if *instructions.vcs() == Vcs::Git && git_config.has_enabled() {
+        add_placeholders(config, &git_keys(git_config));
+    }
+}
+
+/// Whether each instruction is enabled
+#[cfg(feature = "git")]
+fn git_keys(git_config: &Git) -> Vec<(bool, VergenKey)> {
+    let timestamp = *git_config.commit_timestamp();
+    let kind = *git_config.commit_timestamp_kind();
+    let sha = *git_config.sha();
+    let sha_kind = *git_config.sha_kind();
+    let semver = *git_config.semver();
+    let lightweight = *git_config.semver_kind() == SemverKind::Lightweight;
+    vec![
+        (*git_config.branch(), VergenKey::Branch),
+        (timestamp && kind.has_date(), VergenKey::CommitDate),
+        (timestamp && kind.has_time(), VergenKey::CommitTime),
+        (
+            timestamp && kind.has_timestamp(),
+            VergenKey::CommitTimestamp,
+        ),
+        (sha && sha_kind != ShaKind::Short, VergenKey::Sha),
+        (sha && sha_kind != ShaKind::Normal, VergenKey::ShortSha),
+        (semver && !lightweight, VergenKey::Semver),
+        (semver && lightweight, VergenKey::SemverLightweight),
+        (*git_config.tracked_files(), VergenKey::TrackedFiles),
+        (*git_config.commit_age(), VergenKey::CommitAgeSeconds),
+        (*git_config.commits_since_tag(), VergenKey::CommitsSinceTag),
+        (
+            *git_config.commits_since_tag() && git_config.commits_since_tag_subjects().is_some(),
+            VergenKey::CommitsSinceTagSubjects,
+        ),
+        (*git_config.signed(), VergenKey::Signed),
+        (*git_config.signed(), VergenKey::Signer),
+        (*git_config.branch_count(), VergenKey::BranchCount),
+        (*git_config.tag_count(), VergenKey::TagCount),
+        (*git_config.repo_root(), VergenKey::GitRoot),
+        (*git_config.worktree(), VergenKey::GitWorktree),
+        (*git_config.tree_hash(), VergenKey::TreeHash),
+        (*git_config.contributor_count(), VergenKey::ContributorCount),
+        (*git_config.merge_base(), VergenKey::MergeBase),
+        (*git_config.object_format(), VergenKey::ObjectFormat),
+        (*git_config.parent_count(), VergenKey::ParentCount),
+    ]
+}
+
+#[cfg(not(feature = "git"))]
+pub(crate) fn placeholder_git(_instructions: &Instructions, _config: &mut Config) {}
+
+#[cfg(feature = "git")]
+fn add_config_entries(config: &mut Config, git_config: &Git, now: &OffsetDateTime) {
+    match git_config.commit_timestamp_kind() {
+        TimestampKind::DateOnly => add_date_entry(config, now),
+        TimestampKind::TimeOnly => add_time_entry(config, now),
+        TimestampKind::DateAndTime => {
+            add_date_entry(config, now);
+            add_time_entry(config, now);
+        }
+        TimestampKind::Timestamp => add_timestamp_entry(config, now),
+        TimestampKind::All => {
+            add_date_entry(config, now);
+            add_time_entry(config, now);
+            add_timestamp_entry(config, now);
+        }
+    }
+}
+
+#[cfg(feature = "git")]
+fn add_date_entry(config: &mut Config, now: &OffsetDateTime) {
+    add_entry(
+        config,
+        VergenKey::CommitDate,
+        now.format(format_description!("[year]-[month]-[day]")).ok(),
+    );
+}
+
+#[cfg(feature = "git")]
+fn add_time_entry(config: &mut Config, now: &OffsetDateTime) {
+    add_entry(
+        config,
+        VergenKey::CommitTime,
+        now.format(format_description!("[hour]-[minute]-[second]"))
+            .ok(),
+    );
+}
+
+#[cfg(feature = "git")]
+fn add_timestamp_entry(config: &mut Config, now: &OffsetDateTime) {
+    add_entry(
+        config,
+        VergenKey::CommitTimestamp,
+        now.format(&format_description::well_known::Rfc3339).ok(),
+    );
+}
+
+#[cfg(feature = "git")]
+fn add_commit_age(instructions: &Instructions, commit_seconds: i64, config: &mut Config) {
+    let age = if env::var_os("SOURCE_DATE_EPOCH").is_some() {
+        0
+    } else {
+        commit_age(now_utc(instructions).unix_timestamp(), commit_seconds)
+    };
+    add_entry(config, VergenKey::CommitAgeSeconds, Some(age.to_string()));
+}
+
+#[cfg(feature = "git")]
+fn commit_age(now_seconds: i64, commit_seconds: i64) -> i64 {
+    // A commit from the future, i.e. due to clock skew, is brand new
+    (now_seconds - commit_seconds).max(0)
+}
+
+#[cfg(feature = "git")]
+fn add_commits_since_tag<P>(
+    provider: &P,
+    subjects_max: Option<usize>,
+    config: &mut Config,
+) -> Result<()>
+where
+    P: GitProvider + ?Sized,
+{
+    let (count, subjects) = provider.commits_since_tag(subjects_max.is_some())?;
+
+    add_entry(config, VergenKey::CommitsSinceTag, Some(count.to_string()));
+
+    if let Some(max) = subjects_max {
+        add_entry(
+            config,
+            VergenKey::CommitsSinceTagSubjects,
+            Some(subjects.join("; ").chars().take(max).collect()),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "git", not(target_os = "wasi")))]
+mod test {
+    use super::{
+        add_commit_age, add_commits_since_tag, commit_age, configure_git, git_keys, tag_version,
+        Git2Repo, SemverKind, ShaKind,
+    };
+    use crate::{
+        config::{Config, Instructions, VergenKey},
+        feature::{skip_all, TimeZone, TimestampKind},
+        provider::{FakeRepo, FixedClock, GitProvider, PathCommit},
+    };
+    use anyhow::Result;
+    use git2::{Repository, Signature};
+    use regex::Regex;
+    use std::{
+        env, fs,
+        path::{Path, PathBuf},
+        process::Command,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+    use time::{macros::datetime, UtcOffset};
+
+    fn commit(repo: &Repository, message: &str) -> git2::Oid {
+        let sig = Signature::now("yoda", "yoda@dagobah.com").unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit<'_>> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .unwrap()
+    }
+
+    fn since_tag(repo: &Repository, subjects_max: Option<usize>) -> (String, String) {
+        let mut config = Config::default();
+        let opened = Repository::open(repo.path()).unwrap();
+        add_commits_since_tag(&Git2Repo::new(&opened, None), subjects_max, &mut config).unwrap();
+        let get = |key| config.cfg_map()[&key].clone().unwrap_or_default();
+        (
+            get(VergenKey::CommitsSinceTag),
+            get(VergenKey::CommitsSinceTagSubjects),
+        )
+    }
+
+    #[test]
+    fn git_config() {
+        let mut config = Instructions::default();
+        assert!(config.git().branch);
+        assert!(config.git().commit_timestamp);
+        assert_eq!(config.git().commit_timestamp_timezone, TimeZone::Utc);
+        assert_eq!(config.git().commit_timestamp_kind, TimestampKind::Timestamp);
+        assert!(config.git().rerun_on_head_change);
+        assert!(config.git().semver);
+        assert_eq!(config.git().semver_kind, SemverKind::Normal);
+        assert!(config.git().sha);
+        assert_eq!(config.git().sha_kind, ShaKind::Normal);
+        assert!(!config.git().tracked_files);
+        config.git_mut().commit_timestamp_kind = TimestampKind::All;
+        assert_eq!(config.git().commit_timestamp_kind, TimestampKind::All);
+    }
+
+    #[test]
+    fn not_enabled() {
+        let mut config = Instructions::default();
+        *config.git_mut().enabled_mut() = false;
+        assert!(!config.git().has_enabled());
+    }
+
+    #[test]
+    fn no_branch() {
+        let mut config = Instructions::default();
+        *config.git_mut().branch_mut() = false;
+        assert!(config.git().has_enabled());
+    }
+
+    #[test]
+    fn no_timestamp() {
+        let mut config = Instructions::default();
+        *config.git_mut().branch_mut() = false;
+        *config.git_mut().commit_timestamp_mut() = false;
+        assert!(config.git().has_enabled());
+    }
+
+    #[test]
+    fn no_rerun_on_head_change() {
+        let mut config = Instructions::default();
+        *config.git_mut().branch_mut() = false;
+        *config.git_mut().commit_timestamp_mut() = false;
+        *config.git_mut().rerun_on_head_change_mut() = false;
+        assert!(config.git().has_enabled());
+    }
+
+    #[test]
+    fn no_semver() {
+        let mut config = Instructions::default();
+        *config.git_mut().branch_mut() = false;
+        *config.git_mut().commit_timestamp_mut() = false;
+        *config.git_mut().rerun_on_head_change_mut() = false;
+        *config.git_mut().semver_mut() = false;
+        assert!(config.git().has_enabled());
+    }
+
+    #[test]
+    fn nothing() {
+        let mut config = Instructions::default();
+        *config.git_mut().branch_mut() = false;
+        *config.git_mut().commit_timestamp_mut() = false;
+        *config.git_mut().rerun_on_head_change_mut() = false;
+        *config.git_mut().semver_mut() = false;
+        *config.git_mut().sha_mut() = false;
+        assert!(!config.git().has_enabled());
+    }
+
+    #[test]
+    fn tracked_files_only() {
+        let mut config = Instructions::default();
+        *config.git_mut().branch_mut() = false;
+        *config.git_mut().commit_timestamp_mut() = false;
+        *config.git_mut().rerun_on_head_change_mut() = false;
+        *config.git_mut().semver_mut() = false;
+        *config.git_mut().sha_mut() = false;
+        *config.git_mut().tracked_files_mut() = true;
+        assert!(config.git().has_enabled());
+    }
+
+    #[test]
+    fn commit_age_works() {
+        assert_eq!(commit_age(1_000, 400), 600);
+        assert_eq!(commit_age(1_000, 1_000), 0);
+        assert_eq!(commit_age(1_000, 1_400), 0);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn commit_age_honors_source_date_epoch() {
+        env::set_var("SOURCE_DATE_EPOCH", "1613094855");
+        let mut config = Config::default();
+        add_commit_age(&Instructions::default(), 0, &mut config);
+        env::remove_var("SOURCE_DATE_EPOCH");
+        assert_eq!(
+            config.cfg_map()[&VergenKey::CommitAgeSeconds],
+            Some("0".to_string())
+        );
+
+        let mut config = Config::default();
+        add_commit_age(&Instructions::default(), 0, &mut config);
+        let age: i64 = config.cfg_map()[&VergenKey::CommitAgeSeconds]
+            .as_ref()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(age > 0);
+    }
+
+    fn fake_config(instructions: &Instructions) -> Config {
+        let mut config = Config::default();
+        configure_git(instructions, None::<PathBuf>, None, &mut config).unwrap();
+        config
+    }
+
+    fn fake_instructions(repo: FakeRepo) -> Instructions {
+        let mut instructions = Instructions::default();
+        *instructions.providers_mut().git_mut() = Some(Arc::new(repo));
+        *instructions.providers_mut().clock_mut() =
+            Some(Arc::new(FixedClock(datetime!(2021-02-12 01:54:15 UTC))));
+        instructions
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn fake_repo_covers_every_key() {
+        let mut instructions = fake_instructions(FakeRepo {
+            commit_time: datetime!(2021-02-11 01:54:15 UTC),
+            dirty: true,
+            commits_since_tag: vec!["Add a feature".to_string(), "Fix the docs".to_string()],
+            signer: Some("4AEE18F83AFDEB23".to_string()),
+            ..FakeRepo::default()
+        });
+        let git = instructions.git_mut();
+        *git.commit_timestamp_kind_mut() = TimestampKind::All;
+        *git.sha_kind_mut() = ShaKind::Both;
+        *git.semver_dirty_mut() = Some("-dirty");
+        *git.tracked_files_mut() = true;
+        *git.commit_age_mut() = true;
+        *git.commits_since_tag_mut() = true;
+        *git.commits_since_tag_subjects_mut() = Some(100);
+        *git.signed_mut() = true;
+        *git.branch_count_mut() = true;
+        *git.tag_count_mut() = true;
+        *git.repo_root_mut() = true;
+        *git.contributor_count_mut() = true;
+        *git.merge_base_mut() = true;
+        *git.object_format_mut() = true;
+        *git.parent_count_mut() = true;
+
+        let config = fake_config(&instructions);
+        let get = |key| config.cfg_map()[&key].clone().unwrap();
+        assert_eq!(get(VergenKey::Branch), "main");
+        assert_eq!(get(VergenKey::CommitDate), "2021-02-11");
+        assert_eq!(get(VergenKey::CommitTime), "01-54-15");
+        assert_eq!(get(VergenKey::CommitTimestamp), "2021-02-11T01:54:15Z");
+        assert_eq!(
+            get(VergenKey::Sha),
+            "95fc0f5d066710f16e0c23ce3239d6e040abca0d"
+        );
+        assert_eq!(get(VergenKey::ShortSha), "95fc0f5");
+        assert_eq!(get(VergenKey::Semver), "v3.2.0-86-g95fc0f5-dirty");
+        assert_eq!(get(VergenKey::TrackedFiles), "42");
+        assert_eq!(get(VergenKey::CommitsSinceTag), "2");
+        assert_eq!(
+            get(VergenKey::CommitsSinceTagSubjects),
+            "Add a feature; Fix the docs"
+        );
+        assert_eq!(get(VergenKey::CommitAgeSeconds), "86400");
+        assert_eq!(get(VergenKey::Signed), "true");
+        assert_eq!(get(VergenKey::Signer), "4AEE18F83AFDEB23");
+        assert_eq!(get(VergenKey::BranchCount), "3");
+        assert_eq!(get(VergenKey::TagCount), "12");
+        assert_eq!(get(VergenKey::GitRoot), "/home/yoda/projects/vergen");
+        assert_eq!(get(VergenKey::ContributorCount), "27");
+        assert_eq!(
+            get(VergenKey::MergeBase),
+            "2e4a4b1f4b6e2dd6d7a5c0f3e9e0f38a3b7c1d22"
+        );
+        assert_eq!(get(VergenKey::ObjectFormat), "sha1");
+        assert_eq!(get(VergenKey::ParentCount), "1");
+        assert!(config.head_path().is_none());
+    }
+
+    #[test]
+    fn fake_repo_uppercase_sha() {
+        let mut instructions = fake_instructions(FakeRepo::default());
+        *instructions.git_mut().sha_kind_mut() = ShaKind::Both;
+        *instructions.git_mut().uppercase_sha_mut() = true;
+        let config = fake_config(&instructions);
+        let upper_hex = Regex::new(r"^[0-9A-F]+$").unwrap();
+        for key in &[VergenKey::Sha, VergenKey::ShortSha] {
+            let sha = config.cfg_map()[key].clone().unwrap();
+            assert!(upper_hex.is_match(&sha), "{}", sha);
+        }
+        assert_eq!(
+            config.cfg_map()[&VergenKey::ShortSha],
+            Some("95FC0F5".to_string())
+        );
+    }
+
+    #[test]
+    fn fake_repo_lightweight_semver() {
+        let mut instructions = fake_instructions(FakeRepo {
+            semver_lightweight: Some("v3.2.1-lw".to_string()),
+            ..FakeRepo::default()
+        });
+        *instructions.git_mut().semver_kind_mut() = SemverKind::Lightweight;
+        let config = fake_config(&instructions);
+        assert_eq!(
+            config.cfg_map()[&VergenKey::SemverLightweight],
+            Some("v3.2.1-lw".to_string())
+        );
+        assert!(config.cfg_map()[&VergenKey::Semver].is_none());
+    }
+
+    #[test]
+    fn fake_repo_without_tags_uses_short_sha() {
+        let instructions = fake_instructions(FakeRepo {
+            semver: None,
+            ..FakeRepo::default()
+        });
+        let config = fake_config(&instructions);
+        assert_eq!(
+            config.cfg_map()[&VergenKey::Semver],
+            Some("95fc0f5".to_string())
+        );
+    }
+
+    #[test]
+    fn fake_repo_without_tags_uses_pkg_version() {
+        let mut instructions = fake_instructions(FakeRepo {
+            semver: None,
+            ..FakeRepo::default()
+        });
+        *instructions.git_mut().describe_always_mut() = false;
+        let config = fake_config(&instructions);
+        assert_eq!(
+            config.cfg_map()[&VergenKey::Semver],
+            env::var("CARGO_PKG_VERSION").ok()
+        );
+    }
+
+    #[test]
+    fn fake_repo_without_tags_dirty() {
+        let semver = |dirty_files| {
+            let mut instructions = fake_instructions(FakeRepo {
+                semver: None,
+                dirty_files,
+                ..FakeRepo::default()
+            });
+            *instructions.git_mut().semver_dirty_mut() = Some("-modified");
+            *instructions.git_mut().describe_always_mut() = false;
+            fake_config(&instructions).cfg_map()[&VergenKey::Semver].clone()
+        };
+        let version = env::var("CARGO_PKG_VERSION").unwrap();
+        assert_eq!(semver(0), Some(version.clone()));
+        assert_eq!(semver(2), Some(format!("{}-modified", version)));
+    }
+
+    #[test]
+    fn commits_since_tag() {
+        let path = env::temp_dir().join("vergen_commits_since_tag");
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+
+        let _ = commit(&repo, "First commit");
+        let _ = commit(&repo, "Second commit\n\nWith a body");
+        assert_eq!(
+            since_tag(&repo, Some(100)),
+            ("2".to_string(), "Second commit; First commit".to_string())
+        );
+
+        let tagged = repo.find_object(commit(&repo, "Release"), None).unwrap();
+        let sig = Signature::now("yoda", "yoda@dagobah.com").unwrap();
+        let _ = repo.tag("v0.1.0", &tagged, &sig, "v0.1.0", false).unwrap();
+        assert_eq!(since_tag(&repo, None).0, "0");
+
+        let _ = commit(&repo, "Fix the docs");
+        let _ = commit(&repo, "Add a feature");
+        assert_eq!(
+            since_tag(&repo, Some(16)),
+            ("2".to_string(), "Add a feature; F".to_string())
+        );
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn fake_repo_unsigned() {
+        let mut instructions = fake_instructions(FakeRepo::default());
+        *instructions.git_mut().signed_mut() = true;
+        let config = fake_config(&instructions);
+        assert_eq!(
+            config.cfg_map()[&VergenKey::Signed],
+            Some("false".to_string())
+        );
+        assert_eq!(config.cfg_map()[&VergenKey::Signer], Some(String::new()));
+    }
+
+    #[test]
+    fn unsigned_commit() {
+        let path = env::temp_dir().join("vergen_unsigned_commit");
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        let _ = commit(&repo, "Unsigned");
+
+        let repo = Git2Repo::new(&repo, None);
+        assert_eq!(repo.signer().unwrap(), None);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn branch_and_tag_counts() {
+        let path = env::temp_dir().join("vergen_branch_and_tag_counts");
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        let counts = |repo: &Repository| {
+            let opened = Repository::open(repo.path()).unwrap();
+            let repo = Git2Repo::new(&opened, None);
+            (repo.branch_count().unwrap(), repo.tag_count().unwrap())
+        };
+        assert_eq!(counts(&repo), (0, 0));
+
+        let oid = commit(&repo, "First commit");
+        let head = repo.find_commit(oid).unwrap();
+        let _ = repo.branch("feature", &head, false).unwrap();
+        let _ = repo
+            .reference("refs/remotes/origin/main", oid, false, "fetch")
+            .unwrap();
+        let _ = repo
+            .reference_symbolic(
+                "refs/remotes/origin/HEAD",
+                "refs/remotes/origin/main",
+                false,
+                "clone",
+            )
+            .unwrap();
+        let _ = repo
+            .tag_lightweight("v0.1.0", head.as_object(), false)
+            .unwrap();
+        let sig = Signature::now("yoda", "yoda@dagobah.com").unwrap();
+        let _ = repo
+            .tag("v0.2.0", head.as_object(), &sig, "v0.2.0", false)
+            .unwrap();
+        assert_eq!(counts(&repo), (3, 2));
+
+        // A detached HEAD isn't a branch
+        repo.set_head_detached(oid).unwrap();
+        assert_eq!(counts(&repo), (3, 2));
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn contributor_count() {
+        let path = env::temp_dir().join("vergen_contributor_count");
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        let _ = commit(&repo, "First commit");
+        let _ = commit(&repo, "Second commit");
+        let tree = repo.head().unwrap().peel_to_tree().unwrap();
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        let luke = Signature::now("luke", "Luke@Tatooine.com").unwrap();
+        let _ = repo
+            .commit(
+                Some("HEAD"),
+                &luke,
+                &luke,
+                "Third commit",
+                &tree,
+                &[&parent],
+            )
+            .unwrap();
+        let count = || {
+            let opened = Repository::open(repo.path()).unwrap();
+            let count = Git2Repo::new(&opened, None).contributor_count().unwrap();
+            count
+        };
+        assert_eq!(count(), (2, false));
+
+        // Emails are compared without case, and after the mailmap
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        let upper = Signature::now("Luke", "luke@TATOOINE.com").unwrap();
+        let _ = repo
+            .commit(
+                Some("HEAD"),
+                &upper,
+                &upper,
+                "Fourth commit",
+                &tree,
+                &[&parent],
+            )
+            .unwrap();
+        assert_eq!(count(), (2, false));
+        fs::write(
+            path.join(".mailmap"),
+            "Yoda <yoda@dagobah.com> <Luke@Tatooine.com>\nYoda <yoda@dagobah.com> <luke@TATOOINE.com>\n",
+        )
+        .unwrap();
+        assert_eq!(count(), (1, false));
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn fake_repo_shallow_contributor_count() {
+        let mut instructions = fake_instructions(FakeRepo {
+            contributor_count: 4,
+            shallow: true,
+            ..FakeRepo::default()
+        });
+        *instructions.git_mut().contributor_count_mut() = true;
+        let config = fake_config(&instructions);
+        assert_eq!(
+            config.cfg_map()[&VergenKey::ContributorCount],
+            Some("4".to_string())
+        );
+        assert_eq!(config.warnings().len(), 1);
+        assert!(config.warnings()[0].contains("shallow clone"));
+    }
+
+    #[test]
+    fn path_commit() {
+        let path = env::temp_dir().join("vergen_path_commit");
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        let touch = |repo: &Repository, dir: &str| {
+            fs::create_dir_all(path.join(dir)).unwrap();
+            fs::write(path.join(dir).join("lib.rs"), "").unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(&Path::new(dir).join("lib.rs")).unwrap();
+            index.write().unwrap();
+        };
+
+        touch(&repo, "a");
+        let first = commit(&repo, "Touch a");
+        touch(&repo, "b");
+        let second = commit(&repo, "Touch b");
+
+        let opened = Repository::open(repo.path()).unwrap();
+        let git2_repo = Git2Repo::new(&opened, None);
+        let a = git2_repo.path_commit(Path::new("a")).unwrap().unwrap();
+        assert_eq!(a.sha, first.to_string());
+        assert!(a.sha.starts_with(&a.short_sha));
+        let b = git2_repo.path_commit(Path::new("b")).unwrap().unwrap();
+        assert_eq!(b.sha, second.to_string());
+        assert!(git2_repo.path_commit(Path::new("c")).unwrap().is_none());
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn fake_repo_path_scope() {
+        let mut instructions = fake_instructions(FakeRepo {
+            path_commit: Some(PathCommit {
+                sha: "0123456789abcdef0123456789abcdef01234567".to_string(),
+                short_sha: "0123456".to_string(),
+                commit_time: datetime!(2020-01-01 00:00:00 UTC),
+            }),
+            ..FakeRepo::default()
+        });
+        *instructions.git_mut().path_scope_mut() = Some(PathBuf::from("crates/mypkg"));
+        *instructions.git_mut().sha_kind_mut() = ShaKind::Both;
+        let config = fake_config(&instructions);
+        assert_eq!(
+            config.cfg_map()[&VergenKey::Sha],
+            Some("0123456789abcdef0123456789abcdef01234567".to_string())
+        );
+        assert_eq!(
+            config.cfg_map()[&VergenKey::ShortSha],
+            Some("0123456".to_string())
+        );
+        assert_eq!(
+            config.cfg_map()[&VergenKey::CommitTimestamp],
+            Some("2020-01-01T00:00:00Z".to_string())
+        );
+        assert_eq!(
+            config.cfg_map()[&VergenKey::Branch],
+            Some("main".to_string())
+        );
+    }
+
+    #[test]
+    fn fake_repo_path_scope_without_history() {
+        let mut instructions = fake_instructions(FakeRepo::default());
+        *instructions.git_mut().path_scope_mut() = Some(PathBuf::from("crates/mypkg"));
+        *instructions.git_mut().commit_age_mut() = true;
+        let config = fake_config(&instructions);
+        for key in &[
+            VergenKey::Sha,
+            VergenKey::CommitTimestamp,
+            VergenKey::CommitAgeSeconds,
+        ] {
+            assert_eq!(config.cfg_map()[key], Some(String::new()));
+        }
+        assert_eq!(
+            config
+                .cfg_map()
+                .get(&VergenKey::ShortSha)
+                .cloned()
+                .flatten(),
+            None
+        );
+    }
+
+    #[test]
+    fn exact_tag() {
+        let path = env::temp_dir().join("vergen_exact_tag");
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        let exact_tag = |repo: &Repository| {
+            let opened = Repository::open(repo.path()).unwrap();
+            let tag = Git2Repo::new(&opened, None).exact_tag().unwrap();
+            tag
+        };
+
+        let oid = commit(&repo, "First commit");
+        assert_eq!(exact_tag(&repo), None);
+        let head = repo.find_commit(oid).unwrap();
+        let _ = repo
+            .tag_lightweight("v1.4.0", head.as_object(), false)
+            .unwrap();
+        assert_eq!(exact_tag(&repo), Some("v1.4.0".to_string()));
+        let _ = commit(&repo, "Second commit");
+        assert_eq!(exact_tag(&repo), None);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn describe_always() {
+        let path = env::temp_dir().join("vergen_describe_always");
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        let oid = commit(&repo, "First commit");
+        let semver = |always| {
+            let mut instructions = Instructions::default();
+            *instructions.git_mut().describe_always_mut() = always;
+            let mut config = Config::default();
+            configure_git(&instructions, Some(&path), None, &mut config).unwrap();
+            config.cfg_map()[&VergenKey::Semver].clone()
+        };
+
+        // Without tags, the abbreviated SHA rather than CARGO_PKG_VERSION
+        assert_eq!(semver(true), Some(oid.to_string()[..7].to_string()));
+        assert_eq!(semver(false), env::var("CARGO_PKG_VERSION").ok());
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn repo_root() {
+        let path = env::temp_dir().join("vergen_repo_root");
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        fs::create_dir_all(path.join("src")).unwrap();
+
+        let discovered = Repository::discover(path.join("src")).unwrap();
+        let root = Git2Repo::new(&discovered, None).repo_root().unwrap();
+        assert_eq!(root.canonicalize().unwrap(), path.canonicalize().unwrap());
+        assert!(!root.display().to_string().ends_with(['/', '\\'].as_ref()));
+        drop(discovered);
+        drop(repo);
+        fs::remove_dir_all(&path).unwrap();
+
+        let bare = env::temp_dir().join("vergen_repo_root_bare");
+        let _ = fs::remove_dir_all(&bare);
+        let _ = Repository::init_bare(&bare).unwrap();
+        let opened = Repository::open(&bare).unwrap();
+        assert_eq!(Git2Repo::new(&opened, None).repo_root(), None);
+        drop(opened);
+        fs::remove_dir_all(&bare).unwrap();
+    }
+
+    #[test]
+    fn object_format() {
+        let path = env::temp_dir().join("vergen_object_format");
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        // git2 creates a sha1 repository
+        assert_eq!(Git2Repo::new(&repo, None).object_format().unwrap(), "sha1");
+        drop(repo);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn sha256_repo() {
+        let path = env::temp_dir().join("vergen_sha256");
+        let _ = fs::remove_dir_all(&path);
+        // A git older than 2.29 can't create a sha256 repository
+        let created = Command::new("git")
+            .arg("init")
+            .arg("--object-format=sha256")
+            .arg(&path)
+            .output()
+            .map_or(false, |output| output.status.success());
+        if !created {
+            return;
+        }
+
+        let mut instructions = Instructions::default();
+        *instructions.git_mut().object_format_mut() = true;
+        assert!(configure_git(&instructions, Some(&path), None, &mut Config::default()).is_err());
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn commit_offset() {
+        let path = env::temp_dir().join("vergen_commit_offset");
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        let sig = Signature::new(
+            "yoda",
+            "yoda@dagobah.com",
+            &git2::Time::new(1_613_094_855, -330),
+        )
+        .unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let _ = repo
+            .commit(Some("HEAD"), &sig, &sig, "initial", &tree, &[])
+            .unwrap();
+        let offset = Git2Repo::new(&repo, None).commit_offset().unwrap();
+        assert_eq!(offset, UtcOffset::from_hms(-5, -30, 0).unwrap());
+        drop(tree);
+        drop(repo);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn parent_count() {
+        let path = env::temp_dir().join("vergen_parent_count");
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        let initial = commit(&repo, "initial");
+        assert_eq!(Git2Repo::new(&repo, None).parent_count().unwrap(), 0);
+        let ours = commit(&repo, "ours");
+        assert_eq!(Git2Repo::new(&repo, None).parent_count().unwrap(), 1);
+
+        // A side branch off the initial commit, merged back into HEAD
+        let sig = Signature::now("yoda", "yoda@dagobah.com").unwrap();
+        let initial = repo.find_commit(initial).unwrap();
+        let tree = initial.tree().unwrap();
+        let theirs = repo
+            .commit(None, &sig, &sig, "theirs", &tree, &[&initial])
+            .unwrap();
+        let ours = repo.find_commit(ours).unwrap();
+        let theirs = repo.find_commit(theirs).unwrap();
+        let _ = repo
+            .commit(Some("HEAD"), &sig, &sig, "merge", &tree, &[&ours, &theirs])
+            .unwrap();
+        assert_eq!(Git2Repo::new(&repo, None).parent_count().unwrap(), 2);
+        drop((tree, initial, ours, theirs));
+        drop(repo);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn linked_worktree() {
+        let path = env::temp_dir().join("vergen_worktree");
+        let worktree_path = env::temp_dir().join("vergen_worktree_hotfix");
+        let _ = fs::remove_dir_all(&path);
+        let _ = fs::remove_dir_all(&worktree_path);
+        let repo = Repository::init(&path).unwrap();
+        let _ = commit(&repo, "Initial commit");
+        let _ = repo.worktree("hotfix", &worktree_path, None).unwrap();
+        let hotfix = commit(&Repository::open(&worktree_path).unwrap(), "Hotfix");
+        assert!(worktree_path.join(".git").is_file());
+
+        let configure = |repo_path: &Path| {
+            let mut instructions = Instructions::default();
+            *instructions.git_mut().worktree_mut() = true;
+            let mut config = Config::default();
+            configure_git(&instructions, Some(repo_path), None, &mut config).unwrap();
+            config
+        };
+
+        let config = configure(&worktree_path);
+        let get = |key| config.cfg_map()[&key].clone().unwrap();
+        assert_eq!(get(VergenKey::Branch), "hotfix");
+        assert_eq!(get(VergenKey::Sha), hotfix.to_string());
+        assert_eq!(
+            Path::new(&get(VergenKey::GitWorktree))
+                .canonicalize()
+                .unwrap(),
+            worktree_path.canonicalize().unwrap()
+        );
+        let git_dir = path.join(".git").canonicalize().unwrap();
+        assert_eq!(
+            config.head_path().clone().unwrap().canonicalize().unwrap(),
+            git_dir.join("worktrees").join("hotfix").join("HEAD")
+        );
+        // The branch lives in the main repository, not the worktree's git directory
+        assert_eq!(
+            config.ref_path().clone().unwrap().canonicalize().unwrap(),
+            git_dir.join("refs").join("heads").join("hotfix")
+        );
+
+        // The main working tree isn't a linked worktree
+        let config = configure(&path);
+        assert_eq!(
+            config.cfg_map()[&VergenKey::GitWorktree],
+            Some(String::new())
+        );
+        assert_ne!(config.cfg_map()[&VergenKey::Sha], Some(hotfix.to_string()));
+
+        drop(repo);
+        fs::remove_dir_all(&worktree_path).unwrap();
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn tree_hash() {
+        let path = env::temp_dir().join("vergen_tree_hash");
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        let first = commit(&repo, "Initial commit");
+        // Rewording the commit keeps its tree
+        let reworded = repo
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap()
+            .amend(Some("HEAD"), None, None, None, Some("Reworded"), None)
+            .unwrap();
+        let head = repo.find_commit(reworded).unwrap();
+        assert_ne!(first, reworded);
+
+        let mut instructions = Instructions::default();
+        *instructions.git_mut().tree_hash_mut() = true;
+        let mut config = Config::default();
+        configure_git(&instructions, Some(&path), None, &mut config).unwrap();
+        let tree_hash = config.cfg_map()[&VergenKey::TreeHash].clone().unwrap();
+        assert_eq!(tree_hash.len(), 40);
+        assert!(tree_hash
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)));
+        assert_eq!(tree_hash, head.tree_id().to_string());
+        assert_eq!(
+            tree_hash,
+            repo.find_commit(first).unwrap().tree_id().to_string()
+        );
+        assert_ne!(config.cfg_map()[&VergenKey::Sha], Some(tree_hash));
+
+        drop(head);
+        drop(repo);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn fake_repo_bare_root() {
+        let mut instructions = fake_instructions(FakeRepo {
+            repo_root: None,
+            ..FakeRepo::default()
+        });
+        *instructions.git_mut().repo_root_mut() = true;
+        let config = fake_config(&instructions);
+        assert_eq!(config.cfg_map()[&VergenKey::GitRoot], Some(String::new()));
+    }
+
+    #[test]
+    fn skipped_without_repository() {
+        let mut instructions = Instructions::default();
+        *instructions.git_mut().sha_kind_mut() = ShaKind::Both;
+        let mut config = Config::default();
+        skip_all(
+            &mut config,
+            &git_keys(instructions.git()),
+            "the git repository can't be read on wasi",
+        );
+        assert_eq!(config.cfg_map()[&VergenKey::ShortSha], None);
+
+        instructions.warn_skipped(&mut config);
+        let warnings: Vec<&String> = config
+            .warnings()
+            .iter()
+            .filter(|warning| warning.contains("VERGEN_GIT_"))
+            .collect();
+        assert_eq!(
+            warnings,
+            &[
+                "vergen: skipped VERGEN_GIT_BRANCH because the git repository can't be read on wasi",
+                "vergen: skipped VERGEN_GIT_COMMIT_TIMESTAMP because the git repository can't be read on wasi",
+                "vergen: skipped VERGEN_GIT_SEMVER because the git repository can't be read on wasi",
+                "vergen: skipped VERGEN_GIT_SHA because the git repository can't be read on wasi",
+                "vergen: skipped VERGEN_GIT_SHA_SHORT because the git repository can't be read on wasi",
+            ]
+        );
+    }
+
+    #[test]
+    fn fake_repo_skipped() {
+        let mut instructions = fake_instructions(FakeRepo {
+            branch: None,
+            ..FakeRepo::default()
+        });
+        *instructions.git_mut().commits_since_tag_subjects_mut() = Some(80);
+        let mut config = fake_config(&instructions);
+        // Only the git feature ran, the other features' instructions are skipped too
+        instructions.warn_skipped(&mut config);
+        let warnings: Vec<&String> = config
+            .warnings()
+            .iter()
+            .filter(|warning| warning.contains("VERGEN_GIT_"))
+            .collect();
+        assert_eq!(
+            warnings,
+            &[
+                "vergen: skipped VERGEN_GIT_BRANCH because HEAD isn't on a local branch",
+                "vergen: skipped VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS because git.commits_since_tag is false",
+            ]
+        );
+    }
+
+    #[test]
+    fn fake_repo_build_number() {
+        let build_number = |dirty_files| {
+            let mut instructions = fake_instructions(FakeRepo {
+                dirty_files,
+                ..FakeRepo::default()
+            });
+            *instructions.build_number_mut() = true;
+            // None of the git instructions are needed
+            let git = instructions.git_mut();
+            *git.branch_mut() = false;
+            *git.commit_timestamp_mut() = false;
+            *git.rerun_on_head_change_mut() = false;
+            *git.semver_mut() = false;
+            *git.sha_mut() = false;
+            fake_config(&instructions).cfg_map()[&VergenKey::BuildNumber].clone()
+        };
+        assert_eq!(build_number(0), Some("1342".to_string()));
+        assert_eq!(build_number(2), Some("1342+2".to_string()));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn fake_repo_release_url() {
+        let release_url = |exact_tag: Option<&str>, template: Option<&str>| {
+            let mut instructions = fake_instructions(FakeRepo {
+                exact_tag: exact_tag.map(str::to_string),
+                ..FakeRepo::default()
+            });
+            *instructions.release_url_mut() = true;
+            *instructions.url_template_mut() = template.map(str::to_string);
+            fake_config(&instructions).cfg_map()[&VergenKey::ReleaseUrl].clone()
+        };
+        env::set_var(
+            "CARGO_PKG_REPOSITORY",
+            "https://github.com/rustyhorde/vergen.git",
+        );
+        let github = release_url(Some("v5.0.0"), None);
+        let gitlab = release_url(Some("v5.0.0"), Some("{repo}/-/releases/{tag}"));
+        // HEAD isn't tagged
+        let untagged = release_url(None, None);
+        env::remove_var("CARGO_PKG_REPOSITORY");
+        let no_repository = release_url(Some("v5.0.0"), None);
+        assert_eq!(
+            github.as_deref(),
+            Some("https://github.com/rustyhorde/vergen/releases/tag/v5.0.0")
+        );
+        assert_eq!(
+            gitlab.as_deref(),
+            Some("https://github.com/rustyhorde/vergen/-/releases/v5.0.0")
+        );
+        assert_eq!(untagged.as_deref(), Some(""));
+        assert_eq!(no_repository.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn build_number_counts() {
+        let path = env::temp_dir().join("vergen_build_number");
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        fs::write(path.join("README.md"), "vergen").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("README.md")).unwrap();
+        index.write().unwrap();
+        let _ = commit(&repo, "First commit");
+        let _ = commit(&repo, "Second commit");
+
+        let opened = Repository::open(&path).unwrap();
+        let dirty_files = || Git2Repo::new(&opened, None).dirty_files().unwrap();
+        let git2_repo = Git2Repo::new(&opened, None);
+        assert_eq!(git2_repo.commit_count().unwrap(), 2);
+        assert_eq!(git2_repo.dirty_files().unwrap(), 0);
+
+        // Untracked files don't make the working tree dirty
+        fs::write(path.join("notes.txt"), "untracked").unwrap();
+        assert_eq!(dirty_files(), 0);
+        fs::write(path.join("README.md"), "vergen, changed").unwrap();
+        assert_eq!(dirty_files(), 1);
+        // The working tree is only scanned once per provider
+        assert_eq!(git2_repo.dirty_files().unwrap(), 0);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn repository_is_opened_once() {
+        let path = env::temp_dir().join("vergen_opened_once");
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        fs::write(path.join("README.md"), "vergen").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("README.md")).unwrap();
+        index.write().unwrap();
+        let oid = commit(&repo, "First commit");
+        let sig = Signature::now("yoda", "yoda@dagobah.com").unwrap();
+        let head = repo.find_object(oid, None).unwrap();
+        let _ = repo.tag("v1.0.0", &head, &sig, "v1.0.0", false).unwrap();
+        fs::write(path.join("README.md"), "vergen, changed").unwrap();
+
+        let discoveries = Arc::new(AtomicUsize::new(0));
+        let mut instructions = Instructions::all_enabled();
+        *instructions.git_mut().semver_dirty_mut() = Some("-dirty");
+        *instructions.providers_mut().git_discoveries_mut() = Some(Arc::clone(&discoveries));
+        let mut config = Config::default();
+        configure_git(&instructions, Some(&path), None, &mut config).unwrap();
+
+        assert_eq!(discoveries.load(Ordering::SeqCst), 1);
+        let value = |key| config.cfg_map()[&key].clone().unwrap();
+        assert_eq!(value(VergenKey::Semver), "v1.0.0-dirty");
+        assert_eq!(value(VergenKey::BuildNumber), "1+1");
+        assert_eq!(value(VergenKey::Sha), oid.to_string());
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn ref_name() {
+        let path = env::temp_dir().join("vergen_git_ref");
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        fs::write(path.join("README.md"), "vergen").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("README.md")).unwrap();
+        index.write().unwrap();
+        let release = commit(&repo, "First commit");
+        let sig = Signature::now("yoda", "yoda@dagobah.com").unwrap();
+        let tagged = repo.find_object(release, None).unwrap();
+        let _ = repo.tag("v1.0.0", &tagged, &sig, "v1.0.0", false).unwrap();
+        let _ = repo
+            .reference("refs/remotes/origin/release", release, false, "fetch")
+            .unwrap();
+        fs::write(path.join("CHANGELOG.md"), "vergen").unwrap();
+        index.add_path(Path::new("CHANGELOG.md")).unwrap();
+        index.write().unwrap();
+        let head = commit(&repo, "Second commit");
+        fs::write(path.join("README.md"), "vergen, changed").unwrap();
+
+        let mut instructions = Instructions::default();
+        *instructions.git_mut().ref_name_mut() = Some("origin/release".to_string());
+        *instructions.git_mut().semver_dirty_mut() = Some("-dirty");
+        *instructions.git_mut().tracked_files_mut() = true;
+        let mut config = Config::default();
+        configure_git(&instructions, Some(&path), None, &mut config).unwrap();
+
+        let value = |key| config.cfg_map()[&key].clone().unwrap();
+        assert_eq!(value(VergenKey::Sha), release.to_string());
+        assert_ne!(value(VergenKey::Sha), head.to_string());
+        assert_eq!(value(VergenKey::Branch), "origin/release");
+        assert_eq!(value(VergenKey::Semver), "v1.0.0");
+        assert_eq!(value(VergenKey::TrackedFiles), "1");
+        assert!(config.head_path().is_none());
+        assert!(config
+            .ref_path()
+            .as_ref()
+            .unwrap()
+            .ends_with("refs/remotes/origin/release"));
+
+        // A tag describes the same commit, but isn't a branch
+        *instructions.git_mut().ref_name_mut() = Some("v1.0.0".to_string());
+        let mut config = Config::default();
+        configure_git(&instructions, Some(&path), None, &mut config).unwrap();
+        assert_eq!(config.cfg_map()[&VergenKey::Sha], Some(release.to_string()));
+        assert_eq!(config.cfg_map()[&VergenKey::Branch], None);
+
+        *instructions.git_mut().ref_name_mut() = Some("origin/missing".to_string());
+        let err = configure_git(&instructions, Some(&path), None, &mut Config::default())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("the 'origin/missing' ref doesn't resolve to a commit"));
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    // macOS doesn't allow file names that aren't valid UTF-8
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn non_utf8_repo() {
+        use crate::config::NonUtf8Policy;
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let path = env::temp_dir().join(OsStr::from_bytes(b"vergen_non_utf8_\xff"));
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        let _ = commit(&repo, "initial");
+
+        let configure = |policy| {
+            let mut instructions = Instructions::default();
+            *instructions.non_utf8_paths_mut() = policy;
+            *instructions.git_mut().repo_root_mut() = true;
+            let mut config = Config::default();
+            configure_git(&instructions, Some(&path), None, &mut config).unwrap();
+            config
+        };
+
+        let lossy = configure(NonUtf8Policy::Lossy);
+        let head_path = lossy.head_path().clone().unwrap();
+        assert_eq!(
+            head_path.canonicalize().unwrap(),
+            path.join(".git").join("HEAD").canonicalize().unwrap()
+        );
+        assert!(lossy.ref_path().is_some());
+        let root = lossy.cfg_map()[&VergenKey::GitRoot].clone().unwrap();
+        assert!(root.ends_with("vergen_non_utf8_\u{FFFD}"));
+        assert_eq!(lossy.warnings().len(), 3);
+        assert!(lossy.warnings()[0].contains("replacement characters"));
+
+        let skip = configure(NonUtf8Policy::Skip);
+        assert!(skip.head_path().is_none());
+        assert!(skip.ref_path().is_none());
+        assert_eq!(skip.cfg_map()[&VergenKey::GitRoot], Some(String::new()));
+        assert_eq!(skip.warnings().len(), 3);
+        assert!(skip.warnings()[0].contains("skipped"));
+
+        drop(repo);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn merge_base() {
+        let path = env::temp_dir().join("vergen_merge_base");
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        let ancestor = commit(&repo, "Initial commit");
+        let ancestor_commit = repo.find_commit(ancestor).unwrap();
+        let _ = repo.branch("main", &ancestor_commit, false).unwrap();
+        let _ = repo.branch("feature", &ancestor_commit, false).unwrap();
+        repo.set_head("refs/heads/main").unwrap();
+        let _ = commit(&repo, "Work on main");
+        repo.set_head("refs/heads/feature").unwrap();
+        let feature = commit(&repo, "Work on the feature");
+        assert_ne!(feature, ancestor);
+
+        let merge_base = |base: Option<&str>| {
+            let mut instructions = Instructions::default();
+            *instructions.git_mut().merge_base_mut() = true;
+            *instructions.git_mut().merge_base_ref_mut() = base.map(str::to_string);
+            let mut config = Config::default();
+            configure_git(&instructions, Some(&path), None, &mut config).unwrap();
+            config.cfg_map()[&VergenKey::MergeBase].clone().unwrap()
+        };
+        assert_eq!(merge_base(Some("main")), ancestor.to_string());
+        // Neither the default origin/main nor an unknown ref resolves
+        assert_eq!(merge_base(Some("origin/main")), "");
+        assert_eq!(merge_base(Some("no-such-branch")), "");
+        assert_eq!(merge_base(None), "");
+
+        drop(ancestor_commit);
+        drop(repo);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn outside_repository() {
+        let path = env::temp_dir().join("vergen_outside_repository");
+        let _ = fs::create_dir_all(&path);
+        let mut instructions = Instructions::default();
+        let git = instructions.git_mut();
+        *git.branch_mut() = false;
+        *git.commit_timestamp_mut() = false;
+        *git.semver_mut() = false;
+        *git.sha_mut() = false;
+        *git.repo_root_mut() = true;
+        *git.tree_hash_mut() = true;
+
+        let mut config = Config::default();
+        configure_git(&instructions, Some(&path), None, &mut config).unwrap();
+        assert_eq!(config.cfg_map()[&VergenKey::GitRoot], Some(String::new()));
+        assert_eq!(config.cfg_map()[&VergenKey::TreeHash], Some(String::new()));
+        assert!(config.head_path().is_none());
+
+        // The other instructions still need a repository
+        *instructions.git_mut().sha_mut() = true;
+        let mut config = Config::default();
+        assert!(configure_git(&instructions, Some(&path), None, &mut config).is_err());
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn tag_versions() {
+        assert_eq!(tag_version("v1.4.0"), Some("1.4.0"));
+        assert_eq!(tag_version("1.4.0+build.7"), Some("1.4.0"));
+        assert_eq!(tag_version("v1.4.0-rc.1+abc"), Some("1.4.0-rc.1"));
+        assert_eq!(tag_version("release"), None);
+        assert_eq!(tag_version("v"), None);
+    }
+
+    fn tag_check(tag: Option<&str>, strict: bool) -> Result<Vec<String>> {
+        let mut instructions = fake_instructions(FakeRepo {
+            exact_tag: tag.map(str::to_string),
+            ..FakeRepo::default()
+        });
+        *instructions.git_mut().check_tag_version_mut() = true;
+        *instructions.git_mut().check_tag_version_strict_mut() = strict;
+        let mut config = Config::default();
+        configure_git(&instructions, None::<PathBuf>, None, &mut config)?;
+        Ok(config.warnings().clone())
+    }
+
+    #[test]
+    fn tag_version_matches() {
+        let tag = format!("v{}+build.1", env!("CARGO_PKG_VERSION"));
+        assert!(tag_check(Some(&tag), true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn tag_version_mismatch() {
+        let warnings = tag_check(Some("v1.4.0"), false).unwrap();
+        assert_eq!(
+            warnings,
+            vec![format!(
+                "vergen: the 'v1.4.0' tag on HEAD doesn't match the CARGO_PKG_VERSION '{}'",
+                env!("CARGO_PKG_VERSION")
+            )]
+        );
+        let err = tag_check(Some("v1.4.0"), true).unwrap_err();
+        assert!(err.to_string().contains("'v1.4.0' tag on HEAD"));
+        assert!(err.to_string().contains(env!("CARGO_PKG_VERSION")));
+    }
+
+    #[test]
+    fn tag_version_without_tag() {
+        assert!(tag_check(None, true).unwrap().is_empty());
+        assert!(tag_check(Some("nightly"), true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn strict_tag_version_requires_check() {
+        let mut config = Instructions::default();
+        *config.git_mut().check_tag_version_strict_mut() = true;
+        let errors = validate(&config);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "git.check_tag_version_strict");
+    }
+
+    #[test]
+    fn empty_path_scope() {
+        let mut config = Instructions::default();
+        *config.git_mut().path_scope_mut() = Some(PathBuf::new());
+        let errors = validate(&config);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "git.path_scope");
+    }
+
+    #[test]
+    fn empty_ref_name() {
+        let mut config = Instructions::default();
+        *config.git_mut().ref_name_mut() = Some(String::new());
+        let errors = validate(&config);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "git.ref_name");
+    }
+
+    #[test]
+    fn commits_since_tag_subjects_require_commits_since_tag() {
+        let mut config = Instructions::default();
+        *config.git_mut().commits_since_tag_subjects_mut() = Some(10);
+        let errors = validate(&config);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "git.commits_since_tag_subjects");
+
+        *config.git_mut().commits_since_tag_mut() = true;
+        assert!(validate(&config).is_empty());
+        *config.git_mut().commits_since_tag_subjects_mut() = Some(0);
+        assert_eq!(validate(&config).len(), 1);
+    }
+
+    fn validate(config: &Instructions) -> Vec<(String, String)> {
+        let mut errors = vec![];
+        config.git().validate(&mut errors);
+        errors
+            .into_iter()
+            .map(|e| (e.field().clone(), e.value().clone()))
+            .collect()
+    }
+
+    #[test]
+    fn missing_base_dir() {
+        let mut config = Instructions::default();
+        *config.git_mut().base_dir_mut() = Some(PathBuf::from("testdata").join("yoda"));
+        let errors = validate(&config);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "git.base_dir");
+    }
+
+    #[test]
+    fn commit_timestamp_settings_require_commit_timestamp() {
+        let mut config = Instructions::default();
+        *config.git_mut().commit_timestamp_mut() = false;
+        *config.git_mut().commit_timestamp_kind_mut() = TimestampKind::All;
+        *config.git_mut().commit_timestamp_timezone_mut() = TimeZone::Local;
+        let errors = validate(&config);
+        assert_eq!(
+            errors,
+            vec![
+                ("git.commit_timestamp_kind".to_string(), "All".to_string()),
+                (
+                    "git.commit_timestamp_timezone".to_string(),
+                    "Local".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn semver_settings_require_semver() {
+        let mut config = Instructions::default();
+        *config.git_mut().semver_mut() = false;
+        *config.git_mut().semver_kind_mut() = SemverKind::Lightweight;
+        *config.git_mut().semver_dirty_mut() = Some("-dirty");
+        *config.git_mut().describe_always_mut() = false;
+        let errors = validate(&config);
+        assert_eq!(
+            errors,
+            vec![
+                ("git.semver_kind".to_string(), "Lightweight".to_string()),
+                (
+                    "git.semver_dirty".to_string(),
+                    "Some(\"-dirty\")".to_string()
+                ),
+                ("git.describe_always".to_string(), "false".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_dirty_suffix() {
+        let mut config = Instructions::default();
+        *config.git_mut().semver_dirty_mut() = Some("");
+        let errors = validate(&config);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "git.semver_dirty");
+    }
+
+    #[test]
+    fn sha_kind_requires_sha() {
+        let mut config = Instructions::default();
+        *config.git_mut().sha_mut() = false;
+        *config.git_mut().sha_kind_mut() = ShaKind::Both;
+        let errors = validate(&config);
+        assert_eq!(
+            errors,
+            vec![("git.sha_kind".to_string(), "Both".to_string())]
+        );
+    }
+
+    #[test]
+    fn disabled_is_not_validated() {
+        let mut config = Instructions::default();
+        *config.git_mut().enabled_mut() = false;
+        *config.git_mut().sha_mut() = false;
+        *config.git_mut().sha_kind_mut() = ShaKind::Both;
+        assert!(validate(&config).is_empty());
     }
 }
 
@@ -0,0 +1,505 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` mercurial feature implementation
+
+use crate::config::{Config, Instructions};
+use anyhow::Result;
+use std::{path::Path, time::Instant};
+#[cfg(feature = "hg")]
+use {
+    crate::{
+        command,
+        config::{Vcs, VergenKey},
+        error::Error,
+        feature::{add_entry_or_skip, add_placeholders},
+        merge::{pick, SetFields},
+        paths,
+    },
+    std::{env, ffi::OsString, path::PathBuf, process::Command},
+};
+
+/// Configuration for the `VERGEN_HG_*` instructions
+///
+/// These instructions are only generated when the [`vcs`](crate::Config::vcs_mut) field is [`Vcs::Hg`](crate::Vcs::Hg).
+///
+/// # Instructions
+/// The following instructions can be generated:
+///
+/// | Instruction | Default |
+/// | ----------- | :-----: |
+/// | `cargo:rustc-env=VERGEN_HG_BRANCH=default` | * |
+/// | `cargo:rustc-env=VERGEN_HG_REV=42` | * |
+/// | `cargo:rustc-env=VERGEN_HG_SHA=4fa5e6f6a7e1e6f5f0f4c8a1b2d3e4f5a6b7c8d9` | * |
+/// | `cargo:rerun-if-changed=/Users/yoda/projects/rust-lang/vergen/.hg/dirstate` | * |
+///
+/// * If the `branch` field is false, the branch instruction will not be generated.
+/// * If the `rev` field is false, the local revision number instruction will not be generated.
+/// * If the `sha` field is false, the changeset id instruction will not be generated.
+/// * **NOTE** - The information is read by running `hg` in the directory the build script runs in.  Set the `HG`
+/// environment variable to use another `hg` executable.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// use vergen::{vergen, Config};
+///
+/// # pub fn main() -> Result<()> {
+/// let mut config = Config::default();
+#[cfg_attr(
+    feature = "hg",
+    doc = r##"
+// Read the version control information from mercurial
+*config.vcs_mut() = vergen::Vcs::Hg;
+// Turn off the local revision number instruction
+*config.hg_mut().rev_mut() = false;
+"##
+)]
+/// # Ok(())
+/// # }
+#[cfg(feature = "hg")]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
+pub struct Hg {
+    /// Enable/Disable the `VERGEN_HG_*` instructions
+    enabled: bool,
+    /// Enable/Disable the `VERGEN_HG_BRANCH` instruction
+    branch: bool,
+    /// Enable/Disable the `VERGEN_HG_REV` instruction
+    rev: bool,
+    /// Enable/Disable the `VERGEN_HG_SHA` instruction
+    sha: bool,
+    /// The fields set through their `set_*` setter, which win in a [merge](crate::Config::merge)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    set_fields: SetFields,
+}
+
+#[cfg(feature = "hg")]
+impl Hg {
+    /// Enable/Disable the `VERGEN_HG_*` instructions
+    pub(crate) fn enabled(&self) -> &bool {
+        &self.enabled
+    }
+
+    /// Enable/Disable the `VERGEN_HG_*` instructions
+    pub fn enabled_mut(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+
+    /// Enable/Disable the `VERGEN_HG_*` instructions
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "enabled");
+        self.enabled = enabled;
+    }
+
+    /// Enable/Disable the `VERGEN_HG_BRANCH` instruction
+    pub(crate) fn branch(&self) -> &bool {
+        &self.branch
+    }
+
+    /// Enable/Disable the `VERGEN_HG_BRANCH` instruction
+    pub fn branch_mut(&mut self) -> &mut bool {
+        &mut self.branch
+    }
+
+    /// Enable/Disable the `VERGEN_HG_BRANCH` instruction
+    pub fn set_branch(&mut self, branch: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "branch");
+        self.branch = branch;
+    }
+
+    /// Enable/Disable the `VERGEN_HG_REV` instruction
+    pub(crate) fn rev(&self) -> &bool {
+        &self.rev
+    }
+
+    /// Enable/Disable the `VERGEN_HG_REV` instruction
+    pub fn rev_mut(&mut self) -> &mut bool {
+        &mut self.rev
+    }
+
+    /// Enable/Disable the `VERGEN_HG_REV` instruction
+    pub fn set_rev(&mut self, rev: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "rev");
+        self.rev = rev;
+    }
+
+    /// Enable/Disable the `VERGEN_HG_SHA` instruction
+    pub(crate) fn sha(&self) -> &bool {
+        &self.sha
+    }
+
+    /// Enable/Disable the `VERGEN_HG_SHA` instruction
+    pub fn sha_mut(&mut self) -> &mut bool {
+        &mut self.sha
+    }
+
+    /// Enable/Disable the `VERGEN_HG_SHA` instruction
+    pub fn set_sha(&mut self, sha: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "sha");
+        self.sha = sha;
+    }
+}
+
+#[cfg(feature = "hg")]
+impl Default for Hg {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            branch: true,
+            rev: true,
+            sha: true,
+            set_fields: SetFields::default(),
+        }
+    }
+}
+
+/// The [`Hg`] fields that win in a [merge](crate::Config::merge) once set, see [`SetFields`]
+#[cfg(feature = "hg")]
+const MERGED_FIELDS: &[&str] = &["enabled", "branch", "rev", "sha"];
+
+#[cfg(feature = "hg")]
+impl Hg {
+    pub(crate) fn merge(self, overlay: Self) -> Self {
+        let default = Self::default();
+        let overlay_set = overlay.set_fields;
+        let set = |name| overlay_set.contains(MERGED_FIELDS, name);
+        Self {
+            enabled: pick(
+                self.enabled,
+                overlay.enabled,
+                &default.enabled,
+                set("enabled"),
+            ),
+            branch: pick(self.branch, overlay.branch, &default.branch, set("branch")),
+            rev: pick(self.rev, overlay.rev, &default.rev, set("rev")),
+            sha: pick(self.sha, overlay.sha, &default.sha, set("sha")),
+            set_fields: self.set_fields.union(overlay_set),
+        }
+    }
+
+    pub(crate) fn has_enabled(self) -> bool {
+        self.enabled && (self.branch || self.rev || self.sha)
+    }
+}
+
+#[cfg(not(feature = "hg"))]
+pub(crate) fn configure_hg<T>(
+    _instructions: &Instructions,
+    _repo: Option<T>,
+    _deadline: Option<Instant>,
+    _config: &mut Config,
+) -> Result<()>
+where
+    T: AsRef<Path>,
+{
+    Ok(())
+}
+
+#[cfg(feature = "hg")]
+pub(crate) fn configure_hg<T>(
+    instructions: &Instructions,
+    _repo: Option<T>,
+    deadline: Option<Instant>,
+    config: &mut Config,
+) -> Result<()>
+where
+    T: AsRef<Path>,
+{
+    let hg_config = instructions.hg();
+
+    if *instructions.vcs() == Vcs::Hg && hg_config.has_enabled() {
+        let log = hg(
+            &[
+                "log",
+                "-r",
+                ".",
+                "--template",
+                "{node}\\n{branch}\\n{rev}\\n",
+            ],
+            deadline,
+        )?;
+        let mut lines = log.lines().map(str::to_string);
+        let (sha, branch, rev) = (lines.next(), lines.next(), lines.next());
+
+        if *hg_config.branch() {
+            add_entry_or_skip(
+                config,
+                VergenKey::HgBranch,
+                branch,
+                "'hg log' didn't print a branch",
+            );
+        }
+        if *hg_config.rev() {
+            add_entry_or_skip(
+                config,
+                VergenKey::HgRev,
+                rev,
+                "'hg log' didn't print a revision number",
+            );
+        }
+        if *hg_config.sha() {
+            add_entry_or_skip(
+                config,
+                VergenKey::HgSha,
+                sha,
+                "'hg log' didn't print a node",
+            );
+        }
+
+        let root = hg_path(&hg_output(&["root"], deadline)?);
+        let dirstate = paths::normalize(&root.join(".hg").join("dirstate"));
+        if dirstate.exists() {
+            *config.head_path_mut() =
+                paths::emitted_path(*instructions.non_utf8_paths(), dirstate, config);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "hg")]
+fn hg(args: &[&str], deadline: Option<Instant>) -> Result<String> {
+    hg_output(args, deadline).map(|stdout| String::from_utf8_lossy(&stdout).into_owned())
+}
+
+/// The stdout of `hg` with the given arguments, as bytes
+#[cfg(feature = "hg")]
+fn hg_output(args: &[&str], deadline: Option<Instant>) -> Result<Vec<u8>> {
+    let hg = env::var_os("HG").unwrap_or_else(|| OsString::from("hg"));
+    let mut command = Command::new(&hg);
+    let _ = command
+        .args(args)
+        // Ignore any user configuration that changes the output
+        .env("HGPLAIN", "1");
+    let output = command::output(&mut command, deadline)
+        .map_err(|e| Error::Hg(format!("unable to run '{}': {}", hg.to_string_lossy(), e)))?;
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(Error::Hg(format!(
+            "'hg {}' failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+        .into())
+    }
+}
+
+/// The path `hg` printed, without the trailing newline, and without converting it to UTF-8 where paths are bytes
+#[cfg(all(feature = "hg", unix))]
+fn hg_path(stdout: &[u8]) -> PathBuf {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    let end = stdout
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(0, |i| i + 1);
+    PathBuf::from(OsStr::from_bytes(&stdout[..end]))
+}
+
+/// The path `hg` printed, without the trailing newline
+#[cfg(all(feature = "hg", not(unix)))]
+fn hg_path(stdout: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(stdout).trim_end())
+}
+
+#[cfg(feature = "hg")]
+pub(crate) fn placeholder_hg(instructions: &Instructions, config: &mut Config) {
+    let hg_config = instructions.hg();
+
+    if *instructions.vcs() == Vcs::Hg && hg_config.has_enabled() {
+        add_placeholders(
+            config,
+            &[
+                (*hg_config.branch(), VergenKey::HgBranch),
+                (*hg_config.rev(), VergenKey::HgRev),
+                (*hg_config.sha(), VergenKey::HgSha),
+            ],
+        );
+    }
+}
+
+#[cfg(not(feature = "hg"))]
+pub(crate) fn placeholder_hg(_instructions: &Instructions, _config: &mut Config) {}
+
+#[cfg(all(test, feature = "hg"))]
+mod test {
+    use super::configure_hg;
+    use crate::config::{Config, Instructions, Vcs, VergenKey};
+    use std::path::PathBuf;
+
+    #[test]
+    fn hg_config() {
+        let mut config = Instructions::default();
+        assert_eq!(*config.vcs(), Vcs::Git);
+        assert!(config.hg().has_enabled());
+        *config.hg_mut().branch_mut() = false;
+        *config.hg_mut().rev_mut() = false;
+        assert!(config.hg().has_enabled());
+        *config.hg_mut().sha_mut() = false;
+        assert!(!config.hg().has_enabled());
+    }
+
+    #[test]
+    fn git_is_the_default() {
+        let mut config = Config::default();
+        configure_hg(&Instructions::default(), None::<PathBuf>, None, &mut config).unwrap();
+        assert!(config.cfg_map()[&VergenKey::HgSha].is_none());
+    }
+
+    #[test]
+    fn hg_replaces_git() {
+        let mut instructions = Instructions::default();
+        *instructions.vcs_mut() = Vcs::Hg;
+        let config = instructions.placeholder_config();
+        assert!(config.cfg_map()[&VergenKey::HgSha].is_some());
+        #[cfg(feature = "git")]
+        assert!(config.cfg_map()[&VergenKey::Sha].is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    #[serial_test::serial]
+    fn fake_hg() {
+        use std::{env, fs, os::unix::fs::PermissionsExt};
+
+        let dir = env::temp_dir().join("vergen_fake_hg");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(".hg")).unwrap();
+        fs::write(dir.join(".hg").join("dirstate"), "").unwrap();
+        let script = dir.join("hg");
+        fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\nif [ \"$1\" = root ]; then echo {}; else printf 'abc123\\ndefault\\n42\\n'; fi\n",
+                dir.display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut instructions = Instructions::default();
+        *instructions.vcs_mut() = Vcs::Hg;
+        env::set_var("HG", &script);
+        let mut config = Config::default();
+        let result = configure_hg(&instructions, None::<PathBuf>, None, &mut config);
+        env::remove_var("HG");
+
+        assert!(result.is_ok());
+        let get = |key| config.cfg_map()[&key].clone();
+        assert_eq!(get(VergenKey::HgSha), Some("abc123".to_string()));
+        assert_eq!(get(VergenKey::HgBranch), Some("default".to_string()));
+        assert_eq!(get(VergenKey::HgRev), Some("42".to_string()));
+        assert_eq!(config.head_path(), &Some(dir.join(".hg").join("dirstate")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    #[serial_test::serial]
+    fn skipped_branch() {
+        use std::{env, fs, os::unix::fs::PermissionsExt};
+
+        let dir = env::temp_dir().join("vergen_fake_hg_skipped");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("hg");
+        fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\nif [ \"$1\" = root ]; then echo {}; else printf 'abc123\\n'; fi\n",
+                dir.display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut instructions = Instructions::default();
+        *instructions.vcs_mut() = Vcs::Hg;
+        *instructions.hg_mut().rev_mut() = false;
+        env::set_var("HG", &script);
+        let mut config = Config::default();
+        let result = configure_hg(&instructions, None::<PathBuf>, None, &mut config);
+        env::remove_var("HG");
+
+        assert!(result.is_ok());
+        // Only the hg feature ran, the other features' instructions are skipped too
+        instructions.warn_skipped(&mut config);
+        let warnings: Vec<&String> = config
+            .warnings()
+            .iter()
+            .filter(|warning| warning.contains("VERGEN_HG_"))
+            .collect();
+        assert_eq!(
+            warnings,
+            &["vergen: skipped VERGEN_HG_BRANCH because 'hg log' didn't print a branch"]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // macOS doesn't allow file names that aren't valid UTF-8
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    #[serial_test::serial]
+    fn non_utf8_root() {
+        use std::{
+            env,
+            ffi::OsStr,
+            fs,
+            os::unix::{ffi::OsStrExt, fs::PermissionsExt},
+        };
+
+        let dir = env::temp_dir().join(OsStr::from_bytes(b"vergen_fake_hg_\xff"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(".hg")).unwrap();
+        fs::write(dir.join(".hg").join("dirstate"), "").unwrap();
+        let script = dir.join("hg");
+        let mut contents = b"#!/bin/sh\nif [ \"$1\" = root ]; then echo '".to_vec();
+        contents.extend_from_slice(dir.as_os_str().as_bytes());
+        contents.extend_from_slice(b"'; else printf 'abc123\\ndefault\\n42\\n'; fi\n");
+        fs::write(&script, contents).unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut instructions = Instructions::default();
+        *instructions.vcs_mut() = Vcs::Hg;
+        env::set_var("HG", &script);
+        let mut config = Config::default();
+        let result = configure_hg(&instructions, None::<PathBuf>, None, &mut config);
+        env::remove_var("HG");
+
+        assert!(result.is_ok());
+        assert_eq!(config.head_path(), &Some(dir.join(".hg").join("dirstate")));
+        assert_eq!(config.warnings().len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn missing_hg() {
+        let mut instructions = Instructions::default();
+        *instructions.vcs_mut() = Vcs::Hg;
+        std::env::set_var("HG", "vergen_no_such_hg");
+        let mut config = Config::default();
+        let result = configure_hg(&instructions, None::<PathBuf>, None, &mut config);
+        std::env::remove_var("HG");
+        assert!(result.is_err());
+    }
+}
+
+#[cfg(all(test, not(feature = "hg")))]
+mod test {}
@@ -14,30 +14,41 @@
     feature = "git",
     feature = "rustc",
     feature = "si",
+    feature = "hg",
 ))]
-use {crate::config::VergenKey, std::collections::BTreeMap};
+use crate::{
+    config::{Config, VergenKey},
+    constants::PLACEHOLDER,
+    sanitize::trim_value,
+};
 
 mod build;
 mod cargo;
 mod git;
+mod hg;
 mod rustc;
 mod si;
 
-pub(crate) use build::configure_build;
+pub(crate) use build::{configure_build, placeholder_build};
 #[cfg(feature = "build")]
-pub use build::Build;
-pub(crate) use cargo::configure_cargo;
+pub use build::{Build, Precision};
+pub(crate) use cargo::{configure_cargo, placeholder_cargo};
 #[cfg(feature = "cargo")]
-pub use cargo::Cargo;
-pub(crate) use git::configure_git;
+pub use cargo::{Cargo, TargetNames};
+#[cfg(feature = "build")]
+pub(crate) use git::head_commit_time;
+pub(crate) use git::{configure_git, configure_package_git, placeholder_git};
 #[cfg(feature = "git")]
 pub use git::{Git, SemverKind, ShaKind};
-pub(crate) use rustc::configure_rustc;
+#[cfg(feature = "hg")]
+pub use hg::Hg;
+pub(crate) use hg::{configure_hg, placeholder_hg};
 #[cfg(feature = "rustc")]
 pub use rustc::Rustc;
-pub(crate) use si::configure_sysinfo;
+pub(crate) use rustc::{configure_rustc, placeholder_rustc};
 #[cfg(feature = "si")]
 pub use si::Sysinfo;
+pub(crate) use si::{configure_sysinfo, placeholder_sysinfo};
 
 #[cfg(any(
     feature = "build",
@@ -45,18 +56,63 @@ pub use si::Sysinfo;
     feature = "git",
     feature = "rustc",
     feature = "si",
+    feature = "hg",
 ))]
-pub(crate) fn add_entry(
-    map: &mut BTreeMap<VergenKey, Option<String>>,
+pub(crate) fn add_entry(config: &mut Config, key: VergenKey, value: Option<String>) {
+    config.add_value(key, value.map(trim_value));
+}
+
+/// Add the value, or record why the instruction was skipped if there isn't one
+#[cfg(any(
+    feature = "build",
+    feature = "cargo",
+    feature = "git",
+    feature = "rustc",
+    feature = "si",
+    feature = "hg",
+))]
+pub(crate) fn add_entry_or_skip(
+    config: &mut Config,
     key: VergenKey,
     value: Option<String>,
+    reason: &str,
 ) {
-    *map.entry(key).or_insert_with(Option::default) = value;
+    if value.is_none() {
+        config.skip(key, reason);
+    }
+    add_entry(config, key, value);
+}
+
+#[cfg(any(
+    feature = "build",
+    feature = "cargo",
+    feature = "git",
+    feature = "rustc",
+    feature = "si",
+    feature = "hg",
+))]
+pub(crate) fn add_placeholders(config: &mut Config, keys: &[(bool, VergenKey)]) {
+    for (_, key) in keys.iter().filter(|(enabled, _)| *enabled) {
+        add_entry(config, *key, Some(PLACEHOLDER.to_string()));
+    }
+}
+
+/// Skip every enabled instruction of a feature the build host can't gather, i.e. git on wasi
+#[cfg(any(feature = "git", feature = "rustc", feature = "si"))]
+pub(crate) fn skip_all(config: &mut Config, keys: &[(bool, VergenKey)], reason: &str) {
+    for (_, key) in keys.iter().filter(|(enabled, _)| *enabled) {
+        add_entry_or_skip(config, *key, None, reason);
+    }
 }
 
 /// The timezone kind to use with date information
 #[cfg(any(feature = "git", feature = "build"))]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum TimeZone {
     /// UTC
     Utc,
@@ -67,6 +123,11 @@ pub enum TimeZone {
 /// The timestamp kind to output
 #[cfg(any(feature = "git", feature = "build"))]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum TimestampKind {
     /// Output the date only
     DateOnly,
@@ -80,6 +141,21 @@ pub enum TimestampKind {
     All,
 }
 
+#[cfg(any(feature = "git", feature = "build"))]
+impl TimestampKind {
+    pub(crate) fn has_date(self) -> bool {
+        matches!(self, Self::DateOnly | Self::DateAndTime | Self::All)
+    }
+
+    pub(crate) fn has_time(self) -> bool {
+        matches!(self, Self::TimeOnly | Self::DateAndTime | Self::All)
+    }
+
+    pub(crate) fn has_timestamp(self) -> bool {
+        matches!(self, Self::Timestamp | Self::All)
+    }
+}
+
 #[cfg(all(
     test,
     any(
@@ -88,17 +164,77 @@ pub enum TimestampKind {
         feature = "git",
         feature = "rustc",
         feature = "si",
+        feature = "hg",
     )
 ))]
 mod test {
-    use super::add_entry;
-    use crate::config::VergenKey;
-    use std::collections::BTreeMap;
+    use super::{add_entry, add_placeholders};
+    use crate::{
+        config::{Config, DuplicatePolicy, VergenKey},
+        constants::PLACEHOLDER,
+    };
 
     #[test]
     fn check_add_entry() {
-        let mut hm = BTreeMap::new();
-        add_entry(&mut hm, VergenKey::BuildTimestamp, Some("".to_string()));
-        assert!(hm.get(&VergenKey::BuildTimestamp).is_some());
+        let mut config = Config::default();
+        add_entry(&mut config, VergenKey::BuildTimestamp, Some("".to_string()));
+        assert!(config.cfg_map()[&VergenKey::BuildTimestamp].is_some());
+
+        add_entry(&mut config, VergenKey::Sha, Some("abc123\n".to_string()));
+        add_entry(
+            &mut config,
+            VergenKey::RustcSemver,
+            Some(" 1.52.0\r\n".to_string()),
+        );
+        assert_eq!(
+            config.cfg_map()[&VergenKey::Sha],
+            Some("abc123".to_string())
+        );
+        assert_eq!(
+            config.cfg_map()[&VergenKey::RustcSemver],
+            Some("1.52.0".to_string())
+        );
+    }
+
+    #[test]
+    fn duplicate_entries() {
+        let twice = |policy| {
+            let mut config = Config::with_duplicate_policy(policy);
+            add_entry(&mut config, VergenKey::Sha, Some("first".to_string()));
+            add_entry(&mut config, VergenKey::Sha, Some("second".to_string()));
+            config
+        };
+
+        let config = twice(DuplicatePolicy::Overwrite);
+        assert_eq!(
+            config.cfg_map()[&VergenKey::Sha],
+            Some("second".to_string())
+        );
+        assert!(config.duplicates().is_empty());
+
+        let config = twice(DuplicatePolicy::KeepFirst);
+        assert_eq!(config.cfg_map()[&VergenKey::Sha], Some("first".to_string()));
+        assert!(config.duplicates().is_empty());
+
+        let config = twice(DuplicatePolicy::Error);
+        assert_eq!(config.cfg_map()[&VergenKey::Sha], Some("first".to_string()));
+        assert_eq!(config.duplicates(), &[VergenKey::Sha]);
+    }
+
+    #[test]
+    fn check_add_placeholders() {
+        let mut config = Config::default();
+        add_placeholders(
+            &mut config,
+            &[
+                (true, VergenKey::BuildTimestamp),
+                (false, VergenKey::BuildSemver),
+            ],
+        );
+        assert_eq!(
+            config.cfg_map()[&VergenKey::BuildTimestamp],
+            Some(PLACEHOLDER.to_string())
+        );
+        assert_eq!(config.cfg_map()[&VergenKey::BuildSemver], None);
     }
 }
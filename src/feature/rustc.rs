@@ -12,9 +12,14 @@ use crate::config::{Config, Instructions};
 use anyhow::Result;
 #[cfg(feature = "rustc")]
 use {
-    crate::{config::VergenKey, feature::add_entry},
-    getset::{Getters, MutGetters},
-    rustc_version::{version_meta, Channel},
+    crate::{
+        config::VergenKey,
+        error::ConfigError,
+        feature::{add_entry, add_entry_or_skip, add_placeholders, skip_all},
+        merge::{pick, SetFields},
+        provider::rustc_version_meta,
+    },
+    rustc_version::Channel,
 };
 
 /// Configuration for the `VERGEN_RUSTC_*` instructions
@@ -39,6 +44,24 @@ use {
 /// * If the `sha` field is false, the `VERGEN_RUSTC_COMMIT_HASH` instruction will not be generated.
 /// * **NOTE** - The `commit_date` filed is only a date, as we are restricted to the output from `rustc_version`
 /// * **NOTE** - The `VERGEN_RUSTC_LLVM_VERSION` instruction will only be generated on the `nightly` channel, regardless of the `llvm_version` field.
+/// * **NOTE** - These describe the compiler on the host doing the build.  When cross compiling, the `VERGEN_RUSTC_HOST_TRIPLE` isn't the platform being built for, see the [`Cargo`](crate::Cargo) target instructions.
+///
+/// # Offline
+/// The information is read from the output of `rustc -vV`, by running the `rustc` cargo uses for the build.  In a hermetic
+/// build that doesn't allow running other programs, set the `version_override` field to the output captured beforehand,
+/// and `rustc` is never run.
+///
+/// * If the value starts with `rustc `, it is the `rustc -vV` output itself.
+/// * Otherwise, it is the path of a file containing the output, relative to the package root.
+/// * **NOTE** - A change to the file doesn't rerun the build script, emit a `cargo:rerun-if-changed` for it if needed.
+///
+/// ```
+/// use vergen::Config;
+///
+/// let mut config = Config::default();
+/// // Read the 'rustc -vV' output from a file, rather than running rustc
+/// *config.rustc_mut().version_override_mut() = Some("rustc-vV.txt".to_string());
+/// ```
 ///
 /// # Example
 ///
@@ -61,9 +84,17 @@ vergen(config)?;
 /// # Ok(())
 /// # }
 #[cfg(feature = "rustc")]
-#[derive(Clone, Copy, Debug, Getters, MutGetters)]
-#[getset(get = "pub(crate)", get_mut = "pub")]
+#[derive(Clone, Debug)]
 #[allow(clippy::struct_excessive_bools)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
 pub struct Rustc {
     /// Enable/Disable the rustc output
     enabled: bool,
@@ -79,6 +110,142 @@ pub struct Rustc {
     semver: bool,
     /// Enable/Disable the `VERGEN_RUSTC_COMMIT_HASH` instruction
     sha: bool,
+    /// The `rustc -vV` output, or the path of a file containing it, to use instead of running `rustc`
+    version_override: Option<String>,
+    /// The fields set through their `set_*` setter, which win in a [merge](crate::Config::merge)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    set_fields: SetFields,
+}
+
+#[cfg(feature = "rustc")]
+impl Rustc {
+    /// Enable/Disable the rustc output
+    pub(crate) fn enabled(&self) -> &bool {
+        &self.enabled
+    }
+
+    /// Enable/Disable the rustc output
+    pub fn enabled_mut(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+
+    /// Enable/Disable the rustc output
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "enabled");
+        self.enabled = enabled;
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_CHANNEL` instruction
+    pub(crate) fn channel(&self) -> &bool {
+        &self.channel
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_CHANNEL` instruction
+    pub fn channel_mut(&mut self) -> &mut bool {
+        &mut self.channel
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_CHANNEL` instruction
+    pub fn set_channel(&mut self, channel: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "channel");
+        self.channel = channel;
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_COMMIT_DATE` instruction
+    pub(crate) fn commit_date(&self) -> &bool {
+        &self.commit_date
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_COMMIT_DATE` instruction
+    pub fn commit_date_mut(&mut self) -> &mut bool {
+        &mut self.commit_date
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_COMMIT_DATE` instruction
+    pub fn set_commit_date(&mut self, commit_date: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "commit_date");
+        self.commit_date = commit_date;
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_HOST_TRIPLE` instruction
+    pub(crate) fn host_triple(&self) -> &bool {
+        &self.host_triple
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_HOST_TRIPLE` instruction
+    pub fn host_triple_mut(&mut self) -> &mut bool {
+        &mut self.host_triple
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_HOST_TRIPLE` instruction
+    pub fn set_host_triple(&mut self, host_triple: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "host_triple");
+        self.host_triple = host_triple;
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_LLVM_VERSION` instruction
+    pub(crate) fn llvm_version(&self) -> &bool {
+        &self.llvm_version
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_LLVM_VERSION` instruction
+    pub fn llvm_version_mut(&mut self) -> &mut bool {
+        &mut self.llvm_version
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_LLVM_VERSION` instruction
+    pub fn set_llvm_version(&mut self, llvm_version: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "llvm_version");
+        self.llvm_version = llvm_version;
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_SEMVER` instruction
+    pub(crate) fn semver(&self) -> &bool {
+        &self.semver
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_SEMVER` instruction
+    pub fn semver_mut(&mut self) -> &mut bool {
+        &mut self.semver
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_SEMVER` instruction
+    pub fn set_semver(&mut self, semver: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "semver");
+        self.semver = semver;
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_COMMIT_HASH` instruction
+    pub(crate) fn sha(&self) -> &bool {
+        &self.sha
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_COMMIT_HASH` instruction
+    pub fn sha_mut(&mut self) -> &mut bool {
+        &mut self.sha
+    }
+
+    /// Enable/Disable the `VERGEN_RUSTC_COMMIT_HASH` instruction
+    pub fn set_sha(&mut self, sha: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "sha");
+        self.sha = sha;
+    }
+
+    /// The `rustc -vV` output, or the path of a file containing it, to use instead of running `rustc`
+    pub(crate) fn version_override(&self) -> &Option<String> {
+        &self.version_override
+    }
+
+    /// The `rustc -vV` output, or the path of a file containing it, to use instead of running `rustc`
+    pub fn version_override_mut(&mut self) -> &mut Option<String> {
+        &mut self.version_override
+    }
+
+    /// The `rustc -vV` output, or the path of a file containing it, to use instead of running `rustc`
+    pub fn set_version_override(&mut self, version_override: Option<String>) {
+        self.set_fields.mark(MERGED_FIELDS, "version_override");
+        self.version_override = version_override;
+    }
 }
 
 #[cfg(feature = "rustc")]
@@ -92,13 +259,75 @@ impl Default for Rustc {
             llvm_version: true,
             semver: true,
             sha: true,
+            version_override: None,
+            set_fields: SetFields::default(),
         }
     }
 }
 
+/// The [`Rustc`] fields that win in a [merge](crate::Config::merge) once set, see [`SetFields`]
+#[cfg(feature = "rustc")]
+const MERGED_FIELDS: &[&str] = &[
+    "enabled",
+    "channel",
+    "commit_date",
+    "host_triple",
+    "llvm_version",
+    "semver",
+    "sha",
+    "version_override",
+];
+
 #[cfg(feature = "rustc")]
 impl Rustc {
-    pub(crate) fn has_enabled(self) -> bool {
+    pub(crate) fn merge(self, overlay: Self) -> Self {
+        let default = Self::default();
+        let overlay_set = overlay.set_fields;
+        let set = |name| overlay_set.contains(MERGED_FIELDS, name);
+        Self {
+            enabled: pick(
+                self.enabled,
+                overlay.enabled,
+                &default.enabled,
+                set("enabled"),
+            ),
+            channel: pick(
+                self.channel,
+                overlay.channel,
+                &default.channel,
+                set("channel"),
+            ),
+            commit_date: pick(
+                self.commit_date,
+                overlay.commit_date,
+                &default.commit_date,
+                set("commit_date"),
+            ),
+            host_triple: pick(
+                self.host_triple,
+                overlay.host_triple,
+                &default.host_triple,
+                set("host_triple"),
+            ),
+            llvm_version: pick(
+                self.llvm_version,
+                overlay.llvm_version,
+                &default.llvm_version,
+                set("llvm_version"),
+            ),
+            semver: pick(self.semver, overlay.semver, &default.semver, set("semver")),
+            sha: pick(self.sha, overlay.sha, &default.sha, set("sha")),
+            version_override: pick(
+                self.version_override,
+                overlay.version_override,
+                &default.version_override,
+                set("version_override"),
+            ),
+            set_fields: self.set_fields.union(overlay_set),
+        }
+    }
+
+    pub(crate) fn has_enabled(&self) -> bool {
         self.enabled
             && (self.channel
                 || self.commit_date
@@ -106,17 +335,30 @@ impl Rustc {
                 || self.llvm_version
                 || self.sha)
     }
+
+    pub(crate) fn validate(&self, errors: &mut Vec<ConfigError>) {
+        if self.version_override.as_deref().map(str::trim) == Some("") {
+            errors.push(ConfigError::new(
+                "rustc.version_override",
+                "Some(\"\")",
+                "use the 'rustc -vV' output or the path of a file containing it, or None to run rustc",
+            ));
+        }
+    }
 }
 
 #[cfg(feature = "rustc")]
 pub(crate) fn configure_rustc(instructions: &Instructions, config: &mut Config) -> Result<()> {
     let rustc_config = instructions.rustc();
     if rustc_config.has_enabled() {
-        let rustc = version_meta()?;
+        if cfg!(target_os = "wasi") && skip_without_override(instructions, config) {
+            return Ok(());
+        }
+        let rustc = rustc_version_meta(instructions)?;
 
         if *rustc_config.channel() {
             add_entry(
-                config.cfg_map_mut(),
+                config,
                 VergenKey::RustcChannel,
                 Some(
                     match rustc.channel {
@@ -131,16 +373,12 @@ pub(crate) fn configure_rustc(instructions: &Instructions, config: &mut Config)
         }
 
         if *rustc_config.host_triple() {
-            add_entry(
-                config.cfg_map_mut(),
-                VergenKey::RustcHostTriple,
-                Some(rustc.host),
-            );
+            add_entry(config, VergenKey::RustcHostTriple, Some(rustc.host));
         }
 
         if *rustc_config.semver() {
             add_entry(
-                config.cfg_map_mut(),
+                config,
                 VergenKey::RustcSemver,
                 Some(format!("{}", rustc.semver)),
             );
@@ -148,7 +386,7 @@ pub(crate) fn configure_rustc(instructions: &Instructions, config: &mut Config)
 
         if *rustc_config.sha() {
             add_entry(
-                config.cfg_map_mut(),
+                config,
                 VergenKey::RustcCommitHash,
                 Some(rustc.commit_hash.unwrap_or_else(|| "unknown".to_string())),
             );
@@ -156,33 +394,83 @@ pub(crate) fn configure_rustc(instructions: &Instructions, config: &mut Config)
 
         if *rustc_config.commit_date() {
             add_entry(
-                config.cfg_map_mut(),
+                config,
                 VergenKey::RustcCommitDate,
                 Some(rustc.commit_date.unwrap_or_else(|| "unknown".to_string())),
             );
         }
 
         if *rustc_config.llvm_version() {
-            if let Some(llvmver) = rustc.llvm_version {
-                add_entry(
-                    config.cfg_map_mut(),
-                    VergenKey::RustcLlvmVersion,
-                    Some(format!("{}", llvmver)),
-                );
-            }
+            add_entry_or_skip(
+                config,
+                VergenKey::RustcLlvmVersion,
+                rustc.llvm_version.map(|llvmver| format!("{}", llvmver)),
+                "rustc didn't report an LLVM version",
+            );
         }
     }
     Ok(())
 }
 
+/// wasi can't run `rustc`, so there the enabled instructions are skipped unless the `version_override` is set
+#[cfg(feature = "rustc")]
+fn skip_without_override(instructions: &Instructions, config: &mut Config) -> bool {
+    #[cfg(any(test, feature = "test-util"))]
+    {
+        if instructions.providers().rustc().is_some() {
+            return false;
+        }
+    }
+    let rustc_config = instructions.rustc();
+    if rustc_config.version_override().is_some() {
+        return false;
+    }
+    skip_all(
+        config,
+        &rustc_keys(rustc_config),
+        "rustc can't be run on wasi, set rustc.version_override",
+    );
+    true
+}
+
 #[cfg(not(feature = "rustc"))]
 pub(crate) fn configure_rustc(_instructions: &Instructions, _config: &mut Config) -> Result<()> {
     Ok(())
 }
 
+#[cfg(feature = "rustc")]
+pub(crate) fn placeholder_rustc(instructions: &Instructions, config: &mut Config) {
+    let rustc_config = instructions.rustc();
+
+    if rustc_config.has_enabled() {
+        add_placeholders(config, &rustc_keys(rustc_config));
+    }
+}
+
+/// Whether each instruction is enabled
+#[cfg(feature = "rustc")]
+fn rustc_keys(rustc_config: &Rustc) -> [(bool, VergenKey); 6] {
+    [
+        (rustc_config.channel, VergenKey::RustcChannel),
+        (rustc_config.host_triple, VergenKey::RustcHostTriple),
+        (rustc_config.semver, VergenKey::RustcSemver),
+        (rustc_config.sha, VergenKey::RustcCommitHash),
+        (rustc_config.commit_date, VergenKey::RustcCommitDate),
+        (rustc_config.llvm_version, VergenKey::RustcLlvmVersion),
+    ]
+}
+
+#[cfg(not(feature = "rustc"))]
+pub(crate) fn placeholder_rustc(_instructions: &Instructions, _config: &mut Config) {}
+
 #[cfg(all(test, feature = "rustc"))]
 mod test {
-    use crate::config::Instructions;
+    use super::{configure_rustc, skip_without_override};
+    use crate::{
+        config::{Config, Instructions, VergenKey},
+        provider::FakeRustc,
+    };
+    use std::{env, fs, sync::Arc};
 
     #[test]
     fn rustc_config() {
@@ -247,6 +535,112 @@ mod test {
         *config.rustc_mut().sha_mut() = false;
         assert!(!config.rustc().has_enabled());
     }
+
+    #[test]
+    fn fake_rustc() {
+        let mut instructions = Instructions::default();
+        *instructions.providers_mut().rustc_mut() = Some(Arc::new(FakeRustc::default()));
+        let mut config = Config::default();
+        configure_rustc(&instructions, &mut config).unwrap();
+        let get = |key| config.cfg_map()[&key].clone().unwrap();
+        assert_eq!(get(VergenKey::RustcChannel), "stable");
+        assert_eq!(get(VergenKey::RustcHostTriple), "x86_64-unknown-linux-gnu");
+        assert_eq!(get(VergenKey::RustcSemver), "1.57.0");
+        assert_eq!(
+            get(VergenKey::RustcCommitHash),
+            "f1edd0429582dd29cccacaf50fd134b05593bd9c"
+        );
+        assert_eq!(get(VergenKey::RustcCommitDate), "2021-11-29");
+        assert_eq!(get(VergenKey::RustcLlvmVersion), "13.0");
+    }
+
+    #[test]
+    fn skipped_llvm_version() {
+        let output = FakeRustc::default().0.replace("\nLLVM version: 13.0.0", "");
+        let mut instructions = Instructions::default();
+        *instructions.providers_mut().rustc_mut() = Some(Arc::new(FakeRustc(output)));
+        let mut config = Config::default();
+        configure_rustc(&instructions, &mut config).unwrap();
+        assert_eq!(config.cfg_map()[&VergenKey::RustcLlvmVersion], None);
+
+        // Only the rustc feature ran, the other features' instructions are skipped too
+        instructions.warn_skipped(&mut config);
+        let warnings: Vec<&String> = config
+            .warnings()
+            .iter()
+            .filter(|warning| warning.contains("VERGEN_RUSTC_"))
+            .collect();
+        assert_eq!(
+            warnings,
+            &["vergen: skipped VERGEN_RUSTC_LLVM_VERSION because rustc didn't report an LLVM version"]
+        );
+    }
+
+    #[test]
+    fn skipped_without_override() {
+        let mut instructions = Instructions::default();
+        *instructions.rustc_mut().llvm_version_mut() = false;
+        let mut config = Config::default();
+        assert!(skip_without_override(&instructions, &mut config));
+        assert_eq!(config.cfg_map()[&VergenKey::RustcSemver], None);
+
+        instructions.warn_skipped(&mut config);
+        let warnings: Vec<&String> = config
+            .warnings()
+            .iter()
+            .filter(|warning| warning.contains("VERGEN_RUSTC_"))
+            .collect();
+        assert_eq!(warnings.len(), 5);
+        assert!(warnings.contains(
+            &&"vergen: skipped VERGEN_RUSTC_SEMVER because rustc can't be run on wasi, set rustc.version_override"
+                .to_string()
+        ));
+
+        let mut config = Config::default();
+        *instructions.rustc_mut().version_override_mut() = Some(FakeRustc::default().0);
+        assert!(!skip_without_override(&instructions, &mut config));
+
+        *instructions.rustc_mut().version_override_mut() = None;
+        *instructions.providers_mut().rustc_mut() = Some(Arc::new(FakeRustc::default()));
+        assert!(!skip_without_override(&instructions, &mut config));
+    }
+
+    #[test]
+    fn version_override() {
+        let output = FakeRustc::default().0;
+        let path = env::temp_dir().join("vergen_rustc_vv.txt");
+        fs::write(&path, &output).unwrap();
+
+        for version in &[output, path.display().to_string()] {
+            let mut instructions = Instructions::default();
+            *instructions.rustc_mut().version_override_mut() = Some(version.clone());
+            let mut config = Config::default();
+            configure_rustc(&instructions, &mut config).unwrap();
+            assert_eq!(
+                config.cfg_map()[&VergenKey::RustcSemver],
+                Some("1.57.0".to_string())
+            );
+            assert_eq!(
+                config.cfg_map()[&VergenKey::RustcHostTriple],
+                Some("x86_64-unknown-linux-gnu".to_string())
+            );
+        }
+        fs::remove_file(&path).unwrap();
+
+        let mut instructions = Instructions::default();
+        *instructions.rustc_mut().version_override_mut() = Some(path.display().to_string());
+        let error = configure_rustc(&instructions, &mut Config::default()).unwrap_err();
+        assert!(error.to_string().contains("vergen_rustc_vv.txt"));
+    }
+
+    #[test]
+    fn empty_version_override() {
+        let mut instructions = Instructions::default();
+        *instructions.rustc_mut().version_override_mut() = Some(" ".to_string());
+        let errors = instructions.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field(), "rustc.version_override");
+    }
 }
 
 #[cfg(all(test, not(feature = "rustc")))]
@@ -9,17 +9,24 @@
 //! `vergen` sysinfo feature implementation
 
 use crate::config::{Config, Instructions};
+#[cfg(all(feature = "si", target_os = "wasi"))]
+use crate::feature::skip_all;
+#[cfg(feature = "si")]
+use crate::{
+    config::VergenKey,
+    feature::add_placeholders,
+    merge::{pick, SetFields},
+};
 use anyhow::Result;
-#[cfg(all(feature = "si", not(target_os = "macos")))]
+#[cfg(all(feature = "si", not(target_os = "macos"), not(target_os = "wasi")))]
 use {
     crate::error::Error::Pid,
     sysinfo::{get_current_pid, Process, User, UserExt},
 };
-#[cfg(feature = "si")]
+#[cfg(all(feature = "si", not(target_os = "wasi")))]
 use {
-    crate::{config::VergenKey, feature::add_entry},
-    getset::{Getters, MutGetters},
-    sysinfo::{ProcessorExt, System, SystemExt},
+    crate::feature::{add_entry, add_entry_or_skip},
+    sysinfo::{ProcessorExt, RefreshKind, System, SystemExt},
 };
 
 /// Configuration for the `VERGEN_SYSINFO_*` instructions
@@ -42,6 +49,7 @@ use {
 /// * If the `memory` field is false, the `VERGEN_SYSINFO_TOTAL_MEMORY` instruction will not be generated.
 /// * If the `cpu_vendor` field is false, the `VERGEN_SYSINFO_CPU_VENDOR` instruction will not be generated.
 /// * If the `cpu_core_count` field is false, the `VERGEN_SYSINFO_CPU_CORE_COUNT` instruction will not be generated.
+/// * **NOTE** - These describe the host doing the build, not the platform being built for.
 ///
 /// # Example
 ///
@@ -64,9 +72,17 @@ vergen(config)?;
 /// # Ok(())
 /// # }
 #[cfg(feature = "si")]
-#[derive(Clone, Copy, Debug, Getters, MutGetters)]
-#[getset(get = "pub(crate)", get_mut = "pub")]
+#[derive(Clone, Copy, Debug)]
 #[allow(clippy::struct_excessive_bools)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
 pub struct Sysinfo {
     /// Enable/Disable the sysinfo output
     enabled: bool,
@@ -88,6 +104,175 @@ pub struct Sysinfo {
     cpu_brand: bool,
     /// Enable/Disable the `VERGEN_SYSINFO_CPU_FREQUENCY` instruction
     cpu_frequency: bool,
+    /// The fields set through their `set_*` setter, which win in a [merge](crate::Config::merge)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    set_fields: SetFields,
+}
+
+#[cfg(feature = "si")]
+// On wasi, the instructions are skipped without reading the fields
+#[cfg_attr(target_os = "wasi", allow(dead_code))]
+impl Sysinfo {
+    /// Enable/Disable the sysinfo output
+    #[cfg(test)]
+    pub(crate) fn enabled(&self) -> &bool {
+        &self.enabled
+    }
+
+    /// Enable/Disable the sysinfo output
+    pub fn enabled_mut(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+
+    /// Enable/Disable the sysinfo output
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "enabled");
+        self.enabled = enabled;
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_NAME` instruction
+    pub(crate) fn name(&self) -> &bool {
+        &self.name
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_NAME` instruction
+    pub fn name_mut(&mut self) -> &mut bool {
+        &mut self.name
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_NAME` instruction
+    pub fn set_name(&mut self, name: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "name");
+        self.name = name;
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_OS_VERSION` instruction
+    pub(crate) fn os_version(&self) -> &bool {
+        &self.os_version
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_OS_VERSION` instruction
+    pub fn os_version_mut(&mut self) -> &mut bool {
+        &mut self.os_version
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_OS_VERSION` instruction
+    pub fn set_os_version(&mut self, os_version: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "os_version");
+        self.os_version = os_version;
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_USER` instruction
+    pub(crate) fn user(&self) -> &bool {
+        &self.user
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_USER` instruction
+    pub fn user_mut(&mut self) -> &mut bool {
+        &mut self.user
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_USER` instruction
+    pub fn set_user(&mut self, user: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "user");
+        self.user = user;
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_TOTAL_MEMORY` instruction
+    pub(crate) fn memory(&self) -> &bool {
+        &self.memory
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_TOTAL_MEMORY` instruction
+    pub fn memory_mut(&mut self) -> &mut bool {
+        &mut self.memory
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_TOTAL_MEMORY` instruction
+    pub fn set_memory(&mut self, memory: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "memory");
+        self.memory = memory;
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_CPU_VENDOR` instruction
+    pub(crate) fn cpu_vendor(&self) -> &bool {
+        &self.cpu_vendor
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_CPU_VENDOR` instruction
+    pub fn cpu_vendor_mut(&mut self) -> &mut bool {
+        &mut self.cpu_vendor
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_CPU_VENDOR` instruction
+    pub fn set_cpu_vendor(&mut self, cpu_vendor: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "cpu_vendor");
+        self.cpu_vendor = cpu_vendor;
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_CPU_CORE_COUNT` instruction
+    pub(crate) fn cpu_core_count(&self) -> &bool {
+        &self.cpu_core_count
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_CPU_CORE_COUNT` instruction
+    pub fn cpu_core_count_mut(&mut self) -> &mut bool {
+        &mut self.cpu_core_count
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_CPU_CORE_COUNT` instruction
+    pub fn set_cpu_core_count(&mut self, cpu_core_count: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "cpu_core_count");
+        self.cpu_core_count = cpu_core_count;
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_CPU_NAME` instruction
+    pub(crate) fn cpu_name(&self) -> &bool {
+        &self.cpu_name
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_CPU_NAME` instruction
+    pub fn cpu_name_mut(&mut self) -> &mut bool {
+        &mut self.cpu_name
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_CPU_NAME` instruction
+    pub fn set_cpu_name(&mut self, cpu_name: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "cpu_name");
+        self.cpu_name = cpu_name;
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_CPU_BRAND` instruction
+    pub(crate) fn cpu_brand(&self) -> &bool {
+        &self.cpu_brand
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_CPU_BRAND` instruction
+    pub fn cpu_brand_mut(&mut self) -> &mut bool {
+        &mut self.cpu_brand
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_CPU_BRAND` instruction
+    pub fn set_cpu_brand(&mut self, cpu_brand: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "cpu_brand");
+        self.cpu_brand = cpu_brand;
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_CPU_FREQUENCY` instruction
+    pub(crate) fn cpu_frequency(&self) -> &bool {
+        &self.cpu_frequency
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_CPU_FREQUENCY` instruction
+    pub fn cpu_frequency_mut(&mut self) -> &mut bool {
+        &mut self.cpu_frequency
+    }
+
+    /// Enable/Disable the `VERGEN_SYSINFO_CPU_FREQUENCY` instruction
+    pub fn set_cpu_frequency(&mut self, cpu_frequency: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "cpu_frequency");
+        self.cpu_frequency = cpu_frequency;
+    }
 }
 
 #[cfg(feature = "si")]
@@ -104,12 +289,82 @@ impl Default for Sysinfo {
             cpu_name: true,
             cpu_brand: true,
             cpu_frequency: true,
+            set_fields: SetFields::default(),
         }
     }
 }
 
+/// The [`Sysinfo`] fields that win in a [merge](crate::Config::merge) once set, see [`SetFields`]
+#[cfg(feature = "si")]
+const MERGED_FIELDS: &[&str] = &[
+    "enabled",
+    "name",
+    "os_version",
+    "user",
+    "memory",
+    "cpu_vendor",
+    "cpu_core_count",
+    "cpu_name",
+    "cpu_brand",
+    "cpu_frequency",
+];
+
 #[cfg(feature = "si")]
 impl Sysinfo {
+    pub(crate) fn merge(self, overlay: Self) -> Self {
+        let default = Self::default();
+        let overlay_set = overlay.set_fields;
+        let set = |name| overlay_set.contains(MERGED_FIELDS, name);
+        Self {
+            enabled: pick(
+                self.enabled,
+                overlay.enabled,
+                &default.enabled,
+                set("enabled"),
+            ),
+            name: pick(self.name, overlay.name, &default.name, set("name")),
+            os_version: pick(
+                self.os_version,
+                overlay.os_version,
+                &default.os_version,
+                set("os_version"),
+            ),
+            user: pick(self.user, overlay.user, &default.user, set("user")),
+            memory: pick(self.memory, overlay.memory, &default.memory, set("memory")),
+            cpu_vendor: pick(
+                self.cpu_vendor,
+                overlay.cpu_vendor,
+                &default.cpu_vendor,
+                set("cpu_vendor"),
+            ),
+            cpu_core_count: pick(
+                self.cpu_core_count,
+                overlay.cpu_core_count,
+                &default.cpu_core_count,
+                set("cpu_core_count"),
+            ),
+            cpu_name: pick(
+                self.cpu_name,
+                overlay.cpu_name,
+                &default.cpu_name,
+                set("cpu_name"),
+            ),
+            cpu_brand: pick(
+                self.cpu_brand,
+                overlay.cpu_brand,
+                &default.cpu_brand,
+                set("cpu_brand"),
+            ),
+            cpu_frequency: pick(
+                self.cpu_frequency,
+                overlay.cpu_frequency,
+                &default.cpu_frequency,
+                set("cpu_frequency"),
+            ),
+            set_fields: self.set_fields.union(overlay_set),
+        }
+    }
+
     pub(crate) fn has_enabled(self) -> bool {
         self.enabled
             && (self.name
@@ -124,56 +379,77 @@ impl Sysinfo {
     }
 }
 
-#[cfg(all(feature = "si", not(target_os = "macos")))]
-fn setup_system() -> System {
-    let mut system = System::new_all();
-    system.refresh_all();
-    system
+/// Only the parts of the system backing an enabled instruction are refreshed, so the disks, networks, and
+/// components are never probed
+#[cfg(all(feature = "si", not(target_os = "wasi")))]
+fn refresh_kind(sysinfo_config: Sysinfo) -> RefreshKind {
+    let mut refresh_kind = RefreshKind::new();
+    if sysinfo_config.memory {
+        refresh_kind = refresh_kind.with_memory();
+    }
+    if sysinfo_config.cpu_vendor
+        || sysinfo_config.cpu_core_count
+        || sysinfo_config.cpu_name
+        || sysinfo_config.cpu_brand
+        || sysinfo_config.cpu_frequency
+    {
+        refresh_kind = refresh_kind.with_cpu();
+    }
+    // The user isn't available on macOS, so there's nothing to refresh for it
+    if sysinfo_config.user && cfg!(not(target_os = "macos")) {
+        refresh_kind = refresh_kind.with_processes().with_users_list();
+    }
+    refresh_kind
 }
 
-#[cfg(all(feature = "si", target_os = "macos"))]
-fn setup_system() -> System {
-    let mut system = System::new();
-    system.refresh_memory();
-    system.refresh_cpu();
-    system
+#[cfg(all(feature = "si", not(target_os = "wasi")))]
+fn setup_system(sysinfo_config: Sysinfo) -> System {
+    System::new_with_specifics(refresh_kind(sysinfo_config))
 }
 
-#[cfg(feature = "si")]
+#[cfg(all(feature = "si", not(target_os = "wasi")))]
 #[allow(clippy::unnecessary_wraps, clippy::too_many_lines)]
 pub(crate) fn configure_sysinfo(instructions: &Instructions, config: &mut Config) -> Result<()> {
     let sysinfo_config = instructions.sysinfo();
     if sysinfo_config.has_enabled() {
-        let system = setup_system();
+        let system = setup_system(*sysinfo_config);
 
         if *sysinfo_config.name() {
-            add_entry(config.cfg_map_mut(), VergenKey::SysinfoName, system.name());
+            add_entry_or_skip(
+                config,
+                VergenKey::SysinfoName,
+                system.name(),
+                "sysinfo didn't report the OS name",
+            );
         }
 
         if *sysinfo_config.os_version() {
-            add_entry(
-                config.cfg_map_mut(),
+            add_entry_or_skip(
+                config,
                 VergenKey::SysinfoOsVersion,
                 system.long_os_version(),
+                "sysinfo didn't report the OS version",
             );
         }
 
         if *sysinfo_config.user() {
             cfg_if::cfg_if! {
                 if #[cfg(target_os = "macos")] {
+                    config.skip(VergenKey::SysinfoUser, "the user isn't available on macOS");
                 } else {
                     let pid = get_current_pid().map_err(|e| Pid { msg: e })?;
                     if let Some(process) = system.process(pid) {
                         for user in system.users() {
                             if check_user(process, user) {
-                                add_entry(
-                                    config.cfg_map_mut(),
-                                    VergenKey::SysinfoUser,
+                                add_entry(config, VergenKey::SysinfoUser,
                                     Some(user.name().to_string()),
                                 );
                             }
                         }
                     }
+                    if config.cfg_map()[&VergenKey::SysinfoUser].is_none() {
+                        config.skip(VergenKey::SysinfoUser, "the user running the build wasn't found");
+                    }
                 }
             }
         }
@@ -188,34 +464,36 @@ pub(crate) fn configure_sysinfo(instructions: &Instructions, config: &mut Config
             }
 
             add_entry(
-                config.cfg_map_mut(),
+                config,
                 VergenKey::SysinfoMemory,
                 Some(format!("{} {}", curr_memory, suffix(count))),
             );
         }
 
         if *sysinfo_config.cpu_vendor() {
-            add_entry(
-                config.cfg_map_mut(),
+            add_entry_or_skip(
+                config,
                 VergenKey::SysinfoCpuVendor,
                 system
                     .processors()
                     .get(0)
                     .map(|processor| processor.vendor_id().to_string()),
+                "sysinfo didn't report a processor",
             );
         }
 
         if *sysinfo_config.cpu_core_count() {
-            add_entry(
-                config.cfg_map_mut(),
+            add_entry_or_skip(
+                config,
                 VergenKey::SysinfoCpuCoreCount,
                 system.physical_core_count().map(|x| x.to_string()),
+                "sysinfo didn't report the physical core count",
             );
         }
 
         if *sysinfo_config.cpu_name() {
             add_entry(
-                config.cfg_map_mut(),
+                config,
                 VergenKey::SysinfoCpuName,
                 Some(
                     system
@@ -229,24 +507,26 @@ pub(crate) fn configure_sysinfo(instructions: &Instructions, config: &mut Config
         }
 
         if *sysinfo_config.cpu_brand() {
-            add_entry(
-                config.cfg_map_mut(),
+            add_entry_or_skip(
+                config,
                 VergenKey::SysinfoCpuBrand,
                 system
                     .processors()
                     .get(0)
                     .map(|processor| processor.brand().to_string()),
+                "sysinfo didn't report a processor",
             );
         }
 
         if *sysinfo_config.cpu_frequency() {
-            add_entry(
-                config.cfg_map_mut(),
+            add_entry_or_skip(
+                config,
                 VergenKey::SysinfoCpuFrequency,
                 system
                     .processors()
                     .get(0)
                     .map(|processor| processor.frequency().to_string()),
+                "sysinfo didn't report a processor",
             );
         }
     }
@@ -254,13 +534,64 @@ pub(crate) fn configure_sysinfo(instructions: &Instructions, config: &mut Config
     Ok(())
 }
 
+/// The `sysinfo` crate doesn't support wasi, so the enabled instructions are skipped
+#[cfg(all(feature = "si", target_os = "wasi"))]
+#[allow(clippy::unnecessary_wraps)]
+pub(crate) fn configure_sysinfo(instructions: &Instructions, config: &mut Config) -> Result<()> {
+    let sysinfo_config = instructions.sysinfo();
+    if sysinfo_config.has_enabled() {
+        skip_all(
+            config,
+            &sysinfo_keys(*sysinfo_config),
+            "the system information isn't available on wasi",
+        );
+    }
+    Ok(())
+}
+
 #[cfg(not(feature = "si"))]
 #[allow(clippy::unnecessary_wraps)]
 pub(crate) fn configure_sysinfo(_instructions: &Instructions, _config: &mut Config) -> Result<()> {
     Ok(())
 }
 
-#[cfg(all(feature = "si", not(target_os = "windows"), not(target_os = "macos")))]
+#[cfg(feature = "si")]
+pub(crate) fn placeholder_sysinfo(instructions: &Instructions, config: &mut Config) {
+    let sysinfo_config = instructions.sysinfo();
+
+    if sysinfo_config.has_enabled() {
+        add_placeholders(config, &sysinfo_keys(*sysinfo_config));
+    }
+}
+
+/// Whether each instruction is enabled
+#[cfg(feature = "si")]
+fn sysinfo_keys(sysinfo_config: Sysinfo) -> [(bool, VergenKey); 9] {
+    [
+        (sysinfo_config.name, VergenKey::SysinfoName),
+        (sysinfo_config.os_version, VergenKey::SysinfoOsVersion),
+        (sysinfo_config.user, VergenKey::SysinfoUser),
+        (sysinfo_config.memory, VergenKey::SysinfoMemory),
+        (sysinfo_config.cpu_vendor, VergenKey::SysinfoCpuVendor),
+        (
+            sysinfo_config.cpu_core_count,
+            VergenKey::SysinfoCpuCoreCount,
+        ),
+        (sysinfo_config.cpu_name, VergenKey::SysinfoCpuName),
+        (sysinfo_config.cpu_brand, VergenKey::SysinfoCpuBrand),
+        (sysinfo_config.cpu_frequency, VergenKey::SysinfoCpuFrequency),
+    ]
+}
+
+#[cfg(not(feature = "si"))]
+pub(crate) fn placeholder_sysinfo(_instructions: &Instructions, _config: &mut Config) {}
+
+#[cfg(all(
+    feature = "si",
+    not(target_os = "windows"),
+    not(target_os = "macos"),
+    not(target_os = "wasi")
+))]
 fn check_user(process: &Process, user: &User) -> bool {
     *user.uid() == process.uid
 }
@@ -270,7 +601,7 @@ fn check_user(_process: &Process, _user: &User) -> bool {
     false
 }
 
-#[cfg(feature = "si")]
+#[cfg(all(feature = "si", not(target_os = "wasi")))]
 fn suffix(val: usize) -> &'static str {
     match val {
         0 => "KB",
@@ -280,10 +611,14 @@ fn suffix(val: usize) -> &'static str {
     }
 }
 
-#[cfg(all(test, feature = "si"))]
+#[cfg(all(test, feature = "si", not(target_os = "wasi")))]
 mod test {
-    use super::{suffix, Sysinfo};
-    use crate::config::Instructions;
+    use super::{refresh_kind, suffix, sysinfo_keys, Sysinfo};
+    use crate::{
+        config::{Config, Instructions, VergenKey},
+        feature::skip_all,
+    };
+    use sysinfo::RefreshKind;
 
     #[test]
     fn rustc_config() {
@@ -324,6 +659,62 @@ mod test {
         assert!(!sysinfo.has_enabled());
     }
 
+    #[test]
+    fn skipped_without_sysinfo() {
+        let mut instructions = Instructions::default();
+        *instructions.sysinfo_mut().user_mut() = false;
+        let mut config = Config::default();
+        skip_all(
+            &mut config,
+            &sysinfo_keys(*instructions.sysinfo()),
+            "the system information isn't available on wasi",
+        );
+        assert_eq!(config.cfg_map()[&VergenKey::SysinfoName], None);
+
+        instructions.warn_skipped(&mut config);
+        let warnings: Vec<&String> = config
+            .warnings()
+            .iter()
+            .filter(|warning| warning.contains("VERGEN_SYSINFO_"))
+            .collect();
+        assert_eq!(warnings.len(), 8);
+        assert!(warnings.contains(
+            &&"vergen: skipped VERGEN_SYSINFO_NAME because the system information isn't available on wasi"
+                .to_string()
+        ));
+    }
+
+    #[test]
+    fn refresh_only_what_is_enabled() {
+        let mut sysinfo = Sysinfo::default();
+        let kind = refresh_kind(sysinfo);
+        assert!(kind.memory());
+        assert!(kind.cpu());
+        assert_eq!(kind.processes(), cfg!(not(target_os = "macos")));
+        assert_eq!(kind.users_list(), cfg!(not(target_os = "macos")));
+        assert!(!kind.disks());
+        assert!(!kind.disks_list());
+        assert!(!kind.networks());
+        assert!(!kind.networks_list());
+        assert!(!kind.components());
+        assert!(!kind.components_list());
+
+        *sysinfo.user_mut() = false;
+        *sysinfo.cpu_vendor_mut() = false;
+        *sysinfo.cpu_core_count_mut() = false;
+        *sysinfo.cpu_name_mut() = false;
+        *sysinfo.cpu_brand_mut() = false;
+        *sysinfo.cpu_frequency_mut() = false;
+        let kind = refresh_kind(sysinfo);
+        assert!(kind.memory());
+        assert!(!kind.cpu());
+        assert!(!kind.processes());
+        assert!(!kind.users_list());
+
+        *sysinfo.memory_mut() = false;
+        assert_eq!(refresh_kind(sysinfo), RefreshKind::new());
+    }
+
     #[test]
     fn suffix_works() {
         assert_eq!("KB", suffix(0));
@@ -8,11 +8,41 @@
 
 //! `vergen` cargo instruction generation
 
-use crate::config::{Config, Instructions, VergenKey};
+#[cfg(feature = "hash")]
+use crate::hash::add_hash;
+#[cfg(feature = "licenses")]
+use crate::licenses::output_licenses;
+use crate::{
+    banner::add_banner,
+    buildinfo::{lockfile, output_build_info},
+    built::output_built_compat,
+    c_header::output_c_header,
+    collision::check_collisions,
+    condition::EmissionContext,
+    config::{Config, Instructions, VergenKey, WarningLevel},
+    constants::PLACEHOLDER,
+    emit_cfg::output_emit_cfg,
+    error::Error,
+    include::output_include,
+    links::{add_links, output_links_metadata},
+    output::{out_dir, output_file, write_table},
+    overrides::set_overrides,
+    overrides_file::apply_overrides_file,
+    package::{add_packages, suffix},
+    provenance::add_provenance,
+    repeat::{Emitted, EMITTED},
+    sanitize::sanitize,
+    sticky::apply_sticky,
+    syntax::{write_directive, CargoSyntax},
+    truncate::truncate,
+};
 use anyhow::Result;
 use std::{
+    borrow::Borrow,
+    env, fs,
     io::{self, Write},
     path::Path,
+    time::Instant,
 };
 
 /// Generate the `cargo:` instructions
@@ -23,6 +53,11 @@ use std::{
 /// * [I/O](std::io::Error) errors may be generated.
 /// * Errors may be generated from the `rustc_version` library.
 /// * [env](std::env::VarError) errors may be generated.
+/// * An error listing every [`ConfigError`](crate::ConfigError) is generated if the configuration fails [validation](crate::Config::validate).
+/// * An error is generated if two emitted instructions have the same name, ignoring case, and the `on_duplicate_key`
+///   field is [`DuplicatePolicy::Error`](crate::DuplicatePolicy::Error).
+/// * An error is generated if an earlier call in this build script already emitted one of the instructions, and the
+///   `on_duplicate_key` field is [`DuplicatePolicy::Error`](crate::DuplicatePolicy::Error).
 ///
 /// # Usage
 ///
@@ -35,11 +70,29 @@ use std::{
 /// vergen(Config::default())
 /// # }
 /// ```
+///
+/// The configuration can be passed by value or by reference.  Pass a reference to inspect the configuration before,
+/// or reuse it after, generating the instructions.
+///
+/// ```
+/// # use anyhow::Result;
+/// # use vergen::{Config, vergen};
+/// #
+/// # fn main() -> Result<()> {
+/// let config = Config::default();
+/// for (name, source) in config.describe_effective() {
+///     println!("{} from {}", name, source);
+/// }
+/// vergen(&config)
+/// # }
+/// ```
 #[cfg(not(feature = "git"))]
-pub fn vergen(config: crate::Config) -> Result<()> {
-    // This is here to help with type inference
-    let no_repo: Option<&'static str> = None;
-    config_from_instructions(config, no_repo, &mut io::stdout())
+#[allow(clippy::needless_pass_by_value)]
+pub fn vergen<T>(config: T) -> Result<()>
+where
+    T: Borrow<crate::Config>,
+{
+    EMITTED.with(|emitted| vergen_to(config.borrow(), Some(emitted), &mut io::stdout()))
 }
 
 /// Generate the `cargo:` instructions
@@ -50,6 +103,11 @@ pub fn vergen(config: crate::Config) -> Result<()> {
 /// * [I/O](std::io::Error) errors may be generated.
 /// * Errors may be generated from the `rustc_version` library.
 /// * [env](std::env::VarError) errors may be generated.
+/// * An error listing every [`ConfigError`](crate::ConfigError) is generated if the configuration fails [validation](crate::Config::validate).
+/// * An error is generated if two emitted instructions have the same name, ignoring case, and the `on_duplicate_key`
+///   field is [`DuplicatePolicy::Error`](crate::DuplicatePolicy::Error).
+/// * An error is generated if an earlier call in this build script already emitted one of the instructions, and the
+///   `on_duplicate_key` field is [`DuplicatePolicy::Error`](crate::DuplicatePolicy::Error).
 ///
 /// # Usage
 ///
@@ -62,52 +120,610 @@ pub fn vergen(config: crate::Config) -> Result<()> {
 /// vergen(Config::default())
 /// # }
 /// ```
+///
+/// The configuration can be passed by value or by reference.  Pass a reference to inspect the configuration before,
+/// or reuse it after, generating the instructions.
+///
+/// ```
+/// # use anyhow::Result;
+/// # use vergen::{Config, vergen};
+/// #
+/// # fn main() -> Result<()> {
+/// let config = Config::default();
+/// for (name, source) in config.describe_effective() {
+///     println!("{} from {}", name, source);
+/// }
+/// vergen(&config)
+/// # }
+/// ```
+#[cfg(feature = "git")]
+#[allow(clippy::needless_pass_by_value)]
+pub fn vergen<T>(config: T) -> Result<()>
+where
+    T: Borrow<crate::Config>,
+{
+    EMITTED.with(|emitted| vergen_to(config.borrow(), Some(emitted), &mut io::stdout()))
+}
+
+/// Generate the `cargo:` instructions to the given output, checked against the earlier calls if given
+#[cfg(not(feature = "git"))]
+pub(crate) fn vergen_to<T>(
+    config: &crate::Config,
+    emitted: Option<&Emitted>,
+    stdout: &mut T,
+) -> Result<()>
+where
+    T: Write,
+{
+    // This is here to help with type inference
+    let no_repo: Option<&'static str> = None;
+    config_from_instructions_emitted(
+        config,
+        env::vars(),
+        no_repo,
+        emitted,
+        stdout,
+        &mut io::stderr(),
+    )
+}
+
+/// Generate the `cargo:` instructions to the given output, checked against the earlier calls if given
 #[cfg(feature = "git")]
-pub fn vergen(config: crate::Config) -> Result<()> {
+pub(crate) fn vergen_to<T>(
+    config: &crate::Config,
+    emitted: Option<&Emitted>,
+    stdout: &mut T,
+) -> Result<()>
+where
+    T: Write,
+{
     if *config.git().enabled() {
         let base_git_dir = config.git().base_dir().clone();
-        config_from_instructions(config, base_git_dir, &mut io::stdout())
+        config_from_instructions_emitted(
+            config,
+            env::vars(),
+            base_git_dir,
+            emitted,
+            stdout,
+            &mut io::stderr(),
+        )
     } else {
         // This is here to help with type inference
         let no_repo: Option<&'static str> = None;
-        config_from_instructions(config, no_repo, &mut io::stdout())
+        config_from_instructions_emitted(
+            config,
+            env::vars(),
+            no_repo,
+            emitted,
+            stdout,
+            &mut io::stderr(),
+        )
     }
 }
 
+#[cfg(test)]
 fn config_from_instructions<T, U>(
-    instructions: Instructions,
+    instructions: &Instructions,
+    repo: Option<U>,
+    stdout: &mut T,
+) -> Result<()>
+where
+    T: Write,
+    U: AsRef<Path>,
+{
+    config_from_instructions_env(instructions, env::vars(), repo, stdout)
+}
+
+/// Check the instructions against the ones emitted by the earlier calls
+#[cfg(test)]
+fn config_from_instructions_repeat<T, U>(
+    instructions: &Instructions,
+    repo: Option<U>,
+    emitted: &Emitted,
+    stdout: &mut T,
+) -> Result<()>
+where
+    T: Write,
+    U: AsRef<Path>,
+{
+    config_from_instructions_emitted(
+        instructions,
+        env::vars(),
+        repo,
+        Some(emitted),
+        stdout,
+        &mut io::stderr(),
+    )
+}
+
+#[cfg(test)]
+fn config_from_instructions_env<T, U, V>(
+    instructions: &Instructions,
+    vars: V,
+    repo: Option<U>,
+    stdout: &mut T,
+) -> Result<()>
+where
+    T: Write,
+    U: AsRef<Path>,
+    V: IntoIterator<Item = (String, String)>,
+{
+    config_from_instructions_stderr(instructions, vars, repo, stdout, &mut io::stderr())
+}
+
+/// Generate the `cargo:` instructions to the given output, and the `print_table` table to the given error output
+#[cfg(test)]
+fn config_from_instructions_stderr<T, U, V, W>(
+    instructions: &Instructions,
+    vars: V,
+    repo: Option<U>,
+    stdout: &mut T,
+    stderr: &mut W,
+) -> Result<()>
+where
+    T: Write,
+    U: AsRef<Path>,
+    V: IntoIterator<Item = (String, String)>,
+    W: Write,
+{
+    config_from_instructions_emitted(instructions, vars, repo, None, stdout, stderr)
+}
+
+fn config_from_instructions_emitted<T, U, V, W>(
+    instructions: &Instructions,
+    vars: V,
+    repo: Option<U>,
+    emitted: Option<&Emitted>,
+    stdout: &mut T,
+    stderr: &mut W,
+) -> Result<()>
+where
+    T: Write,
+    U: AsRef<Path>,
+    V: IntoIterator<Item = (String, String)>,
+    W: Write,
+{
+    let (instructions, config, warnings) = gather(instructions, vars, repo)?;
+    output(&instructions, &config, warnings.as_deref(), emitted, stdout)?;
+    if *instructions.print_table() {
+        write_table(&config, stderr)?;
+    }
+    Ok(())
+}
+
+/// The instructions with the overrides and conditions applied, the generated values, and the override warnings if
+/// the overrides are read
+pub(crate) fn gather<U, V>(
+    instructions: &Instructions,
+    vars: V,
     repo: Option<U>,
+) -> Result<(Instructions, Config, Option<Vec<String>>)>
+where
+    U: AsRef<Path>,
+    V: IntoIterator<Item = (String, String)>,
+{
+    let start = Instant::now();
+    instructions.validate().map_err(Error::Validation)?;
+
+    let vars: Vec<(String, String)> = vars.into_iter().collect();
+    let docs_rs =
+        *instructions.docs_rs_placeholder() && vars.iter().any(|(name, _)| name == "DOCS_RS");
+    let context = EmissionContext::from_vars(&vars);
+
+    let mut instructions = instructions.clone();
+    let warnings = if *instructions.env_overrides() {
+        Some(instructions.apply_overrides(vars.iter().cloned()))
+    } else {
+        None
+    };
+    instructions.apply_conditions(&context);
+    instructions.unify_time();
+    let mut config = generate_config(&instructions, docs_rs, repo, start)?;
+    if warnings.is_some() {
+        *config.override_vars_mut() = set_overrides(&vars);
+    }
+    add_links(instructions.links_metadata(), &vars, &mut config);
+    check_collisions(&instructions, &mut config)?;
+    if *instructions.verbose() {
+        log_timings(&config, &mut io::stderr())?;
+    }
+    Ok((instructions, config, warnings))
+}
+
+/// Log how long each feature took, see the `verbose` field
+fn log_timings<T>(config: &Config, stderr: &mut T) -> Result<()>
+where
+    T: Write,
+{
+    for (feature, elapsed) in config.timings() {
+        writeln!(stderr, "vergen: the {} feature took {:?}", feature, elapsed)?;
+    }
+    Ok(())
+}
+
+fn output<T>(
+    instructions: &Instructions,
+    config: &Config,
+    override_warnings: Option<&[String]>,
+    emitted: Option<&Emitted>,
     stdout: &mut T,
 ) -> Result<()>
 where
     T: Write,
+{
+    let target = instructions.output_target();
+
+    if target.has_stdout() {
+        let syntax = instructions.cargo_syntax().resolve();
+        // Only the cargo instructions are repeated, the files are replaced
+        let deduped;
+        let emit = match emitted {
+            Some(emitted) => {
+                deduped = emitted.dedupe(*instructions.on_duplicate_key(), config)?;
+                &deduped
+            }
+            None => config,
+        };
+        output_cargo_instructions(instructions, syntax, emit, stdout)?;
+        for warning in emit.warnings() {
+            output_warning(*instructions.warnings(), syntax, false, warning, stdout)?;
+        }
+        if let Some(warnings) = override_warnings {
+            output_env_overrides(*instructions.warnings(), syntax, emit, warnings, stdout)?;
+        }
+    }
+
+    if let Some(path) = target.file() {
+        output_file(
+            config,
+            path,
+            *instructions.output_format(),
+            *instructions.rerun_sidecar(),
+        )?;
+    }
+
+    if *instructions.c_header().enabled() {
+        output_c_header(instructions.c_header(), config)?;
+    }
+
+    if *instructions.built_compat().enabled() {
+        output_built_compat(instructions.built_compat(), config)?;
+    }
+
+    if *instructions.build_info().enabled() {
+        output_build_info(
+            instructions.build_info(),
+            *instructions.source_date_epoch(),
+            config,
+        )?;
+    }
+
+    if *instructions.generate_include() {
+        output_include(config, &instructions.unavailable_required(config))?;
+    }
+    Ok(())
+}
+
+/// The placeholder values, when building on docs.rs
+fn docs_rs_config(instructions: &Instructions) -> Result<Config> {
+    let mut config = instructions.placeholder_config();
+    instructions.placeholder_commands(&mut config);
+    if instructions.banner_format().is_some() {
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Banner, Some(PLACEHOLDER.to_string()));
+    }
+    #[cfg(feature = "hash")]
+    if *instructions.hash().enabled() {
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Hash, Some(PLACEHOLDER.to_string()));
+    }
+    if *instructions.self_timing() {
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::ScriptDuration, Some(PLACEHOLDER.to_string()));
+    }
+    if *instructions.build_number() {
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::BuildNumber, Some(PLACEHOLDER.to_string()));
+    }
+    if *instructions.release_url() {
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::ReleaseUrl, Some(PLACEHOLDER.to_string()));
+    }
+    if *instructions.emit_enabled_summary() {
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::EnabledFeatures, Some(PLACEHOLDER.to_string()));
+    }
+    if *instructions.provenance() {
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::ProvenanceJson, Some(PLACEHOLDER.to_string()));
+    }
+    #[cfg(feature = "licenses")]
+    if *instructions.licenses().enabled() {
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::DependencyLicenses, Some(PLACEHOLDER.to_string()));
+    }
+    instructions.drop_excluded(&mut config);
+    if *instructions.emit_all_with_placeholders() {
+        instructions.fill_unavailable(&mut config);
+    }
+    instructions.retain_listed(&mut config);
+    add_packages(instructions, true, None, &mut config)?;
+    Ok(config)
+}
+
+fn generate_config<U>(
+    instructions: &Instructions,
+    docs_rs: bool,
+    repo: Option<U>,
+    start: Instant,
+) -> Result<Config>
+where
     U: AsRef<Path>,
 {
-    output_cargo_instructions(&instructions.config(repo)?, stdout)
+    if docs_rs {
+        docs_rs_config(instructions)
+    } else {
+        let repo_path = repo.as_ref().map(|repo| repo.as_ref().to_path_buf());
+        let mut config = instructions.config(repo)?;
+        instructions.apply_value_providers(&mut config);
+        instructions.run_commands(&mut config)?;
+        if *instructions.sticky().enabled() {
+            let out_dir = out_dir()?;
+            apply_sticky(
+                instructions.sticky(),
+                &mut config,
+                &out_dir.join("vergen_sticky.cache"),
+            )?;
+        }
+        if let Some(path) = instructions.overrides_file() {
+            apply_overrides_file(path, &mut config)?;
+        }
+        instructions.apply_value_overrides(&mut config);
+        instructions.drop_excluded(&mut config);
+        if *instructions.build_number()
+            && instructions.emits(VergenKey::BuildNumber)
+            && config.cfg_map()[&VergenKey::BuildNumber].is_none()
+        {
+            let _ = config
+                .cfg_map_mut()
+                .insert(VergenKey::BuildNumber, Some(String::new()));
+            config.warnings_mut().push(
+                "vergen: VERGEN_BUILD_NUMBER is empty, it needs the git feature enabled and a git repository"
+                    .to_string(),
+            );
+        }
+        if *instructions.release_url()
+            && instructions.emits(VergenKey::ReleaseUrl)
+            && config.cfg_map()[&VergenKey::ReleaseUrl].is_none()
+        {
+            let _ = config
+                .cfg_map_mut()
+                .insert(VergenKey::ReleaseUrl, Some(String::new()));
+            config.warnings_mut().push(
+                "vergen: VERGEN_RELEASE_URL is empty, it needs the git feature enabled and a git repository"
+                    .to_string(),
+            );
+        }
+        #[cfg(feature = "licenses")]
+        if *instructions.licenses().enabled() && instructions.emits(VergenKey::DependencyLicenses) {
+            let path = output_licenses(instructions.licenses())?;
+            let _ = config.cfg_map_mut().insert(
+                VergenKey::DependencyLicenses,
+                Some(path.display().to_string()),
+            );
+        }
+        if let (Some(format), false) = (
+            instructions.banner_format(),
+            instructions.excluded(VergenKey::Banner),
+        ) {
+            add_banner(format, &mut config);
+        }
+        if *instructions.emit_all_with_placeholders() {
+            instructions.fill_unavailable(&mut config);
+        }
+        instructions.retain_listed(&mut config);
+        instructions.warn_skipped(&mut config);
+        sanitize(*instructions.sanitize(), &mut config)?;
+        truncate(instructions.truncate(), &mut config);
+        if *instructions.emit_enabled_summary() && instructions.emits(VergenKey::EnabledFeatures) {
+            let summary = instructions.enabled_summary(&config);
+            let _ = config
+                .cfg_map_mut()
+                .insert(VergenKey::EnabledFeatures, Some(summary));
+        }
+        if *instructions.self_timing() && instructions.emits(VergenKey::ScriptDuration) {
+            let _ = config.cfg_map_mut().insert(
+                VergenKey::ScriptDuration,
+                Some(start.elapsed().as_millis().to_string()),
+            );
+        }
+        #[cfg(feature = "hash")]
+        if *instructions.hash().enabled() && instructions.emits(VergenKey::Hash) {
+            add_hash(instructions.hash(), &mut config);
+        }
+        if *instructions.provenance() && instructions.emits(VergenKey::ProvenanceJson) {
+            add_provenance(*instructions.source_date_epoch(), &mut config)?;
+        }
+        add_packages(instructions, false, repo_path.as_deref(), &mut config)?;
+        Ok(config)
+    }
 }
 
-fn output_cargo_instructions<T>(config: &Config, stdout: &mut T) -> Result<()>
+fn output_cargo_instructions<T>(
+    instructions: &Instructions,
+    syntax: CargoSyntax,
+    config: &Config,
+    stdout: &mut T,
+) -> Result<()>
 where
     T: Write,
 {
     // Generate the 'cargo:' instruction output
     for (k, v) in config.cfg_map().iter().filter_map(some_vals) {
-        writeln!(stdout, "cargo:rustc-env={}={}", k.name(), v)?;
+        write_directive(stdout, syntax, "rustc-env", format!("{}={}", k.name(), v))?;
+    }
+    for (name, value) in config.commands() {
+        write_directive(stdout, syntax, "rustc-env", format!("{}={}", name, value))?;
+    }
+
+    // Generate the values of each package, with the package suffix
+    for (package, values) in config.packages() {
+        let suffix = suffix(package);
+        for (k, v) in values.iter().filter_map(some_vals) {
+            write_directive(
+                stdout,
+                syntax,
+                "rustc-env",
+                format!("{}_{}={}", k.name(), suffix, v),
+            )?;
+        }
+    }
+
+    // Generate the 'cargo:rustc-cfg' flags for the boolean values
+    output_emit_cfg(instructions.emit_cfg(), syntax, config, stdout)?;
+
+    // Pass the requested values to the dependent crates' build scripts
+    output_links_metadata(instructions.links_metadata(), syntax, config, stdout)?;
+
+    // Embed the values in a linker section if requested
+    if let Some(section) = instructions.emit_link_section() {
+        output_link_section(*instructions.warnings(), syntax, section, config, stdout)?;
     }
 
     // Add the HEAD path to cargo:rerun-if-changed
     if let Some(head_path) = config.head_path() {
-        writeln!(stdout, "cargo:rerun-if-changed={}", head_path.display())?;
+        write_directive(stdout, syntax, "rerun-if-changed", head_path.display())?;
     }
 
     // Add the resolved ref path to cargo:rerun-if-changed
     if let Some(ref_path) = config.ref_path() {
-        writeln!(stdout, "cargo:rerun-if-changed={}", ref_path.display())?;
+        write_directive(stdout, syntax, "rerun-if-changed", ref_path.display())?;
+    }
+
+    // Add the overrides file to cargo:rerun-if-changed
+    if let Some(overrides_path) = config.overrides_path() {
+        write_directive(stdout, syntax, "rerun-if-changed", overrides_path.display())?;
+    }
+
+    // Add the Cargo.lock of the provenance, and the environment listed in the .buildinfo
+    if *instructions.build_info().enabled() || *instructions.provenance() {
+        if let Some(lockfile) = lockfile() {
+            write_directive(stdout, syntax, "rerun-if-changed", lockfile.display())?;
+        }
+    }
+    if *instructions.build_info().enabled() {
+        for name in instructions.build_info().env_names() {
+            write_directive(stdout, syntax, "rerun-if-env-changed", name)?;
+        }
     }
 
     Ok(())
 }
 
+fn output_env_overrides<T>(
+    level: WarningLevel,
+    syntax: CargoSyntax,
+    config: &Config,
+    warnings: &[String],
+    stdout: &mut T,
+) -> Result<()>
+where
+    T: Write,
+{
+    for name in config.override_vars() {
+        write_directive(stdout, syntax, "rerun-if-env-changed", name)?;
+    }
+
+    for warning in warnings {
+        output_warning(level, syntax, true, warning, stdout)?;
+    }
+
+    Ok(())
+}
+
+fn output_warning<T>(
+    level: WarningLevel,
+    syntax: CargoSyntax,
+    is_error: bool,
+    warning: &str,
+    stdout: &mut T,
+) -> Result<()>
+where
+    T: Write,
+{
+    if level.allows(is_error) {
+        write_directive(stdout, syntax, "warning", warning)?;
+    }
+    Ok(())
+}
+
+fn output_link_section<T>(
+    level: WarningLevel,
+    syntax: CargoSyntax,
+    section: &str,
+    config: &Config,
+    stdout: &mut T,
+) -> Result<()>
+where
+    T: Write,
+{
+    if link_section_supported() {
+        let out_dir = out_dir()?;
+        let script_path = out_dir.join("vergen_link_section.ld");
+        fs::write(&script_path, link_script(section, config))?;
+        write_directive(stdout, syntax, "rustc-link-arg", "-T")?;
+        write_directive(stdout, syntax, "rustc-link-arg", script_path.display())?;
+    } else {
+        let warning = format!(
+            "vergen: the '{}' link section is only supported on ELF targets, skipping",
+            section
+        );
+        output_warning(level, syntax, false, &warning, stdout)?;
+    }
+    Ok(())
+}
+
+fn link_section_supported() -> bool {
+    let family = env::var("CARGO_CFG_TARGET_FAMILY").unwrap_or_default();
+    let vendor = env::var("CARGO_CFG_TARGET_VENDOR").unwrap_or_default();
+    family.split(',').any(|f| f == "unix") && vendor != "apple"
+}
+
+fn link_script(section: &str, config: &Config) -> String {
+    let mut bytes = vec![];
+    for (k, v) in config.cfg_map().iter().filter_map(some_vals) {
+        bytes.extend_from_slice(k.name().as_bytes());
+        bytes.push(b'=');
+        bytes.extend_from_slice(v.as_bytes());
+        bytes.push(0);
+    }
+
+    let data: Vec<String> = bytes
+        .chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .map(|b| format!("BYTE(0x{:02x})", b))
+                .collect::<Vec<String>>()
+                .join(" ")
+        })
+        .collect();
+
+    format!(
+        "/* Generated by vergen */\nSECTIONS\n{{\n  {} :\n  {{\n    {}\n  }}\n}}\nINSERT AFTER .rodata;\n",
+        section,
+        data.join("\n    ")
+    )
+}
+
 fn some_vals<'a>(tuple: (&'a VergenKey, &'a Option<String>)) -> Option<(&VergenKey, &String)> {
     if tuple.1.is_some() {
         Some((tuple.0, tuple.1.as_ref().unwrap()))
@@ -118,15 +734,28 @@ fn some_vals<'a>(tuple: (&'a VergenKey, &'a Option<String>)) -> Option<(&VergenK
 
 #[cfg(test)]
 mod test {
-    use super::{config_from_instructions, vergen};
+    use super::{
+        config_from_instructions, config_from_instructions_env, config_from_instructions_stderr,
+        generate_config, log_timings, output_cargo_instructions, vergen,
+    };
     use crate::{
-        config::Instructions,
+        condition::Condition,
+        config::{Config, Instructions, VergenKey, WarningLevel},
+        output::OutputTarget,
+        parse::parse_instructions,
+        sanitize::sanitize,
+        syntax::CargoSyntax,
         testutils::{setup, teardown},
     };
     use anyhow::Result;
     use lazy_static::lazy_static;
     use regex::Regex;
-    use std::{io, path::PathBuf};
+    use std::{
+        env, fs, io,
+        path::PathBuf,
+        process::Command,
+        time::{Duration, Instant},
+    };
 
     lazy_static! {
         static ref VBD_REGEX: Regex = Regex::new(r".*VERGEN_BUILD_TIMESTAMP.*").unwrap();
@@ -180,6 +809,12 @@ mod test {
         static ref GIT_SHA_RE_STR: &'static str = r#"cargo:rustc-env=VERGEN_GIT_SHA=[0-9a-f]{40}"#;
         static ref GIT_SHA_SHORT_RE_STR: &'static str =
             r#"cargo:rustc-env=VERGEN_GIT_SHA_SHORT=[0-9a-f]{7}"#;
+        static ref GIT_TRACKED_FILES_REGEX: Regex =
+            Regex::new(r#"cargo:rustc-env=VERGEN_GIT_TRACKED_FILES=(\d+)"#).unwrap();
+        static ref GIT_COMMIT_AGE_REGEX: Regex =
+            Regex::new(r#"cargo:rustc-env=VERGEN_GIT_COMMIT_AGE_SECONDS=(\d+)"#).unwrap();
+        static ref GIT_COMMITS_SINCE_TAG_REGEX: Regex =
+            Regex::new(r#"cargo:rustc-env=VERGEN_GIT_COMMITS_SINCE_TAG=(\d+)"#).unwrap();
         static ref GIT_RIC_RE_STR: &'static str = r#"cargo:rerun-if-changed=.*\.git/HEAD"#;
         static ref GIT_RIC1_RE_STR: &'static str = r#"cargo:rerun-if-changed=.*"#;
         static ref GIT_RIC_REGEX: Regex = {
@@ -317,6 +952,20 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    #[serial_test::serial]
+    fn vergen_borrows() -> Result<()> {
+        setup();
+        let instructions = Instructions::default();
+        // Dry run, then emit, from the same configuration
+        assert!(instructions.validate().is_ok());
+        assert!(!instructions.describe_effective().is_empty());
+        assert!(vergen(&instructions).is_ok());
+        assert!(vergen(&instructions).is_ok());
+        teardown();
+        Ok(())
+    }
+
     #[test]
     #[serial_test::serial]
     #[cfg(feature = "git")]
@@ -333,7 +982,7 @@ mod test {
     fn describe_falls_back() -> Result<()> {
         let no_tags_path = PathBuf::from("testdata").join("notagsrepo");
         assert!(config_from_instructions(
-            Instructions::default(),
+            &Instructions::default(),
             Some(no_tags_path),
             &mut io::sink(),
         )
@@ -345,7 +994,7 @@ mod test {
     fn describe() -> Result<()> {
         let no_tags_path = PathBuf::from("testdata").join("tagsrepo");
         assert!(config_from_instructions(
-            Instructions::default(),
+            &Instructions::default(),
             Some(no_tags_path),
             &mut io::sink(),
         )
@@ -357,7 +1006,7 @@ mod test {
     fn detached_head() -> Result<()> {
         let dh_path = PathBuf::from("testdata").join("detachedhead");
         assert!(
-            config_from_instructions(Instructions::default(), Some(dh_path), &mut io::sink(),)
+            config_from_instructions(&Instructions::default(), Some(dh_path), &mut io::sink(),)
                 .is_ok()
         );
         Ok(())
@@ -373,7 +1022,7 @@ mod test {
         *config.build_mut().kind_mut() = TimestampKind::DateOnly;
 
         let mut stdout_buf = vec![];
-        assert!(config_from_instructions(config, Some(repo_path), &mut stdout_buf).is_ok());
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf).is_ok());
         let stdout = String::from_utf8_lossy(&stdout_buf);
         assert!(!VBD_REGEX.is_match(&stdout));
         Ok(())
@@ -385,13 +1034,14 @@ mod test {
         not(feature = "git"),
         not(feature = "rustc"),
         not(feature = "si"),
+        not(feature = "hg"),
     ))]
     #[test]
     fn no_features_no_output() {
         let repo_path = PathBuf::from(".");
         let mut stdout_buf = vec![];
         assert!(config_from_instructions(
-            Instructions::default(),
+            &Instructions::default(),
             Some(repo_path),
             &mut stdout_buf,
         )
@@ -405,7 +1055,7 @@ mod test {
         let repo_path = PathBuf::from(".");
         let mut stdout_buf = vec![];
         assert!(config_from_instructions(
-            Instructions::default(),
+            &Instructions::default(),
             Some(repo_path),
             &mut stdout_buf,
         )
@@ -420,7 +1070,7 @@ mod test {
         let mut stdout_buf = vec![];
         let mut instructions = Instructions::default();
         *instructions.build_mut().enabled_mut() = false;
-        assert!(config_from_instructions(instructions, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&instructions, Some(repo_path), &mut stdout_buf,).is_ok());
         assert!(!BUILD_REGEX_INST.is_match(&String::from_utf8_lossy(&stdout_buf)));
     }
 
@@ -432,7 +1082,7 @@ mod test {
         let repo_path = PathBuf::from(".");
         let mut stdout_buf = vec![];
         assert!(config_from_instructions(
-            Instructions::default(),
+            &Instructions::default(),
             Some(repo_path),
             &mut stdout_buf,
         )
@@ -450,7 +1100,7 @@ mod test {
         let mut stdout_buf = vec![];
         let mut instructions = Instructions::default();
         *instructions.cargo_mut().enabled_mut() = false;
-        assert!(config_from_instructions(instructions, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&instructions, Some(repo_path), &mut stdout_buf,).is_ok());
         assert!(!CARGO_REGEX.is_match(&String::from_utf8_lossy(&stdout_buf)));
         teardown();
     }
@@ -459,13 +1109,11 @@ mod test {
     #[test]
     fn contains_git_output() {
         let repo_path = PathBuf::from(".");
+        // A checkout without tags describes HEAD with CARGO_PKG_VERSION, rather than the abbreviated SHA
+        let mut instructions = Instructions::default();
+        *instructions.git_mut().describe_always_mut() = false;
         let mut stdout_buf = vec![];
-        assert!(config_from_instructions(
-            Instructions::default(),
-            Some(repo_path),
-            &mut stdout_buf,
-        )
-        .is_ok());
+        assert!(config_from_instructions(&instructions, Some(repo_path), &mut stdout_buf,).is_ok());
         assert!(GIT_REGEX_INST.is_match(&String::from_utf8_lossy(&stdout_buf)));
         assert!(GIT_RIC_REGEX.is_match(&String::from_utf8_lossy(&stdout_buf)));
     }
@@ -477,7 +1125,7 @@ mod test {
         let mut stdout_buf = vec![];
         let mut instructions = Instructions::default();
         *instructions.git_mut().enabled_mut() = false;
-        assert!(config_from_instructions(instructions, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&instructions, Some(repo_path), &mut stdout_buf,).is_ok());
         assert!(!GIT_REGEX_INST.is_match(&String::from_utf8_lossy(&stdout_buf)));
         assert!(!GIT_RIC_REGEX.is_match(&String::from_utf8_lossy(&stdout_buf)));
     }
@@ -488,7 +1136,7 @@ mod test {
         let repo_path = PathBuf::from(".");
         let mut stdout_buf = vec![];
         assert!(config_from_instructions(
-            Instructions::default(),
+            &Instructions::default(),
             Some(repo_path),
             &mut stdout_buf,
         )
@@ -503,7 +1151,7 @@ mod test {
         let mut stdout_buf = vec![];
         let mut instructions = Instructions::default();
         *instructions.rustc_mut().enabled_mut() = false;
-        assert!(config_from_instructions(instructions, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&instructions, Some(repo_path), &mut stdout_buf,).is_ok());
         check_no_rustc_output(&stdout_buf);
     }
 
@@ -513,7 +1161,7 @@ mod test {
         let repo_path = PathBuf::from(".");
         let mut stdout_buf = vec![];
         assert!(config_from_instructions(
-            Instructions::default(),
+            &Instructions::default(),
             Some(repo_path),
             &mut stdout_buf,
         )
@@ -528,7 +1176,7 @@ mod test {
         let mut stdout_buf = vec![];
         let mut instructions = Instructions::default();
         *instructions.sysinfo_mut().enabled_mut() = false;
-        assert!(config_from_instructions(instructions, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&instructions, Some(repo_path), &mut stdout_buf,).is_ok());
         assert!(!SYSINFO_REGEX_INST.is_match(&String::from_utf8_lossy(&stdout_buf)));
     }
 
@@ -552,7 +1200,7 @@ mod test {
         let mut stdout_buf = vec![];
         let mut config = Instructions::default();
         *config.build_mut().timezone_mut() = TimeZone::Local;
-        assert!(config_from_instructions(config, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf,).is_ok());
     }
 
     #[cfg(feature = "git")]
@@ -565,7 +1213,7 @@ mod test {
         let mut stdout_buf = vec![];
         let mut config = Instructions::default();
         *config.git_mut().commit_timestamp_timezone_mut() = TimeZone::Local;
-        assert!(config_from_instructions(config, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf,).is_ok());
     }
 
     #[cfg(feature = "build")]
@@ -578,7 +1226,7 @@ mod test {
         let mut stdout_buf = vec![];
         let mut config = Instructions::default();
         *config.build_mut().kind_mut() = TimestampKind::TimeOnly;
-        assert!(config_from_instructions(config, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf,).is_ok());
     }
 
     #[cfg(feature = "git")]
@@ -591,7 +1239,7 @@ mod test {
         let mut stdout_buf = vec![];
         let mut config = Instructions::default();
         *config.git_mut().commit_timestamp_kind_mut() = TimestampKind::TimeOnly;
-        assert!(config_from_instructions(config, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf,).is_ok());
     }
 
     #[cfg(feature = "build")]
@@ -604,7 +1252,7 @@ mod test {
         let mut stdout_buf = vec![];
         let mut config = Instructions::default();
         *config.build_mut().kind_mut() = TimestampKind::DateOnly;
-        assert!(config_from_instructions(config, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf,).is_ok());
     }
 
     #[cfg(feature = "git")]
@@ -617,7 +1265,7 @@ mod test {
         let mut stdout_buf = vec![];
         let mut config = Instructions::default();
         *config.git_mut().commit_timestamp_kind_mut() = TimestampKind::DateOnly;
-        assert!(config_from_instructions(config, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf,).is_ok());
     }
 
     #[cfg(feature = "build")]
@@ -630,7 +1278,7 @@ mod test {
         let mut stdout_buf = vec![];
         let mut config = Instructions::default();
         *config.build_mut().kind_mut() = TimestampKind::DateAndTime;
-        assert!(config_from_instructions(config, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf,).is_ok());
     }
 
     #[cfg(feature = "git")]
@@ -643,7 +1291,7 @@ mod test {
         let mut stdout_buf = vec![];
         let mut config = Instructions::default();
         *config.git_mut().commit_timestamp_kind_mut() = TimestampKind::DateAndTime;
-        assert!(config_from_instructions(config, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf,).is_ok());
     }
 
     #[cfg(feature = "build")]
@@ -656,7 +1304,7 @@ mod test {
         let mut stdout_buf = vec![];
         let mut config = Instructions::default();
         *config.build_mut().kind_mut() = TimestampKind::All;
-        assert!(config_from_instructions(config, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf,).is_ok());
     }
 
     #[cfg(feature = "git")]
@@ -669,7 +1317,80 @@ mod test {
         let mut stdout_buf = vec![];
         let mut config = Instructions::default();
         *config.git_mut().commit_timestamp_kind_mut() = TimestampKind::All;
-        assert!(config_from_instructions(config, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf,).is_ok());
+    }
+
+    #[cfg(all(feature = "build", feature = "git"))]
+    #[test]
+    fn build_timestamp_from_git() {
+        use super::config_from_instructions;
+
+        let mut stdout_buf = vec![];
+        let mut config = Instructions::default();
+        *config.build_mut().timestamp_from_git_mut() = true;
+        assert!(config_from_instructions(&config, Some("."), &mut stdout_buf).is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        let value = |name: &str| {
+            stdout
+                .lines()
+                .find_map(|line| line.strip_prefix(&format!("cargo:rustc-env={}=", name)))
+                .map(|value| value[..19].to_string())
+        };
+        // The build timestamp has subseconds, the commit timestamp doesn't
+        assert!(value("VERGEN_BUILD_TIMESTAMP").is_some());
+        assert_eq!(
+            value("VERGEN_BUILD_TIMESTAMP"),
+            value("VERGEN_GIT_COMMIT_TIMESTAMP")
+        );
+    }
+
+    #[cfg(all(feature = "build", feature = "git"))]
+    #[test]
+    fn package_sets() {
+        use super::config_from_instructions;
+        use crate::Package;
+        use git2::{Repository, Signature};
+        use std::path::Path;
+
+        let path = env::temp_dir().join("vergen_package_sets");
+        let _ = fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        let sig = Signature::now("yoda", "yoda@dagobah.com").unwrap();
+        let mut shas = vec![];
+        for dir in &["crates/api", "crates/cli"] {
+            fs::create_dir_all(path.join(dir)).unwrap();
+            fs::write(path.join(dir).join("lib.rs"), "").unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(&Path::new(dir).join("lib.rs")).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+            let parents: Vec<&git2::Commit<'_>> = parent.iter().collect();
+            let sha = repo
+                .commit(Some("HEAD"), &sig, &sig, dir, &tree, &parents)
+                .unwrap();
+            shas.push(sha.to_string());
+        }
+
+        let mut config = Instructions::default();
+        for name in &["api", "cli"] {
+            let mut package = Package::default();
+            *package.path_scope_mut() = Some(PathBuf::from(format!("crates/{}", name)));
+            config.for_package(*name, package);
+        }
+        let mut stdout_buf = vec![];
+        config_from_instructions(&config, Some(&path), &mut stdout_buf).unwrap();
+        fs::remove_dir_all(&path).unwrap();
+
+        let parsed = parse_instructions(&String::from_utf8_lossy(&stdout_buf));
+        assert_eq!(parsed.env("VERGEN_GIT_SHA"), Some(shas[1].as_str()));
+        assert_eq!(parsed.env("VERGEN_GIT_SHA_API"), Some(shas[0].as_str()));
+        assert_eq!(parsed.env("VERGEN_GIT_SHA_CLI"), Some(shas[1].as_str()));
+        // The shared values are gathered once
+        let timestamp = parsed.env("VERGEN_BUILD_TIMESTAMP");
+        assert!(timestamp.is_some());
+        assert_eq!(parsed.env("VERGEN_BUILD_TIMESTAMP_API"), timestamp);
+        assert_eq!(parsed.env("VERGEN_BUILD_TIMESTAMP_CLI"), timestamp);
     }
 
     #[cfg(feature = "git")]
@@ -682,7 +1403,7 @@ mod test {
         let mut stdout_buf = vec![];
         let mut config = Instructions::default();
         *config.git_mut().sha_kind_mut() = ShaKind::Short;
-        assert!(config_from_instructions(config, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf,).is_ok());
     }
 
     #[cfg(feature = "git")]
@@ -695,7 +1416,7 @@ mod test {
         let mut stdout_buf = vec![];
         let mut config = Instructions::default();
         *config.git_mut().semver_kind_mut() = SemverKind::Lightweight;
-        assert!(config_from_instructions(config, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf,).is_ok());
     }
 
     #[cfg(feature = "git")]
@@ -707,6 +1428,1048 @@ mod test {
         let mut stdout_buf = vec![];
         let mut config = Instructions::default();
         *config.git_mut().semver_dirty_mut() = Some("-dirty");
-        assert!(config_from_instructions(config, Some(repo_path), &mut stdout_buf,).is_ok());
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf,).is_ok());
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn link_section() -> Result<()> {
+        let out_dir = env::temp_dir().join("vergen_link_section");
+        fs::create_dir_all(&out_dir)?;
+        env::set_var("OUT_DIR", &out_dir);
+        env::set_var("CARGO_CFG_TARGET_FAMILY", "unix");
+        env::set_var("CARGO_CFG_TARGET_VENDOR", "unknown");
+
+        let repo_path = PathBuf::from(".");
+        let mut stdout_buf = vec![];
+        let mut config = Instructions::default();
+        *config.emit_link_section_mut() = Some(".vergen".to_string());
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf).is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        let script_path = out_dir.join("vergen_link_section.ld");
+        assert!(stdout.contains("cargo:rustc-link-arg=-T\n"));
+        assert!(stdout.contains(&format!("cargo:rustc-link-arg={}", script_path.display())));
+        let script = fs::read_to_string(&script_path)?;
+        assert!(script.contains(".vergen :"));
+        assert!(script.contains("INSERT AFTER .rodata;"));
+
+        env::remove_var("OUT_DIR");
+        env::remove_var("CARGO_CFG_TARGET_FAMILY");
+        env::remove_var("CARGO_CFG_TARGET_VENDOR");
+        Ok(())
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn link_section_unsupported() {
+        env::set_var("CARGO_CFG_TARGET_FAMILY", "unix");
+        env::set_var("CARGO_CFG_TARGET_VENDOR", "apple");
+
+        let repo_path = PathBuf::from(".");
+        let mut stdout_buf = vec![];
+        let mut config = Instructions::default();
+        *config.emit_link_section_mut() = Some(".vergen".to_string());
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf).is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(stdout.contains("cargo:warning=vergen: the '.vergen' link section"));
+        assert!(!stdout.contains("cargo:rustc-link-arg"));
+
+        // This is informational, so it is quieted at the 'Errors' level
+        *config.warnings_mut() = WarningLevel::Errors;
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions(&config, Some("."), &mut stdout_buf).is_ok());
+        assert!(!String::from_utf8_lossy(&stdout_buf).contains("cargo:warning="));
+
+        env::remove_var("CARGO_CFG_TARGET_FAMILY");
+        env::remove_var("CARGO_CFG_TARGET_VENDOR");
+    }
+
+    #[test]
+    fn link_section_bad_name() {
+        let repo_path = PathBuf::from(".");
+        let mut config = Instructions::default();
+        *config.emit_link_section_mut() = Some(".ver gen".to_string());
+        assert!(config_from_instructions(&config, Some(repo_path), &mut io::sink()).is_err());
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn git_tracked_files() {
+        let repo_path = PathBuf::from(".");
+        let mut stdout_buf = vec![];
+        let mut config = Instructions::default();
+        *config.git_mut().tracked_files_mut() = true;
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf).is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        let caps = GIT_TRACKED_FILES_REGEX.captures(&stdout).unwrap();
+        assert!(caps[1].parse::<usize>().unwrap() > 0);
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    #[serial_test::serial]
+    fn git_commit_age() {
+        env::remove_var("SOURCE_DATE_EPOCH");
+        let mut stdout_buf = vec![];
+        let mut config = Instructions::default();
+        *config.git_mut().commit_age_mut() = true;
+        assert!(config_from_instructions(&config, Some("."), &mut stdout_buf).is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(GIT_COMMIT_AGE_REGEX.is_match(&stdout));
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn git_commits_since_tag() {
+        let repo_path = PathBuf::from(".");
+        let mut stdout_buf = vec![];
+        let mut config = Instructions::default();
+        *config.git_mut().commits_since_tag_mut() = true;
+        *config.git_mut().commits_since_tag_subjects_mut() = Some(20);
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf).is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(GIT_COMMITS_SINCE_TAG_REGEX.is_match(&stdout));
+        let subjects = stdout
+            .lines()
+            .find_map(|l| l.strip_prefix("cargo:rustc-env=VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS="))
+            .unwrap();
+        assert!(subjects.chars().count() <= 20);
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn git_tracked_files_off_by_default() {
+        let repo_path = PathBuf::from(".");
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions(
+            &Instructions::default(),
+            Some(repo_path),
+            &mut stdout_buf
+        )
+        .is_ok());
+        assert!(!GIT_TRACKED_FILES_REGEX.is_match(&String::from_utf8_lossy(&stdout_buf)));
+    }
+
+    #[cfg(all(feature = "build", feature = "git"))]
+    #[test]
+    fn env_overrides_rerun() {
+        let repo_path = PathBuf::from(".");
+        let mut instructions = Instructions::default();
+        // Without TARGET and PROFILE, the cargo instructions are skipped
+        *instructions.quiet_mut() = true;
+        let vars = vec![
+            (
+                "VERGEN_DISABLE_BUILD_TIMESTAMP".to_string(),
+                "0".to_string(),
+            ),
+            ("VERGEN_ERROR_POLICY".to_string(), "error".to_string()),
+            ("VERGEN_GIT_SHA".to_string(), "75b390d".to_string()),
+        ];
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions_env(
+            &instructions,
+            vars,
+            Some(repo_path),
+            &mut stdout_buf
+        )
+        .is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(stdout.contains("cargo:rerun-if-env-changed=VERGEN_DISABLE_BUILD_TIMESTAMP\n"));
+        assert!(stdout.contains("cargo:rerun-if-env-changed=VERGEN_ERROR_POLICY\n"));
+        // Only the set override variables are watched
+        assert!(!stdout.contains("cargo:rerun-if-env-changed=VERGEN_DISABLE_GIT\n"));
+        assert!(!stdout.contains("cargo:rerun-if-env-changed=VERGEN_OVERRIDES_FILE\n"));
+        assert!(!stdout.contains("cargo:rerun-if-env-changed=VERGEN_GIT_SHA\n"));
+        assert!(!stdout.contains("cargo:warning="));
+    }
+
+    #[test]
+    fn default_no_rerun() {
+        // Any rerun-if instruction turns off cargo's default of rerunning on a package file change
+        let mut instructions = Instructions::default();
+        #[cfg(feature = "git")]
+        {
+            *instructions.git_mut().enabled_mut() = false;
+        }
+        // Without TARGET and PROFILE, the cargo instructions are skipped
+        *instructions.quiet_mut() = true;
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions_env(
+            &instructions,
+            vec![],
+            None::<PathBuf>,
+            &mut stdout_buf
+        )
+        .is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(!stdout.contains("cargo:rerun-if-"));
+    }
+
+    #[cfg(feature = "build")]
+    #[test]
+    fn print_table() {
+        let mut config = Instructions::default();
+        // Without TARGET and PROFILE, the cargo instructions are skipped
+        *config.quiet_mut() = true;
+        let mut stdout_buf = vec![];
+        let mut stderr_buf = vec![];
+        assert!(config_from_instructions_stderr(
+            &config,
+            vec![],
+            None::<PathBuf>,
+            &mut stdout_buf,
+            &mut stderr_buf
+        )
+        .is_ok());
+        assert!(stderr_buf.is_empty());
+
+        *config.print_table_mut() = true;
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions_stderr(
+            &config,
+            vec![],
+            None::<PathBuf>,
+            &mut stdout_buf,
+            &mut stderr_buf
+        )
+        .is_ok());
+        let stderr = String::from_utf8_lossy(&stderr_buf);
+        assert!(stderr.starts_with("vergen: Instruction "));
+        assert!(stderr
+            .lines()
+            .any(|line| line.starts_with("vergen: VERGEN_BUILD_TIMESTAMP ")));
+    }
+
+    #[test]
+    fn env_overrides_off() {
+        let repo_path = PathBuf::from(".");
+        let mut config = Instructions::default();
+        *config.env_overrides_mut() = false;
+        // Without TARGET and PROFILE, the cargo instructions are skipped
+        *config.quiet_mut() = true;
+        let vars = vec![("VERGEN_DISABLE_TYPO".to_string(), "1".to_string())];
+        let mut stdout_buf = vec![];
+        assert!(
+            config_from_instructions_env(&config, vars, Some(repo_path), &mut stdout_buf).is_ok()
+        );
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(!stdout.contains("cargo:rerun-if-env-changed="));
+        assert!(!stdout.contains("cargo:warning="));
+    }
+
+    #[test]
+    fn env_overrides_unknown_warns() {
+        let repo_path = PathBuf::from(".");
+        let vars = vec![("VERGEN_DISABLE_TYPO".to_string(), "1".to_string())];
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions_env(
+            &Instructions::default(),
+            vars,
+            Some(repo_path),
+            &mut stdout_buf
+        )
+        .is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(stdout
+            .contains("cargo:warning=vergen: ignoring unknown override 'VERGEN_DISABLE_TYPO'"));
+    }
+
+    #[cfg(all(feature = "build", feature = "git"))]
+    #[test]
+    fn overrides_file_precedence() {
+        let dir = env::temp_dir().join("vergen_gen_overrides_file");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("vergen.overrides");
+        fs::write(
+            &path,
+            "VERGEN_GIT_SHA=75b390dc6c05a6a4aa2791cc7b3934591803bc22\n\
+             VERGEN_GIT_BRANCH=release\t1.2\n\
+             VERGEN_BUILD_SEMVER=9.9.9\n",
+        )
+        .unwrap();
+
+        let mut instructions = Instructions::default();
+        *instructions.quiet_mut() = true;
+        *instructions.sanitize_mut().escape_non_printable_mut() = true;
+        // The environment wins over the field
+        *instructions.overrides_file_mut() = Some(dir.join("missing.overrides"));
+        instructions.value_provider("VERGEN_GIT_SHA", || Some("provided".to_string()));
+        instructions.value_provider("VERGEN_GIT_SHA_SHORT", || Some("75b390d".to_string()));
+        let vars = vec![
+            (
+                "VERGEN_OVERRIDES_FILE".to_string(),
+                path.display().to_string(),
+            ),
+            ("VERGEN_DISABLE_GIT".to_string(), "1".to_string()),
+        ];
+        let mut stdout_buf = vec![];
+        config_from_instructions_env(&instructions, vars, Some("."), &mut stdout_buf).unwrap();
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+
+        // The file wins over the value providers and the gathered values, even for a disabled feature
+        assert!(stdout
+            .contains("cargo:rustc-env=VERGEN_GIT_SHA=75b390dc6c05a6a4aa2791cc7b3934591803bc22\n"));
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_GIT_SHA_SHORT=75b390d\n"));
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_BUILD_SEMVER=9.9.9\n"));
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_BUILD_TIMESTAMP="));
+        assert!(!stdout.contains("VERGEN_GIT_COMMIT_TIMESTAMP="));
+        // The values are sanitized
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_GIT_BRANCH=release\\t1.2\n"));
+        assert!(stdout.contains(&format!("cargo:rerun-if-changed={}\n", path.display())));
+        assert!(stdout.contains("cargo:rerun-if-env-changed=VERGEN_OVERRIDES_FILE\n"));
+
+        // The file values count for the required instructions
+        *instructions.overrides_file_mut() = Some(path.clone());
+        *instructions.git_mut().enabled_mut() = false;
+        instructions.require(&["VERGEN_GIT_SHA"]);
+        let config = generate_config(&instructions, false, Some("."), Instant::now()).unwrap();
+        assert!(instructions.unavailable_required(&config).is_empty());
+
+        // Without the environment overrides, the field is used
+        *instructions.overrides_file_mut() = Some(dir.join("missing.overrides"));
+        *instructions.env_overrides_mut() = false;
+        let vars = vec![(
+            "VERGEN_OVERRIDES_FILE".to_string(),
+            path.display().to_string(),
+        )];
+        assert!(
+            config_from_instructions_env(&instructions, vars, Some("."), &mut io::sink()).is_err()
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn warning_levels() {
+        let vars = || vec![("VERGEN_DISABLE_TYPO".to_string(), "1".to_string())];
+        let mut config = Instructions::default();
+        for (level, warned) in &[
+            (WarningLevel::All, true),
+            (WarningLevel::Errors, true),
+            (WarningLevel::None, false),
+        ] {
+            *config.warnings_mut() = *level;
+            let mut stdout_buf = vec![];
+            assert!(
+                config_from_instructions_env(&config, vars(), Some("."), &mut stdout_buf).is_ok()
+            );
+            let stdout = String::from_utf8_lossy(&stdout_buf);
+            assert_eq!(stdout.contains("cargo:warning="), *warned);
+        }
+    }
+
+    #[cfg(feature = "build")]
+    #[test]
+    fn conditions_follow_context() {
+        let vars = |profile: &str| {
+            vec![
+                ("PROFILE".to_string(), profile.to_string()),
+                ("TARGET".to_string(), "x86_64-unknown-linux-gnu".to_string()),
+                ("HOST".to_string(), "x86_64-unknown-linux-gnu".to_string()),
+            ]
+        };
+        let mut config = Instructions::only_build();
+        config.emit_when("build", Condition::only_profiles(&["release"]));
+        config.emit_when(
+            "VERGEN_BUILD_SEMVER",
+            Condition::when(|context| context.is_cross_compile()),
+        );
+
+        let mut stdout_buf = vec![];
+        config_from_instructions_env(&config, vars("debug"), Some("."), &mut stdout_buf).unwrap();
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(!stdout.contains("cargo:rustc-env=VERGEN_BUILD_"));
+        assert!(!stdout.contains("cargo:warning="));
+
+        let mut stdout_buf = vec![];
+        config_from_instructions_env(&config, vars("release"), Some("."), &mut stdout_buf).unwrap();
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(VBD_REGEX.is_match(&stdout));
+        assert!(!stdout.contains("VERGEN_BUILD_SEMVER="));
+
+        // The left out instructions use the placeholder, so an env! still compiles
+        *config.emit_all_with_placeholders_mut() = true;
+        let mut stdout_buf = vec![];
+        config_from_instructions_env(&config, vars("debug"), Some("."), &mut stdout_buf).unwrap();
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_BUILD_TIMESTAMP=VERGEN_UNAVAILABLE\n"));
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_BUILD_SEMVER=VERGEN_UNAVAILABLE\n"));
+    }
+
+    #[cfg(feature = "build")]
+    #[test]
+    fn ci_only() {
+        let vars = |ci: &str| vec![("CI".to_string(), ci.to_string())];
+        let mut config = Instructions::only_build();
+        config.ci_only(&["VERGEN_BUILD_TIMESTAMP"]);
+
+        let mut stdout_buf = vec![];
+        config_from_instructions_env(&config, vars("false"), Some("."), &mut stdout_buf).unwrap();
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(!VBD_REGEX.is_match(&stdout));
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_BUILD_SEMVER="));
+        assert!(!stdout.contains("cargo:warning="));
+
+        let mut stdout_buf = vec![];
+        config_from_instructions_env(&config, vars("true"), Some("."), &mut stdout_buf).unwrap();
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(VBD_REGEX.is_match(&stdout));
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_BUILD_SEMVER="));
+    }
+
+    #[cfg(feature = "build")]
+    #[test]
+    fn if_feature() {
+        let mut config = Instructions::only_build();
+        config.if_feature("fast-hash", "VERGEN_BUILD_TIMESTAMP");
+
+        let mut stdout_buf = vec![];
+        config_from_instructions_env(&config, vec![], Some("."), &mut stdout_buf).unwrap();
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(!VBD_REGEX.is_match(&stdout));
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_BUILD_SEMVER="));
+        assert!(!stdout.contains("cargo:warning="));
+
+        let vars = vec![("CARGO_FEATURE_FAST_HASH".to_string(), "1".to_string())];
+        let mut stdout_buf = vec![];
+        config_from_instructions_env(&config, vars, Some("."), &mut stdout_buf).unwrap();
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(VBD_REGEX.is_match(&stdout));
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_BUILD_SEMVER="));
+    }
+
+    #[cfg(feature = "build")]
+    #[test]
+    fn env_beats_code() {
+        let repo_path = PathBuf::from(".");
+        let mut config = Instructions::default();
+        *config.build_mut().timestamp_mut() = true;
+        let vars = vec![(
+            "VERGEN_DISABLE_BUILD_TIMESTAMP".to_string(),
+            "1".to_string(),
+        )];
+        let mut stdout_buf = vec![];
+        assert!(
+            config_from_instructions_env(&config, vars, Some(repo_path), &mut stdout_buf).is_ok()
+        );
+        assert!(!VBD_REGEX.is_match(&String::from_utf8_lossy(&stdout_buf)));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn docs_rs_placeholder() {
+        // Any git or rustc probing would fail with these
+        let repo_path = env::temp_dir().join("vergen_no_such_repo");
+        let rustc = env::var_os("RUSTC");
+        env::set_var("RUSTC", "vergen_no_such_rustc");
+        let vars = vec![("DOCS_RS".to_string(), "1".to_string())];
+        let mut stdout_buf = vec![];
+        let mut instructions = Instructions::default();
+        instructions.add_command("BUILD_NUMBER", "vergen_no_such_program", &[]);
+        let result =
+            config_from_instructions_env(&instructions, vars, Some(repo_path), &mut stdout_buf);
+        match rustc {
+            Some(rustc) => env::set_var("RUSTC", rustc),
+            None => env::remove_var("RUSTC"),
+        }
+        assert!(result.is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(!stdout.contains("cargo:rerun-if-changed="));
+        let parsed = parse_instructions(&stdout);
+        assert!(parsed
+            .rustc_env()
+            .values()
+            .all(|value| value == "VERGEN_IDEMPOTENT_OUTPUT"));
+        assert_eq!(parsed.env("BUILD_NUMBER"), Some("VERGEN_IDEMPOTENT_OUTPUT"));
+        #[cfg(feature = "build")]
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_BUILD_TIMESTAMP=VERGEN_IDEMPOTENT_OUTPUT"));
+        #[cfg(feature = "cargo")]
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_CARGO_PROFILE=VERGEN_IDEMPOTENT_OUTPUT"));
+        #[cfg(feature = "git")]
+        {
+            assert!(stdout.contains("cargo:rustc-env=VERGEN_GIT_BRANCH=VERGEN_IDEMPOTENT_OUTPUT"));
+            assert!(stdout.contains("cargo:rustc-env=VERGEN_GIT_SHA=VERGEN_IDEMPOTENT_OUTPUT"));
+            assert!(stdout.contains("cargo:rustc-env=VERGEN_GIT_SEMVER=VERGEN_IDEMPOTENT_OUTPUT"));
+        }
+        #[cfg(feature = "rustc")]
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_RUSTC_SEMVER=VERGEN_IDEMPOTENT_OUTPUT"));
+        #[cfg(feature = "si")]
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_SYSINFO_NAME=VERGEN_IDEMPOTENT_OUTPUT"));
+    }
+
+    #[test]
+    fn build_number_unavailable() {
+        let mut config = Instructions::default();
+        *config.build_number_mut() = true;
+        #[cfg(feature = "git")]
+        {
+            *config.git_mut().enabled_mut() = false;
+        }
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions(&config, Some("."), &mut stdout_buf).is_ok());
+        let parsed = parse_instructions(&String::from_utf8_lossy(&stdout_buf));
+        assert_eq!(parsed.env("VERGEN_BUILD_NUMBER"), Some(""));
+        assert!(parsed
+            .warnings()
+            .iter()
+            .any(|warning| warning.contains("VERGEN_BUILD_NUMBER is empty")));
+    }
+
+    #[cfg(feature = "build")]
+    #[test]
+    fn repeated_calls() {
+        use super::config_from_instructions_repeat;
+        use crate::{config::DuplicatePolicy, repeat::Emitted};
+
+        let emitted = Emitted::default();
+        let call = |policy, stdout: &mut Vec<u8>| {
+            let mut instructions = Instructions::only_build();
+            *instructions.on_duplicate_key_mut() = policy;
+            config_from_instructions_repeat(&instructions, Some("."), &emitted, stdout)
+        };
+
+        let mut stdout_buf = vec![];
+        assert!(call(DuplicatePolicy::Error, &mut stdout_buf).is_ok());
+        let parsed = parse_instructions(&String::from_utf8_lossy(&stdout_buf));
+        assert!(parsed.env("VERGEN_BUILD_SEMVER").is_some());
+
+        let mut stdout_buf = vec![];
+        let error = call(DuplicatePolicy::Error, &mut stdout_buf).unwrap_err();
+        assert!(error.to_string().contains("VERGEN_BUILD_SEMVER"));
+        assert!(stdout_buf.is_empty());
+
+        let mut stdout_buf = vec![];
+        assert!(call(DuplicatePolicy::KeepFirst, &mut stdout_buf).is_ok());
+        let parsed = parse_instructions(&String::from_utf8_lossy(&stdout_buf));
+        assert!(parsed.rustc_env().is_empty());
+
+        let mut stdout_buf = vec![];
+        assert!(call(DuplicatePolicy::Overwrite, &mut stdout_buf).is_ok());
+        let parsed = parse_instructions(&String::from_utf8_lossy(&stdout_buf));
+        assert!(parsed.env("VERGEN_BUILD_SEMVER").is_some());
+        assert!(parsed
+            .warnings()
+            .iter()
+            .any(|warning| warning.contains("earlier vergen call")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn custom_commands() {
+        let mut instructions = Instructions::default();
+        instructions.add_command("BUILD_NUMBER", "echo", &["42", "\nignored"]);
+        instructions.add_command("BROKEN", "false", &[]);
+        let mut stdout_buf = vec![];
+        assert!(
+            config_from_instructions(&instructions, Some(PathBuf::from(".")), &mut stdout_buf)
+                .is_ok()
+        );
+        let parsed = parse_instructions(&String::from_utf8_lossy(&stdout_buf));
+        assert_eq!(parsed.env("BUILD_NUMBER"), Some("42"));
+        assert_eq!(parsed.env("BROKEN"), None);
+        assert!(parsed
+            .warnings()
+            .iter()
+            .any(|warning| warning.starts_with("vergen: skipping 'BROKEN', 'false' failed")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn command_keys() {
+        let mut release_train = Command::new("sh");
+        let _ = release_train
+            .arg("-c")
+            .arg("printf 'train\\n2021.03\\n\\n'");
+        let mut broken = Command::new("sh");
+        let _ = broken.arg("-c").arg("exit 2");
+        let mut instructions = Instructions::default();
+        instructions.add_command_key("RELEASE_TRAIN", release_train);
+        let mut stdout_buf = vec![];
+        assert!(
+            config_from_instructions(&instructions, Some(PathBuf::from(".")), &mut stdout_buf)
+                .is_ok()
+        );
+        let parsed = parse_instructions(&String::from_utf8_lossy(&stdout_buf));
+        assert_eq!(
+            parsed.env("VERGEN_CMD_RELEASE_TRAIN"),
+            Some("train 2021.03")
+        );
+
+        // A failed command fails the build, unless the placeholders are asked for
+        instructions.add_command_key("BROKEN", broken);
+        let err = config_from_instructions(&instructions, Some(PathBuf::from(".")), &mut vec![])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("'VERGEN_CMD_BROKEN', 'sh -c exit 2' failed with exit status: 2"));
+
+        instructions.add_command_key("EMPTY", Command::new("true"));
+        *instructions.emit_all_with_placeholders_mut() = true;
+        let mut stdout_buf = vec![];
+        assert!(
+            config_from_instructions(&instructions, Some(PathBuf::from(".")), &mut stdout_buf)
+                .is_ok()
+        );
+        let parsed = parse_instructions(&String::from_utf8_lossy(&stdout_buf));
+        assert_eq!(parsed.env("VERGEN_CMD_BROKEN"), Some("VERGEN_UNAVAILABLE"));
+        assert_eq!(parsed.env("VERGEN_CMD_EMPTY"), Some("VERGEN_UNAVAILABLE"));
+        assert!(parsed.warnings().contains(
+            &"vergen: using the placeholder for 'VERGEN_CMD_BROKEN', 'sh -c exit 2' failed with exit status: 2"
+                .to_string()
+        ));
+        assert!(parsed.warnings().contains(
+            &"vergen: using the placeholder for 'VERGEN_CMD_EMPTY', 'true' printed nothing"
+                .to_string()
+        ));
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn docs_rs_placeholder_off() {
+        let repo_path = env::temp_dir().join("vergen_no_such_repo");
+        let mut config = Instructions::default();
+        *config.docs_rs_placeholder_mut() = false;
+        let vars = vec![("DOCS_RS".to_string(), "1".to_string())];
+        let mut stdout_buf = vec![];
+        assert!(
+            config_from_instructions_env(&config, vars, Some(repo_path), &mut stdout_buf).is_err()
+        );
+    }
+
+    #[cfg(feature = "build")]
+    #[test]
+    #[serial_test::serial]
+    fn sticky_runs_are_identical() -> Result<()> {
+        let out_dir = env::temp_dir().join("vergen_sticky");
+        fs::create_dir_all(&out_dir)?;
+        let _ = fs::remove_file(out_dir.join("vergen_sticky.cache"));
+        env::set_var("OUT_DIR", &out_dir);
+
+        let repo_path = PathBuf::from(".");
+        let mut config = Instructions::default();
+        *config.sticky_mut().enabled_mut() = true;
+        let mut first = vec![];
+        let first_res = config_from_instructions(&config, Some(&repo_path), &mut first);
+        let mut second = vec![];
+        let second_res = config_from_instructions(&config, Some(&repo_path), &mut second);
+
+        env::remove_var("OUT_DIR");
+        assert!(first_res.is_ok());
+        assert!(second_res.is_ok());
+        assert!(VBD_REGEX.is_match(&String::from_utf8_lossy(&first)));
+        assert_eq!(first, second);
+        Ok(())
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn sticky_requires_out_dir() {
+        env::remove_var("OUT_DIR");
+        let repo_path = PathBuf::from(".");
+        let mut config = Instructions::default();
+        *config.sticky_mut().enabled_mut() = true;
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions(&config, Some(repo_path), &mut stdout_buf).is_err());
+    }
+
+    #[test]
+    fn banner() {
+        let mut config = Instructions::default();
+        *config.banner_format_mut() = Some("{name} v{semver} ({profile})".to_string());
+        // A checkout without tags describes HEAD with CARGO_PKG_VERSION, rather than the abbreviated SHA
+        #[cfg(feature = "git")]
+        {
+            *config.git_mut().describe_always_mut() = false;
+        }
+        let vars = vec![("DOCS_RS".to_string(), "1".to_string())];
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions_env(&config, vars, Some("."), &mut stdout_buf).is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_BANNER=VERGEN_IDEMPOTENT_OUTPUT"));
+
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions_env(&config, vec![], Some("."), &mut stdout_buf).is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        let banner = format!(
+            "cargo:rustc-env=VERGEN_BANNER=vergen v{} (",
+            env!("CARGO_PKG_VERSION")
+        );
+        assert!(stdout.contains(&banner));
+    }
+
+    fn output_target(target: OutputTarget) -> (String, String) {
+        let mut config = Instructions::default();
+        *config.banner_format_mut() = Some("{name}".to_string());
+        *config.output_target_mut() = target;
+        let vars = vec![("DOCS_RS".to_string(), "1".to_string())];
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions_env(&config, vars, Some("."), &mut stdout_buf).is_ok());
+        let path = config.output_target().file().unwrap();
+        let contents = fs::read_to_string(path).unwrap();
+        fs::remove_file(path).unwrap();
+        (String::from_utf8_lossy(&stdout_buf).into_owned(), contents)
+    }
+
+    #[test]
+    fn file_target() {
+        let path = env::temp_dir()
+            .join("vergen_file_target")
+            .join("vergen.env");
+        let (stdout, contents) = output_target(OutputTarget::File(path));
+        assert!(stdout.is_empty());
+        assert!(contents
+            .lines()
+            .any(|line| line == "VERGEN_BANNER=VERGEN_IDEMPOTENT_OUTPUT"));
+        assert!(!contents.contains("cargo:"));
+    }
+
+    #[test]
+    fn both_targets() {
+        let path = env::temp_dir()
+            .join("vergen_both_targets")
+            .join("vergen.env");
+        let (stdout, contents) = output_target(OutputTarget::Both(path));
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_BANNER=VERGEN_IDEMPOTENT_OUTPUT"));
+        assert!(contents
+            .lines()
+            .any(|line| line == "VERGEN_BANNER=VERGEN_IDEMPOTENT_OUTPUT"));
+        assert_eq!(
+            contents.lines().count(),
+            parse_instructions(&stdout).rustc_env().len()
+        );
+    }
+
+    #[test]
+    fn value_provider() {
+        let mut config = Instructions::default();
+        config.value_provider("VERGEN_GIT_SHA", || Some("custom".to_string()));
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions(&config, Some("."), &mut stdout_buf).is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_GIT_SHA=custom\n"));
+    }
+
+    #[test]
+    fn value_override() {
+        let mut config = Instructions::default();
+        config.value_provider("VERGEN_GIT_SHA", || Some("custom".to_string()));
+        config.override_value("VERGEN_GIT_SHA", "75b390d");
+        config.override_value("VERGEN_RUSTC_CHANNEL", "stable");
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions(&config, Some("."), &mut stdout_buf).is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_GIT_SHA=75b390d\n"));
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_RUSTC_CHANNEL=stable\n"));
+    }
+
+    #[test]
+    fn adversarial_values_emit_one_instruction() {
+        const FRAGMENTS: &[&str] = &[
+            "\n",
+            "\r",
+            "\r\n",
+            "\0",
+            "\t",
+            "\u{1b}",
+            " ",
+            "=",
+            "a",
+            "é",
+            "cargo:",
+            "cargo::warning=x",
+        ];
+        let mut instructions = Instructions::default();
+        *instructions.env_overrides_mut() = false;
+
+        // Every combination of up to 4 fragments
+        let mut values = vec![String::new()];
+        let mut longest = values.clone();
+        for _ in 0..4 {
+            longest = longest
+                .iter()
+                .flat_map(|value| FRAGMENTS.iter().map(move |f| format!("{}{}", value, f)))
+                .collect();
+            values.extend(longest.iter().cloned());
+        }
+
+        for escape in &[false, true] {
+            *instructions.sanitize_mut().escape_non_printable_mut() = *escape;
+            for value in &values {
+                let mut config = Config::default();
+                let _ = config
+                    .cfg_map_mut()
+                    .insert(VergenKey::CommitsSinceTagSubjects, Some(value.clone()));
+                sanitize(*instructions.sanitize(), &mut config).unwrap();
+                let mut stdout_buf = vec![];
+                output_cargo_instructions(
+                    &instructions,
+                    CargoSyntax::Legacy,
+                    &config,
+                    &mut stdout_buf,
+                )
+                .unwrap();
+                let stdout = String::from_utf8(stdout_buf).unwrap();
+
+                let lines: Vec<&str> = stdout.lines().collect();
+                assert_eq!(lines.len(), 1, "{:?} became {:?}", value, stdout);
+                assert!(stdout.ends_with('\n') && !stdout.contains('\r') && !stdout.contains('\0'));
+                let emitted = lines[0]
+                    .strip_prefix("cargo:rustc-env=VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS=")
+                    .unwrap();
+                assert_eq!(
+                    Some(&Some(emitted.to_string())),
+                    config.cfg_map().get(&VergenKey::CommitsSinceTagSubjects)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn modern_syntax() {
+        let mut config = Instructions::default();
+        *config.cargo_syntax_mut() = CargoSyntax::Modern;
+        config.value_provider("VERGEN_GIT_SHA", || Some("abc123".to_string()));
+        config
+            .emit_cfg_mut()
+            .keys_mut()
+            .push("VERGEN_GIT_SHA".to_string());
+        let vars = vec![("VERGEN_DISABLE_GIT_SHAA".to_string(), "1".to_string())];
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions_env(&config, vars, Some("."), &mut stdout_buf).is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(stdout.contains("cargo::rustc-env=VERGEN_GIT_SHA=abc123\n"));
+        assert!(stdout.contains("cargo::rustc-check-cfg=cfg(vergen_git_sha)\n"));
+        assert!(stdout.contains("cargo::warning="));
+        assert!(stdout
+            .lines()
+            .all(|line| line.starts_with("cargo::") && !line.starts_with("cargo:::")));
+        if cfg!(feature = "git") {
+            assert!(stdout.contains("cargo::rerun-if-changed="));
+        }
+    }
+
+    #[test]
+    fn parses_emitted_output() {
+        for syntax in &[CargoSyntax::Legacy, CargoSyntax::Modern] {
+            let mut config = Instructions::default();
+            *config.cargo_syntax_mut() = *syntax;
+            *config.quiet_mut() = true;
+            config.value_provider("VERGEN_GIT_SHA", || Some("a=b".to_string()));
+            config.value_provider("VERGEN_GIT_SIGNED", || Some("true".to_string()));
+            config
+                .emit_cfg_mut()
+                .keys_mut()
+                .push("VERGEN_GIT_SIGNED".to_string());
+            let vars = vec![("VERGEN_DISABLE_GIT_SHAA".to_string(), "1".to_string())];
+            let mut stdout_buf = vec![];
+            assert!(
+                config_from_instructions_env(&config, vars, Some("."), &mut stdout_buf).is_ok()
+            );
+            let parsed = parse_instructions(&String::from_utf8_lossy(&stdout_buf));
+            assert_eq!(parsed.env("VERGEN_GIT_SHA"), Some("a=b"));
+            assert_eq!(parsed.rustc_cfg(), &["vergen_git_signed"]);
+            assert_eq!(parsed.warnings().len(), 1);
+            if cfg!(feature = "git") {
+                assert!(!parsed.rerun_if_changed().is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn self_timing() {
+        let duration = |config: &Instructions| {
+            let mut stdout_buf = vec![];
+            config_from_instructions(config, Some("."), &mut stdout_buf).unwrap();
+            String::from_utf8_lossy(&stdout_buf)
+                .lines()
+                .find_map(|line| {
+                    line.strip_prefix("cargo:rustc-env=VERGEN_BUILD_SCRIPT_DURATION_MS=")
+                        .map(str::to_string)
+                })
+        };
+
+        let mut config = Instructions::default();
+        #[cfg(feature = "hash")]
+        {
+            *config.hash_mut().enabled_mut() = true;
+        }
+        assert!(duration(&config).is_none());
+        *config.self_timing_mut() = true;
+        assert!(duration(&config).unwrap().parse::<u128>().is_ok());
+        assert!(duration(&Instructions::reproducible()).is_none());
+    }
+
+    #[test]
+    fn verbose_timings() {
+        let mut config = Config::default();
+        config.timings_mut().extend(vec![
+            ("build", Duration::from_millis(3)),
+            ("git", Duration::from_micros(1200)),
+        ]);
+        let mut stderr_buf = vec![];
+        log_timings(&config, &mut stderr_buf).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&stderr_buf),
+            "vergen: the build feature took 3ms\nvergen: the git feature took 1.2ms\n"
+        );
+    }
+
+    #[test]
+    fn enabled_summary() {
+        let summary = |config: &Instructions| {
+            let mut stdout_buf = vec![];
+            config_from_instructions(config, Some("."), &mut stdout_buf).unwrap();
+            String::from_utf8_lossy(&stdout_buf)
+                .lines()
+                .find_map(|line| {
+                    line.strip_prefix("cargo:rustc-env=VERGEN_ENABLED_FEATURES=")
+                        .map(str::to_string)
+                })
+        };
+
+        let mut config = Instructions::default();
+        assert!(summary(&config).is_none());
+        *config.emit_enabled_summary_mut() = true;
+        let enabled = summary(&config).unwrap();
+        let subsystems: Vec<&str> = enabled.split(',').filter(|s| !s.is_empty()).collect();
+        assert!(subsystems.windows(2).all(|pair| pair[0] < pair[1]));
+        if cfg!(feature = "build") {
+            assert!(subsystems.contains(&"build"));
+        }
+        if cfg!(feature = "git") {
+            assert!(subsystems.contains(&"git"));
+        }
+        assert!(!subsystems.contains(&"vergen"));
+    }
+
+    #[test]
+    fn truncated_after_sanitizing() {
+        let mut config = Instructions::default();
+        config.value_provider("VERGEN_GIT_SHA", || Some("abc\u{1b}def".to_string()));
+        *config.sanitize_mut().escape_non_printable_mut() = true;
+        *config.truncate_mut().max_length_mut() = Some(10);
+        let mut stdout_buf = vec![];
+        config_from_instructions(&config, Some("."), &mut stdout_buf).unwrap();
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        // The escaped value is 12 bytes, and the escape sequence isn't split
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_GIT_SHA=abc...\n"));
+        assert!(
+            stdout.contains("cargo:warning=vergen: truncated VERGEN_GIT_SHA from 12 to 6 bytes\n")
+        );
+    }
+
+    #[test]
+    fn unsafe_value_provider() {
+        let mut config = Instructions::default();
+        config.value_provider("VERGEN_GIT_SHA", || {
+            Some("abc\ncargo:rustc-env=EVIL=1".to_string())
+        });
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions(&config, Some("."), &mut stdout_buf).is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        assert!(stdout.contains("cargo:rustc-env=VERGEN_GIT_SHA=abc cargo:rustc-env=EVIL=1\n"));
+        assert!(!stdout
+            .lines()
+            .any(|line| line.starts_with("cargo:rustc-env=EVIL")));
+        assert!(stdout.contains("cargo:warning=vergen: the 'VERGEN_GIT_SHA' value contains"));
+
+        *config.sanitize_mut().reject_directives_mut() = true;
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions(&config, Some("."), &mut stdout_buf).is_err());
+    }
+
+    #[test]
+    fn emit_all_with_placeholders() {
+        let no_repo = env::temp_dir().join("vergen_emit_all_no_repo");
+        fs::create_dir_all(&no_repo).unwrap();
+        let mut config = Instructions::default();
+        *config.env_overrides_mut() = false;
+
+        let mut stdout_buf = vec![];
+        let result = config_from_instructions(&config, Some(&no_repo), &mut stdout_buf);
+        assert_eq!(result.is_err(), cfg!(feature = "git"));
+
+        *config.emit_all_with_placeholders_mut() = true;
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions(&config, Some(&no_repo), &mut stdout_buf).is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        for key in VergenKey::ALL
+            .iter()
+            .copied()
+            .filter(|key| config.feature_enabled(*key))
+        {
+            let prefix = format!("cargo:rustc-env={}=", key.name());
+            assert_eq!(stdout.lines().filter(|l| l.starts_with(&prefix)).count(), 1);
+        }
+        if cfg!(feature = "git") {
+            assert!(stdout.contains("cargo:rustc-env=VERGEN_GIT_SHA=VERGEN_UNAVAILABLE\n"));
+            assert!(stdout.contains("cargo:warning=vergen: using placeholders"));
+        }
+        fs::remove_dir_all(&no_repo).unwrap();
+    }
+
+    #[test]
+    fn banner_unknown_placeholder() {
+        let mut config = Instructions::default();
+        *config.banner_format_mut() = Some("{name} {version}".to_string());
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions(&config, Some("."), &mut stdout_buf).is_err());
+        assert!(stdout_buf.is_empty());
+    }
+
+    #[cfg(any(
+        feature = "build",
+        feature = "cargo",
+        feature = "git",
+        feature = "rustc",
+        feature = "si",
+    ))]
+    fn only_emits(config: &Instructions, prefix: &str) {
+        let mut stdout_buf = vec![];
+        assert!(config_from_instructions(config, Some("."), &mut stdout_buf).is_ok());
+        let stdout = String::from_utf8_lossy(&stdout_buf);
+        let keys: Vec<&str> = stdout
+            .lines()
+            .filter(|l| l.starts_with("cargo:rustc-env="))
+            .collect();
+        assert!(!keys.is_empty());
+        for key in keys {
+            assert!(key.starts_with(prefix), "{} is not a {} key", key, prefix);
+        }
+    }
+
+    #[cfg(feature = "build")]
+    #[test]
+    fn only_build() {
+        only_emits(&Instructions::only_build(), "cargo:rustc-env=VERGEN_BUILD_");
+    }
+
+    #[cfg(feature = "cargo")]
+    #[test]
+    #[serial_test::serial]
+    fn only_cargo() {
+        setup();
+        only_emits(&Instructions::only_cargo(), "cargo:rustc-env=VERGEN_CARGO_");
+        teardown();
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn only_git() {
+        only_emits(&Instructions::only_git(), "cargo:rustc-env=VERGEN_GIT_");
+    }
+
+    #[cfg(feature = "rustc")]
+    #[test]
+    fn only_rustc() {
+        only_emits(&Instructions::only_rustc(), "cargo:rustc-env=VERGEN_RUSTC_");
+    }
+
+    #[cfg(feature = "si")]
+    #[test]
+    fn only_sysinfo() {
+        only_emits(
+            &Instructions::only_sysinfo(),
+            "cargo:rustc-env=VERGEN_SYSINFO_",
+        );
     }
 }
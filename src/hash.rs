@@ -0,0 +1,286 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` hash over the generated values
+
+use crate::{
+    config::{Config, VergenKey},
+    error::ConfigError,
+    merge::{pick, union, SetFields},
+    sticky::find_key,
+};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+
+/// Configuration for the `VERGEN_HASH` instruction
+///
+/// When the `enabled` field is true, a `VERGEN_HASH` instruction is generated with a SHA-256 hash over every
+/// other generated value, to summarize the build provenance in one short token.
+///
+/// The hash is computed over the canonical form of the values, so external tooling can recompute it:
+///
+/// 1. Every generated `KEY=value` pair, except `VERGEN_HASH` and the instructions named in the `exclude` field.
+/// 1. Sorted by the instruction name, byte-wise.
+/// 1. Each pair written as `KEY=value` followed by a `\n` (line feed), and encoded as UTF-8.
+/// 1. The SHA-256 digest of the result, as lower case hex.
+///
+/// i.e. `printf 'VERGEN_GIT_BRANCH=main\nVERGEN_GIT_SHA=abc123\n' | sha256sum`
+///
+/// * By default the build date/time, commit age, and script duration instructions are excluded, so the hash is reproducible.
+/// * Set the `length` field to truncate the hex digest to that many characters.
+/// * **NOTE** - The hash is computed after the [`Sticky`](crate::Sticky) values and the banner are applied.
+///
+/// # Example
+///
+/// ```
+/// use vergen::Config;
+///
+/// let mut config = Config::default();
+/// *config.hash_mut().enabled_mut() = true;
+/// *config.hash_mut().length_mut() = Some(12);
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
+pub struct ValueHash {
+    /// Enable/Disable the `VERGEN_HASH` instruction
+    enabled: bool,
+    /// The number of hex characters to keep, or None for the full 64 character digest.
+    length: Option<usize>,
+    /// The instructions that are left out of the hash, i.e. `VERGEN_BUILD_TIMESTAMP`.
+    exclude: Vec<String>,
+    /// The fields set through their `set_*` setter, which win in a [merge](crate::Config::merge)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    set_fields: SetFields,
+}
+
+impl ValueHash {
+    /// Enable/Disable the `VERGEN_HASH` instruction
+    pub(crate) fn enabled(&self) -> &bool {
+        &self.enabled
+    }
+
+    /// Enable/Disable the `VERGEN_HASH` instruction
+    pub fn enabled_mut(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+
+    /// Enable/Disable the `VERGEN_HASH` instruction
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "enabled");
+        self.enabled = enabled;
+    }
+
+    /// The number of hex characters to keep, or None for the full 64 character digest.
+    #[cfg(test)]
+    pub(crate) fn length(&self) -> &Option<usize> {
+        &self.length
+    }
+
+    /// The number of hex characters to keep, or None for the full 64 character digest.
+    pub fn length_mut(&mut self) -> &mut Option<usize> {
+        &mut self.length
+    }
+
+    /// The number of hex characters to keep, or None for the full 64 character digest.
+    pub fn set_length(&mut self, length: Option<usize>) {
+        self.set_fields.mark(MERGED_FIELDS, "length");
+        self.length = length;
+    }
+
+    /// The instructions that are left out of the hash, i.e. `VERGEN_BUILD_TIMESTAMP`.
+    #[cfg(test)]
+    pub(crate) fn exclude(&self) -> &Vec<String> {
+        &self.exclude
+    }
+
+    /// The instructions that are left out of the hash, i.e. `VERGEN_BUILD_TIMESTAMP`.
+    pub fn exclude_mut(&mut self) -> &mut Vec<String> {
+        &mut self.exclude
+    }
+}
+
+impl Default for ValueHash {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            length: None,
+            exclude: vec![
+                "VERGEN_BUILD_DATE".to_string(),
+                "VERGEN_BUILD_TIME".to_string(),
+                "VERGEN_BUILD_TIMESTAMP".to_string(),
+                "VERGEN_GIT_COMMIT_AGE_SECONDS".to_string(),
+                "VERGEN_BUILD_SCRIPT_DURATION_MS".to_string(),
+            ],
+            set_fields: SetFields::default(),
+        }
+    }
+}
+
+/// The [`ValueHash`] fields that win in a [merge](crate::Config::merge) once set, see [`SetFields`]
+const MERGED_FIELDS: &[&str] = &["enabled", "length"];
+
+impl ValueHash {
+    pub(crate) fn merge(self, overlay: Self) -> Self {
+        let default = Self::default();
+        let overlay_set = overlay.set_fields;
+        let set = |name| overlay_set.contains(MERGED_FIELDS, name);
+        Self {
+            enabled: pick(
+                self.enabled,
+                overlay.enabled,
+                &default.enabled,
+                set("enabled"),
+            ),
+            length: pick(self.length, overlay.length, &default.length, set("length")),
+            exclude: union(self.exclude, overlay.exclude, &default.exclude),
+            set_fields: self.set_fields.union(overlay_set),
+        }
+    }
+
+    pub(crate) fn validate(&self, errors: &mut Vec<ConfigError>) {
+        if !self.enabled {
+            return;
+        }
+
+        if let Some(length) = self.length {
+            if length == 0 || length > 64 {
+                errors.push(ConfigError::new(
+                    "hash.length",
+                    format!("Some({})", length),
+                    "use a length between 1 and 64, or None for the full digest",
+                ));
+            }
+        }
+
+        for name in self.exclude.iter().filter(|name| find_key(name).is_none()) {
+            errors.push(ConfigError::new(
+                "hash.exclude",
+                format!("{:?}", name),
+                "use the name of a generated instruction, i.e. 'VERGEN_BUILD_TIMESTAMP'",
+            ));
+        }
+    }
+}
+
+/// The canonical form of the values, see [`ValueHash`]
+fn canonical(hash: &ValueHash, config: &Config) -> String {
+    let mut pairs: Vec<(&str, &str)> = config
+        .cfg_map()
+        .iter()
+        .filter(|(key, _)| **key != VergenKey::Hash)
+        .filter_map(|(key, value)| value.as_deref().map(|value| (key.name(), value)))
+        .filter(|(name, _)| !hash.exclude.iter().any(|excluded| excluded == name))
+        .collect();
+    pairs.sort_unstable();
+
+    let mut canonical = String::new();
+    for (name, value) in pairs {
+        canonical.push_str(name);
+        canonical.push('=');
+        canonical.push_str(value);
+        canonical.push('\n');
+    }
+    canonical
+}
+
+/// Add the `VERGEN_HASH` instruction over the other generated values
+pub(crate) fn add_hash(hash: &ValueHash, config: &mut Config) {
+    let digest = Sha256::digest(canonical(hash, config).as_bytes());
+    let mut hex = String::with_capacity(64);
+    for byte in digest {
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    if let Some(length) = hash.length {
+        hex.truncate(length);
+    }
+
+    let _ = config.cfg_map_mut().insert(VergenKey::Hash, Some(hex));
+}
+
+#[cfg(test)]
+mod test {
+    use super::{add_hash, canonical, ValueHash};
+    use crate::config::{Config, VergenKey};
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        for (key, value) in &[
+            (VergenKey::Sha, "abc123"),
+            (VergenKey::Branch, "main"),
+            (VergenKey::BuildTimestamp, "2021-02-12T01:54:15Z"),
+        ] {
+            let _ = config
+                .cfg_map_mut()
+                .insert(*key, Some((*value).to_string()));
+        }
+        config
+    }
+
+    fn hash(value_hash: &ValueHash, config: &mut Config) -> String {
+        add_hash(value_hash, config);
+        config.cfg_map()[&VergenKey::Hash].clone().unwrap()
+    }
+
+    #[test]
+    fn canonical_form() {
+        assert_eq!(
+            canonical(&ValueHash::default(), &config()),
+            "VERGEN_GIT_BRANCH=main\nVERGEN_GIT_SHA=abc123\n"
+        );
+    }
+
+    #[test]
+    fn pinned_hash() {
+        let mut config = config();
+        assert_eq!(
+            hash(&ValueHash::default(), &mut config),
+            "b6fd399c03bca17204d5d6f9f34ac517dafe836b35aa481e8f36d8521646219c"
+        );
+
+        // The previous hash isn't part of the next one
+        let value_hash = ValueHash {
+            length: Some(12),
+            ..ValueHash::default()
+        };
+        assert_eq!(hash(&value_hash, &mut config), "b6fd399c03bc");
+    }
+
+    #[test]
+    fn excluded_keys_are_configurable() {
+        let mut value_hash = ValueHash::default();
+        value_hash.exclude.clear();
+        assert!(canonical(&value_hash, &config())
+            .starts_with("VERGEN_BUILD_TIMESTAMP=2021-02-12T01:54:15Z\n"));
+    }
+
+    #[test]
+    fn invalid_settings() {
+        let mut value_hash = ValueHash {
+            length: Some(65),
+            ..ValueHash::default()
+        };
+        value_hash.exclude.push("VERGEN_BUILD_TIMESTMP".to_string());
+        let mut errors = vec![];
+        value_hash.validate(&mut errors);
+        assert!(errors.is_empty());
+
+        value_hash.enabled = true;
+        value_hash.validate(&mut errors);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].field(), "hash.length");
+        assert_eq!(errors[1].field(), "hash.exclude");
+    }
+}
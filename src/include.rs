@@ -0,0 +1,185 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` include file output
+
+use crate::{
+    config::Config,
+    output::{out_dir, write_atomic},
+};
+use anyhow::Result;
+use std::fmt::Write;
+
+/// The generated values, sorted by name, which both `vergen_pretty` and `VERGEN_MAP` are built from
+fn values(config: &Config) -> Vec<(&str, &String)> {
+    let mut values: Vec<(&str, &String)> = config
+        .cfg_map()
+        .iter()
+        .filter_map(|(key, value)| value.as_ref().map(|value| (key.name(), value)))
+        .collect();
+    values.sort_unstable_by_key(|(name, _)| *name);
+    values
+}
+
+/// The report of the generated values, one `NAME  value` line each, with the values aligned
+fn pretty(values: &[(&str, &String)]) -> String {
+    let width = values.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+
+    let mut pretty = String::new();
+    for (name, value) in values {
+        let _ = writeln!(pretty, "{:width$}  {}", name, value, width = width);
+    }
+    pretty
+}
+
+/// The `VERGEN_MAP` entries, one `("NAME", "value"),` line each
+fn map_entries(values: &[(&str, &String)]) -> String {
+    let mut entries = String::new();
+    for (name, value) in values {
+        let _ = writeln!(entries, "    ({:?}, {:?}),", name, value);
+    }
+    entries
+}
+
+/// A `compile_error!` for each required instruction without a value, with the reason it's missing
+fn compile_errors(unavailable: &[(&str, String)]) -> String {
+    let mut errors = String::new();
+    for (name, reason) in unavailable {
+        let message = format!("vergen: {} was not available: {}", name, reason);
+        let _ = writeln!(errors, "compile_error!({:?});", message);
+    }
+    if !errors.is_empty() {
+        errors.push('\n');
+    }
+    errors
+}
+
+/// The contents of the `vergen.rs`
+fn vergen_rs(config: &Config, unavailable: &[(&str, String)]) -> String {
+    let values = values(config);
+    format!(
+        "// Generated by vergen\n\n\
+         {}\
+         /// The generated values, one aligned `NAME  value` line each\n\
+         #[allow(dead_code)]\n\
+         pub fn vergen_pretty() -> String {{\n    \
+         String::from({:?})\n\
+         }}\n\n\
+         /// The generated values as `(NAME, value)` pairs, sorted by name\n\
+         #[allow(dead_code)]\n\
+         pub static VERGEN_MAP: &[(&str, &str)] = &[\n\
+         {}\
+         ];\n",
+        compile_errors(unavailable),
+        pretty(&values),
+        map_entries(&values)
+    )
+}
+
+/// Write the `vergen.rs` to `OUT_DIR`, failing to compile if a required instruction is unavailable
+pub(crate) fn output_include(config: &Config, unavailable: &[(&str, String)]) -> Result<()> {
+    write_atomic(
+        &out_dir()?.join("vergen.rs"),
+        &vergen_rs(config, unavailable),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{pretty, values, vergen_rs};
+    use crate::config::{Config, VergenKey};
+    use std::{env, fs, path::Path};
+    use trybuild::TestCases;
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Sha, Some("abc123".to_string()));
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Branch, Some("say \"hi\" \\ ü".to_string()));
+        let _ = config.cfg_map_mut().insert(VergenKey::TagCount, None);
+        config
+    }
+
+    #[test]
+    fn aligned_report() {
+        assert_eq!(
+            pretty(&values(&config())),
+            "VERGEN_GIT_BRANCH  say \"hi\" \\ ü\nVERGEN_GIT_SHA     abc123\n"
+        );
+        assert_eq!(pretty(&values(&Config::default())), "");
+    }
+
+    #[test]
+    fn sorted_by_name() {
+        let mut config = Config::default();
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::BuildTimestamp, Some("now".to_string()));
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::BuildSemver, Some("1.0.0".to_string()));
+        let names: Vec<&str> = values(&config).iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, ["VERGEN_BUILD_SEMVER", "VERGEN_BUILD_TIMESTAMP"]);
+        assert!(vergen_rs(&config, &[]).contains(
+            "    (\"VERGEN_BUILD_SEMVER\", \"1.0.0\"),\n    (\"VERGEN_BUILD_TIMESTAMP\", \"now\"),\n"
+        ));
+    }
+
+    /// Build the `tests/ui` cases, with the given `vergen.rs` in their `OUT_DIR`
+    ///
+    /// The `OUT_DIR` is laid out like a build script's, next to the test binary, so `trybuild` replaces it with
+    /// `$OUT_DIR[name]` in the expected compiler output.
+    fn include(name: &str, vergen_rs: &str, cases: fn(&TestCases)) {
+        let exe = env::current_exe().unwrap();
+        let build = exe
+            .parent()
+            .and_then(Path::parent)
+            .unwrap()
+            .join("build")
+            .join(format!("{}-0000000000000000", name));
+        let dir = build.join("out");
+        let _ = fs::remove_dir_all(&build);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("vergen.rs"), vergen_rs).unwrap();
+        env::set_var("OUT_DIR", &dir);
+        cases(&TestCases::new());
+        env::remove_var("OUT_DIR");
+        fs::remove_dir_all(&build).unwrap();
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn consumer_compiles() {
+        include("vergen_include", &vergen_rs(&config(), &[]), |cases| {
+            cases.pass("tests/ui/pass/include.rs");
+        });
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn required_unavailable() {
+        let unavailable = [
+            (
+                "VERGEN_GIT_SEMVER",
+                "no tag is reachable from HEAD".to_string(),
+            ),
+            (
+                "VERGEN_GIT_TAG_COUNT",
+                "the git feature failed, \"not a repository\"".to_string(),
+            ),
+        ];
+        include(
+            "vergen_include_required",
+            &vergen_rs(&config(), &unavailable),
+            |cases| cases.compile_fail("tests/ui/fail/include_required.rs"),
+        );
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` inspection of the generated values, outside of a build script
+
+use crate::{config::Instructions, gen::gather, package::suffix};
+use anyhow::Result;
+use std::{env, path::PathBuf, time::Duration};
+
+/// The values a build script would generate, see [`inspect`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Inspection {
+    /// The `rustc-env` values as `(name, value)` pairs, in output order
+    values: Vec<(String, String)>,
+    /// The `warning` messages, in output order
+    warnings: Vec<String>,
+    /// The git directory of the discovered repository, or None if no repository was opened
+    repo_path: Option<PathBuf>,
+    /// How long each gathered feature took, in feature order
+    timings: Vec<(String, Duration)>,
+}
+
+impl Inspection {
+    /// The `rustc-env` values as `(name, value)` pairs, in output order
+    #[must_use]
+    pub fn values(&self) -> &Vec<(String, String)> {
+        &self.values
+    }
+
+    /// The `warning` messages, in output order
+    #[must_use]
+    pub fn warnings(&self) -> &Vec<String> {
+        &self.warnings
+    }
+
+    /// The git directory of the discovered repository, or None if no repository was opened
+    #[must_use]
+    pub fn repo_path(&self) -> &Option<PathBuf> {
+        &self.repo_path
+    }
+
+    /// How long each gathered feature took, in feature order
+    #[must_use]
+    pub fn timings(&self) -> &Vec<(String, Duration)> {
+        &self.timings
+    }
+}
+
+/// Gather the values as [`vergen`](crate::vergen) does, without writing the instructions or the output files
+///
+/// This is what the `cargo vergen` subcommand prints, to see why a value isn't the one you expect without rerunning
+/// the build.  The environment is read as in a build script, so a value that comes from a variable cargo only sets
+/// for build scripts, i.e. `TARGET` or `PROFILE`, is skipped with a warning when the variable isn't set.
+///
+/// * The values of each [`Package`](crate::Package) are named with the package suffix, as in the instructions.
+/// * **NOTE** - The [`Sticky`](crate::Sticky) cache and the dependency licenses file are still written to `OUT_DIR`
+///   when they're enabled.
+///
+/// # Errors
+///
+/// The errors of [`vergen`](crate::vergen), other than those of writing the instructions.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// use vergen::{inspect, Config};
+///
+/// # pub fn main() -> Result<()> {
+/// let inspection = inspect(&Config::default())?;
+/// for (name, value) in inspection.values() {
+///     eprintln!("{} = {}", name, value);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn inspect(config: &crate::Config) -> Result<Inspection> {
+    let (instructions, gathered, override_warnings) =
+        gather(config, env::vars(), base_dir(config))?;
+
+    let mut values: Vec<(String, String)> = gathered
+        .cfg_map()
+        .iter()
+        .filter_map(|(key, value)| {
+            value
+                .as_ref()
+                .map(|value| (key.name().to_string(), value.clone()))
+        })
+        .collect();
+    values.extend(
+        gathered
+            .commands()
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone())),
+    );
+    for (package, package_values) in gathered.packages() {
+        let suffix = suffix(package);
+        values.extend(package_values.iter().filter_map(|(key, value)| {
+            value
+                .as_ref()
+                .map(|value| (format!("{}_{}", key.name(), suffix), value.clone()))
+        }));
+    }
+
+    let mut warnings = gathered.warnings().clone();
+    warnings.extend(override_warnings.unwrap_or_default());
+
+    Ok(Inspection {
+        values,
+        warnings,
+        repo_path: gathered.repo_path().clone(),
+        timings: gathered
+            .timings()
+            .iter()
+            .filter(|(name, _)| requested(&instructions, name))
+            .map(|(name, elapsed)| ((*name).to_string(), *elapsed))
+            .collect(),
+    })
+}
+
+/// Does the named feature have any instruction to generate, the others return without doing anything
+fn requested(instructions: &Instructions, feature: &str) -> bool {
+    let from_repo =
+        (*instructions.build_number() || *instructions.release_url()) && feature == "git";
+    from_repo
+        || instructions
+            .placeholder_config()
+            .cfg_map()
+            .iter()
+            .any(|(key, value)| value.is_some() && key.subsystem() == feature)
+}
+
+/// The directory the repository is discovered from, as for [`vergen`](crate::vergen)
+#[cfg(feature = "git")]
+fn base_dir(config: &crate::Config) -> Option<PathBuf> {
+    if *config.git().enabled() {
+        config.git().base_dir().clone()
+    } else {
+        None
+    }
+}
+
+/// The directory the repository is discovered from, as for [`vergen`](crate::vergen)
+#[cfg(not(feature = "git"))]
+fn base_dir(_config: &crate::Config) -> Option<PathBuf> {
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::inspect;
+    use crate::{
+        config::Instructions,
+        testutils::{setup, teardown},
+    };
+
+    #[test]
+    #[serial_test::serial]
+    fn values_and_timings() {
+        setup();
+        let mut config = Instructions::default();
+        config.add_command("INSPECT_ECHO", "echo", &["inspected"]);
+        let inspection = inspect(&config).unwrap();
+        teardown();
+
+        assert!(inspection
+            .values()
+            .iter()
+            .any(|(name, value)| name == "INSPECT_ECHO" && value == "inspected"));
+        #[cfg(feature = "build")]
+        assert!(inspection
+            .values()
+            .iter()
+            .any(|(name, _)| name == "VERGEN_BUILD_TIMESTAMP"));
+        #[cfg(feature = "git")]
+        {
+            assert!(inspection.repo_path().is_some());
+            assert!(inspection.timings().iter().any(|(name, _)| name == "git"));
+        }
+        assert!(inspection.timings().iter().all(|(name, _)| name != "hg"));
+    }
+}
@@ -54,7 +54,7 @@
 //! ```
 //!
 //! ## Features
-//! `vergen` has five feature toggles allowing you to customize your output.
+//! `vergen` has eleven feature toggles allowing you to customize your output.
 //!
 //! | Feature | Enables |
 //! | ------- | ------- |
@@ -63,8 +63,36 @@
 //! |   git   | `VERGEN_GIT_*` instructions and the `cargo:rerun-if-changed` instructions  |
 //! |  rustc  | `VERGEN_RUSTC_*` instructions |
 //! |   si    | `VERGEN_SYSINFO_*` instructions |
+//! |  hash   | The `VERGEN_HASH` instruction and the `Cargo.lock` checksums, see `ValueHash` |
+//! |   hg    | `VERGEN_HG_*` instructions, see [`Vcs`] |
+//! | licenses | The `VERGEN_DEPENDENCY_LICENSES` file of dependency licenses, see `Licenses` |
+//! | macros | The `VergenFill` derive, to fill a struct of yours from the instructions |
+//! | pretty | The `PrettyPrinter`, to print the values as a table at runtime |
+//! | inspect | `inspect`, to gather the values outside of a build script, as the `cargo vergen` subcommand does |
 //!
-//! **NOTE** - All five features are enabled by default.
+//! **NOTE** - All six features are enabled by default, the `hg`, `licenses`, `macros`, `pretty`, and `inspect`
+//! features are not.
+//!
+//! Each feature only pulls in its own dependencies, i.e. `time` for `build` and `git`, `git2` for `git`, `sha2` for
+//! `hash`, and `sysinfo` for `si`, so a build script that disables the default features and enables one or two
+//! compiles little more than `vergen` itself.
+//!
+//! The `test-util` feature, which is off by default, exposes the `ClockProvider`, `GitProvider`, and
+//! `RustcProvider` traits along with the `FixedClock`, `FakeRepo`, and `FakeRustc` implementations.
+//! Install them on the [`Config`] to generate deterministic instructions in tests.  It also exposes
+//! `parse_instructions`, which reads the cargo instructions a build script wrote back into a `ParsedOutput`.
+//!
+//! The `serde` feature, which is off by default, implements `Serialize` and `Deserialize` for the [`Config`],
+//! so it can be read from a file.  Unknown fields are an error, enable the `serde-ignore-unknown` feature to
+//! ignore them instead.  The data providers are never serialized.
+//!
+//! The deprecated `generate_cargo_keys` and `ConstantsFlags`, with the `build`, `cargo`, `git`, and `rustc` features,
+//! emit the `vergen` 3.x instructions under their 3.x names, to ease an upgrade one build script at a time.
+//!
+//! On a `wasm32-wasi` build host, `vergen` compiles with the default features, but can't open the repository, run
+//! `rustc`, or read the system information.  Those instructions are skipped with a warning, except the `rustc` ones
+//! when `rustc.version_override` is set, and a local timezone falls back to UTC.  The `build` and `cargo`
+//! instructions, and `SOURCE_DATE_EPOCH`, work as usual.
 //!
 //! ## Sample Output
 //! If all features are enabled and the default [`Config`] is used the build script will generate instructions for cargo similar to below.
@@ -104,6 +132,11 @@
 //! | `VERGEN_BUILD_TIME` | 23:28:39.493201 |
 //! | `VERGEN_BUILD_TIMESTAMP` | 2021-02-25T23:28:39.493201+00:00 |
 //! | `VERGEN_BUILD_SEMVER` | 5.0.0 |
+//! | `VERGEN_BUILD_SEMVER_MAJOR` | 5 |
+//! | `VERGEN_BUILD_SEMVER_MINOR` | 0 |
+//! | `VERGEN_BUILD_SEMVER_PATCH` | 0 |
+//! | `VERGEN_BUILD_SEMVER_PRE` | |
+//! | `VERGEN_BUILD_CPU_COUNT` | 8 |
 //! | See [`Git`](crate::Git) to configure the following |
 //! | `VERGEN_GIT_BRANCH` | feature/fun |
 //! | `VERGEN_GIT_COMMIT_DATE` | 2021-02-24 |
@@ -113,6 +146,25 @@
 //! | `VERGEN_GIT_SEMVER_LIGHTWEIGHT` | feature-test |
 //! | `VERGEN_GIT_SHA` | f49246ce334567bff9f950bfd0f3078184a2738a |
 //! | `VERGEN_GIT_SHA_SHORT` | f49246c |
+//! | `VERGEN_GIT_TRACKED_FILES` | 42 |
+//! | `VERGEN_GIT_COMMIT_AGE_SECONDS` | 86400 |
+//! | `VERGEN_GIT_COMMITS_SINCE_TAG` | 3 |
+//! | `VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS` | Fix the docs; Add a feature; Bump the version |
+//! | `VERGEN_GIT_SIGNED` | true |
+//! | `VERGEN_GIT_SIGNER` | 4AEE18F83AFDEB23 |
+//! | `VERGEN_GIT_BRANCH_COUNT` | 12 |
+//! | `VERGEN_GIT_TAG_COUNT` | 87 |
+//! | `VERGEN_GIT_ROOT` | /Users/yoda/projects/rust-lang/vergen |
+//! | `VERGEN_GIT_WORKTREE` | /Users/yoda/projects/rust-lang/vergen-hotfix |
+//! | `VERGEN_GIT_TREE_HASH` | 3b18e512dba79e4c8300dd08aeb37f8e728b8dad |
+//! | `VERGEN_GIT_CONTRIBUTOR_COUNT` | 27 |
+//! | `VERGEN_GIT_MERGE_BASE` | 2e4a4b1f4b6e2dd6d7a5c0f3e9e0f38a3b7c1d22 |
+//! | `VERGEN_GIT_OBJECT_FORMAT` | sha1 |
+//! | `VERGEN_GIT_PARENT_COUNT` | 1 |
+//! | See `Hg` to configure the following |
+//! | `VERGEN_HG_BRANCH` | default |
+//! | `VERGEN_HG_REV` | 42 |
+//! | `VERGEN_HG_SHA` | 4fa5e6f6a7e1e6f5f0f4c8a1b2d3e4f5a6b7c8d9 |
 //! | See [`Rustc`](crate::Rustc) to configure the following |
 //! | `VERGEN_RUSTC_CHANNEL` | nightly |
 //! | `VERGEN_RUSTC_COMMIT_DATE` | 2021-02-24 |
@@ -124,6 +176,23 @@
 //! | `VERGEN_CARGO_FEATURES` | git,build |
 //! | `VERGEN_CARGO_PROFILE` | debug |
 //! | `VERGEN_CARGO_TARGET_TRIPLE` | x86_64-unknown-linux-gnu |
+//! | `VERGEN_CARGO_PKG_DESCRIPTION` | Generate 'cargo:rustc-env' instructions via 'build.rs' for use in your code via the 'env!' macro |
+//! | `VERGEN_CARGO_PKG_AUTHORS` | Jason Ozias <jason.g.ozias@gmail.com> |
+//! | `VERGEN_CARGO_PKG_HOMEPAGE` | https://github.com/rustyhorde/vergen |
+//! | `VERGEN_CARGO_PKG_REPOSITORY` | https://github.com/rustyhorde/vergen |
+//! | `VERGEN_CARGO_PKG_LICENSE` | MIT OR Apache-2.0 |
+//! | `VERGEN_CARGO_PKG_LICENSE_FILE` | LICENSE.txt |
+//! | `VERGEN_CARGO_TARGET_OS` | linux |
+//! | `VERGEN_CARGO_TARGET_ARCH` | x86_64 |
+//! | `VERGEN_CARGO_TARGET_ENV` | gnu |
+//! | `VERGEN_CARGO_TARGET_POINTER_WIDTH` | 64 |
+//! | `VERGEN_CARGO_TARGET_ENDIAN` | little |
+//! | `VERGEN_CARGO_LIBC_VERSION` | glibc 2.36 |
+//! | `VERGEN_TARGET_TRIPLE` | x86_64-unknown-linux-gnu |
+//! | `VERGEN_TARGET_OS` | linux |
+//! | `VERGEN_TARGET_ARCH` | x86_64 |
+//! | `VERGEN_TARGET_ENV` | gnu |
+//! | `VERGEN_IS_CROSS_COMPILE` | false |
 //! | See [`Sysinfo`](crate::Sysinfo) to configure the following |
 //! | `VERGEN_SYSINFO_NAME` | Manjaro Linux |
 //! | `VERGEN_SYSINFO_OS_VERSION` | Linux  Manjaro Linux |
@@ -134,6 +203,15 @@
 //! | `VERGEN_SYSINFO_CPU_NAME` | cpu0,cpu1,cpu2,cpu3,cpu4,cpu5,cpu6,cpu7 |
 //! | `VERGEN_SYSINFO_CPU_BRAND` | AMD Ryzen Threadripper 1900X 8-Core Processor |
 //! | `VERGEN_SYSINFO_CPU_FREQUENCY` | 3792 |
+//! | See [`Config`](crate::Config) to configure the following |
+//! | `VERGEN_BANNER` | vergen 5.0.0 (f49246c 2021-02-24, debug) |
+//! | `VERGEN_HASH` | 4f8bd4b5e0a3 |
+//! | `VERGEN_BUILD_SCRIPT_DURATION_MS` | 182 |
+//! | `VERGEN_BUILD_NUMBER` | 1342+2 |
+//! | `VERGEN_DEPENDENCY_LICENSES` | /target/debug/build/vergen-c2a5a8b8/out/vergen_licenses.txt |
+//! | `VERGEN_ENABLED_FEATURES` | build,cargo,git,rustc |
+//! | `VERGEN_PROVENANCE_JSON` | {"schema":"vergen-provenance/1",...} |
+//! | `VERGEN_RELEASE_URL` | https://github.com/rustyhorde/vergen/releases/tag/5.0.0 |
 //!
 //! ## Usage
 //!
@@ -176,6 +254,36 @@
 //! println!("git semver: {}", env!("VERGEN_GIT_SEMVER"));
 //! ```
 //!
+//! ### Fill a struct
+//! With the `macros` feature, `vergen` is also a regular dependency, usually with the other features disabled, and
+//! `#[derive(VergenFill)]` writes the [`env!`](std::env!) calls for you.  A `&'static str` field is filled with
+//! `env!`, an `Option<&'static str>` field with [`option_env!`](std::option_env!).  A key that isn't the name of an
+//! instruction fails the build.
+//!
+//! ```toml
+//! [dependencies]
+//! vergen = { version = "6", default-features = false, features = ["macros"] }
+//! ```
+//!
+//! ```
+//! # #[cfg(feature = "macros")]
+//! # fn fill() {
+//! use vergen::VergenFill;
+//!
+//! #[derive(VergenFill)]
+//! struct BuildMeta {
+//!     #[vergen(key = "BUILD_TIMESTAMP")]
+//!     built_at: &'static str,
+//!     #[vergen(key = "GIT_SEMVER")]
+//!     semver: &'static str,
+//!     // The key defaults to the field name, VERGEN_RUSTC_CHANNEL
+//!     rustc_channel: Option<&'static str>,
+//! }
+//!
+//! println!("{} built at {}", BuildMeta::COLLECTED.semver, BuildMeta::COLLECTED.built_at);
+//! # }
+//! ```
+//!
 //! [build scripts]: https://doc.rust-lang.org/cargo/reference/build-scripts.html
 //! [cargo:rustc-env]: https://doc.rust-lang.org/cargo/reference/build-scripts.html#rustc-env
 //! [cargo:rerun-if-changed]: https://doc.rust-lang.org/cargo/reference/build-scripts.html#rerun-if-changed
@@ -335,19 +443,84 @@
     rustdoc::private_intra_doc_links,
 ))]
 
+mod banner;
+mod buildinfo;
+mod built;
+mod c_header;
+mod collision;
+mod command;
+#[cfg(all(
+    feature = "build",
+    feature = "cargo",
+    feature = "git",
+    feature = "rustc"
+))]
+mod compat;
+mod condition;
 mod config;
 mod constants;
+mod emit_cfg;
 mod error;
 mod feature;
 mod gen;
+#[cfg(feature = "hash")]
+mod hash;
+mod include;
+#[cfg(feature = "inspect")]
+mod inspect;
+#[cfg(feature = "licenses")]
+mod licenses;
+mod links;
+mod merge;
+mod output;
+mod overrides;
+mod overrides_file;
+mod package;
+#[cfg(any(test, feature = "test-util"))]
+mod parse;
+#[cfg(any(feature = "git", feature = "hg"))]
+mod paths;
+#[cfg(feature = "pretty")]
+mod pretty;
+mod provenance;
+mod provider;
+mod release;
+mod repeat;
+mod sanitize;
+mod sticky;
+mod summary;
+mod syntax;
+mod truncate;
 
+pub use crate::buildinfo::BuildInfo;
+pub use crate::built::BuiltCompat;
+pub use crate::c_header::CHeader;
+#[cfg(all(
+    feature = "build",
+    feature = "cargo",
+    feature = "git",
+    feature = "rustc"
+))]
+#[allow(deprecated)]
+pub use crate::compat::{generate_cargo_keys, ConstantsFlags};
+pub use crate::condition::{Condition, EmissionContext};
+pub use crate::config::DuplicatePolicy;
 pub use crate::config::Instructions as Config;
+pub use crate::config::NonUtf8Policy;
+pub use crate::config::Vcs;
+pub use crate::config::WarningLevel;
+pub use crate::emit_cfg::EmitCfg;
+pub use crate::error::ConfigError;
 #[cfg(feature = "build")]
 pub use crate::feature::Build;
 #[cfg(feature = "cargo")]
 pub use crate::feature::Cargo;
 #[cfg(feature = "git")]
 pub use crate::feature::Git;
+#[cfg(feature = "hg")]
+pub use crate::feature::Hg;
+#[cfg(feature = "build")]
+pub use crate::feature::Precision;
 #[cfg(feature = "rustc")]
 pub use crate::feature::Rustc;
 #[cfg(feature = "git")]
@@ -356,14 +529,46 @@ pub use crate::feature::SemverKind;
 pub use crate::feature::ShaKind;
 #[cfg(feature = "si")]
 pub use crate::feature::Sysinfo;
+#[cfg(feature = "cargo")]
+pub use crate::feature::TargetNames;
 #[cfg(any(feature = "git", feature = "build"))]
 pub use crate::feature::TimeZone;
 #[cfg(any(feature = "git", feature = "build"))]
 pub use crate::feature::TimestampKind;
 pub use crate::gen::vergen;
+#[cfg(feature = "hash")]
+pub use crate::hash::ValueHash;
+#[cfg(feature = "inspect")]
+pub use crate::inspect::{inspect, Inspection};
+#[cfg(feature = "licenses")]
+pub use crate::licenses::Licenses;
+pub use crate::output::OutputFormat;
+pub use crate::output::OutputTarget;
+pub use crate::package::Package;
+#[cfg(feature = "test-util")]
+pub use crate::parse::{parse_instructions, ParsedOutput};
+#[cfg(feature = "pretty")]
+pub use crate::pretty::{ColorChoice, Overflow, PrettyPrinter};
+#[cfg(all(
+    feature = "test-util",
+    any(feature = "build", feature = "git", feature = "rustc")
+))]
+pub use crate::provider::Providers;
+#[cfg(all(feature = "test-util", any(feature = "build", feature = "git")))]
+pub use crate::provider::{ClockProvider, FixedClock, SystemClock};
+#[cfg(all(feature = "test-util", feature = "git"))]
+pub use crate::provider::{FakeRepo, GitProvider, PathCommit};
+#[cfg(all(feature = "test-util", feature = "rustc"))]
+pub use crate::provider::{FakeRustc, RustcProvider, SystemRustc};
+pub use crate::sanitize::Sanitize;
+pub use crate::sticky::Sticky;
+pub use crate::syntax::CargoSyntax;
+pub use crate::truncate::Truncate;
+#[cfg(feature = "macros")]
+pub use vergen_macros::VergenFill;
 
-#[cfg(not(feature = "si"))]
-use cfg_if as _;
+#[cfg(all(test, not(feature = "serde")))]
+use serde_json as _;
 #[cfg(all(test, not(feature = "cargo")))]
 use serial_test as _;
 
@@ -0,0 +1,361 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` dependency license output
+
+use crate::{
+    command,
+    error::{ConfigError, Error},
+    merge::{pick, SetFields},
+    output::{out_dir, write_atomic},
+};
+use anyhow::Result;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The license of a dependency without a `license` field
+const UNKNOWN: &str = "UNKNOWN";
+/// Never touch the network or the `Cargo.lock` from a build script
+const METADATA_ARGS: &[&str] = &["metadata", "--format-version", "1", "--offline", "--locked"];
+
+/// Configuration for writing the licenses of the dependencies to a file
+///
+/// When the `enabled` field is true, a file with a `name@version: license` line for each dependency of the package,
+/// i.e. `serde@1.0.136: MIT OR Apache-2.0`, is written, and the `VERGEN_DEPENDENCY_LICENSES` instruction is its path.
+/// The list can be too long for an instruction value, so a binary includes the file to print it.
+///
+/// ```text
+/// const LICENSES: &str = include_str!(env!("VERGEN_DEPENDENCY_LICENSES"));
+/// ```
+///
+/// * The file is written to `$OUT_DIR/vergen_licenses.txt`, unless the `path` field is set.
+/// * The licenses come from `cargo metadata`, which is run with `--offline` and `--locked`, so the `Cargo.lock`
+///   must be up to date.  Only the dependencies for the target platform are listed.
+/// * The license is the SPDX expression in the `license` field of the dependency's manifest, or `UNKNOWN` when it
+///   doesn't have one, i.e. when it only has a `license-file`.
+/// * The lines are sorted, and the package itself isn't listed.
+/// * If the `normal_only` field is true, only the normal dependencies, and their normal dependencies, are listed.
+///   Otherwise the build and dev dependencies are listed too.
+///
+/// # Example
+///
+/// ```
+/// use vergen::Config;
+///
+/// let mut config = Config::default();
+/// *config.licenses_mut().enabled_mut() = true;
+/// *config.licenses_mut().normal_only_mut() = true;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
+pub struct Licenses {
+    /// Enable/Disable the dependency license file
+    enabled: bool,
+    /// The path of the file, or None for `$OUT_DIR/vergen_licenses.txt`.
+    path: Option<PathBuf>,
+    /// Only list the normal dependencies, without the build and dev dependencies.
+    normal_only: bool,
+    /// The fields set through their `set_*` setter, which win in a [merge](crate::Config::merge)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    set_fields: SetFields,
+}
+
+impl Licenses {
+    /// Enable/Disable the dependency license file
+    pub(crate) fn enabled(&self) -> &bool {
+        &self.enabled
+    }
+
+    /// Enable/Disable the dependency license file
+    pub fn enabled_mut(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+
+    /// Enable/Disable the dependency license file
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "enabled");
+        self.enabled = enabled;
+    }
+
+    /// The path of the file, or None for `$OUT_DIR/vergen_licenses.txt`.
+    pub fn path_mut(&mut self) -> &mut Option<PathBuf> {
+        &mut self.path
+    }
+
+    /// The path of the file, or None for `$OUT_DIR/vergen_licenses.txt`.
+    pub fn set_path(&mut self, path: Option<PathBuf>) {
+        self.set_fields.mark(MERGED_FIELDS, "path");
+        self.path = path;
+    }
+
+    /// Only list the normal dependencies, without the build and dev dependencies.
+    pub fn normal_only_mut(&mut self) -> &mut bool {
+        &mut self.normal_only
+    }
+
+    /// Only list the normal dependencies, without the build and dev dependencies.
+    pub fn set_normal_only(&mut self, normal_only: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "normal_only");
+        self.normal_only = normal_only;
+    }
+}
+
+/// The [`Licenses`] fields that win in a [merge](crate::Config::merge) once set, see [`SetFields`]
+const MERGED_FIELDS: &[&str] = &["enabled", "path", "normal_only"];
+
+impl Licenses {
+    pub(crate) fn merge(self, overlay: Self) -> Self {
+        let default = Self::default();
+        let overlay_set = overlay.set_fields;
+        let set = |name| overlay_set.contains(MERGED_FIELDS, name);
+        Self {
+            enabled: pick(
+                self.enabled,
+                overlay.enabled,
+                &default.enabled,
+                set("enabled"),
+            ),
+            path: pick(self.path, overlay.path, &default.path, set("path")),
+            normal_only: pick(
+                self.normal_only,
+                overlay.normal_only,
+                &default.normal_only,
+                set("normal_only"),
+            ),
+            set_fields: self.set_fields.union(overlay_set),
+        }
+    }
+
+    pub(crate) fn validate(&self, errors: &mut Vec<ConfigError>) {
+        if let Some(path) = &self.path {
+            if self.enabled && path.as_os_str().is_empty() {
+                errors.push(ConfigError::new(
+                    "licenses.path",
+                    "\"\"",
+                    "use the path of the file, or None for '$OUT_DIR/vergen_licenses.txt'",
+                ));
+            }
+        }
+    }
+}
+
+/// Run `cargo metadata` for the package with the given manifest
+fn cargo_metadata(manifest_path: &Path, target: Option<&str>) -> Result<Value> {
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
+    let mut command = Command::new(&cargo);
+    let _ = command
+        .args(METADATA_ARGS)
+        .arg("--manifest-path")
+        .arg(manifest_path);
+    if let Some(target) = target {
+        let _ = command.arg("--filter-platform").arg(target);
+    }
+    let output = command::output(&mut command, None).map_err(|e| {
+        Error::Licenses(format!(
+            "unable to run '{}': {}",
+            cargo.to_string_lossy(),
+            e
+        ))
+    })?;
+
+    if output.status.success() {
+        serde_json::from_slice(&output.stdout)
+            .map_err(|e| Error::Licenses(format!("the output isn't valid: {}", e)).into())
+    } else {
+        Err(Error::Licenses(String::from_utf8_lossy(&output.stderr).trim().to_string()).into())
+    }
+}
+
+/// The `name@version: license` lines for the dependencies of the package with the given manifest
+fn license_lines(metadata: &Value, manifest_path: &Path, normal_only: bool) -> Result<Vec<String>> {
+    let invalid = || Error::Licenses("the output isn't valid".to_string());
+    let packages = metadata["packages"].as_array().ok_or_else(invalid)?;
+    let root = packages
+        .iter()
+        .find(|package| {
+            package["manifest_path"]
+                .as_str()
+                .map_or(false, |path| Path::new(path) == manifest_path)
+        })
+        .and_then(|package| package["id"].as_str())
+        .ok_or_else(|| {
+            Error::Licenses(format!(
+                "the package for '{}' wasn't found",
+                manifest_path.display()
+            ))
+        })?;
+    let nodes: BTreeMap<&str, &Vec<Value>> = metadata["resolve"]["nodes"]
+        .as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .filter_map(|node| Some((node["id"].as_str()?, node["deps"].as_array()?)))
+        .collect();
+
+    // A dev dependency can depend on the package itself, so it's only removed at the end
+    let mut dependencies = BTreeSet::new();
+    let mut pending = vec![root];
+    while let Some(id) = pending.pop() {
+        for dep in nodes.get(id).into_iter().flat_map(|deps| deps.iter()) {
+            // A null kind is a normal dependency
+            let normal = dep["dep_kinds"].as_array().map_or(false, |kinds| {
+                kinds.iter().any(|kind| kind["kind"].is_null())
+            });
+            if let Some(dep_id) = dep["pkg"].as_str() {
+                if (normal || !normal_only) && dependencies.insert(dep_id) {
+                    pending.push(dep_id);
+                }
+            }
+        }
+    }
+    let _ = dependencies.remove(root);
+
+    let mut lines: Vec<String> = packages
+        .iter()
+        .filter(|package| {
+            package["id"]
+                .as_str()
+                .map_or(false, |id| dependencies.contains(id))
+        })
+        .map(|package| {
+            let license = match package["license"].as_str() {
+                Some(license) if !license.trim().is_empty() => license.trim(),
+                _ => UNKNOWN,
+            };
+            format!(
+                "{}@{}: {}",
+                package["name"].as_str().unwrap_or_default(),
+                package["version"].as_str().unwrap_or_default(),
+                license
+            )
+        })
+        .collect();
+    lines.sort();
+    Ok(lines)
+}
+
+/// Write the dependency license file for the package with the given manifest, and return its path
+fn write_licenses(
+    licenses: &Licenses,
+    manifest_path: &Path,
+    target: Option<&str>,
+) -> Result<PathBuf> {
+    let metadata = cargo_metadata(manifest_path, target)?;
+    let mut contents = String::new();
+    for line in license_lines(&metadata, manifest_path, licenses.normal_only)? {
+        contents.push_str(&line);
+        contents.push('\n');
+    }
+    let path = match &licenses.path {
+        Some(path) => path.clone(),
+        None => out_dir()?.join("vergen_licenses.txt"),
+    };
+    write_atomic(&path, &contents)?;
+    Ok(path)
+}
+
+/// Write the dependency license file for the package being built, and return its path
+pub(crate) fn output_licenses(licenses: &Licenses) -> Result<PathBuf> {
+    let manifest_dir = env::var_os("CARGO_MANIFEST_DIR")
+        .ok_or_else(|| Error::Licenses("CARGO_MANIFEST_DIR isn't set".to_string()))?;
+    let target = env::var("TARGET").ok();
+    write_licenses(
+        licenses,
+        &Path::new(&manifest_dir).join("Cargo.toml"),
+        target.as_deref(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{write_licenses, Licenses};
+    use std::{env, fs, path::PathBuf};
+
+    fn fixture() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata")
+            .join("licenses")
+            .join("app")
+            .join("Cargo.toml")
+    }
+
+    fn licenses(normal_only: bool) -> String {
+        let path = env::temp_dir().join(format!("vergen_licenses_{}.txt", normal_only));
+        let mut licenses = Licenses::default();
+        *licenses.enabled_mut() = true;
+        *licenses.path_mut() = Some(path.clone());
+        *licenses.normal_only_mut() = normal_only;
+        assert_eq!(write_licenses(&licenses, &fixture(), None).unwrap(), path);
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        contents
+    }
+
+    #[test]
+    fn all_dependencies() {
+        assert_eq!(
+            licenses(false),
+            "build-helper@0.2.0: Apache-2.0\n\
+             leaf@0.1.0: MIT OR Apache-2.0\n\
+             runtime@1.2.3: MIT\n\
+             test-helper@0.3.0: UNKNOWN\n\
+             unlicensed@0.1.0: UNKNOWN\n"
+        );
+    }
+
+    #[test]
+    fn normal_only() {
+        assert_eq!(
+            licenses(true),
+            "leaf@0.1.0: MIT OR Apache-2.0\n\
+             runtime@1.2.3: MIT\n\
+             unlicensed@0.1.0: UNKNOWN\n"
+        );
+    }
+
+    #[test]
+    fn invalid_path() {
+        let mut licenses = Licenses::default();
+        *licenses.path_mut() = Some(PathBuf::new());
+        let mut errors = vec![];
+        licenses.validate(&mut errors);
+        assert!(errors.is_empty());
+        *licenses.enabled_mut() = true;
+        licenses.validate(&mut errors);
+        assert_eq!(errors[0].field(), "licenses.path");
+    }
+
+    #[test]
+    fn missing_lockfile() {
+        let dir = env::temp_dir().join("vergen_licenses_unlocked");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"unlocked\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n[workspace]\n",
+        )
+        .unwrap();
+        fs::write(dir.join("src").join("lib.rs"), "").unwrap();
+        let err = write_licenses(&Licenses::default(), &dir.join("Cargo.toml"), None).unwrap_err();
+        assert!(err.to_string().contains("cargo metadata"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` `links` metadata for the dependent crates
+
+use crate::{
+    config::Config,
+    sticky::find_key,
+    syntax::{write_directive, CargoSyntax},
+};
+use anyhow::Result;
+use std::io::Write;
+
+/// The variable cargo sets to the `links` key of the package being built
+const LINKS_VAR: &str = "CARGO_MANIFEST_LINKS";
+
+/// The name as cargo passes it to the dependents, upper case with `-` replaced by `_`
+fn metadata_key(name: &str) -> String {
+    name.to_ascii_uppercase().replace('-', "_")
+}
+
+/// The `DEP_<LINKS>_<NAME>` variable a dependent's build script reads the value from
+#[cfg(test)]
+fn dep_var(links: &str, name: &str) -> String {
+    format!("DEP_{}_{}", metadata_key(links), metadata_key(name))
+}
+
+/// Record the `links` key of the package, and warn if values were requested for the dependents without one
+pub(crate) fn add_links(names: &[String], vars: &[(String, String)], config: &mut Config) {
+    if names.is_empty() {
+        return;
+    }
+    *config.links_mut() = vars
+        .iter()
+        .find(|(name, _)| name == LINKS_VAR)
+        .map(|(_, links)| links.clone())
+        .filter(|links| !links.is_empty());
+    if config.links().is_none() {
+        config.warnings_mut().push(
+            "vergen: links_metadata is set, but the package has no 'links' key, so its dependents can't read the values"
+                .to_string(),
+        );
+    }
+}
+
+/// Write the `cargo:KEY=value` metadata for the named values, if the package has a `links` key
+pub(crate) fn output_links_metadata<T>(
+    names: &[String],
+    syntax: CargoSyntax,
+    config: &Config,
+    stdout: &mut T,
+) -> Result<()>
+where
+    T: Write,
+{
+    if config.links().is_none() {
+        return Ok(());
+    }
+    for name in names {
+        let value = match find_key(name) {
+            Some(key) => config.cfg_map().get(&key).cloned().flatten(),
+            None => config.commands().get(name).cloned(),
+        };
+        if let Some(value) = value {
+            let key = metadata_key(name);
+            match syntax {
+                CargoSyntax::Modern => {
+                    write_directive(stdout, syntax, "metadata", format!("{}={}", key, value))?;
+                }
+                // Auto is resolved before any output
+                CargoSyntax::Legacy | CargoSyntax::Auto => {
+                    write_directive(stdout, syntax, &key, value)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{add_links, dep_var, metadata_key, output_links_metadata};
+    use crate::{
+        config::{Config, VergenKey},
+        syntax::CargoSyntax,
+    };
+    use std::{env, ffi::OsString, path::PathBuf, process::Command};
+
+    fn config(links: Option<&str>) -> Config {
+        let mut config = Config::default();
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Sha, Some("75b390d".to_string()));
+        let _ = config
+            .commands_mut()
+            .insert("build_id".to_string(), "42".to_string());
+        let vars: Vec<(String, String)> = links
+            .iter()
+            .map(|links| ("CARGO_MANIFEST_LINKS".to_string(), (*links).to_string()))
+            .collect();
+        add_links(&names(), &vars, &mut config);
+        config
+    }
+
+    fn names() -> Vec<String> {
+        ["VERGEN_GIT_SHA", "build_id", "VERGEN_GIT_BRANCH"]
+            .iter()
+            .map(|name| (*name).to_string())
+            .collect()
+    }
+
+    fn metadata(syntax: CargoSyntax, config: &Config) -> String {
+        let mut stdout_buf = vec![];
+        output_links_metadata(&names(), syntax, config, &mut stdout_buf).unwrap();
+        String::from_utf8(stdout_buf).unwrap()
+    }
+
+    #[test]
+    fn key_names() {
+        assert_eq!(metadata_key("build-id"), "BUILD_ID");
+        assert_eq!(
+            dep_var("my-lib", "VERGEN_GIT_SHA"),
+            "DEP_MY_LIB_VERGEN_GIT_SHA"
+        );
+    }
+
+    #[test]
+    fn metadata_syntax() {
+        let config = config(Some("mylib"));
+        assert!(config.warnings().is_empty());
+        assert_eq!(
+            metadata(CargoSyntax::Legacy, &config),
+            "cargo:VERGEN_GIT_SHA=75b390d\ncargo:BUILD_ID=42\n"
+        );
+        assert_eq!(
+            metadata(CargoSyntax::Modern, &config),
+            "cargo::metadata=VERGEN_GIT_SHA=75b390d\ncargo::metadata=BUILD_ID=42\n"
+        );
+    }
+
+    #[test]
+    fn without_links() {
+        let config = config(None);
+        assert_eq!(config.warnings().len(), 1);
+        assert!(config.warnings()[0].contains("no 'links' key"));
+        assert_eq!(metadata(CargoSyntax::Legacy, &config), "");
+    }
+
+    #[test]
+    fn nothing_requested() {
+        let mut config = Config::default();
+        add_links(&[], &[], &mut config);
+        assert!(config.warnings().is_empty());
+    }
+
+    #[test]
+    fn dependent_reads_the_value() {
+        let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata")
+            .join("links");
+        let cargo = env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
+        let output = Command::new(cargo)
+            .args(&["build", "--offline", "--manifest-path"])
+            .arg(fixture.join("Cargo.toml"))
+            .arg("--target-dir")
+            .arg(env::temp_dir().join("vergen_links_target"))
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "{}", stderr);
+        // The downstream build script reports the value it received
+        assert!(
+            stderr.contains("downstream received DEP_UPSTREAM_VERGEN_CARGO_PROFILE=debug"),
+            "{}",
+            stderr
+        );
+    }
+}
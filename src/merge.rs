@@ -0,0 +1,162 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` configuration layering
+
+use std::collections::BTreeMap;
+
+/// The fields of a configuration section that were set through their `set_*` setter
+///
+/// Each field is a bit, at its index in the section's list of merged field names.  The sections are `Copy`, so a set
+/// of names won't do.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub(crate) struct SetFields(u64);
+
+impl SetFields {
+    /// Record that the named field of the list was set
+    pub(crate) fn mark(&mut self, fields: &[&str], name: &str) {
+        self.0 |= bit(fields, name);
+    }
+
+    /// Was the named field of the list set
+    pub(crate) fn contains(self, fields: &[&str], name: &str) -> bool {
+        self.0 & bit(fields, name) != 0
+    }
+
+    /// The fields set in either
+    pub(crate) fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+/// The bit of the named field of the list
+///
+/// # Panics
+/// A name that isn't in the first 64 of the list panics, in every build, rather than being silently dropped from the
+/// merge.
+fn bit(fields: &[&str], name: &str) -> u64 {
+    match fields.iter().position(|field| *field == name) {
+        Some(index) if index < 64 => 1 << index,
+        _ => panic!("'{}' isn't one of the first 64 merged fields", name),
+    }
+}
+
+/// The overlay value if it was set or changed from the default, otherwise the base value
+///
+/// A configuration read with `serde` has no record of its set fields, so a value that differs from the default
+/// counts as set too.
+pub(crate) fn pick<T>(base: T, overlay: T, default: &T, set: bool) -> T
+where
+    T: PartialEq,
+{
+    if set || overlay != *default {
+        overlay
+    } else {
+        base
+    }
+}
+
+/// The union of the instruction names, base first, unless either side is still the default
+pub(crate) fn union(base: Vec<String>, overlay: Vec<String>, default: &[String]) -> Vec<String> {
+    if overlay == default {
+        base
+    } else if base == default {
+        overlay
+    } else {
+        let mut merged = base;
+        for name in overlay {
+            if !merged.contains(&name) {
+                merged.push(name);
+            }
+        }
+        merged
+    }
+}
+
+/// The union of the maps, the overlay wins for a name in both
+pub(crate) fn union_map<V>(
+    mut base: BTreeMap<String, V>,
+    overlay: BTreeMap<String, V>,
+) -> BTreeMap<String, V> {
+    base.extend(overlay);
+    base
+}
+
+#[cfg(test)]
+mod test {
+    use super::{pick, union, union_map, SetFields};
+    use std::collections::BTreeMap;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| (*name).to_string()).collect()
+    }
+
+    #[test]
+    fn overlay_wins_when_changed() {
+        assert!(!pick(true, false, &true, false));
+        assert!(!pick(false, true, &true, false));
+        assert_eq!(pick(Some(1), None, &None, false), Some(1));
+        assert_eq!(pick(Some(1), Some(2), &None, false), Some(2));
+    }
+
+    #[test]
+    fn overlay_wins_when_set() {
+        assert!(pick(false, true, &true, true));
+        assert_eq!(pick(Some(1), None, &None, true), None);
+    }
+
+    #[test]
+    fn set_fields() {
+        let fields = ["a", "b", "c"];
+        let mut base = SetFields::default();
+        base.mark(&fields, "a");
+        let mut overlay = SetFields::default();
+        overlay.mark(&fields, "c");
+        assert!(base.contains(&fields, "a"));
+        assert!(!base.contains(&fields, "b"));
+        let merged = base.union(overlay);
+        assert!(merged.contains(&fields, "a"));
+        assert!(!merged.contains(&fields, "b"));
+        assert!(merged.contains(&fields, "c"));
+    }
+
+    #[test]
+    #[should_panic(expected = "'d' isn't one of the first 64 merged fields")]
+    fn unknown_field() {
+        let mut set = SetFields::default();
+        set.mark(&["a", "b", "c"], "d");
+    }
+
+    #[test]
+    fn names_are_unioned() {
+        let default = names(&["A", "B"]);
+        assert_eq!(
+            union(names(&["C"]), default.clone(), &default),
+            names(&["C"])
+        );
+        assert_eq!(
+            union(default.clone(), names(&["C"]), &default),
+            names(&["C"])
+        );
+        assert_eq!(
+            union(names(&["A", "C"]), names(&["C", "D"]), &default),
+            names(&["A", "C", "D"])
+        );
+    }
+
+    #[test]
+    fn overlay_wins_in_maps() {
+        let base: BTreeMap<String, u8> = vec![("A".to_string(), 1), ("B".to_string(), 1)]
+            .into_iter()
+            .collect();
+        let overlay: BTreeMap<String, u8> = vec![("B".to_string(), 2)].into_iter().collect();
+        let merged = union_map(base, overlay);
+        assert_eq!(merged["A"], 1);
+        assert_eq!(merged["B"], 2);
+    }
+}
@@ -0,0 +1,642 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` output targets
+
+use crate::{
+    config::Config,
+    error::{ConfigError, Error},
+};
+use anyhow::Result;
+use std::{
+    env::{self, VarError},
+    ffi::OsString,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// Where the generated values are written
+///
+/// * [`CargoStdout`](Self::CargoStdout) writes the `cargo:` instructions to stdout, for cargo to interpret.
+/// * [`File`](Self::File) writes the values as `KEY=value` lines to the given file, for build systems without cargo, i.e. Bazel.  Nothing is written to stdout.
+/// * [`Both`](Self::Both) does both.
+///
+/// * **NOTE** - The file is replaced atomically, and its parent directories are created if necessary.
+/// * **NOTE** - The `cargo:` instructions, including the `cargo:rerun-if-changed` paths, are omitted from the file.
+///
+/// Set the `output_format` field on the [`Config`](crate::Config) to write the file in another format, see [`OutputFormat`].
+///
+/// Set the `rerun_sidecar` field on the [`Config`](crate::Config) to write the `cargo:rerun-if-changed` paths, one per line,
+/// to a sidecar file with `.rerun` appended to the name.
+///
+/// # Example
+///
+/// ```
+/// use vergen::{Config, OutputTarget};
+///
+/// let mut config = Config::default();
+/// *config.output_target_mut() = OutputTarget::File("bazel-out/vergen.env".into());
+/// *config.rerun_sidecar_mut() = true;
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum OutputTarget {
+    /// Write the `cargo:` instructions to stdout
+    CargoStdout,
+    /// Write `KEY=value` lines to the given file
+    File(PathBuf),
+    /// Write the `cargo:` instructions to stdout, and `KEY=value` lines to the given file
+    Both(PathBuf),
+}
+
+#[cfg_attr(msrv, allow(clippy::derivable_impls))]
+impl Default for OutputTarget {
+    fn default() -> Self {
+        Self::CargoStdout
+    }
+}
+
+impl OutputTarget {
+    pub(crate) fn has_stdout(&self) -> bool {
+        !matches!(self, Self::File(_))
+    }
+
+    pub(crate) fn file(&self) -> Option<&Path> {
+        match self {
+            Self::CargoStdout => None,
+            Self::File(path) | Self::Both(path) => Some(path),
+        }
+    }
+
+    pub(crate) fn validate(
+        &self,
+        rerun_sidecar: bool,
+        format: OutputFormat,
+        errors: &mut Vec<ConfigError>,
+    ) {
+        match self.file() {
+            Some(path) if path.as_os_str().is_empty() => errors.push(ConfigError::new(
+                "output_target",
+                format!("{:?}", self),
+                "use the path of the file to write the values to",
+            )),
+            None if rerun_sidecar => errors.push(ConfigError::new(
+                "rerun_sidecar",
+                "true",
+                "set 'output_target' to a file, or set 'rerun_sidecar' to false",
+            )),
+            None if format != OutputFormat::default() => errors.push(ConfigError::new(
+                "output_format",
+                format!("{:?}", format),
+                "set 'output_target' to a file, or remove 'output_format'",
+            )),
+            _ => {}
+        }
+    }
+}
+
+/// The format of the file written for the [`OutputTarget`]
+///
+/// * [`Env`](Self::Env) writes `KEY=value` lines.
+/// * [`Dotenv`](Self::Dotenv) writes `KEY=value` lines sorted by name, quoted so a dotenv parser, i.e. `docker run
+///   --env-file`, or a shell sourcing the file reads the values back unchanged.  A value with characters other than
+///   ASCII alphanumerics and `_-.,:/@+%=` is single quoted, with each `'` written as `'\''`.  If the `export` field is
+///   true, each line starts with `export `, so `. vergen.env` exports the values to the commands the shell runs.
+/// * [`Toml`](Self::Toml) writes a TOML table for each subsystem, with the `toml` feature.  The keys are the instruction
+///   names without the `VERGEN_` and subsystem prefixes, in lowercase, i.e. `VERGEN_BUILD_TIMESTAMP` is written as
+///   `timestamp` in the `[build]` table.  The values that aren't part of a feature, i.e. `VERGEN_BANNER`, are in the
+///   `[vergen]` table, and the custom commands are in the `[commands]` table by name.
+///
+/// ```toml
+/// [build]
+/// timestamp = "2021-02-25T23:28:39.493201+00:00"
+///
+/// [git]
+/// sha = "95fc0f5d066710f16e0c23ce3239d6e040abca0d"
+/// ```
+///
+/// # Example
+///
+/// ```
+/// use vergen::{Config, OutputFormat, OutputTarget};
+///
+/// let mut config = Config::default();
+/// *config.output_target_mut() = OutputTarget::File("target/vergen.toml".into());
+/// # #[cfg(feature = "toml")]
+/// # {
+/// *config.output_format_mut() = OutputFormat::Toml;
+/// # }
+///
+/// // Coexist with the cargo instructions, for a release script to source
+/// *config.output_target_mut() = OutputTarget::Both("target/vergen.env".into());
+/// *config.output_format_mut() = OutputFormat::Dotenv { export: true };
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum OutputFormat {
+    /// `KEY=value` lines
+    Env,
+    /// Sorted and quoted `KEY=value` lines, for dotenv parsers and shells
+    Dotenv {
+        /// Start each line with `export `
+        export: bool,
+    },
+    /// A TOML table for each subsystem
+    #[cfg(feature = "toml")]
+    Toml,
+}
+
+#[cfg_attr(msrv, allow(clippy::derivable_impls))]
+impl Default for OutputFormat {
+    fn default() -> Self {
+        Self::Env
+    }
+}
+
+/// The `OUT_DIR` set by cargo, as it is on disk even if it isn't valid UTF-8
+pub(crate) fn out_dir() -> Result<PathBuf> {
+    env::var_os("OUT_DIR")
+        .map(PathBuf::from)
+        .ok_or_else(|| Error::OutDir(VarError::NotPresent).into())
+}
+
+/// The path of the `cargo:rerun-if-changed` sidecar file for the given output file
+pub(crate) fn sidecar_path(path: &Path) -> PathBuf {
+    let mut sidecar = OsString::from(path.as_os_str());
+    sidecar.push(".rerun");
+    PathBuf::from(sidecar)
+}
+
+/// Write the values in the given format, and the rerun sidecar if requested
+pub(crate) fn output_file(
+    config: &Config,
+    path: &Path,
+    format: OutputFormat,
+    rerun_sidecar: bool,
+) -> Result<()> {
+    let contents = match format {
+        OutputFormat::Env => env_contents(config),
+        OutputFormat::Dotenv { export } => dotenv_contents(config, export),
+        #[cfg(feature = "toml")]
+        OutputFormat::Toml => toml_contents(config)?,
+    };
+    write_atomic(path, &contents)?;
+
+    if rerun_sidecar {
+        let mut paths = String::new();
+        for rerun in config
+            .head_path()
+            .iter()
+            .chain(config.ref_path().iter())
+            .chain(config.overrides_path().iter())
+        {
+            paths.push_str(&rerun.display().to_string());
+            paths.push('\n');
+        }
+        write_atomic(&sidecar_path(path), &paths)?;
+    }
+    Ok(())
+}
+
+/// The `KEY=value` lines
+fn env_contents(config: &Config) -> String {
+    let mut contents = String::new();
+    for (key, value) in config.cfg_map() {
+        if let Some(value) = value {
+            contents.push_str(key.name());
+            contents.push('=');
+            contents.push_str(value);
+            contents.push('\n');
+        }
+    }
+    for (name, value) in config.commands() {
+        contents.push_str(name);
+        contents.push('=');
+        contents.push_str(value);
+        contents.push('\n');
+    }
+    contents
+}
+
+/// The generated values and the command outputs, sorted by name
+fn sorted_values(config: &Config) -> Vec<(&str, &String)> {
+    let mut values: Vec<(&str, &String)> = config
+        .cfg_map()
+        .iter()
+        .filter_map(|(key, value)| value.as_ref().map(|value| (key.name(), value)))
+        .chain(
+            config
+                .commands()
+                .iter()
+                .map(|(name, value)| (name.as_str(), value)),
+        )
+        .collect();
+    values.sort_unstable_by_key(|(name, _)| *name);
+    values
+}
+
+/// The `KEY=value` lines sorted by name, with the values quoted for dotenv parsers and shells
+fn dotenv_contents(config: &Config, export: bool) -> String {
+    let values = sorted_values(config);
+
+    let mut contents = String::new();
+    for (name, value) in values {
+        if export {
+            contents.push_str("export ");
+        }
+        contents.push_str(name);
+        contents.push('=');
+        contents.push_str(&dotenv_value(value));
+        contents.push('\n');
+    }
+    contents
+}
+
+/// The value as is if it's safe unquoted, otherwise single quoted
+///
+/// A single quoted value is read literally by both dotenv parsers and shells, so there's nothing else to escape.  A
+/// line break, which [sanitization](crate::Sanitize) normally removes, can't be single quoted on one line, so a value
+/// with one is double quoted with `\n` instead, as dotenv parsers expect, and carriage returns are dropped.
+fn dotenv_value(value: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "_-.,:/@+%=".contains(c);
+    if value.chars().all(safe) {
+        value.to_string()
+    } else if value.contains(&['\n', '\r'][..]) {
+        let mut quoted = String::from("\"");
+        for c in value.chars() {
+            match c {
+                '\n' => quoted.push_str("\\n"),
+                '\r' => {}
+                '\\' | '"' | '$' => {
+                    quoted.push('\\');
+                    quoted.push(c);
+                }
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        quoted
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// The TOML tables, one per subsystem
+#[cfg(feature = "toml")]
+fn toml_contents(config: &Config) -> Result<String> {
+    use toml::{map::Map, Value};
+
+    let mut tables = Map::new();
+    for (key, value) in config.cfg_map() {
+        if let Some(value) = value {
+            let subsystem = key.subsystem();
+            let name = key.name().trim_start_matches("VERGEN_");
+            let prefix = format!("{}_", subsystem.to_ascii_uppercase());
+            let name = name.strip_prefix(&prefix).unwrap_or(name);
+            let table = tables
+                .entry(subsystem)
+                .or_insert_with(|| Value::Table(Map::new()));
+            if let Value::Table(table) = table {
+                let _ = table.insert(name.to_ascii_lowercase(), Value::String(value.clone()));
+            }
+        }
+    }
+    if !config.commands().is_empty() {
+        let commands = config
+            .commands()
+            .iter()
+            .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+            .collect();
+        let _ = tables.insert("commands".to_string(), Value::Table(commands));
+    }
+    Ok(toml::to_string(&Value::Table(tables))?)
+}
+
+/// Write to a temporary file next to the destination and rename it into place, so readers never see a partial file
+pub(crate) fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut tmp = OsString::from(path.as_os_str());
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Write the generated values as a two column table sorted by name, see the `print_table` field
+pub(crate) fn write_table<T>(config: &Config, stderr: &mut T) -> Result<()>
+where
+    T: Write,
+{
+    let values = sorted_values(config);
+    let width = values
+        .iter()
+        .map(|(name, _)| name.len())
+        .chain(Some("Instruction".len()))
+        .max()
+        .unwrap_or_default();
+    writeln!(
+        stderr,
+        "vergen: {:width$} | Value",
+        "Instruction",
+        width = width
+    )?;
+    writeln!(
+        stderr,
+        "vergen: {:-<width$}-+-{:-<5}",
+        "",
+        "",
+        width = width
+    )?;
+    for (name, value) in values {
+        writeln!(stderr, "vergen: {:width$} | {}", name, value, width = width)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{dotenv_value, output_file, sidecar_path, write_table, OutputFormat, OutputTarget};
+    use crate::config::{Config, VergenKey};
+    use std::{
+        env, fs,
+        path::{Path, PathBuf},
+        process::Command,
+    };
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Sha, Some("abc123".to_string()));
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Branch, Some("main".to_string()));
+        *config.head_path_mut() = Some(PathBuf::from("/repo/.git/HEAD"));
+        *config.ref_path_mut() = Some(PathBuf::from("/repo/.git/refs/heads/main"));
+        config
+    }
+
+    #[test]
+    fn file_has_no_cargo_prefix() {
+        let dir = env::temp_dir().join("vergen_output_file");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("nested").join("vergen.env");
+
+        output_file(&config(), &path, OutputFormat::Env, false).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "VERGEN_GIT_BRANCH=main\nVERGEN_GIT_SHA=abc123\n"
+        );
+        assert!(!sidecar_path(&path).exists());
+        assert!(!dir.join("nested").join("vergen.env.tmp").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn table() {
+        let mut config = config();
+        let _ = config
+            .commands_mut()
+            .insert("BUILD_NUMBER".to_string(), "42".to_string());
+        let mut stderr = vec![];
+        write_table(&config, &mut stderr).unwrap();
+        assert_eq!(
+            String::from_utf8(stderr).unwrap(),
+            "vergen: Instruction       | Value\n\
+             vergen: ------------------+------\n\
+             vergen: BUILD_NUMBER      | 42\n\
+             vergen: VERGEN_GIT_BRANCH | main\n\
+             vergen: VERGEN_GIT_SHA    | abc123\n"
+        );
+    }
+
+    #[test]
+    fn rerun_sidecar() {
+        let dir = env::temp_dir().join("vergen_output_sidecar");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("vergen.env");
+
+        output_file(&config(), &path, OutputFormat::Env, true).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("vergen.env.rerun")).unwrap(),
+            "/repo/.git/HEAD\n/repo/.git/refs/heads/main\n"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn targets() {
+        let path = PathBuf::from("vergen.env");
+        assert!(OutputTarget::default().has_stdout());
+        assert!(OutputTarget::default().file().is_none());
+        assert!(!OutputTarget::File(path.clone()).has_stdout());
+        assert!(OutputTarget::Both(path.clone()).has_stdout());
+        assert_eq!(
+            OutputTarget::Both(path.clone()).file(),
+            Some(path.as_path())
+        );
+    }
+
+    #[test]
+    fn invalid_targets() {
+        let mut errors = vec![];
+        OutputTarget::File(PathBuf::new()).validate(false, OutputFormat::Env, &mut errors);
+        OutputTarget::CargoStdout.validate(true, OutputFormat::Env, &mut errors);
+        OutputTarget::Both(PathBuf::from("vergen.env")).validate(
+            true,
+            OutputFormat::Env,
+            &mut errors,
+        );
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].field(), "output_target");
+        assert_eq!(errors[1].field(), "rerun_sidecar");
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_file() {
+        let dir = env::temp_dir().join("vergen_output_toml");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("vergen.toml");
+        let mut config = config();
+        let _ = config.cfg_map_mut().insert(
+            VergenKey::BuildTimestamp,
+            Some("2021-02-25T23:28:39Z".to_string()),
+        );
+        let _ = config.cfg_map_mut().insert(
+            VergenKey::TargetTriple,
+            Some("x86_64-unknown-linux-gnu".to_string()),
+        );
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Banner, Some("vergen \"6.0.0\"".to_string()));
+        let _ = config
+            .commands_mut()
+            .insert("NODE_VERSION".to_string(), "v16.13.0".to_string());
+
+        output_file(&config, &path, OutputFormat::Toml, false).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            contents,
+            "[build]\ntimestamp = \"2021-02-25T23:28:39Z\"\n\n\
+             [cargo]\ntarget_triple = \"x86_64-unknown-linux-gnu\"\n\n\
+             [commands]\nNODE_VERSION = \"v16.13.0\"\n\n\
+             [git]\nbranch = \"main\"\nsha = \"abc123\"\n\n\
+             [vergen]\nbanner = \"vergen \\\"6.0.0\\\"\"\n"
+        );
+        let parsed: toml::Value = contents.parse().unwrap();
+        assert_eq!(
+            parsed["vergen"]["banner"].as_str(),
+            Some("vergen \"6.0.0\"")
+        );
+        fs::remove_dir_all(&dir).unwrap();
+
+        let mut errors = vec![];
+        OutputTarget::CargoStdout.validate(false, OutputFormat::Toml, &mut errors);
+        assert_eq!(errors[0].field(), "output_format");
+    }
+
+    /// The config with values that need quoting, and the values a dotenv parser should read back
+    fn dotenv_config() -> (Config, Vec<(String, String)>) {
+        let mut config = config();
+        let _ = config.cfg_map_mut().insert(
+            VergenKey::Banner,
+            Some("vergen \"6.0.0\" isn't $HOME `id`".to_string()),
+        );
+        let _ = config.cfg_map_mut().insert(
+            VergenKey::CommitsSinceTagSubjects,
+            Some("Fix the docs; Add a feature".to_string()),
+        );
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::GitRoot, Some(String::new()));
+        let _ = config
+            .commands_mut()
+            .insert("NODE_VERSION".to_string(), "v16.13.0".to_string());
+        let expected = [
+            ("NODE_VERSION", "v16.13.0"),
+            ("VERGEN_BANNER", "vergen \"6.0.0\" isn't $HOME `id`"),
+            (
+                "VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS",
+                "Fix the docs; Add a feature",
+            ),
+            ("VERGEN_GIT_BRANCH", "main"),
+            ("VERGEN_GIT_ROOT", ""),
+            ("VERGEN_GIT_SHA", "abc123"),
+        ]
+        .iter()
+        .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+        .collect();
+        (config, expected)
+    }
+
+    // The iterator doesn't set the environment variables, which the tests share
+    #[allow(deprecated)]
+    fn parse_dotenv(path: &Path) -> Vec<(String, String)> {
+        let mut values: Vec<(String, String)> = dotenv::from_path_iter(path)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        values.sort();
+        values
+    }
+
+    #[test]
+    fn dotenv_file() {
+        let dir = env::temp_dir().join("vergen_output_dotenv");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("vergen.env");
+        let (config, mut expected) = dotenv_config();
+        expected.sort();
+
+        output_file(
+            &config,
+            &path,
+            OutputFormat::Dotenv { export: false },
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "NODE_VERSION=v16.13.0\n\
+             VERGEN_BANNER='vergen \"6.0.0\" isn'\\''t $HOME `id`'\n\
+             VERGEN_GIT_BRANCH=main\n\
+             VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS='Fix the docs; Add a feature'\n\
+             VERGEN_GIT_ROOT=\n\
+             VERGEN_GIT_SHA=abc123\n"
+        );
+        assert_eq!(parse_dotenv(&path), expected);
+
+        output_file(&config, &path, OutputFormat::Dotenv { export: true }, false).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.lines().all(|line| line.starts_with("export ")));
+        assert_eq!(parse_dotenv(&path), expected);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dotenv_sourced() {
+        let dir = env::temp_dir().join("vergen_output_dotenv_sourced");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("vergen.env");
+        let (config, expected) = dotenv_config();
+
+        output_file(&config, &path, OutputFormat::Dotenv { export: true }, false).unwrap();
+        for (name, value) in expected {
+            let output = Command::new("sh")
+                .arg("-c")
+                .arg(format!(
+                    ". '{}' && sh -c 'printf %s \"${}\"'",
+                    path.display(),
+                    name
+                ))
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            assert_eq!(String::from_utf8(output.stdout).unwrap(), value);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn dotenv_line_break() {
+        assert_eq!(dotenv_value("a\r\nb \"$c\""), "\"a\\nb \\\"\\$c\\\"\"");
+        let dir = env::temp_dir().join("vergen_output_dotenv_line_break");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("vergen.env");
+        let mut config = Config::default();
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Banner, Some("a\nb \"$c\"".to_string()));
+        output_file(
+            &config,
+            &path,
+            OutputFormat::Dotenv { export: false },
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            parse_dotenv(&path),
+            vec![("VERGEN_BANNER".to_string(), "a\nb \"$c\"".to_string())]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
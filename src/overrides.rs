@@ -0,0 +1,655 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` environment variable configuration overrides
+
+use crate::{
+    config::{Instructions, VergenKey},
+    overrides_file::OVERRIDES_FILE_VAR,
+};
+use std::{env, path::PathBuf};
+
+const VERGEN_PREFIX: &str = "VERGEN_";
+const OVERRIDE_PREFIX: &str = "VERGEN_DISABLE_";
+/// The variable that sets the `emit_all_with_placeholders` field.
+pub(crate) const ERROR_POLICY_VAR: &str = "VERGEN_ERROR_POLICY";
+
+/// Every recognized override variable, regardless of the enabled features.
+pub(crate) const ENV_OVERRIDES: &[&str] = &[
+    "VERGEN_DISABLE_BUILD",
+    "VERGEN_DISABLE_BUILD_TIMESTAMP",
+    "VERGEN_DISABLE_BUILD_SEMVER",
+    "VERGEN_DISABLE_BUILD_SEMVER_COMPONENTS",
+    "VERGEN_DISABLE_BUILD_CPU_COUNT",
+    "VERGEN_DISABLE_CARGO",
+    "VERGEN_DISABLE_CARGO_FEATURES",
+    "VERGEN_DISABLE_CARGO_IS_CROSS_COMPILE",
+    "VERGEN_DISABLE_CARGO_LIBC_VERSION",
+    "VERGEN_DISABLE_CARGO_PKG_AUTHORS",
+    "VERGEN_DISABLE_CARGO_PKG_DESCRIPTION",
+    "VERGEN_DISABLE_CARGO_PKG_HOMEPAGE",
+    "VERGEN_DISABLE_CARGO_PKG_LICENSE",
+    "VERGEN_DISABLE_CARGO_PKG_LICENSE_FILE",
+    "VERGEN_DISABLE_CARGO_PKG_REPOSITORY",
+    "VERGEN_DISABLE_CARGO_PROFILE",
+    "VERGEN_DISABLE_CARGO_TARGET_ARCH",
+    "VERGEN_DISABLE_CARGO_TARGET_ENDIAN",
+    "VERGEN_DISABLE_CARGO_TARGET_ENV",
+    "VERGEN_DISABLE_CARGO_TARGET_OS",
+    "VERGEN_DISABLE_CARGO_TARGET_POINTER_WIDTH",
+    "VERGEN_DISABLE_CARGO_TARGET_TRIPLE",
+    "VERGEN_DISABLE_GIT",
+    "VERGEN_DISABLE_GIT_BRANCH",
+    "VERGEN_DISABLE_GIT_BRANCH_COUNT",
+    "VERGEN_DISABLE_GIT_COMMIT_AGE",
+    "VERGEN_DISABLE_GIT_COMMITS_SINCE_TAG",
+    "VERGEN_DISABLE_GIT_COMMIT_TIMESTAMP",
+    "VERGEN_DISABLE_GIT_CONTRIBUTOR_COUNT",
+    "VERGEN_DISABLE_GIT_MERGE_BASE",
+    "VERGEN_DISABLE_GIT_OBJECT_FORMAT",
+    "VERGEN_DISABLE_GIT_PARENT_COUNT",
+    "VERGEN_DISABLE_GIT_RERUN_ON_HEAD_CHANGE",
+    "VERGEN_DISABLE_GIT_ROOT",
+    "VERGEN_DISABLE_GIT_SEMVER",
+    "VERGEN_DISABLE_GIT_SHA",
+    "VERGEN_DISABLE_GIT_SIGNED",
+    "VERGEN_DISABLE_GIT_TAG_COUNT",
+    "VERGEN_DISABLE_GIT_TRACKED_FILES",
+    "VERGEN_DISABLE_GIT_TREE_HASH",
+    "VERGEN_DISABLE_GIT_WORKTREE",
+    "VERGEN_DISABLE_HG",
+    "VERGEN_DISABLE_HG_BRANCH",
+    "VERGEN_DISABLE_HG_REV",
+    "VERGEN_DISABLE_HG_SHA",
+    "VERGEN_DISABLE_RUSTC",
+    "VERGEN_DISABLE_RUSTC_CHANNEL",
+    "VERGEN_DISABLE_RUSTC_COMMIT_DATE",
+    "VERGEN_DISABLE_RUSTC_COMMIT_HASH",
+    "VERGEN_DISABLE_RUSTC_HOST_TRIPLE",
+    "VERGEN_DISABLE_RUSTC_LLVM_VERSION",
+    "VERGEN_DISABLE_RUSTC_SEMVER",
+    "VERGEN_DISABLE_SYSINFO",
+    "VERGEN_DISABLE_SYSINFO_NAME",
+    "VERGEN_DISABLE_SYSINFO_OS_VERSION",
+    "VERGEN_DISABLE_SYSINFO_USER",
+    "VERGEN_DISABLE_SYSINFO_TOTAL_MEMORY",
+    "VERGEN_DISABLE_SYSINFO_CPU_VENDOR",
+    "VERGEN_DISABLE_SYSINFO_CPU_CORE_COUNT",
+    "VERGEN_DISABLE_SYSINFO_CPU_NAME",
+    "VERGEN_DISABLE_SYSINFO_CPU_BRAND",
+    "VERGEN_DISABLE_SYSINFO_CPU_FREQUENCY",
+];
+
+/// The recognized override variables that are set, in the order of `vars`.
+///
+/// Only these are watched with `cargo:rerun-if-env-changed`, as any `rerun-if` instruction turns off cargo's default
+/// of rerunning the build script when a package file changes.
+pub(crate) fn set_overrides(vars: &[(String, String)]) -> Vec<String> {
+    vars.iter()
+        .map(|(name, _)| name)
+        .filter(|name| {
+            *name == OVERRIDES_FILE_VAR
+                || *name == ERROR_POLICY_VAR
+                || active_overrides().any(|active| active == *name)
+        })
+        .cloned()
+        .collect()
+}
+
+/// The recognized override variables that apply to the enabled features.
+pub(crate) fn active_overrides() -> impl Iterator<Item = &'static str> {
+    ENV_OVERRIDES.iter().copied().filter(|name| {
+        let feature = name.trim_start_matches(OVERRIDE_PREFIX);
+        (feature.starts_with("BUILD") && cfg!(feature = "build"))
+            || (feature.starts_with("CARGO") && cfg!(feature = "cargo"))
+            || (feature.starts_with("GIT") && cfg!(feature = "git"))
+            || (feature.starts_with("HG") && cfg!(feature = "hg"))
+            || (feature.starts_with("RUSTC") && cfg!(feature = "rustc"))
+            || (feature.starts_with("SYSINFO") && cfg!(feature = "si"))
+    })
+}
+
+impl Instructions {
+    /// Apply the `VERGEN_DISABLE_*` environment variable overrides to this configuration.
+    ///
+    /// This is called automatically by [`vergen`](crate::vergen) unless the `env_overrides`
+    /// field is false.  It allows CI pipelines to turn instructions off without patching `build.rs`.
+    ///
+    /// * A value of `1`, `true`, `yes`, or `on` disables the matching feature or instruction.
+    /// * A value of `0`, `false`, `no`, or `off` enables the matching feature or instruction.
+    /// * **NOTE** - The environment wins over the configuration in code, in both directions.
+    /// * **NOTE** - Variables for features that aren't compiled in are accepted and ignored.
+    /// * **NOTE** - A `cargo:rerun-if-env-changed` instruction is generated for every variable below that is set and applies to an enabled feature,
+    ///   and for `VERGEN_OVERRIDES_FILE` and `VERGEN_ERROR_POLICY` when they're set.  The unset ones aren't watched, so that cargo still reruns the
+    ///   build script when a package file changes, and setting one is picked up the next time the build script runs.
+    ///
+    /// The following variables are recognized, named after the field or instruction they toggle:
+    ///
+    /// | Variable | Field |
+    /// | -------- | ----- |
+    /// | `VERGEN_DISABLE_BUILD` | `build.enabled` |
+    /// | `VERGEN_DISABLE_BUILD_TIMESTAMP` | `build.timestamp` |
+    /// | `VERGEN_DISABLE_BUILD_SEMVER` | `build.semver` |
+    /// | `VERGEN_DISABLE_BUILD_SEMVER_COMPONENTS` | `build.semver_components` |
+    /// | `VERGEN_DISABLE_BUILD_CPU_COUNT` | `build.cpu_count` |
+    /// | `VERGEN_DISABLE_CARGO` | `cargo.enabled` |
+    /// | `VERGEN_DISABLE_CARGO_FEATURES` | `cargo.features` |
+    /// | `VERGEN_DISABLE_CARGO_IS_CROSS_COMPILE` | `cargo.is_cross_compile` |
+    /// | `VERGEN_DISABLE_CARGO_LIBC_VERSION` | `cargo.libc_version` |
+    /// | `VERGEN_DISABLE_CARGO_PKG_AUTHORS` | `cargo.pkg_authors` |
+    /// | `VERGEN_DISABLE_CARGO_PKG_DESCRIPTION` | `cargo.pkg_description` |
+    /// | `VERGEN_DISABLE_CARGO_PKG_HOMEPAGE` | `cargo.pkg_homepage` |
+    /// | `VERGEN_DISABLE_CARGO_PKG_LICENSE` | `cargo.pkg_license` |
+    /// | `VERGEN_DISABLE_CARGO_PKG_LICENSE_FILE` | `cargo.pkg_license_file` |
+    /// | `VERGEN_DISABLE_CARGO_PKG_REPOSITORY` | `cargo.pkg_repository` |
+    /// | `VERGEN_DISABLE_CARGO_PROFILE` | `cargo.profile` |
+    /// | `VERGEN_DISABLE_CARGO_TARGET_ARCH` | `cargo.target_arch` |
+    /// | `VERGEN_DISABLE_CARGO_TARGET_ENDIAN` | `cargo.target_endian` |
+    /// | `VERGEN_DISABLE_CARGO_TARGET_ENV` | `cargo.target_env` |
+    /// | `VERGEN_DISABLE_CARGO_TARGET_OS` | `cargo.target_os` |
+    /// | `VERGEN_DISABLE_CARGO_TARGET_POINTER_WIDTH` | `cargo.target_pointer_width` |
+    /// | `VERGEN_DISABLE_CARGO_TARGET_TRIPLE` | `cargo.target_triple` |
+    /// | `VERGEN_DISABLE_GIT` | `git.enabled` |
+    /// | `VERGEN_DISABLE_GIT_BRANCH` | `git.branch` |
+    /// | `VERGEN_DISABLE_GIT_BRANCH_COUNT` | `git.branch_count` |
+    /// | `VERGEN_DISABLE_GIT_COMMIT_AGE` | `git.commit_age` |
+    /// | `VERGEN_DISABLE_GIT_COMMITS_SINCE_TAG` | `git.commits_since_tag` |
+    /// | `VERGEN_DISABLE_GIT_COMMIT_TIMESTAMP` | `git.commit_timestamp` |
+    /// | `VERGEN_DISABLE_GIT_CONTRIBUTOR_COUNT` | `git.contributor_count` |
+    /// | `VERGEN_DISABLE_GIT_MERGE_BASE` | `git.merge_base` |
+    /// | `VERGEN_DISABLE_GIT_OBJECT_FORMAT` | `git.object_format` |
+    /// | `VERGEN_DISABLE_GIT_PARENT_COUNT` | `git.parent_count` |
+    /// | `VERGEN_DISABLE_GIT_RERUN_ON_HEAD_CHANGE` | `git.rerun_on_head_change` |
+    /// | `VERGEN_DISABLE_GIT_ROOT` | `git.repo_root` |
+    /// | `VERGEN_DISABLE_GIT_SEMVER` | `git.semver` |
+    /// | `VERGEN_DISABLE_GIT_SHA` | `git.sha` |
+    /// | `VERGEN_DISABLE_GIT_SIGNED` | `git.signed` |
+    /// | `VERGEN_DISABLE_GIT_TAG_COUNT` | `git.tag_count` |
+    /// | `VERGEN_DISABLE_GIT_TRACKED_FILES` | `git.tracked_files` |
+    /// | `VERGEN_DISABLE_GIT_TREE_HASH` | `git.tree_hash` |
+    /// | `VERGEN_DISABLE_GIT_WORKTREE` | `git.worktree` |
+    /// | `VERGEN_DISABLE_HG` | `hg.enabled` |
+    /// | `VERGEN_DISABLE_HG_BRANCH` | `hg.branch` |
+    /// | `VERGEN_DISABLE_HG_REV` | `hg.rev` |
+    /// | `VERGEN_DISABLE_HG_SHA` | `hg.sha` |
+    /// | `VERGEN_DISABLE_RUSTC` | `rustc.enabled` |
+    /// | `VERGEN_DISABLE_RUSTC_CHANNEL` | `rustc.channel` |
+    /// | `VERGEN_DISABLE_RUSTC_COMMIT_DATE` | `rustc.commit_date` |
+    /// | `VERGEN_DISABLE_RUSTC_COMMIT_HASH` | `rustc.sha` |
+    /// | `VERGEN_DISABLE_RUSTC_HOST_TRIPLE` | `rustc.host_triple` |
+    /// | `VERGEN_DISABLE_RUSTC_LLVM_VERSION` | `rustc.llvm_version` |
+    /// | `VERGEN_DISABLE_RUSTC_SEMVER` | `rustc.semver` |
+    /// | `VERGEN_DISABLE_SYSINFO` | `sysinfo.enabled` |
+    /// | `VERGEN_DISABLE_SYSINFO_NAME` | `sysinfo.name` |
+    /// | `VERGEN_DISABLE_SYSINFO_OS_VERSION` | `sysinfo.os_version` |
+    /// | `VERGEN_DISABLE_SYSINFO_USER` | `sysinfo.user` |
+    /// | `VERGEN_DISABLE_SYSINFO_TOTAL_MEMORY` | `sysinfo.memory` |
+    /// | `VERGEN_DISABLE_SYSINFO_CPU_VENDOR` | `sysinfo.cpu_vendor` |
+    /// | `VERGEN_DISABLE_SYSINFO_CPU_CORE_COUNT` | `sysinfo.cpu_core_count` |
+    /// | `VERGEN_DISABLE_SYSINFO_CPU_NAME` | `sysinfo.cpu_name` |
+    /// | `VERGEN_DISABLE_SYSINFO_CPU_BRAND` | `sysinfo.cpu_brand` |
+    /// | `VERGEN_DISABLE_SYSINFO_CPU_FREQUENCY` | `sysinfo.cpu_frequency` |
+    ///
+    /// The `VERGEN_OVERRIDES_FILE` variable sets the `overrides_file` field, or turns it off if it's empty, see the
+    /// [overrides file](Self#overrides-file).
+    ///
+    /// The `VERGEN_ERROR_POLICY` variable decides what happens to a value that can't be gathered, see
+    /// [unavailable values](Self#unavailable-values).
+    ///
+    /// * A value of `warn` sets the `emit_all_with_placeholders` field to true, so the instruction uses the
+    ///   placeholder and a `cargo:warning` is generated instead of an error.
+    /// * A value of `error` sets the `emit_all_with_placeholders` field to false.
+    ///
+    /// A warning is returned for every unrecognized `VERGEN_*` variable and every unrecognized value, so typos are
+    /// noticed.  The names of the generated instructions, i.e. `VERGEN_GIT_SHA`, aren't overrides and are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vergen::Config;
+    ///
+    /// let mut config = Config::default();
+    /// for warning in config.apply_env_overrides() {
+    ///     println!("cargo:warning={}", warning);
+    /// }
+    /// ```
+    pub fn apply_env_overrides(&mut self) -> Vec<String> {
+        self.apply_overrides(env::vars())
+    }
+
+    pub(crate) fn apply_overrides<I>(&mut self, vars: I) -> Vec<String>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut warnings = vec![];
+
+        for (name, value) in vars {
+            if name == OVERRIDES_FILE_VAR {
+                *self.overrides_file_mut() = if value.is_empty() {
+                    None
+                } else {
+                    Some(PathBuf::from(value))
+                };
+                continue;
+            }
+
+            if name == ERROR_POLICY_VAR {
+                match value.trim().to_ascii_lowercase().as_str() {
+                    "warn" => *self.emit_all_with_placeholders_mut() = true,
+                    "error" => *self.emit_all_with_placeholders_mut() = false,
+                    _ => warnings.push(format!(
+                        "vergen: ignoring override '{}={}', the value should be one of warn or error",
+                        name, value
+                    )),
+                }
+                continue;
+            }
+
+            if !name.starts_with(VERGEN_PREFIX)
+                || VergenKey::ALL.iter().any(|key| key.name() == name)
+            {
+                continue;
+            }
+
+            if !ENV_OVERRIDES.contains(&name.as_str()) {
+                warnings.push(format!(
+                    "vergen: ignoring unknown override '{}', see the 'apply_env_overrides' documentation for the supported names",
+                    name
+                ));
+                continue;
+            }
+
+            match parse_bool(&value) {
+                Some(disable) => apply(self, &name, disable),
+                None => warnings.push(format!(
+                    "vergen: ignoring override '{}={}', the value should be one of 1, true, yes, on, 0, false, no, or off",
+                    name, value
+                )),
+            }
+        }
+
+        warnings
+    }
+}
+
+pub(crate) fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+#[allow(clippy::too_many_lines)]
+fn apply(instructions: &mut Instructions, name: &str, disable: bool) {
+    let enable = !disable;
+
+    match name {
+        #[cfg(feature = "build")]
+        "VERGEN_DISABLE_BUILD" => *instructions.build_mut().enabled_mut() = enable,
+        #[cfg(feature = "build")]
+        "VERGEN_DISABLE_BUILD_TIMESTAMP" => *instructions.build_mut().timestamp_mut() = enable,
+        #[cfg(feature = "build")]
+        "VERGEN_DISABLE_BUILD_SEMVER" => *instructions.build_mut().semver_mut() = enable,
+        #[cfg(feature = "build")]
+        "VERGEN_DISABLE_BUILD_SEMVER_COMPONENTS" => {
+            *instructions.build_mut().semver_components_mut() = enable;
+        }
+        #[cfg(feature = "build")]
+        "VERGEN_DISABLE_BUILD_CPU_COUNT" => *instructions.build_mut().cpu_count_mut() = enable,
+        #[cfg(feature = "cargo")]
+        "VERGEN_DISABLE_CARGO" => *instructions.cargo_mut().enabled_mut() = enable,
+        #[cfg(feature = "cargo")]
+        "VERGEN_DISABLE_CARGO_FEATURES" => *instructions.cargo_mut().features_mut() = enable,
+        #[cfg(feature = "cargo")]
+        "VERGEN_DISABLE_CARGO_IS_CROSS_COMPILE" => {
+            *instructions.cargo_mut().is_cross_compile_mut() = enable;
+        }
+        #[cfg(feature = "cargo")]
+        "VERGEN_DISABLE_CARGO_LIBC_VERSION" => {
+            *instructions.cargo_mut().libc_version_mut() = enable;
+        }
+        #[cfg(feature = "cargo")]
+        "VERGEN_DISABLE_CARGO_PKG_AUTHORS" => *instructions.cargo_mut().pkg_authors_mut() = enable,
+        #[cfg(feature = "cargo")]
+        "VERGEN_DISABLE_CARGO_PKG_DESCRIPTION" => {
+            *instructions.cargo_mut().pkg_description_mut() = enable;
+        }
+        #[cfg(feature = "cargo")]
+        "VERGEN_DISABLE_CARGO_PKG_HOMEPAGE" => {
+            *instructions.cargo_mut().pkg_homepage_mut() = enable;
+        }
+        #[cfg(feature = "cargo")]
+        "VERGEN_DISABLE_CARGO_PKG_LICENSE" => *instructions.cargo_mut().pkg_license_mut() = enable,
+        #[cfg(feature = "cargo")]
+        "VERGEN_DISABLE_CARGO_PKG_LICENSE_FILE" => {
+            *instructions.cargo_mut().pkg_license_file_mut() = enable;
+        }
+        #[cfg(feature = "cargo")]
+        "VERGEN_DISABLE_CARGO_PKG_REPOSITORY" => {
+            *instructions.cargo_mut().pkg_repository_mut() = enable;
+        }
+        #[cfg(feature = "cargo")]
+        "VERGEN_DISABLE_CARGO_PROFILE" => *instructions.cargo_mut().profile_mut() = enable,
+        #[cfg(feature = "cargo")]
+        "VERGEN_DISABLE_CARGO_TARGET_ARCH" => *instructions.cargo_mut().target_arch_mut() = enable,
+        #[cfg(feature = "cargo")]
+        "VERGEN_DISABLE_CARGO_TARGET_ENDIAN" => {
+            *instructions.cargo_mut().target_endian_mut() = enable;
+        }
+        #[cfg(feature = "cargo")]
+        "VERGEN_DISABLE_CARGO_TARGET_ENV" => *instructions.cargo_mut().target_env_mut() = enable,
+        #[cfg(feature = "cargo")]
+        "VERGEN_DISABLE_CARGO_TARGET_OS" => *instructions.cargo_mut().target_os_mut() = enable,
+        #[cfg(feature = "cargo")]
+        "VERGEN_DISABLE_CARGO_TARGET_POINTER_WIDTH" => {
+            *instructions.cargo_mut().target_pointer_width_mut() = enable;
+        }
+        #[cfg(feature = "cargo")]
+        "VERGEN_DISABLE_CARGO_TARGET_TRIPLE" => {
+            *instructions.cargo_mut().target_triple_mut() = enable;
+        }
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT" => *instructions.git_mut().enabled_mut() = enable,
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_BRANCH" => *instructions.git_mut().branch_mut() = enable,
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_BRANCH_COUNT" => *instructions.git_mut().branch_count_mut() = enable,
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_COMMIT_AGE" => *instructions.git_mut().commit_age_mut() = enable,
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_COMMITS_SINCE_TAG" => {
+            *instructions.git_mut().commits_since_tag_mut() = enable;
+        }
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_COMMIT_TIMESTAMP" => {
+            *instructions.git_mut().commit_timestamp_mut() = enable;
+        }
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_CONTRIBUTOR_COUNT" => {
+            *instructions.git_mut().contributor_count_mut() = enable;
+        }
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_MERGE_BASE" => *instructions.git_mut().merge_base_mut() = enable,
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_OBJECT_FORMAT" => *instructions.git_mut().object_format_mut() = enable,
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_PARENT_COUNT" => *instructions.git_mut().parent_count_mut() = enable,
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_RERUN_ON_HEAD_CHANGE" => {
+            *instructions.git_mut().rerun_on_head_change_mut() = enable;
+        }
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_ROOT" => *instructions.git_mut().repo_root_mut() = enable,
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_SEMVER" => *instructions.git_mut().semver_mut() = enable,
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_SHA" => *instructions.git_mut().sha_mut() = enable,
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_SIGNED" => *instructions.git_mut().signed_mut() = enable,
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_TAG_COUNT" => *instructions.git_mut().tag_count_mut() = enable,
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_TRACKED_FILES" => *instructions.git_mut().tracked_files_mut() = enable,
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_TREE_HASH" => *instructions.git_mut().tree_hash_mut() = enable,
+        #[cfg(feature = "git")]
+        "VERGEN_DISABLE_GIT_WORKTREE" => *instructions.git_mut().worktree_mut() = enable,
+        #[cfg(feature = "hg")]
+        "VERGEN_DISABLE_HG" => *instructions.hg_mut().enabled_mut() = enable,
+        #[cfg(feature = "hg")]
+        "VERGEN_DISABLE_HG_BRANCH" => *instructions.hg_mut().branch_mut() = enable,
+        #[cfg(feature = "hg")]
+        "VERGEN_DISABLE_HG_REV" => *instructions.hg_mut().rev_mut() = enable,
+        #[cfg(feature = "hg")]
+        "VERGEN_DISABLE_HG_SHA" => *instructions.hg_mut().sha_mut() = enable,
+        #[cfg(feature = "rustc")]
+        "VERGEN_DISABLE_RUSTC" => *instructions.rustc_mut().enabled_mut() = enable,
+        #[cfg(feature = "rustc")]
+        "VERGEN_DISABLE_RUSTC_CHANNEL" => *instructions.rustc_mut().channel_mut() = enable,
+        #[cfg(feature = "rustc")]
+        "VERGEN_DISABLE_RUSTC_COMMIT_DATE" => *instructions.rustc_mut().commit_date_mut() = enable,
+        #[cfg(feature = "rustc")]
+        "VERGEN_DISABLE_RUSTC_COMMIT_HASH" => *instructions.rustc_mut().sha_mut() = enable,
+        #[cfg(feature = "rustc")]
+        "VERGEN_DISABLE_RUSTC_HOST_TRIPLE" => *instructions.rustc_mut().host_triple_mut() = enable,
+        #[cfg(feature = "rustc")]
+        "VERGEN_DISABLE_RUSTC_LLVM_VERSION" => {
+            *instructions.rustc_mut().llvm_version_mut() = enable;
+        }
+        #[cfg(feature = "rustc")]
+        "VERGEN_DISABLE_RUSTC_SEMVER" => *instructions.rustc_mut().semver_mut() = enable,
+        #[cfg(feature = "si")]
+        "VERGEN_DISABLE_SYSINFO" => *instructions.sysinfo_mut().enabled_mut() = enable,
+        #[cfg(feature = "si")]
+        "VERGEN_DISABLE_SYSINFO_NAME" => *instructions.sysinfo_mut().name_mut() = enable,
+        #[cfg(feature = "si")]
+        "VERGEN_DISABLE_SYSINFO_OS_VERSION" => {
+            *instructions.sysinfo_mut().os_version_mut() = enable;
+        }
+        #[cfg(feature = "si")]
+        "VERGEN_DISABLE_SYSINFO_USER" => *instructions.sysinfo_mut().user_mut() = enable,
+        #[cfg(feature = "si")]
+        "VERGEN_DISABLE_SYSINFO_TOTAL_MEMORY" => *instructions.sysinfo_mut().memory_mut() = enable,
+        #[cfg(feature = "si")]
+        "VERGEN_DISABLE_SYSINFO_CPU_VENDOR" => {
+            *instructions.sysinfo_mut().cpu_vendor_mut() = enable;
+        }
+        #[cfg(feature = "si")]
+        "VERGEN_DISABLE_SYSINFO_CPU_CORE_COUNT" => {
+            *instructions.sysinfo_mut().cpu_core_count_mut() = enable;
+        }
+        #[cfg(feature = "si")]
+        "VERGEN_DISABLE_SYSINFO_CPU_NAME" => *instructions.sysinfo_mut().cpu_name_mut() = enable,
+        #[cfg(feature = "si")]
+        "VERGEN_DISABLE_SYSINFO_CPU_BRAND" => *instructions.sysinfo_mut().cpu_brand_mut() = enable,
+        #[cfg(feature = "si")]
+        "VERGEN_DISABLE_SYSINFO_CPU_FREQUENCY" => {
+            *instructions.sysinfo_mut().cpu_frequency_mut() = enable;
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{active_overrides, parse_bool, ENV_OVERRIDES};
+    use crate::config::Instructions;
+    use std::path::PathBuf;
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_bool_works() {
+        assert_eq!(parse_bool("1"), Some(true));
+        assert_eq!(parse_bool(" TRUE "), Some(true));
+        assert_eq!(parse_bool("on"), Some(true));
+        assert_eq!(parse_bool("0"), Some(false));
+        assert_eq!(parse_bool("Off"), Some(false));
+        assert_eq!(parse_bool("yoda"), None);
+    }
+
+    #[test]
+    fn all_overrides_are_known() {
+        let mut config = Instructions::default();
+        let all: Vec<(String, String)> = ENV_OVERRIDES
+            .iter()
+            .map(|name| ((*name).to_string(), "1".to_string()))
+            .collect();
+        assert!(config.apply_overrides(all).is_empty());
+    }
+
+    #[test]
+    fn active_overrides_follow_features() {
+        assert_eq!(
+            active_overrides().any(|name| name == "VERGEN_DISABLE_GIT"),
+            cfg!(feature = "git")
+        );
+        assert_eq!(
+            active_overrides().any(|name| name == "VERGEN_DISABLE_GIT_PARENT_COUNT"),
+            cfg!(feature = "git")
+        );
+        assert_eq!(
+            active_overrides().any(|name| name == "VERGEN_DISABLE_SYSINFO_USER"),
+            cfg!(feature = "si")
+        );
+        assert_eq!(
+            active_overrides().any(|name| name == "VERGEN_DISABLE_CARGO_LIBC_VERSION"),
+            cfg!(feature = "cargo")
+        );
+        assert_eq!(
+            active_overrides().any(|name| name == "VERGEN_DISABLE_BUILD_CPU_COUNT"),
+            cfg!(feature = "build")
+        );
+        assert_eq!(
+            active_overrides().any(|name| name == "VERGEN_DISABLE_GIT_OBJECT_FORMAT"),
+            cfg!(feature = "git")
+        );
+    }
+
+    #[test]
+    fn unrelated_vars_are_ignored() {
+        let mut config = Instructions::default();
+        let warnings =
+            config.apply_overrides(vars(&[("VERGEN_GIT_SHA", "abc"), ("PATH", "/usr/bin")]));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn unknown_override_warns() {
+        let mut config = Instructions::default();
+        let warnings = config.apply_overrides(vars(&[("VERGEN_DISABLE_BUILD_TIMESTMP", "1")]));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("VERGEN_DISABLE_BUILD_TIMESTMP"));
+        let warnings = config.apply_overrides(vars(&[("VERGEN_DISABEL_GIT", "1")]));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("VERGEN_DISABEL_GIT"));
+    }
+
+    #[test]
+    fn env_sets_error_policy() {
+        let mut config = Instructions::default();
+        let warnings = config.apply_overrides(vars(&[("VERGEN_ERROR_POLICY", "warn")]));
+        assert!(warnings.is_empty());
+        assert!(config.emit_all_with_placeholders());
+        let _ = config.apply_overrides(vars(&[("VERGEN_ERROR_POLICY", "Error")]));
+        assert!(!config.emit_all_with_placeholders());
+        let warnings = config.apply_overrides(vars(&[("VERGEN_ERROR_POLICY", "panic")]));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("VERGEN_ERROR_POLICY=panic"));
+    }
+
+    #[test]
+    fn bad_value_warns() {
+        let mut config = Instructions::default();
+        let warnings = config.apply_overrides(vars(&[("VERGEN_DISABLE_GIT", "yoda")]));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("VERGEN_DISABLE_GIT=yoda"));
+    }
+
+    #[test]
+    fn env_sets_overrides_file() {
+        let mut config = Instructions::default();
+        *config.overrides_file_mut() = Some(PathBuf::from("vergen.overrides"));
+        let warnings = config.apply_overrides(vars(&[(
+            "VERGEN_OVERRIDES_FILE",
+            "/release/vergen.overrides",
+        )]));
+        assert!(warnings.is_empty());
+        assert_eq!(
+            config.overrides_file(),
+            &Some(PathBuf::from("/release/vergen.overrides"))
+        );
+        let _ = config.apply_overrides(vars(&[("VERGEN_OVERRIDES_FILE", "")]));
+        assert_eq!(config.overrides_file(), &None);
+    }
+
+    #[cfg(feature = "build")]
+    #[test]
+    fn env_disables_build_timestamp() {
+        let mut config = Instructions::default();
+        assert!(config.build().timestamp());
+        let _ = config.apply_overrides(vars(&[("VERGEN_DISABLE_BUILD_TIMESTAMP", "1")]));
+        assert!(!config.build().timestamp());
+        assert!(config.build().semver());
+    }
+
+    #[cfg(feature = "build")]
+    #[test]
+    fn env_beats_code() {
+        let mut config = Instructions::default();
+        *config.build_mut().semver_mut() = false;
+        let _ = config.apply_overrides(vars(&[("VERGEN_DISABLE_BUILD_SEMVER", "false")]));
+        assert!(config.build().semver());
+    }
+
+    #[cfg(feature = "build")]
+    #[test]
+    fn env_enables_build_semver_components() {
+        let mut config = Instructions::default();
+        assert!(!config.build().semver_components());
+        let _ = config.apply_overrides(vars(&[("VERGEN_DISABLE_BUILD_SEMVER_COMPONENTS", "0")]));
+        assert!(config.build().semver_components());
+    }
+
+    #[cfg(feature = "build")]
+    #[test]
+    fn env_enables_build_cpu_count() {
+        let mut config = Instructions::default();
+        assert!(!config.build().cpu_count());
+        let _ = config.apply_overrides(vars(&[("VERGEN_DISABLE_BUILD_CPU_COUNT", "off")]));
+        assert!(config.build().cpu_count());
+    }
+
+    #[cfg(feature = "cargo")]
+    #[test]
+    fn env_enables_cargo_libc_version() {
+        let mut config = Instructions::default();
+        assert!(!config.cargo().libc_version());
+        let _ = config.apply_overrides(vars(&[("VERGEN_DISABLE_CARGO_LIBC_VERSION", "no")]));
+        assert!(config.cargo().libc_version());
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn env_enables_git_object_format() {
+        let mut config = Instructions::default();
+        assert!(!config.git().object_format());
+        let _ = config.apply_overrides(vars(&[("VERGEN_DISABLE_GIT_OBJECT_FORMAT", "false")]));
+        assert!(config.git().object_format());
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn env_enables_git_parent_count() {
+        let mut config = Instructions::default();
+        assert!(!config.git().parent_count());
+        let _ = config.apply_overrides(vars(&[("VERGEN_DISABLE_GIT_PARENT_COUNT", "0")]));
+        assert!(config.git().parent_count());
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn env_disables_git() {
+        let mut config = Instructions::default();
+        let _ = config.apply_overrides(vars(&[("VERGEN_DISABLE_GIT", "true")]));
+        assert!(!config.git().has_enabled());
+    }
+
+    #[cfg(feature = "rustc")]
+    #[test]
+    fn env_disables_rustc_sha() {
+        let mut config = Instructions::default();
+        let _ = config.apply_overrides(vars(&[("VERGEN_DISABLE_RUSTC_COMMIT_HASH", "yes")]));
+        assert!(!config.rustc().sha());
+    }
+}
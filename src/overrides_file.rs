@@ -0,0 +1,169 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` instruction value overrides file
+
+use crate::{
+    config::{Config, VergenKey},
+    error::Error,
+    sticky::find_key,
+};
+use anyhow::Result;
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// The environment variable with the path of the overrides file, in place of the `overrides_file` field
+pub(crate) const OVERRIDES_FILE_VAR: &str = "VERGEN_OVERRIDES_FILE";
+
+/// Replace the values with the ones from the overrides file, and warn about the lines that aren't used
+pub(crate) fn apply_overrides_file(path: &Path, config: &mut Config) -> Result<()> {
+    let contents = fs::read_to_string(path).map_err(|source| Error::OverridesFile {
+        path: path.display().to_string(),
+        source,
+    })?;
+    let mut warnings = vec![];
+    let values = parse_overrides(&path.display().to_string(), &contents, &mut warnings);
+    for (key, value) in values {
+        let _ = config.cfg_map_mut().insert(key, Some(value));
+        let _ = config.file_keys_mut().insert(key);
+    }
+    config.warnings_mut().extend(warnings);
+    *config.overrides_path_mut() = Some(path.to_path_buf());
+    Ok(())
+}
+
+/// The values of the `KEY=value` lines by key, the last one if a key is set more than once
+fn parse_overrides(
+    path: &str,
+    contents: &str,
+    warnings: &mut Vec<String>,
+) -> BTreeMap<VergenKey, String> {
+    let mut values = BTreeMap::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let number = index + 1;
+        let (name, value) = if let Some(eq) = line.find('=') {
+            (line[..eq].trim(), line[eq + 1..].trim())
+        } else {
+            warnings.push(format!(
+                "vergen: ignoring line {} of the overrides file '{}', use 'KEY=value'",
+                number, path
+            ));
+            continue;
+        };
+        match find_key(name) {
+            Some(key) => {
+                if values.insert(key, value.to_string()).is_some() {
+                    warnings.push(format!(
+                        "vergen: {} is set more than once in the overrides file '{}', line {} is used",
+                        name, path, number
+                    ));
+                }
+            }
+            None => warnings.push(format!(
+                "vergen: ignoring unknown instruction '{}' on line {} of the overrides file '{}'",
+                name, number, path
+            )),
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod test {
+    use super::{apply_overrides_file, parse_overrides};
+    use crate::config::{Config, VergenKey};
+    use std::{env, fs, path::PathBuf};
+
+    #[test]
+    fn parsed_lines() {
+        let contents = "# Exported from the release checkout\n\
+                        VERGEN_GIT_SHA=75b390dc6c05a6a4aa2791cc7b3934591803bc22\r\n\
+                        \n\
+                        VERGEN_GIT_BRANCH = release/1.2 \n\
+                        VERGEN_BANNER=a=b\n\
+                        VERGEN_GIT_SHORT_SHA=75b390d\n\
+                        VERGEN_GIT_SEMVER\n\
+                        VERGEN_GIT_BRANCH=main\n";
+        let mut warnings = vec![];
+        let values = parse_overrides("vergen.overrides", contents, &mut warnings);
+        assert_eq!(
+            values.into_iter().collect::<Vec<(VergenKey, String)>>(),
+            [
+                (VergenKey::Branch, "main".to_string()),
+                (
+                    VergenKey::Sha,
+                    "75b390dc6c05a6a4aa2791cc7b3934591803bc22".to_string()
+                ),
+                (VergenKey::Banner, "a=b".to_string()),
+            ]
+        );
+        assert_eq!(
+            warnings,
+            [
+                "vergen: ignoring unknown instruction 'VERGEN_GIT_SHORT_SHA' on line 6 of the overrides file 'vergen.overrides'",
+                "vergen: ignoring line 7 of the overrides file 'vergen.overrides', use 'KEY=value'",
+                "vergen: VERGEN_GIT_BRANCH is set more than once in the overrides file 'vergen.overrides', line 8 is used",
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_file_fails() {
+        let mut config = Config::default();
+        let path = PathBuf::from("testdata").join("vergen.overrides.missing");
+        let error = apply_overrides_file(&path, &mut config).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("config: Unable to read the overrides file"));
+    }
+
+    #[test]
+    fn file_values_win() {
+        let dir = env::temp_dir().join("vergen_overrides_file_values_win");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("vergen.overrides");
+        fs::write(
+            &path,
+            "VERGEN_GIT_SHA=75b390d\nVERGEN_RUSTC_CHANNEL=stable\n",
+        )
+        .unwrap();
+
+        let mut config = Config::default();
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Sha, Some("abc123".to_string()));
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Branch, Some("main".to_string()));
+        apply_overrides_file(&path, &mut config).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            config.cfg_map()[&VergenKey::Sha].as_deref(),
+            Some("75b390d")
+        );
+        assert_eq!(
+            config.cfg_map()[&VergenKey::RustcChannel].as_deref(),
+            Some("stable")
+        );
+        assert_eq!(
+            config.cfg_map()[&VergenKey::Branch].as_deref(),
+            Some("main")
+        );
+        assert_eq!(config.overrides_path(), &Some(path));
+        assert_eq!(
+            config.file_keys().iter().copied().collect::<Vec<_>>(),
+            [VergenKey::Sha, VergenKey::RustcChannel]
+        );
+        assert!(config.warnings().is_empty());
+    }
+}
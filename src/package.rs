@@ -0,0 +1,257 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` per-package instruction sets
+
+use crate::{
+    built::version_parts,
+    config::{Config, Instructions, VergenKey},
+    error::ConfigError,
+    feature::configure_package_git,
+    sanitize::sanitize,
+    truncate::truncate,
+};
+use anyhow::Result;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+/// Configuration for the values of one package of a workspace, see [`for_package`](crate::Config::for_package)
+///
+/// * If the `path_scope` field is set, the package's SHA and commit date/time/age values describe the last commit that
+///   touched that path, as with the `path_scope` [`Git`](crate::Git) field.  The path is relative to the repository
+///   root, i.e. `crates/api`.
+/// * If the `version` field is set, the package's `VERGEN_BUILD_SEMVER` value, and its components if the
+///   `semver_components` [`Build`](crate::Build) field is true, are that version rather than `CARGO_PKG_VERSION`.
+/// * Every other value is the shared value.
+///
+/// # Example
+///
+/// ```
+/// use vergen::{Config, Package};
+///
+/// let mut api = Package::default();
+/// *api.path_scope_mut() = Some("crates/api".into());
+/// *api.version_mut() = Some("1.4.0".to_string());
+///
+/// let mut config = Config::default();
+/// config.for_package("api", api);
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
+pub struct Package {
+    /// The path the package's SHA and commit date/time/age values describe, or None for `HEAD`.
+    path_scope: Option<PathBuf>,
+    /// The package's version, or None for `CARGO_PKG_VERSION`.
+    version: Option<String>,
+}
+
+impl Package {
+    /// The path the package's SHA and commit date/time/age values describe, or None for `HEAD`.
+    pub(crate) fn path_scope(&self) -> &Option<PathBuf> {
+        &self.path_scope
+    }
+
+    /// The path the package's SHA and commit date/time/age values describe, or None for `HEAD`.
+    pub fn path_scope_mut(&mut self) -> &mut Option<PathBuf> {
+        &mut self.path_scope
+    }
+
+    /// The package's version, or None for `CARGO_PKG_VERSION`.
+    pub(crate) fn version(&self) -> &Option<String> {
+        &self.version
+    }
+
+    /// The package's version, or None for `CARGO_PKG_VERSION`.
+    pub fn version_mut(&mut self) -> &mut Option<String> {
+        &mut self.version
+    }
+}
+
+/// The suffix of the package's instruction names, i.e. `API_V2` for the `api-v2` package
+pub(crate) fn suffix(name: &str) -> String {
+    name.to_ascii_uppercase().replace('-', "_")
+}
+
+pub(crate) fn validate(packages: &BTreeMap<String, Package>, errors: &mut Vec<ConfigError>) {
+    let mut suffixes = BTreeSet::new();
+    for (name, package) in packages {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            errors.push(ConfigError::new(
+                "packages",
+                format!("{:?}", name),
+                "use ASCII alphanumeric characters, '-', and '_' only, i.e. the package name",
+            ));
+        } else if !suffixes.insert(suffix(name)) {
+            errors.push(ConfigError::new(
+                "packages",
+                format!("{:?}", name),
+                "use names that differ by more than case and '-' or '_', they share an instruction suffix",
+            ));
+        }
+        if package
+            .path_scope
+            .as_ref()
+            .map_or(false, |path| path.as_os_str().is_empty())
+        {
+            errors.push(ConfigError::new(
+                format!("packages.{}.path_scope", name),
+                "\"\"",
+                "use a path relative to the repository root, i.e. 'crates/api', or None for HEAD",
+            ));
+        }
+    }
+}
+
+/// Add the values of each package, the shared values with the package's own in their place
+///
+/// Only the package's own values are gathered, the shared values are copied from the shared configuration.
+pub(crate) fn add_packages(
+    instructions: &Instructions,
+    docs_rs: bool,
+    repo_path: Option<&Path>,
+    config: &mut Config,
+) -> Result<()> {
+    let mut packages = BTreeMap::new();
+    for (name, package) in instructions.packages() {
+        let mut values = config.cfg_map().clone();
+        if !docs_rs {
+            let mut own = Config::default();
+            if let Some(path_scope) = package.path_scope() {
+                configure_package_git(instructions, path_scope, repo_path, &mut own)?;
+            }
+            if let Some(version) = package.version() {
+                add_version(version, &mut own);
+            }
+            sanitize(*instructions.sanitize(), &mut own)?;
+            truncate(instructions.truncate(), &mut own);
+            config.warnings_mut().extend(own.warnings().iter().cloned());
+
+            // Only the values emitted for the workspace are replaced
+            for (key, value) in own.cfg_map_mut().iter_mut() {
+                if let (Some(shared), Some(value)) = (values.get_mut(key), value.take()) {
+                    if shared.is_some() {
+                        *shared = Some(value);
+                    }
+                }
+            }
+        }
+        let _ = packages.insert(name.clone(), values);
+    }
+    *config.packages_mut() = packages;
+    Ok(())
+}
+
+/// The package's semver and its components
+fn add_version(version: &str, config: &mut Config) {
+    let [major, minor, patch, pre] = version_parts(Some(version));
+    let values = [
+        (VergenKey::BuildSemver, Some(version)),
+        (VergenKey::BuildSemverMajor, major),
+        (VergenKey::BuildSemverMinor, minor),
+        (VergenKey::BuildSemverPatch, patch),
+        (VergenKey::BuildSemverPre, pre),
+    ];
+    for (key, value) in &values {
+        let _ = config.cfg_map_mut().insert(*key, value.map(str::to_string));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{add_packages, suffix, validate, Package};
+    use crate::config::{Config, Instructions, VergenKey};
+    use std::{collections::BTreeMap, path::PathBuf};
+
+    #[test]
+    fn suffixes() {
+        assert_eq!(suffix("api"), "API");
+        assert_eq!(suffix("api-v2"), "API_V2");
+        assert_eq!(suffix("build_support"), "BUILD_SUPPORT");
+    }
+
+    #[test]
+    fn invalid_packages() {
+        let mut packages = BTreeMap::new();
+        for name in &["", "api v2", "api-v2", "API_V2"] {
+            let _ = packages.insert((*name).to_string(), Package::default());
+        }
+        let mut scoped = Package::default();
+        *scoped.path_scope_mut() = Some(PathBuf::new());
+        let _ = packages.insert("cli".to_string(), scoped);
+        let mut errors = vec![];
+        validate(&packages, &mut errors);
+        let errors: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|error| (error.field().as_str(), error.value().as_str()))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                ("packages", "\"\""),
+                ("packages", "\"api v2\""),
+                ("packages", "\"api-v2\""),
+                ("packages.cli.path_scope", "\"\""),
+            ]
+        );
+    }
+
+    #[test]
+    fn package_versions() {
+        let mut instructions = Instructions::default();
+        let mut api = Package::default();
+        *api.version_mut() = Some("2.1.0-rc.1".to_string());
+        instructions.for_package("api", api);
+        instructions.for_package("cli", Package::default());
+
+        let mut config = Config::default();
+        for (key, value) in &[
+            (VergenKey::BuildSemver, "1.0.0"),
+            (VergenKey::BuildSemverMajor, "1"),
+            (VergenKey::BuildTimestamp, "2021-02-12T01:54:15+00:00"),
+        ] {
+            let _ = config
+                .cfg_map_mut()
+                .insert(*key, Some((*value).to_string()));
+        }
+        add_packages(&instructions, false, None, &mut config).unwrap();
+
+        let value = |package: &str, key| config.packages()[package][&key].clone();
+        assert_eq!(
+            value("api", VergenKey::BuildSemver),
+            Some("2.1.0-rc.1".to_string())
+        );
+        assert_eq!(
+            value("api", VergenKey::BuildSemverMajor),
+            Some("2".to_string())
+        );
+        // Only the values emitted for the workspace are replaced
+        assert_eq!(value("api", VergenKey::BuildSemverPre), None);
+        assert_eq!(
+            value("api", VergenKey::BuildTimestamp),
+            value("cli", VergenKey::BuildTimestamp)
+        );
+        assert_eq!(
+            value("cli", VergenKey::BuildSemver),
+            Some("1.0.0".to_string())
+        );
+    }
+}
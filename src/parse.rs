@@ -0,0 +1,161 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` cargo instruction parsing, for testing build scripts
+
+#![cfg_attr(not(feature = "test-util"), allow(unreachable_pub))]
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// The cargo instructions parsed from a build script's output, see [`parse_instructions`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ParsedOutput {
+    /// The `rustc-env` values, by name
+    rustc_env: BTreeMap<String, String>,
+    /// The `rerun-if-changed` paths, in output order
+    rerun_if_changed: Vec<PathBuf>,
+    /// The `rerun-if-env-changed` variable names, in output order
+    rerun_if_env_changed: Vec<String>,
+    /// The `rustc-cfg` flags, in output order
+    rustc_cfg: Vec<String>,
+    /// The `warning` messages, in output order
+    warnings: Vec<String>,
+}
+
+impl ParsedOutput {
+    /// The `rustc-env` values, by name
+    #[must_use]
+    pub fn rustc_env(&self) -> &BTreeMap<String, String> {
+        &self.rustc_env
+    }
+
+    /// The `rerun-if-changed` paths, in output order
+    #[must_use]
+    pub fn rerun_if_changed(&self) -> &Vec<PathBuf> {
+        &self.rerun_if_changed
+    }
+
+    /// The `rerun-if-env-changed` variable names, in output order
+    #[must_use]
+    pub fn rerun_if_env_changed(&self) -> &Vec<String> {
+        &self.rerun_if_env_changed
+    }
+
+    /// The `rustc-cfg` flags, in output order
+    #[must_use]
+    pub fn rustc_cfg(&self) -> &Vec<String> {
+        &self.rustc_cfg
+    }
+
+    /// The `warning` messages, in output order
+    #[must_use]
+    pub fn warnings(&self) -> &Vec<String> {
+        &self.warnings
+    }
+}
+
+impl ParsedOutput {
+    /// The `rustc-env` value with the given name
+    #[must_use]
+    pub fn env(&self, name: &str) -> Option<&str> {
+        self.rustc_env.get(name).map(String::as_str)
+    }
+}
+
+/// Parse the cargo instructions a build script wrote to stdout, i.e. to check them in an integration test
+///
+/// * Both the `cargo:` and `cargo::` syntaxes are understood, and may be mixed.
+/// * Lines that aren't cargo instructions, and instructions other than those in [`ParsedOutput`], are ignored.
+/// * A `rustc-env` value is everything after the first `=` following the name, so it may contain `=` itself.
+/// * A later `rustc-env` value for the same name replaces the earlier one, as it does for cargo.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "test-util")]
+/// # {
+/// use vergen::parse_instructions;
+///
+/// let parsed = parse_instructions(
+///     "cargo:rustc-env=VERGEN_GIT_SHA=95fc0f5\nnot an instruction\ncargo::rustc-env=FLAGS=-C opt-level=3\n",
+/// );
+/// assert_eq!(parsed.env("VERGEN_GIT_SHA"), Some("95fc0f5"));
+/// assert_eq!(parsed.env("FLAGS"), Some("-C opt-level=3"));
+/// # }
+/// ```
+#[must_use]
+pub fn parse_instructions(stdout: &str) -> ParsedOutput {
+    let mut parsed = ParsedOutput::default();
+    let instructions = stdout.lines().filter_map(|line| {
+        line.strip_prefix("cargo::")
+            .or_else(|| line.strip_prefix("cargo:"))
+            .and_then(|instruction| instruction.split_once('='))
+    });
+    for (name, value) in instructions {
+        match name {
+            "rustc-env" => {
+                if let Some((key, value)) = value.split_once('=') {
+                    let _ = parsed.rustc_env.insert(key.to_string(), value.to_string());
+                }
+            }
+            "rerun-if-changed" => parsed.rerun_if_changed.push(PathBuf::from(value)),
+            "rerun-if-env-changed" => parsed.rerun_if_env_changed.push(value.to_string()),
+            "rustc-cfg" => parsed.rustc_cfg.push(value.to_string()),
+            "warning" => parsed.warnings.push(value.to_string()),
+            _ => {}
+        }
+    }
+    parsed
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_instructions;
+    use std::path::PathBuf;
+
+    #[test]
+    fn both_syntaxes() {
+        let parsed = parse_instructions(
+            "cargo:rustc-env=A=1\n\
+             cargo::rustc-env=B=2\n\
+             cargo:rerun-if-changed=.git/HEAD\n\
+             cargo::rerun-if-env-changed=VERGEN_DISABLE_GIT\n\
+             cargo:rustc-cfg=vergen_git_dirty\n\
+             cargo::warning=vergen: something happened\n",
+        );
+        assert_eq!(parsed.env("A"), Some("1"));
+        assert_eq!(parsed.env("B"), Some("2"));
+        assert_eq!(parsed.rerun_if_changed(), &[PathBuf::from(".git/HEAD")]);
+        assert_eq!(parsed.rerun_if_env_changed(), &["VERGEN_DISABLE_GIT"]);
+        assert_eq!(parsed.rustc_cfg(), &["vergen_git_dirty"]);
+        assert_eq!(parsed.warnings(), &["vergen: something happened"]);
+    }
+
+    #[test]
+    fn values_with_equals() {
+        let parsed = parse_instructions("cargo:rustc-env=FLAGS=a=b==c\ncargo:rustc-env=EMPTY=\n");
+        assert_eq!(parsed.env("FLAGS"), Some("a=b==c"));
+        assert_eq!(parsed.env("EMPTY"), Some(""));
+    }
+
+    #[test]
+    fn ignores_other_lines() {
+        let parsed = parse_instructions(
+            "Compiling foo\r\n\
+             cargo:rustc-env=A=1\r\n\
+             cargo:rustc-link-arg=-T\n\
+             cargo:rustc-env=MALFORMED\n\
+             cargo:\n\
+             xcargo:rustc-env=B=2\n\
+             cargo:rustc-env=A=2\n",
+        );
+        assert_eq!(parsed.rustc_env().len(), 1);
+        assert_eq!(parsed.env("A"), Some("2"));
+        assert!(parsed.warnings().is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` repository path normalization
+
+use crate::config::{Config, NonUtf8Policy};
+use std::path::{Path, PathBuf};
+
+/// The `MAX_PATH` limit on Windows, including the terminating NUL
+const MAX_PATH: usize = 260;
+
+/// Normalize a path used for repository discovery or emitted in a `cargo:rerun-if-changed` instruction
+///
+/// On Windows, `git` reports paths with `/` separators, so joining a ref like `refs/heads/main` to them, or to a
+/// path with the verbatim `\\?\` prefix, gives mixed separators that `cargo` can't watch. The normalized path
+/// only uses `\` separators, and keeps the verbatim prefix only when the path is longer than `MAX_PATH`. Without
+/// the prefix, discovery also stops at the drive root or the `\\server\share` root as it should.
+///
+/// Everywhere else, the path is unchanged.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    match path.to_str() {
+        Some(windows_path) if cfg!(windows) => PathBuf::from(normalize_windows(windows_path)),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Apply the [`NonUtf8Policy`] to a path that will be emitted, with a warning if it isn't valid UTF-8
+///
+/// The path itself is only converted when the instruction is written.
+pub(crate) fn emitted_path(
+    policy: NonUtf8Policy,
+    path: PathBuf,
+    config: &mut Config,
+) -> Option<PathBuf> {
+    if path.to_str().is_some() {
+        return Some(path);
+    }
+    let (emitted, action) = match policy {
+        NonUtf8Policy::Lossy => (Some(path.clone()), "emitted with replacement characters"),
+        NonUtf8Policy::Skip => (None, "skipped"),
+    };
+    config.warnings_mut().push(format!(
+        "vergen: the path '{}' isn't valid UTF-8, it was {}",
+        path.display(),
+        action
+    ));
+    emitted
+}
+
+/// Normalize a Windows path, see [`normalize`]
+fn normalize_windows(path: &str) -> String {
+    let path = path.replace('/', "\\");
+    let (unc, rest) = if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        (true, rest)
+    } else if let Some(rest) = path.strip_prefix(r"\\?\") {
+        if !has_drive(rest) {
+            // A volume GUID or device path only works with the prefix
+            return path;
+        }
+        (false, rest)
+    } else if let Some(rest) = path.strip_prefix(r"\\") {
+        (true, rest)
+    } else {
+        (false, path.as_str())
+    };
+
+    let mut rest = rest
+        .split('\\')
+        .enumerate()
+        .filter(|(i, part)| *i == 0 || !(part.is_empty() || *part == "."))
+        .map(|(_, part)| part)
+        .collect::<Vec<_>>()
+        .join("\\");
+    // 'C:' alone is relative to the current directory on that drive
+    if !unc && rest.len() == 2 && has_drive(&rest) {
+        rest.push('\\');
+    }
+
+    let plain = if unc {
+        format!(r"\\{}", rest)
+    } else {
+        rest.clone()
+    };
+    // The limit is in UTF-16 code units, and only absolute paths can take the prefix
+    if plain.encode_utf16().count() < MAX_PATH || !(unc || has_drive(&rest)) {
+        plain
+    } else if unc {
+        format!(r"\\?\UNC\{}", rest)
+    } else {
+        format!(r"\\?\{}", rest)
+    }
+}
+
+/// Does the path start with a drive, i.e. `C:`
+fn has_drive(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+#[cfg(test)]
+mod test {
+    use super::{normalize_windows, MAX_PATH};
+
+    fn long(root: &str) -> String {
+        let mut path = root.to_string();
+        while path.len() < MAX_PATH {
+            path.push_str(r"\very-long-directory");
+        }
+        path
+    }
+
+    #[test]
+    fn mixed_separators() {
+        assert_eq!(
+            normalize_windows(r"C:/work/vergen/.git/refs/heads/feature/git2"),
+            r"C:\work\vergen\.git\refs\heads\feature\git2"
+        );
+        assert_eq!(
+            normalize_windows(r"C:\work\vergen\.git/HEAD"),
+            r"C:\work\vergen\.git\HEAD"
+        );
+        assert_eq!(normalize_windows(r"C:\work\.\vergen\\"), r"C:\work\vergen");
+        assert_eq!(normalize_windows(r"C:/"), r"C:\");
+        assert_eq!(normalize_windows(r"..\vergen"), r"..\vergen");
+    }
+
+    #[test]
+    fn verbatim_prefix() {
+        assert_eq!(
+            normalize_windows(r"\\?\C:\work\vergen/.git/HEAD"),
+            r"C:\work\vergen\.git\HEAD"
+        );
+        assert_eq!(
+            normalize_windows(r"\\?\UNC\server\share\vergen\.git/HEAD"),
+            r"\\server\share\vergen\.git\HEAD"
+        );
+        assert_eq!(
+            normalize_windows(r"//server/share/vergen"),
+            r"\\server\share\vergen"
+        );
+        assert_eq!(
+            normalize_windows(r"\\?\Volume{d1a2b3c4}\vergen"),
+            r"\\?\Volume{d1a2b3c4}\vergen"
+        );
+    }
+
+    #[test]
+    fn long_paths() {
+        let path = long(r"C:\work");
+        assert_eq!(normalize_windows(&path), format!(r"\\?\{}", path));
+        assert_eq!(
+            normalize_windows(&format!(r"\\?\{}", path.replace('\\', "/"))),
+            format!(r"\\?\{}", path)
+        );
+
+        let share = long(r"\\server\share");
+        assert_eq!(
+            normalize_windows(&share),
+            format!(r"\\?\UNC\{}", &share[2..])
+        );
+        assert_eq!(
+            normalize_windows(&format!(r"\\?\UNC\{}", &share[2..])),
+            format!(r"\\?\UNC\{}", &share[2..])
+        );
+
+        // A relative path can't take the prefix
+        let relative = long("work");
+        assert_eq!(normalize_windows(&relative), relative);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn unchanged_elsewhere() {
+        use super::normalize;
+        use std::path::Path;
+
+        let path = Path::new("/work/vergen/.git/refs/heads/feature/git2");
+        assert_eq!(normalize(path), path);
+    }
+}
@@ -0,0 +1,314 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` runtime pretty printer
+
+use std::{collections::BTreeMap, env, io::Write};
+
+/// The escape sequence the labels start with when colored, bold cyan
+const LABEL_COLOR: &str = "\u{1b}[1;36m";
+/// The escape sequence that ends a colored label
+const RESET: &str = "\u{1b}[0m";
+/// The text that ends a truncated value
+const MARKER: &str = "...";
+
+/// Whether the [`PrettyPrinter`] colors the labels
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ColorChoice {
+    /// Always color the labels
+    Always,
+    /// Never color the labels
+    Never,
+    /// Color the labels, unless the `NO_COLOR` environment variable is set to a non-empty value, or `TERM` is `dumb`
+    Auto,
+}
+
+/// What the [`PrettyPrinter`] does with a value longer than the `max_width`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Overflow {
+    /// Continue the value on the following lines, aligned with the values
+    Wrap,
+    /// Cut the value, and end it with `...`
+    Truncate,
+}
+
+/// Write the generated values as an aligned table, i.e. for a `--bug-report` flag
+///
+/// The `pretty` feature enables the printer, which runs in your binary rather than the build script, so `vergen` is
+/// also a regular dependency, usually with the other features disabled.  It takes `(name, value)` pairs, i.e. from
+/// `VERGEN_MAP` in the [include file](crate::Config#include-file), or ones you collect with `env!`, and writes one
+/// `label  value` line each, sorted by name, with the values aligned.
+///
+/// * The `color` field decides whether the labels are colored, see [`ColorChoice`].  `Auto` can't tell whether the
+///   writer is a terminal, so pick `Never` when writing to a file.
+/// * The `keys` field limits the table to the named values, in place of all of them.
+/// * The `labels` field replaces the name of a value with a label of your own, i.e. `Commit` in place of
+///   `VERGEN_GIT_SHA`.
+/// * The `max_width` field limits the width of the value column in characters, and the `overflow` field decides
+///   whether a longer value is wrapped or truncated, see [`Overflow`].
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// use vergen::{ColorChoice, PrettyPrinter};
+///
+/// # pub fn main() -> Result<()> {
+/// let mut printer = PrettyPrinter::default();
+/// *printer.color_mut() = ColorChoice::Never;
+/// let _ = printer
+///     .labels_mut()
+///     .insert("VERGEN_GIT_SHA".to_string(), "Commit".to_string());
+///
+/// let values = [
+///     ("VERGEN_GIT_SHA", "75b390d"),
+///     ("VERGEN_BUILD_SEMVER", "6.0.0"),
+/// ];
+/// let mut stderr = std::io::stderr();
+/// printer.print(values.iter().copied(), &mut stderr)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PrettyPrinter {
+    /// Whether the labels are colored.
+    color: ColorChoice,
+    /// The names of the values to print, or empty for all of them.
+    keys: Vec<String>,
+    /// The label to print in place of the name, by name.
+    labels: BTreeMap<String, String>,
+    /// The maximum width of the value column in characters, or None for no limit.
+    max_width: Option<usize>,
+    /// What to do with a value longer than `max_width`.
+    overflow: Overflow,
+}
+
+impl PrettyPrinter {
+    /// Whether the labels are colored.
+    pub fn color_mut(&mut self) -> &mut ColorChoice {
+        &mut self.color
+    }
+
+    /// The names of the values to print, or empty for all of them.
+    pub fn keys_mut(&mut self) -> &mut Vec<String> {
+        &mut self.keys
+    }
+
+    /// The label to print in place of the name, by name.
+    pub fn labels_mut(&mut self) -> &mut BTreeMap<String, String> {
+        &mut self.labels
+    }
+
+    /// The maximum width of the value column in characters, or None for no limit.
+    pub fn max_width_mut(&mut self) -> &mut Option<usize> {
+        &mut self.max_width
+    }
+
+    /// What to do with a value longer than `max_width`.
+    pub fn overflow_mut(&mut self) -> &mut Overflow {
+        &mut self.overflow
+    }
+}
+
+impl Default for PrettyPrinter {
+    fn default() -> Self {
+        Self {
+            color: ColorChoice::Auto,
+            keys: vec![],
+            labels: BTreeMap::new(),
+            max_width: None,
+            overflow: Overflow::Wrap,
+        }
+    }
+}
+
+impl PrettyPrinter {
+    /// Write the `(name, value)` pairs to the writer, one line each
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the writer fails.
+    pub fn print<I, K, V, T>(&self, values: I, writer: &mut T) -> std::io::Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+        T: Write,
+    {
+        let mut rows: Vec<(String, String)> = values
+            .into_iter()
+            .filter(|(name, _)| {
+                self.keys.is_empty() || self.keys.iter().any(|key| key == name.as_ref())
+            })
+            .map(|(name, value)| {
+                (
+                    name.as_ref().to_string(),
+                    value.as_ref().trim_end().to_string(),
+                )
+            })
+            .collect();
+        rows.sort();
+
+        let labels: Vec<&str> = rows
+            .iter()
+            .map(|(name, _)| self.labels.get(name).map_or(name.as_str(), String::as_str))
+            .collect();
+        let width = labels
+            .iter()
+            .map(|label| label.chars().count())
+            .max()
+            .unwrap_or(0);
+        let colored = self.colored();
+
+        for (label, (_, value)) in labels.iter().zip(&rows) {
+            let padded = format!("{:width$}", label, width = width);
+            let padded = if colored {
+                format!("{}{}{}", LABEL_COLOR, padded, RESET)
+            } else {
+                padded
+            };
+            let mut lines = self.value_lines(value).into_iter();
+            writeln!(writer, "{}  {}", padded, lines.next().unwrap_or_default())?;
+            for line in lines {
+                writeln!(writer, "{:width$}  {}", "", line, width = width)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn colored(&self) -> bool {
+        match self.color {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                env::var_os("NO_COLOR").map_or(true, |no_color| no_color.is_empty())
+                    && env::var_os("TERM").map_or(true, |term| term != "dumb")
+            }
+        }
+    }
+
+    /// The value as the lines of the value column, with the `max_width` applied
+    fn value_lines(&self, value: &str) -> Vec<String> {
+        let chars: Vec<char> = value.chars().collect();
+        match self.max_width {
+            Some(max_width) if max_width > 0 && chars.len() > max_width => match self.overflow {
+                Overflow::Wrap => chars
+                    .chunks(max_width)
+                    .map(|chunk| chunk.iter().collect::<String>().trim_start().to_string())
+                    .collect(),
+                Overflow::Truncate => {
+                    let kept = max_width.saturating_sub(MARKER.len());
+                    vec![format!(
+                        "{}{}",
+                        chars[..kept].iter().collect::<String>(),
+                        &MARKER[..max_width.min(MARKER.len())]
+                    )]
+                }
+            },
+            _ => vec![value.to_string()],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ColorChoice, Overflow, PrettyPrinter};
+    use std::env;
+
+    const VALUES: [(&str, &str); 4] = [
+        ("VERGEN_GIT_SHA", "75b390dc6c05a6a4aa2791cc7b3934591803bc22"),
+        ("VERGEN_BUILD_SEMVER", "6.0.0"),
+        ("VERGEN_RUSTC_CHANNEL", "stable"),
+        (
+            "VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS",
+            "Fix the docs; Add a feature; Bump the version",
+        ),
+    ];
+
+    fn printer(color: ColorChoice) -> PrettyPrinter {
+        let mut printer = PrettyPrinter::default();
+        *printer.color_mut() = color;
+        let _ = printer
+            .labels_mut()
+            .insert("VERGEN_GIT_SHA".to_string(), "Commit".to_string());
+        let _ = printer
+            .labels_mut()
+            .insert("VERGEN_BUILD_SEMVER".to_string(), "Version".to_string());
+        let _ = printer.labels_mut().insert(
+            "VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS".to_string(),
+            "Changes".to_string(),
+        );
+        *printer.max_width_mut() = Some(24);
+        printer
+    }
+
+    fn print(printer: &PrettyPrinter) -> String {
+        let mut buf = vec![];
+        printer.print(VALUES.iter().copied(), &mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn plain_snapshot() {
+        assert_eq!(
+            print(&printer(ColorChoice::Never)),
+            include_str!("../testdata/pretty/plain.txt")
+        );
+    }
+
+    #[test]
+    fn colored_snapshot() {
+        assert_eq!(
+            print(&printer(ColorChoice::Always)),
+            include_str!("../testdata/pretty/colored.txt")
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let mut printer = printer(ColorChoice::Never);
+        *printer.overflow_mut() = Overflow::Truncate;
+        *printer.keys_mut() = vec![
+            "VERGEN_GIT_SHA".to_string(),
+            "VERGEN_BUILD_SEMVER".to_string(),
+        ];
+        assert_eq!(
+            print(&printer),
+            "Version  6.0.0\nCommit   75b390dc6c05a6a4aa279...\n"
+        );
+    }
+
+    #[test]
+    fn without_labels() {
+        let mut printer = PrettyPrinter::default();
+        *printer.color_mut() = ColorChoice::Never;
+        *printer.keys_mut() = vec![
+            "VERGEN_BUILD_SEMVER".to_string(),
+            "VERGEN_RUSTC_CHANNEL".to_string(),
+        ];
+        assert_eq!(
+            print(&printer),
+            "VERGEN_BUILD_SEMVER   6.0.0\nVERGEN_RUSTC_CHANNEL  stable\n"
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn no_color() {
+        let printer = printer(ColorChoice::Auto);
+        env::set_var("NO_COLOR", "1");
+        let plain = print(&printer);
+        env::set_var("NO_COLOR", "");
+        let colored = print(&printer);
+        env::remove_var("NO_COLOR");
+        assert!(!plain.contains('\u{1b}'));
+        if env::var_os("TERM").map_or(true, |term| term != "dumb") {
+            assert!(colored.contains('\u{1b}'));
+        }
+    }
+}
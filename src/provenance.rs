@@ -0,0 +1,195 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` build provenance document
+
+use crate::{
+    buildinfo::lockfile_digest,
+    config::{Config, VergenKey},
+};
+use anyhow::Result;
+use std::{env, fmt::Write};
+
+/// The `schema` member, changed whenever a member is added, removed, or changes meaning
+const SCHEMA: &str = "vergen-provenance/1";
+
+/// The `VERGEN_PROVENANCE_JSON` document
+///
+/// The document is a single line of JSON, so it fits in a `cargo:rustc-env` value.  The members are always present and
+/// in this order, and a value that wasn't generated is `null`:
+///
+/// ```text
+/// {
+///   "schema": "vergen-provenance/1",
+///   "builder": { "id": "vergen", "version": "6.0.0", "rustc": "1.55.0" },
+///   "build": { "target": "x86_64-unknown-linux-gnu", "timestamp": "2021-02-12T01:54:15Z" },
+///   "source": { "git_sha": "75b390dc6c05a6a4aa2791cc7b3934591803bc22" },
+///   "materials": [ { "uri": "Cargo.lock", "digest": { "sha256": "5f1e3c0a..." } } ]
+/// }
+/// ```
+///
+/// * `builder.version` is the version of `vergen` that generated the document.
+/// * `materials` is empty if there's no `Cargo.lock`, or without the `hash` feature.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct ProvenanceDocument {
+    /// `builder.rustc`, the `VERGEN_RUSTC_SEMVER` value
+    rustc: Option<String>,
+    /// `build.target`, the `VERGEN_CARGO_TARGET_TRIPLE` value, or the `TARGET` environment variable
+    target: Option<String>,
+    /// `build.timestamp`, the `VERGEN_BUILD_TIMESTAMP` value
+    timestamp: Option<String>,
+    /// `source.git_sha`, the `VERGEN_GIT_SHA` value
+    git_sha: Option<String>,
+    /// The `sha256` digest of the `Cargo.lock` material, as hex
+    lockfile_sha256: Option<String>,
+}
+
+impl ProvenanceDocument {
+    fn gather(source_date_epoch: bool, config: &Config) -> Result<Self> {
+        let value = |key| {
+            config
+                .cfg_map()
+                .get(&key)
+                .and_then(|value: &Option<String>| value.clone())
+        };
+        // Like the .buildinfo, an ignored SOURCE_DATE_EPOCH would make otherwise identical builds differ
+        let timestamp = value(VergenKey::BuildTimestamp)
+            .filter(|_| source_date_epoch || env::var_os("SOURCE_DATE_EPOCH").is_none());
+        Ok(Self {
+            rustc: value(VergenKey::RustcSemver),
+            target: value(VergenKey::CargoTargetTriple).or_else(|| env::var("TARGET").ok()),
+            timestamp,
+            git_sha: value(VergenKey::Sha),
+            lockfile_sha256: lockfile_digest()?.map(|(digest, _)| digest),
+        })
+    }
+
+    fn to_json(&self) -> String {
+        let materials = match &self.lockfile_sha256 {
+            Some(digest) => format!(
+                r#"{{"uri":"Cargo.lock","digest":{{"sha256":{}}}}}"#,
+                json_string(digest)
+            ),
+            None => String::new(),
+        };
+        format!(
+            r#"{{"schema":{},"builder":{{"id":"vergen","version":{},"rustc":{}}},"build":{{"target":{},"timestamp":{}}},"source":{{"git_sha":{}}},"materials":[{}]}}"#,
+            json_string(SCHEMA),
+            json_string(env!("CARGO_PKG_VERSION")),
+            json_value(&self.rustc),
+            json_value(&self.target),
+            json_value(&self.timestamp),
+            json_value(&self.git_sha),
+            materials,
+        )
+    }
+}
+
+fn json_value(value: &Option<String>) -> String {
+    value
+        .as_ref()
+        .map_or_else(|| "null".to_string(), |value| json_string(value))
+}
+
+/// The value as a JSON string, with quotes, backslashes, and control characters escaped
+fn json_string(value: &str) -> String {
+    let mut json = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", u32::from(c));
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Add the `VERGEN_PROVENANCE_JSON` instruction, from the generated values
+pub(crate) fn add_provenance(source_date_epoch: bool, config: &mut Config) -> Result<()> {
+    let document = ProvenanceDocument::gather(source_date_epoch, config)?;
+    let _ = config
+        .cfg_map_mut()
+        .insert(VergenKey::ProvenanceJson, Some(document.to_json()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{json_string, ProvenanceDocument};
+    use crate::config::{Config, VergenKey};
+
+    #[test]
+    fn document() {
+        let document = ProvenanceDocument {
+            rustc: Some("1.55.0".to_string()),
+            target: Some("x86_64-unknown-linux-gnu".to_string()),
+            timestamp: Some("2021-02-12T01:54:15Z".to_string()),
+            git_sha: Some("75b390dc6c05a6a4aa2791cc7b3934591803bc22".to_string()),
+            lockfile_sha256: Some("5f1e3c0a".to_string()),
+        };
+        assert_eq!(
+            document.to_json(),
+            format!(
+                r#"{{"schema":"vergen-provenance/1","builder":{{"id":"vergen","version":"{}","rustc":"1.55.0"}},"build":{{"target":"x86_64-unknown-linux-gnu","timestamp":"2021-02-12T01:54:15Z"}},"source":{{"git_sha":"75b390dc6c05a6a4aa2791cc7b3934591803bc22"}},"materials":[{{"uri":"Cargo.lock","digest":{{"sha256":"5f1e3c0a"}}}}]}}"#,
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn missing_values() {
+        assert_eq!(
+            ProvenanceDocument::default().to_json(),
+            format!(
+                r#"{{"schema":"vergen-provenance/1","builder":{{"id":"vergen","version":"{}","rustc":null}},"build":{{"target":null,"timestamp":null}},"source":{{"git_sha":null}},"materials":[]}}"#,
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn escaped_strings() {
+        assert_eq!(json_string(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+        assert_eq!(json_string("a\nb\u{1}"), r#""a\nb\u0001""#);
+    }
+
+    #[test]
+    fn generated_values() {
+        let mut config = Config::default();
+        for (key, value) in &[
+            (VergenKey::RustcSemver, "1.55.0"),
+            (VergenKey::CargoTargetTriple, "wasm32-wasi"),
+            (VergenKey::Sha, "75b390dc6c05a6a4aa2791cc7b3934591803bc22"),
+        ] {
+            let _ = config
+                .cfg_map_mut()
+                .insert(*key, Some((*value).to_string()));
+        }
+        let document = ProvenanceDocument::gather(true, &config).unwrap();
+        assert_eq!(document.rustc.as_deref(), Some("1.55.0"));
+        assert_eq!(document.target.as_deref(), Some("wasm32-wasi"));
+        assert_eq!(
+            document.git_sha.as_deref(),
+            Some("75b390dc6c05a6a4aa2791cc7b3934591803bc22")
+        );
+        assert!(document.timestamp.is_none());
+        // The crate's own Cargo.lock
+        #[cfg(feature = "hash")]
+        assert_eq!(
+            document.lockfile_sha256.map(|digest| digest.len()),
+            Some(64)
+        );
+    }
+}
@@ -0,0 +1,695 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` data providers
+//!
+//! The clock, git, and rustc information is read through these traits.  With the `test-util`
+//! feature enabled, fixed implementations can be installed on the [`Config`](crate::Config)
+//! to make the generated instructions deterministic.  Without it, the production implementations
+//! are always called directly.
+
+#![cfg_attr(not(feature = "test-util"), allow(unreachable_pub))]
+
+#[cfg(any(feature = "build", feature = "git", feature = "rustc"))]
+use crate::config::Instructions;
+#[cfg(any(feature = "git", feature = "rustc"))]
+use anyhow::Result;
+#[cfg(any(feature = "build", feature = "git", feature = "rustc"))]
+use std::fmt::Debug;
+#[cfg(feature = "git")]
+use std::path::{Path, PathBuf};
+#[cfg(all(
+    any(test, feature = "test-util"),
+    any(feature = "build", feature = "git", feature = "rustc")
+))]
+use std::sync::Arc;
+#[cfg(all(any(test, feature = "test-util"), feature = "git"))]
+use std::{sync::atomic::AtomicUsize, thread, time::Duration};
+#[cfg(any(feature = "build", feature = "git"))]
+use {
+    crate::config::Config,
+    std::env,
+    time::{OffsetDateTime, UtcOffset},
+};
+#[cfg(feature = "rustc")]
+use {crate::error::Error, rustc_version::VersionMeta, std::fs};
+
+/// Provides the current time
+#[cfg(any(feature = "build", feature = "git"))]
+pub trait ClockProvider: Debug + Send + Sync {
+    /// The current time in UTC
+    fn now_utc(&self) -> OffsetDateTime;
+    /// The current time in the local timezone
+    fn now_local(&self) -> OffsetDateTime;
+}
+
+/// Provides the git repository information
+#[cfg(feature = "git")]
+// On wasi, the repository is never opened, so only the fake repository implements it
+#[cfg_attr(all(target_os = "wasi", not(feature = "test-util")), allow(dead_code))]
+pub trait GitProvider: Debug {
+    /// The current branch name, `detached HEAD`, or None if it can't be determined
+    ///
+    /// # Errors
+    /// The repository could not be read.
+    fn branch(&self) -> Result<Option<String>>;
+    /// The `HEAD` committer time
+    ///
+    /// # Errors
+    /// The repository could not be read.
+    fn commit_time(&self) -> Result<OffsetDateTime>;
+    /// The `HEAD` committer's timezone offset, git's `%cz`
+    ///
+    /// # Errors
+    /// The repository could not be read.
+    #[cfg_attr(not(feature = "build"), allow(dead_code))]
+    fn commit_offset(&self) -> Result<UtcOffset>;
+    /// The full `HEAD` commit SHA
+    ///
+    /// # Errors
+    /// The repository could not be read.
+    fn sha(&self) -> Result<String>;
+    /// The short `HEAD` commit SHA
+    ///
+    /// # Errors
+    /// The repository could not be read.
+    fn short_sha(&self) -> Result<String>;
+    /// The hash of the `HEAD` commit's tree
+    ///
+    /// # Errors
+    /// The repository could not be read.
+    fn tree_hash(&self) -> Result<String>;
+    /// The `git describe` output, including lightweight tags if requested, with the dirty suffix if dirty
+    ///
+    /// If `always` is true, the abbreviated SHA is used when no tag is reachable, as with `git describe --always`.
+    ///
+    /// # Errors
+    /// There are no tags to describe `HEAD` with, and `always` is false or `HEAD` has no commit.
+    fn semver(&self, lightweight: bool, always: bool, dirty_suffix: Option<&str>)
+        -> Result<String>;
+    /// The number of files tracked in the index
+    fn tracked_files(&self) -> usize;
+    /// The number of commits since the last tag, and their subjects, newest first, if requested
+    ///
+    /// # Errors
+    /// The repository could not be read.
+    fn commits_since_tag(&self, subjects: bool) -> Result<(usize, Vec<String>)>;
+    /// The key id of the `HEAD` commit signature, or None if it doesn't have a good signature
+    ///
+    /// # Errors
+    /// The signature could not be checked.
+    fn signer(&self) -> Result<Option<String>>;
+    /// The number of local and remote branches, not counting symbolic refs like `origin/HEAD`
+    ///
+    /// # Errors
+    /// The repository could not be read.
+    fn branch_count(&self) -> Result<usize>;
+    /// The number of tags
+    ///
+    /// # Errors
+    /// The repository could not be read.
+    fn tag_count(&self) -> Result<usize>;
+    /// The number of distinct author emails in the history of `HEAD`, after the `.mailmap`, and whether the history is
+    /// partial because the repository is a shallow clone
+    ///
+    /// # Errors
+    /// The repository could not be read.
+    fn contributor_count(&self) -> Result<(usize, bool)>;
+    /// The commit where `HEAD` and the base ref diverged, the equivalent of `git merge-base HEAD <base>`, or None if
+    /// the base doesn't resolve or shares no history with `HEAD`
+    ///
+    /// # Errors
+    /// The repository could not be read.
+    fn merge_base(&self, base: &str) -> Result<Option<String>>;
+    /// The last commit that touched the path, relative to the repository root, or None if it has no history
+    ///
+    /// # Errors
+    /// The repository could not be read.
+    fn path_commit(&self, path: &Path) -> Result<Option<PathCommit>>;
+    /// The lightweight or annotated tag pointing at `HEAD`, or None if `HEAD` isn't tagged
+    ///
+    /// # Errors
+    /// The repository could not be read.
+    fn exact_tag(&self) -> Result<Option<String>>;
+    /// The absolute path of the working tree, or None for a bare repository
+    fn repo_root(&self) -> Option<PathBuf>;
+    /// The absolute path of the working tree, or None if the repository isn't a linked worktree
+    fn worktree(&self) -> Option<PathBuf>;
+    /// The number of commits reachable from `HEAD`
+    ///
+    /// # Errors
+    /// The repository could not be read.
+    fn commit_count(&self) -> Result<usize>;
+    /// The number of tracked files with uncommitted changes, staged or not
+    ///
+    /// # Errors
+    /// The repository could not be read.
+    fn dirty_files(&self) -> Result<usize>;
+    /// The object format of the repository, `sha1` or `sha256`, the equivalent of `git rev-parse --show-object-format`
+    ///
+    /// # Errors
+    /// The repository could not be read.
+    fn object_format(&self) -> Result<String>;
+    /// The number of parents of the `HEAD` commit, 0 for the initial commit and 2 or more for a merge commit
+    ///
+    /// # Errors
+    /// The repository could not be read.
+    fn parent_count(&self) -> Result<usize>;
+}
+
+/// The last commit that touched a path
+#[cfg(feature = "git")]
+#[cfg_attr(all(target_os = "wasi", not(feature = "test-util")), allow(dead_code))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PathCommit {
+    /// The full commit SHA
+    pub sha: String,
+    /// The short commit SHA
+    pub short_sha: String,
+    /// The committer time
+    pub commit_time: OffsetDateTime,
+}
+
+/// Provides the rustc version information
+#[cfg(feature = "rustc")]
+pub trait RustcProvider: Debug + Send + Sync {
+    /// The `rustc` version metadata
+    ///
+    /// # Errors
+    /// `rustc` could not be run, or its output could not be parsed.
+    fn version_meta(&self) -> Result<VersionMeta>;
+}
+
+/// The system clock
+#[cfg(any(feature = "build", feature = "git"))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+#[cfg(any(feature = "build", feature = "git"))]
+impl ClockProvider for SystemClock {
+    fn now_utc(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc()
+    }
+
+    fn now_local(&self) -> OffsetDateTime {
+        OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc())
+    }
+}
+
+/// The `rustc` used by cargo for this build
+#[cfg(feature = "rustc")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemRustc;
+
+#[cfg(feature = "rustc")]
+impl RustcProvider for SystemRustc {
+    fn version_meta(&self) -> Result<VersionMeta> {
+        Ok(rustc_version::version_meta()?)
+    }
+}
+
+/// A clock that is stopped at the given time
+#[cfg(all(
+    any(test, feature = "test-util"),
+    any(feature = "build", feature = "git")
+))]
+#[derive(Clone, Copy, Debug)]
+pub struct FixedClock(pub OffsetDateTime);
+
+#[cfg(all(
+    any(test, feature = "test-util"),
+    any(feature = "build", feature = "git")
+))]
+impl ClockProvider for FixedClock {
+    fn now_utc(&self) -> OffsetDateTime {
+        self.0
+    }
+
+    fn now_local(&self) -> OffsetDateTime {
+        self.0
+    }
+}
+
+/// A fake git repository that reports the given values
+#[cfg(all(any(test, feature = "test-util"), feature = "git"))]
+#[derive(Clone, Debug)]
+pub struct FakeRepo {
+    /// The `VERGEN_GIT_BRANCH` value
+    pub branch: Option<String>,
+    /// The time used for the commit date/time and age instructions
+    pub commit_time: OffsetDateTime,
+    /// The committer's timezone offset, used by the `timezone_from_git` field of the `Build` configuration
+    pub commit_offset: UtcOffset,
+    /// The `VERGEN_GIT_SHA` value
+    pub sha: String,
+    /// The `VERGEN_GIT_SHA_SHORT` value
+    pub short_sha: String,
+    /// The `VERGEN_GIT_TREE_HASH` value
+    pub tree_hash: String,
+    /// The `VERGEN_GIT_SEMVER` value, or None if there are no tags
+    pub semver: Option<String>,
+    /// The `VERGEN_GIT_SEMVER_LIGHTWEIGHT` value, or None if there are no tags
+    pub semver_lightweight: Option<String>,
+    /// Whether the working tree has changes, which adds the `semver_dirty` suffix
+    pub dirty: bool,
+    /// The `VERGEN_GIT_TRACKED_FILES` value
+    pub tracked_files: usize,
+    /// The subjects of the commits since the last tag, newest first
+    pub commits_since_tag: Vec<String>,
+    /// The `VERGEN_GIT_SIGNER` value, or None if `HEAD` isn't signed
+    pub signer: Option<String>,
+    /// The `VERGEN_GIT_BRANCH_COUNT` value
+    pub branch_count: usize,
+    /// The `VERGEN_GIT_TAG_COUNT` value
+    pub tag_count: usize,
+    /// The `VERGEN_GIT_CONTRIBUTOR_COUNT` value
+    pub contributor_count: usize,
+    /// Whether the repository is a shallow clone, so the contributor count is partial
+    pub shallow: bool,
+    /// The `VERGEN_GIT_MERGE_BASE` value, or None if the base ref doesn't resolve
+    pub merge_base: Option<String>,
+    /// The last commit that touched the `path_scope` path, or None if it has no history
+    pub path_commit: Option<PathCommit>,
+    /// The tag pointing at `HEAD`, or None if `HEAD` isn't tagged
+    pub exact_tag: Option<String>,
+    /// The `VERGEN_GIT_ROOT` value, or None for a bare repository
+    pub repo_root: Option<PathBuf>,
+    /// The `VERGEN_GIT_WORKTREE` value, or None if the repository isn't a linked worktree
+    pub worktree: Option<PathBuf>,
+    /// The number of commits reachable from `HEAD`, the `VERGEN_BUILD_NUMBER` value
+    pub commit_count: usize,
+    /// The number of changed files, the `+N` suffix of the `VERGEN_BUILD_NUMBER` value
+    pub dirty_files: usize,
+    /// The `VERGEN_GIT_OBJECT_FORMAT` value
+    pub object_format: String,
+    /// The `VERGEN_GIT_PARENT_COUNT` value
+    pub parent_count: usize,
+    /// How long each call takes, to simulate a slow repository
+    pub delay: Duration,
+}
+
+#[cfg(all(any(test, feature = "test-util"), feature = "git"))]
+impl Default for FakeRepo {
+    fn default() -> Self {
+        Self {
+            branch: Some("main".to_string()),
+            commit_time: OffsetDateTime::UNIX_EPOCH,
+            commit_offset: UtcOffset::UTC,
+            sha: "95fc0f5d066710f16e0c23ce3239d6e040abca0d".to_string(),
+            short_sha: "95fc0f5".to_string(),
+            tree_hash: "3b18e512dba79e4c8300dd08aeb37f8e728b8dad".to_string(),
+            semver: Some("v3.2.0-86-g95fc0f5".to_string()),
+            semver_lightweight: Some("v3.2.0-86-g95fc0f5".to_string()),
+            dirty: false,
+            tracked_files: 42,
+            commits_since_tag: vec![],
+            signer: None,
+            branch_count: 3,
+            tag_count: 12,
+            contributor_count: 27,
+            shallow: false,
+            merge_base: Some("2e4a4b1f4b6e2dd6d7a5c0f3e9e0f38a3b7c1d22".to_string()),
+            path_commit: None,
+            exact_tag: None,
+            repo_root: Some(PathBuf::from("/home/yoda/projects/vergen")),
+            worktree: None,
+            commit_count: 1342,
+            dirty_files: 0,
+            object_format: "sha1".to_string(),
+            parent_count: 1,
+            delay: Duration::from_secs(0),
+        }
+    }
+}
+
+#[cfg(all(any(test, feature = "test-util"), feature = "git"))]
+impl FakeRepo {
+    fn wait(&self) {
+        if self.delay > Duration::from_secs(0) {
+            thread::sleep(self.delay);
+        }
+    }
+}
+
+#[cfg(all(any(test, feature = "test-util"), feature = "git"))]
+impl GitProvider for FakeRepo {
+    fn branch(&self) -> Result<Option<String>> {
+        self.wait();
+        Ok(self.branch.clone())
+    }
+
+    fn commit_time(&self) -> Result<OffsetDateTime> {
+        self.wait();
+        Ok(self.commit_time)
+    }
+
+    fn commit_offset(&self) -> Result<UtcOffset> {
+        self.wait();
+        Ok(self.commit_offset)
+    }
+
+    fn sha(&self) -> Result<String> {
+        self.wait();
+        Ok(self.sha.clone())
+    }
+
+    fn short_sha(&self) -> Result<String> {
+        self.wait();
+        Ok(self.short_sha.clone())
+    }
+
+    fn tree_hash(&self) -> Result<String> {
+        self.wait();
+        Ok(self.tree_hash.clone())
+    }
+
+    fn semver(
+        &self,
+        lightweight: bool,
+        always: bool,
+        dirty_suffix: Option<&str>,
+    ) -> Result<String> {
+        self.wait();
+        let semver = if lightweight {
+            &self.semver_lightweight
+        } else {
+            &self.semver
+        };
+        let semver = match semver {
+            Some(semver) => semver.clone(),
+            None if always => self.short_sha.clone(),
+            None => return Err(anyhow::anyhow!("no tags in the fake repository")),
+        };
+        match dirty_suffix {
+            Some(suffix) if self.dirty => Ok(format!("{}{}", semver, suffix)),
+            _ => Ok(semver),
+        }
+    }
+
+    fn tracked_files(&self) -> usize {
+        self.wait();
+        self.tracked_files
+    }
+
+    fn commits_since_tag(&self, subjects: bool) -> Result<(usize, Vec<String>)> {
+        self.wait();
+        let count = self.commits_since_tag.len();
+        if subjects {
+            Ok((count, self.commits_since_tag.clone()))
+        } else {
+            Ok((count, vec![]))
+        }
+    }
+
+    fn signer(&self) -> Result<Option<String>> {
+        self.wait();
+        Ok(self.signer.clone())
+    }
+
+    fn branch_count(&self) -> Result<usize> {
+        self.wait();
+        Ok(self.branch_count)
+    }
+
+    fn tag_count(&self) -> Result<usize> {
+        self.wait();
+        Ok(self.tag_count)
+    }
+
+    fn contributor_count(&self) -> Result<(usize, bool)> {
+        self.wait();
+        Ok((self.contributor_count, self.shallow))
+    }
+
+    fn merge_base(&self, _base: &str) -> Result<Option<String>> {
+        self.wait();
+        Ok(self.merge_base.clone())
+    }
+
+    fn path_commit(&self, _path: &Path) -> Result<Option<PathCommit>> {
+        self.wait();
+        Ok(self.path_commit.clone())
+    }
+
+    fn exact_tag(&self) -> Result<Option<String>> {
+        self.wait();
+        Ok(self.exact_tag.clone())
+    }
+
+    fn repo_root(&self) -> Option<PathBuf> {
+        self.wait();
+        self.repo_root.clone()
+    }
+
+    fn worktree(&self) -> Option<PathBuf> {
+        self.wait();
+        self.worktree.clone()
+    }
+
+    fn commit_count(&self) -> Result<usize> {
+        self.wait();
+        Ok(self.commit_count)
+    }
+
+    fn dirty_files(&self) -> Result<usize> {
+        self.wait();
+        Ok(self.dirty_files)
+    }
+
+    fn object_format(&self) -> Result<String> {
+        self.wait();
+        Ok(self.object_format.clone())
+    }
+
+    fn parent_count(&self) -> Result<usize> {
+        self.wait();
+        Ok(self.parent_count)
+    }
+}
+
+/// A fake `rustc` that reports the given `rustc -vV` output
+#[cfg(all(any(test, feature = "test-util"), feature = "rustc"))]
+#[derive(Clone, Debug)]
+pub struct FakeRustc(pub String);
+
+#[cfg(all(any(test, feature = "test-util"), feature = "rustc"))]
+impl Default for FakeRustc {
+    fn default() -> Self {
+        Self(
+            "rustc 1.57.0 (f1edd0429 2021-11-29)
+binary: rustc
+commit-hash: f1edd0429582dd29cccacaf50fd134b05593bd9c
+commit-date: 2021-11-29
+host: x86_64-unknown-linux-gnu
+release: 1.57.0
+LLVM version: 13.0.0"
+                .to_string(),
+        )
+    }
+}
+
+#[cfg(all(any(test, feature = "test-util"), feature = "rustc"))]
+impl RustcProvider for FakeRustc {
+    fn version_meta(&self) -> Result<VersionMeta> {
+        Ok(rustc_version::version_meta_for(&self.0)?)
+    }
+}
+
+/// The data providers installed on a [`Config`](crate::Config)
+///
+/// Any provider that isn't installed falls back to the production implementation.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(all(feature = "test-util", feature = "build"))]
+/// # {
+/// use std::sync::Arc;
+/// use time::macros::datetime;
+/// use vergen::{Config, FixedClock};
+///
+/// let mut config = Config::default();
+/// *config.providers_mut().clock_mut() =
+///     Some(Arc::new(FixedClock(datetime!(2021-02-12 01:54:15 UTC))));
+/// # }
+/// ```
+#[cfg(all(
+    any(test, feature = "test-util"),
+    any(feature = "build", feature = "git", feature = "rustc")
+))]
+#[derive(Clone, Debug, Default)]
+pub struct Providers {
+    /// The clock used for the build date/time and the commit age
+    #[cfg(any(feature = "build", feature = "git"))]
+    clock: Option<Arc<dyn ClockProvider>>,
+    /// The git repository information
+    #[cfg(feature = "git")]
+    git: Option<Arc<dyn GitProvider + Send + Sync>>,
+    /// Counts the git repository discoveries, to check the repository is only opened once
+    #[cfg(feature = "git")]
+    git_discoveries: Option<Arc<AtomicUsize>>,
+    /// The rustc version information
+    #[cfg(feature = "rustc")]
+    rustc: Option<Arc<dyn RustcProvider>>,
+}
+
+#[cfg(all(
+    any(test, feature = "test-util"),
+    any(feature = "build", feature = "git", feature = "rustc")
+))]
+impl Providers {
+    /// The clock used for the build date/time and the commit age
+    #[cfg(any(feature = "build", feature = "git"))]
+    pub(crate) fn clock(&self) -> &Option<Arc<dyn ClockProvider>> {
+        &self.clock
+    }
+
+    /// The clock used for the build date/time and the commit age
+    #[cfg(any(feature = "build", feature = "git"))]
+    pub fn clock_mut(&mut self) -> &mut Option<Arc<dyn ClockProvider>> {
+        &mut self.clock
+    }
+
+    /// The git repository information
+    #[cfg(feature = "git")]
+    pub(crate) fn git(&self) -> &Option<Arc<dyn GitProvider + Send + Sync>> {
+        &self.git
+    }
+
+    /// The git repository information
+    #[cfg(feature = "git")]
+    pub fn git_mut(&mut self) -> &mut Option<Arc<dyn GitProvider + Send + Sync>> {
+        &mut self.git
+    }
+
+    /// Counts the git repository discoveries, to check the repository is only opened once
+    #[cfg(feature = "git")]
+    pub(crate) fn git_discoveries(&self) -> &Option<Arc<AtomicUsize>> {
+        &self.git_discoveries
+    }
+
+    /// Counts the git repository discoveries, to check the repository is only opened once
+    #[cfg(feature = "git")]
+    pub fn git_discoveries_mut(&mut self) -> &mut Option<Arc<AtomicUsize>> {
+        &mut self.git_discoveries
+    }
+
+    /// The rustc version information
+    #[cfg(feature = "rustc")]
+    pub(crate) fn rustc(&self) -> &Option<Arc<dyn RustcProvider>> {
+        &self.rustc
+    }
+
+    /// The rustc version information
+    #[cfg(feature = "rustc")]
+    pub fn rustc_mut(&mut self) -> &mut Option<Arc<dyn RustcProvider>> {
+        &mut self.rustc
+    }
+}
+
+#[cfg(all(
+    any(test, feature = "test-util"),
+    any(feature = "build", feature = "git", feature = "rustc")
+))]
+impl Providers {
+    pub(crate) fn merge(self, overlay: Self) -> Self {
+        Self {
+            #[cfg(any(feature = "build", feature = "git"))]
+            clock: overlay.clock.or(self.clock),
+            #[cfg(feature = "git")]
+            git: overlay.git.or(self.git),
+            #[cfg(feature = "git")]
+            git_discoveries: overlay.git_discoveries.or(self.git_discoveries),
+            #[cfg(feature = "rustc")]
+            rustc: overlay.rustc.or(self.rustc),
+        }
+    }
+}
+
+/// The `SOURCE_DATE_EPOCH` time, if it is honored and set to a valid number of seconds
+#[cfg(any(feature = "build", feature = "git"))]
+pub(crate) fn source_date_epoch(instructions: &Instructions) -> Option<OffsetDateTime> {
+    if !*instructions.source_date_epoch() {
+        return None;
+    }
+    env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.trim().parse::<i64>().ok())
+        .and_then(|epoch| OffsetDateTime::from_unix_timestamp(epoch).ok())
+}
+
+#[cfg(any(feature = "build", feature = "git"))]
+pub(crate) fn now_utc(instructions: &Instructions) -> OffsetDateTime {
+    if let Some(now) = instructions.reference_time() {
+        return now;
+    }
+    #[cfg(any(test, feature = "test-util"))]
+    {
+        if let Some(clock) = instructions.providers().clock() {
+            return clock.now_utc();
+        }
+    }
+    source_date_epoch(instructions).unwrap_or_else(|| SystemClock.now_utc())
+}
+
+#[cfg(any(feature = "build", feature = "git"))]
+pub(crate) fn now_local(instructions: &Instructions, config: &mut Config) -> OffsetDateTime {
+    if let Some(now) = instructions.reference_time() {
+        return now.to_offset(local_offset(config));
+    }
+    #[cfg(any(test, feature = "test-util"))]
+    {
+        if let Some(clock) = instructions.providers().clock() {
+            return clock.now_local();
+        }
+    }
+    let offset = local_offset(config);
+    match source_date_epoch(instructions) {
+        Some(epoch) => epoch.to_offset(offset),
+        None => SystemClock.now_local(),
+    }
+}
+
+/// The offset of the local timezone, or UTC with a warning if the host can't tell, i.e. on wasi
+#[cfg(any(feature = "build", feature = "git"))]
+pub(crate) fn local_offset(config: &mut Config) -> UtcOffset {
+    UtcOffset::current_local_offset().unwrap_or_else(|_| {
+        config.warnings_mut().push(
+            "vergen: the local timezone isn't available on this host, UTC is used".to_string(),
+        );
+        UtcOffset::UTC
+    })
+}
+
+#[cfg(feature = "rustc")]
+pub(crate) fn rustc_version_meta(instructions: &Instructions) -> Result<VersionMeta> {
+    #[cfg(any(test, feature = "test-util"))]
+    {
+        if let Some(rustc) = instructions.providers().rustc() {
+            return rustc.version_meta();
+        }
+    }
+    match instructions.rustc().version_override() {
+        Some(version) => version_meta_override(version),
+        None => SystemRustc.version_meta(),
+    }
+}
+
+/// Parse the `rustc -vV` output, or the contents of the file it names
+#[cfg(feature = "rustc")]
+fn version_meta_override(version: &str) -> Result<VersionMeta> {
+    if version.starts_with("rustc ") {
+        Ok(rustc_version::version_meta_for(version)?)
+    } else {
+        let output = fs::read_to_string(version).map_err(|source| Error::RustcOverride {
+            path: version.to_string(),
+            source,
+        })?;
+        Ok(rustc_version::version_meta_for(&output)?)
+    }
+}
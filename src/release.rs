@@ -0,0 +1,120 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` release URL
+
+use crate::{
+    banner::{tokenize, Token},
+    error::ConfigError,
+};
+
+/// The release page of a tag on GitHub, used without a `url_template`
+#[cfg(feature = "git")]
+const GITHUB_TEMPLATE: &str = "{repo}/releases/tag/{tag}";
+
+pub(crate) fn validate_url_template(template: &str, errors: &mut Vec<ConfigError>) {
+    let suggestion =
+        "use '{repo}' and '{tag}', and '{{' or '}}' for a literal brace, i.e. '{repo}/-/releases/{tag}'";
+
+    match tokenize(template) {
+        Some(tokens) => {
+            for token in &tokens {
+                if let Token::Placeholder(name) = token {
+                    if *name != "repo" && *name != "tag" {
+                        errors.push(ConfigError::new(
+                            "url_template",
+                            format!("{{{}}}", name),
+                            suggestion,
+                        ));
+                    }
+                }
+            }
+            if !tokens.contains(&Token::Placeholder("tag")) {
+                errors.push(ConfigError::new(
+                    "url_template",
+                    format!("{:?}", template),
+                    suggestion,
+                ));
+            }
+        }
+        None => errors.push(ConfigError::new(
+            "url_template",
+            format!("{:?}", template),
+            suggestion,
+        )),
+    }
+}
+
+/// Fill in the template, or the GitHub style one, with the repository URL and the tag, or an empty string if either
+/// is missing.
+///
+/// The template is assumed to be valid.
+#[cfg(feature = "git")]
+pub(crate) fn release_url(template: Option<&str>, repo: Option<&str>, tag: Option<&str>) -> String {
+    let (repo, tag) = match (repo, tag) {
+        (Some(repo), Some(tag)) if !repo.trim().is_empty() && !tag.is_empty() => (repo, tag),
+        _ => return String::new(),
+    };
+    let repo = repo.trim().trim_end_matches('/');
+    let repo = repo.strip_suffix(".git").unwrap_or(repo);
+
+    let mut url = String::new();
+    for token in tokenize(template.unwrap_or(GITHUB_TEMPLATE)).unwrap_or_default() {
+        match token {
+            Token::Text(text) => url.push_str(text),
+            Token::Placeholder("repo") => url.push_str(repo),
+            Token::Placeholder("tag") => url.push_str(tag),
+            Token::Placeholder(_) => {}
+        }
+    }
+    url
+}
+
+#[cfg(test)]
+mod test {
+    use super::validate_url_template;
+
+    #[test]
+    fn templates_need_the_tag() {
+        let mut errors = vec![];
+        validate_url_template("{repo}/-/releases/{tag}", &mut errors);
+        validate_url_template("https://example.com/{{releases}}/{tag}", &mut errors);
+        assert!(errors.is_empty());
+
+        validate_url_template("{repo}/releases/{version}", &mut errors);
+        validate_url_template("{repo}/releases/{tag", &mut errors);
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].field(), "url_template");
+        assert_eq!(errors[0].value(), "{version}");
+        assert_eq!(errors[1].value(), "\"{repo}/releases/{version}\"");
+        assert_eq!(errors[2].value(), "\"{repo}/releases/{tag\"");
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn fills_in_the_repository_and_tag() {
+        use super::release_url;
+
+        let repo = Some("https://github.com/rustyhorde/vergen.git/");
+        assert_eq!(
+            release_url(None, repo, Some("v5.0.0")),
+            "https://github.com/rustyhorde/vergen/releases/tag/v5.0.0"
+        );
+        assert_eq!(
+            release_url(
+                Some("{repo}/-/releases/{tag}"),
+                Some("https://gitlab.com/org/repo"),
+                Some("1.2.3")
+            ),
+            "https://gitlab.com/org/repo/-/releases/1.2.3"
+        );
+        assert_eq!(release_url(None, repo, None), "");
+        assert_eq!(release_url(None, Some(""), Some("v5.0.0")), "");
+        assert_eq!(release_url(None, None, Some("v5.0.0")), "");
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` repeated call detection
+
+use crate::{
+    config::{Config, DuplicatePolicy},
+    error::Error,
+};
+use anyhow::Result;
+use std::{cell::RefCell, collections::BTreeSet};
+
+thread_local! {
+    /// The names emitted by every [`vergen`](crate::vergen) call on this thread.
+    ///
+    /// Cargo runs each build script in its own process, so a call from another crate's build script is never seen here.
+    /// A build script calls `vergen` from its `main` thread, so that's every call in the build script.
+    pub(crate) static EMITTED: Emitted = Emitted::default();
+}
+
+/// The `rustc-env` instruction names emitted by the earlier calls
+#[derive(Debug, Default)]
+pub(crate) struct Emitted(RefCell<BTreeSet<String>>);
+
+impl Emitted {
+    /// Apply the duplicate policy to the instructions an earlier call already emitted, and remember the names
+    /// this call emits
+    pub(crate) fn dedupe(&self, policy: DuplicatePolicy, config: &Config) -> Result<Config> {
+        let mut emitted = self.0.borrow_mut();
+        let mut config = config.clone();
+        let repeated: Vec<String> = names(&config)
+            .into_iter()
+            .filter(|name| emitted.contains(name))
+            .collect();
+
+        if !repeated.is_empty() {
+            match policy {
+                DuplicatePolicy::Error => {
+                    return Err(Error::RepeatedCall(repeated.join(", ")).into());
+                }
+                DuplicatePolicy::KeepFirst => {
+                    for value in config
+                        .cfg_map_mut()
+                        .iter_mut()
+                        .filter(|(key, _)| emitted.contains(key.name()))
+                        .map(|(_, value)| value)
+                    {
+                        *value = None;
+                    }
+                    config.commands_mut().retain(|name, _| !emitted.contains(name));
+                }
+                DuplicatePolicy::Overwrite => config.warnings_mut().push(format!(
+                    "vergen: {} already emitted by an earlier vergen call in this build script, the values from this call win",
+                    repeated.join(", ")
+                )),
+            }
+        }
+
+        emitted.extend(names(&config));
+        Ok(config)
+    }
+}
+
+/// The `rustc-env` instruction names of the generated values
+fn names(config: &Config) -> Vec<String> {
+    config
+        .cfg_map()
+        .iter()
+        .filter(|(_, value)| value.is_some())
+        .map(|(key, _)| key.name().to_string())
+        .chain(config.commands().keys().cloned())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::Emitted;
+    use crate::config::{Config, DuplicatePolicy, VergenKey};
+
+    fn config(sha: &str) -> Config {
+        let mut config = Config::default();
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Sha, Some(sha.to_string()));
+        config
+    }
+
+    #[test]
+    fn first_call_unchanged() {
+        for policy in &[
+            DuplicatePolicy::Overwrite,
+            DuplicatePolicy::KeepFirst,
+            DuplicatePolicy::Error,
+        ] {
+            let deduped = Emitted::default().dedupe(*policy, &config("abc")).unwrap();
+            assert_eq!(deduped.cfg_map()[&VergenKey::Sha], Some("abc".to_string()));
+            assert!(deduped.warnings().is_empty());
+        }
+    }
+
+    #[test]
+    fn repeated_call() {
+        let second = |policy| {
+            let emitted = Emitted::default();
+            let _ = emitted.dedupe(policy, &config("abc")).unwrap();
+            let mut config = config("def");
+            let _ = config
+                .cfg_map_mut()
+                .insert(VergenKey::Branch, Some("main".to_string()));
+            emitted.dedupe(policy, &config)
+        };
+
+        let overwrite = second(DuplicatePolicy::Overwrite).unwrap();
+        assert_eq!(
+            overwrite.cfg_map()[&VergenKey::Sha],
+            Some("def".to_string())
+        );
+        assert_eq!(overwrite.warnings().len(), 1);
+        assert!(overwrite.warnings()[0].contains("VERGEN_GIT_SHA"));
+
+        let keep_first = second(DuplicatePolicy::KeepFirst).unwrap();
+        assert_eq!(keep_first.cfg_map()[&VergenKey::Sha], None);
+        assert_eq!(
+            keep_first.cfg_map()[&VergenKey::Branch],
+            Some("main".to_string())
+        );
+        assert!(keep_first.warnings().is_empty());
+
+        let error = second(DuplicatePolicy::Error).unwrap_err();
+        assert!(error.to_string().contains("'VERGEN_GIT_SHA'"));
+    }
+
+    #[test]
+    fn repeated_command() {
+        let emitted = Emitted::default();
+        let mut config = Config::default();
+        let _ = config
+            .commands_mut()
+            .insert("BUILD_NUMBER".to_string(), "42".to_string());
+        let _ = emitted.dedupe(DuplicatePolicy::KeepFirst, &config).unwrap();
+        let deduped = emitted.dedupe(DuplicatePolicy::KeepFirst, &config).unwrap();
+        assert!(deduped.commands().is_empty());
+    }
+}
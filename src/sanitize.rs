@@ -0,0 +1,306 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` value sanitization
+
+use crate::{
+    config::{Config, VergenKey},
+    error::Error,
+    merge::{pick, SetFields},
+};
+use anyhow::Result;
+use std::{env, path::Path};
+
+/// Configuration for sanitizing the generated values before they are emitted
+///
+/// Cargo reads the build script output one line at a time, so a value containing a line break, i.e. a commit
+/// subject, could truncate the value or smuggle in an extra `cargo:` instruction.  Every generated value,
+/// including the values from a [`value_provider`](crate::Config::value_provider), is sanitized before it is emitted:
+///
+/// * Leading and trailing ASCII whitespace is trimmed, i.e. the trailing line break of the `git` or `rustc` output.
+/// * Each line break (`\n`, `\r`, or `\r\n`) is replaced with a space.
+/// * Each NUL character is removed.
+/// * If the `escape_non_printable` field is true, the other control characters are escaped, i.e. a tab becomes `\t`.
+///
+/// A value with a line that starts with `cargo:` would have started a new instruction.  It is emitted with the
+/// line breaks replaced, along with a `cargo:warning`.  Set the `reject_directives` field to true to fail instead.
+///
+/// A path value, i.e. `VERGEN_GIT_ROOT` or `VERGEN_GIT_WORKTREE`, shows where the build machine keeps its checkouts.
+/// Set the `redact_paths` field to true to replace the home directory at the start of a path with `~`.
+///
+/// # Example
+///
+/// ```
+/// use vergen::Config;
+///
+/// let mut config = Config::default();
+/// *config.sanitize_mut().escape_non_printable_mut() = true;
+/// *config.sanitize_mut().reject_directives_mut() = true;
+/// *config.sanitize_mut().redact_paths_mut() = true;
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
+pub struct Sanitize {
+    /// Escape the control characters other than line breaks and NUL.
+    escape_non_printable: bool,
+    /// Fail, rather than warn, when a value would start a new cargo instruction.
+    reject_directives: bool,
+    /// Replace the home directory at the start of a path value with `~`.
+    redact_paths: bool,
+    /// The fields set through their `set_*` setter, which win in a [merge](crate::Config::merge)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    set_fields: SetFields,
+}
+
+impl Sanitize {
+    /// Escape the control characters other than line breaks and NUL.
+    #[cfg(test)]
+    pub(crate) fn escape_non_printable(&self) -> &bool {
+        &self.escape_non_printable
+    }
+
+    /// Escape the control characters other than line breaks and NUL.
+    pub fn escape_non_printable_mut(&mut self) -> &mut bool {
+        &mut self.escape_non_printable
+    }
+
+    /// Escape the control characters other than line breaks and NUL.
+    pub fn set_escape_non_printable(&mut self, escape_non_printable: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "escape_non_printable");
+        self.escape_non_printable = escape_non_printable;
+    }
+
+    /// Fail, rather than warn, when a value would start a new cargo instruction.
+    #[cfg(test)]
+    pub(crate) fn reject_directives(&self) -> &bool {
+        &self.reject_directives
+    }
+
+    /// Fail, rather than warn, when a value would start a new cargo instruction.
+    pub fn reject_directives_mut(&mut self) -> &mut bool {
+        &mut self.reject_directives
+    }
+
+    /// Fail, rather than warn, when a value would start a new cargo instruction.
+    pub fn set_reject_directives(&mut self, reject_directives: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "reject_directives");
+        self.reject_directives = reject_directives;
+    }
+
+    /// Replace the home directory at the start of a path value with `~`.
+    pub fn redact_paths_mut(&mut self) -> &mut bool {
+        &mut self.redact_paths
+    }
+
+    /// Replace the home directory at the start of a path value with `~`.
+    pub fn set_redact_paths(&mut self, redact_paths: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "redact_paths");
+        self.redact_paths = redact_paths;
+    }
+}
+
+/// The [`Sanitize`] fields that win in a [merge](crate::Config::merge) once set, see [`SetFields`]
+const MERGED_FIELDS: &[&str] = &["escape_non_printable", "reject_directives", "redact_paths"];
+
+impl Sanitize {
+    pub(crate) fn merge(self, overlay: Self) -> Self {
+        let default = Self::default();
+        let overlay_set = overlay.set_fields;
+        let set = |name| overlay_set.contains(MERGED_FIELDS, name);
+        Self {
+            escape_non_printable: pick(
+                self.escape_non_printable,
+                overlay.escape_non_printable,
+                &default.escape_non_printable,
+                set("escape_non_printable"),
+            ),
+            reject_directives: pick(
+                self.reject_directives,
+                overlay.reject_directives,
+                &default.reject_directives,
+                set("reject_directives"),
+            ),
+            redact_paths: pick(
+                self.redact_paths,
+                overlay.redact_paths,
+                &default.redact_paths,
+                set("redact_paths"),
+            ),
+            set_fields: self.set_fields.union(overlay_set),
+        }
+    }
+}
+
+/// Whether any line of the value would be read by cargo as an instruction
+fn has_directive(value: &str) -> bool {
+    value
+        .split(&['\n', '\r'][..])
+        .any(|line| line.trim_start().starts_with("cargo:"))
+}
+
+/// Trim the leading and trailing ASCII whitespace, as soon as a value is generated
+pub(crate) fn trim_value(value: String) -> String {
+    let trimmed = value.trim_matches(|c: char| c.is_ascii_whitespace());
+    if trimmed.len() == value.len() {
+        value
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// Make the value safe to emit on a single line
+pub(crate) fn sanitize_value(value: &str, escape_non_printable: bool) -> String {
+    let mut sanitized = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' => {
+                let _ = chars.next_if_eq(&'\n');
+                sanitized.push(' ');
+            }
+            '\n' => sanitized.push(' '),
+            '\0' => {}
+            c if escape_non_printable && c.is_control() => sanitized.extend(c.escape_default()),
+            c => sanitized.push(c),
+        }
+    }
+    sanitized
+}
+
+/// The home directory of the user running the build
+fn home_dir() -> Option<String> {
+    env::var("HOME")
+        .or_else(|_| env::var("USERPROFILE"))
+        .ok()
+        .filter(|home| !home.is_empty())
+}
+
+/// Replace the home directory at the start of the path with `~`
+fn redact_home(path: &str, home: &str) -> Option<String> {
+    let rest = Path::new(path).strip_prefix(home).ok()?;
+    if rest.as_os_str().is_empty() {
+        Some("~".to_string())
+    } else {
+        Some(Path::new("~").join(rest).display().to_string())
+    }
+}
+
+/// Sanitize every generated value
+pub(crate) fn sanitize(sanitize: Sanitize, config: &mut Config) -> Result<()> {
+    let home = if sanitize.redact_paths {
+        home_dir()
+    } else {
+        None
+    };
+    let mut warnings = vec![];
+    for (key, value) in config.cfg_map_mut().iter_mut() {
+        if let Some(value) = value {
+            if let Some(home) = &home {
+                if matches!(key, VergenKey::GitRoot | VergenKey::GitWorktree) {
+                    if let Some(redacted) = redact_home(value, home) {
+                        *value = redacted;
+                    }
+                }
+            }
+            if has_directive(value) {
+                if sanitize.reject_directives {
+                    return Err(Error::UnsafeValue(key.name().to_string()).into());
+                }
+                warnings.push(format!(
+                    "vergen: the '{}' value contains a cargo instruction, it was emitted on a single line",
+                    key.name()
+                ));
+            }
+            *value = sanitize_value(value, sanitize.escape_non_printable);
+        }
+    }
+    config.warnings_mut().extend(warnings);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{redact_home, sanitize, sanitize_value, trim_value, Sanitize};
+    use crate::config::{Config, VergenKey};
+
+    fn subjects(value: &str) -> Config {
+        let mut config = Config::default();
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::CommitsSinceTagSubjects, Some(value.to_string()));
+        config
+    }
+
+    #[test]
+    fn line_breaks_and_nul() {
+        assert_eq!(sanitize_value("a\nb\r\nc\rd\0e", false), "a b c de");
+        assert_eq!(sanitize_value("a\tb\u{1b}", false), "a\tb\u{1b}");
+        assert_eq!(sanitize_value("a\tb\u{1b}", true), "a\\tb\\u{1b}");
+        assert_eq!(sanitize_value("Fix the ünïcode", true), "Fix the ünïcode");
+    }
+
+    #[test]
+    fn trimmed() {
+        assert_eq!(trim_value("abc123\n".to_string()), "abc123");
+        assert_eq!(trim_value(" \t1.52.0\r\n".to_string()), "1.52.0");
+        assert_eq!(trim_value("fix\nthe build".to_string()), "fix\nthe build");
+        assert_eq!(trim_value("\n".to_string()), "");
+        // Only ASCII whitespace is trimmed
+        assert_eq!(
+            trim_value("\u{a0}main\u{a0}".to_string()),
+            "\u{a0}main\u{a0}"
+        );
+    }
+
+    #[test]
+    fn directives_warn() {
+        let mut config = subjects("fix\ncargo:rustc-env=EVIL=1");
+        sanitize(Sanitize::default(), &mut config).unwrap();
+        assert_eq!(
+            config.cfg_map()[&VergenKey::CommitsSinceTagSubjects],
+            Some("fix cargo:rustc-env=EVIL=1".to_string())
+        );
+        assert_eq!(config.warnings().len(), 1);
+        assert!(config.warnings()[0].contains("VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS"));
+
+        let mut config = subjects("fix: cargo:rustc-env is inline");
+        sanitize(Sanitize::default(), &mut config).unwrap();
+        assert!(config.warnings().is_empty());
+    }
+
+    #[test]
+    fn directives_rejected() {
+        let mut reject = Sanitize::default();
+        *reject.reject_directives_mut() = true;
+        assert!(sanitize(reject, &mut subjects("cargo:warning=hi")).is_err());
+        assert!(sanitize(reject, &mut subjects("fix\r  cargo::warning=hi")).is_err());
+        assert!(sanitize(reject, &mut subjects("fix\nthe build")).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn home_redacted() {
+        let home = "/home/yoda";
+        assert_eq!(
+            redact_home("/home/yoda/projects/vergen", home),
+            Some("~/projects/vergen".to_string())
+        );
+        assert_eq!(redact_home("/home/yoda", home), Some("~".to_string()));
+        // Only whole path components match
+        assert_eq!(redact_home("/home/yodabuild/vergen", home), None);
+        assert_eq!(redact_home("/opt/vergen", home), None);
+    }
+}
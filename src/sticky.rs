@@ -0,0 +1,366 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` sticky value cache
+
+use crate::{
+    config::{Config, VergenKey},
+    error::ConfigError,
+    merge::{pick, union, SetFields},
+};
+use anyhow::Result;
+use std::{collections::BTreeMap, fs, path::Path};
+
+const CACHE_HEADER: &str = "vergen-sticky-cache v1";
+
+/// Configuration for re-emitting previously generated values
+///
+/// Every `cargo:rustc-env` value is an input to compilation, so a new `VERGEN_BUILD_TIMESTAMP`
+/// on every build script run forces the crate to be rebuilt even when nothing meaningful changed.
+///
+/// When the `enabled` field is true, the emitted values are cached in `OUT_DIR`.  On the next run,
+/// the instructions named in the `keys` field re-use their cached value, unless the value of one
+/// of the instructions named in the `triggers` field has changed since the cache was written.
+///
+/// * By default the build date/time instructions are sticky, and a change in the git SHA busts them.
+/// * **NOTE** - The cache is versioned.  A cache from another version, or one that can't be read, is ignored.
+/// * **NOTE** - Sticky values are never used when building on docs.rs.
+///
+/// # Example
+///
+/// ```
+/// use vergen::Config;
+///
+/// let mut config = Config::default();
+/// *config.sticky_mut().enabled_mut() = true;
+/// config.sticky_mut().triggers_mut().push("VERGEN_RUSTC_SEMVER".to_string());
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
+pub struct Sticky {
+    /// Enable/Disable the sticky value cache
+    enabled: bool,
+    /// The instructions that re-use their cached value, i.e. `VERGEN_BUILD_TIMESTAMP`.
+    keys: Vec<String>,
+    /// The instructions that bust the cache when their value changes, i.e. `VERGEN_GIT_SHA`.
+    triggers: Vec<String>,
+    /// The fields set through their `set_*` setter, which win in a [merge](crate::Config::merge)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    set_fields: SetFields,
+}
+
+impl Sticky {
+    /// Enable/Disable the sticky value cache
+    pub(crate) fn enabled(&self) -> &bool {
+        &self.enabled
+    }
+
+    /// Enable/Disable the sticky value cache
+    pub fn enabled_mut(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+
+    /// Enable/Disable the sticky value cache
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.set_fields.mark(MERGED_FIELDS, "enabled");
+        self.enabled = enabled;
+    }
+
+    /// The instructions that re-use their cached value, i.e. `VERGEN_BUILD_TIMESTAMP`.
+    #[cfg(test)]
+    pub(crate) fn keys(&self) -> &Vec<String> {
+        &self.keys
+    }
+
+    /// The instructions that re-use their cached value, i.e. `VERGEN_BUILD_TIMESTAMP`.
+    pub fn keys_mut(&mut self) -> &mut Vec<String> {
+        &mut self.keys
+    }
+
+    /// The instructions that bust the cache when their value changes, i.e. `VERGEN_GIT_SHA`.
+    #[cfg(test)]
+    pub(crate) fn triggers(&self) -> &Vec<String> {
+        &self.triggers
+    }
+
+    /// The instructions that bust the cache when their value changes, i.e. `VERGEN_GIT_SHA`.
+    pub fn triggers_mut(&mut self) -> &mut Vec<String> {
+        &mut self.triggers
+    }
+}
+
+impl Default for Sticky {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            keys: vec![
+                "VERGEN_BUILD_DATE".to_string(),
+                "VERGEN_BUILD_TIME".to_string(),
+                "VERGEN_BUILD_TIMESTAMP".to_string(),
+            ],
+            triggers: vec![
+                "VERGEN_GIT_SHA".to_string(),
+                "VERGEN_GIT_SHA_SHORT".to_string(),
+            ],
+            set_fields: SetFields::default(),
+        }
+    }
+}
+
+/// The [`Sticky`] fields that win in a [merge](crate::Config::merge) once set, see [`SetFields`]
+const MERGED_FIELDS: &[&str] = &["enabled"];
+
+impl Sticky {
+    pub(crate) fn merge(self, overlay: Self) -> Self {
+        let default = Self::default();
+        let overlay_set = overlay.set_fields;
+        let set = |name| overlay_set.contains(MERGED_FIELDS, name);
+        Self {
+            enabled: pick(
+                self.enabled,
+                overlay.enabled,
+                &default.enabled,
+                set("enabled"),
+            ),
+            keys: union(self.keys, overlay.keys, &default.keys),
+            triggers: union(self.triggers, overlay.triggers, &default.triggers),
+            set_fields: self.set_fields.union(overlay_set),
+        }
+    }
+
+    pub(crate) fn validate(&self, errors: &mut Vec<ConfigError>) {
+        if self.enabled {
+            for (field, names) in &[
+                ("sticky.keys", &self.keys),
+                ("sticky.triggers", &self.triggers),
+            ] {
+                for name in names.iter().filter(|name| find_key(name).is_none()) {
+                    errors.push(ConfigError::new(
+                        *field,
+                        format!("{:?}", name),
+                        "use the name of a generated instruction, i.e. 'VERGEN_BUILD_TIMESTAMP'",
+                    ));
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn find_key(name: &str) -> Option<VergenKey> {
+    VergenKey::ALL
+        .iter()
+        .copied()
+        .find(|key| key.name() == name)
+}
+
+/// Whether the name is the name of a `vergen` instruction, ignoring case
+pub(crate) fn is_key_name(name: &str) -> bool {
+    VergenKey::ALL
+        .iter()
+        .any(|key| key.name().eq_ignore_ascii_case(name))
+}
+
+/// Swap the sticky values in `config` for their cached values, if no trigger changed,
+/// and then cache the values that will be emitted.
+pub(crate) fn apply_sticky(sticky: &Sticky, config: &mut Config, cache_path: &Path) -> Result<()> {
+    let current = emitted(config);
+
+    if let Some(cached) = read_cache(cache_path) {
+        let triggered = sticky
+            .triggers
+            .iter()
+            .any(|name| cached.get(name) != current.get(name));
+
+        if !triggered {
+            for name in &sticky.keys {
+                if let (Some(key), Some(value)) = (find_key(name), cached.get(name)) {
+                    if let Some(Some(existing)) = config.cfg_map_mut().get_mut(&key) {
+                        existing.clone_from(value);
+                    }
+                }
+            }
+        }
+    }
+
+    fs::write(cache_path, write_cache(&emitted(config)))?;
+    Ok(())
+}
+
+fn emitted(config: &Config) -> BTreeMap<String, String> {
+    config
+        .cfg_map()
+        .iter()
+        .filter_map(|(k, v)| v.as_ref().map(|v| (k.name().to_string(), v.clone())))
+        .collect()
+}
+
+fn read_cache(cache_path: &Path) -> Option<BTreeMap<String, String>> {
+    let contents = fs::read_to_string(cache_path).ok()?;
+    let mut lines = contents.lines();
+
+    if lines.next()? != CACHE_HEADER {
+        return None;
+    }
+
+    let mut cached = BTreeMap::new();
+    for line in lines {
+        let mut kv = line.splitn(2, '=');
+        let (name, value) = (kv.next()?, kv.next()?);
+        let _ = cached.insert(name.to_string(), unescape(value)?);
+    }
+    Some(cached)
+}
+
+fn write_cache(values: &BTreeMap<String, String>) -> String {
+    let mut contents = String::from(CACHE_HEADER);
+    for (name, value) in values {
+        contents.push('\n');
+        contents.push_str(name);
+        contents.push('=');
+        contents.push_str(&value.replace('\\', "\\\\").replace('\n', "\\n"));
+    }
+    contents.push('\n');
+    contents
+}
+
+fn unescape(value: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next()? {
+                '\\' => unescaped.push('\\'),
+                'n' => unescaped.push('\n'),
+                _ => return None,
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    Some(unescaped)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{apply_sticky, read_cache, write_cache, Sticky, CACHE_HEADER};
+    use crate::config::{Config, VergenKey};
+    use std::{collections::BTreeMap, env, fs};
+
+    fn config(timestamp: &str, sha: &str) -> Config {
+        let mut config = Config::default();
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::BuildTimestamp, Some(timestamp.to_string()));
+        let _ = config
+            .cfg_map_mut()
+            .insert(VergenKey::Sha, Some(sha.to_string()));
+        config
+    }
+
+    fn timestamp(config: &Config) -> String {
+        config
+            .cfg_map()
+            .get(&VergenKey::BuildTimestamp)
+            .cloned()
+            .flatten()
+            .unwrap()
+    }
+
+    #[test]
+    fn off_by_default() {
+        let sticky = Sticky::default();
+        assert!(!sticky.enabled());
+        assert!(sticky
+            .keys()
+            .contains(&"VERGEN_BUILD_TIMESTAMP".to_string()));
+        assert!(sticky.triggers().contains(&"VERGEN_GIT_SHA".to_string()));
+    }
+
+    #[test]
+    fn unknown_names_are_invalid() {
+        let mut sticky = Sticky::default();
+        let mut errors = vec![];
+        sticky.keys_mut().push("VERGEN_BUILD_TIMESTMP".to_string());
+        sticky.validate(&mut errors);
+        assert!(errors.is_empty());
+
+        *sticky.enabled_mut() = true;
+        sticky.validate(&mut errors);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field(), "sticky.keys");
+    }
+
+    #[test]
+    fn cache_round_trip() {
+        let mut values = BTreeMap::new();
+        let _ = values.insert("A".to_string(), "one=1".to_string());
+        let _ = values.insert("B".to_string(), "two\\lines\n".to_string());
+        let path = env::temp_dir().join("vergen_sticky_round_trip.cache");
+        fs::write(&path, write_cache(&values)).unwrap();
+        assert_eq!(read_cache(&path), Some(values));
+    }
+
+    #[test]
+    fn consecutive_runs_are_identical() {
+        let sticky = Sticky::default();
+        let path = env::temp_dir().join("vergen_sticky_identical.cache");
+        let _ = fs::remove_file(&path);
+
+        let mut first = config("2021-02-12T01:54:15.134750+00:00", "abc");
+        apply_sticky(&sticky, &mut first, &path).unwrap();
+        let mut second = config("2021-02-12T01:55:00.000000+00:00", "abc");
+        apply_sticky(&sticky, &mut second, &path).unwrap();
+
+        assert_eq!(first.cfg_map(), second.cfg_map());
+    }
+
+    #[test]
+    fn trigger_busts_cache() {
+        let sticky = Sticky::default();
+        let path = env::temp_dir().join("vergen_sticky_trigger.cache");
+        let _ = fs::remove_file(&path);
+
+        let mut first = config("2021-02-12T01:54:15.134750+00:00", "abc");
+        apply_sticky(&sticky, &mut first, &path).unwrap();
+        let mut second = config("2021-02-12T01:55:00.000000+00:00", "def");
+        apply_sticky(&sticky, &mut second, &path).unwrap();
+        assert_eq!(timestamp(&second), "2021-02-12T01:55:00.000000+00:00");
+
+        // The busted value is sticky from now on
+        let mut third = config("2021-02-12T01:56:00.000000+00:00", "def");
+        apply_sticky(&sticky, &mut third, &path).unwrap();
+        assert_eq!(timestamp(&third), "2021-02-12T01:55:00.000000+00:00");
+    }
+
+    #[test]
+    fn corrupt_cache_is_ignored() {
+        let sticky = Sticky::default();
+        let path = env::temp_dir().join("vergen_sticky_corrupt.cache");
+
+        for contents in &[
+            "garbage".to_string(),
+            format!("{}\nno separator", CACHE_HEADER),
+            format!("{}\nVERGEN_BUILD_TIMESTAMP=bad\\escape", CACHE_HEADER),
+            "vergen-sticky-cache v0\nVERGEN_BUILD_TIMESTAMP=old".to_string(),
+        ] {
+            fs::write(&path, contents).unwrap();
+            let mut config = config("2021-02-12T01:54:15.134750+00:00", "abc");
+            apply_sticky(&sticky, &mut config, &path).unwrap();
+            assert_eq!(timestamp(&config), "2021-02-12T01:54:15.134750+00:00");
+            assert!(read_cache(&path).is_some());
+        }
+    }
+}
@@ -0,0 +1,285 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` configuration summary
+
+use crate::{command::COMMAND_KEY_PREFIX, config::Instructions, sticky::find_key};
+use std::fmt;
+
+/// The sections of the summary, in order
+const SECTIONS: [&str; 7] = ["build", "cargo", "git", "hg", "rustc", "sysinfo", "vergen"];
+
+/// Setting values longer than this are omitted, they're likely captured output or secrets
+const MAX_VALUE_LEN: usize = 64;
+
+/// The fields that are summarized in their own section, or that can't be summarized
+const SKIPPED_FIELDS: [&str; 5] = [
+    "commands",
+    "command_keys",
+    "conditions",
+    "value_providers",
+    "providers",
+];
+
+/// The feature `enabled` fields, already summarized by the feature sections
+const FEATURE_ENABLED_FIELDS: [&str; 6] = [
+    "build.enabled",
+    "cargo.enabled",
+    "git.enabled",
+    "hg.enabled",
+    "rustc.enabled",
+    "sysinfo.enabled",
+];
+
+/// Write the summary of the configuration, see the [`Display`](fmt::Display) implementation of [`Instructions`]
+pub(crate) fn write_summary(
+    instructions: &Instructions,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    let effective = instructions.describe_effective();
+    let features = instructions.enabled_features();
+    for section in &SECTIONS {
+        let keys: Vec<&(&str, &str)> = effective
+            .iter()
+            .filter(|(name, _)| find_key(name).map_or(false, |key| key.subsystem() == *section))
+            .collect();
+        if features.contains(section) || !keys.is_empty() {
+            writeln!(f, "{}", section)?;
+            for (name, source) in keys {
+                writeln!(f, "  {} from {}", name, source)?;
+            }
+        }
+    }
+
+    let settings = settings(instructions);
+    if settings.is_empty() {
+        writeln!(f, "settings: default")?;
+    } else {
+        writeln!(f, "settings")?;
+        for (name, value) in settings {
+            writeln!(f, "  {} = {}", name, value)?;
+        }
+    }
+
+    if instructions.commands().is_empty() && instructions.command_keys().is_empty() {
+        writeln!(f, "commands: none")?;
+    } else {
+        writeln!(f, "commands")?;
+        for (name, command) in instructions.commands() {
+            // The arguments may hold tokens, only their number is shown
+            writeln!(
+                f,
+                "  {} = {} ({} argument(s))",
+                name,
+                omit_long(&command.program),
+                command.args.len()
+            )?;
+        }
+        for name in instructions.command_keys().keys() {
+            writeln!(f, "  {}{} = a command", COMMAND_KEY_PREFIX, name)?;
+        }
+    }
+
+    if !instructions.conditions().is_empty() {
+        writeln!(f, "conditions")?;
+        for (name, condition) in instructions.conditions() {
+            writeln!(f, "  {} = {}", name, condition.describe())?;
+        }
+    }
+    Ok(())
+}
+
+/// The settings that differ from the default, by field path, i.e. `git.sha_kind`
+fn settings(instructions: &Instructions) -> Vec<(String, String)> {
+    let actual = format!("{:?}", instructions);
+    let default = format!("{:?}", Instructions::default());
+    let mut settings = vec![];
+    diff_fields("", &actual, &default, &mut settings);
+    settings
+}
+
+/// Add the fields of the `Debug` output of a struct that differ from the default, recursing into nested structs
+fn diff_fields(prefix: &str, actual: &str, default: &str, settings: &mut Vec<(String, String)>) {
+    let (actual_fields, default_fields) = match (struct_fields(actual), struct_fields(default)) {
+        (Some(actual_fields), Some(default_fields)) => (actual_fields, default_fields),
+        _ => return,
+    };
+    for (name, value) in actual_fields {
+        let path = format!("{}{}", prefix, name);
+        // Every section records its set fields for a merge, they're not settings themselves
+        if SKIPPED_FIELDS.contains(&path.as_str())
+            || FEATURE_ENABLED_FIELDS.contains(&path.as_str())
+            || name == "set_fields"
+        {
+            continue;
+        }
+        match default_fields
+            .iter()
+            .find(|(default_name, _)| *default_name == name)
+        {
+            Some((_, default_value)) if *default_value == value => {}
+            Some((_, default_value))
+                if struct_fields(value).is_some() && struct_fields(default_value).is_some() =>
+            {
+                diff_fields(&format!("{}.", path), value, default_value, settings);
+            }
+            _ => settings.push((path, omit_long(value))),
+        }
+    }
+}
+
+/// The `name: value` pairs of the `Debug` output of a struct, i.e. `Sticky { enabled: true, keys: [] }`
+fn struct_fields(debug: &str) -> Option<Vec<(&str, &str)>> {
+    let open = debug.find(" { ")?;
+    if !debug[..open]
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        || !debug.ends_with(" }")
+    {
+        return None;
+    }
+    let body = &debug[open + 3..debug.len() - 2];
+
+    let mut fields = vec![];
+    let (mut depth, mut in_string, mut escaped, mut start) = (0_usize, false, false, 0);
+    for (i, c) in body.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            _ if in_string => {}
+            '{' | '[' | '(' => depth += 1,
+            '}' | ']' | ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                fields.push(body[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(body[start..].trim());
+    fields
+        .into_iter()
+        .map(|field| {
+            let colon = field.find(": ")?;
+            Some((&field[..colon], &field[colon + 2..]))
+        })
+        .collect()
+}
+
+/// The value, or a note with its length if it's too long to show
+fn omit_long(value: &str) -> String {
+    let len = value.chars().count();
+    if len > MAX_VALUE_LEN {
+        format!("<{} characters omitted>", len)
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(all(
+    test,
+    feature = "build",
+    feature = "cargo",
+    feature = "git",
+    feature = "rustc"
+))]
+mod test {
+    use super::struct_fields;
+    use crate::{
+        config::Instructions,
+        feature::{SemverKind, TimeZone},
+        CargoSyntax, Condition, OutputFormat, OutputTarget,
+    };
+    use std::{fs, path::PathBuf, process::Command};
+
+    fn golden(name: &str) -> String {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata")
+            .join("summary")
+            .join(name);
+        fs::read_to_string(path).unwrap()
+    }
+
+    /// The default configuration, without the features that may not be compiled in
+    fn instructions() -> Instructions {
+        #[allow(unused_mut)]
+        let mut instructions = Instructions::default();
+        #[cfg(feature = "si")]
+        {
+            *instructions.sysinfo_mut().enabled_mut() = false;
+        }
+        instructions
+    }
+
+    #[test]
+    fn default_summary() {
+        assert_eq!(instructions().to_string(), golden("default.txt"));
+    }
+
+    #[test]
+    fn custom_summary() {
+        let mut instructions = instructions();
+        *instructions.cargo_mut().enabled_mut() = false;
+        *instructions.rustc_mut().enabled_mut() = false;
+        *instructions.git_mut().semver_kind_mut() = SemverKind::Lightweight;
+        *instructions.git_mut().commit_timestamp_timezone_mut() = TimeZone::Local;
+        *instructions.git_mut().branch_mut() = false;
+        *instructions.build_number_mut() = true;
+        *instructions.banner_format_mut() = Some("{name} {version}".to_string());
+        *instructions.unavailable_placeholder_mut() = "x".repeat(100);
+        *instructions.sticky_mut().enabled_mut() = true;
+        *instructions.cargo_syntax_mut() = CargoSyntax::Modern;
+        *instructions.output_target_mut() = OutputTarget::Both(PathBuf::from("out/vergen.env"));
+        instructions.add_command("BUILD_NUMBER", "build-number-cli", &["--token", "s3cr3t"]);
+        instructions.add_command_key("RELEASE_TRAIN", Command::new("./release-train.sh"));
+        instructions.value_provider("VERGEN_RUSTC_SEMVER", || None);
+        let summary = instructions.to_string();
+        assert_eq!(summary, golden("custom.txt"));
+        assert!(!summary.contains("s3cr3t"));
+    }
+
+    #[test]
+    fn struct_variant_setting() {
+        let mut instructions = instructions();
+        *instructions.output_target_mut() = OutputTarget::File(PathBuf::from("vergen.env"));
+        *instructions.output_format_mut() = OutputFormat::Dotenv { export: true };
+        assert!(instructions
+            .to_string()
+            .contains("\n  output_format = Dotenv { export: true }\n"));
+    }
+
+    #[test]
+    fn conditions_section() {
+        let mut instructions = instructions();
+        instructions.emit_when("git", Condition::only_profiles(&["release"]));
+        instructions.emit_when(
+            "VERGEN_BUILD_SEMVER",
+            Condition::when(|context| *context.is_ci()),
+        );
+        let summary = instructions.to_string();
+        assert!(summary.contains("settings: default\n"));
+        assert!(summary.ends_with(
+            "commands: none\nconditions\n  VERGEN_BUILD_SEMVER = a function\n  git = profiles [release]\n"
+        ));
+    }
+
+    #[test]
+    fn debug_fields() {
+        assert_eq!(
+            struct_fields(r#"A { b: "x, y }", c: Some(C { d: [1, 2] }), e: 1 }"#),
+            Some(vec![
+                ("b", r#""x, y }""#),
+                ("c", "Some(C { d: [1, 2] })"),
+                ("e", "1")
+            ])
+        );
+        assert_eq!(struct_fields("Some(A { b: 1 })"), None);
+        assert_eq!(struct_fields(r#""A { b: 1 }""#), None);
+    }
+}
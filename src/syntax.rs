@@ -0,0 +1,173 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` cargo instruction syntax
+
+use anyhow::Result;
+use std::{env, ffi::OsString, fmt::Display, io::Write, process::Command};
+
+/// The syntax of the generated `cargo` instructions
+///
+/// * [`Legacy`](Self::Legacy) generates `cargo:rustc-env=...`, which every version of cargo understands.
+/// * [`Modern`](Self::Modern) generates `cargo::rustc-env=...`, which requires cargo 1.77 or later.
+/// * [`Auto`](Self::Auto) runs `$CARGO --version` and uses the modern syntax if cargo is 1.77 or later, and the legacy syntax otherwise.
+///
+/// * **NOTE** - [`Auto`](Self::Auto) falls back to the legacy syntax if the cargo version can't be determined.
+///
+/// * **NOTE** - Every instruction, including `cargo:rerun-if-changed` and `cargo:warning`, uses the same syntax.
+///
+/// # Example
+///
+/// ```
+/// use vergen::{CargoSyntax, Config};
+///
+/// let mut config = Config::default();
+/// *config.cargo_syntax_mut() = CargoSyntax::Auto;
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum CargoSyntax {
+    /// The single colon `cargo:` syntax
+    Legacy,
+    /// The double colon `cargo::` syntax
+    Modern,
+    /// Detect the syntax from the cargo version
+    Auto,
+}
+
+#[cfg_attr(msrv, allow(clippy::derivable_impls))]
+impl Default for CargoSyntax {
+    fn default() -> Self {
+        Self::Legacy
+    }
+}
+
+impl CargoSyntax {
+    /// Resolve [`Auto`](Self::Auto) to the syntax supported by the running cargo
+    pub(crate) fn resolve(self) -> Self {
+        match self {
+            Self::Auto => {
+                let cargo = env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
+                Command::new(cargo)
+                    .arg("--version")
+                    .output()
+                    .ok()
+                    .filter(|output| output.status.success())
+                    .and_then(|output| cargo_version(&String::from_utf8_lossy(&output.stdout)))
+                    .map_or(Self::Legacy, syntax_for)
+            }
+            syntax => syntax,
+        }
+    }
+
+    fn prefix(self) -> &'static str {
+        match self {
+            Self::Modern => "cargo::",
+            // Auto is resolved before any output
+            Self::Legacy | Self::Auto => "cargo:",
+        }
+    }
+}
+
+/// The `(major, minor)` version from the `cargo --version` output, i.e. `cargo 1.77.0 (3fe68eabf 2024-02-29)`
+fn cargo_version(output: &str) -> Option<(u64, u64)> {
+    let version = output.trim().strip_prefix("cargo ")?.split(' ').next()?;
+    let mut parts = version.split(&['.', '-'][..]);
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+fn syntax_for(version: (u64, u64)) -> CargoSyntax {
+    if version >= (1, 77) {
+        CargoSyntax::Modern
+    } else {
+        CargoSyntax::Legacy
+    }
+}
+
+/// Write one `cargo` instruction, every instruction is written through here
+pub(crate) fn write_directive<T>(
+    stdout: &mut T,
+    syntax: CargoSyntax,
+    name: &str,
+    value: impl Display,
+) -> Result<()>
+where
+    T: Write,
+{
+    writeln!(stdout, "{}{}={}", syntax.prefix(), name, value)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{cargo_version, syntax_for, write_directive, CargoSyntax};
+
+    fn directive(syntax: CargoSyntax) -> String {
+        let mut stdout_buf = vec![];
+        write_directive(&mut stdout_buf, syntax, "rerun-if-changed", "build.rs").unwrap();
+        String::from_utf8(stdout_buf).unwrap()
+    }
+
+    #[test]
+    fn both_syntaxes() {
+        assert_eq!(
+            directive(CargoSyntax::Legacy),
+            "cargo:rerun-if-changed=build.rs\n"
+        );
+        assert_eq!(
+            directive(CargoSyntax::Modern),
+            "cargo::rerun-if-changed=build.rs\n"
+        );
+    }
+
+    #[test]
+    fn version_parsing() {
+        assert_eq!(
+            cargo_version("cargo 1.77.0 (3fe68eabf 2024-02-29)\n"),
+            Some((1, 77))
+        );
+        assert_eq!(
+            cargo_version("cargo 1.80.0-nightly (05364cb2f 2024-05-03)"),
+            Some((1, 80))
+        );
+        assert_eq!(cargo_version("cargo 1.52.0"), Some((1, 52)));
+        assert_eq!(cargo_version("rustc 1.77.0"), None);
+        assert_eq!(cargo_version("cargo unknown"), None);
+        assert_eq!(cargo_version(""), None);
+    }
+
+    #[test]
+    fn version_selects_syntax() {
+        assert_eq!(syntax_for((1, 76)), CargoSyntax::Legacy);
+        assert_eq!(syntax_for((1, 77)), CargoSyntax::Modern);
+        assert_eq!(syntax_for((2, 0)), CargoSyntax::Modern);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn auto_resolves() {
+        assert_eq!(CargoSyntax::Legacy.resolve(), CargoSyntax::Legacy);
+        assert_eq!(CargoSyntax::Modern.resolve(), CargoSyntax::Modern);
+        assert_ne!(CargoSyntax::Auto.resolve(), CargoSyntax::Auto);
+
+        let cargo = std::env::var_os("CARGO");
+        std::env::set_var("CARGO", "vergen_no_such_cargo");
+        let resolved = CargoSyntax::Auto.resolve();
+        match cargo {
+            Some(cargo) => std::env::set_var("CARGO", cargo),
+            None => std::env::remove_var("CARGO"),
+        }
+        assert_eq!(resolved, CargoSyntax::Legacy);
+    }
+}
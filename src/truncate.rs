@@ -0,0 +1,338 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `vergen` value length limits
+
+use crate::{
+    config::Config,
+    error::ConfigError,
+    merge::{pick, union_map, SetFields},
+    sticky::find_key,
+};
+use std::collections::BTreeMap;
+
+/// Configuration for limiting the length of the generated values
+///
+/// Every value ends up in the environment of the compiler, and usually in the binary, so a long one, i.e. the commit
+/// subjects, can run into a platform limit on the size of the environment.  When the `max_length` field is set, a
+/// value longer than that many bytes is truncated and ends with the `marker`, `...` by default.  A `cargo:warning`
+/// names each truncated instruction.
+///
+/// * The `per_key` field sets the limit by instruction name, i.e. `VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS`, in place
+///   of the `max_length` limit.  Only the named instructions are limited if `max_length` is None.
+/// * The limit includes the marker, and applies to the emitted value, after the
+///   [value providers](crate::Config::value_provider) and [`Sanitize`](crate::Sanitize).
+/// * A value is only cut between two characters, and never inside an escape sequence from the
+///   `escape_non_printable` [`Sanitize`](crate::Sanitize) field, i.e. `\u{1b}`, so it may end up a few bytes shorter
+///   than the limit.
+/// * **NOTE** - The output of the [added commands](crate::Config::add_command) isn't truncated.
+/// * **NOTE** - A limit shorter than the marker, or a marker with a line break, fails
+///   [validation](crate::Config::validate).
+///
+/// # Example
+///
+/// ```
+/// use vergen::Config;
+///
+/// let mut config = Config::default();
+/// *config.truncate_mut().max_length_mut() = Some(256);
+/// let _ = config
+///     .truncate_mut()
+///     .per_key_mut()
+///     .insert("VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS".to_string(), 1024);
+/// *config.truncate_mut().marker_mut() = "…".to_string();
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "serde-ignore-unknown")),
+    serde(deny_unknown_fields)
+)]
+pub struct Truncate {
+    /// The maximum length of a value in bytes, or None for no limit.
+    max_length: Option<usize>,
+    /// The maximum length of a value in bytes by instruction name, in place of `max_length`.
+    per_key: BTreeMap<String, usize>,
+    /// The text that ends a truncated value.
+    marker: String,
+    /// The fields set through their `set_*` setter, which win in a [merge](crate::Config::merge)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    set_fields: SetFields,
+}
+
+impl Truncate {
+    /// The maximum length of a value in bytes, or None for no limit.
+    pub fn max_length_mut(&mut self) -> &mut Option<usize> {
+        &mut self.max_length
+    }
+
+    /// The maximum length of a value in bytes, or None for no limit.
+    pub fn set_max_length(&mut self, max_length: Option<usize>) {
+        self.set_fields.mark(MERGED_FIELDS, "max_length");
+        self.max_length = max_length;
+    }
+
+    /// The maximum length of a value in bytes by instruction name, in place of `max_length`.
+    pub fn per_key_mut(&mut self) -> &mut BTreeMap<String, usize> {
+        &mut self.per_key
+    }
+
+    /// The text that ends a truncated value.
+    pub fn marker_mut(&mut self) -> &mut String {
+        &mut self.marker
+    }
+
+    /// The text that ends a truncated value.
+    pub fn set_marker(&mut self, marker: String) {
+        self.set_fields.mark(MERGED_FIELDS, "marker");
+        self.marker = marker;
+    }
+}
+
+impl Default for Truncate {
+    fn default() -> Self {
+        Self {
+            max_length: None,
+            per_key: BTreeMap::new(),
+            marker: "...".to_string(),
+            set_fields: SetFields::default(),
+        }
+    }
+}
+
+/// The [`Truncate`] fields that win in a [merge](crate::Config::merge) once set, see [`SetFields`]
+const MERGED_FIELDS: &[&str] = &["max_length", "marker"];
+
+impl Truncate {
+    pub(crate) fn merge(self, overlay: Self) -> Self {
+        let default = Self::default();
+        let overlay_set = overlay.set_fields;
+        let set = |name| overlay_set.contains(MERGED_FIELDS, name);
+        Self {
+            max_length: pick(
+                self.max_length,
+                overlay.max_length,
+                &default.max_length,
+                set("max_length"),
+            ),
+            per_key: union_map(self.per_key, overlay.per_key),
+            marker: pick(self.marker, overlay.marker, &default.marker, set("marker")),
+            set_fields: self.set_fields.union(overlay_set),
+        }
+    }
+
+    pub(crate) fn validate(&self, errors: &mut Vec<ConfigError>) {
+        if self.marker.contains(&['\n', '\r'][..]) {
+            errors.push(ConfigError::new(
+                "truncate.marker",
+                format!("{:?}", self.marker),
+                "use a marker without a line break, i.e. '...'",
+            ));
+        }
+
+        let too_short = format!(
+            "use a limit of at least {} bytes, the length of the marker, or a shorter marker",
+            self.marker.len()
+        );
+        if let Some(max_length) = self.max_length {
+            if max_length < self.marker.len() {
+                errors.push(ConfigError::new(
+                    "truncate.max_length",
+                    format!("Some({})", max_length),
+                    too_short.clone(),
+                ));
+            }
+        }
+        for (name, limit) in &self.per_key {
+            if find_key(name).is_none() {
+                errors.push(ConfigError::new(
+                    "truncate.per_key",
+                    format!("{:?}", name),
+                    "use the name of a generated instruction, i.e. 'VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS'",
+                ));
+            } else if *limit < self.marker.len() {
+                errors.push(ConfigError::new(
+                    "truncate.per_key",
+                    format!("{:?}: {}", name, limit),
+                    too_short.clone(),
+                ));
+            }
+        }
+    }
+
+    /// The limit for the named instruction, if any
+    fn limit(&self, name: &str) -> Option<usize> {
+        self.per_key.get(name).copied().or(self.max_length)
+    }
+}
+
+/// The length of the longest start of the value within the budget that doesn't split a character or an escape sequence
+fn cut(value: &str, budget: usize) -> usize {
+    let mut cut = 0;
+    let mut chars = value.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        // An escape sequence, i.e. `\t` or `\u{1b}`, is kept or dropped whole
+        if c == '\\' {
+            if let Some((_, 'u')) = chars.next() {
+                if let Some((_, '{')) = chars.peek() {
+                    for (_, c) in chars.by_ref() {
+                        if c == '}' {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        let end = chars.peek().map_or(value.len(), |(index, _)| *index);
+        if end > budget {
+            break;
+        }
+        cut = end;
+    }
+    cut
+}
+
+/// Truncate the values that are longer than their limit, and warn about each
+pub(crate) fn truncate(truncate: &Truncate, config: &mut Config) {
+    let mut warnings = vec![];
+    for (key, value) in config.cfg_map_mut().iter_mut() {
+        if let (Some(value), Some(limit)) = (value.as_mut(), truncate.limit(key.name())) {
+            if value.len() > limit {
+                let original = value.len();
+                let len = cut(value, limit.saturating_sub(truncate.marker.len()));
+                value.truncate(len);
+                value.push_str(&truncate.marker);
+                warnings.push(format!(
+                    "vergen: truncated {} from {} to {} bytes",
+                    key.name(),
+                    original,
+                    value.len()
+                ));
+            }
+        }
+    }
+    config.warnings_mut().extend(warnings);
+}
+
+#[cfg(test)]
+mod test {
+    use super::{cut, truncate, Truncate};
+    use crate::config::{Config, VergenKey};
+
+    fn truncated(truncate_config: &Truncate, key: VergenKey, value: &str) -> (String, Vec<String>) {
+        let mut config = Config::default();
+        let _ = config.cfg_map_mut().insert(key, Some(value.to_string()));
+        truncate(truncate_config, &mut config);
+        (
+            config.cfg_map()[&key].clone().unwrap(),
+            config.warnings().clone(),
+        )
+    }
+
+    fn max_length(max_length: usize) -> Truncate {
+        Truncate {
+            max_length: Some(max_length),
+            ..Truncate::default()
+        }
+    }
+
+    #[test]
+    fn short_values_untouched() {
+        let (value, warnings) = truncated(&max_length(6), VergenKey::Sha, "abc123");
+        assert_eq!(value, "abc123");
+        assert!(warnings.is_empty());
+        let (value, _) = truncated(&Truncate::default(), VergenKey::Sha, &"a".repeat(10_000));
+        assert_eq!(value.len(), 10_000);
+    }
+
+    #[test]
+    fn truncated_with_marker() {
+        let (value, warnings) = truncated(&max_length(8), VergenKey::Sha, "abc123def456");
+        assert_eq!(value, "abc12...");
+        assert_eq!(
+            warnings,
+            &["vergen: truncated VERGEN_GIT_SHA from 12 to 8 bytes"]
+        );
+    }
+
+    #[test]
+    fn multibyte_boundaries() {
+        // 'ü' is 2 bytes, '語' is 3, and '🦀' is 4
+        let value = "aü語🦀b";
+        let cuts: Vec<usize> = (0..=value.len()).map(|budget| cut(value, budget)).collect();
+        assert_eq!(cuts, [0, 1, 1, 3, 3, 3, 6, 6, 6, 6, 10, 11]);
+        for budget in 0..=value.len() {
+            assert!(value.is_char_boundary(cut(value, budget)));
+        }
+
+        let mut config = max_length(9);
+        *config.marker_mut() = "…".to_string();
+        let (value, _) = truncated(&config, VergenKey::Banner, "aü語🦀b");
+        assert_eq!(value, "aü語…");
+    }
+
+    #[test]
+    fn escape_sequences_kept_whole() {
+        let value = "a\\tb\\u{1b}c";
+        let cuts: Vec<usize> = (0..=value.len()).map(|budget| cut(value, budget)).collect();
+        assert_eq!(cuts, [0, 1, 1, 3, 4, 4, 4, 4, 4, 4, 10, 11]);
+        // A backslash at the very end isn't an escape sequence
+        assert_eq!(cut("ab\\", 3), 3);
+    }
+
+    #[test]
+    fn per_key_limits() {
+        let mut config = Truncate::default();
+        let _ = config
+            .per_key_mut()
+            .insert("VERGEN_GIT_COMMITS_SINCE_TAG_SUBJECTS".to_string(), 10);
+        let subjects = "Fix the docs; Add a feature";
+        let (value, _) = truncated(&config, VergenKey::CommitsSinceTagSubjects, subjects);
+        assert_eq!(value, "Fix the...");
+        let (value, _) = truncated(&config, VergenKey::Banner, subjects);
+        assert_eq!(value, subjects);
+
+        // The per-key limit replaces the global one, even if it's longer
+        *config.max_length_mut() = Some(5);
+        let (value, _) = truncated(&config, VergenKey::CommitsSinceTagSubjects, subjects);
+        assert_eq!(value, "Fix the...");
+        let (value, _) = truncated(&config, VergenKey::Banner, subjects);
+        assert_eq!(value, "Fi...");
+    }
+
+    #[test]
+    fn invalid_limits() {
+        let mut config = max_length(2);
+        let _ = config
+            .per_key_mut()
+            .insert("VERGEN_GIT_SHAA".to_string(), 10);
+        let _ = config.per_key_mut().insert("VERGEN_GIT_SHA".to_string(), 1);
+        let mut errors = vec![];
+        config.validate(&mut errors);
+        let fields: Vec<&str> = errors.iter().map(|error| error.field().as_str()).collect();
+        assert_eq!(
+            fields,
+            [
+                "truncate.max_length",
+                "truncate.per_key",
+                "truncate.per_key"
+            ]
+        );
+
+        let mut errors = vec![];
+        *config.marker_mut() = "\n".to_string();
+        config.per_key_mut().clear();
+        config.validate(&mut errors);
+        assert_eq!(errors[0].field(), "truncate.marker");
+        assert_eq!(errors.len(), 1);
+    }
+}
@@ -0,0 +1,67 @@
+// A crate using the constants generated by the `built` crate, to check the
+// `built.rs` written by vergen is a drop-in replacement.
+#![deny(warnings)]
+
+pub mod built_info {
+    include!(concat!(env!("OUT_DIR"), "/built.rs"));
+}
+
+pub fn strings() -> Vec<&'static str> {
+    vec![
+        built_info::PKG_VERSION,
+        built_info::PKG_VERSION_MAJOR,
+        built_info::PKG_VERSION_MINOR,
+        built_info::PKG_VERSION_PATCH,
+        built_info::PKG_VERSION_PRE,
+        built_info::PKG_AUTHORS,
+        built_info::PKG_NAME,
+        built_info::PKG_DESCRIPTION,
+        built_info::PKG_HOMEPAGE,
+        built_info::PKG_LICENSE,
+        built_info::PKG_REPOSITORY,
+        built_info::TARGET,
+        built_info::HOST,
+        built_info::PROFILE,
+        built_info::RUSTC,
+        built_info::RUSTDOC,
+        built_info::RUSTC_VERSION,
+        built_info::RUSTDOC_VERSION,
+        built_info::OPT_LEVEL,
+        built_info::FEATURES_STR,
+        built_info::FEATURES_LOWERCASE_STR,
+        built_info::BUILT_TIME_UTC,
+        built_info::CFG_TARGET_ARCH,
+        built_info::CFG_ENDIAN,
+        built_info::CFG_ENV,
+        built_info::CFG_FAMILY,
+        built_info::CFG_OS,
+        built_info::CFG_POINTER_WIDTH,
+        built_info::DEPENDENCIES_STR,
+    ]
+}
+
+pub fn features() -> Vec<&'static str> {
+    built_info::FEATURES
+        .iter()
+        .chain(built_info::FEATURES_LOWERCASE.iter())
+        .copied()
+        .collect()
+}
+
+pub fn dependencies() -> usize {
+    built_info::DEPENDENCIES.iter().count()
+}
+
+pub fn git() -> (Option<&'static str>, Option<bool>, Option<&'static str>) {
+    let _ = (built_info::CI_PLATFORM, built_info::GIT_HEAD_REF);
+    let _ = built_info::GIT_COMMIT_HASH_SHORT;
+    (
+        built_info::GIT_VERSION,
+        built_info::GIT_DIRTY,
+        built_info::GIT_COMMIT_HASH,
+    )
+}
+
+pub fn profile() -> (u32, bool) {
+    (built_info::NUM_JOBS, built_info::DEBUG)
+}
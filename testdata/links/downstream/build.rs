@@ -0,0 +1,11 @@
+use std::env;
+
+fn main() {
+    let profile = env::var("DEP_UPSTREAM_VERGEN_CARGO_PROFILE")
+        .expect("DEP_UPSTREAM_VERGEN_CARGO_PROFILE isn't set");
+    println!(
+        "cargo:warning=downstream received DEP_UPSTREAM_VERGEN_CARGO_PROFILE={}",
+        profile
+    );
+    println!("cargo:rustc-env=UPSTREAM_PROFILE={}", profile);
+}
@@ -0,0 +1 @@
+pub const UPSTREAM_PROFILE: &str = env!("UPSTREAM_PROFILE");
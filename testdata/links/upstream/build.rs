@@ -0,0 +1,9 @@
+use vergen::{vergen, Config};
+
+fn main() {
+    let mut config = Config::default();
+    config
+        .links_metadata_mut()
+        .push("VERGEN_CARGO_PROFILE".to_string());
+    vergen(config).expect("vergen failed");
+}
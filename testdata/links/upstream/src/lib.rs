@@ -0,0 +1 @@
+pub const PROFILE: &str = env!("VERGEN_CARGO_PROFILE");
@@ -0,0 +1,124 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Every crate compiles `vergen` into its build script, so keep the dependencies of each feature in check
+use std::{collections::BTreeSet, fs, path::Path, process::Command};
+
+/// The crates a `--no-default-features --features build` build may compile, including the build dependencies
+const BUILD_ALLOWLIST: &[&str] = &[
+    "anyhow",
+    "deranged",
+    "libc",
+    "num-conv",
+    "num_threads",
+    "powerfmt",
+    "proc-macro2",
+    "quote",
+    "rustversion",
+    "syn",
+    "thiserror",
+    "thiserror-impl",
+    "time",
+    "time-core",
+    "time-macros",
+    "unicode-ident",
+    "vergen",
+];
+
+/// The optional dependencies each feature enables
+const FEATURE_DEPENDENCIES: &[(&str, &[&str])] = &[
+    ("build", &["time"]),
+    ("cargo", &[]),
+    ("git", &["git2", "time"]),
+    ("hash", &["sha2"]),
+    ("hg", &[]),
+    ("inspect", &[]),
+    ("licenses", &["serde_json"]),
+    ("macros", &["vergen-macros"]),
+    ("pretty", &[]),
+    ("rustc", &["rustc_version"]),
+    ("serde-ignore-unknown", &["serde"]),
+    ("si", &["cfg-if", "sysinfo"]),
+    ("test-util", &[]),
+];
+
+/// The names of the crates `cargo tree` lists, one per line without the tree drawing
+fn tree(args: &[&str]) -> BTreeSet<String> {
+    let output = Command::new(env!("CARGO"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["tree", "--prefix", "none", "--format", "{p}"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn core_dependencies() {
+    let direct = tree(&["-e", "normal", "--depth", "1", "--no-default-features"]);
+    let expected: BTreeSet<String> = ["anyhow", "thiserror", "vergen"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    assert_eq!(direct, expected);
+}
+
+#[test]
+fn build_dependencies_are_allowed() {
+    let crates = tree(&[
+        "-e",
+        "features,normal,build",
+        "--no-default-features",
+        "--features",
+        "build",
+    ]);
+    let unexpected: Vec<&String> = crates
+        .iter()
+        .filter(|name| !BUILD_ALLOWLIST.contains(&name.as_str()))
+        .collect();
+    assert!(unexpected.is_empty(), "unexpected crates: {:?}", unexpected);
+}
+
+#[test]
+fn features_only_enable_their_own_dependencies() {
+    let manifest =
+        fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml")).unwrap();
+    let features: Vec<(String, Vec<String>)> = manifest
+        .lines()
+        .skip_while(|line| *line != "[features]")
+        .skip(1)
+        .take_while(|line| !line.starts_with('['))
+        .filter_map(|line| {
+            let (name, list) = line.split_once(" = ")?;
+            let deps = list
+                .trim_matches(|c| c == '[' || c == ']')
+                .split(',')
+                .map(|dep| dep.trim().trim_matches('"').to_string())
+                .filter(|dep| !dep.is_empty())
+                .collect();
+            Some((name.to_string(), deps))
+        })
+        .filter(|(name, _)| name != "default")
+        .collect();
+
+    assert_eq!(features.len(), FEATURE_DEPENDENCIES.len());
+    for ((name, deps), (expected_name, expected_deps)) in features.iter().zip(FEATURE_DEPENDENCIES)
+    {
+        assert_eq!(name, expected_name);
+        assert_eq!(deps, expected_deps, "the '{}' feature", name);
+    }
+}
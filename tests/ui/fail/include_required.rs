@@ -0,0 +1,3 @@
+include!(concat!(env!("OUT_DIR"), "/vergen.rs"));
+
+fn main() {}
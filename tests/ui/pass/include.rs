@@ -0,0 +1,15 @@
+include!(concat!(env!("OUT_DIR"), "/vergen.rs"));
+
+fn main() {
+    assert_eq!(
+        vergen_pretty(),
+        "VERGEN_GIT_BRANCH  say \"hi\" \\ ü\nVERGEN_GIT_SHA     abc123\n"
+    );
+    assert_eq!(
+        VERGEN_MAP,
+        &[
+            ("VERGEN_GIT_BRANCH", "say \"hi\" \\ ü"),
+            ("VERGEN_GIT_SHA", "abc123"),
+        ]
+    );
+}
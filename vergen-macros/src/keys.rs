@@ -0,0 +1,91 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+// Also included by the `vergen` tests, which check it against the generated instructions.
+
+/// The names of the `vergen` instructions, without the `VERGEN_` prefix
+pub(crate) const KEYS: [&str; 78] = [
+    "BUILD_DATE",
+    "BUILD_TIME",
+    "BUILD_TIMESTAMP",
+    "BUILD_SEMVER",
+    "BUILD_SEMVER_MAJOR",
+    "BUILD_SEMVER_MINOR",
+    "BUILD_SEMVER_PATCH",
+    "BUILD_SEMVER_PRE",
+    "BUILD_CPU_COUNT",
+    "GIT_BRANCH",
+    "GIT_COMMIT_DATE",
+    "GIT_COMMIT_TIME",
+    "GIT_COMMIT_TIMESTAMP",
+    "GIT_SEMVER",
+    "GIT_SEMVER_LIGHTWEIGHT",
+    "GIT_SHA",
+    "GIT_SHA_SHORT",
+    "GIT_TRACKED_FILES",
+    "GIT_COMMIT_AGE_SECONDS",
+    "GIT_COMMITS_SINCE_TAG",
+    "GIT_COMMITS_SINCE_TAG_SUBJECTS",
+    "GIT_SIGNED",
+    "GIT_SIGNER",
+    "GIT_BRANCH_COUNT",
+    "GIT_TAG_COUNT",
+    "GIT_ROOT",
+    "GIT_WORKTREE",
+    "GIT_TREE_HASH",
+    "GIT_CONTRIBUTOR_COUNT",
+    "GIT_MERGE_BASE",
+    "GIT_OBJECT_FORMAT",
+    "GIT_PARENT_COUNT",
+    "HG_BRANCH",
+    "HG_REV",
+    "HG_SHA",
+    "RUSTC_CHANNEL",
+    "RUSTC_COMMIT_DATE",
+    "RUSTC_COMMIT_HASH",
+    "RUSTC_HOST_TRIPLE",
+    "RUSTC_LLVM_VERSION",
+    "RUSTC_SEMVER",
+    "CARGO_TARGET_TRIPLE",
+    "CARGO_PROFILE",
+    "CARGO_FEATURES",
+    "CARGO_PKG_DESCRIPTION",
+    "CARGO_PKG_AUTHORS",
+    "CARGO_PKG_HOMEPAGE",
+    "CARGO_PKG_REPOSITORY",
+    "CARGO_PKG_LICENSE",
+    "CARGO_PKG_LICENSE_FILE",
+    "CARGO_TARGET_OS",
+    "CARGO_TARGET_ARCH",
+    "CARGO_TARGET_ENV",
+    "CARGO_TARGET_POINTER_WIDTH",
+    "CARGO_TARGET_ENDIAN",
+    "CARGO_LIBC_VERSION",
+    "TARGET_TRIPLE",
+    "TARGET_OS",
+    "TARGET_ARCH",
+    "TARGET_ENV",
+    "IS_CROSS_COMPILE",
+    "SYSINFO_NAME",
+    "SYSINFO_OS_VERSION",
+    "SYSINFO_USER",
+    "SYSINFO_TOTAL_MEMORY",
+    "SYSINFO_CPU_VENDOR",
+    "SYSINFO_CPU_CORE_COUNT",
+    "SYSINFO_CPU_NAME",
+    "SYSINFO_CPU_BRAND",
+    "SYSINFO_CPU_FREQUENCY",
+    "BANNER",
+    "HASH",
+    "BUILD_SCRIPT_DURATION_MS",
+    "BUILD_NUMBER",
+    "DEPENDENCY_LICENSES",
+    "ENABLED_FEATURES",
+    "PROVENANCE_JSON",
+    "RELEASE_URL",
+];
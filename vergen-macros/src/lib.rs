@@ -0,0 +1,242 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Derive macros for [`vergen`](https://docs.rs/vergen), re-exported by `vergen` with the `macros` feature.
+//!
+//! See [`VergenFill`](macro@VergenFill).
+
+#![deny(missing_docs)]
+
+mod keys;
+
+use crate::keys::KEYS;
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+    parse_macro_input, Attribute, Data, DataStruct, DeriveInput, Error, Fields, GenericArgument,
+    Lit, LitStr, Meta, NestedMeta, PathArguments, Result, Type,
+};
+
+/// The prefix of the environment variables, unless the struct sets its own
+const DEFAULT_PREFIX: &str = "VERGEN_";
+
+/// Fill a struct from the `vergen` instructions at compile time, as its `COLLECTED` associated constant.
+///
+/// * Each field is filled from the instruction named by its `#[vergen(key = "...")]` attribute, the instruction name
+///   without the `VERGEN_` prefix, i.e. `GIT_SHA`.  Without the attribute, the key is the field name in uppercase,
+///   i.e. `git_sha` is filled from `VERGEN_GIT_SHA`.
+/// * A `&'static str` field is filled with [`env!`](std::env!), so the build fails if the instruction wasn't
+///   generated.  An `Option<&'static str>` field is filled with [`option_env!`](std::option_env!), and is `None`
+///   if it wasn't.
+/// * The `#[vergen(prefix = "...")]` struct attribute replaces the `VERGEN_` prefix, i.e. when the values are
+///   passed on to the compiler under another name.
+/// * **NOTE** - A key that isn't the name of a `vergen` instruction, or a field of any other type, fails the build.
+///
+/// # Example
+///
+/// ```
+/// use vergen_macros::VergenFill;
+///
+/// #[derive(VergenFill)]
+/// struct BuildMeta {
+///     #[vergen(key = "GIT_SHA")]
+///     sha: Option<&'static str>,
+///     #[vergen(key = "BUILD_TIMESTAMP")]
+///     built_at: Option<&'static str>,
+///     rustc_channel: Option<&'static str>,
+/// }
+///
+/// const META: BuildMeta = BuildMeta::COLLECTED;
+/// println!("{}", META.sha.unwrap_or("unknown"));
+/// ```
+#[proc_macro_derive(VergenFill, attributes(vergen))]
+pub fn derive_vergen_fill(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => &fields.named,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "VergenFill needs a struct with named fields",
+            ))
+        }
+    };
+
+    let prefix = match attribute_value(&input.attrs, "prefix")? {
+        Some(prefix) => {
+            if !valid_name(&prefix.value()) {
+                return Err(Error::new_spanned(
+                    prefix,
+                    "use ASCII alphanumeric characters and '_' only, i.e. 'VERGEN_'",
+                ));
+            }
+            prefix.value()
+        }
+        None => DEFAULT_PREFIX.to_string(),
+    };
+
+    let mut values = vec![];
+    for field in fields {
+        // Named fields always have an ident
+        let ident = match &field.ident {
+            Some(ident) => ident,
+            None => continue,
+        };
+        let key = match attribute_value(&field.attrs, "key")? {
+            Some(key) => {
+                check_key(
+                    &key.value(),
+                    key.span(),
+                    "use the instruction name without the prefix, i.e. 'GIT_SHA'",
+                )?;
+                key.value()
+            }
+            None => {
+                let key = ident.to_string().trim_start_matches("r#").to_uppercase();
+                check_key(
+                    &key,
+                    ident.span(),
+                    "name the field after one, or set #[vergen(key = \"...\")]",
+                )?;
+                key
+            }
+        };
+        let name = format!("{}{}", prefix, key);
+        let value = if is_str(&field.ty) {
+            quote! { env!(#name) }
+        } else if option_str(&field.ty) {
+            quote! { option_env!(#name) }
+        } else {
+            return Err(Error::new_spanned(
+                &field.ty,
+                "use `&'static str`, or `Option<&'static str>` for an instruction that may not be generated",
+            ));
+        };
+        values.push(quote! { #ident: #value });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// The values of the `vergen` instructions, collected at compile time
+            pub const COLLECTED: Self = Self {
+                #(#values,)*
+            };
+        }
+    })
+}
+
+/// The value of the `#[vergen(name = "...")]` attribute, if it's set
+fn attribute_value(attrs: &[Attribute], name: &str) -> Result<Option<LitStr>> {
+    let mut value = None;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("vergen")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => {
+                return Err(Error::new_spanned(
+                    meta,
+                    format!("use #[vergen({} = \"...\")]", name),
+                ))
+            }
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(pair)) if pair.path.is_ident(name) => {
+                    match pair.lit {
+                        Lit::Str(lit) if value.is_none() => value = Some(lit),
+                        Lit::Str(lit) => {
+                            return Err(Error::new_spanned(lit, format!("{} is already set", name)))
+                        }
+                        lit => {
+                            return Err(Error::new_spanned(
+                                lit,
+                                format!("use a string, i.e. #[vergen({} = \"...\")]", name),
+                            ))
+                        }
+                    }
+                }
+                nested => {
+                    return Err(Error::new_spanned(
+                        nested,
+                        format!("use #[vergen({} = \"...\")]", name),
+                    ))
+                }
+            }
+        }
+    }
+    Ok(value)
+}
+
+fn valid_name(name: &str) -> bool {
+    name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Fail unless the key is the name of a `vergen` instruction, without the prefix
+fn check_key(key: &str, span: Span, help: &str) -> Result<()> {
+    if KEYS.contains(&key) {
+        Ok(())
+    } else if key
+        .strip_prefix(DEFAULT_PREFIX)
+        .map_or(false, |key| KEYS.contains(&key))
+    {
+        Err(Error::new(
+            span,
+            format!(
+                "use the instruction name without the prefix, i.e. '{}'",
+                &key[DEFAULT_PREFIX.len()..]
+            ),
+        ))
+    } else {
+        Err(Error::new(
+            span,
+            format!("'{}' isn't a vergen instruction, {}", key, help),
+        ))
+    }
+}
+
+/// Whether the type is `&str`
+fn is_str(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(reference) => {
+            reference.mutability.is_none()
+                && matches!(&*reference.elem, Type::Path(path) if path.qself.is_none() && path.path.is_ident("str"))
+        }
+        _ => false,
+    }
+}
+
+/// Whether the type is `Option<&str>`
+fn option_str(ty: &Type) -> bool {
+    let segment = match ty {
+        Type::Path(path) if path.qself.is_none() => match path.path.segments.last() {
+            Some(segment) => segment,
+            None => return false,
+        },
+        _ => return false,
+    };
+    if segment.ident != "Option" {
+        return false;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) if arguments.args.len() == 1 => {
+            matches!(&arguments.args[0], GenericArgument::Type(ty) if is_str(ty))
+        }
+        _ => false,
+    }
+}
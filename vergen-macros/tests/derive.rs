@@ -0,0 +1,21 @@
+// Copyright (c) 2016, 2018, 2021 vergen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+use std::env;
+
+#[test]
+fn derive() {
+    env::set_var("VERGEN_GIT_SHA", "75b390dc6c05a6a4aa2791cc7b3934591803bc22");
+    env::set_var("VERGEN_BUILD_TIMESTAMP", "2021-02-25T23:28:39.493201+00:00");
+    env::set_var("MYAPP_GIT_SHA", "e0b3b2d");
+    env::remove_var("VERGEN_RUSTC_CHANNEL");
+    env::remove_var("MYAPP_BUILD_TIMESTAMP");
+
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/ui/pass/*.rs");
+    cases.compile_fail("tests/ui/fail/*.rs");
+}
@@ -0,0 +1,27 @@
+use vergen_macros::VergenFill;
+
+#[derive(VergenFill)]
+#[vergen(prefix = "MY-APP")]
+struct Prefix {
+    git_sha: &'static str,
+}
+
+#[derive(VergenFill)]
+struct Key {
+    #[vergen(name = "GIT_SHA")]
+    sha: &'static str,
+}
+
+#[derive(VergenFill)]
+struct Twice {
+    #[vergen(key = "GIT_SHA", key = "GIT_SHA_SHORT")]
+    sha: &'static str,
+}
+
+#[derive(VergenFill)]
+struct NotAString {
+    #[vergen(key = 1)]
+    sha: &'static str,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use vergen_macros::VergenFill;
+
+#[derive(VergenFill)]
+struct BuildMeta {
+    git_sha: String,
+}
+
+#[derive(VergenFill)]
+struct Timestamp {
+    build_timestamp: Option<&'static mut str>,
+}
+
+fn main() {}
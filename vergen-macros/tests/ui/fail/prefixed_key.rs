@@ -0,0 +1,9 @@
+use vergen_macros::VergenFill;
+
+#[derive(VergenFill)]
+struct BuildMeta {
+    #[vergen(key = "VERGEN_GIT_SHA")]
+    sha: &'static str,
+}
+
+fn main() {}
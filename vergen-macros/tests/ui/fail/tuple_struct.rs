@@ -0,0 +1,6 @@
+use vergen_macros::VergenFill;
+
+#[derive(VergenFill)]
+struct BuildMeta(&'static str);
+
+fn main() {}
@@ -0,0 +1,14 @@
+use vergen_macros::VergenFill;
+
+#[derive(VergenFill)]
+struct BuildMeta {
+    #[vergen(key = "GIT_SHAA")]
+    sha: &'static str,
+}
+
+#[derive(VergenFill)]
+struct Channel {
+    channel: Option<&'static str>,
+}
+
+fn main() {}
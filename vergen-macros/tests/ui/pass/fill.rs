@@ -0,0 +1,17 @@
+use vergen_macros::VergenFill;
+
+#[derive(VergenFill)]
+struct BuildMeta {
+    #[vergen(key = "GIT_SHA")]
+    sha: &'static str,
+    #[vergen(key = "BUILD_TIMESTAMP")]
+    built_at: Option<&'static str>,
+    rustc_channel: Option<&'static str>,
+}
+
+fn main() {
+    const META: BuildMeta = BuildMeta::COLLECTED;
+    assert_eq!(META.sha, "75b390dc6c05a6a4aa2791cc7b3934591803bc22");
+    assert_eq!(META.built_at, Some("2021-02-25T23:28:39.493201+00:00"));
+    assert_eq!(META.rustc_channel, None);
+}
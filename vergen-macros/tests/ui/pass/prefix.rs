@@ -0,0 +1,15 @@
+use vergen_macros::VergenFill;
+
+#[derive(VergenFill)]
+#[vergen(prefix = "MYAPP_")]
+struct BuildMeta<'a> {
+    git_sha: &'a str,
+    #[vergen(key = "BUILD_TIMESTAMP")]
+    built_at: std::option::Option<&'a str>,
+}
+
+fn main() {
+    let meta = BuildMeta::COLLECTED;
+    assert_eq!(meta.git_sha, "e0b3b2d");
+    assert_eq!(meta.built_at, None);
+}